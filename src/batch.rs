@@ -0,0 +1,589 @@
+use chrono::Local;
+use serde::Serialize;
+use serde_json::{json, Value};
+use std::cell::RefCell;
+use std::fs;
+use std::io::{BufRead, Write};
+use std::path::{Component, Path, PathBuf};
+
+use crate::config::Config;
+use crate::error::SnippetVaultError;
+use crate::hooks;
+use crate::index::{self, Access};
+use crate::info::SnippetInfo;
+use crate::language;
+use crate::listing::{self, SnippetFilter, SortCriterion};
+use crate::snippet::{self, PlaceholderStyle, SnippetMeta, TIMESTAMP_FORMAT};
+use crate::style::Styled;
+use crate::vault::{self, Executor};
+
+/// One field of a batch command, as listed by `--batch --describe`.
+#[derive(Debug, Serialize)]
+pub struct FieldSpec {
+    pub name: &'static str,
+    /// JSON type: `string`, `integer`, or `array of strings`.
+    #[serde(rename = "type")]
+    pub kind: &'static str,
+    pub required: bool,
+    pub description: &'static str,
+}
+
+/// One command of `--batch` mode.
+#[derive(Debug, Serialize)]
+pub struct CommandSpec {
+    pub cmd: &'static str,
+    pub description: &'static str,
+    pub fields: &'static [FieldSpec],
+    /// Shape of `result` in a successful response.
+    pub result: &'static str,
+}
+
+/// Fields naming one snippet, shared by the commands that read one.
+const SNIPPET_FIELDS: &[FieldSpec] = &[
+    FieldSpec {
+        name: "id",
+        kind: "string",
+        required: false,
+        description: "Filename timestamp or file stem of the snippet",
+    },
+    FieldSpec {
+        name: "path",
+        kind: "string",
+        required: false,
+        description: "Snippet file, relative to the vault (when no id is given)",
+    },
+];
+
+/// Every command `--batch` understands, in the order `--describe` lists them.
+pub const COMMANDS: &[CommandSpec] = &[
+    CommandSpec {
+        cmd: "list",
+        description: "Snippets matching all given filters",
+        fields: &[
+            FieldSpec {
+                name: "language",
+                kind: "string",
+                required: false,
+                description: "Only snippets in this language",
+            },
+            FieldSpec {
+                name: "tags",
+                kind: "array of strings",
+                required: false,
+                description: "Tags that must all be present (dotted sub-tags match)",
+            },
+            FieldSpec {
+                name: "title",
+                kind: "string",
+                required: false,
+                description: "Case-insensitive substring of the title",
+            },
+            FieldSpec {
+                name: "sort",
+                kind: "string",
+                required: false,
+                description: "One of the --sort_by criteria (default: path)",
+            },
+            FieldSpec {
+                name: "limit",
+                kind: "integer",
+                required: false,
+                description: "At most this many snippets",
+            },
+        ],
+        result: "array of snippet metadata, as printed by --list_snippets --json",
+    },
+    CommandSpec {
+        cmd: "search",
+        description:
+            "Snippets whose contents match a regular expression (literal if it does not parse)",
+        fields: &[FieldSpec {
+            name: "query",
+            kind: "string",
+            required: true,
+            description: "Pattern to search for",
+        }],
+        result: "array of matching snippet paths",
+    },
+    CommandSpec {
+        cmd: "cat",
+        description: "The full text of one snippet",
+        fields: SNIPPET_FIELDS,
+        result: "object with path and content",
+    },
+    CommandSpec {
+        cmd: "info",
+        description: "Everything known about one snippet",
+        fields: SNIPPET_FIELDS,
+        result: "object as printed by --info --json",
+    },
+    CommandSpec {
+        cmd: "create",
+        description: "A new markdown snippet; hooks are not run and no editor opens",
+        fields: &[
+            FieldSpec {
+                name: "language",
+                kind: "string",
+                required: true,
+                description: "Language of the snippet; aliases are resolved",
+            },
+            FieldSpec {
+                name: "tags",
+                kind: "array of strings",
+                required: false,
+                description: "Tags of the snippet",
+            },
+            FieldSpec {
+                name: "code",
+                kind: "string",
+                required: false,
+                description: "Contents of the code fence",
+            },
+        ],
+        result: "object with the path of the new snippet",
+    },
+    CommandSpec {
+        cmd: "describe",
+        description: "This list of commands",
+        fields: &[],
+        result: "array of commands with their fields",
+    },
+];
+
+/// Why a batch request failed, reported as `error.code`.
+#[derive(Debug)]
+enum BatchError {
+    /// The line is not a JSON object.
+    Parse(String),
+    UnknownCommand(String),
+    /// A field is missing or has the wrong type.
+    InvalidParams(String),
+    NotFound(String),
+    Failed(SnippetVaultError),
+}
+
+impl BatchError {
+    fn code(&self) -> &'static str {
+        match self {
+            BatchError::Parse(_) => "parse_error",
+            BatchError::UnknownCommand(_) => "unknown_command",
+            BatchError::InvalidParams(_) => "invalid_params",
+            BatchError::NotFound(_) => "not_found",
+            BatchError::Failed(_) => "failed",
+        }
+    }
+
+    fn message(&self) -> String {
+        match self {
+            BatchError::Parse(message)
+            | BatchError::UnknownCommand(message)
+            | BatchError::InvalidParams(message)
+            | BatchError::NotFound(message) => message.clone(),
+            BatchError::Failed(err) => err.to_string(),
+        }
+    }
+}
+
+impl From<SnippetVaultError> for BatchError {
+    fn from(err: SnippetVaultError) -> Self {
+        BatchError::Failed(err)
+    }
+}
+
+/// Answers the newline-delimited JSON requests read from `input`, one JSON
+/// response line per request on `output`, until `input` ends. A request that
+/// fails gets an error response and the session goes on; only a failure to
+/// read `input` or write `output` ends it early.
+/// - `use_cache`: Serve unchanged snippets from the metadata cache.
+/// - `executor`: Applies (or, with `--dry-run`, only reports) new snippets.
+pub fn run(
+    config: &Config,
+    input: impl BufRead,
+    mut output: impl Write,
+    use_cache: bool,
+    executor: &Executor,
+) -> Result<(), SnippetVaultError> {
+    let session = Session {
+        config,
+        snippet_dir: vault::snippet_dir(config),
+        extensions: config.extensions(),
+        use_cache,
+        executor,
+        snippets: RefCell::new(None),
+    };
+    for line in input.lines() {
+        let line = line?;
+        if line.trim().is_empty() {
+            continue;
+        }
+        let response = match session.handle(&line) {
+            Ok(result) => json!({ "ok": true, "result": result }),
+            Err(err) => json!({
+                "ok": false,
+                "error": { "code": err.code(), "message": err.message() },
+            }),
+        };
+        writeln!(output, "{}", response)?;
+        output.flush()?;
+    }
+    Ok(())
+}
+
+/// State shared by every request of one `--batch` session.
+struct Session<'a> {
+    config: &'a Config,
+    snippet_dir: PathBuf,
+    extensions: Vec<String>,
+    use_cache: bool,
+    executor: &'a Executor,
+    /// The vault's snippets, loaded by the first request that needs them and
+    /// kept until `create` adds one.
+    snippets: RefCell<Option<Vec<SnippetMeta>>>,
+}
+
+impl Session<'_> {
+    /// Calls `f` with the vault's snippets, loading them once per session.
+    fn with_snippets<T>(&self, f: impl FnOnce(&[SnippetMeta]) -> T) -> Result<T, BatchError> {
+        let mut snippets = self.snippets.borrow_mut();
+        if snippets.is_none() {
+            *snippets = Some(vault::load_snippets(
+                &self.snippet_dir,
+                &self.extensions,
+                self.use_cache,
+            )?);
+        }
+        Ok(f(snippets.as_deref().unwrap_or_default()))
+    }
+
+    fn handle(&self, line: &str) -> Result<Value, BatchError> {
+        let request: Value = serde_json::from_str(line)
+            .map_err(|err| BatchError::Parse(format!("invalid JSON: {}", err)))?;
+        if !request.is_object() {
+            return Err(BatchError::Parse(
+                "a request must be a JSON object".to_string(),
+            ));
+        }
+        let cmd = string_field(&request, "cmd")?
+            .ok_or_else(|| BatchError::InvalidParams("missing field 'cmd'".to_string()))?;
+        match cmd {
+            "list" => self.list(&request),
+            "search" => self.search(&request),
+            "cat" => self.cat(&request),
+            "info" => self.info(&request),
+            "create" => self.create(&request),
+            "describe" => Ok(describe()),
+            _ => Err(BatchError::UnknownCommand(format!(
+                "unknown command '{}'; known commands: {}",
+                cmd,
+                COMMANDS
+                    .iter()
+                    .map(|spec| spec.cmd)
+                    .collect::<Vec<_>>()
+                    .join(", ")
+            ))),
+        }
+    }
+
+    fn list(&self, request: &Value) -> Result<Value, BatchError> {
+        let sort = match string_field(request, "sort")? {
+            Some(name) => SortCriterion::from_name(name).ok_or_else(|| {
+                BatchError::InvalidParams(format!(
+                    "unknown sort '{}'; expected one of {}",
+                    name,
+                    listing::SORT_CRITERIA.join(", ")
+                ))
+            })?,
+            None => SortCriterion::default(),
+        };
+        let limit = match request.get("limit") {
+            None | Some(Value::Null) => None,
+            Some(value) => Some(value.as_u64().ok_or_else(|| {
+                BatchError::InvalidParams("'limit' must be a non-negative integer".to_string())
+            })? as usize),
+        };
+        let filter = SnippetFilter {
+            language: string_field(request, "language")?.map(|language| {
+                language::resolve_language(language, &self.config.language_aliases)
+            }),
+            tags: strings_field(request, "tags")?,
+            title: string_field(request, "title")?.map(str::to_string),
+            limit,
+            ..Default::default()
+        };
+        let mut snippets: Vec<SnippetMeta> = self.with_snippets(|snippets| {
+            snippets
+                .iter()
+                .filter(|snippet| filter.matches(snippet))
+                .cloned()
+                .collect()
+        })?;
+        sort.sort(&mut snippets);
+        if let Some(limit) = filter.limit {
+            snippets.truncate(limit);
+        }
+        Ok(json!(snippets))
+    }
+
+    fn search(&self, request: &Value) -> Result<Value, BatchError> {
+        let query = string_field(request, "query")?
+            .ok_or_else(|| BatchError::InvalidParams("missing field 'query'".to_string()))?;
+        let paths = listing::files_containing(&self.snippet_dir, &[query], &self.extensions)?;
+        Ok(json!(paths))
+    }
+
+    fn cat(&self, request: &Value) -> Result<Value, BatchError> {
+        let path = self.snippet_path(request)?;
+        let content = String::from_utf8_lossy(&fs::read(&path).map_err(SnippetVaultError::from)?)
+            .into_owned();
+        index::log_access(&path, Access::Opened);
+        Ok(json!({ "path": path, "content": content }))
+    }
+
+    fn info(&self, request: &Value) -> Result<Value, BatchError> {
+        let path = self.snippet_path(request)?;
+        Ok(json!(SnippetInfo::collect(&path)?))
+    }
+
+    fn create(&self, request: &Value) -> Result<Value, BatchError> {
+        let language = string_field(request, "language")?
+            .ok_or_else(|| BatchError::InvalidParams("missing field 'language'".to_string()))?;
+        let language = language::resolve_language(language, &self.config.language_aliases);
+        let tags = strings_field(request, "tags")?;
+        let code = string_field(request, "code")?.unwrap_or_default();
+
+        // The session can outlive many snippets, so each gets its own timestamp.
+        let now = Local::now().naive_local();
+        let timestamp = snippet::render_timestamp(&now, self.config.timestamp_format())
+            .unwrap_or_else(|_| now.format(TIMESTAMP_FORMAT).to_string());
+        // The executor prints what it would do under --dry-run, which would
+        // corrupt the response stream, so a dry run only names the file.
+        if self.executor.is_dry_run() {
+            let path = self
+                .snippet_dir
+                .join(snippet::snippet_filename(&timestamp, &language, &tags));
+            return Ok(json!({ "path": path, "dry_run": true }));
+        }
+        let hook_env = hooks::SnippetEnv::new(&language, &tags, &timestamp);
+        hooks::run_hook("pre_create", &hook_env.vars(), self.config)?;
+        let path = vault::create_snippet_file(
+            self.config,
+            &language,
+            &tags,
+            &timestamp,
+            code,
+            PlaceholderStyle::default(),
+            self.executor,
+        )?;
+        index::log_access(&path, Access::Created);
+        index::reindex_snippet(&path, &path);
+        self.snippets.replace(None);
+        // The snippet exists either way, so a failing `post_create` hook is
+        // only reported, on stderr to keep the response stream clean.
+        let hook_env = hook_env.with_path(&path);
+        if let Err(err) = hooks::run_hook("post_create", &hook_env.vars(), self.config) {
+            eprintln!("{} {}", "⚠".warning(), err);
+        }
+        Ok(json!({ "path": path, "dry_run": false }))
+    }
+
+    /// The snippet a request names with `id` (looked up as `--info --id` does)
+    /// or `path`, which must stay inside the vault.
+    fn snippet_path(&self, request: &Value) -> Result<PathBuf, BatchError> {
+        if let Some(id) = string_field(request, "id")? {
+            return self
+                .with_snippets(|snippets| vault::find_by_id(snippets, id))?
+                .ok_or_else(|| BatchError::NotFound(format!("no snippet has the id '{}'", id)));
+        }
+        let relative = string_field(request, "path")?
+            .map(Path::new)
+            .ok_or_else(|| BatchError::InvalidParams("give 'id' or 'path'".to_string()))?;
+        if !relative
+            .components()
+            .all(|component| matches!(component, Component::Normal(_)))
+        {
+            return Err(BatchError::InvalidParams(format!(
+                "path '{}' must be relative to the vault",
+                relative.display()
+            )));
+        }
+        let path = self.snippet_dir.join(relative);
+        if !path.is_file() {
+            return Err(BatchError::NotFound(format!(
+                "{} does not exist",
+                path.display()
+            )));
+        }
+        Ok(path)
+    }
+}
+
+/// The commands of `--batch` mode with their fields, as a JSON array.
+pub fn describe() -> Value {
+    json!(COMMANDS)
+}
+
+/// The string field `name` of `request`, if present.
+fn string_field<'a>(request: &'a Value, name: &str) -> Result<Option<&'a str>, BatchError> {
+    match request.get(name) {
+        None | Some(Value::Null) => Ok(None),
+        Some(Value::String(value)) => Ok(Some(value)),
+        Some(_) => Err(BatchError::InvalidParams(format!(
+            "'{}' must be a string",
+            name
+        ))),
+    }
+}
+
+/// The string array field `name` of `request`; missing means empty.
+fn strings_field(request: &Value, name: &str) -> Result<Vec<String>, BatchError> {
+    let invalid = || BatchError::InvalidParams(format!("'{}' must be an array of strings", name));
+    match request.get(name) {
+        None | Some(Value::Null) => Ok(Vec::new()),
+        Some(Value::Array(values)) => values
+            .iter()
+            .map(|value| value.as_str().map(str::to_string).ok_or_else(invalid))
+            .collect(),
+        Some(_) => Err(invalid()),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// The responses of one session over `vault` answering `requests`.
+    fn session(vault: &Path, requests: &[&str], executor: &Executor) -> Vec<Value> {
+        crate::testing::isolate_user_dirs();
+        let config = Config {
+            vault_dir: Some(vault.display().to_string()),
+            ..Config::default()
+        };
+        let mut output = Vec::new();
+        run(
+            &config,
+            requests.join("\n").as_bytes(),
+            &mut output,
+            false,
+            executor,
+        )
+        .unwrap();
+        String::from_utf8(output)
+            .unwrap()
+            .lines()
+            .map(|line| serde_json::from_str(line).unwrap())
+            .collect()
+    }
+
+    fn error_code(response: &Value) -> &str {
+        assert_eq!(response["ok"], false, "{}", response);
+        response["error"]["code"].as_str().unwrap()
+    }
+
+    #[test]
+    fn bad_requests_get_errors_and_the_session_goes_on() {
+        let vault = tempfile::tempdir().unwrap();
+        let responses = session(
+            vault.path(),
+            &[
+                "not json",
+                r#"["list"]"#,
+                r#"{"cmd":"frobnicate"}"#,
+                r#"{"cmd":"list","limit":-1}"#,
+                r#"{"cmd":"list","tags":"git"}"#,
+                r#"{"cmd":"create"}"#,
+                r#"{"cmd":"cat"}"#,
+                r#"{"cmd":"cat","path":"../outside.md"}"#,
+                r#"{"cmd":"cat","path":"/etc/passwd"}"#,
+                r#"{"cmd":"cat","id":"2024-01-01-000000"}"#,
+                r#"{"cmd":"list"}"#,
+            ],
+            &Executor::new(true),
+        );
+        let codes: Vec<&str> = responses[..10].iter().map(error_code).collect();
+        assert_eq!(
+            codes,
+            [
+                "parse_error",
+                "parse_error",
+                "unknown_command",
+                "invalid_params",
+                "invalid_params",
+                "invalid_params",
+                "invalid_params",
+                "invalid_params",
+                "invalid_params",
+                "not_found",
+            ]
+        );
+        assert!(responses[2]["error"]["message"]
+            .as_str()
+            .unwrap()
+            .contains("known commands: list, search"));
+        assert_eq!(responses[10], json!({ "ok": true, "result": [] }));
+    }
+
+    #[test]
+    fn describe_lists_every_command() {
+        let vault = tempfile::tempdir().unwrap();
+        let responses = session(
+            vault.path(),
+            &[r#"{"cmd":"describe"}"#],
+            &Executor::new(true),
+        );
+        let commands: Vec<&str> = responses[0]["result"]
+            .as_array()
+            .unwrap()
+            .iter()
+            .map(|spec| spec["cmd"].as_str().unwrap())
+            .collect();
+        assert_eq!(
+            commands,
+            ["list", "search", "cat", "info", "create", "describe"]
+        );
+    }
+
+    #[test]
+    fn created_snippets_are_listed_and_read_back() {
+        let vault = tempfile::tempdir().unwrap();
+        let responses = session(
+            vault.path(),
+            &[
+                r#"{"cmd":"list"}"#,
+                r#"{"cmd":"create","language":"bash","tags":["git"],"code":"git status"}"#,
+                r#"{"cmd":"list","language":"bash"}"#,
+            ],
+            &Executor::new(false),
+        );
+        assert_eq!(responses[0]["result"], json!([]));
+        let created = &responses[1]["result"];
+        assert_eq!(created["dry_run"], false);
+        let path = PathBuf::from(created["path"].as_str().unwrap());
+        assert!(path.starts_with(vault.path()));
+        assert!(fs::read_to_string(&path).unwrap().contains("git status"));
+
+        // The cached listing was dropped when the snippet was created.
+        let listed = responses[2]["result"].as_array().unwrap();
+        assert_eq!(listed.len(), 1);
+        assert_eq!(listed[0]["path"], created["path"]);
+
+        let name = path.file_name().unwrap().to_str().unwrap();
+        let request = json!({ "cmd": "cat", "path": name }).to_string();
+        let responses = session(vault.path(), &[&request], &Executor::new(true));
+        assert!(responses[0]["result"]["content"]
+            .as_str()
+            .unwrap()
+            .contains("git status"));
+    }
+
+    #[test]
+    fn a_dry_run_create_writes_nothing() {
+        let vault = tempfile::tempdir().unwrap();
+        let responses = session(
+            vault.path(),
+            &[r#"{"cmd":"create","language":"bash"}"#],
+            &Executor::new(true),
+        );
+        assert_eq!(responses[0]["result"]["dry_run"], true);
+        assert_eq!(fs::read_dir(vault.path()).unwrap().count(), 0);
+    }
+}
@@ -0,0 +1,126 @@
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use crate::error::SnippetVaultError;
+use crate::expiry;
+use crate::snippet::{is_markdown, parse_tags_from_filename, split_frontmatter, Frontmatter};
+use crate::tags::retag_filename;
+use crate::vault::{Executor, Mutation};
+
+/// What `--snippet_bulk` does to every snippet its filter selects.
+#[derive(Debug, Clone, PartialEq)]
+pub enum BulkAction {
+    /// Add and remove tags, in the frontmatter and the generated filename.
+    Retag {
+        add: Vec<String>,
+        remove: Vec<String>,
+    },
+    /// Set the `archived` frontmatter field.
+    Archive,
+    Delete,
+    /// Copy into a directory, keeping the filename.
+    Export(PathBuf),
+    /// Only print the path.
+    Print,
+}
+
+impl BulkAction {
+    /// Past-tense verb for the summary line.
+    pub fn verb(&self) -> &'static str {
+        match self {
+            BulkAction::Retag { .. } => "Retagged",
+            BulkAction::Archive => "Archived",
+            BulkAction::Delete => "Deleted",
+            BulkAction::Export(_) => "Exported",
+            BulkAction::Print => "Matched",
+        }
+    }
+
+    /// Whether the action changes the vault (and so needs confirmation).
+    pub fn is_destructive(&self) -> bool {
+        !matches!(self, BulkAction::Print | BulkAction::Export(_))
+    }
+}
+
+/// Applies `action` to the snippet at `path` and returns where the snippet
+/// lives afterwards (`None` once deleted).
+/// - `executor`: Applies (or, with `--dry-run`, only prints) the changes.
+pub fn apply(
+    path: &Path,
+    action: &BulkAction,
+    executor: &Executor,
+) -> Result<Option<PathBuf>, SnippetVaultError> {
+    match action {
+        BulkAction::Retag { add, remove } => retag(path, add, remove, executor).map(Some),
+        BulkAction::Archive => {
+            expiry::archive(path, executor)?;
+            Ok(Some(path.to_path_buf()))
+        }
+        BulkAction::Delete => {
+            executor.apply(Mutation::Remove(path.to_path_buf()))?;
+            Ok(None)
+        }
+        BulkAction::Export(output_dir) => {
+            let file_name = path.file_name().ok_or_else(|| SnippetVaultError::Parse {
+                path: path.to_path_buf(),
+                message: "not a file".to_string(),
+            })?;
+            executor.apply(Mutation::Write {
+                path: output_dir.join(file_name),
+                contents: fs::read_to_string(path)?,
+            })?;
+            Ok(Some(path.to_path_buf()))
+        }
+        BulkAction::Print => Ok(Some(path.to_path_buf())),
+    }
+}
+
+/// Adds `add` and drops `remove` from the snippet's tags, rewriting the
+/// frontmatter of markdown snippets and renaming generated filenames.
+/// Returns the snippet's new path.
+pub fn retag(
+    path: &Path,
+    add: &[String],
+    remove: &[String],
+    executor: &Executor,
+) -> Result<PathBuf, SnippetVaultError> {
+    let update = |tags: &mut Vec<String>| {
+        tags.retain(|tag| !remove.contains(tag));
+        for tag in add {
+            if !tags.contains(tag) {
+                tags.push(tag.clone());
+            }
+        }
+    };
+
+    if is_markdown(path) {
+        let content = fs::read_to_string(path)?;
+        let mut frontmatter = Frontmatter::load(path, &content)?;
+        let before = frontmatter.tags.clone();
+        update(&mut frontmatter.tags);
+        if frontmatter.tags != before {
+            let body = split_frontmatter(&content).1;
+            executor.apply(Mutation::Write {
+                path: path.to_path_buf(),
+                contents: format!("{}{}", frontmatter.render(), body),
+            })?;
+        }
+    }
+
+    let mut tags = parse_tags_from_filename(path);
+    update(&mut tags);
+    let new_path = match retag_filename(path, &tags) {
+        Some(name) => path.with_file_name(name),
+        None => path.to_path_buf(),
+    };
+    if new_path != path {
+        if new_path.exists() {
+            return Err(SnippetVaultError::AlreadyExists(new_path));
+        }
+        executor.apply(Mutation::Rename {
+            from: path.to_path_buf(),
+            to: new_path.clone(),
+        })?;
+    }
+    Ok(new_path)
+}
@@ -0,0 +1,260 @@
+use serde::{Deserialize, Serialize};
+use std::collections::{HashMap, HashSet};
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::time::UNIX_EPOCH;
+
+use crate::error::SnippetVaultError;
+use crate::snippet::{self, SnippetMeta};
+use crate::vault::{write_atomic, Executor, Mutation};
+
+/// Bumped whenever the cached layout changes; older cache files are discarded.
+const CACHE_VERSION: u32 = 6;
+
+/// File fingerprint used to decide whether a cached entry is still valid.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Fingerprint {
+    mtime_secs: u64,
+    mtime_nanos: u32,
+    size: u64,
+}
+
+impl Fingerprint {
+    /// Reads the modification time and size of `path`.
+    pub fn of(path: &Path) -> Option<Self> {
+        let metadata = fs::metadata(path).ok()?;
+        let mtime = metadata.modified().ok()?.duration_since(UNIX_EPOCH).ok()?;
+        Some(Fingerprint {
+            mtime_secs: mtime.as_secs(),
+            mtime_nanos: mtime.subsec_nanos(),
+            size: metadata.len(),
+        })
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+struct CacheEntry {
+    fingerprint: Fingerprint,
+    meta: SnippetMeta,
+    /// See [`MetadataCache::content_hash`]; filled in on first request.
+    #[serde(default)]
+    content_hash: Option<String>,
+}
+
+#[derive(Debug, Default, Serialize, Deserialize)]
+struct CacheFile {
+    version: u32,
+    entries: HashMap<PathBuf, CacheEntry>,
+}
+
+/// Parsed snippet metadata persisted between runs under the XDG cache directory.
+#[derive(Debug, Default)]
+pub struct MetadataCache {
+    entries: HashMap<PathBuf, CacheEntry>,
+    dirty: bool,
+}
+
+impl MetadataCache {
+    /// Location of the cache file.
+    pub fn path() -> PathBuf {
+        dirs::cache_dir()
+            .unwrap_or_else(std::env::temp_dir)
+            .join("snippets_vault")
+            .join("metadata.json")
+    }
+
+    /// Loads the cache from disk. A missing, corrupt, or version-mismatched cache
+    /// silently yields an empty one that will be rebuilt.
+    pub fn load() -> Self {
+        let entries = fs::read_to_string(Self::path())
+            .ok()
+            .and_then(|raw| serde_json::from_str::<CacheFile>(&raw).ok())
+            .filter(|file| file.version == CACHE_VERSION)
+            .map(|file| file.entries)
+            .unwrap_or_default();
+
+        MetadataCache {
+            entries,
+            dirty: false,
+        }
+    }
+
+    /// Returns the cached metadata for `path` if the file has not changed since it was cached.
+    pub fn get(&self, path: &Path, fingerprint: Fingerprint) -> Option<&SnippetMeta> {
+        self.entries
+            .get(path)
+            .filter(|entry| entry.fingerprint == fingerprint)
+            .map(|entry| &entry.meta)
+    }
+
+    /// Every cached snippet, whether or not its file has changed since.
+    pub fn snippets(&self) -> impl Iterator<Item = &SnippetMeta> {
+        self.entries.values().map(|entry| &entry.meta)
+    }
+
+    /// Records freshly parsed metadata for `path`.
+    pub fn insert(&mut self, fingerprint: Fingerprint, meta: SnippetMeta) {
+        self.entries.insert(
+            meta.path.clone(),
+            CacheEntry {
+                fingerprint,
+                meta,
+                content_hash: None,
+            },
+        );
+        self.dirty = true;
+    }
+
+    /// The hash of the code in `path` (see [`snippet::hash_code_blocks`]),
+    /// served from the cache while the file is unchanged. A freshly computed
+    /// hash is only kept when `path` has a current metadata entry.
+    pub fn content_hash(&mut self, path: &Path) -> Result<String, SnippetVaultError> {
+        let entry = Fingerprint::of(path).and_then(|fingerprint| {
+            self.entries
+                .get_mut(path)
+                .filter(|entry| entry.fingerprint == fingerprint)
+        });
+        let Some(entry) = entry else {
+            return snippet::hash_code_blocks(path);
+        };
+        if let Some(hash) = &entry.content_hash {
+            return Ok(hash.clone());
+        }
+        let hash = snippet::hash_code_blocks(path)?;
+        entry.content_hash = Some(hash.clone());
+        self.dirty = true;
+        Ok(hash)
+    }
+
+    /// Drops entries under `snippet_dir` whose files are not in `live`, the
+    /// files just scanned there. Entries of other vaults are kept.
+    pub fn retain(&mut self, snippet_dir: &Path, live: &HashSet<PathBuf>) {
+        let before = self.entries.len();
+        self.entries
+            .retain(|path, _| !path.starts_with(snippet_dir) || live.contains(path));
+        self.dirty |= self.entries.len() != before;
+    }
+
+    /// Writes the cache back atomically if anything changed.
+    pub fn save(self) -> Result<(), SnippetVaultError> {
+        if !self.dirty {
+            return Ok(());
+        }
+
+        let path = Self::path();
+        if let Some(parent) = path.parent() {
+            fs::create_dir_all(parent)?;
+        }
+        let file = CacheFile {
+            version: CACHE_VERSION,
+            entries: self.entries,
+        };
+        let raw = serde_json::to_string(&file).expect("cache is always serializable");
+        write_atomic(&path, &raw)
+    }
+
+    /// Deletes the cache file. Returns whether there was one to delete.
+    pub fn clear(executor: &Executor) -> Result<bool, SnippetVaultError> {
+        let path = Self::path();
+        if !path.exists() {
+            return Ok(false);
+        }
+        executor.apply(Mutation::Remove(path))?;
+        Ok(true)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::time::{Duration, SystemTime};
+
+    /// Writes a snippet titled `title` to `path` and caches its metadata.
+    fn cached(cache: &mut MetadataCache, path: &Path, title: &str) {
+        fs::write(path, format!("# Title: {}\n```bash\necho hi\n```\n", title)).unwrap();
+        let fingerprint = Fingerprint::of(path).unwrap();
+        cache.insert(fingerprint, SnippetMeta::read(path).unwrap());
+    }
+
+    #[test]
+    fn changed_size_or_mtime_invalidates_entry() {
+        let vault = tempfile::tempdir().unwrap();
+        let path = vault.path().join("greet.md");
+        let mut cache = MetadataCache::default();
+        cached(&mut cache, &path, "Greet");
+        let hit = cache.get(&path, Fingerprint::of(&path).unwrap());
+        assert_eq!(hit.map(|meta| meta.title.as_str()), Some("Greet"));
+
+        // Same size, new modification time.
+        let earlier = SystemTime::now() - Duration::from_secs(3600);
+        fs::File::options()
+            .write(true)
+            .open(&path)
+            .unwrap()
+            .set_modified(earlier)
+            .unwrap();
+        assert!(cache.get(&path, Fingerprint::of(&path).unwrap()).is_none());
+
+        // New size: the miss leads to a re-parse that sees the new title.
+        cached(&mut cache, &path, "Greet");
+        fs::write(&path, "# Title: Greet everyone\n```bash\necho hi\n```\n").unwrap();
+        let fingerprint = Fingerprint::of(&path).unwrap();
+        assert!(cache.get(&path, fingerprint).is_none());
+        cache.insert(fingerprint, SnippetMeta::read(&path).unwrap());
+        let hit = cache.get(&path, fingerprint);
+        assert_eq!(hit.map(|meta| meta.title.as_str()), Some("Greet everyone"));
+    }
+
+    #[test]
+    fn retain_only_prunes_the_scanned_vault() {
+        let work = tempfile::tempdir().unwrap();
+        let scanned = work.path().join("work");
+        let other = work.path().join("personal");
+        fs::create_dir_all(&scanned).unwrap();
+        fs::create_dir_all(&other).unwrap();
+        let mut cache = MetadataCache::default();
+        cached(&mut cache, &scanned.join("kept.md"), "Kept");
+        cached(&mut cache, &scanned.join("deleted.md"), "Deleted");
+        cached(&mut cache, &other.join("elsewhere.md"), "Elsewhere");
+
+        let live = HashSet::from([scanned.join("kept.md")]);
+        cache.retain(&scanned, &live);
+
+        let mut titles: Vec<&str> = cache.snippets().map(|meta| meta.title.as_str()).collect();
+        titles.sort_unstable();
+        assert_eq!(titles, ["Elsewhere", "Kept"]);
+    }
+
+    #[test]
+    fn content_hash_follows_the_code_only() {
+        let vault = tempfile::tempdir().unwrap();
+        let path = vault.path().join("undo.md");
+        let mut cache = MetadataCache::default();
+        let mut hash_of = |content: &str| {
+            fs::write(&path, content).unwrap();
+            cache.insert(
+                Fingerprint::of(&path).unwrap(),
+                SnippetMeta::read(&path).unwrap(),
+            );
+            cache.content_hash(&path).unwrap()
+        };
+
+        let original = hash_of(
+            "+++\ntitle = \"Undo\"\nlanguage = \"bash\"\ntags = [\"git\"]\n+++\n\n\
+             Drops the last commit.\n\n```bash\ngit reset HEAD~1\n```\n",
+        );
+        let reworded = hash_of(
+            "+++\ntitle = \"Undo the last commit\"\nlanguage = \"bash\"\n\
+             tags = [\"git\", \"undo\"]\nrating = 5\n+++\n\n\
+             Keeps the changes in the working tree.\n\n```bash\ngit reset HEAD~1\n```\n\nSee also.\n",
+        );
+        assert_eq!(reworded, original);
+        assert_eq!(reworded, snippet::hash_code_blocks(&path).unwrap());
+
+        let recoded = hash_of(
+            "+++\ntitle = \"Undo\"\nlanguage = \"bash\"\ntags = [\"git\"]\n+++\n\n\
+             Drops the last commit.\n\n```bash\ngit reset --soft HEAD~1\n```\n",
+        );
+        assert_ne!(recoded, original);
+    }
+}
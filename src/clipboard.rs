@@ -0,0 +1,111 @@
+use std::io::Write;
+use std::process::{Command as ProcessCommand, ExitStatus, Stdio};
+
+use crate::error::SnippetVaultError;
+use crate::logging;
+use crate::platform::find_in_path;
+
+/// A clipboard program and its arguments.
+type ClipboardCommand = (&'static str, &'static [&'static str]);
+
+/// Commands that print the clipboard, in order of preference for each platform.
+const PASTE_COMMANDS: &[ClipboardCommand] = &[
+    ("pbpaste", &[]),
+    ("wl-paste", &["--no-newline"]),
+    ("xclip", &["-selection", "clipboard", "-o"]),
+    ("xsel", &["--clipboard", "--output"]),
+    ("powershell", &["-NoProfile", "-Command", "Get-Clipboard"]),
+];
+
+/// Commands that replace the clipboard with their stdin, in the same order.
+const COPY_COMMANDS: &[ClipboardCommand] = &[
+    ("pbcopy", &[]),
+    ("wl-copy", &[]),
+    ("xclip", &["-selection", "clipboard", "-i"]),
+    ("xsel", &["--clipboard", "--input"]),
+    ("clip", &[]),
+];
+
+/// Returns the first available clipboard read command.
+fn paste_command() -> Option<ClipboardCommand> {
+    first_available(PASTE_COMMANDS)
+}
+
+/// Returns the first available clipboard write command.
+fn copy_command() -> Option<ClipboardCommand> {
+    first_available(COPY_COMMANDS)
+}
+
+fn first_available(commands: &[ClipboardCommand]) -> Option<ClipboardCommand> {
+    commands
+        .iter()
+        .copied()
+        .filter(|(program, _)| {
+            !program.starts_with("wl-") || std::env::var_os("WAYLAND_DISPLAY").is_some()
+        })
+        .find(|(program, _)| find_in_path(program).is_some())
+}
+
+/// Reads the clipboard as text. Fails when no clipboard tool is installed, the
+/// clipboard is empty, or it holds data that is not valid UTF-8.
+pub fn read_clipboard() -> Result<String, SnippetVaultError> {
+    let (program, args) = paste_command().ok_or_else(|| {
+        SnippetVaultError::Clipboard(
+            "no clipboard tool found (install pbpaste, wl-paste, xclip, or xsel)".to_string(),
+        )
+    })?;
+
+    tracing::debug!(%program, ?args, "reading clipboard");
+    let output = ProcessCommand::new(program).args(args).output()?;
+    if !output.status.success() {
+        return Err(failure(program, &output.status, &output.stderr));
+    }
+
+    let text = String::from_utf8(output.stdout).map_err(|_| {
+        SnippetVaultError::Clipboard("clipboard does not contain UTF-8 text".to_string())
+    })?;
+    if text.trim().is_empty() {
+        return Err(SnippetVaultError::Clipboard(
+            "clipboard is empty".to_string(),
+        ));
+    }
+    Ok(text)
+}
+
+/// Replaces the clipboard contents with `text`.
+pub fn write_clipboard(text: &str) -> Result<(), SnippetVaultError> {
+    let (program, args) = copy_command().ok_or_else(|| {
+        SnippetVaultError::Clipboard(
+            "no clipboard tool found (install pbcopy, wl-copy, xclip, or xsel)".to_string(),
+        )
+    })?;
+
+    tracing::debug!(%program, ?args, "writing clipboard");
+    let mut child = ProcessCommand::new(program)
+        .args(args)
+        .stdin(Stdio::piped())
+        .stdout(Stdio::null())
+        .stderr(Stdio::piped())
+        .spawn()?;
+    if let Some(mut stdin) = child.stdin.take() {
+        stdin.write_all(text.as_bytes())?;
+    }
+    let output = child.wait_with_output()?;
+    if !output.status.success() {
+        return Err(failure(program, &output.status, &output.stderr));
+    }
+    Ok(())
+}
+
+/// The error of a clipboard program that exited with `status`, after saving
+/// what it wrote to `stderr` as the last error.
+fn failure(program: &str, status: &ExitStatus, stderr: &[u8]) -> SnippetVaultError {
+    let message = format!("{} exited with {}", program, status);
+    let stderr = String::from_utf8_lossy(stderr);
+    if stderr.trim().is_empty() {
+        logging::save_last_error(&message);
+    } else {
+        logging::save_last_error(&format!("{}:\n{}", message, stderr.trim_end()));
+    }
+    SnippetVaultError::Clipboard(message)
+}
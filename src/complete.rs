@@ -0,0 +1,151 @@
+use crate::snippet::SnippetMeta;
+use crate::tags;
+
+/// What `__complete` offers as candidates.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Kind {
+    /// Snippet titles.
+    Titles,
+    /// Tags from frontmatter and filenames, including the parents of
+    /// dot-separated tags (`rust` for `rust.async`).
+    Tags,
+}
+
+impl Kind {
+    /// Parses `titles` or `tags`.
+    pub fn from_name(name: &str) -> Option<Self> {
+        match name {
+            "titles" => Some(Kind::Titles),
+            "tags" => Some(Kind::Tags),
+            _ => None,
+        }
+    }
+}
+
+/// Candidates of `kind` among `snippets` that start with `prefix`, compared
+/// case-insensitively, one entry per distinct string. They are sorted by
+/// their lowercase form, then byte by byte, so the order never depends on the
+/// locale. Tabs and line breaks become spaces, keeping one candidate per line.
+/// - `limit`: Keep only the first this many candidates.
+pub fn candidates(
+    snippets: &[SnippetMeta],
+    kind: Kind,
+    prefix: &str,
+    limit: Option<usize>,
+) -> Vec<String> {
+    let prefix = prefix.to_lowercase();
+    let mut found: Vec<(String, String)> = Vec::new();
+    let mut add = |candidate: String| {
+        let candidate = candidate
+            .replace(['\t', '\r', '\n'], " ")
+            .trim()
+            .to_string();
+        let folded = candidate.to_lowercase();
+        if !candidate.is_empty() && folded.starts_with(&prefix) {
+            found.push((folded, candidate));
+        }
+    };
+    for snippet in snippets {
+        match kind {
+            Kind::Titles => add(snippet.title.clone()),
+            Kind::Tags => {
+                for tag in tags::snippet_tags(snippet) {
+                    for (end, _) in tag.match_indices('.') {
+                        add(tag[..end].to_string());
+                    }
+                    add(tag);
+                }
+            }
+        }
+    }
+    found.sort();
+    found.dedup();
+    found
+        .into_iter()
+        .map(|(_, candidate)| candidate)
+        .take(limit.unwrap_or(usize::MAX))
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::snippet::SnippetStats;
+    use std::path::PathBuf;
+
+    fn snippet(title: &str, tags: &[&str]) -> SnippetMeta {
+        SnippetMeta {
+            path: PathBuf::from(format!("/vault/{}.md", title.len())),
+            title: title.to_string(),
+            language: "bash".to_string(),
+            tags: tags.iter().map(|tag| tag.to_string()).collect(),
+            created: None,
+            summary: None,
+            extra: Default::default(),
+            stats: SnippetStats::default(),
+        }
+    }
+
+    fn vault() -> Vec<SnippetMeta> {
+        vec![
+            snippet("git undo", &["git", "rust.async"]),
+            snippet("Git stash", &["Git"]),
+            snippet("Ägypten notes", &["rust.macros"]),
+            snippet("zebra\tstripes", &["zsh"]),
+            snippet("apt update", &["apt"]),
+            snippet("Go modules", &["go"]),
+        ]
+    }
+
+    #[test]
+    fn titles_match_prefix_case_insensitively() {
+        assert_eq!(
+            candidates(&vault(), Kind::Titles, "GI", None),
+            ["Git stash", "git undo"]
+        );
+        assert_eq!(
+            candidates(&vault(), Kind::Titles, "äg", None),
+            ["Ägypten notes"]
+        );
+        assert!(candidates(&vault(), Kind::Titles, "nothing", None).is_empty());
+    }
+
+    #[test]
+    fn tags_include_parents_once() {
+        assert_eq!(
+            candidates(&vault(), Kind::Tags, "rust", None),
+            ["rust", "rust.async", "rust.macros"]
+        );
+        // `git` and `Git` are distinct strings, both offered.
+        assert_eq!(
+            candidates(&vault(), Kind::Tags, "g", None),
+            ["Git", "git", "go"]
+        );
+    }
+
+    #[test]
+    fn limit_keeps_the_first_candidates() {
+        let all = candidates(&vault(), Kind::Titles, "", None);
+        assert_eq!(all.len(), 6);
+        assert_eq!(candidates(&vault(), Kind::Titles, "", Some(2)), all[..2]);
+        assert!(candidates(&vault(), Kind::Titles, "", Some(0)).is_empty());
+    }
+
+    #[test]
+    fn sort_is_stable_and_locale_independent() {
+        // Lowercase first, then bytes: no locale collation, so "Ä" sorts after
+        // every ASCII letter and the input order never matters.
+        let expected = [
+            "apt update",
+            "Git stash",
+            "git undo",
+            "Go modules",
+            "zebra stripes",
+            "Ägypten notes",
+        ];
+        let mut snippets = vault();
+        assert_eq!(candidates(&snippets, Kind::Titles, "", None), expected);
+        snippets.reverse();
+        assert_eq!(candidates(&snippets, Kind::Titles, "", None), expected);
+    }
+}
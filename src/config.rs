@@ -0,0 +1,284 @@
+use serde::Deserialize;
+use std::collections::HashMap;
+use std::fs;
+use std::path::PathBuf;
+
+use crate::editor::Opener;
+use crate::error::SnippetVaultError;
+use crate::housekeep::HousekeepingConfig;
+use crate::language;
+use crate::lint::LintRuleSpec;
+use crate::motd;
+use crate::picker;
+use crate::render;
+use crate::s3::S3Config;
+use crate::snippet;
+use crate::style::{Palette, StatusColors, TerminalTheme, ThemeConfig};
+use crate::usage::UsageStore;
+use crate::webdav::WebDavConfig;
+use crate::width;
+
+/// Preview context used by `--find_in_files` when neither the flag nor the config sets one.
+pub const DEFAULT_SEARCH_CONTEXT: usize = 10;
+
+/// Largest accepted `--context` / `search_context_lines`.
+pub const MAX_SEARCH_CONTEXT: usize = 50;
+
+/// Length from which `--suggest` considers a history command worth saving.
+pub const DEFAULT_SUGGEST_MIN_LENGTH: usize = 60;
+
+/// Number of pipes from which `--suggest` considers a command worth saving, whatever its length.
+pub const DEFAULT_SUGGEST_MIN_PIPES: usize = 2;
+
+/// User settings read from `config.toml`. Every section is optional.
+#[derive(Debug, Default, Deserialize)]
+#[serde(default)]
+pub struct Config {
+    /// Directory holding the snippets; `~` is expanded.
+    pub vault_dir: Option<String>,
+    /// Editor used to open snippets.
+    pub editor: Option<String>,
+    /// Editors keyed by language or file extension, plus `default`.
+    pub editors: HashMap<String, String>,
+    /// Arguments that open `{file}` at `{line}`, for editors without a built-in jump syntax.
+    pub editor_line_template: Option<String>,
+    /// `--editor` from the command line; overrides every other editor setting.
+    #[serde(skip)]
+    pub editor_override: Option<String>,
+    /// `--no-pager` from the command line; long reports go straight to stdout.
+    #[serde(skip)]
+    pub no_pager: bool,
+    /// `--width` from the command line; replaces the detected terminal width.
+    #[serde(skip)]
+    pub width_override: Option<usize>,
+    /// Flags appended to every fzf invocation, after `$FZF_DEFAULT_OPTS`; `--fzf-arg`
+    /// values from the command line are added after these.
+    pub fzf_args: Vec<String>,
+    /// fzf `--preview-window` of the pickers, e.g. `right:50%`; checked when
+    /// the file is loaded.
+    #[serde(deserialize_with = "deserialize_preview_size")]
+    pub fzf_preview_size: Option<String>,
+    /// Shell command previewing a snippet in the pickers, with `{}` for the
+    /// file; replaces the built-in glow, bat, or cat choice.
+    pub preview_command: Option<String>,
+    /// Alternative language names mapped to canonical ones, e.g. `py = "python"`.
+    pub language_aliases: HashMap<String, String>,
+    /// Highlighting theme for `--cat_snippet`, e.g. `base16-ocean.dark`, or a
+    /// `[theme]` section that also holds the status colors.
+    pub theme: Option<ThemeConfig>,
+    /// Bucket that `--snippet_backup_to_s3` uploads to.
+    pub s3_backup: Option<S3Config>,
+    /// Server that `--sync webdav` syncs the vault with.
+    pub webdav: Option<WebDavConfig>,
+    /// Run code blocks through their language's formatter after every editor
+    /// session; the same as `--format-on-save` on every run.
+    pub format_on_save: bool,
+    /// Formatter commands keyed by language, reading code on stdin and writing
+    /// it to stdout; added to or replacing the built-in ones.
+    pub formatters: HashMap<String, String>,
+    /// Extra lint rules keyed by language, added to the built-in ones.
+    pub lint_rules: HashMap<String, Vec<LintRuleSpec>>,
+    /// File extensions read from the vault; defaults to markdown, text, and known code extensions.
+    pub extensions: Option<Vec<String>>,
+    /// Commit and push the vault after every edit when it is a git repository.
+    pub push_after_edit: bool,
+    /// Refuse every change to the vault, e.g. a shared one mounted from a
+    /// server; the same as `--readonly` on every run.
+    pub readonly: bool,
+    /// Confidence (0.0 to 1.0) a language guess needs before it is used instead of `text`.
+    pub detect_threshold: Option<f64>,
+    /// strftime format of the timestamp in new snippet filenames.
+    pub timestamp_format: Option<String>,
+    /// Lines of context around matches in the `--find_in_files` preview.
+    pub search_context_lines: Option<usize>,
+    /// Shell history file read by `--suggest`; `~` is expanded.
+    pub history_file: Option<String>,
+    /// Commands at least this long are `--suggest` candidates.
+    pub suggest_min_length: Option<usize>,
+    /// Commands with at least this many pipes are `--suggest` candidates.
+    pub suggest_min_pipes: Option<usize>,
+    /// Mark an expired snippet `archived = true` the first time it is opened or printed.
+    pub auto_archive_expired: bool,
+    /// Files larger than this many bytes are listed from their filename alone,
+    /// left out of searches and previews, and reported by `--snippet_lint`.
+    pub max_parse_size: Option<u64>,
+    /// Tasks `--housekeep` performs.
+    pub housekeeping: HousekeepingConfig,
+    /// Shell commands run around snippet operations, keyed by hook name
+    /// (`pre_create`, `post_delete`, ...).
+    pub hooks: HashMap<String, String>,
+    /// `--skip_hooks` from the command line; no hook runs.
+    #[serde(skip)]
+    pub skip_hooks: bool,
+    /// Where uses of snippets are counted: `index` (default) or `frontmatter`.
+    pub usage_store: Option<String>,
+    /// `--no-track` from the command line; uses are not counted.
+    #[serde(skip)]
+    pub no_track: bool,
+    /// Where picked snippets open: `editor` (default), `vscode`, or `obsidian`.
+    pub open_with: Option<String>,
+    /// Obsidian's name for the vault; defaults to the vault directory's name.
+    pub obsidian_vault: Option<String>,
+    /// Documentation URLs by language for `--snippet_language_docs`, added to
+    /// or replacing the built-in ones.
+    pub language_docs: HashMap<String, String>,
+    /// Colors of success, error, warning, and accent output; a `[theme]`
+    /// section sets the same and wins over this one.
+    pub status_colors: StatusColors,
+    /// `--theme` from the command line; replaces the configured `preset`.
+    #[serde(skip)]
+    pub status_theme: Option<String>,
+    /// What `--motd` leans towards: `pinned` or `rare` snippets; unset or
+    /// unknown values pick evenly.
+    pub motd_prefer: Option<String>,
+    /// Terminal background: `dark`, `light`, or `auto` (default), which
+    /// guesses from the environment. `--snippet_color_theme` overrides it.
+    pub terminal_theme: TerminalTheme,
+}
+
+impl Config {
+    /// File extensions (without the dot) that count as snippets.
+    pub fn extensions(&self) -> Vec<String> {
+        match &self.extensions {
+            Some(extensions) => extensions
+                .iter()
+                .map(|extension| extension.trim_start_matches('.').to_lowercase())
+                .collect(),
+            None => language::default_extensions(),
+        }
+    }
+
+    /// Format of the timestamp in new snippet filenames.
+    pub fn timestamp_format(&self) -> &str {
+        self.timestamp_format
+            .as_deref()
+            .unwrap_or(snippet::TIMESTAMP_FORMAT)
+    }
+
+    /// Lines of context around matches in the search preview.
+    pub fn search_context_lines(&self) -> usize {
+        self.search_context_lines.unwrap_or(DEFAULT_SEARCH_CONTEXT)
+    }
+
+    /// The shell history `--suggest` reads: `history_file`, then `$HISTFILE`,
+    /// then `~/.zsh_history` when it exists, then `~/.bash_history`.
+    pub fn history_file(&self) -> PathBuf {
+        if let Some(path) = &self.history_file {
+            return PathBuf::from(shellexpand::tilde(path).into_owned());
+        }
+        if let Some(path) = std::env::var_os("HISTFILE").filter(|path| !path.is_empty()) {
+            return PathBuf::from(path);
+        }
+        let home = dirs::home_dir().unwrap_or_default();
+        let zsh = home.join(".zsh_history");
+        if zsh.exists() {
+            zsh
+        } else {
+            home.join(".bash_history")
+        }
+    }
+
+    /// Length from which a history command is a `--suggest` candidate.
+    pub fn suggest_min_length(&self) -> usize {
+        self.suggest_min_length
+            .unwrap_or(DEFAULT_SUGGEST_MIN_LENGTH)
+    }
+
+    /// Number of pipes from which a history command is a `--suggest` candidate.
+    pub fn suggest_min_pipes(&self) -> usize {
+        self.suggest_min_pipes.unwrap_or(DEFAULT_SUGGEST_MIN_PIPES)
+    }
+
+    /// Size in bytes above which vault files are not parsed.
+    pub fn max_parse_size(&self) -> u64 {
+        self.max_parse_size.unwrap_or(snippet::DEFAULT_PARSE_LIMIT)
+    }
+
+    /// Where uses of snippets are counted; unknown values mean the index.
+    pub fn usage_store(&self) -> UsageStore {
+        self.usage_store
+            .as_deref()
+            .and_then(UsageStore::from_name)
+            .unwrap_or_default()
+    }
+
+    /// Where picked snippets open; unknown values mean the editor.
+    pub fn open_with(&self) -> Opener {
+        self.open_with
+            .as_deref()
+            .and_then(Opener::from_name)
+            .unwrap_or_default()
+    }
+
+    /// What `--motd` leans towards; see [`motd::Preference`].
+    pub fn motd_prefer(&self) -> motd::Preference {
+        self.motd_prefer
+            .as_deref()
+            .and_then(motd::Preference::from_name)
+            .unwrap_or_default()
+    }
+
+    /// The palette of status output, with any problems in `[theme]` or
+    /// `status_colors`; see [`Palette::from_config`].
+    pub fn palette(&self) -> (Palette, Vec<String>) {
+        let colors = match self.theme.as_ref().and_then(ThemeConfig::colors) {
+            Some(colors) => colors.or(&self.status_colors),
+            None => self.status_colors.clone(),
+        };
+        Palette::from_config(&colors, self.status_theme.as_deref(), self.terminal_theme)
+    }
+
+    /// Highlighting theme of the built-in renderer: `theme` when set,
+    /// otherwise a light one on light terminals and the default on dark ones.
+    pub fn highlight_theme(&self) -> Option<&str> {
+        let theme = self.theme.as_ref().and_then(ThemeConfig::highlight);
+        match (theme, self.terminal_theme.resolve()) {
+            (Some(theme), _) => Some(theme),
+            (None, TerminalTheme::Light) => Some(render::LIGHT_THEME),
+            (None, _) => None,
+        }
+    }
+
+    /// fzf `--preview-window` of the pickers.
+    pub fn fzf_preview_size(&self) -> &str {
+        self.fzf_preview_size
+            .as_deref()
+            .unwrap_or(picker::DEFAULT_PREVIEW_SIZE)
+    }
+
+    /// Columns available for tables and wrapped text; see [`width::output_width`].
+    pub fn output_width(&self) -> usize {
+        width::output_width(self.width_override)
+    }
+
+    /// Location of the configuration file.
+    pub fn path() -> PathBuf {
+        dirs::config_dir()
+            .unwrap_or_else(std::env::temp_dir)
+            .join("snippets_vault")
+            .join("config.toml")
+    }
+
+    /// Loads the configuration file, falling back to defaults when it does not exist.
+    pub fn load() -> Result<Self, SnippetVaultError> {
+        let path = Self::path();
+        match fs::read_to_string(&path) {
+            Ok(raw) => toml::from_str(&raw).map_err(|err| {
+                SnippetVaultError::Config(format!("{}: {}", path.display(), err.message()))
+            }),
+            Err(err) if err.kind() == std::io::ErrorKind::NotFound => Ok(Config::default()),
+            Err(err) => Err(err.into()),
+        }
+    }
+}
+
+/// Reads `fzf_preview_size`, rejecting values fzf would not accept so the
+/// mistake is reported at startup rather than by a picker that fails to open.
+fn deserialize_preview_size<'de, D>(deserializer: D) -> Result<Option<String>, D::Error>
+where
+    D: serde::Deserializer<'de>,
+{
+    let size = String::deserialize(deserializer)?;
+    picker::validate_preview_size(&size).map_err(serde::de::Error::custom)?;
+    Ok(Some(size))
+}
@@ -0,0 +1,335 @@
+use regex::Regex;
+use std::path::{Path, PathBuf};
+use std::sync::LazyLock;
+
+use crate::error::SnippetVaultError;
+use crate::vault;
+use crate::width;
+
+/// File stems that sync tools give the second copy of a file edited on two
+/// devices, each capturing the stem of the original:
+/// - Syncthing: `notes.sync-conflict-20240310-101500-ABCDEFG`
+/// - Dropbox, Nextcloud, and Obsidian Sync:
+///   `notes (conflicted copy 2024-03-10 101500)`, `notes (Alice's conflicted copy)`
+/// - `--sync webdav`: `notes.conflict-2024-03-10`, `notes.conflict-2024-03-10-2`
+static PATTERNS: LazyLock<[Regex; 3]> = LazyLock::new(|| {
+    [
+        r"^(.+)\.sync-conflict-\d{8}-\d{6}(-[A-Z0-9]+)?$",
+        r"(?i)^(.+?) \([^()]*conflicted copy[^()]*\)$",
+        r"^(.+)\.conflict-\d{4}-\d{2}-\d{2}(-\d+)?$",
+    ]
+    .map(|pattern| Regex::new(pattern).expect("conflict patterns are valid"))
+});
+
+/// The file a sync conflict copy was made from, whether or not it still
+/// exists, or `None` when `path` is not named like a conflict copy.
+pub fn original_of(path: &Path) -> Option<PathBuf> {
+    let stem = path.file_stem()?.to_str()?;
+    let original = PATTERNS
+        .iter()
+        .find_map(|pattern| Some(pattern.captures(stem)?.get(1)?.as_str()))?;
+    let name = match path.extension().and_then(|ext| ext.to_str()) {
+        Some(extension) => format!("{}.{}", original, extension),
+        None => original.to_string(),
+    };
+    Some(path.with_file_name(name))
+}
+
+/// Whether `path` is named like a sync conflict copy; see [`original_of`].
+pub fn is_conflict_file(path: &Path) -> bool {
+    original_of(path).is_some()
+}
+
+/// A conflict copy and the file it conflicts with.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ConflictPair {
+    /// The file the sync tool kept; it may have been deleted since.
+    pub original: PathBuf,
+    pub copy: PathBuf,
+}
+
+/// Every conflict copy in the vault at `snippet_dir`, with its original.
+/// - `extensions`: File extensions that count as snippets.
+pub fn find_conflicts(
+    snippet_dir: &Path,
+    extensions: &[String],
+) -> Result<Vec<ConflictPair>, SnippetVaultError> {
+    Ok(vault::conflict_files(snippet_dir, extensions)?
+        .into_iter()
+        .filter_map(|copy| {
+            Some(ConflictPair {
+                original: original_of(&copy)?,
+                copy,
+            })
+        })
+        .collect())
+}
+
+/// One line of a line diff.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Line<'a> {
+    Same(&'a str),
+    Left(&'a str),
+    Right(&'a str),
+}
+
+/// Most cells [`diff_lines`] fills in its table before it gives up on finding
+/// the common lines of a changed region (about 16 MB of counts).
+const MAX_DIFF_CELLS: usize = 2_000_000;
+
+/// Line diff of `left` and `right` along their longest common subsequence.
+/// Lines shared at the start and end are matched first; when the region
+/// between them is too large for the quadratic table, it is shown as one
+/// change.
+fn diff_lines<'a>(left: &'a str, right: &'a str) -> Vec<Line<'a>> {
+    let left: Vec<&str> = left.lines().collect();
+    let right: Vec<&str> = right.lines().collect();
+    let prefix = left.iter().zip(&right).take_while(|(l, r)| l == r).count();
+    let suffix = left[prefix..]
+        .iter()
+        .rev()
+        .zip(right[prefix..].iter().rev())
+        .take_while(|(l, r)| l == r)
+        .count();
+    let (middle_left, middle_right) = (
+        &left[prefix..left.len() - suffix],
+        &right[prefix..right.len() - suffix],
+    );
+
+    let mut lines: Vec<Line> = left[..prefix].iter().map(|line| Line::Same(line)).collect();
+    if (middle_left.len() + 1).saturating_mul(middle_right.len() + 1) > MAX_DIFF_CELLS {
+        lines.extend(middle_left.iter().map(|line| Line::Left(line)));
+        lines.extend(middle_right.iter().map(|line| Line::Right(line)));
+    } else {
+        lines.extend(common_subsequence_diff(middle_left, middle_right));
+    }
+    lines.extend(
+        left[left.len() - suffix..]
+            .iter()
+            .map(|line| Line::Same(line)),
+    );
+    lines
+}
+
+/// The diff of [`diff_lines`] for the region between the shared start and end.
+fn common_subsequence_diff<'a>(left: &[&'a str], right: &[&'a str]) -> Vec<Line<'a>> {
+    let mut common = vec![vec![0usize; right.len() + 1]; left.len() + 1];
+    for i in (0..left.len()).rev() {
+        for j in (0..right.len()).rev() {
+            common[i][j] = if left[i] == right[j] {
+                common[i + 1][j + 1] + 1
+            } else {
+                common[i + 1][j].max(common[i][j + 1])
+            };
+        }
+    }
+
+    let (mut i, mut j) = (0, 0);
+    let mut lines = Vec::new();
+    while i < left.len() || j < right.len() {
+        if i < left.len() && j < right.len() && left[i] == right[j] {
+            lines.push(Line::Same(left[i]));
+            i += 1;
+            j += 1;
+        } else if j == right.len() || (i < left.len() && common[i + 1][j] >= common[i][j + 1]) {
+            lines.push(Line::Left(left[i]));
+            i += 1;
+        } else {
+            lines.push(Line::Right(right[j]));
+            j += 1;
+        }
+    }
+    lines
+}
+
+/// A common line, or a run of differing lines with the left and right sides.
+enum Hunk<'a> {
+    Same(&'a str),
+    Changed(Vec<&'a str>, Vec<&'a str>),
+}
+
+/// Groups the lines of a diff into common lines and runs of differences.
+fn hunks<'a>(lines: &[Line<'a>]) -> Vec<Hunk<'a>> {
+    let mut out = Vec::new();
+    let mut run: (Vec<&str>, Vec<&str>) = (Vec::new(), Vec::new());
+    for line in lines {
+        match *line {
+            Line::Same(text) => {
+                if !run.0.is_empty() || !run.1.is_empty() {
+                    let (lefts, rights) = std::mem::take(&mut run);
+                    out.push(Hunk::Changed(lefts, rights));
+                }
+                out.push(Hunk::Same(text));
+            }
+            Line::Left(text) => run.0.push(text),
+            Line::Right(text) => run.1.push(text),
+        }
+    }
+    if !run.0.is_empty() || !run.1.is_empty() {
+        out.push(Hunk::Changed(run.0, run.1));
+    }
+    out
+}
+
+/// `left` and `right` next to each other in the style of `diff -y`, fitted to
+/// `width` columns: changed lines are marked `|`, lines only on the left `<`,
+/// and lines only on the right `>`.
+pub fn side_by_side(left: &str, right: &str, width: usize) -> Vec<String> {
+    let column = width.saturating_sub(3) / 2;
+    let cell = |text: &str| {
+        let text = text.replace('\t', "    ");
+        width::pad(&width::truncate(&text, column), column)
+    };
+    let row = |left: &str, marker: char, right: &str| {
+        format!("{} {} {}", cell(left), marker, cell(right))
+            .trim_end()
+            .to_string()
+    };
+    let mut rows = Vec::new();
+    for hunk in hunks(&diff_lines(left, right)) {
+        match hunk {
+            Hunk::Same(text) => rows.push(row(text, ' ', text)),
+            Hunk::Changed(lefts, rights) => {
+                for index in 0..lefts.len().max(rights.len()) {
+                    rows.push(match (lefts.get(index), rights.get(index)) {
+                        (Some(l), Some(r)) => row(l, '|', r),
+                        (Some(l), None) => row(l, '<', ""),
+                        (None, Some(r)) => row("", '>', r),
+                        (None, None) => unreachable!("index is below the longer side"),
+                    });
+                }
+            }
+        }
+    }
+    rows
+}
+
+/// `local` with every run of lines that differs from `remote` wrapped in git
+/// conflict markers, ready to be edited by hand or with an editor's merge
+/// tools. Lines both sides share are written once.
+/// - `local_label`, `remote_label`: Names after `<<<<<<<` and `>>>>>>>`.
+pub fn with_conflict_markers(
+    local: &str,
+    remote: &str,
+    local_label: &str,
+    remote_label: &str,
+) -> String {
+    let mut merged = String::new();
+    for hunk in hunks(&diff_lines(local, remote)) {
+        match hunk {
+            Hunk::Same(text) => {
+                merged.push_str(text);
+                merged.push('\n');
+            }
+            Hunk::Changed(lefts, rights) => {
+                merged.push_str(&format!("<<<<<<< {}\n", local_label));
+                for line in lefts {
+                    merged.push_str(line);
+                    merged.push('\n');
+                }
+                merged.push_str("=======\n");
+                for line in rights {
+                    merged.push_str(line);
+                    merged.push('\n');
+                }
+                merged.push_str(&format!(">>>>>>> {}\n", remote_label));
+            }
+        }
+    }
+    merged
+}
+
+/// Whether `text` still holds a conflict marker line written by
+/// [`with_conflict_markers`].
+pub fn has_conflict_markers(text: &str) -> bool {
+    text.lines()
+        .any(|line| line.starts_with("<<<<<<< ") || line.starts_with(">>>>>>> "))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn conflict_copies_name_their_original() {
+        let cases = [
+            ("notes.sync-conflict-20240310-101500-ABCDEFG.md", "notes.md"),
+            ("notes.sync-conflict-20240310-101500.md", "notes.md"),
+            ("notes (conflicted copy 2024-03-10 101500).md", "notes.md"),
+            ("git undo (Alice's Conflicted Copy).md", "git undo.md"),
+            ("notes.conflict-2024-03-10.md", "notes.md"),
+            ("notes.conflict-2024-03-10-2.md", "notes.md"),
+        ];
+        for (copy, original) in cases {
+            let copy = Path::new("vault/sub").join(copy);
+            assert_eq!(
+                original_of(&copy),
+                Some(Path::new("vault/sub").join(original)),
+                "{}",
+                copy.display()
+            );
+        }
+        for name in [
+            "notes.md",
+            "notes.sync-conflict-2024.md",
+            "notes (copy).md",
+            "notes.conflict-march.md",
+        ] {
+            assert!(!is_conflict_file(Path::new(name)), "{}", name);
+        }
+    }
+
+    #[test]
+    fn diffs_follow_the_longest_common_subsequence() {
+        use Line::{Left, Right, Same};
+        assert_eq!(
+            diff_lines("a\nb\nc\nd\n", "a\nc\nx\nd\n"),
+            [Same("a"), Left("b"), Same("c"), Right("x"), Same("d")]
+        );
+        assert_eq!(diff_lines("", "new\n"), [Right("new")]);
+        assert_eq!(diff_lines("same\n", "same\n"), [Same("same")]);
+    }
+
+    #[test]
+    fn large_changes_skip_the_table_but_keep_shared_lines() {
+        let body = |side: &str| -> String {
+            let lines: String = (0..2000).map(|n| format!("{} {}\n", side, n)).collect();
+            format!("head\n{}tail\n", lines)
+        };
+        let (left, right) = (body("left"), body("right"));
+        let lines = diff_lines(&left, &right);
+        assert_eq!(lines.len(), 4002);
+        assert_eq!(lines[0], Line::Same("head"));
+        assert_eq!(lines[1], Line::Left("left 0"));
+        assert_eq!(lines[2001], Line::Right("right 0"));
+        assert_eq!(lines[4001], Line::Same("tail"));
+    }
+
+    #[test]
+    fn merges_mark_each_changed_run() {
+        let merged = with_conflict_markers(
+            "title\nold\nshared\nlocal only\n",
+            "title\nnew\nshared\n",
+            "notes.md",
+            "notes.conflict.md",
+        );
+        assert_eq!(
+            merged,
+            "title\n\
+             <<<<<<< notes.md\nold\n=======\nnew\n>>>>>>> notes.conflict.md\n\
+             shared\n\
+             <<<<<<< notes.md\nlocal only\n=======\n>>>>>>> notes.conflict.md\n"
+        );
+        assert!(has_conflict_markers(&merged));
+        assert!(!has_conflict_markers("title\nshared\n"));
+    }
+
+    #[test]
+    fn side_by_side_fits_wide_text_to_the_width() {
+        let rows = side_by_side("整理提交整理提交整理提交\nsame\n", "short\nsame\n", 21);
+        assert_eq!(rows, ["整理提交… | short", "same        same"]);
+        for row in rows {
+            assert!(width::display_width(&row) <= 21, "{}", row);
+        }
+    }
+}
@@ -0,0 +1,92 @@
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+
+use crate::error::SnippetVaultError;
+use crate::git;
+use crate::language;
+use crate::listing::{self, SnippetFilter, SortCriterion};
+
+/// Languages of data and prose files, which never make a project's language.
+const NON_CODE_LANGUAGES: &[&str] = &["markdown", "text", "json", "yaml", "toml"];
+
+/// The git project a directory belongs to, as far as snippets are concerned.
+#[derive(Debug, Clone, Default)]
+pub struct ProjectContext {
+    /// Language of most of the tracked source files.
+    pub language: Option<String>,
+    /// Repository name from the `origin` remote, used as a tag.
+    pub name: Option<String>,
+}
+
+/// Detects the project around `cwd`: the language with the most tracked files
+/// (ties go to the alphabetically first) and the name of its `origin` remote.
+/// Returns `None` outside a git repository.
+pub fn detect_project(cwd: &Path) -> Result<Option<ProjectContext>, SnippetVaultError> {
+    let Some(root) = git::repository_root(cwd) else {
+        return Ok(None);
+    };
+
+    let mut counts: HashMap<&'static str, usize> = HashMap::new();
+    for file in git::tracked_files(&root)? {
+        let language = Path::new(&file)
+            .extension()
+            .and_then(|extension| language::language_for_extension(&extension.to_string_lossy()));
+        if let Some(language) = language.filter(|language| !NON_CODE_LANGUAGES.contains(language)) {
+            *counts.entry(language).or_default() += 1;
+        }
+    }
+    let language = counts
+        .into_iter()
+        .max_by(|(a, a_count), (b, b_count)| a_count.cmp(b_count).then(b.cmp(a)))
+        .map(|(language, _)| language.to_string());
+
+    Ok(Some(ProjectContext {
+        language,
+        name: git::origin_name(&root),
+    }))
+}
+
+/// Snippets relevant to the git project around `cwd`: those in its primary
+/// language, followed by those tagged with the repository name. Outside a git
+/// repository (or in one with neither), every snippet is returned.
+/// - `extensions`: File extensions that count as snippets.
+/// - `use_cache`: Serve unchanged snippets from the metadata cache.
+pub fn context_search(
+    cwd: &Path,
+    snippet_dir: &Path,
+    extensions: &[String],
+    use_cache: bool,
+) -> Result<Vec<PathBuf>, SnippetVaultError> {
+    let project = detect_project(cwd)?.unwrap_or_default();
+    let list = |filter: SnippetFilter| {
+        listing::list_snippets_plain(
+            snippet_dir,
+            &filter,
+            SortCriterion::default(),
+            extensions,
+            use_cache,
+        )
+    };
+    if project.language.is_none() && project.name.is_none() {
+        return list(SnippetFilter::default());
+    }
+
+    let mut paths = Vec::new();
+    if let Some(language) = &project.language {
+        paths = list(SnippetFilter {
+            language: Some(language.clone()),
+            ..Default::default()
+        })?;
+    }
+    if let Some(name) = &project.name {
+        for path in list(SnippetFilter {
+            tags: vec![name.clone()],
+            ..Default::default()
+        })? {
+            if !paths.contains(&path) {
+                paths.push(path);
+            }
+        }
+    }
+    Ok(paths)
+}
@@ -0,0 +1,81 @@
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use crate::error::SnippetVaultError;
+use crate::snippet::{is_markdown, rename_language_in_filename, split_frontmatter, Frontmatter};
+use crate::vault::{Executor, Mutation};
+
+/// Changes the recorded language of the markdown snippet at `path` to
+/// `new_language`: the frontmatter `language`, the opening fence of every code
+/// block in the old language (unless `keep_fences`), and the language in the
+/// generated filename. Returns the snippet's new path.
+/// - `executor`: Applies (or, with `--dry-run`, only prints) the rewrite and rename.
+pub fn convert_language(
+    path: &Path,
+    new_language: &str,
+    keep_fences: bool,
+    executor: &Executor,
+) -> Result<PathBuf, SnippetVaultError> {
+    if !is_markdown(path) {
+        return Err(SnippetVaultError::Parse {
+            path: path.to_path_buf(),
+            message: "only markdown snippets can be converted".to_string(),
+        });
+    }
+
+    let content = fs::read_to_string(path)?;
+    let mut frontmatter = Frontmatter::load(path, &content)?;
+    let old_language = std::mem::replace(&mut frontmatter.language, new_language.to_string());
+
+    let body = split_frontmatter(&content).1;
+    let body = if keep_fences {
+        body.to_string()
+    } else {
+        refence(body, &old_language, new_language)
+    };
+
+    let new_path = match rename_language_in_filename(path, new_language) {
+        Some(name) => path.with_file_name(name),
+        None => path.to_path_buf(),
+    };
+    if new_path != path && new_path.exists() {
+        return Err(SnippetVaultError::AlreadyExists(new_path));
+    }
+
+    executor.apply(Mutation::Write {
+        path: path.to_path_buf(),
+        contents: format!("{}{}", frontmatter.render(), body),
+    })?;
+    if new_path != path {
+        executor.apply(Mutation::Rename {
+            from: path.to_path_buf(),
+            to: new_path.clone(),
+        })?;
+    }
+    Ok(new_path)
+}
+
+/// Rewrites every opening fence tagged `old_language` to `new_language`,
+/// leaving other blocks and all closing fences untouched.
+fn refence(body: &str, old_language: &str, new_language: &str) -> String {
+    let mut out = String::with_capacity(body.len());
+    for line in body.split_inclusive('\n') {
+        let indent = &line[..line.len() - line.trim_start().len()];
+        let rewritten = line
+            .trim_start()
+            .strip_prefix("```")
+            .filter(|_| !old_language.is_empty())
+            .and_then(|info| info.strip_prefix(old_language))
+            .filter(|rest| rest.is_empty() || rest.starts_with(char::is_whitespace));
+        match rewritten {
+            Some(rest) => {
+                out.push_str(indent);
+                out.push_str("```");
+                out.push_str(new_language);
+                out.push_str(rest);
+            }
+            None => out.push_str(line),
+        }
+    }
+    out
+}
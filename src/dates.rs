@@ -0,0 +1,475 @@
+use chrono::{DateTime, Datelike, Days, Local, Months, NaiveDate, NaiveDateTime, TimeZone};
+use std::path::Path;
+
+use crate::error::SnippetVaultError;
+use crate::snippet;
+use crate::vault;
+
+/// Accepted forms, repeated in every parse error.
+const FORMS: &str = "a date (2023-06-01, 2023-06) or an age (10d, 3w, 18m, 1y)";
+
+/// A relative age such as `10d`, `3w`, `18m`, or `1y`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Age {
+    Days(u32),
+    Weeks(u32),
+    Months(u32),
+    Years(u32),
+}
+
+impl Age {
+    /// The local time `self` before `now`. Month and year steps keep the day of
+    /// the month, clamped to the target month's last day (Mar 31 - 1m = Feb 28
+    /// or 29; Feb 29 - 1y = Feb 28).
+    pub fn before(self, now: NaiveDateTime) -> NaiveDateTime {
+        let earlier = match self {
+            Age::Days(days) => now.checked_sub_days(Days::new(u64::from(days))),
+            Age::Weeks(weeks) => now.checked_sub_days(Days::new(u64::from(weeks) * 7)),
+            Age::Months(months) => now.checked_sub_months(Months::new(months)),
+            Age::Years(years) => now.checked_sub_months(Months::new(years.saturating_mul(12))),
+        };
+        earlier.unwrap_or(NaiveDateTime::MIN)
+    }
+
+    /// [`Age::before`] the current local time.
+    pub fn ago(self) -> NaiveDateTime {
+        self.before(Local::now().naive_local())
+    }
+}
+
+/// How long before `now` the time `then` was, in words: `just now`,
+/// `5 minutes ago`, `yesterday`, `3 weeks ago`, `2 years ago`. Times after
+/// `now` count as just now.
+pub fn relative_age(then: NaiveDateTime, now: NaiveDateTime) -> String {
+    let elapsed = now.signed_duration_since(then);
+    let plural = |count: i64, unit: &str| match count {
+        1 => format!("1 {} ago", unit),
+        _ => format!("{} {}s ago", count, unit),
+    };
+    match elapsed.num_days() {
+        0 if elapsed.num_minutes() < 1 => "just now".to_string(),
+        0 if elapsed.num_hours() < 1 => plural(elapsed.num_minutes(), "minute"),
+        0 => plural(elapsed.num_hours(), "hour"),
+        1 => "yesterday".to_string(),
+        days @ 2..=13 => plural(days, "day"),
+        days @ 14..=59 => plural(days / 7, "week"),
+        days @ 60..=729 => plural(days / 30, "month"),
+        days if days > 0 => plural(days / 365, "year"),
+        _ => "just now".to_string(),
+    }
+}
+
+/// Parses an age for `--older-than` and friends: a whole number followed by
+/// `d`, `w`, `m` (months), or `y`.
+pub fn parse_age(text: &str) -> Result<Age, String> {
+    let text = text.trim();
+    let split = text.len() - text.trim_start_matches(|c: char| c.is_ascii_digit()).len();
+    let (count, unit) = text.split_at(split);
+    if count.is_empty() {
+        return Err(format!("'{}' is not an age; expected {}", text, FORMS));
+    }
+    let count: u32 = count
+        .parse()
+        .map_err(|_| format!("'{}' is too large an age", text))?;
+
+    match unit {
+        "d" => Ok(Age::Days(count)),
+        "w" => Ok(Age::Weeks(count)),
+        "m" => Ok(Age::Months(count)),
+        "y" => Ok(Age::Years(count)),
+        "" => Err(format!(
+            "'{}' has no unit; add d, w, m, or y (e.g. {}m for months)",
+            text, count
+        )),
+        "M" => Err(format!(
+            "'{}' is ambiguous (minutes or months?); use {}m for months",
+            text, count
+        )),
+        "h" | "min" | "s" => Err(format!(
+            "'{}' is too fine-grained; ages are counted in d, w, m, or y",
+            text
+        )),
+        _ => Err(format!(
+            "unknown unit '{}' in '{}'; use d, w, m, or y",
+            unit, text
+        )),
+    }
+}
+
+/// Parses a point in time for `--since` and `--before`, relative to `now`:
+/// an ISO date (`2023-06-01`), a year and month (`2023-06`, meaning its first
+/// day), a date and time (`2023-06-01T09:30`), or an age (`3w` = three weeks
+/// before `now`). Dates are local time, like the timestamps snippets carry.
+pub fn parse_point(text: &str, now: NaiveDateTime) -> Result<NaiveDateTime, String> {
+    let text = text.trim();
+    if text.is_empty() {
+        return Err(format!("expected {}", FORMS));
+    }
+    if text.len() == 4 && text.chars().all(|c| c.is_ascii_digit()) {
+        return Err(format!(
+            "'{}' is ambiguous (a year or a count without a unit?); write {}-01 for the year",
+            text, text
+        ));
+    }
+    if !text.starts_with(|c: char| c.is_ascii_digit()) || !text.contains(['-', '/', '.']) {
+        return parse_age(text).map(|age| age.before(now));
+    }
+    if text.contains(['/', '.']) {
+        return Err(format!(
+            "'{}' is ambiguous (day or month first?); write it as YYYY-MM-DD or YYYY-MM",
+            text
+        ));
+    }
+
+    let invalid = || format!("'{}' is not a valid date; expected {}", text, FORMS);
+    let year_first = text.split('-').next().is_some_and(|year| year.len() == 4);
+    if !year_first {
+        return Err(format!(
+            "'{}' is ambiguous; write dates year first, as YYYY-MM-DD or YYYY-MM",
+            text
+        ));
+    }
+
+    match text.len() {
+        7 => NaiveDate::parse_from_str(&format!("{}-01", text), "%Y-%m-%d")
+            .map(|date| date.and_hms_opt(0, 0, 0).unwrap_or_default())
+            .map_err(|_| invalid()),
+        10 => NaiveDate::parse_from_str(text, "%Y-%m-%d")
+            .map(|date| date.and_hms_opt(0, 0, 0).unwrap_or_default())
+            .map_err(|_| invalid()),
+        _ => [
+            "%Y-%m-%dT%H:%M:%S",
+            "%Y-%m-%dT%H:%M",
+            "%Y-%m-%d %H:%M:%S",
+            "%Y-%m-%d %H:%M",
+        ]
+        .iter()
+        .find_map(|format| NaiveDateTime::parse_from_str(text, format).ok())
+        .ok_or_else(invalid),
+    }
+}
+
+/// [`parse_point`] relative to the current local time, for use as a clap value parser.
+pub fn parse_when(text: &str) -> Result<NaiveDateTime, String> {
+    parse_point(text, Local::now().naive_local())
+}
+
+/// Parses a frontmatter `created` time: a local date and time
+/// (`2024-05-17T09:30:00`, with a `T` or a space, seconds optional), a bare
+/// date (its midnight), or a time with a UTC offset (`2024-05-17T09:30:00+02:00`,
+/// `...Z`), which is converted to local time so it compares with the local
+/// timestamps the tool writes.
+pub fn parse_created(text: &str) -> Option<NaiveDateTime> {
+    parse_created_in(text, &Local)
+}
+
+/// [`parse_created`] with offsets converted to `zone` instead of local time.
+fn parse_created_in<Tz: TimeZone>(text: &str, zone: &Tz) -> Option<NaiveDateTime> {
+    let text = text.trim();
+    if let Ok(time) = DateTime::parse_from_rfc3339(text) {
+        return Some(time.with_timezone(zone).naive_local());
+    }
+    [
+        "%Y-%m-%dT%H:%M:%S%.f",
+        "%Y-%m-%d %H:%M:%S%.f",
+        "%Y-%m-%dT%H:%M",
+        "%Y-%m-%d %H:%M",
+    ]
+    .iter()
+    .find_map(|format| NaiveDateTime::parse_from_str(text, format).ok())
+    .or_else(|| {
+        NaiveDate::parse_from_str(text, "%Y-%m-%d")
+            .ok()?
+            .and_hms_opt(0, 0, 0)
+    })
+}
+
+/// Parses a calendar day written `YYYY-MM-DD`, for use as a clap value parser.
+pub fn parse_day(text: &str) -> Result<NaiveDate, String> {
+    NaiveDate::parse_from_str(text.trim(), "%Y-%m-%d")
+        .map_err(|_| format!("'{}' is not a date; expected YYYY-MM-DD", text))
+}
+
+/// Day names for [`stats_by_day_of_week`] counts, Monday first.
+pub const WEEKDAYS: [&str; 7] = ["Mon", "Tue", "Wed", "Thu", "Fri", "Sat", "Sun"];
+
+/// The rows of a bar chart of weekday `counts` fitted to `width` columns: each
+/// day name with the rest of its line, a right-aligned label and a bar scaled
+/// so the largest count fills the width.
+/// - `normalize`: Label the bars with percentages of the total instead of counts.
+pub fn weekday_chart(
+    counts: &[usize; 7],
+    normalize: bool,
+    width: usize,
+) -> Vec<(&'static str, String)> {
+    let total: usize = counts.iter().sum();
+    let labels: Vec<String> = counts
+        .iter()
+        .map(|count| {
+            if normalize {
+                format!("{:.1}%", *count as f64 * 100.0 / total.max(1) as f64)
+            } else {
+                count.to_string()
+            }
+        })
+        .collect();
+    let label_width = labels.iter().map(String::len).max().unwrap_or(0);
+    let max = counts.iter().copied().max().unwrap_or(0).max(1);
+    let bar_width = width.saturating_sub(label_width + 7).max(1);
+    WEEKDAYS
+        .iter()
+        .zip(counts)
+        .zip(&labels)
+        .map(|((day, count), label)| {
+            let bar = "█".repeat((count * bar_width).div_ceil(max));
+            (
+                *day,
+                format!("{:>width$}  {}", label, bar, width = label_width),
+            )
+        })
+        .collect()
+}
+
+/// Counts the snippets under `snippet_dir` created on each day of the week,
+/// Monday first, going by the timestamp in their generated filename. Files
+/// without one are not counted.
+/// - `extensions`: File extensions that count as snippets.
+pub fn stats_by_day_of_week(
+    snippet_dir: &Path,
+    extensions: &[String],
+) -> Result<[usize; 7], SnippetVaultError> {
+    let mut counts = [0; 7];
+    for path in vault::vault_files(snippet_dir, extensions)? {
+        if let Some(created) = snippet::parse_timestamp_from_filename(&path) {
+            counts[created.weekday().num_days_from_monday() as usize] += 1;
+        }
+    }
+    Ok(counts)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::Timelike;
+
+    fn at(year: i32, month: u32, day: u32, hour: u32, minute: u32) -> NaiveDateTime {
+        NaiveDate::from_ymd_opt(year, month, day)
+            .unwrap()
+            .and_hms_opt(hour, minute, 0)
+            .unwrap()
+    }
+
+    #[test]
+    fn age_before_clamps_to_month_end() {
+        assert_eq!(
+            Age::Months(1).before(at(2023, 3, 31, 12, 0)),
+            at(2023, 2, 28, 12, 0)
+        );
+        assert_eq!(
+            Age::Months(1).before(at(2024, 3, 31, 12, 0)),
+            at(2024, 2, 29, 12, 0)
+        );
+        assert_eq!(
+            Age::Years(1).before(at(2024, 2, 29, 8, 15)),
+            at(2023, 2, 28, 8, 15)
+        );
+        assert_eq!(
+            Age::Weeks(2).before(at(2024, 1, 10, 0, 0)),
+            at(2023, 12, 27, 0, 0)
+        );
+        assert_eq!(
+            Age::Days(10).before(at(2024, 3, 5, 9, 0)),
+            at(2024, 2, 24, 9, 0)
+        );
+        assert_eq!(
+            Age::Years(u32::MAX).before(at(2024, 1, 1, 0, 0)),
+            NaiveDateTime::MIN
+        );
+    }
+
+    #[test]
+    fn parse_age_units() {
+        assert_eq!(parse_age("10d"), Ok(Age::Days(10)));
+        assert_eq!(parse_age(" 3w "), Ok(Age::Weeks(3)));
+        assert_eq!(parse_age("18m"), Ok(Age::Months(18)));
+        assert_eq!(parse_age("1y"), Ok(Age::Years(1)));
+
+        let error = |text: &str| parse_age(text).unwrap_err();
+        assert!(error("6M").contains("ambiguous"));
+        assert!(error("6M").contains("6m for months"));
+        assert!(error("30").contains("no unit"));
+        assert!(error("2h").contains("too fine-grained"));
+        assert!(error("5q").contains("unknown unit 'q'"));
+        assert!(error("d").contains("not an age"));
+        assert!(error("99999999999d").contains("too large"));
+    }
+
+    #[test]
+    fn parse_point_forms() {
+        let now = at(2024, 3, 31, 18, 30);
+        assert_eq!(parse_point("2023-06-01", now), Ok(at(2023, 6, 1, 0, 0)));
+        assert_eq!(parse_point("2023-06", now), Ok(at(2023, 6, 1, 0, 0)));
+        assert_eq!(
+            parse_point("2023-06-01T09:30", now),
+            Ok(at(2023, 6, 1, 9, 30))
+        );
+        assert_eq!(
+            parse_point("2023-06-01 09:30", now),
+            Ok(at(2023, 6, 1, 9, 30))
+        );
+        assert_eq!(parse_point("1m", now), Ok(at(2024, 2, 29, 18, 30)));
+        assert_eq!(parse_point("3w", now), Ok(at(2024, 3, 10, 18, 30)));
+    }
+
+    #[test]
+    fn parse_point_rejects_ambiguous_input() {
+        let now = at(2024, 3, 31, 18, 30);
+        let error = |text: &str| parse_point(text, now).unwrap_err();
+        // A four-digit number could be a year or a count without a unit.
+        assert!(error("2023").contains("write 2023-01 for the year"));
+        // Day-first or month-first?
+        assert!(error("01/06/2023").contains("YYYY-MM-DD"));
+        assert!(error("01.06.2023").contains("ambiguous"));
+        assert!(error("06-01-2023").contains("year first"));
+        // Minutes or months?
+        assert!(error("6M").contains("ambiguous"));
+        assert!(error("2023-13").contains("not a valid date"));
+        assert!(error("2023-02-30").contains("not a valid date"));
+        assert!(error("").contains("expected"));
+    }
+
+    #[test]
+    fn created_times_with_an_offset_are_converted_to_the_zone() {
+        let utc = chrono::FixedOffset::east_opt(0).unwrap();
+        let new_york = chrono::FixedOffset::west_opt(5 * 3600).unwrap();
+        let tokyo = chrono::FixedOffset::east_opt(9 * 3600).unwrap();
+        let parse = |text: &str, zone: &chrono::FixedOffset| parse_created_in(text, zone);
+
+        assert_eq!(
+            parse("2024-05-17T09:30:00+02:00", &utc),
+            Some(at(2024, 5, 17, 7, 30))
+        );
+        assert_eq!(
+            parse("2024-05-17T09:30:00Z", &new_york),
+            Some(at(2024, 5, 17, 4, 30))
+        );
+        // Crossing midnight moves the day, in both directions.
+        assert_eq!(
+            parse("2024-05-17T01:00:00Z", &new_york),
+            Some(at(2024, 5, 16, 20, 0))
+        );
+        assert_eq!(
+            parse("2024-05-17T20:00:00Z", &tokyo),
+            Some(at(2024, 5, 18, 5, 0))
+        );
+        assert_eq!(
+            parse("2024-12-31 23:30:00-01:00", &utc),
+            Some(at(2025, 1, 1, 0, 30))
+        );
+    }
+
+    #[test]
+    fn local_created_times_are_taken_as_written() {
+        let tokyo = chrono::FixedOffset::east_opt(9 * 3600).unwrap();
+        let parse = |text: &str| parse_created_in(text, &tokyo);
+        assert_eq!(parse("2024-05-17T09:30:00"), Some(at(2024, 5, 17, 9, 30)));
+        assert_eq!(parse("2024-05-17 09:30"), Some(at(2024, 5, 17, 9, 30)));
+        assert_eq!(
+            parse("2024-05-17T09:30:00.250"),
+            at(2024, 5, 17, 9, 30).with_nanosecond(250_000_000)
+        );
+        assert_eq!(parse("2024-05-17"), Some(at(2024, 5, 17, 0, 0)));
+        assert_eq!(parse("17/05/2024"), None);
+        assert_eq!(parse("2024-02-30"), None);
+    }
+
+    fn chart(width: usize, normalize: bool) -> String {
+        weekday_chart(&[4, 9, 0, 2, 7, 1, 3], normalize, width)
+            .into_iter()
+            .map(|(day, bar)| format!("{}  {}", day, bar).trim_end().to_string() + "\n")
+            .collect()
+    }
+
+    #[test]
+    fn weekday_chart_at_60_columns() {
+        let expected = "\
+Mon  4  ████████████████████████
+Tue  9  ████████████████████████████████████████████████████
+Wed  0
+Thu  2  ████████████
+Fri  7  █████████████████████████████████████████
+Sat  1  ██████
+Sun  3  ██████████████████
+";
+        assert_eq!(chart(60, false), expected);
+    }
+
+    #[test]
+    fn weekday_chart_at_80_columns() {
+        let expected = "\
+Mon  4  ████████████████████████████████
+Tue  9  ████████████████████████████████████████████████████████████████████████
+Wed  0
+Thu  2  ████████████████
+Fri  7  ████████████████████████████████████████████████████████
+Sat  1  ████████
+Sun  3  ████████████████████████
+";
+        assert_eq!(chart(80, false), expected);
+    }
+
+    #[test]
+    fn weekday_chart_at_120_columns() {
+        let expected = "\
+Mon  4  ██████████████████████████████████████████████████
+Tue  9  ████████████████████████████████████████████████████████████████████████████████████████████████████████████████
+Wed  0
+Thu  2  █████████████████████████
+Fri  7  ████████████████████████████████████████████████████████████████████████████████████████
+Sat  1  █████████████
+Sun  3  ██████████████████████████████████████
+";
+        assert_eq!(chart(120, false), expected);
+    }
+
+    #[test]
+    fn weekday_chart_with_percentages() {
+        let expected = "\
+Mon  15.4%  ██████████████████████
+Tue  34.6%  ████████████████████████████████████████████████
+Wed   0.0%
+Thu   7.7%  ███████████
+Fri  26.9%  ██████████████████████████████████████
+Sat   3.8%  ██████
+Sun  11.5%  ████████████████
+";
+        assert_eq!(chart(60, true), expected);
+    }
+
+    #[test]
+    fn relative_ages_switch_units_at_the_boundaries() {
+        let now = at(2024, 3, 31, 18, 30);
+        let ago = |minutes: i64| relative_age(now - chrono::Duration::minutes(minutes), now);
+        let days = |days: i64| ago(days * 24 * 60);
+        assert_eq!(relative_age(now, now), "just now");
+        assert_eq!(ago(1), "1 minute ago");
+        assert_eq!(ago(59), "59 minutes ago");
+        assert_eq!(ago(60), "1 hour ago");
+        assert_eq!(ago(23 * 60 + 59), "23 hours ago");
+        assert_eq!(days(1), "yesterday");
+        assert_eq!(days(2), "2 days ago");
+        assert_eq!(days(13), "13 days ago");
+        assert_eq!(days(14), "2 weeks ago");
+        assert_eq!(days(59), "8 weeks ago");
+        assert_eq!(days(60), "2 months ago");
+        assert_eq!(days(729), "24 months ago");
+        assert_eq!(days(730), "2 years ago");
+    }
+
+    #[test]
+    fn future_times_are_just_now() {
+        let now = at(2024, 3, 31, 18, 30);
+        assert_eq!(relative_age(at(2024, 3, 31, 18, 45), now), "just now");
+        assert_eq!(relative_age(at(2025, 1, 1, 0, 0), now), "just now");
+    }
+}
@@ -0,0 +1,304 @@
+use regex::Regex;
+use std::path::Path;
+
+use crate::language;
+
+/// Confidence below which a guess is discarded in favour of `text`.
+pub const DEFAULT_THRESHOLD: f64 = 0.5;
+
+/// Keyword patterns per language with their weights. A snippet scores the sum of
+/// the weights of the patterns it contains (each counted once).
+const KEYWORDS: &[(&str, &[(&str, f64)])] = &[
+    (
+        "python",
+        &[
+            (r"(?m)^\s*def \w+\(.*\)\s*(->.*)?:\s*$", 3.0),
+            (r"(?m)^\s*(from \w[\w.]* )?import \w", 2.0),
+            (r"(?m)^\s*(if|elif|for|while|with|class) .*:\s*$", 1.5),
+            (r"\bself\.", 1.5),
+            (r"\bprint\(", 1.0),
+            (r"\bNone\b|\bTrue\b|\bFalse\b", 1.0),
+        ],
+    ),
+    (
+        "bash",
+        &[
+            (r"(?m)^\s*(if|while) \[\[? ", 3.0),
+            (r"(?m)^\s*(fi|done|esac)\s*$", 3.0),
+            (r"\$\{?\w+\}?", 1.0),
+            (r"(?m)^\s*(echo|export|cd|sudo|apt|brew) ", 2.0),
+            (r"\|\s*(grep|awk|sed|xargs)\b", 2.0),
+        ],
+    ),
+    (
+        "rust",
+        &[
+            (r"\bfn \w+(<.*>)?\(", 3.0),
+            (r"\blet (mut )?\w+", 1.5),
+            (r"\b(impl|pub|struct|enum|trait|mod|use) ", 1.5),
+            (r"::\w+", 1.0),
+            (r"\w+!\(", 1.5),
+            (r"&(mut |self|str)", 1.5),
+        ],
+    ),
+    (
+        "javascript",
+        &[
+            (r"\b(const|let|var) \w+ = ", 1.5),
+            (r"\bfunction\s*\w*\(", 2.5),
+            (r"=>", 1.5),
+            (r"\bconsole\.log\(", 3.0),
+            (
+                r"\b(require|import)\(|\bexport (default|const|function)\b",
+                2.0,
+            ),
+            (r";\s*$", 0.5),
+        ],
+    ),
+    (
+        "sql",
+        &[
+            (r"(?i)\bselect\b[\s\S]+\bfrom\b", 3.0),
+            (r"(?i)\b(insert into|update \w+ set|delete from)\b", 3.0),
+            (r"(?i)\b(create|alter|drop) (table|index|view)\b", 3.0),
+            (r"(?i)\b(where|join|group by|order by)\b", 1.5),
+        ],
+    ),
+    (
+        "yaml",
+        &[
+            (r"(?m)^[\w-]+:\s*$", 2.0),
+            (r"(?m)^\s+[\w-]+: \S", 1.5),
+            (r"(?m)^\s*- \w", 1.5),
+            (r"(?m)^---\s*$", 1.0),
+        ],
+    ),
+    (
+        "dockerfile",
+        &[
+            (r"(?m)^FROM \S+", 4.0),
+            (
+                r"(?m)^(RUN|COPY|ADD|WORKDIR|ENV|EXPOSE|CMD|ENTRYPOINT) ",
+                2.0,
+            ),
+        ],
+    ),
+];
+
+/// A guessed language and how sure the detector is (0.0 to 1.0).
+#[derive(Debug, Clone, PartialEq)]
+pub struct Detection {
+    pub language: String,
+    pub confidence: f64,
+}
+
+impl Detection {
+    fn certain(language: &str) -> Self {
+        Detection {
+            language: language.to_string(),
+            confidence: 1.0,
+        }
+    }
+}
+
+/// Guesses the language of `code`. A shebang or a known file extension decides
+/// outright; valid JSON is recognised by parsing; otherwise keyword patterns are
+/// scored and the confidence is the winner's share of all points.
+/// - `file`: The file the code came from, if any.
+pub fn detect_language(code: &str, file: Option<&Path>) -> Option<Detection> {
+    if let Some(language) = file.and_then(language_from_file) {
+        return Some(Detection::certain(&language));
+    }
+    if let Some(language) = language_from_shebang(code) {
+        return Some(Detection::certain(language));
+    }
+
+    let trimmed = code.trim_start();
+    if (trimmed.starts_with('{') || trimmed.starts_with('['))
+        && serde_json::from_str::<serde_json::Value>(code).is_ok()
+    {
+        return Some(Detection {
+            language: "json".to_string(),
+            confidence: 0.95,
+        });
+    }
+
+    let scores: Vec<(&str, f64)> = KEYWORDS
+        .iter()
+        .map(|(language, patterns)| {
+            let score = patterns
+                .iter()
+                .filter(|(pattern, _)| {
+                    Regex::new(pattern)
+                        .expect("keyword patterns are valid")
+                        .is_match(code)
+                })
+                .map(|(_, weight)| weight)
+                .sum();
+            (*language, score)
+        })
+        .collect();
+
+    let total: f64 = scores.iter().map(|(_, score)| score).sum();
+    let (language, best) = scores.into_iter().max_by(|a, b| a.1.total_cmp(&b.1))?;
+    if best <= 0.0 {
+        return None;
+    }
+    Some(Detection {
+        language: language.to_string(),
+        confidence: best / total,
+    })
+}
+
+/// The language of a fenced code block: its info string, or for a bare fence
+/// the snippet's own language, or failing that a guess from `code` confident
+/// enough to pass [`DEFAULT_THRESHOLD`]. Empty when nothing is known.
+pub fn block_language(info: &str, code: &str, snippet_language: &str) -> String {
+    if !info.is_empty() {
+        return info.to_string();
+    }
+    if !snippet_language.is_empty() {
+        return snippet_language.to_string();
+    }
+    detect_language(code, None)
+        .filter(|detection| detection.confidence >= DEFAULT_THRESHOLD)
+        .map(|detection| detection.language)
+        .unwrap_or_default()
+}
+
+fn language_from_file(file: &Path) -> Option<String> {
+    let name = file.file_name()?.to_str()?;
+    if name == "Dockerfile" || name.starts_with("Dockerfile.") {
+        return Some("dockerfile".to_string());
+    }
+    let extension = file.extension()?.to_str()?;
+    language::language_for_extension(extension)
+        .filter(|language| *language != "text" && *language != "markdown")
+        .map(str::to_string)
+}
+
+fn language_from_shebang(code: &str) -> Option<&'static str> {
+    let shebang = code.lines().next()?.strip_prefix("#!")?;
+    let interpreter = shebang
+        .split_whitespace()
+        .find(|word| !word.ends_with("/env") && !word.starts_with('-'))?;
+    let interpreter = interpreter.rsplit('/').next()?;
+
+    match interpreter.trim_end_matches(|c: char| c.is_ascii_digit() || c == '.') {
+        "python" => Some("python"),
+        "bash" | "sh" | "dash" => Some("bash"),
+        "zsh" => Some("zsh"),
+        "node" | "deno" => Some("javascript"),
+        "ruby" => Some("ruby"),
+        "perl" => Some("perl"),
+        "lua" => Some("lua"),
+        _ => None,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Samples with the language they must be detected as, without a file.
+    const SAMPLES: &[(&str, &str)] = &[
+        (
+            "python",
+            "import os\n\ndef main(path):\n    if os.path.exists(path):\n        print(path)\n",
+        ),
+        (
+            "bash",
+            "if [ -f \"$HOME/.bashrc\" ]; then\n    echo \"found\"\nfi\n",
+        ),
+        (
+            "rust",
+            "fn main() {\n    let mut total = 0;\n    println!(\"{}\", total);\n}\n",
+        ),
+        (
+            "javascript",
+            "const total = items.map((item) => item.price);\nconsole.log(total);\n",
+        ),
+        (
+            "sql",
+            "SELECT name, count(*)\nFROM users\nWHERE active = 1\nGROUP BY name;\n",
+        ),
+        (
+            "yaml",
+            "services:\n  web:\n    image: nginx\n    ports:\n      - 8080:80\n",
+        ),
+        ("json", "{\"name\": \"vault\", \"tags\": [\"a\", \"b\"]}\n"),
+        (
+            "dockerfile",
+            "FROM rust:1.80\nWORKDIR /app\nCOPY . .\nRUN cargo build --release\n",
+        ),
+        ("python", "#!/usr/bin/env python3\nx = 1\n"),
+        ("bash", "#!/bin/sh\nls\n"),
+        ("javascript", "#!/usr/bin/env -S node --harmony\nx\n"),
+    ];
+
+    #[test]
+    fn detects_samples_above_threshold() {
+        for (expected, code) in SAMPLES {
+            let detection = detect_language(code, None)
+                .unwrap_or_else(|| panic!("no guess for the {} sample", expected));
+            assert_eq!(detection.language, *expected, "sample:\n{}", code);
+            assert!(
+                detection.confidence >= DEFAULT_THRESHOLD,
+                "{} sample only {:.2} confident",
+                expected,
+                detection.confidence
+            );
+        }
+    }
+
+    #[test]
+    fn extension_and_file_name_decide_outright() {
+        let cases = [
+            ("query.py", "python"),
+            ("deploy.sh", "bash"),
+            ("main.rs", "rust"),
+            ("Dockerfile", "dockerfile"),
+            ("Dockerfile.dev", "dockerfile"),
+        ];
+        for (file, expected) in cases {
+            // The content looks like SQL; the file name still wins.
+            let detection = detect_language("SELECT * FROM users;", Some(Path::new(file)));
+            assert_eq!(detection, Some(Detection::certain(expected)), "{}", file);
+        }
+        // Text and markdown extensions say nothing about the code.
+        let detection = detect_language("SELECT * FROM users;", Some(Path::new("notes.txt")));
+        assert_eq!(detection.map(|d| d.language).as_deref(), Some("sql"));
+    }
+
+    #[test]
+    fn shebang_beats_keywords() {
+        let code = "#!/usr/bin/env bash\nimport_data() { echo done; }\nprint(1)\n";
+        assert_eq!(
+            detect_language(code, None),
+            Some(Detection::certain("bash"))
+        );
+    }
+
+    #[test]
+    fn prose_and_ambiguous_code_fall_below_threshold() {
+        // No pattern at all: no guess, so the caller uses text.
+        assert_eq!(detect_language("remember to water the plants", None), None);
+        // A lone assignment scores for several languages at once.
+        let detection = detect_language("let total = $count", None).unwrap();
+        assert!(
+            detection.confidence < DEFAULT_THRESHOLD,
+            "{} guessed with {:.2}",
+            detection.language,
+            detection.confidence
+        );
+    }
+
+    #[test]
+    fn bare_fences_prefer_the_snippet_language_over_a_guess() {
+        let python = SAMPLES[0].1;
+        assert_eq!(block_language("rust", python, "bash"), "rust");
+        assert_eq!(block_language("", python, "bash"), "bash");
+        assert_eq!(block_language("", python, ""), "python");
+        assert_eq!(block_language("", "let total = $count", ""), "");
+    }
+}
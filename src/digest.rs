@@ -0,0 +1,124 @@
+use chrono::{DateTime, Duration, Local, NaiveDateTime};
+use rusqlite::Connection;
+use std::collections::{HashMap, HashSet};
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use crate::error::SnippetVaultError;
+use crate::index::{self, Access};
+use crate::language;
+use crate::snippet::SnippetMeta;
+use crate::vault;
+
+/// Number of newest snippets listed in a digest.
+const NEWEST_COUNT: usize = 3;
+
+/// Summary of vault activity over the last `period_days`.
+#[derive(Debug, Clone)]
+pub struct WeeklyDigest {
+    pub since: NaiveDateTime,
+    pub period_days: u32,
+    /// Snippets whose creation timestamp falls in the period.
+    pub created: usize,
+    /// Older snippets modified on disk or opened during the period.
+    pub edited: usize,
+    /// Languages of the created and edited snippets, most used first.
+    pub languages: Vec<(String, usize)>,
+    /// The newest snippets created in the period, newest first.
+    pub newest: Vec<SnippetMeta>,
+}
+
+/// Builds the digest of the last `period_days` from the snippets' timestamps,
+/// their modification times, and the access log.
+/// - `extensions`: File extensions that count as snippets.
+/// - `aliases`: Language aliases; snippets are counted under the canonical name.
+/// - `use_cache`: Serve unchanged snippets from the metadata cache.
+pub fn weekly_digest(
+    snippet_dir: &Path,
+    db: &Connection,
+    period_days: u32,
+    extensions: &[String],
+    aliases: &HashMap<String, String>,
+    use_cache: bool,
+) -> Result<WeeklyDigest, SnippetVaultError> {
+    let since = Local::now().naive_local() - Duration::days(i64::from(period_days));
+    let snippets = vault::load_snippets(snippet_dir, extensions, use_cache)?;
+    let opened: HashSet<PathBuf> = index::accessed_since(db, Access::Opened, since)?
+        .into_iter()
+        .collect();
+
+    let mut created: Vec<&SnippetMeta> = Vec::new();
+    let mut edited: Vec<&SnippetMeta> = Vec::new();
+    for snippet in &snippets {
+        if snippet.created.is_some_and(|created| created >= since) {
+            created.push(snippet);
+        } else if opened.contains(&snippet.path) || modified_since(&snippet.path, since) {
+            edited.push(snippet);
+        }
+    }
+
+    let mut counts: HashMap<String, usize> = HashMap::new();
+    for snippet in created.iter().chain(&edited) {
+        *counts
+            .entry(language::canonical_language(&snippet.language, aliases))
+            .or_default() += 1;
+    }
+    let mut languages: Vec<(String, usize)> = counts
+        .into_iter()
+        .filter(|(language, _)| !language.is_empty())
+        .collect();
+    languages.sort_by(|a, b| b.1.cmp(&a.1).then(a.0.cmp(&b.0)));
+
+    created.sort_by_key(|snippet| std::cmp::Reverse(snippet.created));
+    Ok(WeeklyDigest {
+        since,
+        period_days,
+        created: created.len(),
+        edited: edited.len(),
+        languages,
+        newest: created.into_iter().take(NEWEST_COUNT).cloned().collect(),
+    })
+}
+
+/// Renders the digest as GitHub Flavored Markdown.
+pub fn render_markdown(digest: &WeeklyDigest) -> String {
+    let mut out = format!(
+        "## Snippet digest: last {} days (since {})\n\n",
+        digest.period_days,
+        digest.since.format("%Y-%m-%d")
+    );
+    out.push_str("| Created | Edited |\n|---:|---:|\n");
+    out.push_str(&format!("| {} | {} |\n\n", digest.created, digest.edited));
+
+    out.push_str("### Languages\n\n");
+    if digest.languages.is_empty() {
+        out.push_str("_No activity._\n");
+    }
+    for (language, count) in &digest.languages {
+        out.push_str(&format!("- `{}`: {}\n", language, count));
+    }
+
+    out.push_str("\n### Newest snippets\n\n");
+    if digest.newest.is_empty() {
+        out.push_str("_None._\n");
+    }
+    for snippet in &digest.newest {
+        out.push_str(&format!(
+            "- **{}** ({}, {})\n",
+            snippet.title,
+            snippet.language,
+            snippet
+                .created
+                .map(|created| created.format("%Y-%m-%d").to_string())
+                .unwrap_or_default()
+        ));
+    }
+    out
+}
+
+fn modified_since(path: &Path, since: NaiveDateTime) -> bool {
+    fs::metadata(path)
+        .and_then(|metadata| metadata.modified())
+        .map(|modified| DateTime::<Local>::from(modified).naive_local() >= since)
+        .unwrap_or(false)
+}
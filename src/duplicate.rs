@@ -0,0 +1,117 @@
+use chrono::{NaiveDateTime, Timelike};
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use crate::error::SnippetVaultError;
+use crate::snippet::{
+    is_markdown, snippet_filename_with_extension, split_frontmatter, Frontmatter,
+};
+use crate::usage::USES_FIELD;
+use crate::vault::{self, Executor, Mutation};
+
+/// Frontmatter field naming the snippet a duplicate was made from.
+pub const DERIVED_FROM_FIELD: &str = "derived_from";
+
+/// Copies the snippet at `source` into a new file next to it, named from
+/// `timestamp` (with a `-2`, `-3`, ... counter when taken), and returns the new
+/// path. A markdown copy gets `title`, `tags`, `created` set to `created`, a
+/// `derived_from` field with the source's file name, and no `uses` count; its
+/// `# Title:` and `### Tags:` lines follow. Plain code files are copied as they
+/// are, with `tags` in the new filename. The source is never changed.
+/// - `executor`: Applies (or, with `--dry-run`, only prints) the new file.
+pub fn duplicate_snippet(
+    source: &Path,
+    title: &str,
+    tags: &[String],
+    created: NaiveDateTime,
+    timestamp: &str,
+    executor: &Executor,
+) -> Result<PathBuf, SnippetVaultError> {
+    let content = fs::read_to_string(source)?;
+    let (language, contents) = if is_markdown(source) {
+        let mut frontmatter = Frontmatter::load(source, &content)?;
+        frontmatter.title = title.to_string();
+        frontmatter.tags = tags.to_vec();
+        frontmatter.created = Some(created.with_nanosecond(0).unwrap_or(created));
+        frontmatter.extra.remove(USES_FIELD);
+        if let Some(name) = source.file_name() {
+            frontmatter.extra.insert(
+                DERIVED_FROM_FIELD.to_string(),
+                toml::Value::String(name.to_string_lossy().to_string()),
+            );
+        }
+        let body = retitle_body(split_frontmatter(&content).1, title, tags);
+        let contents = format!("{}{}", frontmatter.render(), body);
+        (frontmatter.language, contents)
+    } else {
+        (Frontmatter::from_plain(source, &content).language, content)
+    };
+
+    let dir = source.parent().unwrap_or(Path::new("."));
+    let extension = source
+        .extension()
+        .and_then(|ext| ext.to_str())
+        .unwrap_or("md");
+    let path = vault::available_path(dir, timestamp, |timestamp| {
+        snippet_filename_with_extension(timestamp, &language, tags, extension)
+    });
+    executor.apply(Mutation::Write {
+        path: path.clone(),
+        contents,
+    })?;
+    Ok(path)
+}
+
+/// `body` with its first `# Title:` and `### Tags:` lines rewritten for the
+/// copy; bodies without them are returned unchanged.
+fn retitle_body(body: &str, title: &str, tags: &[String]) -> String {
+    let (mut retitled, mut retagged) = (false, false);
+    let mut lines: Vec<String> = Vec::new();
+    for line in body.split_inclusive('\n') {
+        let ending = &line[line.trim_end_matches(['\r', '\n']).len()..];
+        if !retitled && line.starts_with("# Title:") {
+            retitled = true;
+            lines.push(format!("# Title: {}{}", title, ending));
+        } else if !retagged && line.starts_with("### Tags:") {
+            retagged = true;
+            lines.push(format!("### Tags: {}{}", tags.join(", "), ending));
+        } else {
+            lines.push(line.to_string());
+        }
+    }
+    lines.concat()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn tags() -> Vec<String> {
+        vec!["git".to_string(), "undo".to_string()]
+    }
+
+    #[test]
+    fn retitle_rewrites_the_first_title_and_tags_lines() {
+        let body = "# Title: Old\n### Tags: old\n\n```bash\n# Title: in code\n```\n";
+        assert_eq!(
+            retitle_body(body, "New", &tags()),
+            "# Title: New\n### Tags: git, undo\n\n```bash\n# Title: in code\n```\n"
+        );
+    }
+
+    #[test]
+    fn retitle_keeps_line_endings() {
+        assert_eq!(
+            retitle_body("# Title: Old\r\n### Tags: old\r\nbody\r\n", "New", &tags()),
+            "# Title: New\r\n### Tags: git, undo\r\nbody\r\n"
+        );
+        assert_eq!(retitle_body("# Title: Old", "New", &[]), "# Title: New");
+    }
+
+    #[test]
+    fn retitle_leaves_bodies_without_those_lines_alone() {
+        let body = "```rust\nfn main() {}\n```\n";
+        assert_eq!(retitle_body(body, "New", &tags()), body);
+        assert_eq!(retitle_body("", "New", &tags()), "");
+    }
+}
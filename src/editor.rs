@@ -0,0 +1,443 @@
+use std::io;
+use std::path::{Path, PathBuf};
+use std::process::{Command as ProcessCommand, ExitStatus};
+
+use crate::config::Config;
+use crate::error::SnippetVaultError;
+use crate::format;
+use crate::logging;
+use crate::platform;
+use crate::prompt;
+use crate::snippet::SnippetMeta;
+use crate::style::Styled;
+use crate::vault::{self, Executor};
+
+/// How to do without an editor, for the error shown when there is no terminal.
+const EDITOR_HINT: &str = "print the path with --path or --pick instead";
+
+/// Locally built neovim binaries preferred over `nvim` from `PATH`.
+const NEOVIM_BUILDS: &[&str] = &[
+    "$HOME/dev/nvim/bin/nvim",
+    "$HOME/dev/neovim/build/bin/nvim",
+    "$HOME/dev/neovim/bin/nvim",
+    "/usr/local/bin/nvim",
+];
+
+/// Visual Studio Code launchers tried by [`open_in_vscode`], in order.
+const VSCODE_PROGRAMS: &[&str] = &["code", "code-insiders"];
+
+/// Lua run by neovim before [`select_lines`] loads the file: when neovim
+/// exits, the first and last line of the visual selection (the current one,
+/// or else the last) are written to `{out}`.
+const SELECTION_LUA: &str = "local out = [==[{out}]==] \
+    vim.api.nvim_create_autocmd('VimLeavePre', { callback = function() \
+    local first, last = vim.fn.line(\"'<\"), vim.fn.line(\"'>\") \
+    local mode = vim.fn.mode() \
+    if mode == 'v' or mode == 'V' or mode == '\\22' then first, last = vim.fn.line('v'), vim.fn.line('.') end \
+    if first > 0 and last > 0 then vim.fn.writefile({ math.min(first, last) .. ' ' .. math.max(first, last) }, out) end \
+    end })";
+
+/// Values of `--open-with` and the `open_with` config key.
+pub const OPENERS: &[&str] = &["editor", "vscode", "obsidian"];
+
+/// Where selected snippets are opened.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum Opener {
+    /// The configured editor; see [`editor_for`].
+    #[default]
+    Editor,
+    /// Visual Studio Code; see [`open_in_vscode`].
+    Vscode,
+    /// Obsidian, through its `obsidian://` URI handler.
+    Obsidian,
+}
+
+impl Opener {
+    /// Parses one of [`OPENERS`].
+    pub fn from_name(name: &str) -> Option<Self> {
+        match name {
+            "editor" => Some(Opener::Editor),
+            "vscode" => Some(Opener::Vscode),
+            "obsidian" => Some(Opener::Obsidian),
+            _ => None,
+        }
+    }
+}
+
+/// Argument templates that open `{file}` at `{line}`, by editor program name.
+const LINE_TEMPLATES: &[(&[&str], &str)] = &[
+    (
+        &[
+            "vi",
+            "vim",
+            "nvim",
+            "gvim",
+            "mvim",
+            "nano",
+            "emacs",
+            "emacsclient",
+            "kak",
+            "micro",
+        ],
+        "+{line} {file}",
+    ),
+    (
+        &["code", "code-insiders", "codium", "cursor", "windsurf"],
+        "--goto {file}:{line}",
+    ),
+    (&["hx", "helix", "subl", "zed"], "{file}:{line}"),
+];
+
+/// Splits an editor command such as `open -a "Visual Studio Code"` into the
+/// program and its arguments. Single and double quotes group words and a
+/// backslash escapes the next character, as in a shell.
+pub fn split_command(command: &str) -> Vec<String> {
+    let mut words = Vec::new();
+    let mut word = String::new();
+    let mut in_word = false;
+    let mut quote: Option<char> = None;
+    let mut chars = command.chars();
+
+    while let Some(c) = chars.next() {
+        match (quote, c) {
+            (Some(open), c) if c == open => quote = None,
+            (Some('"'), '\\') | (None, '\\') => {
+                word.extend(chars.next());
+                in_word = true;
+            }
+            (Some(_), c) => word.push(c),
+            (None, '\'' | '"') => {
+                quote = Some(c);
+                in_word = true;
+            }
+            (None, c) if c.is_whitespace() => {
+                if in_word {
+                    words.push(std::mem::take(&mut word));
+                    in_word = false;
+                }
+            }
+            (None, c) => {
+                word.push(c);
+                in_word = true;
+            }
+        }
+    }
+    if in_word {
+        words.push(word);
+    }
+    words
+}
+
+/// The editor used when no configuration names one: a locally built neovim
+/// if there is one, otherwise `nvim` from `PATH`.
+pub fn builtin_editor() -> String {
+    NEOVIM_BUILDS
+        .iter()
+        .map(|path| shellexpand::env(path).map(|path| path.into_owned()))
+        .find_map(|path| path.ok().filter(|path| Path::new(path).exists()))
+        .unwrap_or_else(|| "nvim".to_string())
+}
+
+/// The editor for files no `editors` entry matches: `--editor`, then
+/// `editors.default`, then `editor`, then the built-in default.
+pub fn default_editor(config: &Config) -> String {
+    config
+        .editor_override
+        .clone()
+        .or_else(|| config.editors.get("default").cloned())
+        .or_else(|| config.editor.clone())
+        .unwrap_or_else(builtin_editor)
+}
+
+/// The editor for `path`. `--editor` wins; otherwise the `editors` map is
+/// consulted for the file's full extension (`excalidraw.md`), its language,
+/// and its last extension, before falling back to [`default_editor`].
+pub fn editor_for(config: &Config, path: &Path) -> String {
+    if let Some(editor) = &config.editor_override {
+        return editor.clone();
+    }
+    if config.editors.is_empty() {
+        return default_editor(config);
+    }
+
+    let file_name = path
+        .file_name()
+        .map(|name| name.to_string_lossy().to_lowercase())
+        .unwrap_or_default();
+    let full_extension = file_name
+        .split_once('.')
+        .map(|(_, extension)| extension.to_string());
+    let language = SnippetMeta::read(path)
+        .ok()
+        .map(|meta| meta.language.to_lowercase())
+        .filter(|language| !language.is_empty());
+    let extension = path
+        .extension()
+        .map(|extension| extension.to_string_lossy().to_lowercase());
+
+    [full_extension, language, extension]
+        .into_iter()
+        .flatten()
+        .find_map(|key| config.editors.get(&key).cloned())
+        .unwrap_or_else(|| default_editor(config))
+}
+
+/// Builds the argument list that opens `path` at `line` with `editor`:
+/// the editor's words followed by `template` (or the built-in template for
+/// the editor's program, see [`LINE_TEMPLATES`]) with `{file}` and `{line}`
+/// filled in. Without a line, or for an editor with no known jump syntax,
+/// the path is simply appended.
+pub fn editor_args(
+    editor: &str,
+    template: Option<&str>,
+    path: &Path,
+    line: Option<usize>,
+) -> Vec<String> {
+    let mut args = split_command(editor);
+    let file = path.to_string_lossy().into_owned();
+
+    let program = args
+        .first()
+        .and_then(|program| Path::new(program).file_name())
+        .map(|name| name.to_string_lossy().into_owned())
+        .unwrap_or_default();
+    let template = template.or_else(|| {
+        LINE_TEMPLATES
+            .iter()
+            .find(|(programs, _)| programs.contains(&program.as_str()))
+            .map(|(_, template)| *template)
+    });
+
+    match (line, template) {
+        (Some(line), Some(template)) => {
+            args.extend(split_command(template).into_iter().map(|word| {
+                word.replace("{file}", &file)
+                    .replace("{line}", &line.to_string())
+            }))
+        }
+        _ => args.push(file),
+    }
+    args
+}
+
+/// Warns before an editor opens on a read-only vault: the editor may save,
+/// but nothing is committed, pushed, or archived afterwards.
+fn warn_read_only() {
+    if vault::is_read_only() {
+        println!(
+            "{} The vault is read-only; edits you save are not committed or pushed.",
+            "⚠".warning()
+        );
+    }
+}
+
+/// Opens `path` in its editor (see [`editor_for`]), with the cursor on `line`
+/// when the editor's jump syntax is known or `editor_line_template` is set.
+/// - `executor`: Formats the saved file (see [`format::format_after_edit`]).
+pub fn open_in_editor(
+    config: &Config,
+    path: &Path,
+    line: Option<usize>,
+    executor: &Executor,
+) -> io::Result<ExitStatus> {
+    prompt::require_interactive("open the editor", EDITOR_HINT).map_err(io::Error::other)?;
+    warn_read_only();
+    let editor = editor_for(config, path);
+    let args = editor_args(&editor, config.editor_line_template.as_deref(), path, line);
+    let Some((program, args)) = args.split_first() else {
+        return Err(io::Error::new(
+            io::ErrorKind::InvalidInput,
+            "the editor command is empty",
+        ));
+    };
+    let status = logging::run(ProcessCommand::new(program).args(args))?;
+    format::format_after_edit(config, &[path.to_path_buf()], executor);
+    Ok(status)
+}
+
+/// Opens `paths` in their editors (see [`editor_for`]). Consecutive files that
+/// share an editor are opened in one invocation, so vim gets them as buffers.
+/// - `executor`: Formats the saved files (see [`format::format_after_edit`]).
+pub fn open(config: &Config, paths: &[PathBuf], executor: &Executor) -> io::Result<ExitStatus> {
+    prompt::require_interactive("open the editor", EDITOR_HINT).map_err(io::Error::other)?;
+    warn_read_only();
+    let mut status = None;
+    let mut start = 0;
+    while start < paths.len() {
+        let editor = editor_for(config, &paths[start]);
+        let end = (start + 1..paths.len())
+            .find(|&index| editor_for(config, &paths[index]) != editor)
+            .unwrap_or(paths.len());
+
+        let words = split_command(&editor);
+        let Some((program, args)) = words.split_first() else {
+            return Err(io::Error::new(
+                io::ErrorKind::InvalidInput,
+                "the editor command is empty",
+            ));
+        };
+        status = Some(logging::run(
+            ProcessCommand::new(program)
+                .args(args)
+                .args(&paths[start..end]),
+        )?);
+        start = end;
+    }
+    format::format_after_edit(config, paths, executor);
+    Ok(status.unwrap_or_default())
+}
+
+/// Opens `path` in Visual Studio Code (`code`, or `code-insiders` when only
+/// that is installed) with the cursor at the start of the file. When neither
+/// is on `PATH`, the file is opened in its configured editor instead.
+/// - `executor`: Formats the file when it falls back to the editor.
+pub fn open_in_vscode(
+    config: &Config,
+    path: &Path,
+    executor: &Executor,
+) -> Result<(), SnippetVaultError> {
+    let Some(program) = VSCODE_PROGRAMS
+        .iter()
+        .find_map(|program| platform::find_in_path(program))
+    else {
+        tracing::info!("neither code nor code-insiders is installed; using the editor");
+        open(config, &[path.to_path_buf()], executor)?;
+        return Ok(());
+    };
+
+    let status = logging::run(
+        ProcessCommand::new(&program)
+            .arg("--goto")
+            .arg(format!("{}:1:1", path.display())),
+    )?;
+    if !status.success() {
+        return Err(SnippetVaultError::Command(format!(
+            "{}: {}",
+            program.display(),
+            status
+        )));
+    }
+    Ok(())
+}
+
+/// Opens `path` read-only in neovim in linewise visual mode and returns the
+/// first and last selected line (1-based, inclusive) once neovim exits, or
+/// `None` when nothing was selected. The range is handed back through a file
+/// in a new temporary directory, written by [`SELECTION_LUA`].
+pub fn select_lines(path: &Path) -> Result<Option<(usize, usize)>, SnippetVaultError> {
+    prompt::require_interactive("select lines in neovim", EDITOR_HINT)?;
+    let nvim = builtin_editor();
+    if platform::find_in_path(&nvim).is_none() && !Path::new(&nvim).exists() {
+        return Err(SnippetVaultError::Command(
+            "selecting lines needs neovim (nvim) on PATH".to_string(),
+        ));
+    }
+
+    // A private directory of its own, so no other user can plant or read
+    // the file under a name they could guess.
+    let dir = tempfile::Builder::new()
+        .prefix("snippets_vault_selection")
+        .tempdir()?;
+    let out = dir.path().join("selection.txt");
+    // `-i NONE` keeps marks of earlier sessions from posing as a selection.
+    let status = logging::run(
+        ProcessCommand::new(&nvim)
+            .args(["-R", "-i", "NONE", "--cmd"])
+            .arg(format!(
+                "lua {}",
+                SELECTION_LUA.replace("{out}", &out.display().to_string())
+            ))
+            .args(["-c", "call feedkeys('V', 'n')"])
+            .arg(path),
+    )?;
+    let range = std::fs::read_to_string(&out).ok();
+    if !status.success() {
+        return Err(SnippetVaultError::Command(format!("{}: {}", nvim, status)));
+    }
+
+    Ok(range.and_then(|range| {
+        let (first, last) = range.trim().split_once(' ')?;
+        Some((first.parse().ok()?, last.parse().ok()?))
+    }))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn args(editor: &str, template: Option<&str>, line: Option<usize>) -> Vec<String> {
+        editor_args(editor, template, Path::new("/vault/git undo.md"), line)
+    }
+
+    #[test]
+    fn builtin_line_templates() {
+        assert_eq!(
+            args("vim", None, Some(12)),
+            ["vim", "+12", "/vault/git undo.md"]
+        );
+        assert_eq!(
+            args("/usr/local/bin/nvim -u NONE", None, Some(3)),
+            [
+                "/usr/local/bin/nvim",
+                "-u",
+                "NONE",
+                "+3",
+                "/vault/git undo.md"
+            ]
+        );
+        assert_eq!(
+            args("code --wait", None, Some(7)),
+            ["code", "--wait", "--goto", "/vault/git undo.md:7"]
+        );
+        assert_eq!(args("hx", None, Some(4)), ["hx", "/vault/git undo.md:4"]);
+    }
+
+    #[test]
+    fn without_a_line_or_template_the_path_is_appended() {
+        assert_eq!(args("vim", None, None), ["vim", "/vault/git undo.md"]);
+        assert_eq!(
+            args("gedit --new-window", None, Some(9)),
+            ["gedit", "--new-window", "/vault/git undo.md"]
+        );
+    }
+
+    #[test]
+    fn custom_template_overrides_builtin() {
+        assert_eq!(
+            args("gedit", Some("{file} +{line}"), Some(9)),
+            ["gedit", "/vault/git undo.md", "+9"]
+        );
+        assert_eq!(
+            args("vim", Some("-c 'normal {line}G' {file}"), Some(2)),
+            ["vim", "-c", "normal 2G", "/vault/git undo.md"]
+        );
+    }
+
+    #[test]
+    fn split_command_quoting() {
+        assert_eq!(
+            split_command(r#"open -a "Visual Studio Code""#),
+            ["open", "-a", "Visual Studio Code"]
+        );
+        assert_eq!(
+            split_command(r"'/Applications/My Editor' --flag"),
+            ["/Applications/My Editor", "--flag"]
+        );
+        assert_eq!(
+            split_command(r"/opt/my\ editor/bin/ed  -x"),
+            ["/opt/my editor/bin/ed", "-x"]
+        );
+        assert_eq!(split_command(r#"ed "say \"hi\"""#), ["ed", r#"say "hi""#]);
+        assert_eq!(split_command(r"ed 'a\b'"), ["ed", r"a\b"]);
+        assert_eq!(split_command(r#"ed """#), ["ed", ""]);
+        assert!(split_command("   ").is_empty());
+    }
+
+    #[test]
+    fn quoted_editor_keeps_its_template() {
+        assert_eq!(
+            args(r#""/opt/my apps/nvim" --clean"#, None, Some(5)),
+            ["/opt/my apps/nvim", "--clean", "+5", "/vault/git undo.md"]
+        );
+    }
+}
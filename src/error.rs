@@ -0,0 +1,112 @@
+use std::fmt;
+use std::io;
+use std::path::PathBuf;
+
+/// Errors surfaced by vault operations.
+#[derive(Debug)]
+pub enum SnippetVaultError {
+    /// Underlying filesystem failure.
+    Io(io::Error),
+    /// A snippet file could not be parsed.
+    Parse { path: PathBuf, message: String },
+    /// An output path already exists and overwriting was not requested.
+    AlreadyExists(PathBuf),
+    /// The configuration file is invalid.
+    Config(String),
+    /// An external command could not be run or exited unsuccessfully.
+    Command(String),
+    /// The clipboard could not be read or written.
+    Clipboard(String),
+    /// The static HTTP server failed.
+    Server(String),
+    /// Creating a vault backup failed.
+    Backup(String),
+    /// The SQLite index could not be read or updated.
+    Index(String),
+    /// Snippet placeholders were left without a value (their names).
+    MissingParams(Vec<String>),
+    /// Another process held the vault lock for too long (its PID, if known).
+    Locked(Option<u32>),
+    /// A change was refused because the vault is read-only (`readonly = true`
+    /// or `--readonly`).
+    ReadOnly,
+    /// A picker, editor, or prompt was needed without a terminal to show it on.
+    NotInteractive {
+        action: &'static str,
+        hint: &'static str,
+    },
+}
+
+impl fmt::Display for SnippetVaultError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            SnippetVaultError::Io(err) => write!(f, "I/O error: {}", err),
+            SnippetVaultError::Parse { path, message } => {
+                write!(f, "Failed to parse {}: {}", path.display(), message)
+            }
+            SnippetVaultError::AlreadyExists(path) => {
+                write!(f, "{} already exists (use --overwrite)", path.display())
+            }
+            SnippetVaultError::Config(message) => write!(f, "Invalid configuration: {}", message),
+            SnippetVaultError::Command(message) => write!(f, "Command failed: {}", message),
+            SnippetVaultError::Clipboard(message) => write!(f, "Clipboard error: {}", message),
+            SnippetVaultError::Server(message) => write!(f, "Server error: {}", message),
+            SnippetVaultError::Backup(message) => write!(f, "Backup failed: {}", message),
+            SnippetVaultError::Index(message) => write!(f, "Index error: {}", message),
+            SnippetVaultError::MissingParams(names) => write!(
+                f,
+                "no value for placeholder(s) {} (pass --param name=value)",
+                names.join(", ")
+            ),
+            SnippetVaultError::Locked(Some(pid)) => write!(
+                f,
+                "another snippets_vault process holds the lock (pid {})",
+                pid
+            ),
+            SnippetVaultError::Locked(None) => {
+                write!(f, "another snippets_vault process holds the lock")
+            }
+            SnippetVaultError::ReadOnly => write!(
+                f,
+                "the vault is read-only (readonly = true or --readonly); nothing was changed"
+            ),
+            SnippetVaultError::NotInteractive { action, hint } => write!(
+                f,
+                "cannot {} without an interactive terminal; {}",
+                action, hint
+            ),
+        }
+    }
+}
+
+impl std::error::Error for SnippetVaultError {}
+
+impl From<io::Error> for SnippetVaultError {
+    fn from(err: io::Error) -> Self {
+        SnippetVaultError::Io(err)
+    }
+}
+
+impl From<zip::result::ZipError> for SnippetVaultError {
+    fn from(err: zip::result::ZipError) -> Self {
+        SnippetVaultError::Backup(err.to_string())
+    }
+}
+
+impl From<notify::Error> for SnippetVaultError {
+    fn from(err: notify::Error) -> Self {
+        SnippetVaultError::Server(err.to_string())
+    }
+}
+
+impl From<rusqlite::Error> for SnippetVaultError {
+    fn from(err: rusqlite::Error) -> Self {
+        SnippetVaultError::Index(err.to_string())
+    }
+}
+
+impl From<csv::Error> for SnippetVaultError {
+    fn from(err: csv::Error) -> Self {
+        SnippetVaultError::Io(err.into())
+    }
+}
@@ -0,0 +1,117 @@
+use chrono::{Days, NaiveDate};
+use std::path::Path;
+
+use crate::error::SnippetVaultError;
+use crate::fields;
+use crate::snippet::SnippetMeta;
+use crate::vault::{self, Executor};
+
+/// Frontmatter field holding the day a snippet expires, as `YYYY-MM-DD`.
+pub const EXPIRES_FIELD: &str = "expires_at";
+
+/// Frontmatter flag of archived snippets, set by `--snippet_bulk --archive`
+/// and by `auto_archive_expired`.
+pub const ARCHIVED_FIELD: &str = "archived";
+
+/// Writes `expires_at = "YYYY-MM-DD"` to the frontmatter of the markdown
+/// snippet at `path`. Returns whether the field changed.
+/// - `executor`: Applies (or, with `--dry-run`, only prints) the rewrite.
+pub fn set_expiry(
+    path: &Path,
+    date: NaiveDate,
+    executor: &Executor,
+) -> Result<bool, SnippetVaultError> {
+    let value = toml::Value::String(date.format("%Y-%m-%d").to_string());
+    fields::set_field(path, EXPIRES_FIELD, Some(value), executor)
+}
+
+/// The expiry day of `snippet`, if it has a valid one.
+pub fn expires_at(snippet: &SnippetMeta) -> Option<NaiveDate> {
+    let text = snippet.extra.get(EXPIRES_FIELD)?;
+    // A bare TOML date or datetime is kept as written, e.g. `2025-01-31T00:00:00`.
+    NaiveDate::parse_from_str(text.get(..10)?, "%Y-%m-%d").ok()
+}
+
+/// The day the snippet at `path` expired, if it has expired by `today`.
+/// Unreadable snippets count as not expired.
+pub fn expired_on(path: &Path, today: NaiveDate) -> Option<NaiveDate> {
+    let snippet = SnippetMeta::read(path).ok()?;
+    expires_at(&snippet).filter(|date| is_expired(*date, today))
+}
+
+/// Whether a snippet expiring on `date` has expired by `today`; it expires at
+/// the start of that day.
+pub fn is_expired(date: NaiveDate, today: NaiveDate) -> bool {
+    date <= today
+}
+
+/// Whether `snippet` is marked `archived = true`.
+pub fn is_archived(snippet: &SnippetMeta) -> bool {
+    snippet.extra.get(ARCHIVED_FIELD).map(String::as_str) == Some("true")
+}
+
+/// Snippets not archived whose expiry day is on or before `until`,
+/// soonest first, with that day.
+/// - `extensions`: File extensions that count as snippets.
+/// - `use_cache`: Serve unchanged snippets from the metadata cache.
+fn expiring_until(
+    snippet_dir: &Path,
+    until: NaiveDate,
+    extensions: &[String],
+    use_cache: bool,
+) -> Result<Vec<(NaiveDate, SnippetMeta)>, SnippetVaultError> {
+    let mut snippets: Vec<(NaiveDate, SnippetMeta)> =
+        vault::load_snippets(snippet_dir, extensions, use_cache)?
+            .into_iter()
+            .filter(|snippet| !is_archived(snippet))
+            .filter_map(|snippet| Some((expires_at(&snippet)?, snippet)))
+            .filter(|(date, _)| *date <= until)
+            .collect();
+    snippets.sort_by(|(a_date, a), (b_date, b)| a_date.cmp(b_date).then(a.path.cmp(&b.path)));
+    Ok(snippets)
+}
+
+/// Snippets that have expired by `today`, longest expired first.
+/// - `extensions`: File extensions that count as snippets.
+/// - `use_cache`: Serve unchanged snippets from the metadata cache.
+pub fn find_expired(
+    snippet_dir: &Path,
+    today: NaiveDate,
+    extensions: &[String],
+    use_cache: bool,
+) -> Result<Vec<(NaiveDate, SnippetMeta)>, SnippetVaultError> {
+    expiring_until(snippet_dir, today, extensions, use_cache)
+}
+
+/// Snippets that have not expired by `today` but will within `days` days,
+/// soonest first.
+/// - `extensions`: File extensions that count as snippets.
+/// - `use_cache`: Serve unchanged snippets from the metadata cache.
+pub fn find_expiring_soon(
+    snippet_dir: &Path,
+    today: NaiveDate,
+    days: u64,
+    extensions: &[String],
+    use_cache: bool,
+) -> Result<Vec<(NaiveDate, SnippetMeta)>, SnippetVaultError> {
+    let until = today
+        .checked_add_days(Days::new(days))
+        .unwrap_or(NaiveDate::MAX);
+    Ok(expiring_until(snippet_dir, until, extensions, use_cache)?
+        .into_iter()
+        .filter(|(date, _)| !is_expired(*date, today))
+        .collect())
+}
+
+/// Marks the markdown snippet at `path` `archived = true`. Returns whether
+/// the flag changed.
+/// - `executor`: Applies (or, with `--dry-run`, only prints) the rewrite.
+pub fn archive(path: &Path, executor: &Executor) -> Result<bool, SnippetVaultError> {
+    let _lock = executor.lock()?;
+    fields::set_field(
+        path,
+        ARCHIVED_FIELD,
+        Some(toml::Value::Boolean(true)),
+        executor,
+    )
+}
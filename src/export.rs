@@ -0,0 +1,763 @@
+use pulldown_cmark::{html, Options, Parser};
+use std::collections::{BTreeMap, HashMap};
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use crate::error::SnippetVaultError;
+use crate::language;
+use crate::snippet::{self, split_frontmatter, Frontmatter, SnippetMeta};
+use crate::style::Styled;
+use crate::vault::{self, snippet_files, Executor, Mutation};
+use crate::width;
+
+/// Copies every snippet into `output_dir` as an Obsidian-compatible note.
+/// Filenames (and sub-directories) are kept verbatim so existing vault references still resolve.
+/// - `overwrite`: Allow exporting into a non-empty `output_dir`.
+pub fn export_obsidian(
+    snippet_dir: &Path,
+    output_dir: &Path,
+    overwrite: bool,
+    executor: &Executor,
+) -> Result<usize, SnippetVaultError> {
+    prepare_output_dir(output_dir, overwrite, executor)?;
+
+    let files = snippet_files(snippet_dir)?;
+    for path in &files {
+        let content = fs::read_to_string(path)?;
+        let frontmatter = Frontmatter::load(path, &content)?;
+        let (_, body) = split_frontmatter(&content);
+
+        let target = output_dir.join(path.strip_prefix(snippet_dir).unwrap_or(path));
+        if let Some(parent) = target.parent().filter(|parent| *parent != output_dir) {
+            executor.apply(Mutation::CreateDir(parent.to_path_buf()))?;
+        }
+        tracing::debug!(path = %target.display(), "exporting obsidian note");
+        let note = format!(
+            "{}{}",
+            obsidian_frontmatter(&frontmatter),
+            obsidian_body(body)
+        );
+        executor.apply(Mutation::Write {
+            path: target,
+            contents: note,
+        })?;
+    }
+
+    Ok(files.len())
+}
+
+/// Renders every snippet to a standalone HTML page under `output_dir`, plus an
+/// `index.html` linking to all of them sorted by title.
+/// - `overwrite`: Allow exporting into a non-empty `output_dir`.
+pub fn export_html(
+    snippet_dir: &Path,
+    output_dir: &Path,
+    overwrite: bool,
+    executor: &Executor,
+) -> Result<usize, SnippetVaultError> {
+    prepare_output_dir(output_dir, overwrite, executor)?;
+
+    let mut pages: Vec<(Frontmatter, PathBuf)> = Vec::new();
+    for path in snippet_files(snippet_dir)? {
+        let content = fs::read_to_string(&path)?;
+        let frontmatter = Frontmatter::load(&path, &content)?;
+        let (_, body) = split_frontmatter(&content);
+
+        let relative = path
+            .strip_prefix(snippet_dir)
+            .unwrap_or(&path)
+            .with_extension("html");
+        let target = output_dir.join(&relative);
+        if let Some(parent) = target.parent().filter(|parent| *parent != output_dir) {
+            executor.apply(Mutation::CreateDir(parent.to_path_buf()))?;
+        }
+        executor.apply(Mutation::Write {
+            path: target,
+            contents: html_page(&frontmatter.title, &markdown_to_html(body)),
+        })?;
+        pages.push((frontmatter, relative));
+    }
+
+    pages.sort_by(|a, b| a.0.title.cmp(&b.0.title));
+    let mut index = String::from("<h1>Snippets</h1>\n<ul>\n");
+    for (frontmatter, relative) in &pages {
+        index.push_str(&format!(
+            "<li><a href=\"{}\">{}</a> <small>{} {}</small></li>\n",
+            escape_html(&relative.to_string_lossy()),
+            escape_html(&frontmatter.title),
+            escape_html(&frontmatter.language),
+            escape_html(&frontmatter.tags.join(", ")),
+        ));
+    }
+    index.push_str("</ul>\n");
+    executor.apply(Mutation::Write {
+        path: output_dir.join("index.html"),
+        contents: html_page("Snippets", &index),
+    })?;
+
+    Ok(pages.len())
+}
+
+/// Output format of [`export_by_language`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ExportFormat {
+    Markdown,
+    Html,
+    PlainText,
+}
+
+impl ExportFormat {
+    /// Names accepted by `--format`.
+    pub const NAMES: &'static [&'static str] = &["markdown", "html", "text"];
+
+    /// Parses one of [`ExportFormat::NAMES`].
+    pub fn from_name(name: &str) -> Option<Self> {
+        match name {
+            "markdown" => Some(ExportFormat::Markdown),
+            "html" => Some(ExportFormat::Html),
+            "text" => Some(ExportFormat::PlainText),
+            _ => None,
+        }
+    }
+
+    fn extension(self) -> &'static str {
+        match self {
+            ExportFormat::Markdown => "md",
+            ExportFormat::Html => "html",
+            ExportFormat::PlainText => "txt",
+        }
+    }
+}
+
+/// Writes one file per language (`rust_snippets.md`, ...) into `output_dir`,
+/// concatenating that language's snippets under `## {title}` headings separated
+/// by horizontal rules, and returns the file written for each language.
+/// Languages are grouped under their canonical name, so `Rust`, `rs`, and
+/// `rust` share one file. Snippets that cannot be read are skipped with a
+/// warning.
+/// - `aliases`: Language aliases from the `[language_aliases]` config section.
+/// - `overwrite`: Allow exporting into a non-empty `output_dir`.
+pub fn export_by_language(
+    snippet_dir: &Path,
+    output_dir: &Path,
+    format: ExportFormat,
+    aliases: &HashMap<String, String>,
+    overwrite: bool,
+    executor: &Executor,
+) -> Result<HashMap<String, PathBuf>, SnippetVaultError> {
+    prepare_output_dir(output_dir, overwrite, executor)?;
+
+    let mut by_language: BTreeMap<String, Vec<(String, String)>> = BTreeMap::new();
+    for path in snippet_files(snippet_dir)? {
+        let (frontmatter, body) = match language_entry(&path) {
+            Ok(entry) => entry,
+            Err(err) => {
+                eprintln!("{} Skipping {}: {}.", "⚠".warning(), path.display(), err);
+                continue;
+            }
+        };
+        let language = match language::canonical_language(&frontmatter.language, aliases) {
+            language if language.is_empty() => "unknown".to_string(),
+            language => language,
+        };
+        by_language
+            .entry(language)
+            .or_default()
+            .push((frontmatter.title, body));
+    }
+
+    let mut outputs = HashMap::new();
+    for (language, mut snippets) in by_language {
+        snippets.sort_by(|a, b| a.0.cmp(&b.0));
+        let markdown = snippets
+            .iter()
+            .map(|(title, body)| format!("## {}\n\n{}\n", title, body))
+            .collect::<Vec<_>>()
+            .join("\n---\n\n");
+        let heading = format!("{} snippets", language);
+
+        let contents = match format {
+            ExportFormat::Markdown => format!("# {}\n\n{}", heading, markdown),
+            ExportFormat::Html => html_page(
+                &heading,
+                &markdown_to_html(&format!("# {}\n\n{}", heading, markdown)),
+            ),
+            ExportFormat::PlainText => snippets
+                .iter()
+                .map(|(title, body)| {
+                    let underline = "=".repeat(width::display_width(title));
+                    format!("{}\n{}\n\n{}\n", title, underline, body)
+                })
+                .collect::<Vec<_>>()
+                .join(&format!("\n{}\n\n", "-".repeat(72))),
+        };
+
+        let path = output_dir.join(format!(
+            "{}_snippets.{}",
+            file_stem(&language),
+            format.extension()
+        ));
+        executor.apply(Mutation::Write {
+            path: path.clone(),
+            contents,
+        })?;
+        outputs.insert(language, path);
+    }
+
+    Ok(outputs)
+}
+
+/// Reads the frontmatter and trimmed body of the snippet at `path` for
+/// [`export_by_language`], without any `# Title:` line.
+fn language_entry(path: &Path) -> Result<(Frontmatter, String), SnippetVaultError> {
+    let content = fs::read_to_string(path)?;
+    let frontmatter = Frontmatter::load(path, &content)?;
+    let body = split_frontmatter(&content)
+        .1
+        .lines()
+        .filter(|line| !line.starts_with("# Title:"))
+        .collect::<Vec<_>>()
+        .join("\n");
+    Ok((frontmatter, body.trim().to_string()))
+}
+
+/// File name stem for `language` that stays inside the output directory:
+/// characters other than letters, digits, `+`, `#`, and `-` become `_`, so
+/// `html and css` is `html_and_css` while `c++` and `c#` stay distinct.
+fn file_stem(language: &str) -> String {
+    let stem: String = language
+        .chars()
+        .map(|c| {
+            if c.is_alphanumeric() || matches!(c, '+' | '#' | '-') {
+                c
+            } else {
+                '_'
+            }
+        })
+        .collect();
+    match stem.trim_matches('_') {
+        "" => "unknown".to_string(),
+        stem => stem.to_string(),
+    }
+}
+
+/// Compiles `snippets` (already filtered and sorted) into one markdown cheatsheet
+/// for `language`: a table of contents linking to each entry, then each
+/// snippet's title, tags, and non-empty code blocks. Snippets without code are
+/// left out, and repeated titles get a ` (2)`, ` (3)`, ... suffix. Returns the
+/// document and the number of snippets in it.
+pub fn cheatsheet(
+    language: &str,
+    snippets: &[SnippetMeta],
+) -> Result<(String, usize), SnippetVaultError> {
+    let mut entries = Vec::new();
+    let mut seen: HashMap<String, usize> = HashMap::new();
+    let mut anchors: HashMap<String, usize> = HashMap::new();
+
+    for meta in snippets {
+        let Some(content) = snippet::read_text(&meta.path)? else {
+            continue;
+        };
+        let blocks: Vec<(String, String)> = if snippet::is_markdown(&meta.path) {
+            snippet::code_blocks(&content)
+                .into_iter()
+                .filter(|block| !block.code.trim().is_empty())
+                .map(|block| (block.language, block.code))
+                .collect()
+        } else if content.trim().is_empty() {
+            Vec::new()
+        } else {
+            vec![(meta.language.clone(), content)]
+        };
+        if blocks.is_empty() {
+            continue;
+        }
+
+        let occurrence = seen.entry(meta.title.to_lowercase()).or_insert(0);
+        *occurrence += 1;
+        let title = match *occurrence {
+            1 => meta.title.clone(),
+            n => format!("{} ({})", meta.title, n),
+        };
+        let mut anchor = slug(&title);
+        let uses = anchors.entry(anchor.clone()).or_insert(0);
+        *uses += 1;
+        if *uses > 1 {
+            anchor = format!("{}-{}", anchor, uses);
+        }
+        entries.push((title, anchor, &meta.tags, blocks));
+    }
+
+    let mut markdown = format!("# {} cheatsheet\n\n", language);
+    for (title, anchor, _, _) in &entries {
+        markdown.push_str(&format!("- [{}](#{})\n", title, anchor));
+    }
+    markdown.push('\n');
+    for (title, anchor, tags, blocks) in &entries {
+        markdown.push_str(&format!(
+            "---\n\n<a id=\"{}\"></a>\n\n## {}\n\n",
+            anchor, title
+        ));
+        if !tags.is_empty() {
+            let tags: Vec<String> = tags.iter().map(|tag| format!("`{}`", tag)).collect();
+            markdown.push_str(&format!("Tags: {}\n\n", tags.join(", ")));
+        }
+        for (fence, code) in blocks {
+            markdown.push_str(&format!("```{}\n{}", fence, code));
+            if !code.ends_with('\n') {
+                markdown.push('\n');
+            }
+            markdown.push_str("```\n\n");
+        }
+    }
+    markdown.truncate(markdown.trim_end().len());
+    markdown.push('\n');
+    Ok((markdown, entries.len()))
+}
+
+/// Renders a markdown document from [`cheatsheet`] as a standalone HTML page.
+pub fn cheatsheet_html(language: &str, markdown: &str) -> String {
+    html_page(
+        &format!("{} cheatsheet", language),
+        &markdown_to_html(markdown),
+    )
+}
+
+/// Anchor name for `title`: lowercase letters and digits joined by hyphens.
+fn slug(title: &str) -> String {
+    let slug = title
+        .to_lowercase()
+        .split(|c: char| !c.is_alphanumeric())
+        .filter(|word| !word.is_empty())
+        .collect::<Vec<_>>()
+        .join("-");
+    if slug.is_empty() {
+        "snippet".to_string()
+    } else {
+        slug
+    }
+}
+
+/// Columns of [`export_csv`], in order.
+pub const CSV_HEADERS: &[&str] = &[
+    "path",
+    "language",
+    "tags",
+    "created_at",
+    "modified_at",
+    "size_bytes",
+    "word_count",
+    "code_lines",
+    "rating",
+    "status",
+];
+
+/// How [`export_csv`] writes `created_at` and `modified_at`.
+pub const CSV_TIME_FORMAT: &str = "%Y-%m-%dT%H:%M:%S";
+
+/// Writes the metadata of every snippet (not its content) to `output` as CSV
+/// with the [`CSV_HEADERS`] columns, one row per snippet, and returns the row
+/// count. Paths are relative to the vault, tags comma-separated, and `rating`
+/// and `status` come from the frontmatter fields of those names. Files that
+/// cannot be read get no row and a warning naming them.
+/// - `full`: Also fill `word_count` and `code_lines`, which means reading every
+///   file; they are left empty otherwise. Binary files get no row then.
+/// - `extensions`: File extensions that count as snippets.
+/// - `use_cache`: Serve unchanged snippets from the metadata cache.
+pub fn export_csv(
+    snippet_dir: &Path,
+    output: &Path,
+    full: bool,
+    extensions: &[String],
+    use_cache: bool,
+    executor: &Executor,
+) -> Result<usize, SnippetVaultError> {
+    let snippets = vault::load_snippets(snippet_dir, extensions, use_cache)?;
+    let mut writer = csv::Writer::from_writer(Vec::new());
+    writer.write_record(CSV_HEADERS)?;
+
+    let mut rows = 0;
+    for meta in &snippets {
+        // A file that cannot be read (deleted meanwhile, no permission) loses
+        // its row rather than the whole export.
+        match csv_record(snippet_dir, meta, full) {
+            Ok(Some(record)) => {
+                writer.write_record(record)?;
+                rows += 1;
+            }
+            Ok(None) => {}
+            Err(err) => eprintln!(
+                "{} Skipping {}: {}.",
+                "⚠".warning(),
+                meta.path.display(),
+                err
+            ),
+        }
+    }
+
+    let bytes = writer
+        .into_inner()
+        .map_err(|err| SnippetVaultError::Io(err.into_error()))?;
+    executor.apply(Mutation::Write {
+        path: output.to_path_buf(),
+        contents: String::from_utf8_lossy(&bytes).into_owned(),
+    })?;
+    Ok(rows)
+}
+
+/// The [`CSV_HEADERS`] columns of one [`export_csv`] row, or `None` for a file
+/// [`snippet::read_text`] skips.
+fn csv_record(
+    snippet_dir: &Path,
+    meta: &SnippetMeta,
+    full: bool,
+) -> std::io::Result<Option<[String; 10]>> {
+    let timestamp = |time: Option<chrono::NaiveDateTime>| {
+        time.map(|time| time.format(CSV_TIME_FORMAT).to_string())
+            .unwrap_or_default()
+    };
+    let (word_count, code_lines) = if full {
+        let Some(content) = snippet::read_text(&meta.path)? else {
+            return Ok(None);
+        };
+        let code_lines = if snippet::is_markdown(&meta.path) {
+            snippet::code_blocks(&content)
+                .iter()
+                .map(|block| block.code.lines().count())
+                .sum()
+        } else {
+            content.lines().count()
+        };
+        let words = snippet::SnippetStats::of(content.as_bytes()).words;
+        (words.to_string(), code_lines.to_string())
+    } else {
+        (String::new(), String::new())
+    };
+    let size = fs::metadata(&meta.path)?.len();
+
+    Ok(Some([
+        meta.path
+            .strip_prefix(snippet_dir)
+            .unwrap_or(&meta.path)
+            .display()
+            .to_string(),
+        meta.language.clone(),
+        meta.tags.join(","),
+        timestamp(meta.created),
+        timestamp(snippet::modified_time(&meta.path)),
+        size.to_string(),
+        word_count,
+        code_lines,
+        meta.extra.get("rating").cloned().unwrap_or_default(),
+        meta.extra.get("status").cloned().unwrap_or_default(),
+    ]))
+}
+
+/// Creates `output_dir`, refusing to reuse a non-empty one unless `overwrite` is set.
+fn prepare_output_dir(
+    output_dir: &Path,
+    overwrite: bool,
+    executor: &Executor,
+) -> Result<(), SnippetVaultError> {
+    let occupied = output_dir.exists() && fs::read_dir(output_dir)?.next().is_some();
+    if occupied && !overwrite {
+        return Err(SnippetVaultError::AlreadyExists(output_dir.to_path_buf()));
+    }
+    executor.apply(Mutation::CreateDir(output_dir.to_path_buf()))
+}
+
+/// Converts a markdown body to an HTML fragment.
+fn markdown_to_html(markdown: &str) -> String {
+    let mut output = String::new();
+    let options =
+        Options::ENABLE_TABLES | Options::ENABLE_STRIKETHROUGH | Options::ENABLE_TASKLISTS;
+    html::push_html(&mut output, Parser::new_ext(markdown, options));
+    output
+}
+
+/// Wraps an HTML fragment in a minimal standalone page.
+fn html_page(title: &str, body: &str) -> String {
+    format!(
+        "<!DOCTYPE html>\n<html>\n<head>\n<meta charset=\"utf-8\">\n<title>{}</title>\n\
+         <style>body {{ max-width: 50rem; margin: 2rem auto; font-family: sans-serif; }} \
+         pre {{ background: #f4f4f4; padding: 1rem; overflow-x: auto; }}</style>\n\
+         </head>\n<body>\n{}</body>\n</html>\n",
+        escape_html(title),
+        body
+    )
+}
+
+/// Escapes text for inclusion in HTML.
+fn escape_html(text: &str) -> String {
+    text.replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+        .replace('"', "&quot;")
+}
+
+/// Renders the YAML frontmatter Obsidian understands (`tags:` and `aliases:`).
+fn obsidian_frontmatter(frontmatter: &Frontmatter) -> String {
+    let mut yaml = String::from("---\ntags:\n");
+    for tag in frontmatter
+        .tags
+        .iter()
+        .chain(std::iter::once(&frontmatter.language))
+        .filter(|tag| !tag.is_empty())
+    {
+        // Obsidian tags cannot contain spaces.
+        yaml.push_str(&format!("  - {}\n", yaml_string(&tag.replace(' ', "-"))));
+    }
+    yaml.push_str("aliases:\n");
+    if !frontmatter.title.is_empty() {
+        yaml.push_str(&format!("  - {}\n", yaml_string(&frontmatter.title)));
+    }
+    yaml.push_str("---\n");
+    yaml
+}
+
+/// Quotes a YAML scalar.
+fn yaml_string(value: &str) -> String {
+    format!("\"{}\"", value.replace('\\', "\\\\").replace('"', "\\\""))
+}
+
+/// Rewrites the template body: `# Title:` becomes a heading, `### Link:` URLs become
+/// markdown links, and `[[snippet.md]]` references become `[[snippet]]` wikilinks.
+fn obsidian_body(body: &str) -> String {
+    let mut output = String::with_capacity(body.len());
+    let mut in_link_section = false;
+    let mut in_code = false;
+
+    for line in body.lines() {
+        if line.trim_start().starts_with("```") {
+            in_code = !in_code;
+        }
+        if in_code || line.trim_start().starts_with("```") {
+            output.push_str(line);
+            output.push('\n');
+            continue;
+        }
+
+        if let Some(title) = line.strip_prefix("# Title:") {
+            output.push_str(&format!("# {}\n", title.trim()));
+            continue;
+        }
+        if line.trim() == "# ---" {
+            continue;
+        }
+
+        if line.starts_with("###") {
+            in_link_section = line.starts_with("### Link:");
+        }
+        let line = if in_link_section {
+            link_urls(line)
+        } else {
+            line.to_string()
+        };
+        output.push_str(&wikilinks(&line));
+        output.push('\n');
+    }
+
+    output
+}
+
+/// Turns bare `http(s)://` URLs in `line` into `[source](URL)` links.
+fn link_urls(line: &str) -> String {
+    line.split(' ')
+        .map(|word| {
+            if word.starts_with("http://") || word.starts_with("https://") {
+                format!("[source]({})", word)
+            } else {
+                word.to_string()
+            }
+        })
+        .collect::<Vec<_>>()
+        .join(" ")
+}
+
+/// Strips the `.md` extension from `[[target]]` / `[[target|label]]` references.
+fn wikilinks(line: &str) -> String {
+    let mut output = String::with_capacity(line.len());
+    let mut rest = line;
+
+    while let Some(start) = rest.find("[[") {
+        let Some(end) = rest[start..].find("]]") else {
+            break;
+        };
+        let inner = &rest[start + 2..start + end];
+        let (target, label) = match inner.split_once('|') {
+            Some((target, label)) => (target, Some(label)),
+            None => (inner, None),
+        };
+        let target = target.strip_suffix(".md").unwrap_or(target);
+
+        output.push_str(&rest[..start]);
+        output.push_str("[[");
+        output.push_str(target);
+        if let Some(label) = label {
+            output.push('|');
+            output.push_str(label);
+        }
+        output.push_str("]]");
+        rest = &rest[start + end + 2..];
+    }
+
+    output.push_str(rest);
+    output
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Every file and directory under `root` with its bytes (empty for
+    /// directories), keyed by path.
+    fn tree(root: &Path) -> BTreeMap<PathBuf, Vec<u8>> {
+        let mut entries = BTreeMap::new();
+        let mut pending = vec![root.to_path_buf()];
+        while let Some(dir) = pending.pop() {
+            for entry in fs::read_dir(&dir).unwrap() {
+                let path = entry.unwrap().path();
+                if path.is_dir() {
+                    entries.insert(path.clone(), Vec::new());
+                    pending.push(path);
+                } else {
+                    entries.insert(path.clone(), fs::read(&path).unwrap());
+                }
+            }
+        }
+        entries
+    }
+
+    #[test]
+    fn obsidian_dry_run_leaves_output_untouched() {
+        let vault = tempfile::tempdir().unwrap();
+        fs::create_dir(vault.path().join("docker")).unwrap();
+        fs::write(
+            vault.path().join("git_undo.md"),
+            "---\ntitle: Undo commit\ntags: [git]\n---\n```bash\ngit reset HEAD~1\n```\n",
+        )
+        .unwrap();
+        fs::write(
+            vault.path().join("docker").join("prune.md"),
+            "# Prune images\n### Tags: docker\n```bash\ndocker image prune\n```\n",
+        )
+        .unwrap();
+
+        let output = tempfile::tempdir().unwrap();
+        fs::write(output.path().join("git_undo.md"), "stale export\n").unwrap();
+        let before = tree(output.path());
+
+        let exported =
+            export_obsidian(vault.path(), output.path(), true, &Executor::new(true)).unwrap();
+
+        assert_eq!(exported, 2);
+        assert_eq!(tree(output.path()), before);
+    }
+
+    /// A vault with a shell snippet and a binary file posing as one.
+    fn vault_with_binary() -> tempfile::TempDir {
+        let vault = tempfile::tempdir().unwrap();
+        fs::write(
+            vault.path().join("undo.md"),
+            "+++\ntitle = \"Undo commit\"\nlanguage = \"bash\"\ntags = []\n+++\n\n```bash\ngit reset HEAD~1\n```\n",
+        )
+        .unwrap();
+        fs::write(
+            vault.path().join("image.md"),
+            b"\x89PNG\r\n\x1a\n\0\0\xff\xfe",
+        )
+        .unwrap();
+        vault
+    }
+
+    #[test]
+    fn binary_snippets_are_skipped_by_exports() {
+        crate::testing::isolate_user_dirs();
+        let vault = vault_with_binary();
+        let extensions = vec!["md".to_string()];
+        let snippets = vault::load_snippets(vault.path(), &extensions, false).unwrap();
+        assert_eq!(snippets.len(), 2);
+
+        let (markdown, count) = cheatsheet("bash", &snippets).unwrap();
+        assert_eq!(count, 1);
+        assert!(markdown.contains("git reset HEAD~1"));
+
+        let output = tempfile::tempdir().unwrap();
+        let csv = output.path().join("vault.csv");
+        let rows = export_csv(
+            vault.path(),
+            &csv,
+            true,
+            &extensions,
+            false,
+            &Executor::new(false),
+        )
+        .unwrap();
+        assert_eq!(rows, 1);
+        let written = fs::read_to_string(&csv).unwrap();
+        assert!(written.contains("undo.md"));
+        assert!(!written.contains("image.md"));
+    }
+
+    #[test]
+    fn languages_are_exported_under_their_canonical_name() {
+        crate::testing::isolate_user_dirs();
+        let vault = vault_with_binary();
+        for (name, language, title) in [
+            ("spawn.md", "Rust", "Spawn"),
+            ("join.md", "rs", "Join"),
+            ("greet.md", "rust", "挨拶"),
+            ("escape.md", "../etc", "Escape"),
+        ] {
+            fs::write(
+                vault.path().join(name),
+                format!(
+                    "+++\ntitle = \"{}\"\nlanguage = \"{}\"\ntags = []\n+++\n```\nx\n```\n",
+                    title, language
+                ),
+            )
+            .unwrap();
+        }
+        let aliases = HashMap::from([("rs".to_string(), "rust".to_string())]);
+
+        let output = tempfile::tempdir().unwrap();
+        let outputs = export_by_language(
+            vault.path(),
+            output.path(),
+            ExportFormat::PlainText,
+            &aliases,
+            false,
+            &Executor::new(false),
+        )
+        .unwrap();
+
+        let mut languages: Vec<&String> = outputs.keys().collect();
+        languages.sort();
+        assert_eq!(languages, ["../etc", "bash", "rust"]);
+        assert_eq!(outputs["../etc"], output.path().join("etc_snippets.txt"));
+        let rust = fs::read_to_string(&outputs["rust"]).unwrap();
+        assert!(rust.contains("Join\n====\n"), "{}", rust);
+        assert!(rust.contains("Spawn\n=====\n"), "{}", rust);
+        assert!(rust.contains("挨拶\n====\n"), "{}", rust);
+    }
+
+    #[test]
+    fn unreadable_snippets_fail_only_their_row() {
+        crate::testing::isolate_user_dirs();
+        let vault = vault_with_binary();
+        let extensions = vec!["md".to_string()];
+        let snippets = vault::load_snippets(vault.path(), &extensions, false).unwrap();
+        let undo = snippets
+            .iter()
+            .find(|meta| meta.path.ends_with("undo.md"))
+            .unwrap();
+
+        let record = csv_record(vault.path(), undo, true).unwrap().unwrap();
+        assert_eq!(record[0], "undo.md");
+        assert_eq!(record[1], "bash");
+        // Gone between listing and reading, as with a sync running meanwhile.
+        fs::remove_file(&undo.path).unwrap();
+        assert!(csv_record(vault.path(), undo, true).is_err());
+        assert!(csv_record(vault.path(), undo, false).is_err());
+    }
+}
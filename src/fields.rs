@@ -0,0 +1,47 @@
+use std::fs;
+use std::path::Path;
+
+use crate::error::SnippetVaultError;
+use crate::snippet::{is_markdown, split_frontmatter, Frontmatter, RESERVED_FIELDS};
+use crate::vault::{Executor, Mutation};
+
+/// Sets (or with `value: None`, removes) the custom frontmatter field `key` of
+/// the markdown snippet at `path`. Only the frontmatter block is rewritten; the
+/// body is kept byte-for-byte. Returns whether the field changed.
+/// - `executor`: Applies (or, with `--dry-run`, only prints) the rewrite.
+pub fn set_field(
+    path: &Path,
+    key: &str,
+    value: Option<toml::Value>,
+    executor: &Executor,
+) -> Result<bool, SnippetVaultError> {
+    if RESERVED_FIELDS.contains(&key) {
+        return Err(SnippetVaultError::Config(format!(
+            "'{}' is a built-in field and cannot be set with --set_field",
+            key
+        )));
+    }
+    if !is_markdown(path) {
+        return Err(SnippetVaultError::Parse {
+            path: path.to_path_buf(),
+            message: "custom fields are only supported in markdown snippets".to_string(),
+        });
+    }
+
+    let content = fs::read_to_string(path)?;
+    let mut frontmatter = Frontmatter::load(path, &content)?;
+    let previous = match value {
+        Some(value) => frontmatter.extra.insert(key.to_string(), value.clone()),
+        None => frontmatter.extra.remove(key),
+    };
+    if previous == frontmatter.extra.get(key).cloned() {
+        return Ok(false);
+    }
+
+    let body = split_frontmatter(&content).1;
+    executor.apply(Mutation::Write {
+        path: path.to_path_buf(),
+        contents: format!("{}{}", frontmatter.render(), body),
+    })?;
+    Ok(true)
+}
@@ -0,0 +1,346 @@
+use std::collections::{BTreeMap, HashMap, HashSet};
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::process::Command as ProcessCommand;
+use std::sync::{LazyLock, Mutex};
+
+use crate::config::Config;
+use crate::editor;
+use crate::error::SnippetVaultError;
+use crate::language;
+use crate::logging;
+use crate::platform;
+use crate::snippet::{self, Frontmatter};
+use crate::style::Styled;
+use crate::vault::{self, Executor, Mutation};
+
+/// Formatters compiled into the binary, by language. Each reads a code block
+/// on stdin and writes it formatted to stdout; `[formatters]` in the config
+/// adds languages or replaces these.
+const DEFAULT_FORMATTERS: &[(&str, &str)] = &[
+    ("rust", "rustfmt --emit stdout"),
+    ("python", "black --quiet -"),
+    ("bash", "shfmt"),
+    ("sh", "shfmt"),
+    ("javascript", "prettier --stdin-filepath snippet.js"),
+    ("typescript", "prettier --stdin-filepath snippet.ts"),
+    ("json", "prettier --stdin-filepath snippet.json"),
+    ("css", "prettier --stdin-filepath snippet.css"),
+];
+
+/// Whether each formatter program is on `PATH`, looked up once per run.
+static INSTALLED: LazyLock<Mutex<HashMap<String, bool>>> = LazyLock::new(Default::default);
+
+/// Missing formatter programs already reported this run.
+static REPORTED_MISSING: LazyLock<Mutex<HashSet<String>>> = LazyLock::new(Default::default);
+
+/// Every language with a formatter and its command: the built-in ones with
+/// the config's `[formatters]` applied, language names resolved through
+/// `language_aliases`.
+pub fn formatters(config: &Config) -> BTreeMap<String, String> {
+    let mut formatters: BTreeMap<String, String> = DEFAULT_FORMATTERS
+        .iter()
+        .map(|(language, command)| (language.to_string(), command.to_string()))
+        .collect();
+    for (language, command) in &config.formatters {
+        let language = language::resolve_language(language, &config.language_aliases);
+        formatters.insert(language.to_lowercase(), command.clone());
+    }
+    formatters
+}
+
+/// The program a formatter command runs, e.g. `rustfmt`.
+pub fn program(command: &str) -> Option<String> {
+    editor::split_command(command).into_iter().next()
+}
+
+/// Whether the program of `command` is on `PATH`.
+pub fn is_installed(command: &str) -> bool {
+    let Some(program) = program(command) else {
+        return false;
+    };
+    let mut installed = INSTALLED.lock().unwrap_or_else(|err| err.into_inner());
+    *installed
+        .entry(program.clone())
+        .or_insert_with(|| platform::find_in_path(&program).is_some())
+}
+
+/// A block whose formatter did not succeed, and why.
+#[derive(Debug, Clone)]
+pub struct FormatFailure {
+    /// 1-based line of the block's opening fence.
+    pub line: usize,
+    pub message: String,
+}
+
+/// What formatting one snippet did.
+#[derive(Debug, Default)]
+pub struct FormatReport {
+    /// Blocks whose content changed.
+    pub formatted: usize,
+    /// Blocks whose formatter failed; when there are any, the file is left as it was.
+    pub failures: Vec<FormatFailure>,
+    /// Formatter programs that are not installed; their blocks are skipped.
+    pub missing: Vec<String>,
+}
+
+/// Runs every fenced code block of the markdown snippet at `path` (fenced as
+/// [`snippet::code_blocks`] reads them) through the formatter for its language
+/// (the fence's, else the snippet's) and writes the result. Only the lines between the fences change; fence lines, prose, and
+/// frontmatter stay as they are. When any formatter fails, nothing is written.
+/// Plain code files are not touched.
+/// - `executor`: Applies (or, with `--dry-run`, only prints) the rewrite.
+pub fn format_snippet(
+    config: &Config,
+    path: &Path,
+    executor: &Executor,
+) -> Result<FormatReport, SnippetVaultError> {
+    let mut report = FormatReport::default();
+    if !snippet::is_markdown(path) {
+        return Ok(report);
+    }
+    let content = fs::read_to_string(path)?;
+    let snippet_language = Frontmatter::load(path, &content)?.language;
+    let formatters = formatters(config);
+
+    let lines: Vec<&str> = content.split_inclusive('\n').collect();
+    let mut output = String::new();
+    // Lines of `content` already copied to `output`.
+    let mut copied = 0;
+    for block in snippet::code_blocks(&content) {
+        let first = block.start_line;
+        let end = first + block.code.matches('\n').count();
+        // An unterminated block runs to the end and is left as written.
+        if end >= lines.len() {
+            break;
+        }
+        output.push_str(&lines[copied..first].concat());
+        copied = end;
+
+        let code = lines[first..end].concat();
+        let language = match block.language.as_str() {
+            "" => snippet_language.as_str(),
+            language => language,
+        };
+        let language = language::resolve_language(language, &config.language_aliases);
+        match formatters.get(&language.to_lowercase()) {
+            Some(command) if !is_installed(command) => {
+                report.missing.extend(program(command));
+                output.push_str(&code);
+            }
+            Some(command) => match run_formatter(command, &code) {
+                Ok(formatted) => {
+                    let formatted = with_line_endings_of(&code, &formatted);
+                    report.formatted += usize::from(formatted != code);
+                    output.push_str(&formatted);
+                }
+                Err(message) => {
+                    report.failures.push(FormatFailure {
+                        line: first,
+                        message,
+                    });
+                    output.push_str(&code);
+                }
+            },
+            None => output.push_str(&code),
+        }
+    }
+    output.push_str(&lines[copied..].concat());
+
+    report.missing.sort();
+    report.missing.dedup();
+    if report.failures.is_empty() && output != content {
+        executor.apply(Mutation::Write {
+            path: path.to_path_buf(),
+            contents: output,
+        })?;
+    } else {
+        report.formatted = 0;
+    }
+    Ok(report)
+}
+
+/// Formats `code` with `command`, returning its stdout when it exits 0 and
+/// the first line of its stderr (or its exit status) otherwise.
+fn run_formatter(command: &str, code: &str) -> Result<String, String> {
+    let words = editor::split_command(command);
+    let Some((program, args)) = words.split_first() else {
+        return Err("the formatter command is empty".to_string());
+    };
+    let output = logging::run_filter(ProcessCommand::new(program).args(args), code)
+        .map_err(|err| format!("{}: {}", program, err))?;
+    if !output.status.success() {
+        let stderr = String::from_utf8_lossy(&output.stderr);
+        return Err(match stderr.lines().find(|line| !line.trim().is_empty()) {
+            Some(line) => format!("{}: {}", program, line.trim()),
+            None => format!("{} exited with {}", program, output.status),
+        });
+    }
+    String::from_utf8(output.stdout).map_err(|_| format!("{} printed invalid UTF-8", program))
+}
+
+/// `formatted` with a final newline, and with CRLF line endings when the
+/// `original` block used them, so the fence after it stays on its own line.
+fn with_line_endings_of(original: &str, formatted: &str) -> String {
+    let mut formatted = formatted.replace("\r\n", "\n");
+    if !formatted.is_empty() && !formatted.ends_with('\n') {
+        formatted.push('\n');
+    }
+    if original.contains("\r\n") {
+        formatted = formatted.replace('\n', "\r\n");
+    }
+    formatted
+}
+
+/// Formats the markdown snippets among `paths` after an editor session when
+/// `format_on_save` (or `--format-on-save`) is on, printing what changed and
+/// which blocks were left alone. A read-only vault is never rewritten.
+/// - `executor`: Applies (or, with `--dry-run`, only prints) the rewrite.
+pub fn format_after_edit(config: &Config, paths: &[PathBuf], executor: &Executor) {
+    if !config.format_on_save || vault::is_read_only() {
+        return;
+    }
+    for path in paths {
+        match format_snippet(config, path, executor) {
+            Ok(report) => print_report(path, &report, executor.is_dry_run()),
+            Err(err) => println!(
+                "{} Not formatting {}: {}",
+                "⚠".warning(),
+                path.display(),
+                err
+            ),
+        }
+    }
+}
+
+/// Prints the outcome of [`format_snippet`] for `path`: failed blocks, how
+/// many blocks changed (unless `dry_run`, where the executor has said what
+/// would be written), and each missing formatter the first time it is met.
+pub fn print_report(path: &Path, report: &FormatReport, dry_run: bool) {
+    let mut reported = REPORTED_MISSING
+        .lock()
+        .unwrap_or_else(|err| err.into_inner());
+    for program in &report.missing {
+        if reported.insert(program.clone()) {
+            println!(
+                "{} {} is not on PATH; its code blocks are not formatted (see --doctor).",
+                "⚠".warning(),
+                program
+            );
+        }
+    }
+    for failure in &report.failures {
+        println!(
+            "{} {}:{}: {}",
+            "⚠".warning(),
+            path.display(),
+            failure.line,
+            failure.message
+        );
+    }
+    if !report.failures.is_empty() {
+        println!("{} {} was left unformatted.", "⚠".warning(), path.display());
+    } else if report.formatted > 0 && !dry_run {
+        println!(
+            "{} Formatted {} code blocks in {}",
+            "✔".success(),
+            report.formatted,
+            path.display()
+        );
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn config_with(formatters: &[(&str, &str)]) -> Config {
+        let mut config = Config::default();
+        for (language, command) in formatters {
+            config
+                .formatters
+                .insert(language.to_string(), command.to_string());
+        }
+        config
+    }
+
+    fn snippet(dir: &Path, body: &str) -> PathBuf {
+        let path = dir.join("snippet.md");
+        let content = format!(
+            "+++\ntitle = \"Shout\"\nlanguage = \"shout\"\ntags = []\n+++\n\n{}",
+            body
+        );
+        fs::write(&path, content).unwrap();
+        path
+    }
+
+    #[test]
+    fn every_fence_style_is_formatted_and_prose_is_kept() {
+        let dir = tempfile::tempdir().unwrap();
+        let config = config_with(&[("shout", "tr a-z A-Z")]);
+        let path = snippet(
+            dir.path(),
+            "some prose\n```\nplain fence\n```\n~~~shout\ntilde fence\n~~~\n\
+             ````shout\n```\nnested fence\n```\n````\n```text\nleft alone\n```\n",
+        );
+
+        let report = format_snippet(&config, &path, &Executor::new(false)).unwrap();
+        assert_eq!(report.formatted, 3);
+        assert!(report.failures.is_empty() && report.missing.is_empty());
+        let content = fs::read_to_string(&path).unwrap();
+        assert!(content.ends_with(
+            "some prose\n```\nPLAIN FENCE\n```\n~~~shout\nTILDE FENCE\n~~~\n\
+             ````shout\n```\nNESTED FENCE\n```\n````\n```text\nleft alone\n```\n"
+        ));
+        assert!(content.contains("title = \"Shout\""));
+    }
+
+    #[test]
+    fn a_failing_formatter_leaves_the_file_alone() {
+        let dir = tempfile::tempdir().unwrap();
+        // `false` exits 1, which a picker would take as "nothing chosen".
+        let config = config_with(&[("shout", "false"), ("text", "tr a-z A-Z")]);
+        let body = "```text\nfine\n```\n\n```\nbroken\n```\n";
+        let path = snippet(dir.path(), body);
+        let before = fs::read_to_string(&path).unwrap();
+
+        let report = format_snippet(&config, &path, &Executor::new(false)).unwrap();
+        assert_eq!(report.formatted, 0);
+        assert_eq!(report.failures.len(), 1);
+        assert_eq!(report.failures[0].line, 11);
+        assert!(report.failures[0].message.contains("false"));
+        assert_eq!(fs::read_to_string(&path).unwrap(), before);
+    }
+
+    #[test]
+    fn missing_formatters_and_unterminated_blocks_are_skipped() {
+        let dir = tempfile::tempdir().unwrap();
+        let config = config_with(&[("shout", "sv-no-such-formatter --stdin")]);
+        let path = snippet(dir.path(), "```\nquiet\n```\n\n```shout\nunterminated\n");
+        let before = fs::read_to_string(&path).unwrap();
+
+        let report = format_snippet(&config, &path, &Executor::new(false)).unwrap();
+        assert_eq!(report.missing, ["sv-no-such-formatter"]);
+        assert_eq!(fs::read_to_string(&path).unwrap(), before);
+    }
+
+    #[test]
+    fn large_blocks_do_not_block_on_the_pipes() {
+        let dir = tempfile::tempdir().unwrap();
+        let config = config_with(&[("shout", "cat")]);
+        // Far more than a pipe buffer, echoed back while it is still being written.
+        let code = "echo 'line of code'\n".repeat(100_000);
+        let path = snippet(dir.path(), &format!("```\n{}```\n", code));
+
+        let report = format_snippet(&config, &path, &Executor::new(false)).unwrap();
+        assert!(report.failures.is_empty());
+        assert_eq!(report.formatted, 0);
+    }
+
+    #[test]
+    fn formatted_code_keeps_the_block_line_endings() {
+        assert_eq!(with_line_endings_of("a\r\nb\r\n", "a\nb"), "a\r\nb\r\n");
+        assert_eq!(with_line_endings_of("a\n", "a\r\n"), "a\n");
+        assert_eq!(with_line_endings_of("", ""), "");
+    }
+}
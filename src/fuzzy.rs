@@ -0,0 +1,152 @@
+use fuzzy_matcher::skim::SkimMatcherV2;
+use fuzzy_matcher::FuzzyMatcher;
+use std::path::PathBuf;
+
+use crate::snippet::SnippetMeta;
+
+/// Lowest score per query word for the best match to be opened directly.
+const MIN_SCORE_PER_WORD: i64 = 40;
+
+/// How far the best score must be ahead of the runner-up (as a ratio) to be
+/// opened directly rather than offered in fzf.
+const MIN_LEAD: f64 = 1.5;
+
+/// Scores every snippet against `query` by its title, tags, and file name and
+/// returns the matching ones as `(score, path)`, best first. Every word of the
+/// query has to match for a snippet to be included.
+pub fn rank(snippets: &[SnippetMeta], query: &str) -> Vec<(i64, PathBuf)> {
+    let matcher = SkimMatcherV2::default().ignore_case();
+    let words: Vec<&str> = query.split_whitespace().collect();
+
+    let mut ranked: Vec<(i64, PathBuf)> = snippets
+        .iter()
+        .filter_map(|snippet| {
+            let haystack = format!(
+                "{} {} {}",
+                snippet.title,
+                snippet.tags.join(" "),
+                snippet
+                    .path
+                    .file_name()
+                    .map(|name| name.to_string_lossy())
+                    .unwrap_or_default()
+            );
+            words
+                .iter()
+                .map(|word| matcher.fuzzy_match(&haystack, word))
+                .sum::<Option<i64>>()
+                .map(|score| (score, snippet.path.clone()))
+        })
+        .collect();
+    ranked.sort_by(|a, b| b.0.cmp(&a.0).then_with(|| a.1.cmp(&b.1)));
+    ranked
+}
+
+/// Returns the best match of `ranked` when it is confident enough to open
+/// without asking: its score clears the threshold for a `words`-word query and
+/// it is clearly ahead of the runner-up.
+pub fn confident_match(ranked: &[(i64, PathBuf)], words: usize) -> Option<&PathBuf> {
+    let (best, path) = ranked.first()?;
+    if *best < MIN_SCORE_PER_WORD * words.max(1) as i64 {
+        return None;
+    }
+    match ranked.get(1) {
+        Some((runner_up, _)) if (*best as f64) < (*runner_up as f64) * MIN_LEAD => None,
+        _ => Some(path),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::snippet::SnippetStats;
+
+    fn snippet(file_name: &str, title: &str, tags: &[&str]) -> SnippetMeta {
+        SnippetMeta {
+            path: PathBuf::from("/vault").join(file_name),
+            title: title.to_string(),
+            language: String::new(),
+            tags: tags.iter().map(|tag| tag.to_string()).collect(),
+            created: None,
+            summary: None,
+            extra: Default::default(),
+            stats: SnippetStats::default(),
+        }
+    }
+
+    fn ranked(scores: &[i64]) -> Vec<(i64, PathBuf)> {
+        scores
+            .iter()
+            .enumerate()
+            .map(|(index, score)| (*score, PathBuf::from(format!("/vault/{}.md", index))))
+            .collect()
+    }
+
+    #[test]
+    fn every_word_must_match_title_tags_or_file_name() {
+        let snippets = [
+            snippet("undo.md", "Undo the last commit", &["git"]),
+            snippet("rebase.md", "Interactive rebase", &["git", "history"]),
+            snippet(
+                "snippet_2024-05-17-093000_docker_prune.md",
+                "Free disk space",
+                &[],
+            ),
+        ];
+        let names = |query: &str| -> Vec<String> {
+            rank(&snippets, query)
+                .into_iter()
+                .map(|(_, path)| path.file_name().unwrap().to_string_lossy().into_owned())
+                .collect()
+        };
+
+        assert_eq!(names("undo commit"), ["undo.md"]);
+        assert_eq!(names("history rebase"), ["rebase.md"]);
+        assert_eq!(
+            names("docker prune"),
+            ["snippet_2024-05-17-093000_docker_prune.md"]
+        );
+        assert!(names("undo docker").is_empty());
+        assert_eq!(names("git").len(), 2);
+    }
+
+    #[test]
+    fn ranking_is_best_first_with_ties_by_path() {
+        let snippets = [
+            snippet("b.md", "Deploy", &[]),
+            snippet("a.md", "Deploy", &[]),
+            snippet("c.md", "Describe pod logs as yaml", &[]),
+        ];
+        let ranked = rank(&snippets, "deploy");
+        assert_eq!(ranked.len(), 3);
+        assert_eq!(ranked[0].0, ranked[1].0);
+        assert_eq!(ranked[0].1, PathBuf::from("/vault/a.md"));
+        assert_eq!(ranked[1].1, PathBuf::from("/vault/b.md"));
+        assert!(ranked[2].0 < ranked[1].0);
+    }
+
+    #[test]
+    fn the_score_threshold_scales_with_the_word_count() {
+        let at_least = MIN_SCORE_PER_WORD;
+        assert!(confident_match(&ranked(&[at_least]), 1).is_some());
+        assert!(confident_match(&ranked(&[at_least - 1]), 1).is_none());
+        assert!(confident_match(&ranked(&[at_least * 3]), 3).is_some());
+        assert!(confident_match(&ranked(&[at_least * 3 - 1]), 3).is_none());
+        // A query without words is held to the one-word threshold.
+        assert!(confident_match(&ranked(&[at_least - 1]), 0).is_none());
+        assert!(confident_match(&[], 1).is_none());
+    }
+
+    #[test]
+    fn the_best_match_needs_a_clear_lead() {
+        // 150 is exactly MIN_LEAD times 100.
+        assert_eq!(
+            confident_match(&ranked(&[150, 100]), 1),
+            Some(&PathBuf::from("/vault/0.md"))
+        );
+        assert!(confident_match(&ranked(&[149, 100]), 1).is_none());
+        assert!(confident_match(&ranked(&[100, 100]), 1).is_none());
+        // Only the runner-up counts, not the rest.
+        assert!(confident_match(&ranked(&[300, 100, 299]), 1).is_some());
+    }
+}
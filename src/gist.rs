@@ -0,0 +1,79 @@
+use serde_json::json;
+use std::fs;
+use std::path::Path;
+
+use crate::detect;
+use crate::error::SnippetVaultError;
+use crate::language;
+use crate::snippet::{self, SnippetMeta};
+
+/// GitHub endpoint that creates gists.
+pub const GISTS_API: &str = "https://api.github.com/gists";
+
+/// Creates a GitHub gist from the first code block of the snippet at `path`
+/// (or the whole file for plain code files) and returns the gist's URL.
+/// The gist is described by the snippet's title, and its file is named after
+/// the snippet with the extension of the fence language (see
+/// [`detect::block_language`] for bare fences).
+/// - `token`: GitHub token with the `gist` scope.
+/// - `public`: Create a public gist instead of a secret one.
+pub fn create_gist(path: &Path, token: &str, public: bool) -> Result<String, SnippetVaultError> {
+    let meta = SnippetMeta::read(path)?;
+    let (filename, code) = gist_file(path, &meta)?;
+
+    let mut files = serde_json::Map::new();
+    files.insert(filename, json!({ "content": code }));
+    let body = json!({
+        "description": meta.title,
+        "public": public,
+        "files": files,
+    });
+    tracing::debug!(path = %path.display(), public, "creating gist");
+    let response = ureq::post(GISTS_API)
+        .set("Authorization", &format!("Bearer {}", token))
+        .set("Accept", "application/vnd.github+json")
+        .set("User-Agent", "snippets_vault")
+        .send_json(body)
+        .map_err(|err| match err {
+            ureq::Error::Status(status, response) => SnippetVaultError::Command(format!(
+                "GitHub answered {}: {}",
+                status,
+                response.into_string().unwrap_or_default().trim()
+            )),
+            err => SnippetVaultError::Command(format!("GitHub request failed: {}", err)),
+        })?;
+
+    let created: serde_json::Value = response.into_json()?;
+    created["html_url"]
+        .as_str()
+        .map(str::to_string)
+        .ok_or_else(|| SnippetVaultError::Command("GitHub returned no gist URL".to_string()))
+}
+
+/// The gist's file name and contents for the snippet at `path`.
+fn gist_file(path: &Path, meta: &SnippetMeta) -> Result<(String, String), SnippetVaultError> {
+    let content = fs::read_to_string(path)?;
+    let stem = path
+        .file_stem()
+        .and_then(|stem| stem.to_str())
+        .unwrap_or("snippet");
+
+    if !snippet::is_markdown(path) {
+        let name = path
+            .file_name()
+            .and_then(|name| name.to_str())
+            .unwrap_or(stem);
+        return Ok((name.to_string(), content));
+    }
+
+    let block = snippet::code_blocks(&content)
+        .into_iter()
+        .find(|block| !block.code.trim().is_empty())
+        .ok_or_else(|| SnippetVaultError::Parse {
+            path: path.to_path_buf(),
+            message: "no code block to share".to_string(),
+        })?;
+    let language = detect::block_language(&block.language, &block.code, &meta.language);
+    let extension = language::extension_for(&language.to_lowercase()).unwrap_or("txt");
+    Ok((format!("{}.{}", stem, extension), block.code))
+}
@@ -0,0 +1,182 @@
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::process::Command as ProcessCommand;
+use tempfile::TempDir;
+
+use crate::error::SnippetVaultError;
+use crate::logging;
+
+/// Environment variable the askpass helper reads the token from, so the token
+/// never appears in a command line or on disk.
+const TOKEN_ENV: &str = "SNIPPETS_VAULT_GIT_TOKEN";
+
+/// Askpass helper answering GitHub's username and password prompts with a token.
+const ASKPASS_SCRIPT: &str = r#"#!/bin/sh
+case "$1" in
+    Username*) echo "x-access-token" ;;
+    *) echo "$SNIPPETS_VAULT_GIT_TOKEN" ;;
+esac
+"#;
+
+/// File name of [`ASKPASS_SCRIPT`] inside its temporary directory.
+const ASKPASS_NAME: &str = "askpass";
+
+/// Clones `repo_url` into `dest`, or fast-forwards `dest` when it already exists.
+/// A failure is also saved as the last error.
+/// - `token`: GitHub token supplied to git through `GIT_ASKPASS`.
+pub fn github_sync(
+    repo_url: &str,
+    dest: &Path,
+    token: Option<&str>,
+) -> Result<(), SnippetVaultError> {
+    let result = if dest.exists() {
+        run_git(Some(dest), &["pull", "--ff-only"], token)
+    } else {
+        let dest = dest.to_string_lossy();
+        run_git(None, &["clone", repo_url, &dest], token)
+    };
+    result
+        .map(|_| ())
+        .inspect_err(|err| logging::save_last_error(&err.to_string()))
+}
+
+/// Commits every change in the repository at `dir` (if there are any) and pushes it.
+/// A failure is also saved as the last error.
+pub fn push_changes(dir: &Path) -> Result<(), SnippetVaultError> {
+    commit_and_push(dir).inspect_err(|err| logging::save_last_error(&err.to_string()))
+}
+
+fn commit_and_push(dir: &Path) -> Result<(), SnippetVaultError> {
+    run_git(Some(dir), &["add", "--all"], None)?;
+    let status = run_git(Some(dir), &["status", "--porcelain"], None)?;
+    if !status.trim().is_empty() {
+        run_git(
+            Some(dir),
+            &["commit", "--quiet", "-m", "Update snippets"],
+            None,
+        )?;
+    }
+    run_git(Some(dir), &["push", "--quiet"], None)?;
+    Ok(())
+}
+
+/// Whether `dir` is the root of a git working tree.
+pub fn is_repository(dir: &Path) -> bool {
+    dir.join(".git").exists()
+}
+
+/// Root of the git working tree containing `dir`, or `None` outside one.
+pub fn repository_root(dir: &Path) -> Option<PathBuf> {
+    let root = run_git(Some(dir), &["rev-parse", "--show-toplevel"], None).ok()?;
+    Some(PathBuf::from(root.trim())).filter(|root| !root.as_os_str().is_empty())
+}
+
+/// Paths of the files git tracks in the repository at `root`, relative to it.
+pub fn tracked_files(root: &Path) -> Result<Vec<String>, SnippetVaultError> {
+    Ok(run_git(Some(root), &["ls-files"], None)?
+        .lines()
+        .map(str::to_string)
+        .collect())
+}
+
+/// Name of the repository at `root`: the last component of its `origin` URL
+/// without `.git` (`git@github.com:me/tools.git` gives `tools`), or `None`
+/// without an `origin` remote.
+pub fn origin_name(root: &Path) -> Option<String> {
+    let url = run_git(Some(root), &["remote", "get-url", "origin"], None).ok()?;
+    let name = url
+        .trim()
+        .trim_end_matches('/')
+        .rsplit(['/', ':'])
+        .next()?
+        .trim_end_matches(".git");
+    (!name.is_empty()).then(|| name.to_string())
+}
+
+/// Runs git with `args` (inside `dir` when given) and returns its stdout.
+/// Stderr is captured and becomes the error message when git fails.
+fn run_git(
+    dir: Option<&Path>,
+    args: &[&str],
+    token: Option<&str>,
+) -> Result<String, SnippetVaultError> {
+    let mut command = ProcessCommand::new("git");
+    if let Some(dir) = dir {
+        command.arg("-C").arg(dir);
+    }
+    command.args(args).env("GIT_TERMINAL_PROMPT", "0");
+
+    // Dropping the directory removes the script once git is done.
+    let _askpass = match token {
+        Some(token) => {
+            let askpass = write_askpass()?;
+            command
+                .env("GIT_ASKPASS", askpass.path().join(ASKPASS_NAME))
+                .env(TOKEN_ENV, token);
+            Some(askpass)
+        }
+        None => None,
+    };
+
+    tracing::debug!(?args, dir = ?dir, "running git");
+    let output = command.output();
+    let output = output.map_err(|err| SnippetVaultError::Command(format!("git: {}", err)))?;
+
+    if output.status.success() {
+        Ok(String::from_utf8_lossy(&output.stdout).into_owned())
+    } else {
+        Err(SnippetVaultError::Command(format!(
+            "git {}: {}",
+            args.first().unwrap_or(&""),
+            String::from_utf8_lossy(&output.stderr).trim()
+        )))
+    }
+}
+
+/// Writes [`ASKPASS_SCRIPT`] as [`ASKPASS_NAME`] into a new private temporary
+/// directory, which only the current user can enter. The script is created
+/// executable in one step, so it is never readable or replaceable by others.
+#[cfg(unix)]
+fn write_askpass() -> Result<TempDir, SnippetVaultError> {
+    use std::io::Write;
+    use std::os::unix::fs::{OpenOptionsExt, PermissionsExt};
+
+    let dir = tempfile::Builder::new()
+        .prefix("snippets_vault_askpass")
+        .permissions(fs::Permissions::from_mode(0o700))
+        .tempdir()?;
+    let mut script = fs::OpenOptions::new()
+        .write(true)
+        .create_new(true)
+        .mode(0o700)
+        .open(dir.path().join(ASKPASS_NAME))?;
+    script.write_all(ASKPASS_SCRIPT.as_bytes())?;
+    Ok(dir)
+}
+
+#[cfg(not(unix))]
+fn write_askpass() -> Result<TempDir, SnippetVaultError> {
+    Err(SnippetVaultError::Command(
+        "token authentication is only supported on Unix".to_string(),
+    ))
+}
+
+#[cfg(all(test, unix))]
+mod tests {
+    use super::*;
+    use std::os::unix::fs::PermissionsExt;
+
+    #[test]
+    fn askpass_script_is_private_and_removed_on_drop() {
+        let askpass = write_askpass().unwrap();
+        let script = askpass.path().join(ASKPASS_NAME);
+        let mode = |path: &Path| fs::metadata(path).unwrap().permissions().mode() & 0o777;
+        assert_eq!(mode(askpass.path()), 0o700);
+        assert_eq!(mode(&script), 0o700);
+        assert_eq!(fs::read_to_string(&script).unwrap(), ASKPASS_SCRIPT);
+
+        let dir = askpass.path().to_path_buf();
+        drop(askpass);
+        assert!(!dir.exists());
+    }
+}
@@ -0,0 +1,99 @@
+use std::collections::BTreeMap;
+use std::fs;
+use std::path::Path;
+
+use crate::error::SnippetVaultError;
+use crate::snippet::Frontmatter;
+use crate::vault::scan_snippets;
+
+/// Fill colours assigned to languages; a language always gets the same one.
+const PALETTE: &[&str] = &[
+    "#8dd3c7", "#ffffb3", "#bebada", "#fb8072", "#80b1d3", "#fdb462", "#b3de69", "#fccde5",
+    "#d9d9d9", "#bc80bd", "#ccebc5", "#ffed6f",
+];
+
+/// Builds a DOT graph of the `links` declared in snippet frontmatter: one node
+/// per snippet labelled by title and coloured by language, one edge per link
+/// labelled by its relation. Links to files outside the vault become dashed nodes.
+pub fn generate_dependency_graph(snippet_dir: &Path) -> Result<String, SnippetVaultError> {
+    let snippets: BTreeMap<String, Frontmatter> = scan_snippets(snippet_dir, |path| {
+        let content = fs::read_to_string(path).ok()?;
+        Frontmatter::load(path, &content).ok()
+    })?
+    .into_iter()
+    .filter_map(|(path, frontmatter)| Some((node_id(snippet_dir, &path), frontmatter?)))
+    .collect();
+
+    let mut dot = String::from("digraph snippets {\n");
+    dot.push_str("    rankdir=LR;\n    node [shape=box, style=\"rounded,filled\"];\n");
+
+    for (id, frontmatter) in &snippets {
+        dot.push_str(&format!(
+            "    \"{}\" [label=\"{}\\n{}\", fillcolor=\"{}\"];\n",
+            escape(id),
+            escape(&frontmatter.title),
+            escape(&frontmatter.language),
+            language_color(&frontmatter.language)
+        ));
+    }
+
+    for (id, frontmatter) in &snippets {
+        for link in &frontmatter.links {
+            let target = resolve_target(&snippets, &link.target);
+            if !snippets.contains_key(&target) {
+                dot.push_str(&format!(
+                    "    \"{}\" [style=dashed, fillcolor=white];\n",
+                    escape(&target)
+                ));
+            }
+            dot.push_str(&format!(
+                "    \"{}\" -> \"{}\" [label=\"{}\"];\n",
+                escape(id),
+                escape(&target),
+                escape(&link.relation)
+            ));
+        }
+    }
+
+    dot.push_str("}\n");
+    Ok(dot)
+}
+
+/// Identifier of a snippet in the graph: its path relative to the vault.
+fn node_id(snippet_dir: &Path, path: &Path) -> String {
+    path.strip_prefix(snippet_dir)
+        .unwrap_or(path)
+        .to_string_lossy()
+        .into_owned()
+}
+
+/// Matches a link target against the vault, accepting either a vault-relative
+/// path or a bare file name (with or without `.md`).
+fn resolve_target(snippets: &BTreeMap<String, Frontmatter>, target: &str) -> String {
+    let with_extension = if target.ends_with(".md") {
+        target.to_string()
+    } else {
+        format!("{}.md", target)
+    };
+    snippets
+        .keys()
+        .find(|id| {
+            *id == &with_extension
+                || Path::new(id)
+                    .file_name()
+                    .is_some_and(|name| name == with_extension.as_str())
+        })
+        .cloned()
+        .unwrap_or_else(|| target.to_string())
+}
+
+fn language_color(language: &str) -> &'static str {
+    let hash = language.bytes().fold(0usize, |hash, byte| {
+        hash.wrapping_mul(31).wrapping_add(byte as usize)
+    });
+    PALETTE[hash % PALETTE.len()]
+}
+
+fn escape(text: &str) -> String {
+    text.replace('\\', "\\\\").replace('"', "\\\"")
+}
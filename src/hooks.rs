@@ -0,0 +1,173 @@
+use std::io;
+use std::path::Path;
+use std::process::{Command as ProcessCommand, Stdio};
+
+use crate::config::Config;
+use crate::error::SnippetVaultError;
+use crate::logging;
+use crate::platform;
+use crate::snippet;
+use crate::style::Styled;
+
+/// Hooks that can be configured under `[hooks]`.
+pub const HOOK_NAMES: &[&str] = &["pre_create", "post_create", "pre_delete", "post_delete"];
+
+/// Environment variable holding the path of the snippet a hook runs for.
+pub const PATH_VAR: &str = "SV_SNIPPET_PATH";
+/// Environment variable holding the snippet's language.
+pub const LANGUAGE_VAR: &str = "SV_LANGUAGE";
+/// Environment variable holding the snippet's tags, separated by spaces.
+pub const TAGS_VAR: &str = "SV_TAGS";
+/// Environment variable holding the timestamp of the snippet's filename.
+pub const TIMESTAMP_VAR: &str = "SV_TIMESTAMP";
+
+/// The environment a hook gets for one snippet.
+#[derive(Debug, Default)]
+pub struct SnippetEnv {
+    path: String,
+    language: String,
+    tags: String,
+    timestamp: String,
+}
+
+impl SnippetEnv {
+    /// The environment of a snippet about to be created, which has no path yet.
+    pub fn new(language: &str, tags: &[String], timestamp: &str) -> Self {
+        SnippetEnv {
+            path: String::new(),
+            language: language.to_string(),
+            tags: tags.join(" "),
+            timestamp: timestamp.to_string(),
+        }
+    }
+
+    /// The environment of the existing snippet at `path`, read from its
+    /// metadata. Read it before a delete: the `post_delete` hook needs it too.
+    pub fn read(path: &Path) -> Self {
+        let (language, tags) = snippet::SnippetMeta::read(path)
+            .map(|meta| (meta.language, meta.tags.join(" ")))
+            .unwrap_or_default();
+        SnippetEnv {
+            path: path.display().to_string(),
+            language,
+            tags,
+            timestamp: snippet::filename_timestamp(path).unwrap_or_default(),
+        }
+    }
+
+    /// Adds the path of the snippet once it exists.
+    pub fn with_path(mut self, path: &Path) -> Self {
+        self.path = path.display().to_string();
+        self
+    }
+
+    /// The variables as [`run_hook`] takes them; the path only once it is known.
+    pub fn vars(&self) -> Vec<(&str, &str)> {
+        let mut vars = vec![
+            (LANGUAGE_VAR, self.language.as_str()),
+            (TAGS_VAR, self.tags.as_str()),
+            (TIMESTAMP_VAR, self.timestamp.as_str()),
+        ];
+        if !self.path.is_empty() {
+            vars.push((PATH_VAR, self.path.as_str()));
+        }
+        vars
+    }
+}
+
+/// Runs the shell command configured for `hook_name` under `[hooks]` with
+/// `env` added to its environment. Hooks that are not configured, and every
+/// hook under `--skip_hooks`, are skipped. Fails when the command cannot start
+/// or exits non-zero, which makes a `pre_` hook abort its operation. The
+/// hook's output goes to stderr, so it never mixes into `--batch` responses.
+pub fn run_hook(
+    hook_name: &str,
+    env: &[(&str, &str)],
+    config: &Config,
+) -> Result<(), SnippetVaultError> {
+    if config.skip_hooks {
+        return Ok(());
+    }
+    let Some(command) = config
+        .hooks
+        .get(hook_name)
+        .filter(|command| !command.trim().is_empty())
+    else {
+        return Ok(());
+    };
+
+    let (shell, args) = platform::shell_command();
+    let status = logging::run(
+        ProcessCommand::new(shell)
+            .args(args)
+            .arg(command)
+            .envs(env.iter().copied())
+            .stdout(Stdio::from(io::stderr())),
+    )
+    .map_err(|err| SnippetVaultError::Command(format!("{} hook: {}", hook_name, err)))?;
+    if !status.success() {
+        return Err(SnippetVaultError::Command(format!(
+            "{} hook exited with {}",
+            hook_name, status
+        )));
+    }
+    Ok(())
+}
+
+/// Runs a `post_` hook, which can no longer stop its operation: a failure is
+/// only reported.
+pub fn run_post_hook(hook_name: &str, env: &[(&str, &str)], config: &Config) {
+    if let Err(err) = run_hook(hook_name, env, config) {
+        println!("{} {}", "⚠".warning(), err);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn config_with(hook_name: &str, command: &str) -> Config {
+        let mut config = Config::default();
+        config
+            .hooks
+            .insert(hook_name.to_string(), command.to_string());
+        config
+    }
+
+    #[test]
+    fn unconfigured_and_skipped_hooks_do_not_run() {
+        assert!(run_hook("pre_create", &[], &Config::default()).is_ok());
+
+        let mut config = config_with("pre_create", "exit 1");
+        config.skip_hooks = true;
+        assert!(run_hook("pre_create", &[], &config).is_ok());
+    }
+
+    #[test]
+    fn a_missing_script_fails() {
+        let config = config_with("pre_delete", "/nonexistent/sv-hook.sh");
+        let err = run_hook("pre_delete", &[], &config).unwrap_err();
+        assert!(err.to_string().contains("pre_delete hook"), "{}", err);
+    }
+
+    #[test]
+    fn hooks_see_the_snippet_and_a_failure_aborts() {
+        let dir = tempfile::tempdir().unwrap();
+        let seen = dir.path().join("seen");
+        let config = config_with(
+            "pre_create",
+            &format!(
+                "printf '%s %s' \"$SV_LANGUAGE\" \"$SV_TAGS\" > '{}'",
+                seen.display()
+            ),
+        );
+        let tags = ["git".to_string(), "undo".to_string()];
+        let env = SnippetEnv::new("bash", &tags, "2024-05-17-093000");
+        run_hook("pre_create", &env.vars(), &config).unwrap();
+        assert_eq!(std::fs::read_to_string(&seen).unwrap(), "bash git undo");
+
+        let config = config_with("pre_create", "exit 3");
+        let err = run_hook("pre_create", &env.vars(), &config).unwrap_err();
+        assert!(err.to_string().contains("exited with"), "{}", err);
+    }
+}
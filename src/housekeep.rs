@@ -0,0 +1,194 @@
+use chrono::{NaiveDateTime, TimeDelta};
+use rusqlite::Connection;
+use serde::Deserialize;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use crate::error::SnippetVaultError;
+use crate::index;
+use crate::snippet;
+use crate::vault;
+
+/// Index age from which `--housekeep` rebuilds it, when the config sets none.
+pub const DEFAULT_INDEX_MAX_AGE_DAYS: u64 = 7;
+
+/// Backups `--housekeep` keeps, when the config sets no number.
+pub const DEFAULT_KEEP_BACKUPS: usize = 10;
+
+/// Days a file stays in the trash before `--housekeep` deletes it, when the
+/// config sets none.
+pub const DEFAULT_TRASH_MAX_AGE_DAYS: u64 = 30;
+
+/// Which maintenance `--housekeep` performs, from the `[housekeeping]` config
+/// section. Every task is on unless turned off.
+#[derive(Debug, Clone, Deserialize)]
+#[serde(default)]
+pub struct HousekeepingConfig {
+    /// Delete zero-byte snippet files.
+    pub delete_empty: bool,
+    /// Rebuild and vacuum the title index when it is stale.
+    pub rebuild_index: bool,
+    /// Days after which a built index counts as stale.
+    pub index_max_age_days: u64,
+    /// Delete all but the newest `keep_backups` vault backups.
+    pub rotate_backups: bool,
+    pub keep_backups: usize,
+    /// Delete files that have been in the `.trash` for `trash_max_age_days`.
+    pub empty_trash: bool,
+    pub trash_max_age_days: u64,
+}
+
+impl Default for HousekeepingConfig {
+    fn default() -> Self {
+        HousekeepingConfig {
+            delete_empty: true,
+            rebuild_index: true,
+            index_max_age_days: DEFAULT_INDEX_MAX_AGE_DAYS,
+            rotate_backups: true,
+            keep_backups: DEFAULT_KEEP_BACKUPS,
+            empty_trash: true,
+            trash_max_age_days: DEFAULT_TRASH_MAX_AGE_DAYS,
+        }
+    }
+}
+
+/// Zero-byte files among the snippets under `snippet_dir`.
+/// - `extensions`: File extensions that count as snippets.
+pub fn empty_snippets(
+    snippet_dir: &Path,
+    extensions: &[String],
+) -> Result<Vec<PathBuf>, SnippetVaultError> {
+    Ok(vault::vault_files(snippet_dir, extensions)?
+        .into_iter()
+        .filter(|path| fs::metadata(path).is_ok_and(|metadata| metadata.len() == 0))
+        .collect())
+}
+
+/// Why the index in `db` needs rebuilding, or `None` when it is current: it
+/// was never built, was built more than `max_age_days` before `now`, or holds
+/// a different number of snippets than the vault.
+/// - `extensions`: File extensions that count as snippets.
+pub fn index_staleness(
+    db: &Connection,
+    snippet_dir: &Path,
+    extensions: &[String],
+    max_age_days: u64,
+    now: NaiveDateTime,
+) -> Result<Option<String>, SnippetVaultError> {
+    let Some(built_at) = index::built_at(db)? else {
+        return Ok(Some("never built".to_string()));
+    };
+    let max_age = TimeDelta::try_days(max_age_days as i64).unwrap_or(TimeDelta::MAX);
+    if now - built_at > max_age {
+        return Ok(Some(format!("built {}", built_at.format("%Y-%m-%d"))));
+    }
+    let indexed = index::indexed_count(db)?;
+    let files = vault::vault_files(snippet_dir, extensions)?.len();
+    if indexed != files {
+        return Ok(Some(format!("{} indexed, {} in the vault", indexed, files)));
+    }
+    Ok(None)
+}
+
+/// Backups in `backup_dir` beyond the newest `keep`, oldest first.
+pub fn old_backups(backup_dir: &Path, keep: usize) -> Result<Vec<PathBuf>, SnippetVaultError> {
+    if !backup_dir.is_dir() {
+        return Ok(Vec::new());
+    }
+    let mut backups: Vec<(Option<NaiveDateTime>, PathBuf)> = fs::read_dir(backup_dir)?
+        .filter_map(|entry| entry.ok().map(|entry| entry.path()))
+        .filter(|path| {
+            path.is_file()
+                && path.file_name().is_some_and(|name| {
+                    let name = name.to_string_lossy();
+                    name.starts_with("snippets_vault_") && name.ends_with(".zip")
+                })
+        })
+        .map(|path| (snippet::modified_time(&path), path))
+        .collect();
+    backups.sort();
+    let excess = backups.len().saturating_sub(keep);
+    Ok(backups
+        .into_iter()
+        .take(excess)
+        .map(|(_, path)| path)
+        .collect())
+}
+
+/// Files in the trash of the vault at `snippet_dir` that were trashed more
+/// than `max_age_days` before `now` (see [`vault::move_to_trash`]), sorted by
+/// path.
+pub fn old_trash(
+    snippet_dir: &Path,
+    max_age_days: u64,
+    now: NaiveDateTime,
+) -> Result<Vec<PathBuf>, SnippetVaultError> {
+    let max_age = TimeDelta::try_days(max_age_days as i64).unwrap_or(TimeDelta::MAX);
+    let mut old = Vec::new();
+    let mut pending = vec![snippet_dir.join(vault::TRASH_DIR)];
+    while let Some(dir) = pending.pop() {
+        if !dir.is_dir() {
+            continue;
+        }
+        for entry in fs::read_dir(&dir)? {
+            let path = entry?.path();
+            if path.is_dir() {
+                pending.push(path);
+            } else if snippet::modified_time(&path).is_some_and(|trashed| now - trashed > max_age) {
+                old.push(path);
+            }
+        }
+    }
+    old.sort();
+    Ok(old)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::Local;
+    use std::time::{Duration, SystemTime};
+
+    fn age(path: &Path, days: u64) {
+        fs::File::options()
+            .write(true)
+            .open(path)
+            .unwrap()
+            .set_modified(SystemTime::now() - Duration::from_secs(days * 24 * 60 * 60))
+            .unwrap();
+    }
+
+    #[test]
+    fn only_files_trashed_long_ago_are_old() {
+        crate::testing::isolate_user_dirs();
+        let vault = tempfile::tempdir().unwrap();
+        let trash = vault.path().join(vault::TRASH_DIR);
+        fs::create_dir_all(trash.join("git")).unwrap();
+        for (path, days) in [
+            (trash.join("stale.md"), 40),
+            (trash.join("git/recent.md"), 5),
+            (trash.join("git/stale.md"), 31),
+            (vault.path().join("old.md"), 400),
+        ] {
+            fs::write(&path, "x").unwrap();
+            age(&path, days);
+        }
+        // Trashing restarts the clock, however old the file is.
+        vault::move_to_trash(
+            vault.path(),
+            &vault.path().join("old.md"),
+            &vault::Executor::new(false),
+        )
+        .unwrap();
+
+        let now = Local::now().naive_local();
+        assert_eq!(
+            old_trash(vault.path(), 30, now).unwrap(),
+            [trash.join("git/stale.md"), trash.join("stale.md")]
+        );
+        assert_eq!(old_trash(vault.path(), 3, now).unwrap().len(), 3);
+        assert!(old_trash(tempfile::tempdir().unwrap().path(), 30, now)
+            .unwrap()
+            .is_empty());
+    }
+}
@@ -0,0 +1,383 @@
+use chrono::{Local, NaiveDateTime};
+use serde::Deserialize;
+use std::fs;
+use std::path::{Component, Path, PathBuf};
+
+use crate::config::Config;
+use crate::error::SnippetVaultError;
+use crate::export::CSV_TIME_FORMAT;
+use crate::hooks;
+use crate::index;
+use crate::snippet::{self, split_frontmatter, Frontmatter};
+use crate::style::Styled;
+use crate::vault::{self, Executor, Mutation};
+
+/// Which rows [`import_csv`] acts on, by whether their `path` already exists.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ImportMode {
+    /// Only create new snippets; rows naming an existing file are errors.
+    Create,
+    /// Only update existing snippets; rows naming no existing file are errors.
+    Update,
+    /// Create or update, whichever applies.
+    Upsert,
+}
+
+impl ImportMode {
+    /// Names accepted by `--mode`.
+    pub const NAMES: &'static [&'static str] = &["create", "update", "upsert"];
+
+    /// Parses one of [`ImportMode::NAMES`].
+    pub fn from_name(name: &str) -> Option<Self> {
+        match name {
+            "create" => Some(ImportMode::Create),
+            "update" => Some(ImportMode::Update),
+            "upsert" => Some(ImportMode::Upsert),
+            _ => None,
+        }
+    }
+}
+
+/// One CSV row; columns the import does not use (sizes, counts) are ignored.
+#[derive(Debug, Default, Deserialize)]
+#[serde(default)]
+struct CsvRow {
+    path: String,
+    title: String,
+    language: String,
+    tags: String,
+    created_at: String,
+    rating: String,
+    status: String,
+    content: Option<String>,
+}
+
+/// What happened to one row.
+enum Imported {
+    Created(PathBuf),
+    Updated(PathBuf),
+    Unchanged,
+}
+
+/// Creates or updates snippets from a CSV in the `--snippet_export_csv` layout
+/// and returns `(created, updated)`. Rows are matched to files by their
+/// vault-relative `path`; a row without one always creates a snippet with a
+/// generated filename. The frontmatter is rebuilt from `title`, `language`,
+/// `tags` (comma-separated), `created_at`, `rating`, and `status`; an optional
+/// `content` column becomes the code of new snippets. Invalid rows are reported
+/// and skipped without stopping the import.
+/// New snippets run the `pre_create` and `post_create` hooks; a failing
+/// `pre_create` hook skips the row.
+/// - `config`: Hooks, and the strftime format of generated filenames.
+pub fn import_csv(
+    csv_path: &Path,
+    snippet_dir: &Path,
+    mode: ImportMode,
+    config: &Config,
+    executor: &Executor,
+) -> Result<(usize, usize), SnippetVaultError> {
+    let mut reader = csv::Reader::from_path(csv_path)?;
+    let _lock = executor.lock()?;
+    let (mut created, mut updated) = (0, 0);
+
+    for (index, row) in reader.deserialize::<CsvRow>().enumerate() {
+        let imported = row
+            .map_err(SnippetVaultError::from)
+            .and_then(|row| import_row(&row, csv_path, snippet_dir, mode, config, executor));
+        match imported {
+            Ok(Imported::Created(path)) => {
+                created += 1;
+                if !executor.is_dry_run() {
+                    index::log_access(&path, index::Access::Created);
+                    let hook_env = hooks::SnippetEnv::read(&path);
+                    hooks::run_post_hook("post_create", &hook_env.vars(), config);
+                }
+            }
+            Ok(Imported::Updated(path)) => {
+                updated += 1;
+                if !executor.is_dry_run() {
+                    index::reindex_snippet(&path, &path);
+                }
+            }
+            Ok(Imported::Unchanged) => {}
+            Err(err) => println!("{} row {}: {}", "✘".error(), index + 1, err),
+        }
+    }
+    Ok((created, updated))
+}
+
+fn import_row(
+    row: &CsvRow,
+    csv_path: &Path,
+    snippet_dir: &Path,
+    mode: ImportMode,
+    config: &Config,
+    executor: &Executor,
+) -> Result<Imported, SnippetVaultError> {
+    let invalid = |message: String| SnippetVaultError::Parse {
+        path: csv_path.to_path_buf(),
+        message,
+    };
+    let created = match row.created_at.trim() {
+        "" => None,
+        text => Some(
+            NaiveDateTime::parse_from_str(text, CSV_TIME_FORMAT)
+                .ok()
+                .or_else(|| snippet::parse_timestamp(text))
+                .ok_or_else(|| invalid(format!("invalid created_at '{}'", text)))?,
+        ),
+    };
+    let target = match row.path.trim() {
+        "" => None,
+        relative => {
+            let relative = Path::new(relative);
+            if !relative
+                .components()
+                .all(|component| matches!(component, Component::Normal(_)))
+            {
+                return Err(invalid(format!(
+                    "path '{}' must be relative to the vault",
+                    relative.display()
+                )));
+            }
+            Some(snippet_dir.join(relative))
+        }
+    };
+
+    match target {
+        Some(path) if path.exists() => {
+            if mode == ImportMode::Create {
+                return Err(invalid(format!("{} already exists", path.display())));
+            }
+            update_snippet(&path, row, created, executor)
+        }
+        Some(path) if mode == ImportMode::Update => {
+            Err(invalid(format!("{} does not exist", path.display())))
+        }
+        None if mode == ImportMode::Update => Err(invalid("updating needs a path".to_string())),
+        target => {
+            if row.language.trim().is_empty() {
+                return Err(invalid("a new snippet needs a language".to_string()));
+            }
+            create_snippet(snippet_dir, target, row, created, config, executor)
+        }
+    }
+}
+
+/// Overwrites `frontmatter` with the row's metadata. Empty `title` and
+/// `language` keep the current values; empty `rating` and `status` remove them.
+fn apply_row(frontmatter: &mut Frontmatter, row: &CsvRow, created: Option<NaiveDateTime>) {
+    if !row.title.trim().is_empty() {
+        frontmatter.title = row.title.trim().to_string();
+    }
+    if !row.language.trim().is_empty() {
+        frontmatter.language = row.language.trim().to_string();
+    }
+    frontmatter.tags = row
+        .tags
+        .split(',')
+        .map(str::trim)
+        .filter(|tag| !tag.is_empty())
+        .map(str::to_string)
+        .collect();
+    if created.is_some() {
+        frontmatter.created = created;
+    }
+    for (key, value) in [("rating", &row.rating), ("status", &row.status)] {
+        match value.trim() {
+            "" => frontmatter.extra.remove(key),
+            value => frontmatter
+                .extra
+                .insert(key.to_string(), snippet::field_from_str(value)),
+        };
+    }
+}
+
+fn update_snippet(
+    path: &Path,
+    row: &CsvRow,
+    created: Option<NaiveDateTime>,
+    executor: &Executor,
+) -> Result<Imported, SnippetVaultError> {
+    if !snippet::is_markdown(path) {
+        return Err(SnippetVaultError::Parse {
+            path: path.to_path_buf(),
+            message: "only markdown snippets have frontmatter to update".to_string(),
+        });
+    }
+    let content = fs::read_to_string(path)?;
+    let current = Frontmatter::load(path, &content)?;
+    // The export wrote the created time listings show, which may come from the
+    // filename or the file's mtime; writing it back unchanged is no update.
+    let effective = current
+        .created
+        .or_else(|| snippet::parse_timestamp_from_filename(path))
+        .or_else(|| snippet::modified_time(path));
+    let same_second =
+        |a: NaiveDateTime, b: NaiveDateTime| a.and_utc().timestamp() == b.and_utc().timestamp();
+    let created =
+        created.filter(|created| !effective.is_some_and(|time| same_second(time, *created)));
+    let mut frontmatter = current.clone();
+    apply_row(&mut frontmatter, row, created);
+    if frontmatter == current {
+        return Ok(Imported::Unchanged);
+    }
+
+    executor.apply(Mutation::Write {
+        path: path.to_path_buf(),
+        contents: format!("{}{}", frontmatter.render(), split_frontmatter(&content).1),
+    })?;
+    Ok(Imported::Updated(path.to_path_buf()))
+}
+
+fn create_snippet(
+    snippet_dir: &Path,
+    target: Option<PathBuf>,
+    row: &CsvRow,
+    created: Option<NaiveDateTime>,
+    config: &Config,
+    executor: &Executor,
+) -> Result<Imported, SnippetVaultError> {
+    let language = row.language.trim();
+    let time = created.unwrap_or_else(|| Local::now().naive_local());
+    let timestamp = render_timestamp(&time, config.timestamp_format());
+    let mut frontmatter = Frontmatter::default();
+    apply_row(&mut frontmatter, row, Some(time));
+    if frontmatter.title.is_empty() {
+        frontmatter.title = format!("{} - Snippet", language);
+    }
+    let code = row.content.as_deref().unwrap_or_default();
+
+    let path = target.unwrap_or_else(|| {
+        vault::available_path(snippet_dir, &timestamp, |timestamp| {
+            snippet::snippet_filename(timestamp, language, &frontmatter.tags)
+        })
+    });
+    let contents = if snippet::is_markdown(&path) {
+        render_snippet(&frontmatter, &timestamp, code)
+    } else {
+        code.to_string()
+    };
+
+    if !executor.is_dry_run() {
+        let hook_env = hooks::SnippetEnv::new(language, &frontmatter.tags, &timestamp);
+        hooks::run_hook("pre_create", &hook_env.vars(), config)?;
+    }
+    if let Some(parent) = path.parent().filter(|parent| !parent.exists()) {
+        executor.apply(Mutation::CreateDir(parent.to_path_buf()))?;
+    }
+    executor.apply(Mutation::Write {
+        path: path.clone(),
+        contents,
+    })?;
+    Ok(Imported::Created(path))
+}
+
+/// `time` as a filename timestamp in `format`, or the default format when
+/// `format` cannot be used in filenames.
+pub fn render_timestamp(time: &NaiveDateTime, format: &str) -> String {
+    snippet::render_timestamp(time, format)
+        .unwrap_or_else(|_| time.format(snippet::TIMESTAMP_FORMAT).to_string())
+}
+
+/// A new markdown snippet from the usual template, with `frontmatter` in place
+/// of the generated one and `code` in its code fence.
+pub fn render_snippet(frontmatter: &Frontmatter, timestamp: &str, code: &str) -> String {
+    let language = &frontmatter.language;
+    let template = snippet::render_new_snippet(language, &frontmatter.tags, timestamp, code);
+    let body = split_frontmatter(&template).1.replacen(
+        &format!("# Title: {} - Snippet", language),
+        &format!("# Title: {}", frontmatter.title),
+        1,
+    );
+    format!("{}{}", frontmatter.render(), body)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::export;
+
+    #[test]
+    fn reimporting_an_unedited_export_changes_nothing() {
+        crate::testing::isolate_user_dirs();
+        let vault = tempfile::tempdir().unwrap();
+        // No `created` in the frontmatter: the export takes it from the filename.
+        fs::write(
+            vault.path().join("snippet_2024-05-17-093000_bash_git.md"),
+            "+++\ntitle = \"Undo\"\nlanguage = \"bash\"\ntags = [\"git\"]\nrating = 4\n+++\n\n```bash\ngit reset\n```\n",
+        )
+        .unwrap();
+        fs::write(
+            vault.path().join("notes.md"),
+            "+++\ntitle = \"Notes\"\nlanguage = \"text\"\ntags = []\n+++\n\nplain\n",
+        )
+        .unwrap();
+        let out = tempfile::tempdir().unwrap();
+        let csv = out.path().join("vault.csv");
+        let extensions = vec!["md".to_string()];
+        export::export_csv(
+            vault.path(),
+            &csv,
+            false,
+            &extensions,
+            false,
+            &Executor::new(false),
+        )
+        .unwrap();
+
+        let config = Config::default();
+        let dry_run = Executor::new(true);
+        let mut reader = csv::Reader::from_path(&csv).unwrap();
+        for row in reader.deserialize::<CsvRow>() {
+            let mut row = row.unwrap();
+            let imported = import_row(
+                &row,
+                &csv,
+                vault.path(),
+                ImportMode::Update,
+                &config,
+                &dry_run,
+            )
+            .unwrap();
+            assert!(matches!(imported, Imported::Unchanged), "{}", row.path);
+
+            row.created_at = "2020-01-01T00:00:00".to_string();
+            let imported = import_row(
+                &row,
+                &csv,
+                vault.path(),
+                ImportMode::Update,
+                &config,
+                &dry_run,
+            )
+            .unwrap();
+            assert!(matches!(imported, Imported::Updated(_)), "{}", row.path);
+        }
+    }
+
+    #[test]
+    fn a_failing_pre_create_hook_skips_the_row() {
+        let vault = tempfile::tempdir().unwrap();
+        let mut config = Config::default();
+        config
+            .hooks
+            .insert("pre_create".to_string(), "exit 1".to_string());
+        let row = CsvRow {
+            language: "bash".to_string(),
+            title: "Blocked".to_string(),
+            ..CsvRow::default()
+        };
+
+        let imported = import_row(
+            &row,
+            Path::new("rows.csv"),
+            vault.path(),
+            ImportMode::Create,
+            &config,
+            &Executor::new(false),
+        );
+        assert!(imported.is_err());
+        assert_eq!(fs::read_dir(vault.path()).unwrap().count(), 0);
+    }
+}
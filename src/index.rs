@@ -0,0 +1,269 @@
+use chrono::{Local, NaiveDateTime};
+use rusqlite::{params, Connection, OptionalExtension};
+use std::collections::HashMap;
+use std::fmt;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use crate::error::SnippetVaultError;
+use crate::lock::LOCK_TIMEOUT;
+use crate::snippet::SnippetMeta;
+use crate::vault;
+
+/// Format of timestamps stored in the index.
+const INDEX_TIMESTAMP_FORMAT: &str = "%Y-%m-%d %H:%M:%S";
+
+/// What happened to a snippet, as recorded in the access log.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Access {
+    Created,
+    Opened,
+    /// Code copied, run, or printed with `--get`.
+    Used,
+}
+
+impl fmt::Display for Access {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Access::Created => write!(f, "created"),
+            Access::Opened => write!(f, "opened"),
+            Access::Used => write!(f, "used"),
+        }
+    }
+}
+
+/// Location of the SQLite index.
+pub fn index_path() -> PathBuf {
+    dirs::data_dir()
+        .unwrap_or_else(std::env::temp_dir)
+        .join("snippets_vault")
+        .join("index.sqlite")
+}
+
+/// Opens (creating if needed) the index at `path` and makes sure its schema exists.
+pub fn open(path: &Path) -> Result<Connection, SnippetVaultError> {
+    if let Some(parent) = path.parent() {
+        fs::create_dir_all(parent)?;
+    }
+    let db = Connection::open(path)?;
+    // Another process may be writing; wait for it instead of failing with SQLITE_BUSY.
+    db.busy_timeout(LOCK_TIMEOUT)?;
+    db.execute_batch(
+        "CREATE TABLE IF NOT EXISTS access_log (
+            path   TEXT NOT NULL,
+            action TEXT NOT NULL,
+            at     TEXT NOT NULL
+        );
+        CREATE INDEX IF NOT EXISTS access_log_at ON access_log (at);
+        CREATE TABLE IF NOT EXISTS snippets (
+            path     TEXT PRIMARY KEY,
+            title    TEXT NOT NULL DEFAULT '',
+            language TEXT NOT NULL DEFAULT '',
+            tags     TEXT NOT NULL DEFAULT ''
+        );
+        CREATE VIRTUAL TABLE IF NOT EXISTS snippet_fts USING fts5(title, tags, path UNINDEXED);
+        CREATE TABLE IF NOT EXISTS index_meta (
+            key   TEXT PRIMARY KEY,
+            value TEXT NOT NULL
+        );",
+    )?;
+    Ok(db)
+}
+
+/// Appends an entry for `path` to the access log.
+pub fn record_access(
+    db: &Connection,
+    path: &Path,
+    action: Access,
+) -> Result<(), SnippetVaultError> {
+    let at = Local::now()
+        .naive_local()
+        .format(INDEX_TIMESTAMP_FORMAT)
+        .to_string();
+    db.execute(
+        "INSERT INTO access_log (path, action, at) VALUES (?1, ?2, ?3)",
+        params![path.to_string_lossy(), action.to_string(), at],
+    )?;
+    Ok(())
+}
+
+/// Records an access in the default index. The log is best effort: a failure is
+/// only reported in the debug log and never fails the command.
+pub fn log_access(path: &Path, action: Access) {
+    if let Err(err) = open(&index_path()).and_then(|db| record_access(&db, path, action)) {
+        tracing::warn!(path = %path.display(), %err, "cannot update the access log");
+    }
+}
+
+/// Returns the distinct paths with a logged `action` at or after `since`.
+pub fn accessed_since(
+    db: &Connection,
+    action: Access,
+    since: NaiveDateTime,
+) -> Result<Vec<PathBuf>, SnippetVaultError> {
+    let mut statement =
+        db.prepare("SELECT DISTINCT path FROM access_log WHERE action = ?1 AND at >= ?2")?;
+    let paths = statement
+        .query_map(
+            params![
+                action.to_string(),
+                since.format(INDEX_TIMESTAMP_FORMAT).to_string()
+            ],
+            |row| row.get::<_, String>(0),
+        )?
+        .collect::<Result<Vec<_>, _>>()?;
+    Ok(paths.into_iter().map(PathBuf::from).collect())
+}
+
+/// How many times `action` was logged for `path`.
+pub fn access_count(
+    db: &Connection,
+    path: &Path,
+    action: Access,
+) -> Result<usize, SnippetVaultError> {
+    let count: i64 = db.query_row(
+        "SELECT COUNT(*) FROM access_log WHERE path = ?1 AND action = ?2",
+        params![path.to_string_lossy(), action.to_string()],
+        |row| row.get(0),
+    )?;
+    Ok(count as usize)
+}
+
+/// How many times `action` was logged, per path.
+pub fn access_counts(
+    db: &Connection,
+    action: Access,
+) -> Result<HashMap<PathBuf, usize>, SnippetVaultError> {
+    let mut statement =
+        db.prepare("SELECT path, COUNT(*) FROM access_log WHERE action = ?1 GROUP BY path")?;
+    let counts = statement
+        .query_map(params![action.to_string()], |row| {
+            Ok((
+                PathBuf::from(row.get::<_, String>(0)?),
+                row.get::<_, i64>(1)? as usize,
+            ))
+        })?
+        .collect::<Result<HashMap<_, _>, _>>()?;
+    Ok(counts)
+}
+
+/// Replaces the indexed snippets (and their full-text entries) with the
+/// current contents of `snippet_dir`, returning how many were indexed.
+/// - `extensions`: File extensions that count as snippets.
+/// - `use_cache`: Serve unchanged snippets from the metadata cache.
+pub fn build_title_index(
+    snippet_dir: &Path,
+    db: &Connection,
+    extensions: &[String],
+    use_cache: bool,
+) -> Result<usize, SnippetVaultError> {
+    let snippets = vault::load_snippets(snippet_dir, extensions, use_cache)?;
+
+    let transaction = db.unchecked_transaction()?;
+    transaction.execute("DELETE FROM snippets", [])?;
+    transaction.execute("DELETE FROM snippet_fts", [])?;
+    for snippet in &snippets {
+        upsert_snippet_row(&transaction, snippet)?;
+    }
+    transaction.execute(
+        "INSERT OR REPLACE INTO index_meta (key, value) VALUES ('built_at', ?1)",
+        params![Local::now().format(INDEX_TIMESTAMP_FORMAT).to_string()],
+    )?;
+    transaction.commit()?;
+
+    tracing::info!(count = snippets.len(), "title index rebuilt");
+    Ok(snippets.len())
+}
+
+/// When [`build_title_index`] last ran, or `None` if it never has.
+pub fn built_at(db: &Connection) -> Result<Option<NaiveDateTime>, SnippetVaultError> {
+    let value: Option<String> = db
+        .query_row(
+            "SELECT value FROM index_meta WHERE key = 'built_at'",
+            [],
+            |row| row.get(0),
+        )
+        .optional()?;
+    Ok(value.and_then(|value| NaiveDateTime::parse_from_str(&value, INDEX_TIMESTAMP_FORMAT).ok()))
+}
+
+/// Reclaims the space left by deleted rows.
+pub fn vacuum(db: &Connection) -> Result<(), SnippetVaultError> {
+    db.execute_batch("VACUUM")?;
+    Ok(())
+}
+
+/// Inserts or replaces the index row and full-text entry of one snippet.
+pub fn upsert_snippet_row(db: &Connection, snippet: &SnippetMeta) -> Result<(), SnippetVaultError> {
+    let path = snippet.path.to_string_lossy();
+    let tags = snippet.tags.join(" ");
+    db.execute(
+        "INSERT OR REPLACE INTO snippets (path, title, language, tags) VALUES (?1, ?2, ?3, ?4)",
+        params![path, snippet.title, snippet.language, tags],
+    )?;
+    db.execute("DELETE FROM snippet_fts WHERE path = ?1", params![path])?;
+    db.execute(
+        "INSERT INTO snippet_fts (title, tags, path) VALUES (?1, ?2, ?3)",
+        params![snippet.title, tags, path],
+    )?;
+    Ok(())
+}
+
+/// Removes the index row and full-text entry of the snippet at `path`.
+pub fn delete_snippet_row(db: &Connection, path: &Path) -> Result<(), SnippetVaultError> {
+    let path = path.to_string_lossy();
+    db.execute("DELETE FROM snippets WHERE path = ?1", params![path])?;
+    db.execute("DELETE FROM snippet_fts WHERE path = ?1", params![path])?;
+    Ok(())
+}
+
+/// Updates the default index after a snippet moved from `old_path` to `new_path`
+/// (which may be the same path). Like [`log_access`], failures are only logged.
+pub fn reindex_snippet(old_path: &Path, new_path: &Path) {
+    let result = open(&index_path()).and_then(|db| {
+        delete_snippet_row(&db, old_path)?;
+        upsert_snippet_row(&db, &SnippetMeta::read(new_path)?)
+    });
+    if let Err(err) = result {
+        tracing::warn!(path = %new_path.display(), %err, "cannot update the index");
+    }
+}
+
+/// Drops a deleted snippet from the default index. Like [`log_access`],
+/// failures are only logged.
+pub fn forget_snippet(path: &Path) {
+    if let Err(err) = open(&index_path()).and_then(|db| delete_snippet_row(&db, path)) {
+        tracing::warn!(path = %path.display(), %err, "cannot update the index");
+    }
+}
+
+/// Number of snippets in the index.
+pub fn indexed_count(db: &Connection) -> Result<usize, SnippetVaultError> {
+    let count: i64 = db.query_row("SELECT COUNT(*) FROM snippets", [], |row| row.get(0))?;
+    Ok(count as usize)
+}
+
+/// Searches indexed titles and tags, returning `(path, title)` pairs, best match first.
+/// Every word of `query` must match the start of a word in the title or tags.
+pub fn search_by_title(
+    query: &str,
+    db: &Connection,
+) -> Result<Vec<(PathBuf, String)>, SnippetVaultError> {
+    let fts_query = query
+        .split_whitespace()
+        .map(|word| format!("\"{}\"*", word.replace('"', "\"\"")))
+        .collect::<Vec<_>>()
+        .join(" ");
+    if fts_query.is_empty() {
+        return Ok(Vec::new());
+    }
+
+    let mut statement =
+        db.prepare("SELECT path, title FROM snippet_fts WHERE snippet_fts MATCH ?1 ORDER BY rank")?;
+    let results = statement
+        .query_map(params![fts_query], |row| {
+            Ok((PathBuf::from(row.get::<_, String>(0)?), row.get(1)?))
+        })?
+        .collect::<Result<Vec<_>, _>>()?;
+    Ok(results)
+}
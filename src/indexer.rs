@@ -0,0 +1,118 @@
+use notify::{EventKind, RecursiveMode, Watcher};
+use std::collections::BTreeSet;
+use std::path::{Path, PathBuf};
+use std::sync::mpsc::{self, RecvTimeoutError, TryRecvError};
+use std::thread;
+use std::time::Duration;
+
+use crate::error::SnippetVaultError;
+use crate::index;
+use crate::snippet::SnippetMeta;
+use crate::style::Styled;
+use crate::vault;
+
+/// How long a burst of filesystem events must settle before the index is updated.
+const INDEX_DEBOUNCE: Duration = Duration::from_millis(500);
+
+/// How often the indexer checks for a stop signal while the vault is quiet.
+const STOP_POLL: Duration = Duration::from_millis(100);
+
+/// Watches `snippet_dir` on a background thread and keeps the snippet rows of
+/// the index at `db_path` in step with it: files that are created or written
+/// are upserted, files that are removed or renamed away are deleted. Events
+/// are applied once they have been quiet for 500 ms. The thread ends, after
+/// applying what it has already seen, when `stop` receives a message or its
+/// sender is dropped.
+/// - `extensions`: File extensions that count as snippets.
+pub fn start_background_indexer(
+    snippet_dir: &Path,
+    db_path: &Path,
+    extensions: &[String],
+    stop: mpsc::Receiver<()>,
+) -> Result<thread::JoinHandle<()>, SnippetVaultError> {
+    // Open once up front so a broken index is reported before the thread starts.
+    index::open(db_path)?;
+    let (tx, rx) = mpsc::channel::<notify::Result<notify::Event>>();
+    let mut watcher = notify::recommended_watcher(tx)?;
+    watcher.watch(snippet_dir, RecursiveMode::Recursive)?;
+
+    let snippet_dir = snippet_dir.to_path_buf();
+    let db_path = db_path.to_path_buf();
+    let extensions = extensions.to_vec();
+    Ok(thread::spawn(move || {
+        // The watcher stops delivering events once dropped, so the thread owns it.
+        let _watcher = watcher;
+        let stopped = || !matches!(stop.try_recv(), Err(TryRecvError::Empty));
+        let mut changed = BTreeSet::new();
+        loop {
+            match rx.recv_timeout(STOP_POLL) {
+                Ok(event) => collect_paths(event, &mut changed),
+                Err(RecvTimeoutError::Timeout) if stopped() => break,
+                Err(RecvTimeoutError::Timeout) => continue,
+                Err(RecvTimeoutError::Disconnected) => break,
+            }
+            while let Ok(event) = rx.recv_timeout(INDEX_DEBOUNCE) {
+                collect_paths(event, &mut changed);
+            }
+            changed.retain(|path: &PathBuf| vault::is_vault_file(&snippet_dir, path, &extensions));
+            if !changed.is_empty() {
+                apply_changes(&db_path, std::mem::take(&mut changed));
+            }
+            if stopped() {
+                break;
+            }
+        }
+    }))
+}
+
+/// Adds the paths of a create, write, rename, or remove `event` to `changed`.
+fn collect_paths(event: notify::Result<notify::Event>, changed: &mut BTreeSet<PathBuf>) {
+    let Ok(event) = event else {
+        return;
+    };
+    if matches!(
+        event.kind,
+        EventKind::Create(_) | EventKind::Modify(_) | EventKind::Remove(_)
+    ) {
+        changed.extend(event.paths);
+    }
+}
+
+/// Upserts the rows of the `changed` files that still exist and deletes the
+/// rest, in one transaction; files that cannot be parsed are skipped.
+/// Failures are reported and the next burst retries with a fresh connection.
+fn apply_changes(db_path: &Path, changed: BTreeSet<PathBuf>) {
+    let result = index::open(db_path).and_then(|db| {
+        let transaction = db.unchecked_transaction()?;
+        let (mut updated, mut removed) = (0, 0);
+        for path in &changed {
+            if path.is_file() {
+                match SnippetMeta::read(path) {
+                    Ok(meta) => index::upsert_snippet_row(&transaction, &meta)?,
+                    Err(err) => {
+                        tracing::warn!(path = %path.display(), %err, "cannot index snippet");
+                        continue;
+                    }
+                }
+                updated += 1;
+            } else {
+                index::delete_snippet_row(&transaction, path)?;
+                removed += 1;
+            }
+        }
+        transaction.commit()?;
+        Ok((updated, removed))
+    });
+    match result {
+        Ok((updated, removed)) => {
+            tracing::info!(updated, removed, "index updated from file events");
+            println!(
+                "{} Index: {} updated, {} removed.",
+                "↻".accent(),
+                updated,
+                removed
+            );
+        }
+        Err(err) => println!("{} Cannot update the index: {}", "✘".error(), err),
+    }
+}
@@ -0,0 +1,110 @@
+use chrono::{NaiveDateTime, Timelike};
+use serde::Serialize;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use crate::error::SnippetVaultError;
+use crate::index;
+use crate::snippet::{self, Frontmatter, SnippetMeta};
+
+/// Frontmatter field naming the short names `--get` finds a snippet by.
+pub const ALIAS_FIELD: &str = "alias";
+
+/// Lines of the first code block shown as a teaser.
+const TEASER_LINES: usize = 3;
+
+/// Everything known about one snippet, as printed by `--info`. Every field is
+/// always present; unknown values are `None` (`null` in JSON).
+#[derive(Debug, Serialize)]
+pub struct SnippetInfo {
+    /// Timestamp of the generated filename, which identifies the snippet.
+    pub id: Option<String>,
+    pub path: PathBuf,
+    pub title: Option<String>,
+    pub language: Option<String>,
+    pub tags: Vec<String>,
+    pub created: Option<NaiveDateTime>,
+    pub modified: Option<NaiveDateTime>,
+    /// Times the snippet was opened, from the access log.
+    pub opened: Option<usize>,
+    pub bytes: usize,
+    pub lines: usize,
+    pub pinned: bool,
+    pub aliases: Vec<String>,
+    /// URLs of the `### Link:` section.
+    pub links: Vec<String>,
+    pub code_blocks: usize,
+    /// First non-blank lines of the first code block.
+    pub teaser: Vec<String>,
+}
+
+impl SnippetInfo {
+    /// Gathers the metadata of the snippet at `path`. The access log is best
+    /// effort: when it cannot be read, `opened` is `None`.
+    pub fn collect(path: &Path) -> Result<Self, SnippetVaultError> {
+        let meta = SnippetMeta::read(path)?;
+        let content = String::from_utf8_lossy(&fs::read(path)?).into_owned();
+        let markdown = snippet::is_markdown(path);
+        let frontmatter = if markdown {
+            Frontmatter::parse(path, &content).ok().flatten()
+        } else {
+            None
+        };
+
+        let blocks = if markdown {
+            snippet::code_blocks(&content)
+        } else {
+            Vec::new()
+        };
+        let first_code = match blocks.first() {
+            Some(block) => block.code.as_str(),
+            None if !markdown => content.as_str(),
+            None => "",
+        };
+        let teaser = first_code
+            .lines()
+            .skip_while(|line| line.trim().is_empty())
+            .take(TEASER_LINES)
+            .map(str::to_string)
+            .collect();
+
+        let aliases = match frontmatter
+            .as_ref()
+            .and_then(|frontmatter| frontmatter.extra.get(ALIAS_FIELD))
+        {
+            Some(toml::Value::Array(values)) => {
+                values.iter().map(snippet::field_to_string).collect()
+            }
+            Some(value) => vec![snippet::field_to_string(value)],
+            None => Vec::new(),
+        };
+        let opened = index::open(&index::index_path())
+            .and_then(|db| index::access_count(&db, path, index::Access::Opened))
+            .ok();
+
+        Ok(SnippetInfo {
+            id: snippet::filename_timestamp(path),
+            path: path.to_path_buf(),
+            title: Some(meta.title).filter(|title| !title.is_empty()),
+            language: Some(meta.language).filter(|language| !language.is_empty()),
+            tags: meta.tags,
+            created: meta.created,
+            modified: snippet::modified_time(path).and_then(|time| time.with_nanosecond(0)),
+            opened,
+            bytes: meta.stats.bytes,
+            lines: meta.stats.lines,
+            pinned: meta
+                .extra
+                .get("pinned")
+                .is_some_and(|value| value == "true"),
+            aliases,
+            links: snippet::link_urls(&content),
+            code_blocks: if markdown {
+                blocks.len()
+            } else {
+                usize::from(!content.trim().is_empty())
+            },
+            teaser,
+        })
+    }
+}
@@ -0,0 +1,300 @@
+use std::collections::HashMap;
+
+/// Known languages: (name, file extensions, line-comment prefix).
+/// The first extension is the one used when creating a plain file.
+const LANGUAGES: &[(&str, &[&str], &str)] = &[
+    ("python", &["py"], "#"),
+    ("bash", &["sh", "bash"], "#"),
+    ("zsh", &["zsh"], "#"),
+    ("rust", &["rs"], "//"),
+    ("javascript", &["js", "mjs", "cjs"], "//"),
+    ("typescript", &["ts", "tsx"], "//"),
+    ("golang", &["go"], "//"),
+    ("ruby", &["rb"], "#"),
+    ("lua", &["lua"], "--"),
+    ("sql", &["sql"], "--"),
+    ("c", &["c", "h"], "//"),
+    ("cpp", &["cpp", "cc", "hpp", "hh"], "//"),
+    ("java", &["java"], "//"),
+    ("kotlin", &["kt", "kts"], "//"),
+    ("swift", &["swift"], "//"),
+    ("scala", &["scala"], "//"),
+    ("php", &["php"], "//"),
+    ("perl", &["pl"], "#"),
+    ("haskell", &["hs"], "--"),
+    ("rlang", &["r"], "#"),
+    ("vim", &["vim"], "\""),
+    ("emacs", &["el"], ";;"),
+    ("latex", &["tex"], "%"),
+    ("yaml", &["yaml", "yml"], "#"),
+    ("toml", &["toml"], "#"),
+    ("dockerfile", &["dockerfile"], "#"),
+    ("json", &["json"], ""),
+    ("markdown", &["md"], ""),
+    ("text", &["txt"], ""),
+];
+
+/// Languages shown by `--languages`, as they are written in snippets.
+pub const LISTED_LANGUAGES: &[&str] = &[
+    "python",
+    "cpp",
+    "bash",
+    "terminal",
+    "shell",
+    "zsh",
+    "php",
+    "typescript",
+    "scala",
+    "nvim",
+    "neovim",
+    "pdf",
+    "markdown",
+    "org",
+    "text",
+    "powershell",
+    "perl",
+    "haskell",
+    "kotlin",
+    "sql",
+    "matlap",
+    "groovy",
+    "lua",
+    "rust",
+    "ruby",
+    "html",
+    "css",
+    "java",
+    "javascript",
+    "swift",
+    "c++",
+    "c#",
+    "docker",
+    "kubernetes",
+    "docker-compose",
+    "rlang",
+    "golang",
+    "vim",
+    "apple",
+    "mac",
+    "macos",
+    "applescript",
+    "git",
+    "gnuplot",
+    "github",
+    "linux",
+    "gnu-linux",
+    "ubuntu",
+    "note",
+    "memo",
+    "awk",
+    "sed",
+    "tr",
+    "cat",
+    "jupyter",
+    "jupyterlab",
+    "lab",
+    "bat",
+    "latex",
+    "emacs",
+];
+
+/// Official documentation by language name, for `--snippet_language_docs`.
+/// Covers every entry of [`LISTED_LANGUAGES`] that has documentation (plain
+/// `text`, `note`, and `memo` do not), plus the other known languages.
+const LANGUAGE_DOCS: &[(&str, &str)] = &[
+    ("python", "https://docs.python.org/3/"),
+    ("c", "https://en.cppreference.com/w/c"),
+    ("cpp", "https://en.cppreference.com/w/cpp"),
+    ("c++", "https://en.cppreference.com/w/cpp"),
+    ("bash", "https://www.gnu.org/software/bash/manual/bash.html"),
+    ("terminal", "https://www.gnu.org/software/bash/manual/bash.html"),
+    (
+        "shell",
+        "https://pubs.opengroup.org/onlinepubs/9799919799/utilities/V3_chap02.html",
+    ),
+    ("zsh", "https://zsh.sourceforge.io/Doc/"),
+    ("php", "https://www.php.net/manual/en/"),
+    ("typescript", "https://www.typescriptlang.org/docs/"),
+    ("scala", "https://docs.scala-lang.org/"),
+    ("nvim", "https://neovim.io/doc/"),
+    ("neovim", "https://neovim.io/doc/"),
+    ("vim", "https://vimhelp.org/"),
+    ("pdf", "https://opensource.adobe.com/dc-acrobat-sdk-docs/"),
+    ("markdown", "https://commonmark.org/help/"),
+    ("org", "https://orgmode.org/manual/"),
+    ("powershell", "https://learn.microsoft.com/en-us/powershell/"),
+    ("perl", "https://perldoc.perl.org/"),
+    ("haskell", "https://www.haskell.org/documentation/"),
+    ("kotlin", "https://kotlinlang.org/docs/home.html"),
+    ("sql", "https://www.postgresql.org/docs/current/sql.html"),
+    ("matlab", "https://www.mathworks.com/help/matlab/"),
+    ("matlap", "https://www.mathworks.com/help/matlab/"),
+    ("groovy", "https://groovy-lang.org/documentation.html"),
+    ("lua", "https://www.lua.org/manual/5.4/"),
+    ("rust", "https://doc.rust-lang.org/std/"),
+    ("ruby", "https://docs.ruby-lang.org/en/"),
+    ("html", "https://developer.mozilla.org/en-US/docs/Web/HTML"),
+    ("css", "https://developer.mozilla.org/en-US/docs/Web/CSS"),
+    ("java", "https://docs.oracle.com/en/java/javase/21/docs/api/"),
+    (
+        "javascript",
+        "https://developer.mozilla.org/en-US/docs/Web/JavaScript",
+    ),
+    ("swift", "https://www.swift.org/documentation/"),
+    ("c#", "https://learn.microsoft.com/en-us/dotnet/csharp/"),
+    ("csharp", "https://learn.microsoft.com/en-us/dotnet/csharp/"),
+    ("docker", "https://docs.docker.com/reference/cli/docker/"),
+    ("dockerfile", "https://docs.docker.com/reference/dockerfile/"),
+    ("kubernetes", "https://kubernetes.io/docs/home/"),
+    ("docker-compose", "https://docs.docker.com/reference/compose-file/"),
+    ("rlang", "https://cran.r-project.org/manuals.html"),
+    ("r", "https://cran.r-project.org/manuals.html"),
+    ("golang", "https://pkg.go.dev/std"),
+    ("go", "https://pkg.go.dev/std"),
+    (
+        "applescript",
+        "https://developer.apple.com/library/archive/documentation/AppleScript/Conceptual/AppleScriptLangGuide/",
+    ),
+    (
+        "apple",
+        "https://developer.apple.com/library/archive/documentation/AppleScript/Conceptual/AppleScriptLangGuide/",
+    ),
+    ("mac", "https://support.apple.com/guide/terminal/welcome/mac"),
+    ("macos", "https://support.apple.com/guide/terminal/welcome/mac"),
+    ("git", "https://git-scm.com/docs"),
+    ("gnuplot", "http://www.gnuplot.info/documentation.html"),
+    ("github", "https://docs.github.com/"),
+    ("linux", "https://man7.org/linux/man-pages/"),
+    ("gnu-linux", "https://man7.org/linux/man-pages/"),
+    ("ubuntu", "https://help.ubuntu.com/"),
+    ("awk", "https://www.gnu.org/software/gawk/manual/"),
+    ("sed", "https://www.gnu.org/software/sed/manual/"),
+    (
+        "tr",
+        "https://www.gnu.org/software/coreutils/manual/html_node/tr-invocation.html",
+    ),
+    (
+        "cat",
+        "https://www.gnu.org/software/coreutils/manual/html_node/cat-invocation.html",
+    ),
+    ("jupyter", "https://docs.jupyter.org/"),
+    ("jupyterlab", "https://jupyterlab.readthedocs.io/"),
+    ("lab", "https://jupyterlab.readthedocs.io/"),
+    ("bat", "https://github.com/sharkdp/bat#readme"),
+    ("latex", "https://www.latex-project.org/help/documentation/"),
+    ("emacs", "https://www.gnu.org/software/emacs/manual/"),
+    ("yaml", "https://yaml.org/spec/1.2.2/"),
+    ("toml", "https://toml.io/en/v1.0.0"),
+    ("json", "https://www.json.org/json-en.html"),
+];
+
+/// Extensions read from the vault when no `extensions` allowlist is configured.
+pub fn default_extensions() -> Vec<String> {
+    LANGUAGES
+        .iter()
+        .flat_map(|(_, extensions, _)| extensions.iter())
+        .map(|extension| extension.to_string())
+        .collect()
+}
+
+/// Returns the language a file extension belongs to.
+pub fn language_for_extension(extension: &str) -> Option<&'static str> {
+    let extension = extension.to_lowercase();
+    LANGUAGES
+        .iter()
+        .find(|(_, extensions, _)| extensions.contains(&extension.as_str()))
+        .map(|(name, _, _)| *name)
+}
+
+/// Returns the preferred file extension for `language`.
+pub fn extension_for(language: &str) -> Option<&'static str> {
+    LANGUAGES
+        .iter()
+        .find(|(name, _, _)| *name == language)
+        .and_then(|(_, extensions, _)| extensions.first().copied())
+}
+
+/// Returns the line-comment prefix for `language`, or `None` if it has no line comments.
+pub fn comment_prefix(language: &str) -> Option<&'static str> {
+    LANGUAGES
+        .iter()
+        .find(|(name, _, _)| *name == language)
+        .map(|(_, _, prefix)| *prefix)
+        .filter(|prefix| !prefix.is_empty())
+}
+
+/// Returns the built-in official documentation URL for `language`, compared
+/// case-insensitively.
+pub fn language_docs_url(language: &str) -> Option<&'static str> {
+    let language = language.to_lowercase();
+    LANGUAGE_DOCS
+        .iter()
+        .find(|(name, _)| *name == language)
+        .map(|(_, url)| *url)
+}
+
+/// Returns the canonical language `input` is an alias for (`py` for `python`,
+/// per the `[language_aliases]` config section), or `input` unchanged when it
+/// is not an alias. Alias names are compared case-insensitively.
+pub fn resolve_language(input: &str, aliases: &HashMap<String, String>) -> String {
+    aliases
+        .iter()
+        .find(|(alias, _)| alias.eq_ignore_ascii_case(input))
+        .map(|(_, canonical)| canonical.clone())
+        .unwrap_or_else(|| input.to_string())
+}
+
+/// Returns the name `language` is counted under in statistics: the canonical
+/// language it is an alias for, lowercased, so `Py`, `py`, and `python` are
+/// one language.
+pub fn canonical_language(language: &str, aliases: &HashMap<String, String>) -> String {
+    resolve_language(language, aliases).to_lowercase()
+}
+
+/// Splits a search query into the language and tags of a snippet to create
+/// for it: the first word naming a known language (after alias resolution),
+/// else the first word, is the language; the other words become tags.
+/// Characters that cannot appear in a tag are dropped.
+pub fn language_and_tags(query: &str, aliases: &HashMap<String, String>) -> (String, Vec<String>) {
+    let words: Vec<String> = query
+        .split_whitespace()
+        .map(|word| {
+            word.to_lowercase()
+                .chars()
+                .filter(|c| c.is_alphanumeric() || matches!(c, '-' | '.' | '+' | '#'))
+                .collect::<String>()
+        })
+        .filter(|word| !word.is_empty())
+        .collect();
+    let position = words
+        .iter()
+        .position(|word| extension_for(&resolve_language(word, aliases)).is_some())
+        .unwrap_or(0);
+    let Some(language) = words.get(position) else {
+        return ("text".to_string(), Vec::new());
+    };
+    let language = resolve_language(language, aliases);
+    let mut tags: Vec<String> = Vec::new();
+    for (index, word) in words.iter().enumerate() {
+        if index != position && !tags.contains(word) {
+            tags.push(word.clone());
+        }
+    }
+    (language, tags)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn every_listed_language_has_docs() {
+        let undocumented: Vec<&str> = LISTED_LANGUAGES
+            .iter()
+            .copied()
+            .filter(|language| !["text", "note", "memo"].contains(language))
+            .filter(|language| language_docs_url(language).is_none())
+            .collect();
+        assert!(undocumented.is_empty(), "no docs for {:?}", undocumented);
+    }
+}
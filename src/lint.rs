@@ -0,0 +1,221 @@
+use regex::Regex;
+use serde::Deserialize;
+use std::collections::HashMap;
+use std::fmt;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use crate::error::SnippetVaultError;
+use crate::snippet::{self, code_blocks, Frontmatter};
+use crate::vault;
+
+/// How serious a lint finding is.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Severity {
+    Error,
+    Warning,
+    Info,
+}
+
+impl fmt::Display for Severity {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let label = match self {
+            Severity::Error => "error",
+            Severity::Warning => "warning",
+            Severity::Info => "info",
+        };
+        write!(f, "{}", label)
+    }
+}
+
+/// A compiled per-language lint rule.
+/// Normal rules flag every line matching `pattern`; `require` rules flag a code
+/// block in which `pattern` never matches.
+#[derive(Debug, Clone)]
+pub struct LintRule {
+    pub language: String,
+    pub pattern: Regex,
+    pub message: String,
+    pub severity: Severity,
+    pub require: bool,
+}
+
+/// A lint rule as written under `[lint_rules]` in `config.toml`.
+#[derive(Debug, Clone, Deserialize)]
+pub struct LintRuleSpec {
+    pub pattern: String,
+    pub message: String,
+    #[serde(default = "default_severity")]
+    pub severity: Severity,
+    #[serde(default)]
+    pub require: bool,
+}
+
+fn default_severity() -> Severity {
+    Severity::Warning
+}
+
+/// One finding reported by the linter.
+#[derive(Debug, Clone)]
+pub struct LintWarning {
+    pub path: PathBuf,
+    pub line: Option<usize>,
+    pub message: String,
+    pub severity: Severity,
+}
+
+/// Rules compiled into the binary: (language, pattern, message, severity, require).
+const BUILTIN_RULES: &[(&str, &str, &str, Severity, bool)] = &[
+    (
+        "bash",
+        r"^\s*set\s+-[a-zA-Z]*e",
+        "script does not `set -e`; failures will be ignored",
+        Severity::Warning,
+        true,
+    ),
+    (
+        "python",
+        r"^\s*except\s*:",
+        "bare `except:` swallows every exception, including KeyboardInterrupt",
+        Severity::Warning,
+        false,
+    ),
+    (
+        "rust",
+        r"^[^/]*\.unwrap\(\)[^/]*$",
+        "`unwrap()` without a comment explaining why it cannot fail",
+        Severity::Info,
+        false,
+    ),
+];
+
+/// Compiles the built-in rules followed by the ones from `config.toml`.
+pub fn load_rules(
+    configured: &HashMap<String, Vec<LintRuleSpec>>,
+) -> Result<Vec<LintRule>, SnippetVaultError> {
+    let mut rules: Vec<LintRule> = BUILTIN_RULES
+        .iter()
+        .map(
+            |&(language, pattern, message, severity, require)| LintRule {
+                language: language.to_string(),
+                pattern: Regex::new(pattern).expect("built-in lint patterns are valid"),
+                message: message.to_string(),
+                severity,
+                require,
+            },
+        )
+        .collect();
+
+    for (language, specs) in configured {
+        for spec in specs {
+            let pattern = Regex::new(&spec.pattern).map_err(|err| {
+                SnippetVaultError::Config(format!("lint_rules.{}: {}", language, err))
+            })?;
+            rules.push(LintRule {
+                language: language.clone(),
+                pattern,
+                message: spec.message.clone(),
+                severity: spec.severity,
+                require: spec.require,
+            });
+        }
+    }
+
+    Ok(rules)
+}
+
+/// Applies `rules` to every code block of the snippet at `path`.
+/// A block's language comes from its fence, falling back to the snippet's language.
+/// Binary files and files over the parse limit are not read; a single finding
+/// suggests moving them to the attachments directory instead.
+pub fn lint_snippet(path: &Path, rules: &[LintRule]) -> Vec<LintWarning> {
+    let content = match snippet::read_limited(path) {
+        Ok(Some(content)) if !snippet::is_binary(&content) => match String::from_utf8(content) {
+            Ok(content) => content,
+            Err(_) => {
+                return vec![LintWarning {
+                    path: path.to_path_buf(),
+                    line: None,
+                    message: "not valid UTF-8; it is listed by filename but never parsed, re-save it as UTF-8".to_string(),
+                    severity: Severity::Warning,
+                }]
+            }
+        },
+        Ok(Some(_)) => {
+            return vec![misplaced_file(path, "binary file")];
+        }
+        Ok(None) => {
+            let size = fs::metadata(path)
+                .map(|meta| meta.len())
+                .unwrap_or_default();
+            let reason = format!(
+                "{} bytes, over the {}-byte parse limit",
+                size,
+                snippet::parse_limit()
+            );
+            return vec![misplaced_file(path, &reason)];
+        }
+        Err(err) => {
+            return vec![LintWarning {
+                path: path.to_path_buf(),
+                line: None,
+                message: format!("cannot read file: {}", err),
+                severity: Severity::Error,
+            }]
+        }
+    };
+    let snippet_language = Frontmatter::load(path, &content)
+        .map(|frontmatter| frontmatter.language)
+        .unwrap_or_default();
+
+    let mut warnings = Vec::new();
+    for block in code_blocks(&content) {
+        let language = if block.language.is_empty() {
+            &snippet_language
+        } else {
+            &block.language
+        };
+
+        for rule in rules.iter().filter(|rule| rule.language == *language) {
+            if rule.require {
+                if !block.code.trim().is_empty() && !rule.pattern.is_match(&block.code) {
+                    warnings.push(LintWarning {
+                        path: path.to_path_buf(),
+                        line: Some(block.start_line),
+                        message: rule.message.clone(),
+                        severity: rule.severity,
+                    });
+                }
+                continue;
+            }
+
+            for (offset, line) in block.code.lines().enumerate() {
+                if rule.pattern.is_match(line) {
+                    warnings.push(LintWarning {
+                        path: path.to_path_buf(),
+                        line: Some(block.start_line + 1 + offset),
+                        message: rule.message.clone(),
+                        severity: rule.severity,
+                    });
+                }
+            }
+        }
+    }
+
+    warnings
+}
+
+/// A finding for a file that does not belong among the snippets.
+fn misplaced_file(path: &Path, reason: &str) -> LintWarning {
+    LintWarning {
+        path: path.to_path_buf(),
+        line: None,
+        message: format!(
+            "{}; it is listed but never parsed or previewed, move it to {}/ in the vault",
+            reason,
+            vault::ATTACHMENTS_DIR
+        ),
+        severity: Severity::Warning,
+    }
+}
@@ -0,0 +1,864 @@
+use chrono::NaiveDateTime;
+use regex::Regex;
+use serde::Serialize;
+use std::collections::{BTreeMap, HashMap};
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use crate::cache::MetadataCache;
+use crate::error::SnippetVaultError;
+use crate::expiry;
+use crate::language;
+use crate::lock::VaultLock;
+use crate::snippet::{self, modified_time, SnippetMeta};
+use crate::tags::{filter_by_tag_prefix, snippet_tags};
+use crate::usage;
+use crate::vault;
+use crate::width;
+
+/// Names accepted by `--sort_by`.
+pub const SORT_CRITERIA: &[&str] = &[
+    "path", "created", "title", "language", "lines", "natural", "uses",
+];
+
+/// Which snippets a listing or bulk operation includes. Empty fields match
+/// everything. Shared by `--list_snippets` and `--snippet_bulk` so both select
+/// exactly the same snippets for the same flags.
+#[derive(Debug, Clone, Default)]
+pub struct SnippetFilter {
+    pub language: Option<String>,
+    /// Tags that must all be present; each also matches its dotted sub-tags
+    /// (`rust` matches `rust.async`).
+    pub tags: Vec<String>,
+    /// Case-insensitive substring of the title.
+    pub title: Option<String>,
+    /// Only snippets created at or after this time.
+    pub created_after: Option<NaiveDateTime>,
+    /// Only snippets created before this time.
+    pub created_before: Option<NaiveDateTime>,
+    /// Only files modified at or after this time.
+    pub modified_after: Option<NaiveDateTime>,
+    /// Only files modified before this time.
+    pub modified_before: Option<NaiveDateTime>,
+    /// Required value of the `pinned` frontmatter field.
+    pub pinned: Option<bool>,
+    /// Required value of the `archived` frontmatter field.
+    pub archived: Option<bool>,
+    /// Custom frontmatter fields that must all have the given value (`--where key=value`).
+    pub fields: Vec<(String, String)>,
+    /// Maximum number of snippets, applied after sorting.
+    pub limit: Option<usize>,
+}
+
+impl SnippetFilter {
+    /// Whether any criterion is set, i.e. the listing is not the whole vault.
+    pub fn is_active(&self) -> bool {
+        self.language.is_some()
+            || !self.tags.is_empty()
+            || self.title.is_some()
+            || self.created_after.is_some()
+            || self.created_before.is_some()
+            || self.modified_after.is_some()
+            || self.modified_before.is_some()
+            || self.pinned.is_some()
+            || self.archived.is_some()
+            || !self.fields.is_empty()
+            || self.limit.is_some()
+    }
+
+    /// Whether `snippet` passes every set criterion (language, tags, and title
+    /// compared case-insensitively, custom fields exactly). Snippets without a
+    /// creation time never pass a created range.
+    pub fn matches(&self, snippet: &SnippetMeta) -> bool {
+        let language_matches = self
+            .language
+            .as_ref()
+            .is_none_or(|language| snippet.language.eq_ignore_ascii_case(language));
+        let tags_match = self.tags.is_empty() || {
+            let tags = snippet_tags(snippet);
+            self.tags.iter().all(|tag| filter_by_tag_prefix(tag, &tags))
+        };
+        let title_matches = self
+            .title
+            .as_ref()
+            .is_none_or(|title| snippet.title.to_lowercase().contains(&title.to_lowercase()));
+        let created_matches = in_range(snippet.created, self.created_after, self.created_before);
+        let modified_matches = (self.modified_after.is_none() && self.modified_before.is_none())
+            || in_range(
+                modified_time(&snippet.path),
+                self.modified_after,
+                self.modified_before,
+            );
+        let state_matches = flag_matches(snippet, "pinned", self.pinned)
+            && flag_matches(snippet, expiry::ARCHIVED_FIELD, self.archived);
+        let fields_match = self
+            .fields
+            .iter()
+            .all(|(key, value)| snippet.extra.get(key) == Some(value));
+        language_matches
+            && tags_match
+            && title_matches
+            && created_matches
+            && modified_matches
+            && state_matches
+            && fields_match
+    }
+}
+
+fn in_range(
+    time: Option<NaiveDateTime>,
+    after: Option<NaiveDateTime>,
+    before: Option<NaiveDateTime>,
+) -> bool {
+    if after.is_none() && before.is_none() {
+        return true;
+    }
+    time.is_some_and(|time| {
+        after.is_none_or(|after| time >= after) && before.is_none_or(|before| time < before)
+    })
+}
+
+/// A missing field counts as `false`, so `--archived` and its absence split the vault.
+fn flag_matches(snippet: &SnippetMeta, field: &str, wanted: Option<bool>) -> bool {
+    wanted.is_none_or(|wanted| {
+        snippet
+            .extra
+            .get(field)
+            .is_some_and(|value| value == "true")
+            == wanted
+    })
+}
+
+/// Order of a listing.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum SortCriterion {
+    #[default]
+    Path,
+    /// Newest first; snippets without a timestamp come last.
+    Created,
+    Title,
+    Language,
+    /// Longest first.
+    Lines,
+    /// By path, with runs of digits compared as numbers (`snippet_2` before
+    /// `snippet_10`).
+    Natural,
+    /// Most used first; see [`usage::use_counts`].
+    Uses,
+}
+
+impl SortCriterion {
+    /// Parses one of [`SORT_CRITERIA`].
+    pub fn from_name(name: &str) -> Option<Self> {
+        match name {
+            "path" => Some(SortCriterion::Path),
+            "created" => Some(SortCriterion::Created),
+            "title" => Some(SortCriterion::Title),
+            "language" => Some(SortCriterion::Language),
+            "lines" => Some(SortCriterion::Lines),
+            "natural" => Some(SortCriterion::Natural),
+            "uses" => Some(SortCriterion::Uses),
+            _ => None,
+        }
+    }
+
+    /// Sorts `snippets` in place; ties are broken by path so output is stable.
+    pub fn sort(self, snippets: &mut [SnippetMeta]) {
+        let uses = if self == SortCriterion::Uses {
+            usage::use_counts(snippets)
+        } else {
+            HashMap::new()
+        };
+        let uses_of = |snippet: &SnippetMeta| uses.get(&snippet.path).copied().unwrap_or(0);
+        snippets.sort_by(|a, b| {
+            let order = match self {
+                SortCriterion::Path => std::cmp::Ordering::Equal,
+                SortCriterion::Created => b.created.cmp(&a.created),
+                SortCriterion::Title => a.title.to_lowercase().cmp(&b.title.to_lowercase()),
+                SortCriterion::Language => a.language.cmp(&b.language),
+                SortCriterion::Lines => b.stats.lines.cmp(&a.stats.lines),
+                SortCriterion::Natural => natural_sort_key(&a.path.to_string_lossy())
+                    .cmp(&natural_sort_key(&b.path.to_string_lossy())),
+                SortCriterion::Uses => uses_of(b).cmp(&uses_of(a)),
+            };
+            order.then_with(|| a.path.cmp(&b.path))
+        });
+    }
+}
+
+/// One run of a [`natural_sort_key`]. Numbers sort before text, as digits do
+/// before letters.
+#[derive(Debug, Clone, PartialEq, Eq, PartialOrd, Ord)]
+pub enum NaturalPart {
+    Number(u64),
+    /// Lowercased, so case does not affect the order.
+    Text(String),
+}
+
+/// Splits `s` into alternating text and digit runs so that numbers compare by
+/// value: `snippet_2.md` sorts before `snippet_10.md`. A digit run too long
+/// for a `u64` is kept as text.
+pub fn natural_sort_key(s: &str) -> Vec<NaturalPart> {
+    let mut parts = Vec::new();
+    let mut rest = s;
+    while let Some(first) = rest.chars().next() {
+        let digits = first.is_ascii_digit();
+        let end = rest
+            .find(|c: char| c.is_ascii_digit() != digits)
+            .unwrap_or(rest.len());
+        let (run, tail) = rest.split_at(end);
+        parts.push(match run.parse() {
+            Ok(number) if digits => NaturalPart::Number(number),
+            _ => NaturalPart::Text(run.to_lowercase()),
+        });
+        rest = tail;
+    }
+    parts
+}
+
+/// Loads the snippets under `snippet_dir` that pass `filter`, ordered by `sort`.
+/// - `extensions`: File extensions that count as snippets.
+/// - `use_cache`: Serve unchanged snippets from the metadata cache.
+pub fn query_snippets(
+    snippet_dir: &Path,
+    filter: &SnippetFilter,
+    sort: SortCriterion,
+    extensions: &[String],
+    use_cache: bool,
+) -> Result<Vec<SnippetMeta>, SnippetVaultError> {
+    let mut snippets: Vec<SnippetMeta> = vault::load_snippets(snippet_dir, extensions, use_cache)?
+        .into_iter()
+        .filter(|snippet| filter.matches(snippet))
+        .collect();
+    sort.sort(&mut snippets);
+    if let Some(limit) = filter.limit {
+        snippets.truncate(limit);
+    }
+    Ok(snippets)
+}
+
+/// Names accepted by `--group-by`.
+pub const GROUP_BY: &[&str] = &["language", "tag"];
+
+/// Label of the group of snippets without tags in tag grouping.
+pub const UNTAGGED_GROUP: &str = "(untagged)";
+
+/// Label of the group of snippets without a language in language grouping.
+pub const NO_LANGUAGE_GROUP: &str = "(no language)";
+
+/// How `--list_snippets --group-by` sections the listing.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum GroupBy {
+    Language,
+    /// One section per tag; a snippet with several tags is in each of them.
+    Tag,
+}
+
+impl GroupBy {
+    /// Parses one of [`GROUP_BY`].
+    pub fn from_name(name: &str) -> Option<Self> {
+        match name {
+            "language" => Some(GroupBy::Language),
+            "tag" => Some(GroupBy::Tag),
+            _ => None,
+        }
+    }
+}
+
+/// One section of a grouped listing.
+#[derive(Debug, Serialize)]
+pub struct SnippetGroup {
+    pub name: String,
+    /// Snippets in the group before `--limit` cut it.
+    pub count: usize,
+    /// Most recent first; at most `--limit` of them.
+    pub snippets: Vec<SnippetMeta>,
+}
+
+/// A grouped listing, as printed by `--group-by ... --format json`.
+#[derive(Debug, Serialize)]
+pub struct GroupedListing {
+    pub group_by: GroupBy,
+    /// Distinct snippets across all groups; with tag grouping, a snippet in
+    /// several groups counts once.
+    pub total: usize,
+    pub groups: Vec<SnippetGroup>,
+}
+
+/// When a snippet was created, or else last modified.
+pub fn recency(snippet: &SnippetMeta) -> Option<NaiveDateTime> {
+    snippet.created.or_else(|| modified_time(&snippet.path))
+}
+
+/// Sections `snippets` by language or by tag (see [`snippet_tags`]; untagged
+/// snippets go under [`UNTAGGED_GROUP`]). Languages are lowercased and resolved
+/// through `aliases`, so `Py` and `python` share a group, and snippets without
+/// one go under [`NO_LANGUAGE_GROUP`]. Groups are ordered by name and their
+/// snippets most recent first (see [`recency`]), each group cut to `limit`.
+pub fn group_snippets(
+    snippets: Vec<SnippetMeta>,
+    group_by: GroupBy,
+    limit: Option<usize>,
+    aliases: &HashMap<String, String>,
+) -> GroupedListing {
+    let total = snippets.len();
+    let mut groups: BTreeMap<String, Vec<(Option<NaiveDateTime>, SnippetMeta)>> = BTreeMap::new();
+    for snippet in snippets {
+        let names = match group_by {
+            GroupBy::Language => match snippet.language.trim() {
+                "" => vec![NO_LANGUAGE_GROUP.to_string()],
+                name => {
+                    vec![language::resolve_language(&name.to_lowercase(), aliases).to_lowercase()]
+                }
+            },
+            GroupBy::Tag => match snippet_tags(&snippet) {
+                tags if tags.is_empty() => vec![UNTAGGED_GROUP.to_string()],
+                tags => tags,
+            },
+        };
+        let when = recency(&snippet);
+        for name in names {
+            groups
+                .entry(name)
+                .or_default()
+                .push((when, snippet.clone()));
+        }
+    }
+
+    let groups = groups
+        .into_iter()
+        .map(|(name, mut members)| {
+            members.sort_by(|a, b| b.0.cmp(&a.0).then_with(|| a.1.path.cmp(&b.1.path)));
+            let count = members.len();
+            members.truncate(limit.unwrap_or(usize::MAX));
+            SnippetGroup {
+                name,
+                count,
+                snippets: members.into_iter().map(|(_, snippet)| snippet).collect(),
+            }
+        })
+        .collect();
+    GroupedListing {
+        group_by,
+        total,
+        groups,
+    }
+}
+
+/// A snippet's metadata with the hash of its code, as printed by
+/// `--list_snippets --json --show_content_hash`.
+#[derive(Debug, Serialize)]
+pub struct HashedSnippet {
+    #[serde(flatten)]
+    pub meta: SnippetMeta,
+    pub content_hash: String,
+}
+
+/// Pairs each of `snippets` with the SHA-256 of its code blocks.
+/// - `use_cache`: Reuse hashes from the metadata cache for unchanged files and
+///   store the ones computed here.
+pub fn with_content_hashes(
+    snippets: Vec<SnippetMeta>,
+    use_cache: bool,
+) -> Result<Vec<HashedSnippet>, SnippetVaultError> {
+    let mut cache = if use_cache {
+        MetadataCache::load()
+    } else {
+        MetadataCache::default()
+    };
+    let hashed = snippets
+        .into_iter()
+        .map(|meta| {
+            let content_hash = cache.content_hash(&meta.path)?;
+            Ok(HashedSnippet { meta, content_hash })
+        })
+        .collect::<Result<Vec<_>, SnippetVaultError>>()?;
+    if use_cache {
+        if let Some(_lock) = VaultLock::try_acquire() {
+            let _ = cache.save();
+        }
+    }
+    Ok(hashed)
+}
+
+/// Paths of the snippets [`query_snippets`] selects, for line-by-line output.
+pub fn list_snippets_plain(
+    snippet_dir: &Path,
+    filter: &SnippetFilter,
+    sort: SortCriterion,
+    extensions: &[String],
+    use_cache: bool,
+) -> Result<Vec<PathBuf>, SnippetVaultError> {
+    Ok(
+        query_snippets(snippet_dir, filter, sort, extensions, use_cache)?
+            .into_iter()
+            .map(|snippet| snippet.path)
+            .collect(),
+    )
+}
+
+/// The snippets [`query_snippets`] selects, in the same order as
+/// [`list_snippets_plain`], as a pretty-printed JSON array of their metadata.
+/// - `show_hash`: Add each snippet's `content_hash` (see [`with_content_hashes`]).
+pub fn list_snippets_json(
+    snippet_dir: &Path,
+    filter: &SnippetFilter,
+    sort: SortCriterion,
+    extensions: &[String],
+    show_hash: bool,
+    use_cache: bool,
+) -> Result<String, SnippetVaultError> {
+    let snippets = query_snippets(snippet_dir, filter, sort, extensions, use_cache)?;
+    let json = if show_hash {
+        serde_json::to_string_pretty(&with_content_hashes(snippets, use_cache)?)
+    } else {
+        serde_json::to_string_pretty(&snippets)
+    };
+    Ok(json.expect("metadata is always serializable"))
+}
+
+/// One line of the compact picker: `id`, language, tags, and title in
+/// columns of 6, 12, 30, and 40, long values cut with `…`. The id is never
+/// cut, so it can be passed to the commands that take one; see
+/// [`vault::find_by_id`].
+pub fn format_compact_line(meta: &SnippetMeta, id: &str) -> String {
+    let column = |text: &str, width: usize| width::pad(&width::truncate(text, width), width);
+    let title = if meta.title.is_empty() {
+        meta.path
+            .file_name()
+            .map(|name| name.to_string_lossy().into_owned())
+            .unwrap_or_default()
+    } else {
+        meta.title.clone()
+    };
+    let line = format!(
+        "{} {} {} {}",
+        width::pad(id, 6),
+        column(&meta.language, 12),
+        column(&meta.tags.join(","), 30),
+        column(&title, 40)
+    );
+    line.replace(['\t', '\n', '\r'], " ").trim_end().to_string()
+}
+
+/// Parses a `--where` argument of the form `key=value`.
+pub fn parse_field_condition(condition: &str) -> Result<(String, String), String> {
+    match condition.split_once('=') {
+        Some((key, value)) if !key.trim().is_empty() => {
+            Ok((key.trim().to_string(), value.trim().to_string()))
+        }
+        _ => Err(format!("expected key=value, got '{}'", condition)),
+    }
+}
+
+/// The 1-based number of the first line of `path` matching `pattern`, which is
+/// interpreted like in [`files_containing`].
+pub fn first_match_line(path: &Path, pattern: &str) -> Option<usize> {
+    let regex = pattern_regex(pattern);
+    let content = fs::read(path).ok()?;
+    String::from_utf8_lossy(&content)
+        .lines()
+        .position(|line| regex.is_match(line))
+        .map(|index| index + 1)
+}
+
+/// Returns the files under `snippet_dir` whose contents match every one of
+/// `patterns`, sorted by path. Each pattern is a regular expression; when it
+/// does not parse it is matched literally. Binary files and files over the
+/// parse limit are not searched.
+/// - `extensions`: File extensions that count as snippets.
+pub fn files_containing(
+    snippet_dir: &Path,
+    patterns: &[&str],
+    extensions: &[String],
+) -> Result<Vec<PathBuf>, SnippetVaultError> {
+    let regexes: Vec<Regex> = patterns
+        .iter()
+        .map(|pattern| pattern_regex(pattern))
+        .collect();
+
+    Ok(
+        vault::scan_files(vault::vault_files(snippet_dir, extensions)?, |path| {
+            match snippet::read_limited(path) {
+                Ok(Some(content)) if !snippet::is_binary(&content) => {
+                    let content = String::from_utf8_lossy(&content);
+                    regexes.iter().all(|regex| regex.is_match(&content))
+                }
+                _ => false,
+            }
+        })
+        .into_iter()
+        .filter(|(_, matched)| *matched)
+        .map(|(path, _)| path)
+        .collect(),
+    )
+}
+
+/// `pattern` as a regular expression, or matched literally when it does not parse.
+fn pattern_regex(pattern: &str) -> Regex {
+    Regex::new(pattern)
+        .or_else(|_| Regex::new(&regex::escape(pattern)))
+        .expect("an escaped pattern is always a valid regex")
+}
+
+/// Columns accepted by `--columns`.
+pub const TABLE_COLUMNS: &[&str] = &[
+    "title", "language", "tags", "created", "size", "lines", "words", "path",
+];
+
+/// Columns shown when `--columns` is not given.
+pub const DEFAULT_TABLE_COLUMNS: &[&str] = &["title", "language", "tags", "created", "size"];
+
+/// Narrowest the title column is truncated to when the table is too wide.
+const MIN_TITLE_WIDTH: usize = 10;
+
+/// Narrowest the other columns are truncated to.
+const MIN_COLUMN_WIDTH: usize = 4;
+
+/// Text of one table cell.
+fn cell(snippet: &SnippetMeta, column: &str) -> String {
+    match column {
+        "title" => snippet.title.clone(),
+        "language" => snippet.language.clone(),
+        "tags" => snippet.tags.join(","),
+        "created" => snippet
+            .created
+            .map(|created| created.format("%Y-%m-%d %H:%M").to_string())
+            .unwrap_or_default(),
+        "size" => snippet.stats.bytes.to_string(),
+        "lines" if snippet.stats.binary => "binary".to_string(),
+        "lines" => snippet.stats.lines.to_string(),
+        "words" => snippet.stats.words.to_string(),
+        "path" => snippet.path.display().to_string(),
+        _ => String::new(),
+    }
+}
+
+/// Renders `snippets` as tab-separated values, one snippet per line.
+/// - `header`: Start with a line of column names.
+pub fn render_tsv(snippets: &[SnippetMeta], columns: &[&str], header: bool) -> String {
+    let mut out = String::new();
+    if header {
+        out.push_str(&columns.join("\t"));
+        out.push('\n');
+    }
+    for snippet in snippets {
+        let row: Vec<String> = columns
+            .iter()
+            .map(|column| cell(snippet, column).replace(['\t', '\n'], " "))
+            .collect();
+        out.push_str(&row.join("\t"));
+        out.push('\n');
+    }
+    out
+}
+
+/// Renders `snippets` as an aligned table. When the table is wider than
+/// `width`, the title column is truncated to make it fit, then (once the title
+/// is down to [`MIN_TITLE_WIDTH`]) the tags, then the widest other columns.
+/// - `header`: Start with a line of upper-cased column names.
+pub fn render_table(
+    snippets: &[SnippetMeta],
+    columns: &[&str],
+    header: bool,
+    width: Option<usize>,
+) -> String {
+    let mut rows: Vec<Vec<String>> = snippets
+        .iter()
+        .map(|snippet| columns.iter().map(|column| cell(snippet, column)).collect())
+        .collect();
+    if header {
+        rows.insert(
+            0,
+            columns.iter().map(|column| column.to_uppercase()).collect(),
+        );
+    }
+
+    let mut widths: Vec<usize> = (0..columns.len())
+        .map(|index| {
+            rows.iter()
+                .map(|row| width::display_width(&row[index]))
+                .max()
+                .unwrap_or(0)
+        })
+        .collect();
+    if let Some(width) = width {
+        let total: usize = widths.iter().sum::<usize>() + 2 * columns.len().saturating_sub(1);
+        let mut excess = total.saturating_sub(width);
+        let title = columns.iter().position(|c| *c == "title");
+        let tags = columns.iter().position(|c| *c == "tags");
+        for (column, narrowest) in [(title, MIN_TITLE_WIDTH), (tags, MIN_COLUMN_WIDTH)] {
+            if let Some(column) = column {
+                let cut = excess.min(widths[column].saturating_sub(narrowest));
+                widths[column] -= cut;
+                excess -= cut;
+            }
+        }
+        while excess > 0 {
+            let widest = (0..widths.len())
+                .filter(|index| Some(*index) != title && widths[*index] > MIN_COLUMN_WIDTH)
+                .max_by_key(|index| widths[*index]);
+            let Some(widest) = widest else {
+                break;
+            };
+            widths[widest] -= 1;
+            excess -= 1;
+        }
+    }
+
+    let mut out = String::new();
+    for row in rows {
+        let cells: Vec<String> = row
+            .iter()
+            .zip(&widths)
+            .map(|(text, column)| width::pad(&width::truncate(text, *column), *column))
+            .collect();
+        out.push_str(cells.join("  ").trim_end());
+        out.push('\n');
+    }
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::snippet::SnippetStats;
+    use chrono::NaiveDate;
+    use std::path::PathBuf;
+
+    fn snippet(title: &str, language: &str, tags: &[&str], lines: usize) -> SnippetMeta {
+        SnippetMeta {
+            path: PathBuf::from(format!("/vault/{}.md", language)),
+            title: title.to_string(),
+            language: language.to_string(),
+            tags: tags.iter().map(|tag| tag.to_string()).collect(),
+            created: NaiveDate::from_ymd_opt(2024, 5, 17)
+                .and_then(|date| date.and_hms_opt(9, 30, 0)),
+            summary: None,
+            extra: Default::default(),
+            stats: SnippetStats {
+                lines,
+                ..Default::default()
+            },
+        }
+    }
+
+    fn table(width: usize) -> String {
+        let snippets = [
+            snippet(
+                "Undo the last commit but keep the changes staged for review",
+                "bash",
+                &["git", "undo"],
+                3,
+            ),
+            snippet(
+                "使用 git rebase 整理提交历史并推送到远程仓库",
+                "git",
+                &["git"],
+                12,
+            ),
+            snippet(
+                "Docker コンテナをすべて停止して削除する",
+                "docker",
+                &["docker", "cleanup"],
+                140,
+            ),
+        ];
+        render_table(
+            &snippets,
+            &["title", "language", "tags", "created", "lines"],
+            true,
+            Some(width),
+        )
+    }
+
+    fn widest_line(table: &str) -> usize {
+        table.lines().map(width::display_width).max().unwrap_or(0)
+    }
+
+    #[test]
+    fn table_at_60_columns() {
+        // The title stops shrinking at MIN_TITLE_WIDTH; the tags give up the rest.
+        let expected = "\
+TITLE       LANGUAGE  TAGS           CREATED           LINES
+Undo the …  bash      git,undo       2024-05-17 09:30  3
+使用 git …  git       git            2024-05-17 09:30  12
+Docker コ…  docker    docker,clean…  2024-05-17 09:30  140
+";
+        assert_eq!(table(60), expected);
+        assert!(widest_line(expected) <= 60);
+    }
+
+    #[test]
+    fn narrower_tables_cut_the_widest_columns() {
+        for width in [40, 50] {
+            assert!(widest_line(&table(width)) <= width, "{}", table(width));
+        }
+    }
+
+    #[test]
+    fn table_at_80_columns() {
+        let expected = "\
+TITLE                          LANGUAGE  TAGS            CREATED           LINES
+Undo the last commit but kee…  bash      git,undo        2024-05-17 09:30  3
+使用 git rebase 整理提交历史…  git       git             2024-05-17 09:30  12
+Docker コンテナをすべて停止…   docker    docker,cleanup  2024-05-17 09:30  140
+";
+        assert_eq!(table(80), expected);
+        assert_eq!(widest_line(expected), 80);
+    }
+
+    #[test]
+    fn table_at_120_columns() {
+        let expected = "\
+TITLE                                                        LANGUAGE  TAGS            CREATED           LINES
+Undo the last commit but keep the changes staged for review  bash      git,undo        2024-05-17 09:30  3
+使用 git rebase 整理提交历史并推送到远程仓库                 git       git             2024-05-17 09:30  12
+Docker コンテナをすべて停止して削除する                      docker    docker,cleanup  2024-05-17 09:30  140
+";
+        assert_eq!(table(120), expected);
+        assert!(widest_line(expected) <= 120);
+    }
+
+    #[test]
+    fn json_lists_the_snippets_no_fzf_prints() {
+        crate::testing::isolate_user_dirs();
+        let vault = tempfile::tempdir().unwrap();
+        for (name, language, title) in [
+            ("b.md", "rust", "Beta"),
+            ("a.md", "rust", "Alpha"),
+            ("c.md", "python", "Gamma"),
+        ] {
+            fs::write(
+                vault.path().join(name),
+                format!(
+                    "+++\ntitle = \"{}\"\nlanguage = \"{}\"\ntags = []\n+++\n\n```{}\nx\n```\n",
+                    title, language, language
+                ),
+            )
+            .unwrap();
+        }
+        let filter = SnippetFilter {
+            language: Some("rust".to_string()),
+            ..Default::default()
+        };
+        let extensions = vec!["md".to_string()];
+
+        for sort in [SortCriterion::Path, SortCriterion::Title] {
+            let plain =
+                list_snippets_plain(vault.path(), &filter, sort, &extensions, false).unwrap();
+            for show_hash in [false, true] {
+                let json =
+                    list_snippets_json(vault.path(), &filter, sort, &extensions, show_hash, false)
+                        .unwrap();
+                let listed: Vec<PathBuf> = serde_json::from_str::<Vec<serde_json::Value>>(&json)
+                    .unwrap()
+                    .iter()
+                    .map(|snippet| PathBuf::from(snippet["path"].as_str().unwrap()))
+                    .collect();
+                assert_eq!(listed, plain);
+            }
+        }
+        assert_eq!(
+            list_snippets_plain(
+                vault.path(),
+                &filter,
+                SortCriterion::Path,
+                &extensions,
+                false
+            )
+            .unwrap(),
+            [vault.path().join("a.md"), vault.path().join("b.md")]
+        );
+    }
+
+    /// A snippet at `name`.md created on day `day` of May 2024.
+    fn dated(name: &str, language: &str, tags: &[&str], day: u32) -> SnippetMeta {
+        SnippetMeta {
+            path: PathBuf::from(format!("/vault/{}.md", name)),
+            created: NaiveDate::from_ymd_opt(2024, 5, day)
+                .and_then(|date| date.and_hms_opt(9, 0, 0)),
+            ..snippet(name, language, tags, 1)
+        }
+    }
+
+    fn names(group: &SnippetGroup) -> Vec<&str> {
+        group
+            .snippets
+            .iter()
+            .map(|snippet| snippet.title.as_str())
+            .collect()
+    }
+
+    #[test]
+    fn language_groups_merge_aliases_and_label_missing_languages() {
+        let snippets = vec![
+            dated("venv", "python", &[], 1),
+            dated("lambda", "Py", &[], 3),
+            dated("loop", "PYTHON", &[], 2),
+            dated("note", "", &[], 4),
+            dated("undo", "bash", &[], 5),
+        ];
+        let aliases = HashMap::from([("py".to_string(), "python".to_string())]);
+        let listing = group_snippets(snippets, GroupBy::Language, None, &aliases);
+
+        assert_eq!(listing.total, 5);
+        let groups: Vec<(&str, usize)> = listing
+            .groups
+            .iter()
+            .map(|group| (group.name.as_str(), group.count))
+            .collect();
+        assert_eq!(groups, [(NO_LANGUAGE_GROUP, 1), ("bash", 1), ("python", 3)]);
+        assert_eq!(names(&listing.groups[2]), ["lambda", "loop", "venv"]);
+    }
+
+    #[test]
+    fn files_must_contain_every_pattern() {
+        let dir = tempfile::tempdir().unwrap();
+        fs::write(dir.path().join("a.md"), "docker run --rm alpine\n").unwrap();
+        fs::write(dir.path().join("b.md"), "docker ps\nrun the tests\n").unwrap();
+        fs::write(dir.path().join("c.md"), "podman run (alpine)\n").unwrap();
+        let extensions = vec!["md".to_string()];
+        let names = |patterns: &[&str]| -> Vec<String> {
+            files_containing(dir.path(), patterns, &extensions)
+                .unwrap()
+                .iter()
+                .map(|path| path.file_name().unwrap().to_string_lossy().into_owned())
+                .collect()
+        };
+
+        assert_eq!(names(&["docker run"]), ["a.md"]);
+        assert_eq!(names(&["run", "docker"]), ["a.md", "b.md"]);
+        assert_eq!(names(&["alpine", "run"]), ["a.md", "c.md"]);
+        assert_eq!(names(&["docker", "podman"]), Vec::<String>::new());
+        // An invalid regex is matched literally.
+        assert_eq!(names(&["run", "(alpine"]), ["c.md"]);
+    }
+
+    #[test]
+    fn tag_groups_repeat_snippets_and_limit_each_group() {
+        let snippets = vec![
+            dated("rebase", "git", &["git", "history"], 1),
+            dated("undo", "git", &["git"], 2),
+            dated("stash", "git", &["git"], 3),
+            dated("todo", "text", &[], 4),
+        ];
+        let listing = group_snippets(snippets, GroupBy::Tag, Some(2), &HashMap::new());
+
+        assert_eq!(listing.total, 4);
+        let groups: Vec<(&str, usize, Vec<&str>)> = listing
+            .groups
+            .iter()
+            .map(|group| (group.name.as_str(), group.count, names(group)))
+            .collect();
+        assert_eq!(
+            groups,
+            [
+                (UNTAGGED_GROUP, 1, vec!["todo"]),
+                ("git", 3, vec!["stash", "undo"]),
+                ("history", 1, vec!["rebase"]),
+            ]
+        );
+    }
+}
@@ -0,0 +1,148 @@
+use std::fs::{self, OpenOptions};
+use std::io::{self, Write};
+use std::path::{Path, PathBuf};
+use std::process;
+use std::sync::Mutex;
+use std::thread;
+use std::time::{Duration, Instant};
+
+use crate::error::SnippetVaultError;
+use crate::platform;
+
+/// How long a mutating command waits for another process to release the lock.
+pub const LOCK_TIMEOUT: Duration = Duration::from_secs(5);
+
+/// Pause between attempts to take a held lock.
+const RETRY_INTERVAL: Duration = Duration::from_millis(100);
+
+/// Guards held by this process; the lock file exists while this is non-zero,
+/// so nested mutating operations do not wait on themselves.
+static HELD: Mutex<usize> = Mutex::new(0);
+
+/// Advisory lock serialising changes to shared state (vault files, the metadata
+/// cache, git commits) across concurrent `snippets_vault` processes. The lock is
+/// a file holding the owner's PID; it is released when the last guard drops.
+/// Read-only commands never take it.
+#[derive(Debug)]
+pub struct VaultLock {
+    _private: (),
+}
+
+impl VaultLock {
+    /// Location of the lock file.
+    pub fn path() -> PathBuf {
+        dirs::cache_dir()
+            .unwrap_or_else(std::env::temp_dir)
+            .join("snippets_vault")
+            .join("lock")
+    }
+
+    /// Takes the lock, waiting up to [`LOCK_TIMEOUT`] for another process to
+    /// release it. A lock left behind by a process that is no longer running is
+    /// broken automatically.
+    pub fn acquire() -> Result<Self, SnippetVaultError> {
+        Self::acquire_within(LOCK_TIMEOUT)
+    }
+
+    /// Takes the lock only if it is free right now, for best-effort writes that
+    /// read-only commands make (such as saving the metadata cache).
+    pub fn try_acquire() -> Option<Self> {
+        Self::acquire_within(Duration::ZERO).ok()
+    }
+
+    fn acquire_within(timeout: Duration) -> Result<Self, SnippetVaultError> {
+        let mut held = HELD.lock().unwrap_or_else(|poisoned| poisoned.into_inner());
+        if *held == 0 {
+            take_lock_file(&Self::path(), timeout)?;
+        }
+        *held += 1;
+        Ok(VaultLock { _private: () })
+    }
+}
+
+impl Drop for VaultLock {
+    fn drop(&mut self) {
+        let mut held = HELD.lock().unwrap_or_else(|poisoned| poisoned.into_inner());
+        *held -= 1;
+        if *held == 0 {
+            let _ = fs::remove_file(Self::path());
+            tracing::debug!("vault lock released");
+        }
+    }
+}
+
+/// Creates the lock file at `path`, waiting up to `timeout` while another
+/// process holds it. A lock whose owner is no longer running, or one left
+/// empty for longer than [`LOCK_TIMEOUT`] by a writer that died before
+/// writing its PID, is broken.
+fn take_lock_file(path: &Path, timeout: Duration) -> Result<(), SnippetVaultError> {
+    if let Some(parent) = path.parent() {
+        fs::create_dir_all(parent)?;
+    }
+
+    let started = Instant::now();
+    loop {
+        match OpenOptions::new().write(true).create_new(true).open(path) {
+            Ok(mut file) => {
+                write!(file, "{}", process::id())?;
+                tracing::debug!(path = %path.display(), "vault lock taken");
+                return Ok(());
+            }
+            Err(err) if err.kind() == io::ErrorKind::AlreadyExists => {}
+            Err(err) => return Err(err.into()),
+        }
+
+        // An unreadable or empty file is a lock being written right now,
+        // unless it has stayed empty for longer than any writer takes.
+        let contents = fs::read_to_string(path).unwrap_or_default();
+        let owner = contents.trim().parse::<u32>().ok();
+        let abandoned = contents.trim().is_empty()
+            && fs::metadata(path)
+                .and_then(|metadata| metadata.modified())
+                .is_ok_and(|modified| modified.elapsed().is_ok_and(|age| age > LOCK_TIMEOUT));
+        let dead_owner = owner.filter(|pid| !platform::process_alive(*pid));
+        if dead_owner.is_some() || abandoned {
+            // Only remove the file if another process has not replaced it meanwhile.
+            if fs::read_to_string(path).is_ok_and(|current| current == contents) {
+                tracing::warn!(pid = ?dead_owner, "breaking stale vault lock");
+                let _ = fs::remove_file(path);
+            }
+            continue;
+        }
+
+        if started.elapsed() >= timeout {
+            return Err(SnippetVaultError::Locked(owner));
+        }
+        thread::sleep(RETRY_INTERVAL);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::time::SystemTime;
+
+    #[test]
+    fn an_empty_lock_file_is_stale_once_older_than_the_timeout() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("lock");
+
+        fs::write(&path, "").unwrap();
+        assert!(matches!(
+            take_lock_file(&path, Duration::ZERO),
+            Err(SnippetVaultError::Locked(None))
+        ));
+
+        fs::File::options()
+            .write(true)
+            .open(&path)
+            .unwrap()
+            .set_modified(SystemTime::now() - LOCK_TIMEOUT * 2)
+            .unwrap();
+        take_lock_file(&path, Duration::ZERO).unwrap();
+        assert_eq!(
+            fs::read_to_string(&path).unwrap(),
+            process::id().to_string()
+        );
+    }
+}
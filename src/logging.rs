@@ -0,0 +1,288 @@
+use chrono::Local;
+use std::fs::{self, OpenOptions};
+use std::io::{self, Write};
+use std::path::{Path, PathBuf};
+use std::process::{Command as ProcessCommand, ExitStatus, Output, Stdio};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Mutex;
+use tracing_subscriber::fmt::format::FmtSpan;
+use tracing_subscriber::EnvFilter;
+
+use crate::config::Config;
+use crate::style::Styled;
+
+/// Environment variable holding the log filter, e.g. `SNIPPETS_VAULT_LOG=debug`.
+pub const LOG_ENV: &str = "SNIPPETS_VAULT_LOG";
+
+/// File in the config directory holding the last error of an external command.
+pub const LAST_ERROR_FILE: &str = "last_error.txt";
+
+/// fzf's exit statuses for "nothing matched" and "dismissed", which are not errors.
+const PICKER_CANCEL_CODES: &[i32] = &[1, 130];
+
+static VERBOSE: AtomicBool = AtomicBool::new(false);
+
+/// Makes command failures print to stderr as they happen (`--verbose`), and
+/// leaves the stderr of [`run_quiet`] commands on the terminal instead of
+/// capturing it.
+pub fn set_verbose(verbose: bool) {
+    VERBOSE.store(verbose, Ordering::Relaxed);
+}
+
+fn is_verbose() -> bool {
+    VERBOSE.load(Ordering::Relaxed)
+}
+
+/// Location of [`LAST_ERROR_FILE`].
+pub fn last_error_path() -> PathBuf {
+    Config::path().with_file_name(LAST_ERROR_FILE)
+}
+
+/// Replaces the last error with `err`, stamped with the current time, for
+/// `--snippet_last_error`. Best effort: a file that cannot be written is
+/// logged, never returned.
+pub fn save_last_error(err: &str) {
+    if is_verbose() {
+        eprintln!("{} {}", "✘".error(), err);
+    }
+    let path = last_error_path();
+    let entry = format!(
+        "{}  {}\n",
+        Local::now().format("%Y-%m-%d %H:%M:%S"),
+        err.trim_end()
+    );
+    let written = path
+        .parent()
+        .map_or(Ok(()), fs::create_dir_all)
+        .and_then(|()| fs::write(&path, entry));
+    if let Err(err) = written {
+        tracing::warn!(path = %path.display(), %err, "cannot save the last error");
+    }
+}
+
+/// Records that `command` failed with `status`, adding the `stderr` it wrote
+/// when that was captured.
+fn record_failure(command: &ProcessCommand, status: &ExitStatus, stderr: &[u8]) {
+    let stderr = String::from_utf8_lossy(stderr);
+    let mut message = format!("{} exited with {}", command_line(command), status);
+    if !stderr.trim().is_empty() {
+        message.push_str(":\n");
+        message.push_str(stderr.trim_end());
+    }
+    save_last_error(&message);
+}
+
+/// Records that `command` could not be started.
+fn record_start_failure(command: &ProcessCommand, err: &io::Error) {
+    save_last_error(&format!(
+        "{} failed to start: {}",
+        command_line(command),
+        err
+    ));
+}
+
+/// Installs the global tracing subscriber.
+/// Logs go to `log_file` when given (defaulting to `debug` level), otherwise to stderr
+/// only if `SNIPPETS_VAULT_LOG` is set, so the interactive terminal is left alone by default.
+pub fn init(log_file: Option<&Path>) -> io::Result<()> {
+    let default_level = if log_file.is_some() { "debug" } else { "off" };
+    let filter = EnvFilter::try_from_env(LOG_ENV).unwrap_or_else(|_| EnvFilter::new(default_level));
+    let builder = tracing_subscriber::fmt()
+        .with_env_filter(filter)
+        .with_span_events(FmtSpan::CLOSE);
+
+    match log_file {
+        Some(path) => {
+            let file = OpenOptions::new().create(true).append(true).open(path)?;
+            builder
+                .with_ansi(false)
+                .with_writer(Mutex::new(file))
+                .init();
+        }
+        None => builder.with_writer(io::stderr).init(),
+    }
+    Ok(())
+}
+
+/// Runs `command` to completion, logging the program, its arguments, and the exit status.
+/// stderr stays attached to the terminal, so a failure is recorded with its
+/// exit status only.
+pub fn run(command: &mut ProcessCommand) -> io::Result<ExitStatus> {
+    let program = log_spawn(command);
+    let result = command.status();
+    match &result {
+        Ok(status) => {
+            tracing::debug!(%program, %status, "command finished");
+            if !status.success() {
+                record_failure(command, status, &[]);
+            }
+        }
+        Err(err) => {
+            tracing::warn!(%program, error = %err, "command failed to start");
+            record_start_failure(command, err);
+        }
+    }
+    result
+}
+
+/// Runs `command`, a program that needs no terminal, to completion with its
+/// stderr captured, so a failure is recorded with what the program said.
+/// With `--verbose` stderr is left on the terminal instead.
+pub fn run_quiet(command: &mut ProcessCommand) -> io::Result<ExitStatus> {
+    let program = log_spawn(command);
+    if !is_verbose() {
+        command.stderr(Stdio::piped());
+    }
+    let result = command.spawn().and_then(|child| child.wait_with_output());
+    match &result {
+        Ok(output) => {
+            tracing::debug!(%program, status = %output.status, "command finished");
+            if !output.status.success() {
+                record_failure(command, &output.status, &output.stderr);
+            }
+        }
+        Err(err) => {
+            tracing::warn!(%program, error = %err, "command failed to start");
+            record_start_failure(command, err);
+        }
+    }
+    result.map(|output| output.status)
+}
+
+/// Runs `command` with `input` on its stdin and captures its stdout; stderr stays
+/// attached to the terminal so interactive tools like fzf can draw their UI.
+/// Failures other than fzf's "no match" and "dismissed" statuses are recorded
+/// with their exit status.
+pub fn run_with_input(command: &mut ProcessCommand, input: &str) -> io::Result<Output> {
+    let program = log_spawn(command);
+    let result = command
+        .stdin(Stdio::piped())
+        .stdout(Stdio::piped())
+        .spawn()
+        .and_then(|mut child| {
+            if let Some(mut stdin) = child.stdin.take() {
+                // The child may exit before reading everything (e.g. fzf on Esc).
+                let _ = stdin.write_all(input.as_bytes());
+            }
+            child.wait_with_output()
+        });
+    match &result {
+        Ok(output) => {
+            tracing::debug!(%program, status = %output.status, "command finished");
+            record_picker_failure(command, &output.status);
+        }
+        Err(err) => {
+            tracing::warn!(%program, error = %err, "command failed to start");
+            record_start_failure(command, err);
+        }
+    }
+    result
+}
+
+/// Runs `command` as a filter with `input` on its stdin, capturing its stdout
+/// and stderr. The input is written from another thread, so a program that
+/// prints before it has read everything cannot block on a full pipe. Every
+/// non-zero status is a failure and is recorded with the captured stderr.
+pub fn run_filter(command: &mut ProcessCommand, input: &str) -> io::Result<Output> {
+    let program = log_spawn(command);
+    let result = command
+        .stdin(Stdio::piped())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped())
+        .spawn()
+        .and_then(|mut child| {
+            let stdin = child.stdin.take();
+            let input = input.to_string();
+            let writer = std::thread::spawn(move || {
+                if let Some(mut stdin) = stdin {
+                    // A program that exits early closes the pipe; its status says why.
+                    let _ = stdin.write_all(input.as_bytes());
+                }
+            });
+            let output = child.wait_with_output();
+            let _ = writer.join();
+            output
+        });
+    match &result {
+        Ok(output) => {
+            tracing::debug!(%program, status = %output.status, "command finished");
+            if !output.status.success() {
+                record_failure(command, &output.status, &output.stderr);
+            }
+        }
+        Err(err) => {
+            tracing::warn!(%program, error = %err, "command failed to start");
+            record_start_failure(command, err);
+        }
+    }
+    result
+}
+
+/// Runs `command` with `input` on its stdin and its stdout and stderr attached to
+/// the terminal, for pagers.
+pub fn run_with_stdin(command: &mut ProcessCommand, input: &str) -> io::Result<ExitStatus> {
+    let program = log_spawn(command);
+    let result = command.stdin(Stdio::piped()).spawn().and_then(|mut child| {
+        if let Some(mut stdin) = child.stdin.take() {
+            // The pager may quit before reading everything; that is not an error.
+            let _ = stdin.write_all(input.as_bytes());
+        }
+        child.wait()
+    });
+    match &result {
+        Ok(status) => tracing::debug!(%program, %status, "command finished"),
+        Err(err) => tracing::warn!(%program, error = %err, "command failed to start"),
+    }
+    result
+}
+
+/// Runs `command` with stdin and stderr attached to the terminal and captures
+/// its stdout, for shell pipelines that end in an interactive picker. Failures
+/// are recorded like those of [`run_with_input`].
+pub fn run_capture(command: &mut ProcessCommand) -> io::Result<Output> {
+    let program = log_spawn(command);
+    let result = command
+        .stdout(Stdio::piped())
+        .spawn()
+        .and_then(|child| child.wait_with_output());
+    match &result {
+        Ok(output) => {
+            tracing::debug!(%program, status = %output.status, "command finished");
+            record_picker_failure(command, &output.status);
+        }
+        Err(err) => {
+            tracing::warn!(%program, error = %err, "command failed to start");
+            record_start_failure(command, err);
+        }
+    }
+    result
+}
+
+/// Records a failed picker run, unless fzf only reported that nothing was chosen.
+fn record_picker_failure(command: &ProcessCommand, status: &ExitStatus) {
+    let cancelled = status
+        .code()
+        .is_some_and(|code| PICKER_CANCEL_CODES.contains(&code));
+    if !status.success() && !cancelled {
+        record_failure(command, status, &[]);
+    }
+}
+
+/// The program and its arguments, for error messages.
+fn command_line(command: &ProcessCommand) -> String {
+    std::iter::once(command.get_program())
+        .chain(command.get_args())
+        .map(|part| part.to_string_lossy())
+        .collect::<Vec<_>>()
+        .join(" ")
+}
+
+fn log_spawn(command: &ProcessCommand) -> String {
+    let program = command.get_program().to_string_lossy().into_owned();
+    let args: Vec<String> = command
+        .get_args()
+        .map(|arg| arg.to_string_lossy().into_owned())
+        .collect();
+    tracing::debug!(%program, ?args, "spawning command");
+    program
+}
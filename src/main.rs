@@ -1,13 +1,87 @@
-use chrono::Local;
-use clap::{Arg, Command};
+mod batch;
+mod bulk;
+mod cache;
+mod clipboard;
+mod complete;
+mod config;
+mod conflicts;
+mod context;
+mod convert;
+mod dates;
+mod detect;
+mod digest;
+mod duplicate;
+mod editor;
+mod error;
+mod expiry;
+mod export;
+mod fields;
+mod format;
+mod fuzzy;
+mod gist;
+mod git;
+mod graph;
+mod hooks;
+mod housekeep;
+mod import;
+mod index;
+mod indexer;
+mod info;
+mod language;
+mod lint;
+mod listing;
+mod lock;
+mod logging;
+mod merge;
+mod migrate;
+mod motd;
+mod notion;
+mod obsidian;
+mod pager;
+mod pet;
+mod picker;
+mod placeholder;
+mod platform;
+mod prompt;
+mod rename;
+mod render;
+mod replace;
+mod s3;
+mod serve;
+mod setup;
+mod snippet;
+mod spelling;
+mod style;
+mod suggest;
+mod tags;
+#[cfg(test)]
+mod testing;
+mod uri;
+mod usage;
+mod vault;
+mod webdav;
+mod width;
+mod words;
+
+use chrono::{Local, NaiveDate, NaiveDateTime};
+use clap::{Arg, ArgAction, ArgGroup, Command};
 use colored::*;
-use std::env;
-use std::fs;
-use std::path::Path;
+use std::collections::{HashMap, HashSet};
+use std::io::{IsTerminal, Write};
+use std::path::{Path, PathBuf};
 use std::process::Command as ProcessCommand;
 
+use config::Config;
+use error::SnippetVaultError;
+use migrate::migrate_v1_to_v2;
+use pager::Pager;
+use snippet::TIMESTAMP_FORMAT;
+use style::Styled;
+use vault::{Executor, Mutation};
+
 /// Directory where snippets are stored. Customize this as needed.
 const SNIPPET_DIR: &str = "Documents/myObsidianDoc/mysnippetsCollection";
+const VERSION: &str = env!("CARGO_PKG_VERSION");
 
 /// Entry point of the application.
 /// Defines available subcommands and routes the input to appropriate handlers.
@@ -16,24 +90,1313 @@ const SNIPPET_DIR: &str = "Documents/myObsidianDoc/mysnippetsCollection";
 ///
 fn main() {
     let matches = Command::new("SnippetsVault")
-        .version("0.2.0")
+        .version(VERSION)
         .author("Ghasak Ibrahim")
         .about("A secure and organized vault for managing your code snippets")
+        .arg(
+            Arg::new("no_cache")
+                .long("no-cache")
+                .global(true)
+                .action(ArgAction::SetTrue)
+                .help("Parse every snippet instead of using the metadata cache"),
+        )
+        .arg(
+            Arg::new("dry_run")
+                .long("dry-run")
+                .visible_alias("dry_run")
+                .global(true)
+                .action(ArgAction::SetTrue)
+                .help("Print every change a command would make, without making it"),
+        )
+        .arg(
+            Arg::new("format_on_save")
+                .long("format-on-save")
+                .visible_alias("format_on_save")
+                .global(true)
+                .action(ArgAction::SetTrue)
+                .help("Run code blocks through their language's formatter after editing (see format_on_save)"),
+        )
+        .arg(
+            Arg::new("readonly")
+                .long("readonly")
+                .global(true)
+                .action(ArgAction::SetTrue)
+                .help("Refuse every change to the vault; listing, searching, copying, and exporting still work"),
+        )
+        .arg(
+            Arg::new("editor")
+                .long("editor")
+                .global(true)
+                .value_name("CMD")
+                .help("Open files with CMD for this run, overriding the configured editors"),
+        )
+        .arg(
+            Arg::new("vault")
+                .long("vault")
+                .global(true)
+                .value_name("DIR")
+                .help("Use DIR as the vault for this run (overrides $SNIPPETS_VAULT_DIR and vault_dir)"),
+        )
+        .arg(
+            Arg::new("non_interactive")
+                .long("non-interactive")
+                .visible_alias("non_interactive")
+                .global(true)
+                .action(ArgAction::SetTrue)
+                .help("Never start fzf, an editor, a pager, or a prompt; fail instead when one is needed"),
+        )
+        .arg(
+            Arg::new("skip_hooks")
+                .long("skip_hooks")
+                .visible_alias("skip-hooks")
+                .global(true)
+                .action(ArgAction::SetTrue)
+                .help("Do not run the commands configured under [hooks]"),
+        )
+        .arg(
+            Arg::new("status_theme")
+                .long("theme")
+                .visible_aliases(["status-theme", "status_theme"])
+                .global(true)
+                .value_name("THEME")
+                .value_parser(style::PRESETS.to_vec())
+                .help("Color status output with THEME (high-contrast avoids red and green)"),
+        )
+        .arg(
+            Arg::new("snippet_color_theme")
+                .long("snippet_color_theme")
+                .visible_alias("color-theme")
+                .global(true)
+                .value_name("BACKGROUND")
+                .value_parser(style::TerminalTheme::NAMES.to_vec())
+                .help("Style glow, bat, and status colors for a dark or light terminal, or guess (auto)"),
+        )
+        .arg(
+            Arg::new("verbose")
+                .long("verbose")
+                .global(true)
+                .action(ArgAction::SetTrue)
+                .help("Print the errors of external commands to stderr as they happen"),
+        )
+        .arg(
+            Arg::new("no_track")
+                .long("no-track")
+                .visible_alias("no_track")
+                .global(true)
+                .action(ArgAction::SetTrue)
+                .help("Do not count copying, running, or getting a snippet as a use"),
+        )
+        .arg(
+            Arg::new("no_pager")
+                .long("no-pager")
+                .visible_alias("no_pager")
+                .global(true)
+                .action(ArgAction::SetTrue)
+                .help("Print long reports directly instead of through $PAGER"),
+        )
+        .arg(
+            Arg::new("width")
+                .long("width")
+                .global(true)
+                .value_name("COLS")
+                .value_parser(clap::value_parser!(u16).range(1..))
+                .help("Format tables and wrapped text for COLS columns instead of the terminal width"),
+        )
+        .arg(
+            Arg::new("fzf_arg")
+                .long("fzf-arg")
+                .visible_alias("fzf_arg")
+                .global(true)
+                .value_name("FLAG")
+                .action(ArgAction::Append)
+                .allow_hyphen_values(true)
+                .help("Pass FLAG to fzf for this run, after fzf_args from the config (repeatable)"),
+        )
+        .arg(
+            Arg::new("preview_size")
+                .long("preview_size")
+                .visible_alias("preview-size")
+                .global(true)
+                .value_name("SIZE")
+                .value_parser(|size: &str| {
+                    picker::validate_preview_size(size).map(|()| size.to_string())
+                })
+                .help("Size the fzf preview for this run, e.g. right:50% (overrides fzf_preview_size)"),
+        )
+        .arg(
+            Arg::new("log_file")
+                .long("log-file")
+                .global(true)
+                .value_name("PATH")
+                .help("Write debug logs to PATH (filter with SNIPPETS_VAULT_LOG)"),
+        )
         .subcommand(
             Command::new("--create_snippet")
                 .about("Create a new snippet")
-                .arg(Arg::new("language").required(true))
-                .arg(Arg::new("tags").num_args(1..)),
+                .arg(
+                    Arg::new("language")
+                        .required_unless_present_any(["file", "stdin", "from_clipboard"])
+                        .help("Snippet language; omit (or pass `auto`) to detect it from the content"),
+                )
+                .arg(Arg::new("tags").num_args(1..))
+                .arg(
+                    Arg::new("plain_file")
+                        .long("plain-file")
+                        .visible_alias("plain_file")
+                        .action(ArgAction::SetTrue)
+                        .help("Create a bare code file (e.g. .py) instead of a markdown snippet"),
+                )
+                .arg(
+                    Arg::new("placeholder_style")
+                        .long("placeholder_style")
+                        .conflicts_with("plain_file")
+                        .value_parser(snippet::PlaceholderStyle::NAMES.to_vec())
+                        .default_value("none")
+                        .help("Leave $1/$2/$0 tab stops for LuaSnip or vim-vsnip in the new snippet"),
+                )
+                .arg(
+                    Arg::new("file")
+                        .long("file")
+                        .value_name("PATH")
+                        .help("Use the contents of PATH as the snippet's code"),
+                )
+                .arg(
+                    Arg::new("stdin")
+                        .long("stdin")
+                        .action(ArgAction::SetTrue)
+                        .help("Read the snippet's code from stdin"),
+                )
+                .arg(
+                    Arg::new("from_clipboard")
+                        .long("from-clipboard")
+                        .visible_alias("from_clipboard")
+                        .action(ArgAction::SetTrue)
+                        .help("Use the clipboard text as the snippet's code"),
+                )
+                .group(
+                    ArgGroup::new("source")
+                        .args(["file", "stdin", "from_clipboard"])
+                        .conflicts_with("plain_file"),
+                )
+                .arg(
+                    Arg::new("yes")
+                        .long("yes")
+                        .action(ArgAction::SetTrue)
+                        .help("Accept a detected language without asking"),
+                ),
+        )
+        .subcommand(
+            Command::new("--snippet_clipboard_create")
+                .about("Create a snippet from the clipboard contents")
+                .arg(
+                    Arg::new("language")
+                        .help("Snippet language; omit (or pass `auto`) to detect it from the content"),
+                )
+                .arg(Arg::new("tags").num_args(1..))
+                .arg(
+                    Arg::new("no_editor")
+                        .long("no_editor")
+                        .action(ArgAction::SetTrue)
+                        .help("Do not open the new snippet in the editor"),
+                )
+                .arg(
+                    Arg::new("yes")
+                        .long("yes")
+                        .action(ArgAction::SetTrue)
+                        .help("Accept a detected language without asking"),
+                ),
+        )
+        .subcommand(
+            Command::new("--list_snippets")
+                .about("List all snippets")
+                .arg(
+                    Arg::new("query")
+                        .num_args(1..)
+                        .help("Initial fzf query (fzf search syntax, see below)"),
+                )
+                .arg(
+                    Arg::new("json")
+                        .long("json")
+                        .action(ArgAction::SetTrue)
+                        .help("Print snippet metadata (with line, word, and byte counts) as JSON"),
+                )
+                .arg(
+                    Arg::new("show_content_hash")
+                        .long("show_content_hash")
+                        .visible_alias("snippet_show_content_hash")
+                        .requires("json")
+                        .action(ArgAction::SetTrue)
+                        .help("Add a SHA-256 of each snippet's code blocks to the JSON"),
+                )
+                .arg(
+                    Arg::new("no_fzf")
+                        .long("no_fzf")
+                        .action(ArgAction::SetTrue)
+                        .help("Print one snippet path per line instead of launching fzf"),
+                )
+                .arg(
+                    Arg::new("table")
+                        .long("table")
+                        .action(ArgAction::SetTrue)
+                        .help("Print an aligned table (tab-separated when piped, unless --width is given)"),
+                )
+                .arg(
+                    Arg::new("count")
+                        .long("count")
+                        .action(ArgAction::SetTrue)
+                        .help("Print only the number of matching snippets (exit 1 when zero)"),
+                )
+                .arg(
+                    Arg::new("compact")
+                        .long("compact")
+                        .visible_alias("snippet_compact_view")
+                        .action(ArgAction::SetTrue)
+                        .conflicts_with("output")
+                        .help("Show id, language, tags, and title on one fzf line per snippet"),
+                )
+                .group(ArgGroup::new("output").args(["json", "no_fzf", "table", "count"]))
+                .arg(multi_arg().conflicts_with("output"))
+                .arg(
+                    Arg::new("group_by")
+                        .long("group-by")
+                        .visible_alias("group_by")
+                        .value_parser(listing::GROUP_BY.to_vec())
+                        .conflicts_with_all(["json", "no_fzf", "table", "count", "compact", "multi"])
+                        .help("Print sections per language or tag, newest first within each"),
+                )
+                .arg(
+                    Arg::new("format")
+                        .long("format")
+                        .requires("group_by")
+                        .value_parser(["text", "json"])
+                        .default_value("text")
+                        .help("Print the groups as text or as nested JSON"),
+                )
+                .arg(
+                    Arg::new("columns")
+                        .long("columns")
+                        .requires("table")
+                        .value_delimiter(',')
+                        .value_parser(listing::TABLE_COLUMNS.to_vec())
+                        .help("Comma-separated table columns, in order"),
+                )
+                .arg(
+                    Arg::new("no_header")
+                        .long("no-header")
+                        .visible_alias("no_header")
+                        .requires("table")
+                        .action(ArgAction::SetTrue)
+                        .help("Omit the table's column headers"),
+                )
+                .args(filter_args())
+                .arg(
+                    Arg::new("sort_by")
+                        .long("sort_by")
+                        .visible_alias("sort")
+                        .value_parser(listing::SORT_CRITERIA.to_vec())
+                        .default_value("path")
+                        .help("Order of the listing"),
+                )
+                .arg(
+                    Arg::new("natural_sort")
+                        .long("natural_sort")
+                        .visible_alias("snippet_natural_sort")
+                        .action(ArgAction::SetTrue)
+                        .help("Sort by path with numbers compared by value (same as --sort_by natural)"),
+                )
+                .arg(vscode_arg())
+                .arg(open_with_arg())
+                .arg(
+                    Arg::new("limit")
+                        .long("limit")
+                        .value_name("N")
+                        .value_parser(clap::value_parser!(usize))
+                        .help("List at most N snippets, after sorting"),
+                ),
+        )
+        .subcommand(
+            Command::new("--snippet_rename_batch")
+                .about("Rename snippets listed as old_path<TAB>new_path lines, all or none")
+                .arg(
+                    Arg::new("spec_file")
+                        .required(true)
+                        .help("TSV file of renames; relative paths are taken from the vault"),
+                )
+                .arg(
+                    Arg::new("overwrite")
+                        .long("overwrite")
+                        .action(ArgAction::SetTrue)
+                        .help("Replace destination files that already exist"),
+                ),
+        )
+        .subcommand(
+            Command::new("--snippet_bulk")
+                .alias("bulk")
+                .about("Apply one action to every snippet matching a filter")
+                .args(filter_args())
+                .arg(
+                    Arg::new("yes")
+                        .long("yes")
+                        .global(true)
+                        .action(ArgAction::SetTrue)
+                        .help("Do not ask for confirmation"),
+                )
+                .subcommand_required(true)
+                .subcommand(
+                    Command::new("retag")
+                        .about("Add or remove tags")
+                        .arg(
+                            Arg::new("add")
+                                .long("add")
+                                .action(ArgAction::Append)
+                                .help("Tag to add (repeatable)"),
+                        )
+                        .arg(
+                            Arg::new("remove")
+                                .long("remove")
+                                .action(ArgAction::Append)
+                                .help("Tag to remove (repeatable)"),
+                        )
+                        .group(
+                            ArgGroup::new("change")
+                                .args(["add", "remove"])
+                                .multiple(true)
+                                .required(true),
+                        ),
+                )
+                .subcommand(Command::new("archive").about("Mark as archived"))
+                .subcommand(Command::new("delete").about("Delete the files"))
+                .subcommand(
+                    Command::new("export")
+                        .about("Copy into a directory")
+                        .arg(Arg::new("output_dir").required(true)),
+                )
+                .subcommand(Command::new("print").about("Print the matching paths")),
+        )
+        .subcommand(
+            Command::new("--snippet_regex_replace")
+                .alias("replace")
+                .about("Replace a regex in the code blocks of every snippet matching a filter")
+                .arg(Arg::new("pattern").required(true))
+                .arg(
+                    Arg::new("replacement")
+                        .required(true)
+                        .help("Replacement text; $1 or ${name} refer to capture groups"),
+                )
+                .args(filter_args()),
+        )
+        .subcommand(
+            Command::new("--replace_in_files")
+                .about("Replace text in every snippet matching a filter, after showing a diff")
+                .arg(Arg::new("pattern").required(true))
+                .arg(
+                    Arg::new("replacement")
+                        .required(true)
+                        .help("Replacement text; with --regex, $1 or ${name} refer to capture groups"),
+                )
+                .arg(
+                    Arg::new("regex")
+                        .long("regex")
+                        .action(ArgAction::SetTrue)
+                        .help("Treat the pattern as a regular expression instead of literal text"),
+                )
+                .arg(
+                    Arg::new("include_archived")
+                        .long("include_archived")
+                        .visible_alias("include-archived")
+                        .action(ArgAction::SetTrue)
+                        .help("Also edit snippets marked `archived = true`"),
+                )
+                .arg(
+                    Arg::new("yes")
+                        .long("yes")
+                        .short('y')
+                        .action(ArgAction::SetTrue)
+                        .help("Apply every change without asking"),
+                )
+                .args(filter_args()),
+        )
+        .subcommand(
+            Command::new("--snippet_summary")
+                .about("Print a snippet's one-line description (its first line of prose)")
+                .arg(Arg::new("file").required(true)),
+        )
+        .subcommand(
+            Command::new("--largest")
+                .about("Show the biggest snippets by line count")
+                .arg(
+                    Arg::new("count")
+                        .default_value("10")
+                        .value_parser(clap::value_parser!(usize)),
+                ),
+        )
+        .subcommand(
+            Command::new("--snippet_word_frequency")
+                .alias("words")
+                .about("Show the most common words in the prose of matching snippets")
+                .arg(
+                    Arg::new("count")
+                        .default_value("20")
+                        .value_parser(clap::value_parser!(usize)),
+                )
+                .arg(
+                    Arg::new("min_length")
+                        .long("min_length")
+                        .visible_alias("min-length")
+                        .value_name("N")
+                        .default_value("3")
+                        .value_parser(clap::value_parser!(usize))
+                        .help("Skip words shorter than N characters"),
+                )
+                .args(filter_args()),
+        )
+        .subcommand(
+            Command::new("--snippet_context_search")
+                .alias("context")
+                .about("Pick among snippets in the language of the current git project or tagged with its name"),
+        )
+        .subcommand(
+            Command::new("--snippet_grep_tags")
+                .alias("grep_tags")
+                .about("Pick among the snippets carrying both of two tags")
+                .arg(Arg::new("tag_a").required(true))
+                .arg(Arg::new("tag_b").required(true))
+                .arg(
+                    Arg::new("count_only")
+                        .long("count_only")
+                        .visible_alias("count-only")
+                        .action(ArgAction::SetTrue)
+                        .help("Print only the number of matching snippets"),
+                ),
+        )
+        .subcommand(
+            Command::new("--edit_snippet")
+                .alias("edit")
+                .about("Edit a snippet using fuzzy finder")
+                .arg(
+                    Arg::new("query")
+                        .num_args(1..)
+                        .help("Open the best match directly when it is unambiguous"),
+                )
+                .arg(
+                    Arg::new("pick")
+                        .long("pick")
+                        .action(ArgAction::SetTrue)
+                        .help("Print the selected paths instead of opening them"),
+                )
+                .arg(multi_arg())
+                .arg(vscode_arg().conflicts_with("pick"))
+                .arg(open_with_arg().conflicts_with("pick")),
+        )
+        .subcommand(
+            Command::new("--snippet_open_in_vscode")
+                .alias("--open_in_vscode")
+                .about("Open a snippet in Visual Studio Code")
+                .arg(Arg::new("file").help("Snippet to open; omit to pick one with fzf")),
+        )
+        .subcommand(
+            Command::new("--snippet_create_from_selection")
+                .visible_alias("--extract_selection")
+                .about("Select lines of a snippet in neovim and save them as a new snippet (fzf ctrl-x)")
+                .arg(Arg::new("file").help("Snippet to extract from; omit to pick one with fzf")),
+        )
+        .subcommand(
+            Command::new("--snippet_language_docs")
+                .alias("--language_docs")
+                .about("Open the official documentation of a snippet's language in the browser")
+                .arg(Arg::new("file").help("Snippet to look up; omit to pick one with fzf")),
+        )
+        .subcommand(
+            Command::new("--copy_snippet")
+                .alias("copy")
+                .about("Copy a snippet's code blocks to the clipboard")
+                .arg(
+                    Arg::new("file")
+                        .conflicts_with("multi")
+                        .help("Snippet to copy; omit to pick one with fzf"),
+                )
+                .arg(multi_arg())
+                .arg(param_arg()),
+        )
+        .subcommand(
+            Command::new("--get")
+                .about("Print the code of the snippet whose `alias` field is ALIAS")
+                .arg(Arg::new("alias").required(true))
+                .arg(param_arg()),
+        )
+        .subcommand(
+            Command::new("--exec")
+                .about("Run a shell snippet's code")
+                .arg(
+                    Arg::new("file")
+                        .help("Snippet to run; omit to pick one with fzf"),
+                )
+                .arg(param_arg()),
+        )
+        .subcommand(
+            Command::new("--delete_snippet")
+                .alias("--delete")
+                .about("Delete a snippet")
+                .arg(
+                    Arg::new("file")
+                        .conflicts_with("multi")
+                        .help("Snippet to delete; omit to pick one with fzf"),
+                )
+                .arg(multi_arg())
+                .arg(
+                    Arg::new("yes")
+                        .long("yes")
+                        .action(ArgAction::SetTrue)
+                        .help("Do not ask for confirmation"),
+                ),
+        )
+        .subcommand(
+            Command::new("--version")
+                .about("Show version information")
+                .arg(
+                    Arg::new("json")
+                        .long("json")
+                        .action(ArgAction::SetTrue)
+                        .help("Print version, commit, build date, config path, and vault path as JSON"),
+                ),
+        )
+        .subcommand(
+            Command::new("--check-version")
+                .alias("--check_version")
+                .about("Exit 0 if this version satisfies a semver requirement, 1 otherwise")
+                .arg(
+                    Arg::new("requirement")
+                        .required(true)
+                        .help("Semver requirement, e.g. \">=0.2\" or \"^0.2.1\""),
+                ),
+        )
+        .subcommand(
+            Command::new("--cat_snippet")
+                .about("Print a snippet with highlighted code blocks")
+                .arg(
+                    Arg::new("file")
+                        .help("Snippet to print; omit to pick one with fzf"),
+                ),
+        )
+        .subcommand(
+            Command::new("--info")
+                .about("Show the metadata of one snippet")
+                .arg(
+                    Arg::new("id")
+                        .long("id")
+                        .value_name("ID")
+                        .conflicts_with("file")
+                        .help("Snippet whose filename timestamp (or file stem) is ID"),
+                )
+                .arg(
+                    Arg::new("file")
+                        .long("file")
+                        .value_name("PATH")
+                        .help("Show PATH instead of picking a snippet"),
+                )
+                .arg(
+                    Arg::new("json")
+                        .long("json")
+                        .action(ArgAction::SetTrue)
+                        .help("Print the metadata as one JSON object"),
+                ),
+        )
+        .subcommand(
+            Command::new("--suggest")
+                .about("Suggest snippets for complex commands repeated in the shell history")
+                .arg(
+                    Arg::new("count")
+                        .default_value("20")
+                        .value_parser(clap::value_parser!(usize))
+                        .help("Offer at most this many commands"),
+                )
+                .arg(
+                    Arg::new("history")
+                        .long("history")
+                        .value_name("FILE")
+                        .help("Read FILE instead of the configured shell history"),
+                )
+                .arg(
+                    Arg::new("min_count")
+                        .long("min_count")
+                        .visible_alias("min-count")
+                        .value_name("N")
+                        .default_value("2")
+                        .value_parser(clap::value_parser!(u64).range(1..))
+                        .help("Skip commands run fewer than N times"),
+                ),
+        )
+        .subcommand(
+            Command::new("--snippet_render_terminal")
+                .alias("render")
+                .about("Render a snippet's markdown for the terminal without glow")
+                .arg(
+                    Arg::new("file")
+                        .help("Snippet to render; omit to pick one with fzf"),
+                ),
+        )
+        .subcommand(
+            Command::new("--motd")
+                .alias("motd")
+                .visible_alias("--snippet_of_the_day")
+                .about("Print today's snippet as one dim line, for a shell rc file or MOTD")
+                .arg(
+                    Arg::new("full")
+                        .long("full")
+                        .action(ArgAction::SetTrue)
+                        .help("Render the whole snippet instead of its title and first code line"),
+                ),
         )
-        .subcommand(Command::new("--list_snippets").about("List all snippets"))
-        .subcommand(Command::new("--edit_snippet").about("Edit a snippet using fuzzy finder"))
-        .subcommand(Command::new("--version").about("Show version information"))
         .subcommand(Command::new("--languages").about("Show supported languages"))
+        .subcommand(
+            Command::new("--list_aliases")
+                .about("Show the language aliases from the [language_aliases] config section"),
+        )
         .subcommand(
             Command::new("--find_in_files")
+                .alias("--snippet_search_and_open")
                 .about("Search for a string in files and preview results with fuzzy finder")
-                .arg(Arg::new("search_term").required(true)),
+                .arg(Arg::new("search_term").required(true))
+                .arg(
+                    Arg::new("count")
+                        .long("count")
+                        .action(ArgAction::SetTrue)
+                        .help("Print only the number of matching files (exit 1 when zero)"),
+                )
+                .arg(
+                    Arg::new("limit")
+                        .long("limit")
+                        .value_name("N")
+                        .value_parser(clap::value_parser!(usize))
+                        .help("Pass at most N matching files to fzf"),
+                )
+                .arg(
+                    Arg::new("context")
+                        .long("context")
+                        .value_name("N")
+                        .value_parser(clap::value_parser!(u64).range(0..=config::MAX_SEARCH_CONTEXT as u64))
+                        .help("Lines of context around each match in the preview (default 10)"),
+                )
+                .args(created_range_args()),
+        )
+        .subcommand(
+            Command::new("--snippet_search_recent_and_create")
+                .alias("--search_or_create")
+                .about("Search snippet contents; offer to create a snippet when nothing matches")
+                .arg(
+                    Arg::new("query")
+                        .required(true)
+                        .num_args(1..)
+                        .help("Search terms; a new snippet takes its language and tags from them"),
+                )
+                .arg(
+                    Arg::new("yes")
+                        .long("yes")
+                        .short('y')
+                        .action(ArgAction::SetTrue)
+                        .help("Create the snippet without asking when nothing matches"),
+                ),
+        )
+        .subcommand(
+            Command::new("--snippet_backup_to_s3")
+                .about("Zip the vault and upload the archive to the [s3_backup] bucket")
+                .arg(remote_arg())
+                .arg(
+                    Arg::new("prune_remote")
+                        .long("prune-remote")
+                        .visible_alias("prune_remote")
+                        .value_name("N")
+                        .value_parser(clap::value_parser!(u64).range(1..))
+                        .help("After uploading, delete all but the newest N backups in the bucket"),
+                ),
+        )
+        .subcommand(
+            Command::new("--snippet_restore_from_s3")
+                .visible_alias("--restore_from_s3")
+                .about("Download a vault backup from the [s3_backup] bucket and extract it into the vault")
+                .arg(Arg::new("backup").help(
+                    "Archive to restore (its file name, or latest); omit to choose from a list",
+                ))
+                .arg(remote_arg())
+                .arg(
+                    Arg::new("list")
+                        .long("list")
+                        .action(ArgAction::SetTrue)
+                        .conflicts_with("backup")
+                        .help("List the backups in the bucket, newest first, and restore nothing"),
+                ),
+        )
+        .subcommand(
+            Command::new("--snippet_sync")
+                .visible_alias("--sync")
+                .about("Two-way sync of the vault with the [webdav] server (Nextcloud, ownCloud, ...)")
+                .arg(
+                    Arg::new("backend")
+                        .required(true)
+                        .value_parser(["webdav"])
+                        .help("Where to sync to"),
+                )
+                .arg(
+                    Arg::new("push_only")
+                        .long("push-only")
+                        .visible_alias("push_only")
+                        .action(ArgAction::SetTrue)
+                        .conflicts_with("pull_only")
+                        .help("Only upload new and changed local snippets"),
+                )
+                .arg(
+                    Arg::new("pull_only")
+                        .long("pull-only")
+                        .visible_alias("pull_only")
+                        .action(ArgAction::SetTrue)
+                        .help("Only download new and changed remote snippets"),
+                ),
+        )
+        .subcommand(
+            Command::new("--snippet_migrate_v2")
+                .about("Upgrade the vault from v1 (no frontmatter) to v2 (TOML frontmatter)")
+                .arg(
+                    Arg::new("skip_backup")
+                        .long("skip_backup")
+                        .action(ArgAction::SetTrue)
+                        .help("Do not zip the vault before migrating"),
+                ),
+        )
+        .subcommand(
+            Command::new("--export_obsidian")
+                .about("Export snippets as Obsidian-compatible notes")
+                .arg(Arg::new("output_dir").required(true))
+                .arg(
+                    Arg::new("overwrite")
+                        .long("overwrite")
+                        .action(ArgAction::SetTrue)
+                        .help("Re-export into an existing output directory"),
+                ),
+        )
+        .subcommand(
+            Command::new("--export_html")
+                .about("Export snippets as a static HTML site")
+                .arg(Arg::new("output_dir").required(true))
+                .arg(
+                    Arg::new("overwrite")
+                        .long("overwrite")
+                        .action(ArgAction::SetTrue)
+                        .help("Re-export into an existing output directory"),
+                ),
+        )
+        .subcommand(
+            Command::new("--cheatsheet")
+                .about("Compile every snippet of a language into one document, sorted by title")
+                .arg(Arg::new("language").required(true))
+                .arg(
+                    Arg::new("tag_filter")
+                        .long("tag")
+                        .visible_alias("tag_filter")
+                        .action(ArgAction::Append)
+                        .help("Only snippets carrying this tag or a sub-tag of it (repeatable)"),
+                )
+                .arg(
+                    Arg::new("output")
+                        .long("output")
+                        .short('o')
+                        .value_name("FILE")
+                        .help("Write the cheatsheet to FILE instead of printing it"),
+                )
+                .arg(
+                    Arg::new("format")
+                        .long("format")
+                        .value_parser(["markdown", "html"])
+                        .default_value("markdown"),
+                ),
+        )
+        .subcommand(
+            Command::new("--snippet_bulk_export_by_language")
+                .about("Export one file per language containing all of its snippets")
+                .arg(Arg::new("output_dir").required(true))
+                .arg(
+                    Arg::new("format")
+                        .long("format")
+                        .value_parser(export::ExportFormat::NAMES.to_vec())
+                        .default_value("markdown"),
+                )
+                .arg(
+                    Arg::new("overwrite")
+                        .long("overwrite")
+                        .action(ArgAction::SetTrue)
+                        .help("Re-export into an existing output directory"),
+                ),
+        )
+        .subcommand(
+            Command::new("--snippet_export_csv")
+                .about("Export snippet metadata (not content) as a CSV file")
+                .arg(Arg::new("output").required(true))
+                .arg(
+                    Arg::new("full")
+                        .long("full")
+                        .action(ArgAction::SetTrue)
+                        .help("Also count words and code lines (reads every file)"),
+                ),
+        )
+        .subcommand(
+            Command::new("--snippet_import_csv")
+                .about("Create or update snippets from a CSV in the --snippet_export_csv layout")
+                .arg(Arg::new("csv").required(true))
+                .arg(
+                    Arg::new("mode")
+                        .long("mode")
+                        .value_parser(import::ImportMode::NAMES.to_vec())
+                        .default_value("upsert")
+                        .help("Only create new snippets, only update existing ones, or both"),
+                ),
+        )
+        .subcommand(
+            Command::new("--import")
+                .about("Import snippets from another snippet manager")
+                .arg(Arg::new("format").required(true).value_parser(["pet"]))
+                .arg(
+                    Arg::new("path")
+                        .required(true)
+                        .help("The file to import, e.g. pet's snippet.toml"),
+                ),
+        )
+        .subcommand(
+            Command::new("--export")
+                .about("Export snippets for another snippet manager")
+                .arg(Arg::new("format").required(true).value_parser(["pet"]))
+                .arg(
+                    Arg::new("output")
+                        .long("output")
+                        .short('o')
+                        .value_name("FILE")
+                        .help("Write to FILE instead of stdout"),
+                ),
+        )
+        .subcommand(
+            Command::new("--snippet_export_notion")
+                .about("Print a snippet as Notion API blocks (JSON)")
+                .arg(
+                    Arg::new("file")
+                        .required_unless_present("all")
+                        .help("Snippet to export"),
+                )
+                .arg(
+                    Arg::new("output")
+                        .long("output")
+                        .value_name("FILE")
+                        .conflicts_with("all")
+                        .help("Write the JSON to FILE instead of stdout"),
+                )
+                .arg(
+                    Arg::new("all")
+                        .long("all")
+                        .value_name("DIR")
+                        .conflicts_with("file")
+                        .help("Write one JSON file per snippet into DIR"),
+                ),
+        )
+        .subcommand(
+            Command::new("--snippet_serve_static")
+                .about("Serve the vault as HTML at localhost:PORT, rebuilding on changes")
+                .arg(
+                    Arg::new("port")
+                        .default_value("8080")
+                        .value_parser(clap::value_parser!(u16)),
+                )
+                .arg(
+                    Arg::new("watch_index")
+                        .long("watch_index")
+                        .action(ArgAction::SetTrue)
+                        .help("Also keep the snippet index up to date while serving"),
+                ),
+        )
+        .subcommand(
+            Command::new("--snippet_file_watcher_index")
+                .visible_alias("--watch_index")
+                .about("Rebuild the snippet index, then keep it up to date as files change until Ctrl-C"),
+        )
+        .subcommand(
+            Command::new("--snippet_lint")
+                .about("Check snippet code blocks for common anti-patterns")
+                .arg(Arg::new("file").help("Snippet to lint"))
+                .arg(
+                    Arg::new("all")
+                        .long("all")
+                        .action(ArgAction::SetTrue)
+                        .conflicts_with("file")
+                        .help("Lint every snippet in the vault"),
+                )
+                .arg(
+                    Arg::new("fix")
+                        .long("fix")
+                        .action(ArgAction::SetTrue)
+                        .help("Format code blocks with their language's formatter before linting"),
+                ),
+        )
+        .subcommand(
+            Command::new("--spellcheck")
+                .visible_alias("--snippet_spellcheck")
+                .about("Check the spelling of snippet prose, skipping code blocks and inline code")
+                .arg(Arg::new("file").help("Snippet to check"))
+                .arg(
+                    Arg::new("all")
+                        .long("all")
+                        .action(ArgAction::SetTrue)
+                        .conflicts_with("file")
+                        .help("Check every markdown snippet in the vault"),
+                )
+                .group(
+                    ArgGroup::new("target")
+                        .args(["file", "all"])
+                        .required(true),
+                )
+                .arg(
+                    Arg::new("interactive")
+                        .long("interactive")
+                        .short('i')
+                        .action(ArgAction::SetTrue)
+                        .help("Ask for each word whether to add it to the vault dictionary, fix it, or skip it"),
+                ),
+        )
+        .subcommand(
+            Command::new("--snippet_dedup_tags")
+                .about("Remove repeated tags from a snippet's filename and frontmatter")
+                .arg(Arg::new("file").help("Snippet to fix; omit to pick one with fzf"))
+                .arg(
+                    Arg::new("all")
+                        .long("all")
+                        .action(ArgAction::SetTrue)
+                        .conflicts_with("file")
+                        .help("Fix every snippet in the vault"),
+                ),
+        )
+        .subcommand(
+            Command::new("--snippet_interactive_tag_editor")
+                .alias("--edit_tags")
+                .about("Edit a snippet's tags on a prompt with tag completion")
+                .arg(Arg::new("file").help("Snippet to retag; omit to pick one with fzf")),
+        )
+        .subcommand(
+            Command::new("--snippet_diff_tags")
+                .about("Preview which snippets renaming a tag would affect")
+                .arg(Arg::new("old_tag").required(true))
+                .arg(Arg::new("new_tag").required(true)),
+        )
+        .subcommand(
+            Command::new("--snippet_tag_rename_cascade")
+                .visible_alias("--rename_tag")
+                .about("Rename a tag in every snippet and in the tag filters of _collections/*.toml")
+                .arg(Arg::new("old_tag").required(true))
+                .arg(Arg::new("new_tag").required(true)),
+        )
+        .subcommand(
+            Command::new("--snippet_weekly_digest")
+                .alias("--weekly_digest")
+                .about("Summarize the snippets created and edited this week")
+                .arg(
+                    Arg::new("period")
+                        .long("period")
+                        .value_name("DAYS")
+                        .default_value("7")
+                        .value_parser(clap::value_parser!(u32))
+                        .help("Length of the window in days"),
+                )
+                .arg(
+                    Arg::new("markdown")
+                        .long("markdown")
+                        .action(ArgAction::SetTrue)
+                        .help("Print the digest as GitHub Flavored Markdown"),
+                ),
+        )
+        .subcommand(
+            Command::new("--snippet_title_index")
+                .about("Rebuild the full-text index of snippet titles and tags"),
+        )
+        .subcommand(
+            Command::new("--find_snippet_by_title")
+                .about("Search snippet titles and tags, pick a match with fzf, and open it")
+                .arg(Arg::new("query").required(true).num_args(1..)),
+        )
+        .subcommand(
+            Command::new("--snippet_github_sync")
+                .about("Clone or pull a snippet vault from a GitHub repository")
+                .arg(Arg::new("repo_url").required(true))
+                .arg(
+                    Arg::new("dest")
+                        .help("Where to clone the vault (defaults to the snippet directory)"),
+                )
+                .arg(
+                    Arg::new("token")
+                        .long("token")
+                        .help("GitHub token for private repositories (default: $GITHUB_TOKEN)"),
+                ),
+        )
+        .subcommand(
+            Command::new("--snippet_dependency_graph")
+                .about("Print a Graphviz DOT graph of the links between snippets")
+                .arg(
+                    Arg::new("output")
+                        .long("output")
+                        .value_name("FILE")
+                        .help("Write the DOT graph to FILE instead of stdout"),
+                )
+                .arg(
+                    Arg::new("render")
+                        .long("render")
+                        .value_parser(["png", "svg"])
+                        .help("Render the graph with Graphviz `dot` and open the image"),
+                ),
+        )
+        .subcommand(
+            Command::new("--snippet_convert_language")
+                .about("Change a snippet's language, re-fencing its code blocks and renaming it")
+                .arg(Arg::new("file").required(true))
+                .arg(Arg::new("new_language").required(true))
+                .arg(
+                    Arg::new("keep_fences")
+                        .long("keep_fences")
+                        .action(ArgAction::SetTrue)
+                        .help("Leave the code fence languages unchanged"),
+                ),
+        )
+        .subcommand(
+            Command::new("--duplicate_snippet")
+                .visible_alias("--snippet_duplicate")
+                .about("Copy a snippet into a new one as a starting point and edit it")
+                .arg(Arg::new("file").help("Snippet to copy; omit to pick one with fzf"))
+                .arg(
+                    Arg::new("title")
+                        .long("title")
+                        .help("Title of the copy (default: asks, suggesting \"Copy of <title>\")"),
+                )
+                .arg(
+                    Arg::new("tags")
+                        .long("tags")
+                        .value_delimiter(',')
+                        .help("Comma-separated tags of the copy (default: asks, starting from the original's)"),
+                ),
+        )
+        .subcommand(
+            Command::new("--snippet_merge")
+                .about("Combine two or more snippets into a new one")
+                .arg(Arg::new("sources").required(true).num_args(2..))
+                .arg(
+                    Arg::new("language")
+                        .long("language")
+                        .help("Language of the merged snippet (default: the first source's)"),
+                )
+                .arg(
+                    Arg::new("tags")
+                        .long("tags")
+                        .value_delimiter(',')
+                        .help("Comma-separated tags (default: every source's tags)"),
+                )
+                .arg(
+                    Arg::new("delete_sources")
+                        .long("delete_sources")
+                        .action(ArgAction::SetTrue)
+                        .help("Delete the merged snippets, asking for each"),
+                )
+                .arg(
+                    Arg::new("force")
+                        .long("force")
+                        .requires("delete_sources")
+                        .action(ArgAction::SetTrue)
+                        .help("Delete the sources without asking"),
+                ),
+        )
+        .subcommand(
+            Command::new("--set_field")
+                .about("Set a custom frontmatter field on a snippet picked with fzf")
+                .arg(Arg::new("key").required(true))
+                .arg(Arg::new("value").required(true))
+                .arg(
+                    Arg::new("file")
+                        .long("file")
+                        .value_name("PATH")
+                        .help("Edit PATH instead of picking a snippet"),
+                ),
+        )
+        .subcommand(
+            Command::new("--unset_field")
+                .about("Remove a custom frontmatter field from a snippet picked with fzf")
+                .arg(Arg::new("key").required(true))
+                .arg(
+                    Arg::new("file")
+                        .long("file")
+                        .value_name("PATH")
+                        .help("Edit PATH instead of picking a snippet"),
+                ),
+        )
+        .subcommand(
+            Command::new("--snippet_expiry")
+                .about("Set the day a snippet picked with fzf expires")
+                .arg(
+                    Arg::new("date")
+                        .required(true)
+                        .value_parser(dates::parse_day)
+                        .help("Expiry day, as YYYY-MM-DD"),
+                )
+                .arg(
+                    Arg::new("file")
+                        .long("file")
+                        .value_name("PATH")
+                        .help("Edit PATH instead of picking a snippet"),
+                ),
+        )
+        .subcommand(Command::new("--find_expired").about("List snippets past their expiry day"))
+        .subcommand(
+            Command::new("--find_expiring_soon")
+                .about("List snippets that expire within the given number of days")
+                .arg(
+                    Arg::new("days")
+                        .required(true)
+                        .value_parser(clap::value_parser!(u64)),
+                ),
+        )
+        .subcommand(
+            Command::new("--housekeep")
+                .about("Run the maintenance tasks enabled under [housekeeping] and summarize them")
+                .arg(
+                    Arg::new("yes")
+                        .long("yes")
+                        .short('y')
+                        .action(ArgAction::SetTrue)
+                        .help("Delete empty snippets without asking"),
+                ),
+        )
+        .subcommand(
+            Command::new("--open_dir")
+                .about("Open the vault directory in the system file manager")
+                .arg(
+                    Arg::new("snippet")
+                        .long("snippet")
+                        .action(ArgAction::SetTrue)
+                        .help("Pick a snippet with fzf and show that file instead"),
+                ),
+        )
+        .subcommand(
+            Command::new("--snippet_open_link")
+                .about("Open the URL in a snippet's ### Link: section in the browser")
+                .arg(
+                    Arg::new("file")
+                        .help("Snippet to open the link of; omit to pick one with fzf"),
+                ),
+        )
+        .subcommand(
+            Command::new("--snippet_convert_to_gist")
+                .alias("gist")
+                .about("Share a snippet's code block as a GitHub Gist and link it from the snippet")
+                .arg(
+                    Arg::new("file")
+                        .help("Snippet to share; omit to pick one with fzf"),
+                )
+                .arg(
+                    Arg::new("public")
+                        .long("public")
+                        .action(ArgAction::SetTrue)
+                        .help("Create a public gist (secret by default)"),
+                )
+                .arg(
+                    Arg::new("token")
+                        .long("token")
+                        .help("GitHub token with the gist scope (default: $GITHUB_TOKEN)"),
+                )
+                .arg(
+                    Arg::new("open")
+                        .long("open")
+                        .action(ArgAction::SetTrue)
+                        .help("Open the new gist in the browser"),
+                ),
+        )
+        .subcommand(
+            Command::new("--path")
+                .about("Print the vault directory, e.g. for cd \"$(snippets_vault --path)\""),
+        )
+        .subcommand(
+            Command::new("--copy_path")
+                .about("Copy a snippet's absolute path to the clipboard (fzf ctrl-y)")
+                .arg(Arg::new("file").required(true)),
+        )
+        .subcommand(
+            Command::new("--open_snippet")
+                .about("Open a snippet file in its editor (fzf ctrl-o)")
+                .arg(Arg::new("file").required(true)),
+        )
+        .subcommand(
+            Command::new("__complete")
+                .hide(true)
+                .about("Print completion candidates for shell widgets")
+                .long_about(
+                    "Print completion candidates for shell widgets, read from the metadata \
+                     cache without starting other programs.\n\n\
+                     Output: one candidate per line on stdout, UTF-8, nothing else. \
+                     Candidates start with PREFIX (compared case-insensitively), appear \
+                     once each, and are sorted by their lowercase form, then byte by byte, \
+                     whatever the locale. Tabs and line breaks inside a candidate are \
+                     printed as spaces. No match prints nothing and exits 0; errors go to \
+                     stderr with exit status 1.",
+                )
+                .subcommand_required(true)
+                .subcommand(complete_command("titles", "Complete snippet titles"))
+                .subcommand(complete_command(
+                    "tags",
+                    "Complete tags, including the parents of dotted tags",
+                )),
+        )
+        .subcommand(Command::new("--setup").about("Configure the vault location and editor"))
+        .subcommand(Command::new("--config_init").about(
+            "Write a configuration file with the default vault and editor, without asking",
+        ))
+        .subcommand(Command::new("--tags").about("List all tags with their snippet counts"))
+        .subcommand(
+            Command::new("--stats")
+                .about("Summarize the vault and list the ten most used snippets"),
+        )
+        .subcommand(
+            Command::new("--snippet_resolve_conflicts")
+                .visible_alias("--resolve_conflicts")
+                .about("Compare sync conflict copies with their originals and keep one, or merge them in the editor"),
+        )
+        .subcommand(
+            Command::new("--snippet_stats_per_day_of_week")
+                .visible_alias("--stats_by_weekday")
+                .about("Chart how many snippets were created on each day of the week")
+                .arg(
+                    Arg::new("normalize")
+                        .long("normalize")
+                        .action(ArgAction::SetTrue)
+                        .help("Show each day as a percentage of all dated snippets"),
+                ),
+        )
+        .subcommand(
+            Command::new("--snippet_last_error")
+                .visible_alias("--last_error")
+                .about("Show the last error of an external command, such as fzf, git, or the opener"),
+        )
+        .subcommand(
+            Command::new("--clear_last_error").about("Forget the error --snippet_last_error shows"),
+        )
+        .subcommand(
+            Command::new("--tag_tree")
+                .about("Show dotted tags (rust.async) as a tree with snippet counts"),
+        )
+        .subcommand(Command::new("--cache_clear").about("Delete the snippet metadata cache"))
+        .subcommand(
+            Command::new("--snippet_custom_hooks")
+                .alias("--hooks")
+                .about("Show the commands configured under [hooks]"),
+        )
+        .subcommand(
+            Command::new("--batch")
+                .about("Answer newline-delimited JSON requests from stdin with one JSON line each")
+                .arg(
+                    Arg::new("describe")
+                        .long("describe")
+                        .action(ArgAction::SetTrue)
+                        .help("Print the supported commands and their fields as JSON and exit"),
+                ),
         )
+        .subcommand(Command::new("--doctor").about("Check the configuration and external tools"))
         .after_help(
             r#"
 NOTES:
@@ -61,273 +1424,5690 @@ NOTES:
 
         .get_matches();
 
-    let timestamp = Local::now().format("%Y-%m-%d-%H%M%S").to_string();
+    let log_file = matches.get_one::<String>("log_file").map(PathBuf::from);
+    if let Err(err) = logging::init(log_file.as_deref()) {
+        println!("{} Cannot open log file: {}", "✘".error(), err);
+    }
+
+    let use_cache = !matches.get_flag("no_cache");
+    let executor = Executor::new(matches.get_flag("dry_run"));
+    if matches.get_flag("non_interactive") {
+        prompt::set_non_interactive();
+    }
+    logging::set_verbose(matches.get_flag("verbose"));
+
+    let mut config = match Config::load() {
+        Ok(config) => config,
+        Err(err) => {
+            println!("{} {}", "✘".error(), err);
+            std::process::exit(1);
+        }
+    };
+
+    let wants_setup = matches.subcommand_name() == Some("--setup");
+    // `--path` and `--get` are meant for `$(...)`, where prompts would be captured,
+    // and fzf runs the key binding commands without a usable terminal.
+    let onboarding = matches.subcommand_name().is_some_and(|name| {
+        ![
+            "--path",
+            "--get",
+            "--copy_path",
+            "--open_snippet",
+            "--snippet_create_from_selection",
+            "--batch",
+            "--motd",
+            "--config_init",
+            "__complete",
+        ]
+        .contains(&name)
+    }) && setup::needs_onboarding();
+    if wants_setup || onboarding {
+        if wants_setup || prompt::is_interactive() {
+            match setup::run_setup(&executor) {
+                Ok(configured) => config = configured,
+                Err(err) => println!("{} Setup failed: {}", "✘".error(), err),
+            }
+        } else {
+            eprintln!(
+                "{} No configuration found; using defaults. Run `snippets_vault --config_init` to write one.",
+                "•".accent()
+            );
+        }
+    }
+
+    if let Some(editor) = matches.get_one::<String>("editor") {
+        config.editor_override = Some(editor.clone());
+    }
+    config.no_pager = matches.get_flag("no_pager") || matches.get_flag("non_interactive");
+    config.skip_hooks = matches.get_flag("skip_hooks");
+    config.no_track = matches.get_flag("no_track");
+    config.format_on_save |= matches.get_flag("format_on_save");
+    config.status_theme = matches.get_one::<String>("status_theme").cloned();
+    if let Some(theme) = matches
+        .get_one::<String>("snippet_color_theme")
+        .and_then(|name| style::TerminalTheme::from_name(name))
+    {
+        config.terminal_theme = theme;
+    }
+    let (palette, problems) = config.palette();
+    style::set_palette(palette);
+    for problem in problems {
+        tracing::warn!(%problem, "ignoring part of status_colors");
+    }
+    config.width_override = matches
+        .get_one::<u16>("width")
+        .map(|width| usize::from(*width));
+    snippet::set_parse_limit(config.max_parse_size());
+    config.fzf_args.extend(
+        matches
+            .get_many::<String>("fzf_arg")
+            .unwrap_or_default()
+            .cloned(),
+    );
+    if let Some(size) = matches.get_one::<String>("preview_size") {
+        config.fzf_preview_size = Some(size.clone());
+    }
+    if let Some(dir) = matches.get_one::<String>("vault") {
+        config.vault_dir = Some(dir.clone());
+    } else if let Some(dir) = std::env::var(vault::VAULT_DIR_ENV)
+        .ok()
+        .filter(|dir| !dir.is_empty())
+    {
+        config.vault_dir = Some(dir);
+    }
+    if matches.get_flag("readonly") || config.readonly {
+        vault::set_read_only(&vault::snippet_dir(&config));
+    }
+
+    tracing::debug!(
+        config_path = %Config::path().display(),
+        snippet_dir = %vault::snippet_dir(&config).display(),
+        editor = %editor::default_editor(&config),
+        "resolved configuration"
+    );
+    let now = Local::now().naive_local();
+    let timestamp =
+        snippet::render_timestamp(&now, config.timestamp_format()).unwrap_or_else(|err| {
+            tracing::warn!(%err, "ignoring timestamp_format");
+            now.format(TIMESTAMP_FORMAT).to_string()
+        });
+
+    let _span = tracing::info_span!(
+        "subcommand",
+        name = matches.subcommand_name().unwrap_or("none")
+    )
+    .entered();
 
     match matches.subcommand() {
         Some(("--create_snippet", sub_matches)) => {
-            let language = sub_matches.get_one::<String>("language").unwrap();
+            let language = sub_matches.get_one::<String>("language");
+            let tags: Vec<&str> = sub_matches
+                .get_many::<String>("tags")
+                .unwrap_or_default()
+                .map(|s| s.as_str())
+                .collect();
+            let plain_file = sub_matches.get_flag("plain_file");
+            let placeholders = sub_matches
+                .get_one::<String>("placeholder_style")
+                .and_then(|name| snippet::PlaceholderStyle::from_name(name))
+                .unwrap_or_default();
+            let file = sub_matches.get_one::<String>("file").map(PathBuf::from);
+
+            let code = if let Some(file) = &file {
+                std::fs::read_to_string(file).map_err(SnippetVaultError::from)
+            } else if sub_matches.get_flag("stdin") {
+                std::io::read_to_string(std::io::stdin()).map_err(SnippetVaultError::from)
+            } else if sub_matches.get_flag("from_clipboard") {
+                clipboard::read_clipboard()
+            } else {
+                Ok(String::new())
+            };
+            let resolved = code.and_then(|code| {
+                let language = choose_language(
+                    &config,
+                    language.map(|s| s.as_str()),
+                    &code,
+                    file.as_deref(),
+                    sub_matches.get_flag("yes"),
+                )?;
+                Ok((language, code))
+            });
+
+            match resolved {
+                Ok((language, code)) => {
+                    create_snippet(
+                        &config,
+                        &language,
+                        &tags,
+                        &timestamp,
+                        plain_file,
+                        &code,
+                        placeholders,
+                        &executor,
+                    );
+                    push_after_edit(&config, &executor);
+                }
+                Err(err) => println!("{} {}", "✘".error(), err),
+            }
+        }
+        Some(("--snippet_clipboard_create", sub_matches)) => {
+            let language = sub_matches
+                .get_one::<String>("language")
+                .map(|s| s.as_str());
             let tags: Vec<&str> = sub_matches
                 .get_many::<String>("tags")
                 .unwrap_or_default()
                 .map(|s| s.as_str())
                 .collect();
+            let open_editor = !sub_matches.get_flag("no_editor");
+            let yes = sub_matches.get_flag("yes");
+
+            if let Err(err) = clipboard_create(
+                &config,
+                language,
+                &tags,
+                &timestamp,
+                open_editor,
+                yes,
+                &executor,
+            ) {
+                println!("{} {}", "✘".error(), err);
+            } else if open_editor {
+                push_after_edit(&config, &executor);
+            }
+        }
+        Some(("--list_snippets", sub_matches)) => {
+            let filter = snippet_filter(sub_matches);
+            let sort = if sub_matches.get_flag("natural_sort") {
+                listing::SortCriterion::Natural
+            } else {
+                sub_matches
+                    .get_one::<String>("sort_by")
+                    .and_then(|name| listing::SortCriterion::from_name(name))
+                    .unwrap_or_default()
+            };
+            let query = sub_matches
+                .get_many::<String>("query")
+                .map(|words| words.map(|s| s.as_str()).collect::<Vec<_>>().join(" "));
+            let multi = sub_matches.get_flag("multi");
+            let opener = snippet_opener(&config, sub_matches);
+
+            if let Some(group_by) = sub_matches
+                .get_one::<String>("group_by")
+                .and_then(|name| listing::GroupBy::from_name(name))
+            {
+                let json =
+                    sub_matches.get_one::<String>("format").map(String::as_str) == Some("json");
+                list_snippets_grouped(&config, &filter, group_by, json, use_cache);
+            } else if sub_matches.get_flag("json") {
+                let show_hash = sub_matches.get_flag("show_content_hash");
+                list_snippets_json(&config, &filter, sort, show_hash, use_cache);
+            } else if sub_matches.get_flag("table") {
+                let columns: Vec<&str> = match sub_matches.get_many::<String>("columns") {
+                    Some(columns) => columns.map(|column| column.as_str()).collect(),
+                    None => listing::DEFAULT_TABLE_COLUMNS.to_vec(),
+                };
+                let header = !sub_matches.get_flag("no_header");
+                list_snippets_table(&config, &filter, sort, &columns, header, use_cache);
+            } else if sub_matches.get_flag("no_fzf") {
+                list_snippets_no_fzf(&config, &filter, sort, use_cache);
+            } else if sub_matches.get_flag("count") {
+                count_snippets(&config, &filter, use_cache);
+            } else if sub_matches.get_flag("compact") {
+                pick_snippet_compact(
+                    &config,
+                    &filter,
+                    sort,
+                    query.as_deref(),
+                    multi,
+                    opener,
+                    use_cache,
+                    &executor,
+                );
+                push_after_edit(&config, &executor);
+            } else if filter.is_active()
+                || sort != listing::SortCriterion::Path
+                || opener != editor::Opener::Editor
+            {
+                pick_snippet(
+                    &config,
+                    &filter,
+                    sort,
+                    query.as_deref(),
+                    multi,
+                    opener,
+                    use_cache,
+                    &executor,
+                );
+                push_after_edit(&config, &executor);
+            } else {
+                list_snippets(&config, query.as_deref(), multi, &executor);
+                push_after_edit(&config, &executor);
+            }
+        }
+        Some(("--snippet_rename_batch", sub_matches)) => {
+            let spec_file = PathBuf::from(sub_matches.get_one::<String>("spec_file").unwrap());
+            let overwrite = sub_matches.get_flag("overwrite");
+            match rename::rename_batch(
+                &spec_file,
+                &vault::snippet_dir(&config),
+                overwrite,
+                &executor,
+            ) {
+                Ok((_, errors)) if !errors.is_empty() => {
+                    for error in &errors {
+                        println!("{} {}", "✘".error(), error);
+                    }
+                    println!("{} Nothing was renamed.", "•".accent());
+                    std::process::exit(1);
+                }
+                Ok(_) if executor.is_dry_run() => {}
+                Ok((renamed, _)) => {
+                    println!("{} Renamed {} snippets.", "✔".success(), renamed);
+                    push_after_edit(&config, &executor);
+                }
+                Err(err) => {
+                    println!("{} {}: {}", "✘".error(), spec_file.display(), err);
+                    std::process::exit(1);
+                }
+            }
+        }
+        Some(("--snippet_bulk", sub_matches)) => {
+            let filter = snippet_filter(sub_matches);
+            let yes = sub_matches.get_flag("yes");
+            let action = match sub_matches.subcommand() {
+                Some(("retag", action_matches)) => {
+                    let tags = |id: &str| -> Vec<String> {
+                        action_matches
+                            .get_many::<String>(id)
+                            .unwrap_or_default()
+                            .cloned()
+                            .collect()
+                    };
+                    bulk::BulkAction::Retag {
+                        add: tags("add"),
+                        remove: tags("remove"),
+                    }
+                }
+                Some(("archive", _)) => bulk::BulkAction::Archive,
+                Some(("delete", _)) => bulk::BulkAction::Delete,
+                Some(("export", action_matches)) => bulk::BulkAction::Export(PathBuf::from(
+                    action_matches.get_one::<String>("output_dir").unwrap(),
+                )),
+                _ => bulk::BulkAction::Print,
+            };
+            if let Err(err) = bulk_apply(&config, &filter, &action, yes, use_cache, &executor) {
+                println!("{} {}", "✘".error(), err);
+            }
+        }
+        Some(("--snippet_regex_replace", sub_matches)) => {
+            let pattern = sub_matches.get_one::<String>("pattern").unwrap();
+            let replacement = sub_matches.get_one::<String>("replacement").unwrap();
+            match replace::regex_replace_in_snippets(
+                &vault::snippet_dir(&config),
+                pattern,
+                replacement,
+                &snippet_filter(sub_matches),
+                &config.extensions(),
+                use_cache,
+                &executor,
+            ) {
+                Ok((files, count)) if executor.is_dry_run() => println!(
+                    "{} {} matches in {} snippets would be replaced.",
+                    "•".accent(),
+                    count,
+                    files
+                ),
+                Ok((files, count)) => {
+                    println!(
+                        "{} Replaced {} matches in {} snippets.",
+                        "✔".success(),
+                        count,
+                        files
+                    );
+                    if files > 0 {
+                        push_after_edit(&config, &executor);
+                    }
+                }
+                Err(err) => println!("{} {}", "✘".error(), err),
+            }
+        }
+        Some(("--replace_in_files", sub_matches)) => {
+            let pattern = sub_matches.get_one::<String>("pattern").unwrap();
+            let replacement = sub_matches.get_one::<String>("replacement").unwrap();
+            let regex = sub_matches.get_flag("regex");
+            let include_archived = sub_matches.get_flag("include_archived");
+            let yes = sub_matches.get_flag("yes");
+            match replace_in_files(
+                &config,
+                pattern,
+                replacement,
+                regex,
+                &snippet_filter(sub_matches),
+                include_archived,
+                yes,
+                use_cache,
+                &executor,
+            ) {
+                Ok(true) => push_after_edit(&config, &executor),
+                Ok(false) => {}
+                Err(err) => {
+                    println!("{} {}", "✘".error(), err);
+                    std::process::exit(1);
+                }
+            }
+        }
+        Some(("--snippet_summary", sub_matches)) => {
+            let file = sub_matches.get_one::<String>("file").unwrap();
+            match std::fs::read_to_string(file) {
+                Ok(content) => {
+                    if let Some(summary) = snippet::extract_summary(&content) {
+                        println!("{}", summary);
+                    }
+                }
+                Err(err) => println!("{} {}: {}", "✘".error(), file, err),
+            }
+        }
+        Some(("--largest", sub_matches)) => {
+            let count = *sub_matches.get_one::<usize>("count").unwrap();
+            largest_snippets(&config, count, use_cache);
+        }
+        Some(("--snippet_word_frequency", sub_matches)) => {
+            let count = *sub_matches.get_one::<usize>("count").unwrap();
+            let min_length = *sub_matches.get_one::<usize>("min_length").unwrap();
+            word_frequency(
+                &config,
+                count,
+                min_length,
+                &snippet_filter(sub_matches),
+                use_cache,
+            );
+        }
+        Some(("--snippet_context_search", _)) => {
+            context_search(&config, use_cache, &executor);
+            push_after_edit(&config, &executor);
+        }
+        Some(("--snippet_grep_tags", sub_matches)) => {
+            let tag_a = sub_matches.get_one::<String>("tag_a").unwrap();
+            let tag_b = sub_matches.get_one::<String>("tag_b").unwrap();
+            let count_only = sub_matches.get_flag("count_only");
+            grep_tags(&config, tag_a, tag_b, count_only, use_cache, &executor);
+        }
+        Some(("--edit_snippet", sub_matches)) => {
+            let query: Vec<&str> = sub_matches
+                .get_many::<String>("query")
+                .unwrap_or_default()
+                .map(|s| s.as_str())
+                .collect();
+            let query = (!query.is_empty()).then(|| query.join(" "));
+            let pick = sub_matches.get_flag("pick");
+            let multi = sub_matches.get_flag("multi");
+            let opener = snippet_opener(&config, sub_matches);
 
-            create_snippet(language, &tags, &timestamp);
+            match edit_snippet(
+                &config,
+                query.as_deref(),
+                multi,
+                pick,
+                opener,
+                use_cache,
+                &executor,
+            ) {
+                Ok(true) if !pick => push_after_edit(&config, &executor),
+                Ok(_) => {}
+                Err(err) => println!("{} {}", "✘".error(), err),
+            }
+        }
+        Some(("--snippet_open_in_vscode", sub_matches)) => {
+            let file = sub_matches.get_one::<String>("file").map(PathBuf::from);
+            if let Err(err) = open_in_vscode(&config, file, use_cache, &executor) {
+                println!("{} {}", "✘".error(), err);
+            }
+        }
+        Some(("--snippet_create_from_selection", sub_matches)) => {
+            let file = sub_matches.get_one::<String>("file").map(PathBuf::from);
+            if let Err(err) = extract_selection(&config, file, &timestamp, use_cache, &executor) {
+                println!("{} {}", "✘".error(), err);
+            }
+        }
+        Some(("--snippet_language_docs", sub_matches)) => {
+            let file = sub_matches.get_one::<String>("file").map(PathBuf::from);
+            let Some(path) =
+                file.or_else(|| choose_snippet(&config, &config.extensions(), use_cache))
+            else {
+                return;
+            };
+            if let Err(err) = open_language_docs(&config, &path) {
+                println!("{} {}", "✘".error(), err);
+            }
+        }
+        Some(("--copy_snippet", sub_matches)) => {
+            let file = sub_matches.get_one::<String>("file").map(PathBuf::from);
+            let multi = sub_matches.get_flag("multi");
+            let params = snippet_params(sub_matches);
+            if let Err(err) = copy_snippets(&config, file, multi, &params, use_cache, &executor) {
+                println!("{} {}", "✘".error(), err);
+            }
         }
-        Some(("--list_snippets", _)) => {
-            list_snippets();
+        Some(("--get", sub_matches)) => {
+            let alias = sub_matches.get_one::<String>("alias").unwrap();
+            match get_snippet(
+                &config,
+                alias,
+                &snippet_params(sub_matches),
+                use_cache,
+                &executor,
+            ) {
+                Ok(true) => {}
+                Ok(false) => {
+                    println!("{} No snippet has the alias '{}'.", "✘".error(), alias);
+                    std::process::exit(1);
+                }
+                Err(err) => {
+                    println!("{} {}", "✘".error(), err);
+                    std::process::exit(1);
+                }
+            }
         }
-        Some(("--edit_snippet", _)) => {
-            edit_snippet();
+        Some(("--exec", sub_matches)) => {
+            let file = sub_matches.get_one::<String>("file").map(PathBuf::from);
+            let params = snippet_params(sub_matches);
+            if let Err(err) = exec_snippet(&config, file, &params, use_cache, &executor) {
+                println!("{} {}", "✘".error(), err);
+                std::process::exit(1);
+            }
+        }
+        Some(("--delete_snippet", sub_matches)) => {
+            let file = sub_matches.get_one::<String>("file").map(PathBuf::from);
+            let multi = sub_matches.get_flag("multi");
+            let yes = sub_matches.get_flag("yes");
+            match delete_snippets(&config, file, multi, yes, use_cache, &executor) {
+                Ok(true) => push_after_edit(&config, &executor),
+                Ok(false) => {}
+                Err(err) => println!("{} {}", "✘".error(), err),
+            }
         }
         Some(("--find_in_files", sub_matches)) => {
             let search_term = sub_matches.get_one::<String>("search_term").unwrap();
-            find_in_files(search_term);
+            let limit = sub_matches.get_one::<usize>("limit").copied();
+            let filter = created_range_filter(sub_matches);
+            if sub_matches.get_flag("count") {
+                count_files_containing(&config, search_term, &filter);
+            } else {
+                let context = match sub_matches.get_one::<u64>("context") {
+                    Some(context) => *context as usize,
+                    None => config.search_context_lines(),
+                };
+                if context > config::MAX_SEARCH_CONTEXT {
+                    println!(
+                        "{} search_context_lines must be between 0 and {}, got {}",
+                        "✘".error(),
+                        config::MAX_SEARCH_CONTEXT,
+                        context
+                    );
+                } else {
+                    find_in_files(&config, search_term, &filter, limit, context, &executor);
+                    push_after_edit(&config, &executor);
+                }
+            }
+        }
+        Some(("--snippet_search_recent_and_create", sub_matches)) => {
+            let query: Vec<&str> = sub_matches
+                .get_many::<String>("query")
+                .unwrap_or_default()
+                .map(|s| s.as_str())
+                .collect();
+            let yes = sub_matches.get_flag("yes");
+            let snippet_dir = vault::snippet_dir(&config);
+            match search_or_create(&config, &query, &snippet_dir, &timestamp, yes, &executor) {
+                Ok(true) => push_after_edit(&config, &executor),
+                Ok(false) => {}
+                Err(err) => println!("{} {}", "✘".error(), err),
+            }
+        }
+        Some(("--version", sub_matches)) => {
+            if sub_matches.get_flag("json") {
+                let info = serde_json::json!({
+                    "version": VERSION,
+                    "commit": env!("SNIPPETS_VAULT_GIT_COMMIT"),
+                    "build_date": env!("SNIPPETS_VAULT_BUILD_DATE"),
+                    "config_path": Config::path(),
+                    "vault_path": vault::snippet_dir(&config),
+                });
+                println!("{:#}", info);
+            } else {
+                println!("{}", format!("SnippetVault Version: {}", VERSION).success());
+            }
+        }
+        Some(("--check-version", sub_matches)) => {
+            let requirement = sub_matches.get_one::<String>("requirement").unwrap();
+            std::process::exit(check_version(requirement));
+        }
+        Some(("--cat_snippet", sub_matches)) => {
+            let file = sub_matches.get_one::<String>("file").map(PathBuf::from);
+            cat_snippet(&config, file, use_cache, &executor);
         }
-        Some(("--version", _)) => {
-            println!("{}", "SnippetVault Version: 0.2.0".green());
+        Some(("--info", sub_matches)) => {
+            let id = sub_matches.get_one::<String>("id").map(String::as_str);
+            let file = sub_matches.get_one::<String>("file").map(PathBuf::from);
+            let json = sub_matches.get_flag("json");
+            match snippet_info(&config, id, file, json, use_cache) {
+                Ok(true) => {}
+                Ok(false) => {
+                    println!(
+                        "{} No snippet has the id '{}'.",
+                        "✘".error(),
+                        id.unwrap_or_default()
+                    );
+                    std::process::exit(1);
+                }
+                Err(err) => {
+                    println!("{} {}", "✘".error(), err);
+                    std::process::exit(1);
+                }
+            }
+        }
+        Some(("--suggest", sub_matches)) => {
+            let count = *sub_matches.get_one::<usize>("count").unwrap();
+            let history = sub_matches
+                .get_one::<String>("history")
+                .map(|path| PathBuf::from(shellexpand::tilde(path).into_owned()))
+                .unwrap_or_else(|| config.history_file());
+            let min_count = *sub_matches.get_one::<u64>("min_count").unwrap() as usize;
+            if suggest_snippets(&config, &history, count, min_count, use_cache, &executor) {
+                push_after_edit(&config, &executor);
+            }
+        }
+        Some(("--snippet_render_terminal", sub_matches)) => {
+            let file = sub_matches.get_one::<String>("file").map(PathBuf::from);
+            render_terminal(&config, file, use_cache);
+        }
+        Some(("--motd", sub_matches)) => {
+            motd(&config, sub_matches.get_flag("full"));
         }
         Some(("--languages", _)) => {
-            list_languages();
+            list_languages(&config);
         }
-        _ => println!(
-            "{}",
-            "Unknown command. Use --help for usage information.".red()
-        ),
-    }
-}
-
-/// Creates a new snippet.
-/// - `language`: The programming language of the snippet.
-/// - `tags`: Tags associated with the snippet.
-/// - `timestamp`: A timestamp for naming the snippet.
-fn create_snippet(language: &str, tags: &[&str], timestamp: &str) {
-    let home_dir = env::var("HOME").unwrap();
-    let snippet_dir = format!("{}/{}", home_dir, SNIPPET_DIR);
-
-    if !Path::new(&snippet_dir).exists() {
-        fs::create_dir_all(&snippet_dir).unwrap();
-        println!("{} Directory created: {}", "✔".green(), snippet_dir);
-    }
-
-    // Combine all arguments for the filename, including language and tags
-    let mut filename_parts = vec![timestamp.to_string(), language.to_string()];
-    filename_parts.extend(tags.iter().map(|tag| tag.to_string()));
-
-    let filename = format!("{}/snippet_{}.md", snippet_dir, filename_parts.join("_"));
-
-    // Format the content with the language and tags
-    let content = format!(
-        "# Title: {} - Snippet\n# ---\n### Tags: {}\n\n### Content\n\n```{}\n\n```\n### Link:\n### Note:\n",
-        language, tags.join(", "), language
-    );
-
-    // Write the snippet content to the file
-    fs::write(&filename, content).unwrap();
-    println!("{} Snippet created: {}", "✔".green(), filename);
-
-    // Open the file in the default editor
-    let editor = get_default_editor();
-    let _ = ProcessCommand::new(editor).arg(&filename).status();
-
-    // Preview the file using glow
-    let _ = ProcessCommand::new("glow").arg(&filename).status();
-}
-/// Lists all snippets using fuzzy search and preview tools.
-fn list_snippets() {
-    let home_dir = env::var("HOME").unwrap();
-    let snippet_dir = format!("{}/{}", home_dir, SNIPPET_DIR);
+        Some(("--list_aliases", _)) => {
+            list_aliases(&config);
+        }
+        Some(("--snippet_backup_to_s3", sub_matches)) => {
+            let remote = sub_matches.get_one::<String>("remote").map(String::as_str);
+            let prune = sub_matches
+                .get_one::<u64>("prune_remote")
+                .map(|keep| *keep as usize);
+            backup_to_s3(&config, remote, prune, &executor);
+        }
+        Some(("--snippet_restore_from_s3", sub_matches)) => {
+            let remote = sub_matches.get_one::<String>("remote").map(String::as_str);
+            let result = if sub_matches.get_flag("list") {
+                list_s3_backups(&config, remote)
+            } else {
+                let backup = sub_matches.get_one::<String>("backup").map(String::as_str);
+                restore_from_s3(&config, remote, backup, &executor)
+            };
+            if let Err(err) = result {
+                println!("{} {}", "✘".error(), err);
+                std::process::exit(1);
+            }
+        }
+        Some(("--snippet_sync", sub_matches)) => {
+            let direction = if sub_matches.get_flag("push_only") {
+                webdav::Direction::PushOnly
+            } else if sub_matches.get_flag("pull_only") {
+                webdav::Direction::PullOnly
+            } else {
+                webdav::Direction::Both
+            };
+            if let Err(err) = sync_webdav(&config, direction, &executor) {
+                println!("{} {}", "✘".error(), err);
+                std::process::exit(1);
+            }
+        }
+        Some(("--snippet_migrate_v2", sub_matches)) => {
+            let skip_backup = sub_matches.get_flag("skip_backup");
+            migrate_vault(&config, skip_backup, &executor);
+        }
+        Some(("--export_obsidian", sub_matches)) => {
+            let output_dir = sub_matches.get_one::<String>("output_dir").unwrap();
+            let overwrite = sub_matches.get_flag("overwrite");
+            match export::export_obsidian(
+                &vault::snippet_dir(&config),
+                &PathBuf::from(output_dir),
+                overwrite,
+                &executor,
+            ) {
+                Ok(count) => println!(
+                    "{} Exported {} snippets to {}",
+                    "✔".success(),
+                    count,
+                    output_dir
+                ),
+                Err(err) => println!("{} {}", "✘".error(), err),
+            }
+        }
+        Some(("--cheatsheet", sub_matches)) => {
+            let language = language::resolve_language(
+                sub_matches.get_one::<String>("language").unwrap(),
+                &config.language_aliases,
+            );
+            let filter = listing::SnippetFilter {
+                language: Some(language),
+                tags: sub_matches
+                    .get_many::<String>("tag_filter")
+                    .unwrap_or_default()
+                    .cloned()
+                    .collect(),
+                ..Default::default()
+            };
+            let output = sub_matches.get_one::<String>("output").map(PathBuf::from);
+            let html = sub_matches.get_one::<String>("format").unwrap() == "html";
+            if let Err(err) = cheatsheet(&config, &filter, output, html, use_cache, &executor) {
+                println!("{} {}", "✘".error(), err);
+            }
+        }
+        Some(("--export_html", sub_matches)) => {
+            let output_dir = sub_matches.get_one::<String>("output_dir").unwrap();
+            let overwrite = sub_matches.get_flag("overwrite");
+            match export::export_html(
+                &vault::snippet_dir(&config),
+                &PathBuf::from(output_dir),
+                overwrite,
+                &executor,
+            ) {
+                Ok(count) => println!(
+                    "{} Exported {} snippets to {}",
+                    "✔".success(),
+                    count,
+                    output_dir
+                ),
+                Err(err) => println!("{} {}", "✘".error(), err),
+            }
+        }
+        Some(("--snippet_bulk_export_by_language", sub_matches)) => {
+            let output_dir = sub_matches.get_one::<String>("output_dir").unwrap();
+            let format = sub_matches
+                .get_one::<String>("format")
+                .and_then(|name| export::ExportFormat::from_name(name))
+                .unwrap_or(export::ExportFormat::Markdown);
+            let overwrite = sub_matches.get_flag("overwrite");
+            match export::export_by_language(
+                &vault::snippet_dir(&config),
+                &PathBuf::from(output_dir),
+                format,
+                &config.language_aliases,
+                overwrite,
+                &executor,
+            ) {
+                Ok(outputs) => {
+                    let mut outputs: Vec<_> = outputs.into_iter().collect();
+                    outputs.sort();
+                    for (language, path) in outputs {
+                        println!("{} {}: {}", "✔".success(), language, path.display());
+                    }
+                }
+                Err(err) => println!("{} {}", "✘".error(), err),
+            }
+        }
+        Some(("--snippet_export_csv", sub_matches)) => {
+            let output = PathBuf::from(sub_matches.get_one::<String>("output").unwrap());
+            match export::export_csv(
+                &vault::snippet_dir(&config),
+                &output,
+                sub_matches.get_flag("full"),
+                &config.extensions(),
+                use_cache,
+                &executor,
+            ) {
+                Ok(_) if executor.is_dry_run() => {}
+                Ok(count) => println!(
+                    "{} Exported {} snippets to {}",
+                    "✔".success(),
+                    count,
+                    output.display()
+                ),
+                Err(err) => println!("{} {}", "✘".error(), err),
+            }
+        }
+        Some(("--snippet_import_csv", sub_matches)) => {
+            let csv = PathBuf::from(sub_matches.get_one::<String>("csv").unwrap());
+            let mode = sub_matches
+                .get_one::<String>("mode")
+                .and_then(|name| import::ImportMode::from_name(name))
+                .unwrap_or(import::ImportMode::Upsert);
+            match import::import_csv(&csv, &vault::snippet_dir(&config), mode, &config, &executor) {
+                Ok(_) if executor.is_dry_run() => {}
+                Ok((created, updated)) => {
+                    println!(
+                        "{} Created {} and updated {} snippets.",
+                        "✔".success(),
+                        created,
+                        updated
+                    );
+                    if created + updated > 0 {
+                        push_after_edit(&config, &executor);
+                    }
+                }
+                Err(err) => println!("{} {}: {}", "✘".error(), csv.display(), err),
+            }
+        }
+        Some(("--import", sub_matches)) => {
+            let path = PathBuf::from(sub_matches.get_one::<String>("path").unwrap());
+            match pet::import_pet(
+                &path,
+                &vault::snippet_dir(&config),
+                &config.extensions(),
+                use_cache,
+                config.timestamp_format(),
+                &executor,
+            ) {
+                Ok(_) if executor.is_dry_run() => {}
+                Ok((imported, skipped)) => {
+                    println!(
+                        "{} Imported {} snippets ({} skipped as duplicates or empty).",
+                        "✔".success(),
+                        imported,
+                        skipped
+                    );
+                    if imported > 0 {
+                        push_after_edit(&config, &executor);
+                    }
+                }
+                Err(err) => println!("{} {}", "✘".error(), err),
+            }
+        }
+        Some(("--export", sub_matches)) => {
+            let output = sub_matches.get_one::<String>("output").map(PathBuf::from);
+            if let Err(err) = export_pet(&config, output, use_cache, &executor) {
+                println!("{} {}", "✘".error(), err);
+            }
+        }
+        Some(("--snippet_export_notion", sub_matches)) => {
+            if let Some(output_dir) = sub_matches.get_one::<String>("all") {
+                let written =
+                    vault::vault_files(&vault::snippet_dir(&config), &config.extensions())
+                        .and_then(|files| {
+                            notion::export_notion_all(&files, Path::new(output_dir), &executor)
+                        });
+                match written {
+                    Ok(_) if executor.is_dry_run() => {}
+                    Ok(written) => println!(
+                        "{} Exported {} snippets to {}",
+                        "✔".success(),
+                        written.len(),
+                        output_dir
+                    ),
+                    Err(err) => println!("{} {}", "✘".error(), err),
+                }
+            } else {
+                let file = Path::new(sub_matches.get_one::<String>("file").unwrap());
+                let exported = notion::export_notion_json(file).and_then(|json| match sub_matches
+                    .get_one::<String>(
+                    "output",
+                ) {
+                    Some(output) => executor.apply(Mutation::Write {
+                        path: PathBuf::from(output),
+                        contents: json,
+                    }),
+                    None => {
+                        println!("{}", json);
+                        Ok(())
+                    }
+                });
+                if let Err(err) = exported {
+                    println!("{} {}", "✘".error(), err);
+                }
+            }
+        }
+        Some(("--snippet_serve_static", sub_matches)) => {
+            let port = *sub_matches.get_one::<u16>("port").unwrap();
+            let snippet_dir = vault::snippet_dir(&config);
+            // The server exits the process on Ctrl-C, which also ends the indexer.
+            let (_stop, stop_rx) = std::sync::mpsc::channel();
+            if sub_matches.get_flag("watch_index") {
+                if let Err(err) = indexer::start_background_indexer(
+                    &snippet_dir,
+                    &index::index_path(),
+                    &config.extensions(),
+                    stop_rx,
+                ) {
+                    println!("{} Not watching the index: {}", "⚠".warning(), err);
+                }
+            }
+            if let Err(err) = serve::serve_static(&snippet_dir, port) {
+                println!("{} {}", "✘".error(), err);
+            }
+        }
+        Some(("--snippet_file_watcher_index", _)) => {
+            if let Err(err) = watch_index(&config, use_cache) {
+                println!("{} {}", "✘".error(), err);
+            }
+        }
+        Some(("--snippet_lint", sub_matches)) => {
+            let file = sub_matches.get_one::<String>("file").map(PathBuf::from);
+            let all = sub_matches.get_flag("all");
+            let fix = sub_matches.get_flag("fix");
+            if lint_snippets(&config, file, all, fix, &executor) {
+                push_after_edit(&config, &executor);
+            }
+        }
+        Some(("--spellcheck", sub_matches)) => {
+            let file = sub_matches.get_one::<String>("file").map(PathBuf::from);
+            let interactive = sub_matches.get_flag("interactive");
+            match spellcheck(&config, file, interactive, &executor) {
+                Ok((changed, remaining)) => {
+                    if changed {
+                        push_after_edit(&config, &executor);
+                    }
+                    if remaining > 0 {
+                        std::process::exit(1);
+                    }
+                }
+                Err(err) => {
+                    println!("{} {}", "✘".error(), err);
+                    std::process::exit(1);
+                }
+            }
+        }
+        Some(("--snippet_dedup_tags", sub_matches)) => {
+            let file = sub_matches.get_one::<String>("file").map(PathBuf::from);
+            let all = sub_matches.get_flag("all");
+            if dedup_tags(&config, file, all, use_cache, &executor) {
+                push_after_edit(&config, &executor);
+            }
+        }
+        Some(("--snippet_interactive_tag_editor", sub_matches)) => {
+            let file = sub_matches.get_one::<String>("file").map(PathBuf::from);
+            match interactive_tag_editor(&config, file, use_cache, &executor) {
+                Ok(true) => push_after_edit(&config, &executor),
+                Ok(false) => {}
+                Err(err) => println!("{} {}", "✘".error(), err),
+            }
+        }
+        Some(("--snippet_diff_tags", sub_matches)) => {
+            let old_tag = sub_matches.get_one::<String>("old_tag").unwrap();
+            let new_tag = sub_matches.get_one::<String>("new_tag").unwrap();
+            diff_tags(&config, old_tag, new_tag);
+        }
+        Some(("--snippet_tag_rename_cascade", sub_matches)) => {
+            let old_tag = sub_matches.get_one::<String>("old_tag").unwrap();
+            let new_tag = sub_matches.get_one::<String>("new_tag").unwrap();
+            match rename_tag(&config, old_tag, new_tag, &executor) {
+                Ok(true) => push_after_edit(&config, &executor),
+                Ok(false) => {}
+                Err(err) => {
+                    println!("{} {}", "✘".error(), err);
+                    std::process::exit(1);
+                }
+            }
+        }
+        Some(("--snippet_weekly_digest", sub_matches)) => {
+            let period = *sub_matches.get_one::<u32>("period").unwrap();
+            let markdown = sub_matches.get_flag("markdown");
+            weekly_digest(&config, period, markdown, use_cache);
+        }
+        Some(("--snippet_title_index", _)) => {
+            let built = index::open(&index::index_path()).and_then(|db| {
+                index::build_title_index(
+                    &vault::snippet_dir(&config),
+                    &db,
+                    &config.extensions(),
+                    use_cache,
+                )
+            });
+            match built {
+                Ok(count) => println!("{} Indexed {} snippets.", "✔".success(), count),
+                Err(err) => println!("{} {}", "✘".error(), err),
+            }
+        }
+        Some(("--find_snippet_by_title", sub_matches)) => {
+            let query: Vec<&str> = sub_matches
+                .get_many::<String>("query")
+                .unwrap_or_default()
+                .map(|s| s.as_str())
+                .collect();
+            match find_snippet_by_title(&config, &query.join(" "), use_cache, &executor) {
+                Ok(()) => push_after_edit(&config, &executor),
+                Err(err) => println!("{} {}", "✘".error(), err),
+            }
+        }
+        Some(("--snippet_github_sync", sub_matches)) => {
+            let repo_url = sub_matches.get_one::<String>("repo_url").unwrap();
+            let dest = sub_matches
+                .get_one::<String>("dest")
+                .map(|dest| PathBuf::from(shellexpand::tilde(dest).into_owned()))
+                .unwrap_or_else(|| vault::snippet_dir(&config));
+            let token = sub_matches
+                .get_one::<String>("token")
+                .cloned()
+                .or_else(|| std::env::var("GITHUB_TOKEN").ok());
+            github_sync(&config, repo_url, &dest, token.as_deref(), &executor);
+        }
+        Some(("--snippet_dependency_graph", sub_matches)) => {
+            let output = sub_matches.get_one::<String>("output").map(PathBuf::from);
+            let render = sub_matches.get_one::<String>("render").map(|s| s.as_str());
+            if let Err(err) = dependency_graph(&config, output.as_deref(), render, &executor) {
+                println!("{} {}", "✘".error(), err);
+            }
+        }
+        Some(("--snippet_convert_language", sub_matches)) => {
+            let file = PathBuf::from(sub_matches.get_one::<String>("file").unwrap());
+            let new_language = sub_matches.get_one::<String>("new_language").unwrap();
+            let keep_fences = sub_matches.get_flag("keep_fences");
+            match convert::convert_language(&file, new_language, keep_fences, &executor) {
+                Ok(_) if executor.is_dry_run() => {}
+                Ok(new_path) => {
+                    index::reindex_snippet(&file, &new_path);
+                    println!(
+                        "{} Converted to {}: {}",
+                        "✔".success(),
+                        new_language,
+                        new_path.display()
+                    );
+                }
+                Err(err) => println!("{} {}", "✘".error(), err),
+            }
+        }
+        Some(("--duplicate_snippet", sub_matches)) => {
+            let file = sub_matches.get_one::<String>("file").map(PathBuf::from);
+            let title = sub_matches.get_one::<String>("title").cloned();
+            let tags: Option<Vec<String>> = sub_matches
+                .get_many::<String>("tags")
+                .map(|tags| tags.cloned().collect());
+            match duplicate_snippet(
+                &config, file, title, tags, now, &timestamp, use_cache, &executor,
+            ) {
+                Ok(true) => push_after_edit(&config, &executor),
+                Ok(false) => {}
+                Err(err) => println!("{} {}", "✘".error(), err),
+            }
+        }
+        Some(("--snippet_merge", sub_matches)) => {
+            let sources: Vec<PathBuf> = sub_matches
+                .get_many::<String>("sources")
+                .unwrap()
+                .map(PathBuf::from)
+                .collect();
+            let language = sub_matches.get_one::<String>("language").cloned();
+            let tags: Option<Vec<String>> = sub_matches
+                .get_many::<String>("tags")
+                .map(|tags| tags.cloned().collect());
+            let delete_sources = sub_matches.get_flag("delete_sources");
+            let force = sub_matches.get_flag("force");
+            merge_snippets(
+                &config,
+                &sources,
+                language,
+                tags,
+                &timestamp,
+                delete_sources,
+                force,
+                &executor,
+            );
+        }
+        Some(("--set_field", sub_matches)) => {
+            let key = sub_matches.get_one::<String>("key").unwrap();
+            let value = sub_matches.get_one::<String>("value").unwrap();
+            let file = sub_matches.get_one::<String>("file").map(PathBuf::from);
+            let value = snippet::field_from_str(value);
+            edit_field(&config, file, key, Some(value), use_cache, &executor);
+        }
+        Some(("--unset_field", sub_matches)) => {
+            let key = sub_matches.get_one::<String>("key").unwrap();
+            let file = sub_matches.get_one::<String>("file").map(PathBuf::from);
+            edit_field(&config, file, key, None, use_cache, &executor);
+        }
+        Some(("--snippet_expiry", sub_matches)) => {
+            let date = *sub_matches.get_one::<NaiveDate>("date").unwrap();
+            let file = sub_matches.get_one::<String>("file").map(PathBuf::from);
+            snippet_expiry(&config, file, date, use_cache, &executor);
+        }
+        Some(("--find_expired", _)) => find_expiring(&config, None, use_cache),
+        Some(("--find_expiring_soon", sub_matches)) => {
+            let days = *sub_matches.get_one::<u64>("days").unwrap();
+            find_expiring(&config, Some(days), use_cache);
+        }
+        Some(("--housekeep", sub_matches)) => {
+            let yes = sub_matches.get_flag("yes");
+            if !housekeep(&config, yes, use_cache, &executor) {
+                std::process::exit(1);
+            }
+        }
+        Some(("--setup", _)) => {}
+        Some(("--config_init", _)) => {
+            if let Err(err) = setup::init_config(&executor) {
+                println!("{} {}", "✘".error(), err);
+                std::process::exit(1);
+            }
+        }
+        Some(("--open_dir", sub_matches)) => {
+            let snippet = sub_matches.get_flag("snippet");
+            open_dir(&config, snippet, use_cache, &executor);
+        }
+        Some(("--snippet_open_link", sub_matches)) => {
+            let file = sub_matches
+                .get_one::<String>("file")
+                .map(PathBuf::from)
+                .or_else(|| choose_snippet(&config, &config.extensions(), use_cache));
+            if let Some(file) = file {
+                if let Err(err) = open_link(&file) {
+                    println!("{} {}", "✘".error(), err);
+                }
+            }
+        }
+        Some(("--snippet_convert_to_gist", sub_matches)) => {
+            let token = sub_matches
+                .get_one::<String>("token")
+                .cloned()
+                .or_else(|| std::env::var("GITHUB_TOKEN").ok());
+            let file = sub_matches
+                .get_one::<String>("file")
+                .map(PathBuf::from)
+                .or_else(|| choose_snippet(&config, &config.extensions(), use_cache));
+            match (file, token) {
+                (Some(file), Some(token)) => share_gist(
+                    &file,
+                    &token,
+                    sub_matches.get_flag("public"),
+                    sub_matches.get_flag("open"),
+                    &executor,
+                ),
+                (Some(_), None) => println!(
+                    "{} Pass --token or set GITHUB_TOKEN to create a gist.",
+                    "✘".error()
+                ),
+                (None, _) => {}
+            }
+        }
+        Some(("--path", _)) => {
+            let snippet_dir = vault::snippet_dir(&config);
+            if !snippet_dir.exists() && prompt::is_interactive() {
+                ensure_vault_dir(&config, &executor);
+            }
+            println!("{}", snippet_dir.display());
+        }
+        Some(("__complete", sub_matches)) => {
+            let (name, sub_matches) = sub_matches.subcommand().unwrap();
+            let kind = complete::Kind::from_name(name).unwrap();
+            let prefix = sub_matches
+                .get_one::<String>("prefix")
+                .map(String::as_str)
+                .unwrap_or_default();
+            let limit = sub_matches.get_one::<usize>("limit").copied();
+            match vault::load_snippets(
+                &vault::snippet_dir(&config),
+                &config.extensions(),
+                use_cache,
+            ) {
+                Ok(snippets) => {
+                    let mut out = std::io::stdout().lock();
+                    for candidate in complete::candidates(&snippets, kind, prefix, limit) {
+                        if writeln!(out, "{}", candidate).is_err() {
+                            break;
+                        }
+                    }
+                }
+                Err(err) => {
+                    eprintln!("{} {}", "✘".error(), err);
+                    std::process::exit(1);
+                }
+            }
+        }
+        Some(("--copy_path", sub_matches)) => {
+            let file = Path::new(sub_matches.get_one::<String>("file").unwrap());
+            let copied = std::fs::canonicalize(file)
+                .map_err(SnippetVaultError::from)
+                .and_then(|path| {
+                    clipboard::write_clipboard(&path.display().to_string()).map(|_| path)
+                });
+            match copied {
+                Ok(path) => println!("{} Copied {}", "✔".success(), path.display()),
+                Err(err) => println!("{} {}: {}", "✘".error(), file.display(), err),
+            }
+        }
+        Some(("--open_snippet", sub_matches)) => {
+            let path = PathBuf::from(sub_matches.get_one::<String>("file").unwrap());
+            if !path.is_file() {
+                println!("{} No such snippet: {}", "✘".error(), path.display());
+            } else {
+                index::log_access(&path, index::Access::Opened);
+                if let Err(err) = editor::open(&config, &[path], &executor) {
+                    println!("{} Cannot open the editor: {}", "✘".error(), err);
+                }
+            }
+        }
+        Some(("--tags", _)) => {
+            list_tags(&config, use_cache);
+        }
+        Some(("--tag_tree", _)) => {
+            tag_tree(&config, use_cache);
+        }
+        Some(("--stats", _)) => vault_stats(&config, use_cache),
+        Some(("--snippet_resolve_conflicts", _)) => match resolve_conflicts(&config, &executor) {
+            Ok(true) => push_after_edit(&config, &executor),
+            Ok(false) => {}
+            Err(err) => {
+                println!("{} {}", "✘".error(), err);
+                std::process::exit(1);
+            }
+        },
+        Some(("--snippet_stats_per_day_of_week", sub_matches)) => {
+            weekday_stats(&config, sub_matches.get_flag("normalize"));
+        }
+        Some(("--snippet_last_error", _)) => {
+            match std::fs::read_to_string(logging::last_error_path()) {
+                Ok(last_error) => print!("{}", last_error),
+                Err(err) if err.kind() == std::io::ErrorKind::NotFound => {
+                    println!("{} No error recorded.", "•".accent())
+                }
+                Err(err) => println!("{} {}", "✘".error(), err),
+            }
+        }
+        Some(("--clear_last_error", _)) => {
+            let path = logging::last_error_path();
+            if !path.exists() {
+                println!("{} No error recorded.", "•".accent());
+            } else {
+                match executor.apply(Mutation::Remove(path)) {
+                    Ok(()) => println!("{} Last error cleared.", "✔".success()),
+                    Err(err) => println!("{} {}", "✘".error(), err),
+                }
+            }
+        }
+        Some(("--snippet_custom_hooks", _)) => list_hooks(&config),
+        Some(("--batch", sub_matches)) => {
+            if sub_matches.get_flag("describe") {
+                println!("{:#}", batch::describe());
+                return;
+            }
+            // Responses are the only output; nothing may prompt or color it.
+            prompt::set_non_interactive();
+            colored::control::set_override(false);
+            let stdin = std::io::stdin();
+            if let Err(err) = batch::run(
+                &config,
+                stdin.lock(),
+                std::io::stdout().lock(),
+                use_cache,
+                &executor,
+            ) {
+                eprintln!("{} {}", "✘".error(), err);
+                std::process::exit(1);
+            }
+        }
+        Some(("--doctor", _)) => {
+            doctor(&config);
+        }
+        Some(("--cache_clear", _)) => match cache::MetadataCache::clear(&executor) {
+            Ok(true) => println!("{} Metadata cache cleared.", "✔".success()),
+            Ok(false) => println!("{} No metadata cache to clear.", "•".accent()),
+            Err(err) => println!("{} {}", "✘".error(), err),
+        },
+        _ => println!(
+            "{}",
+            "Unknown command. Use --help for usage information.".error()
+        ),
+    }
 
-    if Path::new(&snippet_dir).exists() {
-        let editor = get_default_editor();
+    if prompt::was_refused() || vault::was_refused_read_only() {
+        std::process::exit(1);
+    }
+}
+
+/// Creates a new snippet.
+/// - `language`: The programming language of the snippet.
+/// - `tags`: Tags associated with the snippet.
+/// - `timestamp`: A timestamp for naming the snippet.
+/// - `plain_file`: Write a bare code file instead of a markdown snippet.
+/// - `code`: Initial contents of the code fence (empty for a blank snippet).
+/// - `placeholders`: Tab stops to leave for the editor's snippet engine.
+/// - `executor`: Applies (or, with `--dry-run`, only prints) the file changes.
+#[allow(clippy::too_many_arguments)]
+fn create_snippet(
+    config: &Config,
+    language: &str,
+    tags: &[&str],
+    timestamp: &str,
+    plain_file: bool,
+    code: &str,
+    placeholders: snippet::PlaceholderStyle,
+    executor: &Executor,
+) {
+    let language = language::resolve_language(language, &config.language_aliases);
+    let tags: Vec<String> = tags.iter().map(|tag| tag.to_string()).collect();
+    let hook_env = hooks::SnippetEnv::new(&language, &tags, timestamp);
+    if !executor.is_dry_run() {
+        if let Err(err) = hooks::run_hook("pre_create", &hook_env.vars(), config) {
+            println!("{} {}; the snippet was not created.", "✘".error(), err);
+            return;
+        }
+    }
+    let created = if plain_file {
+        vault::create_plain_file(config, &language, &tags, timestamp, executor)
+    } else {
+        vault::create_snippet_file(
+            config,
+            &language,
+            &tags,
+            timestamp,
+            code,
+            placeholders,
+            executor,
+        )
+    };
+    let filename = match created {
+        Ok(path) => path,
+        Err(err) => {
+            println!("{} {}", "✘".error(), err);
+            return;
+        }
+    };
+    if executor.is_dry_run() {
+        return;
+    }
+    println!("{} Snippet created: {}", "✔".success(), filename.display());
+    index::log_access(&filename, index::Access::Created);
+
+    // Open the file in its editor, with the cursor inside the code fence
+    index::log_access(&filename, index::Access::Opened);
+    let line = std::fs::read_to_string(&filename)
+        .ok()
+        .and_then(|content| snippet::entry_line(&filename, &content));
+    let _ = editor::open_in_editor(config, &filename, line, executor);
+    // After the editor closes, so the index has the title the user gave it.
+    index::reindex_snippet(&filename, &filename);
+    let hook_env = hook_env.with_path(&filename);
+    hooks::run_post_hook("post_create", &hook_env.vars(), config);
+
+    // Preview the file the way the pickers do (glow, bat, or cat)
+    let preview = picker::choose_previewer(&filename, config)
+        .replace("{}", &picker::shell_quote(&filename.to_string_lossy()));
+    let (shell, shell_args) = platform::shell_command();
+    let _ = logging::run(ProcessCommand::new(shell).args(shell_args).arg(preview));
+}
+
+/// Returns `given` unless it is missing or `auto`, in which case the language is
+/// detected from `code`. Interactive sessions confirm the guess (unless `yes`);
+/// guesses below the configured confidence threshold fall back to `text`.
+/// - `file`: The file the code was read from, whose extension decides outright.
+fn choose_language(
+    config: &Config,
+    given: Option<&str>,
+    code: &str,
+    file: Option<&Path>,
+    yes: bool,
+) -> Result<String, SnippetVaultError> {
+    if let Some(language) = given.filter(|language| *language != "auto") {
+        return Ok(language.to_string());
+    }
+
+    let threshold = config.detect_threshold.unwrap_or(detect::DEFAULT_THRESHOLD);
+    let detection =
+        detect::detect_language(code, file).filter(|detection| detection.confidence >= threshold);
+    let Some(detection) = detection else {
+        println!(
+            "{} Could not detect the language; using text.",
+            "•".accent()
+        );
+        return Ok("text".to_string());
+    };
+
+    println!(
+        "{} Detected {} ({:.0}% confidence)",
+        "•".accent(),
+        detection.language.bold(),
+        detection.confidence * 100.0
+    );
+    if yes || !prompt::is_interactive() || prompt::confirm("Use it?", true)? {
+        return Ok(detection.language);
+    }
+    Ok(prompt::ask("Language:", "text")?)
+}
+
+/// Creates a snippet whose code block holds the current clipboard text.
+/// - `language`: Snippet language; detected from the clipboard text when `None`.
+/// - `open_editor`: Open the new snippet for annotation afterwards.
+/// - `yes`: Accept a detected language without asking.
+fn clipboard_create(
+    config: &Config,
+    language: Option<&str>,
+    tags: &[&str],
+    timestamp: &str,
+    open_editor: bool,
+    yes: bool,
+    executor: &Executor,
+) -> Result<(), SnippetVaultError> {
+    let code = clipboard::read_clipboard()?;
+    let language = choose_language(config, language, &code, None, yes)?;
+    let language = language::resolve_language(&language, &config.language_aliases);
+    let tags: Vec<String> = tags.iter().map(|tag| tag.to_string()).collect();
+    let hook_env = hooks::SnippetEnv::new(&language, &tags, timestamp);
+    if !executor.is_dry_run() {
+        hooks::run_hook("pre_create", &hook_env.vars(), config)?;
+    }
+    let filename = vault::create_snippet_file(
+        config,
+        &language,
+        &tags,
+        timestamp,
+        &code,
+        snippet::PlaceholderStyle::None,
+        executor,
+    )?;
+    if executor.is_dry_run() {
+        return Ok(());
+    }
+    println!("{} Snippet created: {}", "✔".success(), filename.display());
+    index::log_access(&filename, index::Access::Created);
+
+    if open_editor {
+        index::log_access(&filename, index::Access::Opened);
+        let line = snippet::entry_line(&filename, &std::fs::read_to_string(&filename)?);
+        editor::open_in_editor(config, &filename, line, executor)?;
+    }
+    index::reindex_snippet(&filename, &filename);
+    let hook_env = hook_env.with_path(&filename);
+    hooks::run_post_hook("post_create", &hook_env.vars(), config);
+    Ok(())
+}
+
+/// Lists all snippets using fuzzy search and preview tools.
+/// - `query`: Initial fzf query, so the picker opens already filtered.
+/// - `multi`: Allow marking several snippets, which are then all opened.
+/// - `executor`: Formats edited snippets when `format_on_save` is on.
+fn list_snippets(config: &Config, query: Option<&str>, multi: bool, executor: &Executor) {
+    if let Err(err) = prompt::require_interactive(
+        "pick with fzf",
+        "list with --no_fzf, --table, or --json instead",
+    ) {
+        println!("{} {}", "✘".error(), err);
+        return;
+    }
+    let snippet_dir = vault::snippet_dir(config).display().to_string();
 
+    if Path::new(&snippet_dir).exists() {
         // Delegate to bash commands for listing snippets
         let args = format!(
             r#"
             if [[ "$2" != "" ]]; then
-                rga --files-with-matches $2 | fzf --sort --preview-window down:80%:wrap --preview 'glow --style=dark {{}}'
+                rga --files-with-matches $2 | fzf {multi} --preview '{preview}' {fzf_args}
             else
                 if [[ -d "{}" ]]; then
                     cd "{}" &&
-                        selected_article=$(fzf --exact --info=inline --border --margin=1 --padding=1 --sort --preview-window down:80%:wrap --preview 'glow --style=dark {{}}')
-                    {} $selected_article
+                        selected_article=$(fzf {multi} --query "$1" --preview '{preview}' {fzf_args})
+                    printf '%s\n' "$selected_article"
                 fi
             fi
             "#,
-            snippet_dir, snippet_dir, editor
+            snippet_dir,
+            snippet_dir,
+            preview = picker::preview_with_summary(config),
+            multi = if multi { "--multi" } else { "" },
+            fzf_args = picker::fzf_shell_args(config, "{}")
         );
 
-        let status = ProcessCommand::new("bash")
-            .arg("-c")
-            .arg(args)
-            .status()
-            .expect("Failed to execute shell commands");
+        // The query is passed as $1 rather than spliced into the script, so spaces
+        // and fzf operators like `!` or `|` need no quoting.
+        let output = logging::run_capture(
+            ProcessCommand::new("bash")
+                .arg("-c")
+                .arg(args)
+                .arg("snippets_vault")
+                .arg(query.unwrap_or_default()),
+        )
+        .expect("Failed to execute shell commands");
 
-        if !status.success() {
-            println!("{} Failed to list snippets.", "✘".red());
+        if output.status.success() {
+            open_selection(
+                config,
+                Path::new(&snippet_dir),
+                &output.stdout,
+                None,
+                executor,
+            );
+        } else {
+            println!("{} Failed to list snippets.", "✘".error());
         }
     } else {
-        println!("{} Snippet directory does not exist.", "✘".red());
+        println!("{} Snippet directory does not exist.", "✘".error());
     }
 }
 
-/// Opens a snippet for editing using fuzzy search to locate the file.
-fn edit_snippet() {
-    let home_dir = env::var("HOME").unwrap();
-    let snippet_dir = format!("{}/{}", home_dir, SNIPPET_DIR);
+/// Copies `file` (picked with fzf when `None`) into a new snippet created
+/// `now`, then opens the copy in the editor when there is a terminal. The title and tags are asked for
+/// unless given; the title prompt suggests "Copy of <title>" and the tags
+/// prompt starts from the original's. Returns whether a copy was written.
+/// - `use_cache`: Serve unchanged snippets from the metadata cache.
+/// - `executor`: Applies (or, with `--dry-run`, only prints) the new file.
+#[allow(clippy::too_many_arguments)]
+fn duplicate_snippet(
+    config: &Config,
+    file: Option<PathBuf>,
+    title: Option<String>,
+    tags: Option<Vec<String>>,
+    now: NaiveDateTime,
+    timestamp: &str,
+    use_cache: bool,
+    executor: &Executor,
+) -> Result<bool, SnippetVaultError> {
+    let Some(source) = file.or_else(|| choose_snippet(config, &config.extensions(), use_cache))
+    else {
+        return Ok(false);
+    };
+    let meta = snippet::SnippetMeta::read(&source)?;
+    let title = match title {
+        Some(title) => title,
+        // Plain code files keep their title comment, so there is nothing to ask.
+        None if !snippet::is_markdown(&source) => meta.title.clone(),
+        None => prompt::ask("Title:", &format!("Copy of {}", meta.title))?,
+    };
+    let tags = match tags {
+        Some(tags) => tags::dedup_preserving_order(&tags),
+        None => {
+            let mut known: Vec<String> =
+                vault::load_snippets(&vault::snippet_dir(config), &config.extensions(), use_cache)?
+                    .iter()
+                    .flat_map(tags::snippet_tags)
+                    .collect();
+            known.sort();
+            known.dedup();
+            let current = tags::snippet_tags(&meta).join(", ");
+            match prompt::edit_line("Tags:", &current, known) {
+                Ok(answer) => tags::parse_tag_list(&answer),
+                Err(err) if err.kind() == std::io::ErrorKind::Interrupted => return Ok(false),
+                Err(err) => return Err(err.into()),
+            }
+        }
+    };
 
-    if Path::new(&snippet_dir).exists() {
-        let editor = get_default_editor();
+    let lock = executor.lock()?;
+    let copy = duplicate::duplicate_snippet(&source, &title, &tags, now, timestamp, executor)?;
+    // Not held while the editor is open, or every other change would wait on it.
+    drop(lock);
+    if executor.is_dry_run() {
+        return Ok(false);
+    }
+    println!(
+        "{} Duplicated {} as {}",
+        "✔".success(),
+        source.display(),
+        copy.display()
+    );
+    index::log_access(&copy, index::Access::Created);
+    index::reindex_snippet(&copy, &copy);
 
-        // Delegate to bash commands for editing snippets
-        let args = format!(
-            r#"
-            cd "{}"
-            IFS=$'\n' files=($(fzf --exact --info=inline --border --margin=1 --padding=1 --sort --preview-window down:80%:wrap --preview 'glow --style=dark {{}}'))
-            [[ -n "$files" ]] && {} "${{files[@]}}"
-            "#,
-            snippet_dir, editor
-        );
+    if !prompt::is_interactive() {
+        return Ok(true);
+    }
+    index::log_access(&copy, index::Access::Opened);
+    let line = std::fs::read_to_string(&copy)
+        .ok()
+        .and_then(|content| snippet::entry_line(&copy, &content));
+    if let Err(err) = editor::open_in_editor(config, &copy, line, executor) {
+        println!("{} Cannot open the editor: {}", "✘".error(), err);
+    }
+    Ok(true)
+}
+
+/// Merges `sources` into a new snippet, then optionally deletes them.
+/// - `language`, `tags`: For the new snippet; default to the first source's
+///   language and the union of every source's tags.
+/// - `delete_sources`: Remove the sources afterwards, confirming each unless `force`.
+#[allow(clippy::too_many_arguments)]
+fn merge_snippets(
+    config: &Config,
+    sources: &[PathBuf],
+    language: Option<String>,
+    tags: Option<Vec<String>>,
+    timestamp: &str,
+    delete_sources: bool,
+    force: bool,
+    executor: &Executor,
+) {
+    let metas: Vec<snippet::SnippetMeta> = match sources
+        .iter()
+        .map(|path| snippet::SnippetMeta::read(path))
+        .collect()
+    {
+        Ok(metas) => metas,
+        Err(err) => {
+            println!("{} {}", "✘".error(), err);
+            return;
+        }
+    };
+    let language = language.unwrap_or_else(|| metas[0].language.clone());
+    let tags = tags.unwrap_or_else(|| {
+        let mut tags: Vec<String> = Vec::new();
+        for tag in metas.iter().flat_map(|meta| &meta.tags) {
+            if !tags.contains(tag) {
+                tags.push(tag.clone());
+            }
+        }
+        tags
+    });
+    let tags: Vec<&str> = tags.iter().map(|tag| tag.as_str()).collect();
 
-        let status = ProcessCommand::new("bash")
-            .arg("-c")
-            .arg(args)
-            .status()
-            .expect("Failed to execute shell commands");
+    let _lock = match executor.lock() {
+        Ok(lock) => lock,
+        Err(err) => {
+            println!("{} {}", "✘".error(), err);
+            return;
+        }
+    };
+    let merged = match merge::merge_snippets(
+        &vault::snippet_dir(config),
+        sources,
+        &language,
+        &tags,
+        timestamp,
+        executor,
+    ) {
+        Ok(merged) => merged,
+        Err(err) => {
+            println!("{} {}", "✘".error(), err);
+            return;
+        }
+    };
+    if !executor.is_dry_run() {
+        println!("{} Merged into {}", "✔".success(), merged.display());
+        index::log_access(&merged, index::Access::Created);
+        index::reindex_snippet(&merged, &merged);
+    }
 
-        if !status.success() {
-            println!("{} Failed to edit snippets.", "✘".red());
+    if !delete_sources {
+        return;
+    }
+    for source in sources {
+        let confirmed = force
+            || executor.is_dry_run()
+            || (prompt::is_interactive()
+                && prompt::confirm(&format!("Delete {}?", source.display()), false)
+                    .unwrap_or(false));
+        if !confirmed {
+            println!("{} Kept {}", "•".accent(), source.display());
+            continue;
+        }
+        match executor.apply(Mutation::Remove(source.clone())) {
+            Ok(()) if executor.is_dry_run() => {}
+            Ok(()) => {
+                index::forget_snippet(source);
+                println!("{} Deleted {}", "✔".success(), source.display());
+            }
+            Err(err) => println!("{} {}: {}", "✘".error(), source.display(), err),
         }
+    }
+}
+
+/// The `--remote` option of the S3 backup commands.
+fn remote_arg() -> Arg {
+    Arg::new("remote")
+        .long("remote")
+        .value_name("URL")
+        .help("Use s3://bucket/prefix instead of the [s3_backup] bucket and prefix")
+}
+
+/// A `__complete` subcommand printing candidates of kind `name`.
+fn complete_command(name: &'static str, about: &'static str) -> Command {
+    Command::new(name)
+        .about(about)
+        .arg(
+            Arg::new("prefix")
+                .default_value("")
+                .help("Print only candidates starting with PREFIX"),
+        )
+        .arg(
+            Arg::new("limit")
+                .long("limit")
+                .value_name("N")
+                .value_parser(clap::value_parser!(usize))
+                .help("Print at most N candidates"),
+        )
+}
+
+/// The `--multi` flag of the commands that pick snippets with fzf.
+fn multi_arg() -> Arg {
+    Arg::new("multi")
+        .long("multi")
+        .short('m')
+        .action(ArgAction::SetTrue)
+        .help("Select several snippets in fzf (tab marks one)")
+}
+
+/// The `--vscode` flag of the commands that open snippets in the editor.
+fn vscode_arg() -> Arg {
+    Arg::new("vscode")
+        .long("vscode")
+        .action(ArgAction::SetTrue)
+        .help("Open the selection in Visual Studio Code instead of the configured editor")
+}
+
+/// The `--open-with` flag of the commands that open snippets in the editor,
+/// read back by [`snippet_opener`] together with [`vscode_arg`].
+fn open_with_arg() -> Arg {
+    Arg::new("open_with")
+        .long("open-with")
+        .visible_alias("open_with")
+        .value_name("APP")
+        .value_parser(editor::OPENERS.to_vec())
+        .conflicts_with("vscode")
+        .help("Open the selection with APP instead of the configured open_with")
+}
+
+/// Where to open the selection of a command with [`vscode_arg`] and
+/// [`open_with_arg`]: `--vscode`, then `--open-with`, then `open_with`.
+fn snippet_opener(config: &Config, matches: &clap::ArgMatches) -> editor::Opener {
+    if matches.get_flag("vscode") {
+        return editor::Opener::Vscode;
+    }
+    matches
+        .get_one::<String>("open_with")
+        .and_then(|name| editor::Opener::from_name(name))
+        .unwrap_or_else(|| config.open_with())
+}
+
+/// The `--param name=value` flag of the commands that fill in snippet
+/// placeholders, read back by [`snippet_params`].
+fn param_arg() -> Arg {
+    Arg::new("param")
+        .long("param")
+        .short('p')
+        .value_name("NAME=VALUE")
+        .action(ArgAction::Append)
+        .value_parser(placeholder::parse_param)
+        .help("Value for a {{name}} or <name> placeholder (repeatable)")
+}
+
+/// The values given with [`param_arg`], by placeholder name.
+fn snippet_params(matches: &clap::ArgMatches) -> HashMap<String, String> {
+    matches
+        .get_many::<(String, String)>("param")
+        .unwrap_or_default()
+        .cloned()
+        .collect()
+}
+
+/// The snippet filter flags shared by `--list_snippets`, `--snippet_bulk`,
+/// `--snippet_regex_replace`, and `--snippet_word_frequency`, read back by
+/// [`snippet_filter`].
+fn filter_args() -> Vec<Arg> {
+    let [since, before] = created_range_args();
+    vec![
+        Arg::new("language")
+            .long("language")
+            .help("Only snippets in this language"),
+        Arg::new("tag_filter")
+            .long("tag_filter")
+            .visible_alias("tag")
+            .action(ArgAction::Append)
+            .help("Only snippets carrying this tag or a sub-tag of it, e.g. rust.async for rust (repeatable; all must match)"),
+        Arg::new("title")
+            .long("title")
+            .help("Only snippets whose title contains this text"),
+        Arg::new("older_than")
+            .long("older-than")
+            .visible_alias("older_than")
+            .value_name("AGE")
+            .value_parser(dates::parse_age)
+            .conflicts_with("before")
+            .help("Only snippets created more than AGE ago (e.g. 30d, 6w, 18m, 1y)"),
+        Arg::new("newer_than")
+            .long("newer-than")
+            .visible_alias("newer_than")
+            .value_name("AGE")
+            .value_parser(dates::parse_age)
+            .conflicts_with("since")
+            .help("Only snippets created within AGE"),
+        since,
+        before,
+        Arg::new("modified_older_than")
+            .long("modified-older-than")
+            .visible_alias("modified_older_than")
+            .value_name("AGE")
+            .value_parser(dates::parse_age)
+            .help("Only files last modified more than AGE ago"),
+        Arg::new("modified_newer_than")
+            .long("modified-newer-than")
+            .visible_alias("modified_newer_than")
+            .value_name("AGE")
+            .value_parser(dates::parse_age)
+            .help("Only files modified within AGE"),
+        Arg::new("pinned")
+            .long("pinned")
+            .action(ArgAction::SetTrue)
+            .help("Only snippets with `pinned = true`"),
+        Arg::new("archived")
+            .long("archived")
+            .action(ArgAction::SetTrue)
+            .conflicts_with("unarchived")
+            .help("Only snippets with `archived = true`"),
+        Arg::new("unarchived")
+            .long("unarchived")
+            .action(ArgAction::SetTrue)
+            .help("Skip snippets with `archived = true`"),
+        Arg::new("where")
+            .long("where")
+            .value_name("KEY=VALUE")
+            .action(ArgAction::Append)
+            .value_parser(listing::parse_field_condition)
+            .help("Only snippets whose custom field KEY equals VALUE (repeatable)"),
+    ]
+}
+
+/// `--since` and `--before`, bounding the creation time of the snippets a
+/// command considers; read back by [`created_range_filter`].
+fn created_range_args() -> [Arg; 2] {
+    [
+        Arg::new("since")
+            .long("since")
+            .value_name("WHEN")
+            .value_parser(dates::parse_when)
+            .help(
+                "Only snippets created at or after WHEN (2023-06-01, 2023-06, or an age like 3w)",
+            ),
+        Arg::new("before")
+            .long("before")
+            .value_name("WHEN")
+            .value_parser(dates::parse_when)
+            .help("Only snippets created before WHEN"),
+    ]
+}
+
+/// The filter selected by the [`created_range_args`] flags in `matches`.
+fn created_range_filter(matches: &clap::ArgMatches) -> listing::SnippetFilter {
+    listing::SnippetFilter {
+        created_after: matches.get_one::<chrono::NaiveDateTime>("since").copied(),
+        created_before: matches.get_one::<chrono::NaiveDateTime>("before").copied(),
+        ..Default::default()
+    }
+}
+
+/// Builds the filter selected by the [`filter_args`] flags in `matches`.
+fn snippet_filter(matches: &clap::ArgMatches) -> listing::SnippetFilter {
+    let ago = |id: &str| matches.get_one::<dates::Age>(id).map(|age| age.ago());
+    let when = |id: &str| matches.get_one::<chrono::NaiveDateTime>(id).copied();
+    let archived = if matches.get_flag("archived") {
+        Some(true)
+    } else if matches.get_flag("unarchived") {
+        Some(false)
     } else {
-        println!("{} Snippet directory does not exist.", "✘".red());
+        None
+    };
+
+    listing::SnippetFilter {
+        language: matches.get_one::<String>("language").cloned(),
+        tags: matches
+            .get_many::<String>("tag_filter")
+            .unwrap_or_default()
+            .cloned()
+            .collect(),
+        title: matches.get_one::<String>("title").cloned(),
+        created_after: when("since").or_else(|| ago("newer_than")),
+        created_before: when("before").or_else(|| ago("older_than")),
+        modified_after: ago("modified_newer_than"),
+        modified_before: ago("modified_older_than"),
+        pinned: matches.get_flag("pinned").then_some(true),
+        archived,
+        fields: matches
+            .get_many::<(String, String)>("where")
+            .unwrap_or_default()
+            .cloned()
+            .collect(),
+        limit: matches
+            .try_get_one::<usize>("limit")
+            .ok()
+            .flatten()
+            .copied(),
     }
 }
 
-/// Searches for a string in files within the snippet directory, previews results using `rg` and `fzf`,
-/// and opens the selected file in `nvim`.
-/// - `search_term`: The string to search for in the files.
-fn find_in_files(search_term: &str) {
-    let home_dir = env::var("HOME").unwrap();
-    let snippet_dir = format!("{}/{}", home_dir, SNIPPET_DIR);
-    let editor = get_default_editor();
+/// Applies `action` to every snippet `filter` selects, after showing how many
+/// match and asking for confirmation (skipped with `yes`, under `--dry-run`,
+/// and for read-only actions).
+fn bulk_apply(
+    config: &Config,
+    filter: &listing::SnippetFilter,
+    action: &bulk::BulkAction,
+    yes: bool,
+    use_cache: bool,
+    executor: &Executor,
+) -> Result<(), SnippetVaultError> {
+    let snippets = listing::query_snippets(
+        &vault::snippet_dir(config),
+        filter,
+        listing::SortCriterion::Path,
+        &config.extensions(),
+        use_cache,
+    )?;
+    if snippets.is_empty() {
+        println!("{} No snippets match.", "•".accent());
+        return Ok(());
+    }
 
-    if Path::new(&snippet_dir).exists() {
-        // Delegate the functionality to a shell script using `rg`, `fzf`, and `nvim`
-        let args = format!(
-            r#"
-                cd "{}" &&
-                rg --files-with-matches --no-messages '{}' |
-                fzf --sort --preview-window down:80%:wrap --preview "rg --ignore-case --pretty --context 10 --colors 'match:bg:red' --colors 'match:fg:white' '{}' {{}}" |
-                xargs -r {}
-                "#,
-            snippet_dir, search_term, search_term, editor
+    if *action == bulk::BulkAction::Print {
+        for snippet in &snippets {
+            println!("{}", snippet.path.display());
+        }
+        return Ok(());
+    }
+
+    println!("{} {} snippets match.", "•".accent(), snippets.len());
+    if action.is_destructive() && !yes && !executor.is_dry_run() {
+        if !prompt::is_interactive() {
+            return Err(SnippetVaultError::Config(
+                "refusing to change snippets without a terminal (pass --yes)".to_string(),
+            ));
+        }
+        if !prompt::confirm("Continue?", false)? {
+            return Ok(());
+        }
+    }
+
+    let _lock = executor.lock()?;
+    if let bulk::BulkAction::Export(output_dir) = action {
+        executor.apply(Mutation::CreateDir(output_dir.clone()))?;
+    }
+
+    let deleting = *action == bulk::BulkAction::Delete;
+    let mut done = 0;
+    for snippet in &snippets {
+        // Read before the file goes, so `post_delete` sees the same values.
+        let hook_env = deleting.then(|| hooks::SnippetEnv::read(&snippet.path));
+        if let Some(hook_env) = hook_env.as_ref().filter(|_| !executor.is_dry_run()) {
+            if let Err(err) = hooks::run_hook("pre_delete", &hook_env.vars(), config) {
+                println!("{} {}: {}; kept.", "✘".error(), snippet.path.display(), err);
+                continue;
+            }
+        }
+        match bulk::apply(&snippet.path, action, executor) {
+            Ok(_) if executor.is_dry_run() => {}
+            Ok(Some(new_path)) => {
+                if action.is_destructive() {
+                    index::reindex_snippet(&snippet.path, &new_path);
+                }
+                done += 1;
+            }
+            Ok(None) => {
+                index::forget_snippet(&snippet.path);
+                done += 1;
+                if let Some(hook_env) = &hook_env {
+                    hooks::run_post_hook("post_delete", &hook_env.vars(), config);
+                }
+            }
+            Err(err) => println!("{} {}: {}", "✘".error(), snippet.path.display(), err),
+        }
+    }
+    if !executor.is_dry_run() {
+        println!("{} {} {} snippets.", "✔".success(), action.verb(), done);
+    }
+    Ok(())
+}
+
+/// Prints the metadata of the selected snippets as a JSON array, in the order
+/// `--no_fzf` prints their paths.
+/// - `filter`, `sort`: Which snippets to print, and in what order.
+/// - `show_hash`: Add each snippet's `content_hash`.
+/// - `use_cache`: Serve unchanged snippets from the metadata cache.
+fn list_snippets_json(
+    config: &Config,
+    filter: &listing::SnippetFilter,
+    sort: listing::SortCriterion,
+    show_hash: bool,
+    use_cache: bool,
+) {
+    match listing::list_snippets_json(
+        &vault::snippet_dir(config),
+        filter,
+        sort,
+        &config.extensions(),
+        show_hash,
+        use_cache,
+    ) {
+        Ok(json) => println!("{}", json),
+        Err(err) => println!("{} {}", "✘".error(), err),
+    }
+}
+
+/// Prints the selected snippets in sections per language or tag, each headed
+/// by its name and size, with every snippet's title and age below it (see
+/// [`listing::group_snippets`]); `json` prints the same structure as JSON.
+/// `--limit` applies to each group rather than to the whole listing.
+/// - `use_cache`: Serve unchanged snippets from the metadata cache.
+fn list_snippets_grouped(
+    config: &Config,
+    filter: &listing::SnippetFilter,
+    group_by: listing::GroupBy,
+    json: bool,
+    use_cache: bool,
+) {
+    let unlimited = listing::SnippetFilter {
+        limit: None,
+        ..filter.clone()
+    };
+    let snippets = match listing::query_snippets(
+        &vault::snippet_dir(config),
+        &unlimited,
+        listing::SortCriterion::Path,
+        &config.extensions(),
+        use_cache,
+    ) {
+        Ok(snippets) => snippets,
+        Err(err) => {
+            println!("{} {}", "✘".error(), err);
+            return;
+        }
+    };
+    let listing =
+        listing::group_snippets(snippets, group_by, filter.limit, &config.language_aliases);
+    if json {
+        println!(
+            "{}",
+            serde_json::to_string_pretty(&listing).expect("metadata is always serializable")
         );
+        return;
+    }
+
+    let mut out = Pager::new(config);
+    let kind = match group_by {
+        listing::GroupBy::Language => "languages",
+        listing::GroupBy::Tag => "tags",
+    };
+    let shown: usize = listing.groups.iter().map(|group| group.count).sum();
+    if shown > listing.total {
+        out.line(format_args!(
+            "{} snippets in {} {} ({} listed; snippets with several tags appear under each)",
+            listing.total,
+            listing.groups.len(),
+            kind,
+            shown
+        ));
+    } else {
+        out.line(format_args!(
+            "{} snippets in {} {}",
+            listing.total,
+            listing.groups.len(),
+            kind
+        ));
+    }
+    let now = Local::now().naive_local();
+    for group in &listing.groups {
+        out.line("");
+        out.line(format_args!(
+            "{} ({})",
+            group.name.as_str().accent().bold(),
+            group.count
+        ));
+        for snippet in &group.snippets {
+            let age = listing::recency(snippet)
+                .map(|when| dates::relative_age(when, now))
+                .unwrap_or_default();
+            out.line(format_args!("  {}  {}", snippet.title, age.dimmed()));
+        }
+        if group.snippets.len() < group.count {
+            out.line(format_args!(
+                "  {}",
+                format!("… and {} more", group.count - group.snippets.len()).dimmed()
+            ));
+        }
+    }
+}
 
-        let status = ProcessCommand::new("bash")
-            .arg("-c")
-            .arg(args)
-            .status()
-            .expect("Failed to execute search and open command");
+/// Prints the path of each selected snippet on its own line, for shell pipelines.
+/// - `filter`, `sort`: Which snippets to print, and in what order.
+/// - `use_cache`: Serve unchanged snippets from the metadata cache.
+fn list_snippets_no_fzf(
+    config: &Config,
+    filter: &listing::SnippetFilter,
+    sort: listing::SortCriterion,
+    use_cache: bool,
+) {
+    match listing::list_snippets_plain(
+        &vault::snippet_dir(config),
+        filter,
+        sort,
+        &config.extensions(),
+        use_cache,
+    ) {
+        Ok(paths) => {
+            for path in paths {
+                println!("{}", path.display());
+            }
+        }
+        Err(err) => eprintln!("{} {}", "✘".error(), err),
+    }
+}
 
-        if !status.success() {
-            println!(
-                "{} Failed to find or open files with the term '{}'.",
-                "✘".red(),
-                search_term
-            );
+/// Prints the selected snippets as an aligned table fitted to the output width,
+/// or as tab-separated values when stdout is not a terminal and no `--width`
+/// was given.
+/// - `filter`, `sort`: Which snippets to print, and in what order.
+/// - `columns`: Table columns, in order.
+/// - `header`: Print the column names first.
+/// - `use_cache`: Serve unchanged snippets from the metadata cache.
+fn list_snippets_table(
+    config: &Config,
+    filter: &listing::SnippetFilter,
+    sort: listing::SortCriterion,
+    columns: &[&str],
+    header: bool,
+    use_cache: bool,
+) {
+    let snippets = match listing::query_snippets(
+        &vault::snippet_dir(config),
+        filter,
+        sort,
+        &config.extensions(),
+        use_cache,
+    ) {
+        Ok(snippets) => snippets,
+        Err(err) => {
+            eprintln!("{} {}", "✘".error(), err);
+            return;
         }
+    };
+
+    if std::io::stdout().is_terminal() || config.width_override.is_some() {
+        let table = listing::render_table(&snippets, columns, header, Some(config.output_width()));
+        print!("{}", table);
     } else {
-        println!("{} Snippet directory does not exist.", "✘".red());
+        print!("{}", listing::render_tsv(&snippets, columns, header));
     }
 }
 
-/// Displays a list of supported programming languages.
-fn list_languages() {
-    let languages = vec![
-        "python",
-        "cpp",
-        "bash",
-        "terminal",
-        "shell",
-        "zsh",
-        "php",
-        "typescript",
-        "scala",
-        "nvim",
-        "neovim",
-        "pdf",
-        "markdown",
-        "org",
-        "text",
-        "shell",
-        "powerShell",
-        "perl",
-        "haskell",
-        "kotlin",
-        "sql",
-        "matlap",
-        "groovy",
-        "lua",
-        "rust",
-        "ruby",
-        "html and css",
-        "ruby",
-        "java",
-        "javascript",
-        "swift",
-        "c++",
-        "c#",
-        "docker",
-        "kubernetes",
-        "docker-compose",
-        "rlang(R)",
-        "golang(Go)",
-        "vim",
-        "apple",
-        "mac",
-        "macos",
-        "applescript",
-        "git",
-        "gnuplot",
-        "github",
-        "linux",
-        "gnu-linux",
-        "ubuntu",
-        "note",
-        "memo",
-        "awk",
-        "sed",
-        "tr",
-        "cat",
-        "jupyter",
-        "jupyterlab",
-        "lab",
-        "bat",
-        "latex",
-        "emacs",
-    ];
+/// Prints the number of selected snippets without spawning any process, and
+/// exits with status 1 when there are none so shell conditionals read naturally.
+/// - `filter`: Which snippets to count.
+/// - `use_cache`: Serve unchanged snippets from the metadata cache.
+fn count_snippets(config: &Config, filter: &listing::SnippetFilter, use_cache: bool) {
+    match listing::query_snippets(
+        &vault::snippet_dir(config),
+        filter,
+        listing::SortCriterion::Path,
+        &config.extensions(),
+        use_cache,
+    ) {
+        Ok(snippets) => exit_with_count(snippets.len()),
+        Err(err) => {
+            eprintln!("{} {}", "✘".error(), err);
+            std::process::exit(2);
+        }
+    }
+}
 
-    for lang in languages {
-        println!("{}", lang.cyan());
+/// Prints how many files contain `search_term` and pass `filter`, searching
+/// in-process instead of spawning `rg`, and exits with status 1 when there are
+/// none. These are the files the `--find_in_files` picker offers.
+fn count_files_containing(config: &Config, search_term: &str, filter: &listing::SnippetFilter) {
+    match matching_files(config, &vault::snippet_dir(config), &[search_term], filter) {
+        Ok(files) => exit_with_count(files.len()),
+        Err(err) => {
+            eprintln!("{} {}", "✘".error(), err);
+            std::process::exit(2);
+        }
     }
 }
 
-/// Retrieves the default editor for editing snippets.
-/// Tries a list of known paths for `nvim` or defaults to `nvim`.
-fn get_default_editor() -> String {
-    let editor_paths = vec![
-        "$HOME/dev/nvim/bin/nvim",
-        "$HOME/dev/neovim/build/bin/nvim",
-        "$HOME/dev/neovim/bin/nvim",
-        "/usr/local/bin/nvim",
-    ];
+fn exit_with_count(count: usize) {
+    println!("{}", count);
+    if count == 0 {
+        std::process::exit(1);
+    }
+}
+
+/// Offers the snippets relevant to the git project of the working directory
+/// in fzf (see [`context::context_search`]) and opens the chosen one. Outside
+/// a git repository this is the plain `--list_snippets` picker.
+/// - `use_cache`: Serve unchanged snippets from the metadata cache.
+/// - `executor`: Formats edited snippets when `format_on_save` is on.
+fn context_search(config: &Config, use_cache: bool, executor: &Executor) {
+    let cwd = std::env::current_dir().unwrap_or_default();
+    let project = match context::detect_project(&cwd) {
+        Ok(Some(project)) => project,
+        Ok(None) => {
+            list_snippets(config, None, false, executor);
+            return;
+        }
+        Err(err) => {
+            println!("{} {}", "✘".error(), err);
+            return;
+        }
+    };
+
+    let snippet_dir = vault::snippet_dir(config);
+    let paths = match context::context_search(&cwd, &snippet_dir, &config.extensions(), use_cache) {
+        Ok(paths) => paths,
+        Err(err) => {
+            println!("{} {}", "✘".error(), err);
+            return;
+        }
+    };
+    if paths.is_empty() {
+        println!("{} No snippets match this project.", "•".accent());
+        return;
+    }
+
+    let header = match (&project.language, &project.name) {
+        (Some(language), Some(name)) => {
+            format!("{} snippets and snippets tagged '{}'", language, name)
+        }
+        (Some(language), None) => format!("{} snippets", language),
+        (None, Some(name)) => format!("Snippets tagged '{}'", name),
+        (None, None) => "All snippets".to_string(),
+    };
+    let entries: Vec<(String, PathBuf)> = paths
+        .into_iter()
+        .map(|path| {
+            let label = path.strip_prefix(&snippet_dir).unwrap_or(&path).display();
+            (label.to_string(), path)
+        })
+        .collect();
+    match picker::pick_with_header(config, &entries, &header) {
+        Ok(Some(path)) => {
+            index::log_access(&path, index::Access::Opened);
+            if let Err(err) = editor::open(config, &[path], executor) {
+                println!("{} Cannot open the editor: {}", "✘".error(), err);
+            }
+        }
+        Ok(None) => {}
+        Err(err) => println!("{} {}", "✘".error(), err),
+    }
+}
+
+/// Passes the selected snippets to fzf and opens the chosen one in the editor.
+/// - `filter`, `sort`: Which snippets to offer, and in what order.
+/// - `query`: Initial fzf query, applied on top of the filtered set.
+/// - `multi`: Allow choosing several snippets, opened together.
+/// - `opener`: Where to open them.
+/// - `use_cache`: Serve unchanged snippets from the metadata cache.
+/// - `executor`: Formats edited snippets when `format_on_save` is on.
+#[allow(clippy::too_many_arguments)]
+fn pick_snippet(
+    config: &Config,
+    filter: &listing::SnippetFilter,
+    sort: listing::SortCriterion,
+    query: Option<&str>,
+    multi: bool,
+    opener: editor::Opener,
+    use_cache: bool,
+    executor: &Executor,
+) {
+    let snippet_dir = vault::snippet_dir(config);
+    let picked =
+        listing::list_snippets_plain(&snippet_dir, filter, sort, &config.extensions(), use_cache)
+            .and_then(|paths| {
+                let entries: Vec<(String, PathBuf)> = paths
+                    .into_iter()
+                    .map(|path| {
+                        let label = path.strip_prefix(&snippet_dir).unwrap_or(&path).display();
+                        (label.to_string(), path)
+                    })
+                    .collect();
+                if matches!(
+                    sort,
+                    listing::SortCriterion::Natural | listing::SortCriterion::Uses
+                ) {
+                    picker::pick_in_order(config, &entries, query, multi)
+                } else if multi {
+                    picker::pick_many(config, &entries, query)
+                } else {
+                    picker::pick(config, &entries, query).map(Vec::from_iter)
+                }
+            });
+
+    match picked {
+        Ok(paths) if paths.is_empty() => {}
+        Ok(paths) => {
+            for path in &paths {
+                index::log_access(path, index::Access::Opened);
+            }
+            if let Err(err) = open_snippets(config, &paths, opener, executor) {
+                println!("{} {}", "✘".error(), err);
+            }
+        }
+        Err(err) => println!("{} {}", "✘".error(), err),
+    }
+}
+
+/// Like [`pick_snippet`], but shows each snippet as a compact line of id,
+/// language, tags, and title ([`listing::format_compact_line`]); the picked
+/// lines are opened by the path fzf hands back.
+/// - `executor`: Formats edited snippets when `format_on_save` is on.
+#[allow(clippy::too_many_arguments)]
+fn pick_snippet_compact(
+    config: &Config,
+    filter: &listing::SnippetFilter,
+    sort: listing::SortCriterion,
+    query: Option<&str>,
+    multi: bool,
+    opener: editor::Opener,
+    use_cache: bool,
+    executor: &Executor,
+) {
+    let snippet_dir = vault::snippet_dir(config);
+    let snippets = match listing::query_snippets(
+        &snippet_dir,
+        filter,
+        sort,
+        &config.extensions(),
+        use_cache,
+    ) {
+        Ok(snippets) => snippets,
+        Err(err) => {
+            println!("{} {}", "✘".error(), err);
+            return;
+        }
+    };
+    let entries: Vec<(String, String, PathBuf)> = snippets
+        .iter()
+        .map(|snippet| {
+            let id = vault::snippet_id(&snippet.path);
+            let line = listing::format_compact_line(snippet, &id);
+            (id, line, snippet.path.clone())
+        })
+        .collect();
+
+    let paths = match picker::pick_compact(config, &entries, query, multi) {
+        Ok(paths) => paths,
+        Err(err) => {
+            println!("{} {}", "✘".error(), err);
+            return;
+        }
+    };
+    if paths.is_empty() {
+        return;
+    }
+    for path in &paths {
+        index::log_access(path, index::Access::Opened);
+    }
+    if let Err(err) = open_snippets(config, &paths, opener, executor) {
+        println!("{} {}", "✘".error(), err);
+    }
+}
+
+/// Prints the `count` snippets with the most lines, biggest first.
+/// Binary files are ranked by size and marked instead of showing line counts.
+/// - `use_cache`: Serve unchanged snippets from the metadata cache.
+fn largest_snippets(config: &Config, count: usize, use_cache: bool) {
+    let mut snippets =
+        match vault::load_snippets(&vault::snippet_dir(config), &config.extensions(), use_cache) {
+            Ok(snippets) => snippets,
+            Err(err) => {
+                println!("{} {}", "✘".error(), err);
+                return;
+            }
+        };
+
+    snippets.sort_by(|a, b| {
+        (b.stats.lines, b.stats.bytes)
+            .cmp(&(a.stats.lines, a.stats.bytes))
+            .then(a.path.cmp(&b.path))
+    });
+    let mut out = Pager::new(config);
+    for snippet in snippets.iter().take(count) {
+        let lines = if snippet.stats.binary {
+            "binary".to_string()
+        } else {
+            snippet.stats.lines.to_string()
+        };
+        out.line(format_args!(
+            "{:>7} lines {:>6} words {:>8} bytes  {}  {}",
+            lines.bold(),
+            snippet.stats.words,
+            snippet.stats.bytes,
+            snippet.title.accent(),
+            snippet.path.display()
+        ));
+    }
+}
+
+/// Prints the `count` most common prose words of the snippets `filter` selects
+/// as a word and count table.
+/// - `min_length`: Skip words shorter than this many characters.
+fn word_frequency(
+    config: &Config,
+    count: usize,
+    min_length: usize,
+    filter: &listing::SnippetFilter,
+    use_cache: bool,
+) {
+    let frequencies = match words::word_frequency(
+        &vault::snippet_dir(config),
+        count,
+        min_length,
+        filter,
+        &config.extensions(),
+        use_cache,
+    ) {
+        Ok(frequencies) => frequencies,
+        Err(err) => {
+            println!("{} {}", "✘".error(), err);
+            return;
+        }
+    };
+    if frequencies.is_empty() {
+        println!("{} No prose words found.", "•".accent());
+        return;
+    }
+
+    let column = frequencies
+        .iter()
+        .map(|(word, _)| width::display_width(word))
+        .max()
+        .unwrap_or(0)
+        .max("WORD".len());
+    let mut out = Pager::new(config);
+    out.line(format_args!(
+        "{}",
+        format!("{}  {:>6}", width::pad("WORD", column), "COUNT").bold()
+    ));
+    for (word, occurrences) in &frequencies {
+        out.line(format_args!(
+            "{}  {:>6}",
+            width::pad(word, column),
+            occurrences
+        ));
+    }
+}
+
+/// Lets the user pick among the snippets tagged both `tag_a` and `tag_b` and
+/// opens the choice in its editor.
+/// - `count_only`: Print just the number of such snippets instead.
+/// - `use_cache`: Serve unchanged snippets from the metadata cache.
+/// - `executor`: Formats edited snippets when `format_on_save` is on.
+fn grep_tags(
+    config: &Config,
+    tag_a: &str,
+    tag_b: &str,
+    count_only: bool,
+    use_cache: bool,
+    executor: &Executor,
+) {
+    let snippet_dir = vault::snippet_dir(config);
+    let paths = match tags::find_tag_cooccurrence(
+        tag_a,
+        tag_b,
+        &snippet_dir,
+        &config.extensions(),
+        use_cache,
+    ) {
+        Ok(paths) => paths,
+        Err(err) => {
+            println!("{} {}", "✘".error(), err);
+            return;
+        }
+    };
+    if count_only {
+        println!("{}", paths.len());
+        return;
+    }
+    if paths.is_empty() {
+        println!(
+            "{} No snippets are tagged both '{}' and '{}'.",
+            "•".accent(),
+            tag_a,
+            tag_b
+        );
+        return;
+    }
+
+    let entries: Vec<(String, PathBuf)> = paths
+        .into_iter()
+        .map(|path| {
+            let label = path
+                .strip_prefix(&snippet_dir)
+                .unwrap_or(&path)
+                .display()
+                .to_string();
+            (label, path)
+        })
+        .collect();
+    let header = format!("Snippets tagged both '{}' and '{}'", tag_a, tag_b);
+    match picker::pick_with_header(config, &entries, &header) {
+        Ok(Some(path)) => {
+            index::log_access(&path, index::Access::Opened);
+            if let Err(err) = editor::open_in_editor(config, &path, None, executor) {
+                println!("{} Cannot open the editor: {}", "✘".error(), err);
+            }
+        }
+        Ok(None) => {}
+        Err(err) => println!("{} {}", "✘".error(), err),
+    }
+}
+
+/// Opens the files a picker script printed, one path per line relative to
+/// `snippet_dir`, each in its editor.
+/// - `search_term`: Put the cursor on the first match when a single file was picked.
+/// - `executor`: Formats edited snippets when `format_on_save` is on.
+fn open_selection(
+    config: &Config,
+    snippet_dir: &Path,
+    stdout: &[u8],
+    search_term: Option<&str>,
+    executor: &Executor,
+) {
+    let paths: Vec<PathBuf> = String::from_utf8_lossy(stdout)
+        .lines()
+        .filter(|line| !line.trim().is_empty())
+        .map(|line| snippet_dir.join(line))
+        .collect();
+    for path in &paths {
+        index::log_access(path, index::Access::Opened);
+    }
+    let opened = match (paths.as_slice(), search_term) {
+        ([path], Some(term)) => editor::open_in_editor(
+            config,
+            path,
+            listing::first_match_line(path, term),
+            executor,
+        ),
+        _ => editor::open(config, &paths, executor),
+    };
+    if let Err(err) = opened {
+        println!("{} Cannot open the editor: {}", "✘".error(), err);
+    }
+}
+
+/// Opens snippets picked with fzf. With a `query`, the best match is opened
+/// directly when it is confident, otherwise the picker opens filtered by it.
+/// All selections go to the editor in one invocation. Returns whether anything
+/// was selected.
+/// - `multi`: Pick several snippets (never skips the picker).
+/// - `pick`: Print the selected paths instead of opening them.
+/// - `opener`: Where to open them.
+/// - `use_cache`: Serve unchanged snippets from the metadata cache.
+/// - `executor`: Archives expired snippets when configured to.
+fn edit_snippet(
+    config: &Config,
+    query: Option<&str>,
+    multi: bool,
+    pick: bool,
+    opener: editor::Opener,
+    use_cache: bool,
+    executor: &Executor,
+) -> Result<bool, SnippetVaultError> {
+    let snippet_dir = vault::snippet_dir(config);
+    let snippets = vault::load_snippets(&snippet_dir, &config.extensions(), use_cache)?;
+    let confident = query.filter(|_| !multi).and_then(|query| {
+        let ranked = fuzzy::rank(&snippets, query);
+        fuzzy::confident_match(&ranked, query.split_whitespace().count()).cloned()
+    });
+
+    let selected = match confident {
+        Some(path) => vec![path],
+        None => {
+            let entries = picker_entries(&snippet_dir, snippets);
+            if multi {
+                picker::pick_many(config, &entries, query)?
+            } else {
+                picker::pick(config, &entries, query)?.into_iter().collect()
+            }
+        }
+    };
 
-    for path in editor_paths {
-        let expanded_path = shellexpand::tilde(path).to_string();
-        if Path::new(&expanded_path).exists() {
-            return expanded_path;
+    if selected.is_empty() {
+        return Ok(false);
+    }
+    if pick {
+        for path in &selected {
+            println!("{}", path.display());
+        }
+    } else {
+        for path in &selected {
+            check_expiry(config, path, executor);
+            index::log_access(path, index::Access::Opened);
         }
+        open_snippets(config, &selected, opener, executor)?;
     }
+    Ok(true)
+}
 
-    "nvim".to_string()
+/// Opens `paths` with `opener`: in their editors, or one by one in Visual
+/// Studio Code (see [`editor::open_in_vscode`]) or Obsidian. A file Obsidian
+/// cannot be launched for is opened in the editor after a warning.
+/// - `executor`: Formats edited snippets when `format_on_save` is on.
+fn open_snippets(
+    config: &Config,
+    paths: &[PathBuf],
+    opener: editor::Opener,
+    executor: &Executor,
+) -> Result<(), SnippetVaultError> {
+    match opener {
+        editor::Opener::Editor => {
+            editor::open(config, paths, executor)?;
+        }
+        editor::Opener::Vscode => {
+            for path in paths {
+                editor::open_in_vscode(config, path, executor)?;
+            }
+        }
+        editor::Opener::Obsidian => {
+            let snippet_dir = vault::snippet_dir(config);
+            let name = obsidian::vault_name(config.obsidian_vault.as_deref(), &snippet_dir);
+            for path in paths {
+                let opened = match &name {
+                    Some(name) => obsidian::open(&snippet_dir, name, path),
+                    None => Err(SnippetVaultError::Config(
+                        "set obsidian_vault to the vault's name in Obsidian".to_string(),
+                    )),
+                };
+                if let Err(err) = opened {
+                    println!(
+                        "{} Cannot open {} in Obsidian ({}); using the editor.",
+                        "⚠".warning(),
+                        path.display(),
+                        err
+                    );
+                    editor::open(config, std::slice::from_ref(path), executor)?;
+                }
+            }
+        }
+    }
+    Ok(())
+}
+
+/// Opens `file` (or the snippet picked with fzf) in Visual Studio Code, falling
+/// back to the configured editor when it is not installed.
+/// - `use_cache`: Serve unchanged snippets from the metadata cache for the picker.
+/// - `executor`: Archives an expired snippet when configured to.
+fn open_in_vscode(
+    config: &Config,
+    file: Option<PathBuf>,
+    use_cache: bool,
+    executor: &Executor,
+) -> Result<(), SnippetVaultError> {
+    let Some(path) = file.or_else(|| choose_snippet(config, &config.extensions(), use_cache))
+    else {
+        return Ok(());
+    };
+    check_expiry(config, &path, executor);
+    index::log_access(&path, index::Access::Opened);
+    editor::open_in_vscode(config, &path, executor)
+}
+
+/// Lets the user select lines of `file` (or the snippet picked with fzf) in
+/// neovim and creates a new snippet from them, with the source's language and
+/// tags, as `--create_snippet` would.
+/// - `timestamp`: Names the new snippet.
+/// - `use_cache`: Serve unchanged snippets from the metadata cache for the picker.
+/// - `executor`: Applies (or, with `--dry-run`, only prints) the new snippet.
+fn extract_selection(
+    config: &Config,
+    file: Option<PathBuf>,
+    timestamp: &str,
+    use_cache: bool,
+    executor: &Executor,
+) -> Result<(), SnippetVaultError> {
+    let Some(source) = file.or_else(|| choose_snippet(config, &config.extensions(), use_cache))
+    else {
+        return Ok(());
+    };
+    let meta = snippet::SnippetMeta::read(&source)?;
+    let content = std::fs::read_to_string(&source)?;
+    let Some((first, last)) = editor::select_lines(&source)? else {
+        println!("{} Nothing selected; no snippet created.", "•".accent());
+        return Ok(());
+    };
+    let code = content
+        .lines()
+        .skip(first.saturating_sub(1))
+        .take(last.saturating_sub(first) + 1)
+        .collect::<Vec<_>>()
+        .join("\n");
+    if code.trim().is_empty() {
+        println!(
+            "{} Only blank lines selected; no snippet created.",
+            "•".accent()
+        );
+        return Ok(());
+    }
+
+    let tags: Vec<&str> = meta.tags.iter().map(String::as_str).collect();
+    create_snippet(
+        config,
+        &meta.language,
+        &tags,
+        timestamp,
+        false,
+        &code,
+        snippet::PlaceholderStyle::default(),
+        executor,
+    );
+    Ok(())
+}
+
+/// Searches for a string in files within the snippet directory, previews results using `rg` and `fzf`,
+/// and opens the selected file in its editor.
+/// - `search_term`: The string to search for in the files.
+/// - `filter`: Only offer snippets passing it (a `--since`/`--before` range).
+/// - `limit`: Pass at most this many matching files to fzf.
+/// - `context`: Lines shown around each match in the preview.
+/// - `executor`: Formats edited snippets when `format_on_save` is on.
+fn find_in_files(
+    config: &Config,
+    search_term: &str,
+    filter: &listing::SnippetFilter,
+    limit: Option<usize>,
+    context: usize,
+    executor: &Executor,
+) {
+    let snippet_dir = vault::snippet_dir(config);
+    if !snippet_dir.exists() {
+        println!("{} Snippet directory does not exist.", "✘".error());
+        return;
+    }
+    match search_and_open(
+        config,
+        &[search_term],
+        filter,
+        &snippet_dir,
+        limit,
+        context,
+        executor,
+    ) {
+        Ok(true) => {}
+        Ok(false) => println!(
+            "{} No file was opened for the term '{}'.",
+            "•".accent(),
+            search_term
+        ),
+        Err(err) => println!("{} {}", "✘".error(), err),
+    }
+}
+
+/// Searches the contents of the snippets for all the `words` of a query and
+/// lets the user open a match as [`search_and_open`] does. When nothing
+/// matches, offers to create a snippet instead, with the language and tags
+/// taken from the words (see [`language::language_and_tags`]). Returns whether
+/// a snippet was opened or created; a dismissed picker or a declined prompt
+/// both return `false`.
+/// - `timestamp`: Names the new snippet.
+/// - `yes`: Create it without asking.
+/// - `executor`: Applies (or, with `--dry-run`, only prints) the new snippet.
+fn search_or_create(
+    config: &Config,
+    words: &[&str],
+    snippet_dir: &Path,
+    timestamp: &str,
+    yes: bool,
+    executor: &Executor,
+) -> Result<bool, SnippetVaultError> {
+    if !snippet_dir.exists() {
+        return Err(SnippetVaultError::Config(
+            "snippet directory does not exist".to_string(),
+        ));
+    }
+    if !listing::files_containing(snippet_dir, words, &config.extensions())?.is_empty() {
+        return search_and_open(
+            config,
+            words,
+            &listing::SnippetFilter::default(),
+            snippet_dir,
+            None,
+            config.search_context_lines(),
+            executor,
+        );
+    }
+
+    if !yes && !prompt::confirm("No matches found. Create a new snippet?", false)? {
+        return Ok(false);
+    }
+    let (language, tags) = language::language_and_tags(&words.join(" "), &config.language_aliases);
+    let tags: Vec<&str> = tags.iter().map(String::as_str).collect();
+    create_snippet(
+        config,
+        &language,
+        &tags,
+        timestamp,
+        false,
+        "",
+        snippet::PlaceholderStyle::default(),
+        executor,
+    );
+    Ok(true)
+}
+
+/// Lists the snippets of `snippet_dir` containing every one of `terms` and
+/// passing `filter` (the files `--count` counts, see [`matching_files`]), lets
+/// the user choose one in fzf with the matches previewed in context, and opens
+/// it in its editor at the first match. Returns whether a file was opened;
+/// nothing matching and a dismissed picker both return `false`.
+/// - `limit`: Pass at most this many matching files to fzf.
+/// - `context`: Lines shown around each match in the preview.
+/// - `executor`: Formats edited snippets when `format_on_save` is on.
+#[allow(clippy::too_many_arguments)]
+fn search_and_open(
+    config: &Config,
+    terms: &[&str],
+    filter: &listing::SnippetFilter,
+    snippet_dir: &Path,
+    limit: Option<usize>,
+    context: usize,
+    executor: &Executor,
+) -> Result<bool, SnippetVaultError> {
+    let files: String = matching_files(config, snippet_dir, terms, filter)?
+        .iter()
+        .filter_map(|path| path.strip_prefix(snippet_dir).ok())
+        .take(limit.unwrap_or(usize::MAX))
+        .map(|path| format!("{}\n", path.display()))
+        .collect();
+    if files.is_empty() {
+        return Ok(false);
+    }
+
+    prompt::require_interactive("pick with fzf", "count matches with --count instead")?;
+    let patterns: String = terms
+        .iter()
+        .map(|term| format!("-e {} ", picker::shell_quote(term)))
+        .collect();
+    let preview = format!(
+        "rg --ignore-case --pretty --context {} --colors 'match:bg:red' --colors 'match:fg:white' {}{{}}",
+        context, patterns
+    );
+    let mut fzf = ProcessCommand::new("fzf");
+    fzf.current_dir(snippet_dir)
+        .arg("--preview")
+        .arg(preview)
+        .args(picker::fzf_args(config, "{}"));
+    let output = logging::run_with_input(&mut fzf, &files)
+        .map_err(|err| SnippetVaultError::Command(format!("fzf: {}", err)))?;
+    let selection = String::from_utf8_lossy(&output.stdout);
+    let Some(selected) = selection.lines().find(|line| !line.trim().is_empty()) else {
+        return Ok(false);
+    };
+
+    let path = snippet_dir.join(selected);
+    index::log_access(&path, index::Access::Opened);
+    editor::open_in_editor(
+        config,
+        &path,
+        terms
+            .iter()
+            .find_map(|term| listing::first_match_line(&path, term)),
+        executor,
+    )?;
+    Ok(true)
+}
+
+/// The files [`listing::files_containing`] finds for `terms` whose snippets also
+/// pass `filter`; an inactive filter reads no metadata.
+fn matching_files(
+    config: &Config,
+    snippet_dir: &Path,
+    terms: &[&str],
+    filter: &listing::SnippetFilter,
+) -> Result<Vec<PathBuf>, SnippetVaultError> {
+    let files = listing::files_containing(snippet_dir, terms, &config.extensions())?;
+    if !filter.is_active() {
+        return Ok(files);
+    }
+    Ok(files
+        .into_iter()
+        .filter(|path| snippet::SnippetMeta::read(path).is_ok_and(|meta| filter.matches(&meta)))
+        .collect())
+}
+
+/// Migrates the vault to v2 frontmatter and prints a summary.
+/// - `skip_backup`: Do not zip the vault before migrating.
+/// - `executor`: Applies (or, with `--dry-run`, only prints) the rewrites.
+fn migrate_vault(config: &Config, skip_backup: bool, executor: &Executor) {
+    let snippet_dir = vault::snippet_dir(config);
+
+    if !snippet_dir.exists() {
+        println!("{} Snippet directory does not exist.", "✘".error());
+        return;
+    }
+    let _lock = match executor.lock() {
+        Ok(lock) => lock,
+        Err(err) => {
+            println!("{} {}", "✘".error(), err);
+            return;
+        }
+    };
+
+    if !executor.is_dry_run() && !skip_backup {
+        match vault::backup_vault(&snippet_dir) {
+            Ok(archive) => println!("{} Backup created: {}", "✔".success(), archive.display()),
+            Err(err) => {
+                println!(
+                    "{} {} (use --skip_backup to migrate anyway)",
+                    "✘".error(),
+                    err
+                );
+                return;
+            }
+        }
+    }
+
+    match migrate_v1_to_v2(&snippet_dir, executor) {
+        Ok(report) => {
+            let verb = if executor.is_dry_run() {
+                "Would migrate"
+            } else {
+                "Migrated"
+            };
+            println!("{} {}: {}", "✔".success(), verb, report.migrated);
+            println!("{} Already v2: {}", "•".accent(), report.skipped);
+            for (path, err) in &report.errors {
+                println!("{} {}: {}", "✘".error(), path.display(), err);
+            }
+        }
+        Err(err) => println!("{} {}", "✘".error(), err),
+    }
+}
+
+/// Compares this version against the semver `requirement` and returns the exit
+/// code: 0 when it is satisfied, 1 when it is not, 2 when the requirement is invalid.
+fn check_version(requirement: &str) -> i32 {
+    let requirement = match semver::VersionReq::parse(requirement) {
+        Ok(requirement) => requirement,
+        Err(err) => {
+            println!("{} Invalid version requirement: {}", "✘".error(), err);
+            return 2;
+        }
+    };
+    let version = semver::Version::parse(VERSION).expect("package version is valid semver");
+    if requirement.matches(&version) {
+        println!("{} {} satisfies {}", "✔".success(), version, requirement);
+        0
+    } else {
+        println!(
+            "{} {} does not satisfy {}",
+            "✘".error(),
+            version,
+            requirement
+        );
+        1
+    }
+}
+
+/// Prints the configured language aliases with the canonical names they map to.
+fn list_aliases(config: &Config) {
+    if config.language_aliases.is_empty() {
+        println!(
+            "{} No language aliases configured; add a [language_aliases] section to {}.",
+            "•".accent(),
+            Config::path().display()
+        );
+        return;
+    }
+
+    let mut aliases: Vec<(&String, &String)> = config.language_aliases.iter().collect();
+    aliases.sort();
+    let column = aliases
+        .iter()
+        .map(|(alias, _)| width::display_width(alias))
+        .max()
+        .unwrap_or(0);
+    for (alias, canonical) in aliases {
+        println!(
+            "{}  {} {}",
+            width::pad(alias, column),
+            "→".accent(),
+            canonical.success()
+        );
+    }
+}
+
+/// Prints every tag in the vault with the number of snippets carrying it,
+/// most used first.
+/// - `use_cache`: Serve unchanged snippets from the metadata cache.
+fn list_tags(config: &Config, use_cache: bool) {
+    let snippets =
+        match vault::load_snippets(&vault::snippet_dir(config), &config.extensions(), use_cache) {
+            Ok(snippets) => snippets,
+            Err(err) => {
+                println!("{} {}", "✘".error(), err);
+                return;
+            }
+        };
+
+    let mut counts: HashMap<&str, usize> = HashMap::new();
+    for tag in snippets.iter().flat_map(|snippet| &snippet.tags) {
+        *counts.entry(tag.as_str()).or_default() += 1;
+    }
+
+    let mut counts: Vec<(&str, usize)> = counts.into_iter().collect();
+    counts.sort_by(|a, b| b.1.cmp(&a.1).then(a.0.cmp(b.0)));
+    let mut out = Pager::new(config);
+    for (tag, count) in counts {
+        out.line(format_args!("{:>5}  {}", count, tag.accent()));
+    }
+}
+
+/// Goes through the sync conflict copies in the vault (see
+/// [`conflicts::original_of`]), showing each next to its original and asking
+/// which to keep. The file not kept is moved to the vault's trash. Merging
+/// writes both versions into the original between conflict markers and opens
+/// it in the editor; the two versions go to the trash. Returns whether any
+/// file changed.
+/// - `executor`: Under `--dry-run`, the chosen steps are only printed.
+fn resolve_conflicts(config: &Config, executor: &Executor) -> Result<bool, SnippetVaultError> {
+    let snippet_dir = vault::snippet_dir(config);
+    let pairs = conflicts::find_conflicts(&snippet_dir, &config.extensions())?;
+    if pairs.is_empty() {
+        println!("{} No sync conflicts in the vault.", "✔".success());
+        return Ok(false);
+    }
+    prompt::require_interactive(
+        "resolve sync conflicts",
+        "run it in a terminal; --doctor lists the conflict copies",
+    )?;
+
+    let relative = |path: &Path| {
+        path.strip_prefix(&snippet_dir)
+            .unwrap_or(path)
+            .display()
+            .to_string()
+    };
+    let done = |message: String| {
+        if !executor.is_dry_run() {
+            println!("{} {}", "✔".success(), message);
+        }
+    };
+    let width = config.output_width();
+    let mut changed = false;
+    for pair in &pairs {
+        let (original, copy) = (relative(&pair.original), relative(&pair.copy));
+        println!();
+        println!("{} {}", "CONFLICT".bold(), copy);
+        let copy_text = std::fs::read_to_string(&pair.copy)?;
+
+        if !pair.original.exists() {
+            let options = vec![
+                format!("Keep the copy as {}", original),
+                "Move the copy to the trash".to_string(),
+                "Skip".to_string(),
+            ];
+            let choice = prompt::choose(&format!("{} no longer exists.", original), &options)?;
+            // Taken per change rather than for the whole walk, which waits on
+            // the user between pairs.
+            let _lock = (choice < 2).then(|| executor.lock()).transpose()?;
+            match choice {
+                0 => {
+                    executor.apply(Mutation::Rename {
+                        from: pair.copy.clone(),
+                        to: pair.original.clone(),
+                    })?;
+                    done(format!("Restored {}", original));
+                }
+                1 => {
+                    let trashed = vault::move_to_trash(&snippet_dir, &pair.copy, executor)?;
+                    done(format!("Moved the copy to {}", relative(&trashed)));
+                }
+                _ => continue,
+            }
+            changed = true;
+            continue;
+        }
+
+        let local_text = std::fs::read_to_string(&pair.original)?;
+        let column = width.saturating_sub(3) / 2;
+        println!(
+            "{}",
+            format!("{}   {}", width::pad(&original, column), copy).dimmed()
+        );
+        for row in conflicts::side_by_side(&local_text, &copy_text, width) {
+            println!("{}", row);
+        }
+
+        let options = vec![
+            format!("Keep local ({})", original),
+            format!("Keep remote ({})", copy),
+            "Merge both in the editor".to_string(),
+            "Skip".to_string(),
+        ];
+        let choice = prompt::choose("Resolve how?", &options)?;
+        let lock = (choice < 3).then(|| executor.lock()).transpose()?;
+        match choice {
+            0 => {
+                let trashed = vault::move_to_trash(&snippet_dir, &pair.copy, executor)?;
+                done(format!(
+                    "Kept {}; the copy is in {}",
+                    original,
+                    relative(&trashed)
+                ));
+            }
+            1 => {
+                let trashed = vault::move_to_trash(&snippet_dir, &pair.original, executor)?;
+                executor.apply(Mutation::Rename {
+                    from: pair.copy.clone(),
+                    to: pair.original.clone(),
+                })?;
+                done(format!(
+                    "Kept {}; the old version is in {}",
+                    copy,
+                    relative(&trashed)
+                ));
+            }
+            2 => {
+                let merged =
+                    conflicts::with_conflict_markers(&local_text, &copy_text, &original, &copy);
+                let first_marker = merged.lines().position(|line| line.starts_with("<<<<<<< "));
+                vault::move_to_trash(&snippet_dir, &pair.original, executor)?;
+                vault::move_to_trash(&snippet_dir, &pair.copy, executor)?;
+                executor.apply(Mutation::Write {
+                    path: pair.original.clone(),
+                    contents: merged,
+                })?;
+                // Other commands may use the vault while the editor is open.
+                drop(lock);
+                if !executor.is_dry_run() {
+                    editor::open_in_editor(
+                        config,
+                        &pair.original,
+                        first_marker.map(|line| line + 1),
+                        executor,
+                    )?;
+                    if conflicts::has_conflict_markers(&std::fs::read_to_string(&pair.original)?) {
+                        println!(
+                            "{} {} still has conflict markers; both versions are in the trash",
+                            "⚠".warning(),
+                            original
+                        );
+                    } else {
+                        done(format!("Merged into {}", original));
+                    }
+                }
+            }
+            _ => continue,
+        }
+        changed = true;
+    }
+    Ok(changed && !executor.is_dry_run())
+}
+
+/// Prints a bar chart of the snippets created on each day of the week; see
+/// [`dates::stats_by_day_of_week`].
+/// - `normalize`: Label the bars with percentages of the total instead of counts.
+fn weekday_stats(config: &Config, normalize: bool) {
+    let counts =
+        match dates::stats_by_day_of_week(&vault::snippet_dir(config), &config.extensions()) {
+            Ok(counts) => counts,
+            Err(err) => {
+                println!("{} {}", "✘".error(), err);
+                return;
+            }
+        };
+    let total: usize = counts.iter().sum();
+    if total == 0 {
+        println!(
+            "{} No snippet has a creation timestamp in its filename.",
+            "•".accent()
+        );
+        return;
+    }
+
+    let mut out = Pager::new(config);
+    for (day, bar) in dates::weekday_chart(&counts, normalize, config.output_width()) {
+        out.line(format_args!("{}  {}", day.accent(), bar));
+    }
+    out.line(format_args!(
+        "{}",
+        format!("{} snippets with a creation timestamp", total).dimmed()
+    ));
+}
+
+/// Prints the number of snippets, languages, tags, and lines in the vault,
+/// followed by the ten most used snippets; see [`usage::use_counts`].
+/// - `use_cache`: Serve unchanged snippets from the metadata cache.
+fn vault_stats(config: &Config, use_cache: bool) {
+    let snippet_dir = vault::snippet_dir(config);
+    let snippets = match vault::load_snippets(&snippet_dir, &config.extensions(), use_cache) {
+        Ok(snippets) => snippets,
+        Err(err) => {
+            println!("{} {}", "✘".error(), err);
+            return;
+        }
+    };
+
+    let languages: HashSet<String> = snippets
+        .iter()
+        .filter(|snippet| !snippet.language.is_empty())
+        .map(|snippet| language::canonical_language(&snippet.language, &config.language_aliases))
+        .collect();
+    let tags: HashSet<&str> = snippets
+        .iter()
+        .flat_map(|snippet| &snippet.tags)
+        .map(String::as_str)
+        .collect();
+    let lines: usize = snippets.iter().map(|snippet| snippet.stats.lines).sum();
+
+    let mut out = Pager::new(config);
+    for (label, value) in [
+        ("Snippets", snippets.len()),
+        ("Languages", languages.len()),
+        ("Tags", tags.len()),
+        ("Lines", lines),
+    ] {
+        out.line(format_args!("{} {}", width::pad(label, 12).accent(), value));
+    }
+
+    let uses = usage::use_counts(&snippets);
+    let mut most_used: Vec<(&snippet::SnippetMeta, usize)> = snippets
+        .iter()
+        .filter_map(|snippet| Some((snippet, *uses.get(&snippet.path)?)))
+        .collect();
+    most_used.sort_by(|(a, a_uses), (b, b_uses)| b_uses.cmp(a_uses).then(a.path.cmp(&b.path)));
+    out.line(format_args!(""));
+    out.line(format_args!("{}", "MOST USED".bold()));
+    if most_used.is_empty() {
+        out.line(format_args!(
+            "{} No snippet was copied, run, or printed with --get yet.",
+            "•".accent()
+        ));
+    }
+    for (snippet, count) in most_used.into_iter().take(10) {
+        let relative = snippet
+            .path
+            .strip_prefix(&snippet_dir)
+            .unwrap_or(&snippet.path);
+        let title = if snippet.title.is_empty() {
+            relative.display().to_string()
+        } else {
+            snippet.title.clone()
+        };
+        out.line(format_args!(
+            "{:>5}  {}  {}",
+            count,
+            title,
+            relative.display().to_string().dimmed()
+        ));
+    }
+}
+
+/// Prints the dot-separated tag hierarchy as a tree. A tag's count includes
+/// the snippets tagged below it.
+/// - `use_cache`: Serve unchanged snippets from the metadata cache.
+fn tag_tree(config: &Config, use_cache: bool) {
+    let snippets =
+        match vault::load_snippets(&vault::snippet_dir(config), &config.extensions(), use_cache) {
+            Ok(snippets) => snippets,
+            Err(err) => {
+                println!("{} {}", "✘".error(), err);
+                return;
+            }
+        };
+
+    let all_tags: Vec<String> = snippets
+        .iter()
+        .flat_map(|snippet| snippet.tags.iter().cloned())
+        .collect();
+    let hierarchy = tags::parse_tag_hierarchy(&all_tags);
+    let counts: HashMap<String, usize> = hierarchy
+        .values()
+        .flatten()
+        .map(|tag| {
+            let count = snippets
+                .iter()
+                .filter(|snippet| tags::filter_by_tag_prefix(tag, &snippet.tags))
+                .count();
+            (tag.clone(), count)
+        })
+        .collect();
+
+    let mut out = Pager::new(config);
+    for line in tags::render_tag_tree(&hierarchy, &counts) {
+        out.line(line);
+    }
+}
+
+/// The `[s3_backup]` settings with credentials resolved, pointed at `remote`
+/// (an `s3://bucket/prefix` URL) when given.
+fn s3_settings(config: &Config, remote: Option<&str>) -> Result<s3::S3Config, SnippetVaultError> {
+    let s3_config = match (config.s3_backup.clone(), remote) {
+        (s3_config, Some(remote)) => s3_config.unwrap_or_default().with_remote(remote)?,
+        (Some(s3_config), None) => s3_config,
+        (None, None) => {
+            return Err(SnippetVaultError::Config(format!(
+            "add an [s3_backup] section with a bucket to {}, or pass --remote s3://bucket/prefix",
+            Config::path().display()
+        )))
+        }
+    };
+    s3_config.resolve()
+}
+
+/// Zips the vault into the backup directory and uploads the archive to the
+/// bucket configured under `[s3_backup]`, printing its URL. The local archive
+/// is kept whether or not the upload succeeds.
+/// - `remote`: `s3://bucket/prefix` to upload to instead of the configured one.
+/// - `prune`: After a successful upload, delete all but this many of the
+///   newest backups in the bucket.
+/// - `executor`: Under `--dry-run`, nothing is zipped, uploaded, or deleted.
+fn backup_to_s3(config: &Config, remote: Option<&str>, prune: Option<usize>, executor: &Executor) {
+    let s3_config = match s3_settings(config, remote) {
+        Ok(s3_config) => s3_config,
+        Err(err) => {
+            println!("{} {}", "✘".error(), err);
+            return;
+        }
+    };
+    let snippet_dir = vault::snippet_dir(config);
+    if executor.is_dry_run() {
+        println!(
+            "{} would zip {} and upload it to {}",
+            "•".accent(),
+            snippet_dir.display(),
+            s3_config.url_for(&s3_config.key_for(Path::new("snippets_vault_<timestamp>.zip")))
+        );
+        if let Some(keep) = prune {
+            println!(
+                "{} would delete all but the newest {} backups in the bucket",
+                "•".accent(),
+                keep
+            );
+        }
+        return;
+    }
+
+    let uploaded = vault::backup_vault(&snippet_dir).and_then(|archive| {
+        println!("{} Backup created: {}", "✔".success(), archive.display());
+        s3::backup_to_s3(&s3_config, &archive)
+    });
+    match uploaded {
+        Ok(url) => println!("{} Uploaded to {}", "✔".success(), url),
+        Err(err) => {
+            println!("{} {}", "✘".error(), err);
+            return;
+        }
+    }
+
+    if let Some(keep) = prune {
+        let pruned = s3::list_backups(&s3_config).and_then(|backups| {
+            let old = &backups[..backups.len().saturating_sub(keep)];
+            s3::delete_backups(&s3_config, old).map(|()| old.len())
+        });
+        match pruned {
+            Ok(0) => println!("{} No remote backups to prune.", "•".accent()),
+            Ok(count) => println!("{} Deleted {} old remote backups.", "✔".success(), count),
+            Err(err) => println!("{} Pruning failed: {}", "✘".error(), err),
+        }
+    }
+}
+
+/// Prints the backups in the bucket, newest first, with their upload time and size.
+/// - `remote`: `s3://bucket/prefix` to list instead of the configured one.
+fn list_s3_backups(config: &Config, remote: Option<&str>) -> Result<(), SnippetVaultError> {
+    let s3_config = s3_settings(config, remote)?;
+    let backups = s3::list_backups(&s3_config)?;
+    if backups.is_empty() {
+        println!(
+            "{} No backups in {}",
+            "•".accent(),
+            s3_config.url_for(&s3_config.key_for(Path::new("")))
+        );
+        return Ok(());
+    }
+    for backup in backups.iter().rev() {
+        let uploaded = backup
+            .uploaded
+            .map(|time| time.format("%Y-%m-%d %H:%M").to_string())
+            .unwrap_or_default();
+        println!(
+            "{}  {:<16}  {:>10} bytes",
+            backup.name.accent(),
+            uploaded,
+            backup.size
+        );
+    }
+    Ok(())
+}
+
+/// Downloads a backup from the bucket into the local backups directory (or
+/// reuses the copy already there) and extracts it into the vault. The current
+/// vault is zipped first, so the restore can be undone.
+/// - `remote`: `s3://bucket/prefix` to restore from instead of the configured one.
+/// - `backup`: File name of the archive, or `latest`; without it the user
+///   chooses from the backups, newest first.
+/// - `executor`: Under `--dry-run`, nothing is downloaded or written.
+fn restore_from_s3(
+    config: &Config,
+    remote: Option<&str>,
+    backup: Option<&str>,
+    executor: &Executor,
+) -> Result<(), SnippetVaultError> {
+    let s3_config = s3_settings(config, remote)?;
+    let mut backups = s3::list_backups(&s3_config)?;
+    backups.reverse();
+    let chosen = match backup {
+        _ if backups.is_empty() => {
+            return Err(SnippetVaultError::Backup(format!(
+                "no backups in {}",
+                s3_config.url_for(&s3_config.key_for(Path::new("")))
+            )))
+        }
+        Some("latest") => backups[0].clone(),
+        Some(name) => backups
+            .iter()
+            .find(|backup| backup.name == name || backup.name.trim_end_matches(".zip") == name)
+            .cloned()
+            .ok_or_else(|| {
+                SnippetVaultError::Backup(format!("no backup named {} (see --list)", name))
+            })?,
+        None => {
+            prompt::require_interactive(
+                "choose a backup",
+                "name the backup, or use latest; --list shows them",
+            )?;
+            let options: Vec<String> = backups
+                .iter()
+                .map(|backup| format!("{}  ({} bytes)", backup.name, backup.size))
+                .collect();
+            backups[prompt::choose("Restore which backup?", &options)?].clone()
+        }
+    };
+
+    let snippet_dir = vault::snippet_dir(config);
+    if executor.is_dry_run() {
+        println!(
+            "{} would download {} and restore it into {}",
+            "•".accent(),
+            chosen.name,
+            snippet_dir.display()
+        );
+        return Ok(());
+    }
+
+    let _lock = executor.lock()?;
+    let local = vault::backup_dir().join(&chosen.name);
+    let archive = if std::fs::metadata(&local).is_ok_and(|metadata| metadata.len() == chosen.size) {
+        println!("{} Using the local copy {}", "•".accent(), local.display());
+        local
+    } else {
+        let archive = s3::download_backup(&s3_config, &chosen, &vault::backup_dir())?;
+        println!("{} Downloaded {}", "✔".success(), archive.display());
+        archive
+    };
+
+    if !vault::vault_files(&snippet_dir, &config.extensions())?.is_empty() {
+        let current = vault::backup_vault(&snippet_dir)?;
+        println!(
+            "{} Current vault saved to {}",
+            "✔".success(),
+            current.display()
+        );
+    }
+    let restored = vault::restore_backup(&archive, &snippet_dir)?;
+    println!(
+        "{} Restored {} files from {} into {}",
+        "✔".success(),
+        restored,
+        chosen.name,
+        snippet_dir.display()
+    );
+
+    let rebuilt = index::open(&index::index_path())
+        .and_then(|db| index::build_title_index(&snippet_dir, &db, &config.extensions(), true));
+    match rebuilt {
+        Ok(count) => println!("{} Indexed {} snippets.", "✔".success(), count),
+        Err(err) => println!("{} {}", "✘".error(), err),
+    }
+    Ok(())
+}
+
+/// Syncs the vault with the server under `[webdav]` and prints what changed.
+/// Conflicting files keep their local contents; the remote copy is saved next
+/// to them and listed.
+/// - `direction`: Whether to copy both ways or only push or pull.
+/// - `executor`: Under `--dry-run`, nothing is written on either side.
+fn sync_webdav(
+    config: &Config,
+    direction: webdav::Direction,
+    executor: &Executor,
+) -> Result<(), SnippetVaultError> {
+    let settings = config
+        .webdav
+        .clone()
+        .ok_or_else(|| {
+            SnippetVaultError::Config(format!(
+                "add a [webdav] section with a url to {}",
+                Config::path().display()
+            ))
+        })?
+        .resolve()?;
+    let snippet_dir = vault::snippet_dir(config);
+    let _lock = executor.lock()?;
+    let report = webdav::sync(
+        &settings,
+        &snippet_dir,
+        &config.extensions(),
+        direction,
+        executor,
+    )?;
+
+    let verb = if executor.is_dry_run() {
+        "Would sync"
+    } else {
+        "Synced"
+    };
+    println!(
+        "{} {} with {}: {} uploaded, {} downloaded, {} deleted on the server, {} deleted locally",
+        "✔".success(),
+        verb,
+        settings.root(),
+        report.uploaded,
+        report.downloaded,
+        report.deleted_remote,
+        report.deleted_local
+    );
+    for copy in &report.conflicts {
+        println!(
+            "{} Conflict: both sides changed; the server copy is in {}",
+            "⚠".warning(),
+            copy.display()
+        );
+    }
+    for path in &report.skipped {
+        println!(
+            "{} Skipped {}: it changed on the server too (sync without --push-only)",
+            "⚠".warning(),
+            path
+        );
+    }
+
+    if !executor.is_dry_run() && report.downloaded + report.deleted_local > 0 {
+        let rebuilt = index::open(&index::index_path())
+            .and_then(|db| index::build_title_index(&snippet_dir, &db, &config.extensions(), true));
+        if let Err(err) = rebuilt {
+            println!("{} {}", "✘".error(), err);
+        }
+    }
+    Ok(())
+}
+
+/// Syncs `dest` with `repo_url`, then rebuilds the vault's title index when
+/// `dest` is the vault or lies inside it.
+/// - `token`: GitHub token for private repositories.
+/// - `executor`: Under `--dry-run`, only describes the sync.
+fn github_sync(
+    config: &Config,
+    repo_url: &str,
+    dest: &Path,
+    token: Option<&str>,
+    executor: &Executor,
+) {
+    if executor.is_dry_run() {
+        let action = if dest.exists() {
+            "pull into"
+        } else {
+            "clone into"
+        };
+        println!(
+            "{} would {} {} from {}",
+            "•".accent(),
+            action,
+            dest.display(),
+            repo_url
+        );
+        return;
+    }
+
+    let _lock = match executor.lock() {
+        Ok(lock) => lock,
+        Err(err) => {
+            println!("{} {}", "✘".error(), err);
+            return;
+        }
+    };
+    if let Err(err) = git::github_sync(repo_url, dest, token) {
+        println!("{} {}", "✘".error(), err);
+        return;
+    }
+    println!(
+        "{} Synced {} with {}",
+        "✔".success(),
+        dest.display(),
+        repo_url
+    );
+
+    // The index only covers the vault; a clone elsewhere leaves it as it is.
+    let snippet_dir = vault::snippet_dir(config);
+    let canonical = |path: &Path| std::fs::canonicalize(path).unwrap_or(path.to_path_buf());
+    if !canonical(dest).starts_with(canonical(&snippet_dir)) {
+        return;
+    }
+    let rebuilt = index::open(&index::index_path())
+        .and_then(|db| index::build_title_index(&snippet_dir, &db, &config.extensions(), true));
+    match rebuilt {
+        Ok(count) => println!("{} Indexed {} snippets.", "✔".success(), count),
+        Err(err) => println!("{} {}", "✘".error(), err),
+    }
+}
+
+/// Commits and pushes the vault after an edit when `push_after_edit` is set
+/// and the vault is a git repository.
+fn push_after_edit(config: &Config, executor: &Executor) {
+    let snippet_dir = vault::snippet_dir(config);
+    if !config.push_after_edit || vault::is_read_only() || !git::is_repository(&snippet_dir) {
+        return;
+    }
+    if executor.is_dry_run() {
+        println!(
+            "{} would commit and push {}",
+            "•".accent(),
+            snippet_dir.display()
+        );
+        return;
+    }
+
+    let _lock = match executor.lock() {
+        Ok(lock) => lock,
+        Err(err) => {
+            println!("{} Not pushing: {}", "⚠".warning(), err);
+            return;
+        }
+    };
+    match git::push_changes(&snippet_dir) {
+        Ok(()) => println!("{} Pushed vault changes.", "✔".success()),
+        Err(err) => println!("{} {}", "⚠".warning(), err),
+    }
+}
+
+/// Rebuilds the snippet index, then keeps it in step with the vault on a
+/// background thread until Ctrl-C, which stops the thread after it has
+/// applied the changes already seen.
+/// - `use_cache`: Serve unchanged snippets from the metadata cache for the rebuild.
+fn watch_index(config: &Config, use_cache: bool) -> Result<(), SnippetVaultError> {
+    let snippet_dir = vault::snippet_dir(config);
+    let db_path = index::index_path();
+    let count = index::build_title_index(
+        &snippet_dir,
+        &index::open(&db_path)?,
+        &config.extensions(),
+        use_cache,
+    )?;
+    println!("{} Indexed {} snippets.", "✔".success(), count);
+
+    let (stop, stop_rx) = std::sync::mpsc::channel();
+    let indexer =
+        indexer::start_background_indexer(&snippet_dir, &db_path, &config.extensions(), stop_rx)?;
+    ctrlc::set_handler(move || {
+        let _ = stop.send(());
+    })
+    .map_err(|err| SnippetVaultError::Server(err.to_string()))?;
+    println!(
+        "{} Watching {} for changes. Press Ctrl-C to stop.",
+        "•".accent(),
+        snippet_dir.display()
+    );
+    let _ = indexer.join();
+    println!("\n{} Stopped watching.", "✔".success());
+    Ok(())
+}
+
+/// Emits the snippet link graph as DOT, and optionally renders it with Graphviz.
+/// - `output`: File to write the DOT to; stdout when `None`.
+/// - `render`: Image format (`png` or `svg`) to render and open.
+/// - `executor`: Applies (or, with `--dry-run`, only prints) the file writes.
+fn dependency_graph(
+    config: &Config,
+    output: Option<&Path>,
+    render: Option<&str>,
+    executor: &Executor,
+) -> Result<(), SnippetVaultError> {
+    let dot = graph::generate_dependency_graph(&vault::snippet_dir(config))?;
+
+    match output {
+        Some(path) => {
+            executor.apply(vault::Mutation::Write {
+                path: path.to_path_buf(),
+                contents: dot.clone(),
+            })?;
+            if !executor.is_dry_run() {
+                println!("{} Graph written to {}", "✔".success(), path.display());
+            }
+        }
+        None if render.is_none() => print!("{}", dot),
+        None => {}
+    }
+
+    let Some(format) = render else {
+        return Ok(());
+    };
+    if platform::find_in_path("dot").is_none() {
+        return Err(SnippetVaultError::Command(
+            "Graphviz `dot` is not installed".to_string(),
+        ));
+    }
+    let image = output.map(|path| path.with_extension(format));
+    if executor.is_dry_run() {
+        match image {
+            Some(image) => println!("{} would render {}", "•".accent(), image.display()),
+            None => println!("{} would render a temporary {} file", "•".accent(), format),
+        }
+        return Ok(());
+    }
+    let image = match image {
+        Some(image) => image,
+        // Created up front under a random name, so no other user can plant a
+        // file or symlink where `dot` writes. Kept for the viewer to open.
+        None => tempfile::Builder::new()
+            .prefix("snippets_graph_")
+            .suffix(&format!(".{}", format))
+            .tempfile()?
+            .into_temp_path()
+            .keep()
+            .map_err(std::io::Error::from)?,
+    };
+
+    let mut command = ProcessCommand::new("dot");
+    command.arg(format!("-T{}", format)).arg("-o").arg(&image);
+    let rendered = logging::run_with_input(&mut command, &dot)?;
+    if !rendered.status.success() {
+        return Err(SnippetVaultError::Command(format!(
+            "dot exited with {}",
+            rendered.status
+        )));
+    }
+    println!("{} Graph rendered to {}", "✔".success(), image.display());
+    platform::open_with_system(&image.display().to_string())
+}
+
+/// Makes sure the vault directory exists, offering to create it when it does
+/// not. Returns false when it is still missing.
+/// - `executor`: Creates the directory (or, with `--dry-run`, only describes it).
+fn ensure_vault_dir(config: &Config, executor: &Executor) -> bool {
+    let snippet_dir = vault::snippet_dir(config);
+    if snippet_dir.exists() {
+        return true;
+    }
+    if !prompt::is_interactive() {
+        println!(
+            "{} The vault {} does not exist yet.",
+            "✘".error(),
+            snippet_dir.display()
+        );
+        return false;
+    }
+
+    let question = format!(
+        "The vault {} does not exist. Create it?",
+        snippet_dir.display()
+    );
+    match prompt::confirm(&question, true) {
+        Ok(true) => match executor.apply(Mutation::CreateDir(snippet_dir.clone())) {
+            Ok(()) if executor.is_dry_run() => false,
+            Ok(()) => {
+                println!("{} Created {}", "✔".success(), snippet_dir.display());
+                true
+            }
+            Err(err) => {
+                println!("{} {}", "✘".error(), err);
+                false
+            }
+        },
+        Ok(false) => false,
+        Err(err) => {
+            println!("{} {}", "✘".error(), err);
+            false
+        }
+    }
+}
+
+/// Opens the vault directory in the system file manager, or with `snippet`,
+/// shows a snippet picked with fzf there.
+/// - `use_cache`: Serve unchanged snippets from the metadata cache for the picker.
+/// - `executor`: Creates a missing vault directory (or describes it under `--dry-run`).
+fn open_dir(config: &Config, snippet: bool, use_cache: bool, executor: &Executor) {
+    if !ensure_vault_dir(config, executor) {
+        return;
+    }
+
+    let opened = if snippet {
+        match choose_snippet(config, &config.extensions(), use_cache) {
+            Some(path) => platform::reveal_file(&path),
+            None => return,
+        }
+    } else {
+        platform::open_directory(&vault::snippet_dir(config))
+    };
+    if let Err(err) = opened {
+        println!("{} {}", "✘".error(), err);
+    }
+}
+
+/// Creates a gist from the snippet at `path`, prints its URL, and adds the URL
+/// to the snippet's `### Link:` section.
+/// - `public`: Create a public gist instead of a secret one.
+/// - `open`: Open the gist in the browser afterwards.
+/// - `executor`: Under `--dry-run`, nothing is uploaded or written.
+fn share_gist(path: &Path, token: &str, public: bool, open: bool, executor: &Executor) {
+    if executor.is_dry_run() {
+        let visibility = if public { "public" } else { "secret" };
+        println!(
+            "{} would create a {} gist from {}",
+            "•".accent(),
+            visibility,
+            path.display()
+        );
+        return;
+    }
+
+    let url = match gist::create_gist(path, token, public) {
+        Ok(url) => url,
+        Err(err) => {
+            println!("{} {}", "✘".error(), err);
+            return;
+        }
+    };
+    println!("{} Gist created: {}", "✔".success(), url);
+
+    // Plain code files have no Link section to record the URL in.
+    let linked = if !snippet::is_markdown(path) {
+        Ok(())
+    } else {
+        std::fs::read_to_string(path)
+            .map_err(SnippetVaultError::from)
+            .and_then(|content| match snippet::add_link(&content, &url) {
+                Some(contents) => executor.apply(Mutation::Write {
+                    path: path.to_path_buf(),
+                    contents,
+                }),
+                None => Ok(()),
+            })
+    };
+    match linked {
+        Ok(()) => index::reindex_snippet(path, path),
+        Err(err) => println!(
+            "{} Could not add the link to the snippet: {}",
+            "⚠".warning(),
+            err
+        ),
+    }
+
+    if open {
+        if let Err(err) = platform::open_with_system(&url) {
+            println!("{} {}", "✘".error(), err);
+        }
+    }
+}
+
+/// Opens the official documentation of the language of the snippet at `path`
+/// in the browser: its `[language_docs]` entry, else the built-in URL (see
+/// [`language::language_docs_url`]). A language with neither is reported with
+/// a hint to configure it.
+fn open_language_docs(config: &Config, path: &Path) -> Result<(), SnippetVaultError> {
+    let language = language::resolve_language(
+        &snippet::SnippetMeta::read(path)?.language,
+        &config.language_aliases,
+    );
+    if language.is_empty() {
+        println!("{} {} has no language.", "•".accent(), path.display());
+        return Ok(());
+    }
+
+    let url = config
+        .language_docs
+        .iter()
+        .find(|(name, _)| name.eq_ignore_ascii_case(&language))
+        .map(|(_, url)| url.as_str())
+        .or_else(|| language::language_docs_url(&language));
+    let Some(url) = url else {
+        println!(
+            "{} No documentation URL for the language '{}'. Add one under [language_docs] in {}:",
+            "•".accent(),
+            language,
+            Config::path().display()
+        );
+        println!("    \"{}\" = \"https://...\"", language);
+        return Ok(());
+    };
+
+    println!("{} Opening {}", "•".accent(), url);
+    platform::open_with_system(url)
+}
+
+/// Opens the URL in the `### Link:` section of `path` with the system default
+/// application. When the section holds several URLs, the user chooses one.
+fn open_link(path: &Path) -> Result<(), SnippetVaultError> {
+    let content = std::fs::read_to_string(path)?;
+    let urls = snippet::link_urls(&content);
+    let url = match urls.as_slice() {
+        [] => {
+            return Err(SnippetVaultError::Parse {
+                path: path.to_path_buf(),
+                message: "no http(s) URL in the ### Link: section".to_string(),
+            })
+        }
+        [url] => url,
+        urls => &urls[prompt::choose("Which link?", urls)?],
+    };
+
+    println!("{} Opening {}", "•".accent(), url);
+    platform::open_with_system(url)
+}
+
+/// Lets the user pick a snippet with fzf, showing each one's vault-relative
+/// path and title. Returns `None` (after printing any error) when nothing was picked.
+/// - `extensions`: File extensions offered in the picker.
+/// - `use_cache`: Serve unchanged snippets from the metadata cache.
+fn choose_snippet(config: &Config, extensions: &[String], use_cache: bool) -> Option<PathBuf> {
+    choose_snippets(config, extensions, false, use_cache)
+        .into_iter()
+        .next()
+}
+
+/// Like [`choose_snippet`], but with `multi` the user can mark several snippets.
+/// Returns an empty list (after printing any error) when nothing was picked.
+fn choose_snippets(
+    config: &Config,
+    extensions: &[String],
+    multi: bool,
+    use_cache: bool,
+) -> Vec<PathBuf> {
+    let snippet_dir = vault::snippet_dir(config);
+    vault::load_snippets(&snippet_dir, extensions, use_cache)
+        .and_then(|snippets| {
+            let entries = picker_entries(&snippet_dir, snippets);
+            if multi {
+                picker::pick_many(config, &entries, None)
+            } else {
+                picker::pick(config, &entries, None).map(Vec::from_iter)
+            }
+        })
+        .unwrap_or_else(|err| {
+            println!("{} {}", "✘".error(), err);
+            Vec::new()
+        })
+}
+
+/// Picker entries labelled with each snippet's vault-relative path and title.
+fn picker_entries(
+    snippet_dir: &Path,
+    snippets: Vec<snippet::SnippetMeta>,
+) -> Vec<(String, PathBuf)> {
+    snippets
+        .into_iter()
+        .map(|snippet| {
+            let relative = snippet
+                .path
+                .strip_prefix(snippet_dir)
+                .unwrap_or(&snippet.path)
+                .display()
+                .to_string();
+            (format!("{}  {}", relative, snippet.title), snippet.path)
+        })
+        .collect()
+}
+
+/// Copies the code of `file` (or of the snippets picked with fzf) to the
+/// clipboard: every code block of a markdown snippet, or a plain code file
+/// whole, with placeholders filled in. Several snippets are separated by
+/// `==> path <==` lines.
+/// - `multi`: Pick several snippets instead of one.
+/// - `params`: Placeholder values given with `--param`.
+/// - `use_cache`: Serve unchanged snippets from the metadata cache.
+/// - `executor`: Under `--dry-run`, the copy is not counted as a use.
+fn copy_snippets(
+    config: &Config,
+    file: Option<PathBuf>,
+    multi: bool,
+    params: &HashMap<String, String>,
+    use_cache: bool,
+    executor: &Executor,
+) -> Result<(), SnippetVaultError> {
+    let paths = match file {
+        Some(file) => vec![file],
+        None => choose_snippets(config, &config.extensions(), multi, use_cache),
+    };
+    if paths.is_empty() {
+        return Ok(());
+    }
+
+    let snippet_dir = vault::snippet_dir(config);
+    let mut sections = Vec::with_capacity(paths.len());
+    for path in &paths {
+        let code = filled_code(path, params)?;
+        if paths.len() == 1 {
+            sections.push(code);
+        } else {
+            let relative = path.strip_prefix(&snippet_dir).unwrap_or(path);
+            sections.push(format!("==> {} <==\n{}", relative.display(), code));
+        }
+    }
+
+    clipboard::write_clipboard(&sections.join("\n"))?;
+    for path in &paths {
+        index::log_access(path, index::Access::Opened);
+        track_use(config, path, executor);
+    }
+    println!(
+        "{} Copied the code of {} snippet(s) to the clipboard.",
+        "✔".success(),
+        paths.len()
+    );
+    Ok(())
+}
+
+/// Counts a use of the snippet at `path` in the configured usage store,
+/// unless `--no-track` or `--dry-run` is given.
+fn track_use(config: &Config, path: &Path, executor: &Executor) {
+    if !config.no_track {
+        usage::record_use(path, config.usage_store(), executor);
+    }
+}
+
+/// The code of the snippet at `path` (its code blocks joined, or a plain code
+/// file whole) with its placeholders filled in; see [`placeholder::fill_in`].
+fn filled_code(path: &Path, params: &HashMap<String, String>) -> Result<String, SnippetVaultError> {
+    let meta = snippet::SnippetMeta::read(path)?;
+    let content = std::fs::read_to_string(path)?;
+    let code = if snippet::is_markdown(path) {
+        snippet::code_blocks(&content)
+            .into_iter()
+            .map(|block| block.code)
+            .collect::<Vec<_>>()
+            .join("\n")
+    } else {
+        content
+    };
+    placeholder::fill_in(&code, &meta.language, params)
+}
+
+/// Prints the code of the snippet whose `alias` frontmatter field is `alias`
+/// (compared case-insensitively), with placeholders filled in. Returns whether
+/// such a snippet exists.
+/// - `params`: Placeholder values given with `--param`.
+/// - `use_cache`: Serve unchanged snippets from the metadata cache.
+/// - `executor`: Under `--dry-run`, the lookup is not counted as a use.
+fn get_snippet(
+    config: &Config,
+    alias: &str,
+    params: &HashMap<String, String>,
+    use_cache: bool,
+    executor: &Executor,
+) -> Result<bool, SnippetVaultError> {
+    let snippet_dir = vault::snippet_dir(config);
+    let Some(snippet) = vault::load_snippets(&snippet_dir, &config.extensions(), use_cache)?
+        .into_iter()
+        .find(|snippet| {
+            snippet
+                .extra
+                .get(info::ALIAS_FIELD)
+                .is_some_and(|value| value.eq_ignore_ascii_case(alias))
+        })
+    else {
+        return Ok(false);
+    };
+
+    let code = filled_code(&snippet.path, params)?;
+    index::log_access(&snippet.path, index::Access::Opened);
+    track_use(config, &snippet.path, executor);
+    print!("{}", code);
+    if !code.ends_with('\n') {
+        println!();
+    }
+    Ok(true)
+}
+
+/// Runs the code of `file` (or of the snippet picked with fzf) with the shell
+/// named by its language, after filling in placeholders. Only shell snippets
+/// can be run; under `--dry-run` the code is printed instead.
+/// - `params`: Placeholder values given with `--param`.
+/// - `use_cache`: Serve unchanged snippets from the metadata cache.
+fn exec_snippet(
+    config: &Config,
+    file: Option<PathBuf>,
+    params: &HashMap<String, String>,
+    use_cache: bool,
+    executor: &Executor,
+) -> Result<(), SnippetVaultError> {
+    let Some(path) = file.or_else(|| choose_snippet(config, &config.extensions(), use_cache))
+    else {
+        return Ok(());
+    };
+    let language = snippet::SnippetMeta::read(&path)?.language.to_lowercase();
+    let shell = match language.as_str() {
+        "shell" => "sh",
+        language if pet::SHELL_LANGUAGES.contains(&language) => language,
+        _ => {
+            return Err(SnippetVaultError::Command(format!(
+                "{} is a {} snippet; only shell snippets can be run",
+                path.display(),
+                language
+            )))
+        }
+    };
+
+    let code = filled_code(&path, params)?;
+    index::log_access(&path, index::Access::Opened);
+    if executor.is_dry_run() {
+        println!("{} would run with {}:", "•".accent(), shell);
+        print!("{}", code);
+        return Ok(());
+    }
+    track_use(config, &path, executor);
+    let status = logging::run(ProcessCommand::new(shell).arg("-c").arg(&code))?;
+    if !status.success() {
+        return Err(SnippetVaultError::Command(format!("{}: {}", shell, status)));
+    }
+    Ok(())
+}
+
+/// Deletes `file` (or the snippets picked with fzf) after listing them and
+/// asking for confirmation, skipped with `yes` and under `--dry-run`. Returns
+/// whether anything was deleted.
+/// - `multi`: Pick several snippets instead of one.
+/// - `use_cache`: Serve unchanged snippets from the metadata cache.
+fn delete_snippets(
+    config: &Config,
+    file: Option<PathBuf>,
+    multi: bool,
+    yes: bool,
+    use_cache: bool,
+    executor: &Executor,
+) -> Result<bool, SnippetVaultError> {
+    let paths = match file {
+        Some(file) if !file.is_file() => {
+            return Err(SnippetVaultError::Config(format!(
+                "no such snippet: {}",
+                file.display()
+            )))
+        }
+        Some(file) => vec![file],
+        None => choose_snippets(config, &config.extensions(), multi, use_cache),
+    };
+    if paths.is_empty() {
+        return Ok(false);
+    }
+
+    if !yes && !executor.is_dry_run() {
+        if !prompt::is_interactive() {
+            return Err(SnippetVaultError::Config(
+                "refusing to delete snippets without a terminal (pass --yes)".to_string(),
+            ));
+        }
+        for path in &paths {
+            println!("  {}", path.display());
+        }
+        if !prompt::confirm(&format!("Delete {} snippet(s)?", paths.len()), false)? {
+            return Ok(false);
+        }
+    }
+
+    let _lock = executor.lock()?;
+    let mut deleted = 0;
+    for path in &paths {
+        let hook_env = hooks::SnippetEnv::read(path);
+        if !executor.is_dry_run() {
+            if let Err(err) = hooks::run_hook("pre_delete", &hook_env.vars(), config) {
+                println!("{} {}: {}; kept.", "✘".error(), path.display(), err);
+                continue;
+            }
+        }
+        match bulk::apply(path, &bulk::BulkAction::Delete, executor) {
+            Ok(_) if executor.is_dry_run() => {}
+            Ok(_) => {
+                index::forget_snippet(path);
+                println!("{} Deleted {}", "✔".success(), path.display());
+                deleted += 1;
+                hooks::run_post_hook("post_delete", &hook_env.vars(), config);
+            }
+            Err(err) => println!("{} {}: {}", "✘".error(), path.display(), err),
+        }
+    }
+    Ok(deleted > 0)
+}
+
+/// Compiles the snippets `filter` selects into a cheatsheet sorted by title, then
+/// writes it to `output` or prints it: HTML as-is, markdown rendered through the pager.
+/// - `html`: Produce a standalone HTML page instead of markdown.
+/// - `use_cache`: Serve unchanged snippets from the metadata cache.
+fn cheatsheet(
+    config: &Config,
+    filter: &listing::SnippetFilter,
+    output: Option<PathBuf>,
+    html: bool,
+    use_cache: bool,
+    executor: &Executor,
+) -> Result<(), SnippetVaultError> {
+    let language = filter.language.as_deref().unwrap_or_default();
+    let snippets = listing::query_snippets(
+        &vault::snippet_dir(config),
+        filter,
+        listing::SortCriterion::Title,
+        &config.extensions(),
+        use_cache,
+    )?;
+    let (markdown, count) = export::cheatsheet(language, &snippets)?;
+    if count == 0 {
+        println!("{} No {} snippets with code match.", "•".accent(), language);
+        return Ok(());
+    }
+    let document = if html {
+        export::cheatsheet_html(language, &markdown)
+    } else {
+        markdown
+    };
+
+    match output {
+        Some(path) => {
+            executor.apply(Mutation::Write {
+                path: path.clone(),
+                contents: document,
+            })?;
+            if !executor.is_dry_run() {
+                println!(
+                    "{} Wrote a cheatsheet of {} snippets to {}",
+                    "✔".success(),
+                    count,
+                    path.display()
+                );
+            }
+        }
+        None if html => print!("{}", document),
+        None => {
+            let color = colored::control::SHOULD_COLORIZE.should_colorize();
+            let renderer = render::Renderer::new(config.highlight_theme(), color);
+            Pager::new(config).text(&renderer.render_markdown(&document));
+        }
+    }
+    Ok(())
+}
+
+/// Writes the vault's shell snippets as a pet `snippet.toml` to `output`, or
+/// prints it to stdout.
+/// - `use_cache`: Serve unchanged snippets from the metadata cache.
+fn export_pet(
+    config: &Config,
+    output: Option<PathBuf>,
+    use_cache: bool,
+    executor: &Executor,
+) -> Result<(), SnippetVaultError> {
+    let snippets =
+        vault::load_snippets(&vault::snippet_dir(config), &config.extensions(), use_cache)?;
+    let (toml, count) = pet::export_pet(&snippets)?;
+    match output {
+        Some(path) => {
+            executor.apply(Mutation::Write {
+                path: path.clone(),
+                contents: toml,
+            })?;
+            if !executor.is_dry_run() {
+                println!(
+                    "{} Exported {} shell snippets to {}",
+                    "✔".success(),
+                    count,
+                    path.display()
+                );
+            }
+        }
+        None => print!("{}", toml),
+    }
+    Ok(())
+}
+
+/// Prints `file` (or a snippet picked with fzf) with its headings bolded and
+/// code blocks highlighted, through the pager when it is long.
+/// - `use_cache`: Serve unchanged snippets from the metadata cache for the picker.
+/// - `executor`: Archives an expired snippet when configured to.
+fn cat_snippet(config: &Config, file: Option<PathBuf>, use_cache: bool, executor: &Executor) {
+    let Some(path) = file.or_else(|| choose_snippet(config, &config.extensions(), use_cache))
+    else {
+        return;
+    };
+    check_expiry(config, &path, executor);
+    let content = match std::fs::read_to_string(&path) {
+        Ok(content) => content,
+        Err(err) => {
+            println!("{} {}: {}", "✘".error(), path.display(), err);
+            return;
+        }
+    };
+
+    let color = colored::control::SHOULD_COLORIZE.should_colorize();
+    let renderer = render::Renderer::new(config.highlight_theme(), color);
+    let mut out = Pager::new(config);
+    out.text(&renderer.render_file(&path, &content));
+    index::log_access(&path, index::Access::Opened);
+}
+
+/// Prints the metadata of one snippet as a card, or with `json` as one JSON
+/// object. The snippet is found by `id` (its filename timestamp or file
+/// stem), given as `file`, or picked with fzf. Returns `false` when no
+/// snippet has the id.
+/// - `use_cache`: Serve unchanged snippets from the metadata cache.
+fn snippet_info(
+    config: &Config,
+    id: Option<&str>,
+    file: Option<PathBuf>,
+    json: bool,
+    use_cache: bool,
+) -> Result<bool, SnippetVaultError> {
+    let path = match (id, file) {
+        (Some(id), _) => {
+            let snippet_dir = vault::snippet_dir(config);
+            let snippets = vault::load_snippets(&snippet_dir, &config.extensions(), use_cache)?;
+            match vault::find_by_id(&snippets, id) {
+                Some(path) => path,
+                None => return Ok(false),
+            }
+        }
+        (None, Some(file)) => file,
+        (None, None) => match choose_snippet(config, &config.extensions(), use_cache) {
+            Some(path) => path,
+            None => return Ok(true),
+        },
+    };
+    let info = info::SnippetInfo::collect(&path)?;
+    if json {
+        println!("{:#}", serde_json::json!(info));
+        return Ok(true);
+    }
+
+    let missing = || "—".dimmed().to_string();
+    let or_missing = |value: Option<String>| value.unwrap_or_else(missing);
+    let list = |values: &[String]| (!values.is_empty()).then(|| values.join(", "));
+    let time = |time: Option<chrono::NaiveDateTime>| {
+        time.map(|time| time.format("%Y-%m-%d %H:%M").to_string())
+    };
+    let rows = [
+        ("Title", or_missing(info.title.clone()).bold().to_string()),
+        ("ID", or_missing(info.id.clone())),
+        ("Path", info.path.display().to_string()),
+        ("Language", or_missing(info.language.clone())),
+        ("Tags", or_missing(list(&info.tags))),
+        ("Created", or_missing(time(info.created))),
+        ("Modified", or_missing(time(info.modified))),
+        (
+            "Opened",
+            or_missing(info.opened.map(|count| format!("{} times", count))),
+        ),
+        (
+            "Size",
+            format!("{} bytes, {} lines", info.bytes, info.lines),
+        ),
+        ("Pinned", if info.pinned { "yes" } else { "no" }.to_string()),
+        ("Aliases", or_missing(list(&info.aliases))),
+        ("Link", or_missing(list(&info.links))),
+        ("Code blocks", info.code_blocks.to_string()),
+    ];
+    let mut out = Pager::new(config);
+    for (label, value) in rows {
+        out.line(format_args!("{} {}", width::pad(label, 12).accent(), value));
+    }
+    if info.teaser.is_empty() {
+        out.line(format_args!(
+            "{} {}",
+            width::pad("Teaser", 12).accent(),
+            missing()
+        ));
+    } else {
+        for (index, line) in info.teaser.iter().enumerate() {
+            let label = if index == 0 { "Teaser" } else { "" };
+            out.line(format_args!(
+                "{} {}",
+                width::pad(label, 12).accent(),
+                line.dimmed()
+            ));
+        }
+    }
+    Ok(true)
+}
+
+/// Offers the `count` most repeated complex commands of `history` that are not
+/// saved yet in fzf, and creates a bash snippet for each one marked. Returns
+/// whether any snippet was created.
+/// - `min_count`: Skip commands run fewer times than this.
+/// - `use_cache`: Serve unchanged snippets from the metadata cache.
+fn suggest_snippets(
+    config: &Config,
+    history: &Path,
+    count: usize,
+    min_count: usize,
+    use_cache: bool,
+    executor: &Executor,
+) -> bool {
+    let snippet_dir = vault::snippet_dir(config);
+    let thresholds = suggest::Thresholds {
+        min_length: config.suggest_min_length(),
+        min_pipes: config.suggest_min_pipes(),
+        min_count,
+    };
+    let mut candidates = match suggest::find_candidates(
+        history,
+        &snippet_dir,
+        thresholds,
+        &config.extensions(),
+        use_cache,
+    ) {
+        Ok(candidates) => candidates,
+        Err(err) => {
+            println!("{} {}: {}", "✘".error(), history.display(), err);
+            return false;
+        }
+    };
+    if candidates.is_empty() {
+        println!("{} No repeated commands to suggest.", "•".accent());
+        return false;
+    }
+    candidates.truncate(count);
+
+    let labels: Vec<String> = candidates
+        .iter()
+        .map(|candidate| format!("{:>4}×  {}", candidate.count, candidate.label()))
+        .collect();
+    let header = "Repeated commands not in the vault (tab marks, enter saves)";
+    let chosen: Vec<suggest::Candidate> = match picker::pick_labels(config, &labels, header) {
+        Ok(indices) => indices
+            .into_iter()
+            .filter_map(|index| candidates.get(index).cloned())
+            .collect(),
+        Err(err) => {
+            println!("{} {}", "✘".error(), err);
+            return false;
+        }
+    };
+    if chosen.is_empty() {
+        return false;
+    }
+
+    match suggest::save_candidates(&chosen, &snippet_dir, config.timestamp_format(), executor) {
+        Ok(_) if executor.is_dry_run() => false,
+        Ok(paths) => {
+            for path in &paths {
+                println!("{} Snippet created: {}", "✔".success(), path.display());
+            }
+            true
+        }
+        Err(err) => {
+            println!("{} {}", "✘".error(), err);
+            false
+        }
+    }
+}
+
+/// Prints `file` (or a snippet picked with fzf) rendered by the built-in
+/// markdown renderer, wrapped to the output width; plain code files are
+/// highlighted as in `--cat_snippet`. Expired snippets get a warning but,
+/// since this also draws picker previews, are never archived.
+fn render_terminal(config: &Config, file: Option<PathBuf>, use_cache: bool) {
+    let Some(path) = file.or_else(|| choose_snippet(config, &config.extensions(), use_cache))
+    else {
+        return;
+    };
+    warn_if_expired(&path);
+    let content = match std::fs::read_to_string(&path) {
+        Ok(content) => content,
+        Err(err) => {
+            println!("{} {}: {}", "✘".error(), path.display(), err);
+            return;
+        }
+    };
+
+    let width = u16::try_from(config.output_width()).unwrap_or(u16::MAX);
+    let color = colored::control::SHOULD_COLORIZE.should_colorize();
+    let renderer = render::Renderer::new(config.highlight_theme(), color);
+    if snippet::is_markdown(&path) {
+        print!("{}", renderer.render_markdown_terminal(&content, width));
+    } else {
+        print!("{}", renderer.render_file(&path, &content));
+    }
+}
+
+/// Prints the snippet of the day (see [`motd::snippet_of_the_day`]) as one
+/// dim line cut to the output width, or rendered in full with `full`. Meant
+/// for shell startup, so it only reads the metadata cache, runs nothing
+/// external, and prints nothing when the cache is cold.
+fn motd(config: &Config, full: bool) {
+    let today = Local::now().date_naive();
+    let Some(snippet) =
+        motd::snippet_of_the_day(&vault::snippet_dir(config), today, config.motd_prefer())
+    else {
+        return;
+    };
+    if full {
+        render_terminal(config, Some(snippet.path), false);
+        return;
+    }
+    let line = width::truncate(&motd::headline(&snippet), config.output_width());
+    println!("{}", line.dimmed());
+}
+
+/// Sets (or with `value: None`, removes) a custom field on `file`, or on a
+/// snippet picked with fzf when no file is given.
+/// - `use_cache`: Serve unchanged snippets from the metadata cache for the picker.
+/// - `executor`: Applies (or, with `--dry-run`, only prints) the rewrite.
+fn edit_field(
+    config: &Config,
+    file: Option<PathBuf>,
+    key: &str,
+    value: Option<toml::Value>,
+    use_cache: bool,
+    executor: &Executor,
+) {
+    let path = match file {
+        Some(file) => Some(file),
+        None => choose_snippet(config, &["md".to_string()], use_cache),
+    };
+    let Some(path) = path else {
+        return;
+    };
+
+    let removing = value.is_none();
+    match fields::set_field(&path, key, value, executor) {
+        Ok(_) if executor.is_dry_run() => {}
+        Ok(true) => {
+            index::reindex_snippet(&path, &path);
+            let verb = if removing { "Removed from" } else { "Set on" };
+            println!("{} {} {} {}", "✔".success(), key, verb, path.display());
+        }
+        Ok(false) => println!("{} {} is unchanged.", "•".accent(), key),
+        Err(err) => println!("{} {}", "✘".error(), err),
+    }
+}
+
+/// Sets the expiry day of `file`, or of a markdown snippet picked with fzf
+/// when no file is given.
+/// - `use_cache`: Serve unchanged snippets from the metadata cache for the picker.
+/// - `executor`: Applies (or, with `--dry-run`, only prints) the rewrite.
+fn snippet_expiry(
+    config: &Config,
+    file: Option<PathBuf>,
+    date: NaiveDate,
+    use_cache: bool,
+    executor: &Executor,
+) {
+    let Some(path) = file.or_else(|| choose_snippet(config, &["md".to_string()], use_cache)) else {
+        return;
+    };
+    match expiry::set_expiry(&path, date, executor) {
+        Ok(_) if executor.is_dry_run() => {}
+        Ok(true) => {
+            index::reindex_snippet(&path, &path);
+            println!(
+                "{} {} expires on {}",
+                "✔".success(),
+                path.display(),
+                date.format("%Y-%m-%d")
+            );
+        }
+        Ok(false) => println!("{} {} is unchanged.", "•".accent(), expiry::EXPIRES_FIELD),
+        Err(err) => println!("{} {}", "✘".error(), err),
+    }
+}
+
+/// Lists the snippets that have expired, or with `within: Some(days)` those
+/// that will expire in the next `days` days, by expiry day.
+/// - `use_cache`: Serve unchanged snippets from the metadata cache.
+fn find_expiring(config: &Config, within: Option<u64>, use_cache: bool) {
+    let snippet_dir = vault::snippet_dir(config);
+    let today = Local::now().date_naive();
+    let found = match within {
+        None => expiry::find_expired(&snippet_dir, today, &config.extensions(), use_cache),
+        Some(days) => {
+            expiry::find_expiring_soon(&snippet_dir, today, days, &config.extensions(), use_cache)
+        }
+    };
+    let snippets = match found {
+        Ok(snippets) => snippets,
+        Err(err) => {
+            println!("{} {}", "✘".error(), err);
+            return;
+        }
+    };
+    if snippets.is_empty() {
+        match within {
+            None => println!("{} No snippets have expired.", "•".accent()),
+            Some(days) => println!("{} No snippets expire within {} days.", "•".accent(), days),
+        }
+        return;
+    }
+
+    let mut out = Pager::new(config);
+    for (date, snippet) in &snippets {
+        let relative = snippet
+            .path
+            .strip_prefix(&snippet_dir)
+            .unwrap_or(&snippet.path);
+        out.line(format_args!(
+            "{}  {}  {}",
+            date.format("%Y-%m-%d").to_string().warning(),
+            snippet.title,
+            relative.display().to_string().dimmed()
+        ));
+    }
+}
+
+/// Runs the maintenance tasks enabled under `[housekeeping]`, then prints what
+/// each did. Returns whether all of them succeeded; having nothing to do counts
+/// as success.
+/// - `yes`: Delete empty snippets without asking.
+/// - `use_cache`: Serve unchanged snippets from the metadata cache when rebuilding the index.
+fn housekeep(config: &Config, yes: bool, use_cache: bool, executor: &Executor) -> bool {
+    let settings = &config.housekeeping;
+    let snippet_dir = vault::snippet_dir(config);
+    let extensions = config.extensions();
+    let disabled = || Ok("disabled".to_string());
+
+    let results: Vec<(&str, Result<String, SnippetVaultError>)> = vec![
+        (
+            "empty files",
+            if settings.delete_empty {
+                housekeep_empty_snippets(&snippet_dir, &extensions, yes, executor)
+            } else {
+                disabled()
+            },
+        ),
+        (
+            "index",
+            if settings.rebuild_index {
+                housekeep_index(
+                    &snippet_dir,
+                    &extensions,
+                    settings.index_max_age_days,
+                    use_cache,
+                    executor,
+                )
+            } else {
+                disabled()
+            },
+        ),
+        (
+            "backups",
+            if settings.rotate_backups {
+                housekeep_backups(settings.keep_backups, executor)
+            } else {
+                disabled()
+            },
+        ),
+        (
+            "trash",
+            if settings.empty_trash {
+                housekeep_trash(&snippet_dir, settings.trash_max_age_days, executor)
+            } else {
+                disabled()
+            },
+        ),
+    ];
+
+    println!(
+        "{}",
+        format!("{}  {}", width::pad("TASK", 11), "RESULT").bold()
+    );
+    let mut ok = true;
+    for (task, result) in results {
+        match result {
+            Ok(summary) => println!("{}  {}", width::pad(task, 11), summary),
+            Err(err) => {
+                ok = false;
+                println!("{}  {} {}", width::pad(task, 11), "✘".error(), err);
+            }
+        }
+    }
+    ok
+}
+
+/// Deletes the zero-byte snippets, after confirmation unless `yes`. Without a
+/// terminal to ask on they are kept, so unattended runs never delete them
+/// unless `--yes` is given.
+fn housekeep_empty_snippets(
+    snippet_dir: &Path,
+    extensions: &[String],
+    yes: bool,
+    executor: &Executor,
+) -> Result<String, SnippetVaultError> {
+    let empty = housekeep::empty_snippets(snippet_dir, extensions)?;
+    if empty.is_empty() {
+        return Ok("nothing to do".to_string());
+    }
+    if !yes && !executor.is_dry_run() {
+        if !prompt::is_interactive() {
+            return Ok(format!("kept {} (pass --yes to delete)", empty.len()));
+        }
+        for path in &empty {
+            println!("  {}", path.display());
+        }
+        if !prompt::confirm(&format!("Delete {} empty snippet(s)?", empty.len()), false)? {
+            return Ok(format!("kept {}", empty.len()));
+        }
+    }
+
+    let _lock = executor.lock()?;
+    for path in &empty {
+        executor.apply(Mutation::Remove(path.clone()))?;
+        if !executor.is_dry_run() {
+            index::forget_snippet(path);
+        }
+    }
+    Ok(if executor.is_dry_run() {
+        format!("would delete {}", empty.len())
+    } else {
+        format!("deleted {}", empty.len())
+    })
+}
+
+/// Rebuilds and vacuums the title index when it is stale.
+/// - `max_age_days`: Age from which a built index is stale.
+/// - `use_cache`: Serve unchanged snippets from the metadata cache.
+fn housekeep_index(
+    snippet_dir: &Path,
+    extensions: &[String],
+    max_age_days: u64,
+    use_cache: bool,
+    executor: &Executor,
+) -> Result<String, SnippetVaultError> {
+    let db = index::open(&index::index_path())?;
+    let now = Local::now().naive_local();
+    let Some(reason) = housekeep::index_staleness(&db, snippet_dir, extensions, max_age_days, now)?
+    else {
+        return Ok("up to date".to_string());
+    };
+    if executor.is_dry_run() {
+        println!("{} would rebuild the index", "•".accent());
+        return Ok(format!("would rebuild ({})", reason));
+    }
+    let count = index::build_title_index(snippet_dir, &db, extensions, use_cache)?;
+    index::vacuum(&db)?;
+    Ok(format!("rebuilt with {} snippets ({})", count, reason))
+}
+
+/// Deletes the vault backups beyond the newest `keep`.
+fn housekeep_backups(keep: usize, executor: &Executor) -> Result<String, SnippetVaultError> {
+    let old = housekeep::old_backups(&vault::backup_dir(), keep)?;
+    if old.is_empty() {
+        return Ok("nothing to do".to_string());
+    }
+    for path in &old {
+        executor.apply(Mutation::Remove(path.clone()))?;
+    }
+    Ok(if executor.is_dry_run() {
+        format!("would delete {} (keeping {})", old.len(), keep)
+    } else {
+        format!("deleted {} (kept {})", old.len(), keep)
+    })
+}
+
+/// Deletes the files trashed more than `max_age_days` ago, and the trash
+/// directories that leaves empty.
+fn housekeep_trash(
+    snippet_dir: &Path,
+    max_age_days: u64,
+    executor: &Executor,
+) -> Result<String, SnippetVaultError> {
+    let old = housekeep::old_trash(snippet_dir, max_age_days, Local::now().naive_local())?;
+    if old.is_empty() {
+        return Ok("nothing to do".to_string());
+    }
+    let _lock = executor.lock()?;
+    for path in &old {
+        executor.apply(Mutation::Remove(path.clone()))?;
+    }
+    if !executor.is_dry_run() {
+        let trash = snippet_dir.join(vault::TRASH_DIR);
+        let mut dirs: Vec<&Path> = old
+            .iter()
+            .flat_map(|path| path.ancestors().skip(1))
+            .filter(|dir| dir.starts_with(&trash) && *dir != trash)
+            .collect();
+        // Deepest first, so a directory is emptied before its parent is checked.
+        dirs.sort_by_key(|dir| (std::cmp::Reverse(dir.components().count()), *dir));
+        dirs.dedup();
+        for dir in dirs {
+            if std::fs::read_dir(dir).is_ok_and(|mut entries| entries.next().is_none()) {
+                executor.apply(Mutation::RemoveDir(dir.to_path_buf()))?;
+            }
+        }
+    }
+    Ok(if executor.is_dry_run() {
+        format!(
+            "would delete {} (older than {} days)",
+            old.len(),
+            max_age_days
+        )
+    } else {
+        format!("deleted {} (older than {} days)", old.len(), max_age_days)
+    })
+}
+
+/// Prints a warning banner when the snippet at `path` has expired, and
+/// returns whether it has.
+fn warn_if_expired(path: &Path) -> bool {
+    let Some(date) = expiry::expired_on(path, Local::now().date_naive()) else {
+        return false;
+    };
+    println!(
+        "{} This snippet expired on {}",
+        "⚠".warning(),
+        date.format("%Y-%m-%d")
+    );
+    true
+}
+
+/// Warns when the snippet at `path` has expired and, with
+/// `auto_archive_expired` set, marks it `archived = true`.
+/// - `executor`: Applies (or, with `--dry-run`, only prints) the rewrite.
+fn check_expiry(config: &Config, path: &Path, executor: &Executor) {
+    if !warn_if_expired(path) || !config.auto_archive_expired || vault::is_read_only() {
+        return;
+    }
+    match expiry::archive(path, executor) {
+        Ok(true) if !executor.is_dry_run() => println!("{} Archived", "✔".success()),
+        Ok(_) => {}
+        Err(err) => println!("{} Cannot archive {}: {}", "✘".error(), path.display(), err),
+    }
+}
+
+/// Looks `query` up in the title index (building the index first if it was
+/// never built), lets the user pick among the matches with fzf, and opens the choice.
+/// Snippets that were used show their use count after the title.
+/// - `use_cache`: Serve unchanged snippets from the metadata cache when building the index.
+/// - `executor`: Formats edited snippets when `format_on_save` is on.
+fn find_snippet_by_title(
+    config: &Config,
+    query: &str,
+    use_cache: bool,
+    executor: &Executor,
+) -> Result<(), SnippetVaultError> {
+    let db = index::open(&index::index_path())?;
+    // Rows added one by one (see `index::reindex_snippet`) do not make a built index.
+    if index::built_at(&db)?.is_none() {
+        index::build_title_index(
+            &vault::snippet_dir(config),
+            &db,
+            &config.extensions(),
+            use_cache,
+        )?;
+    }
+
+    let matches = index::search_by_title(query, &db)?;
+    if matches.is_empty() {
+        println!("{} No snippet titles match '{}'.", "•".accent(), query);
+        return Ok(());
+    }
+
+    let snippets: Vec<snippet::SnippetMeta> = matches
+        .iter()
+        .filter_map(|(path, _)| snippet::SnippetMeta::read(path).ok())
+        .collect();
+    let uses = usage::use_counts(&snippets);
+    let entries: Vec<(String, PathBuf)> = matches
+        .into_iter()
+        .map(|(path, title)| match uses.get(&path) {
+            Some(count) => (format!("{}  [{}×]", title, count), path),
+            None => (title, path),
+        })
+        .collect();
+    if let Some(path) = picker::pick(config, &entries, None)? {
+        index::log_access(&path, index::Access::Opened);
+        editor::open(config, &[path], executor)?;
+    }
+    Ok(())
+}
+
+/// Prints the activity digest for the last `period` days.
+/// - `markdown`: Print GitHub Flavored Markdown instead of the colored report.
+/// - `use_cache`: Serve unchanged snippets from the metadata cache.
+fn weekly_digest(config: &Config, period: u32, markdown: bool, use_cache: bool) {
+    let digest = index::open(&index::index_path()).and_then(|db| {
+        digest::weekly_digest(
+            &vault::snippet_dir(config),
+            &db,
+            period,
+            &config.extensions(),
+            &config.language_aliases,
+            use_cache,
+        )
+    });
+    let digest = match digest {
+        Ok(digest) => digest,
+        Err(err) => {
+            println!("{} {}", "✘".error(), err);
+            return;
+        }
+    };
+
+    let mut out = Pager::new(config);
+    if markdown {
+        out.text(&digest::render_markdown(&digest));
+        return;
+    }
+
+    out.line(format_args!(
+        "{}",
+        format!(
+            "Snippet digest: last {} days (since {})",
+            digest.period_days,
+            digest.since.format("%Y-%m-%d")
+        )
+        .bold()
+    ));
+    out.line("");
+    out.line(format_args!(
+        "  {:<10}{}",
+        "Created",
+        digest.created.to_string().success().bold()
+    ));
+    out.line(format_args!(
+        "  {:<10}{}",
+        "Edited",
+        digest.edited.to_string().warning().bold()
+    ));
+
+    out.line("");
+    out.line("Languages".bold());
+    if digest.languages.is_empty() {
+        out.line(format_args!("  {}", "no activity".dimmed()));
+    }
+    for (language, count) in &digest.languages {
+        out.line(format_args!("  {:<14}{}", language.accent(), count));
+    }
+
+    out.line("");
+    out.line("Newest snippets".bold());
+    if digest.newest.is_empty() {
+        out.line(format_args!("  {}", "none".dimmed()));
+    }
+    for snippet in &digest.newest {
+        let created = snippet
+            .created
+            .map(|created| created.format("%Y-%m-%d %H:%M").to_string())
+            .unwrap_or_default();
+        out.line(format_args!(
+            "  {}  {}  {}",
+            created.dimmed(),
+            snippet.title.accent(),
+            snippet.language
+        ));
+    }
+}
+
+/// Lints one snippet or, with `all`, the whole vault, and prints the findings
+/// grouped by severity followed by a summary. Returns whether `fix` changed
+/// any file.
+/// - `file`: The snippet to lint when `all` is not set.
+/// - `fix`: First run the code blocks through their formatters (see [`format`]).
+/// - `executor`: Applies (or, with `--dry-run`, only prints) the fixes.
+fn lint_snippets(
+    config: &Config,
+    file: Option<PathBuf>,
+    all: bool,
+    fix: bool,
+    executor: &Executor,
+) -> bool {
+    let rules = match lint::load_rules(&config.lint_rules) {
+        Ok(rules) => rules,
+        Err(err) => {
+            println!("{} {}", "✘".error(), err);
+            return false;
+        }
+    };
+
+    let files = match (file, all) {
+        (Some(file), _) => vec![file],
+        (None, true) => match vault::vault_files(&vault::snippet_dir(config), &config.extensions())
+        {
+            Ok(files) => files,
+            Err(err) => {
+                println!("{} {}", "✘".error(), err);
+                return false;
+            }
+        },
+        (None, false) => {
+            println!("{} Pass a snippet file or --all.", "✘".error());
+            return false;
+        }
+    };
+
+    let mut fixed = false;
+    if fix {
+        let _lock = match executor.lock() {
+            Ok(lock) => lock,
+            Err(err) => {
+                println!("{} {}", "✘".error(), err);
+                return false;
+            }
+        };
+        for path in &files {
+            match format::format_snippet(config, path, executor) {
+                Ok(report) => {
+                    fixed |= report.formatted > 0 && !executor.is_dry_run();
+                    format::print_report(path, &report, executor.is_dry_run());
+                }
+                Err(err) => println!("{} {}: {}", "✘".error(), path.display(), err),
+            }
+        }
+    }
+
+    let mut warnings: Vec<lint::LintWarning> = files
+        .iter()
+        .flat_map(|path| lint::lint_snippet(path, &rules))
+        .collect();
+    warnings.sort_by(|a, b| a.severity.cmp(&b.severity).then(a.path.cmp(&b.path)));
+
+    let mut out = Pager::new(config);
+    let mut current = None;
+    for warning in &warnings {
+        if current != Some(warning.severity) {
+            current = Some(warning.severity);
+            let heading = format!("{}:", warning.severity).to_uppercase();
+            match warning.severity {
+                lint::Severity::Error => out.line(heading.error().bold()),
+                lint::Severity::Warning => out.line(heading.warning().bold()),
+                lint::Severity::Info => out.line(heading.accent().bold()),
+            }
+        }
+        let location = match warning.line {
+            Some(line) => format!("{}:{}", warning.path.display(), line),
+            None => warning.path.display().to_string(),
+        };
+        out.line(format_args!("  {} {}", location, warning.message));
+    }
+
+    let count = |severity| warnings.iter().filter(|w| w.severity == severity).count();
+    let summary = format!(
+        "{} files checked: {} errors, {} warnings, {} info",
+        files.len(),
+        count(lint::Severity::Error),
+        count(lint::Severity::Warning),
+        count(lint::Severity::Info)
+    );
+    if warnings.is_empty() {
+        out.line(format_args!("{} {}", "✔".success(), summary));
+    } else {
+        out.line(format_args!("{} {}", "•".accent(), summary));
+    }
+    fixed
+}
+
+/// Spell-checks the prose of one snippet or of every markdown snippet, and prints each unknown word with its line and suggestions.
+/// Returns whether any file or the vault dictionary changed, and how many
+/// unknown words are left.
+/// - `file`: The snippet to check; `None` checks the whole vault.
+/// - `interactive`: Ask, word by word, to add it to the vault dictionary (a),
+///   replace it with a suggestion (f), or leave it (s).
+/// - `executor`: Applies (or, with `--dry-run`, only prints) fixes and
+///   dictionary additions.
+fn spellcheck(
+    config: &Config,
+    file: Option<PathBuf>,
+    interactive: bool,
+    executor: &Executor,
+) -> Result<(bool, usize), SnippetVaultError> {
+    let snippet_dir = vault::snippet_dir(config);
+    let files: Vec<PathBuf> = match file {
+        Some(file) => vec![file],
+        None => vault::vault_files(&snippet_dir, &config.extensions())?
+            .into_iter()
+            .filter(|path| snippet::is_markdown(path))
+            .collect(),
+    };
+    if interactive {
+        prompt::require_interactive("check spelling interactively", "run without --interactive")?;
+    }
+    let mut dictionary = spelling::Dictionary::load(&snippet_dir)?;
+    let _lock = if interactive {
+        Some(executor.lock()?)
+    } else {
+        None
+    };
+
+    let (mut changed, mut added, mut remaining) = (false, false, 0);
+    let mut out = Pager::new(config);
+    for path in &files {
+        let content = match std::fs::read_to_string(path) {
+            Ok(content) => content,
+            Err(err) => {
+                println!("{} {}: {}", "✘".error(), path.display(), err);
+                continue;
+            }
+        };
+        let misspellings = spelling::check(&content, &dictionary);
+        if !interactive {
+            for misspelling in &misspellings {
+                let hint = match misspelling.suggestions.is_empty() {
+                    true => String::new(),
+                    false => format!(" (did you mean {}?)", misspelling.suggestions.join(", ")),
+                };
+                out.line(format_args!(
+                    "{}:{}:{}: {}{}",
+                    path.display(),
+                    misspelling.line,
+                    misspelling.column + 1,
+                    misspelling.word.warning(),
+                    hint
+                ));
+            }
+            remaining += misspellings.len();
+            continue;
+        }
+
+        let mut fixes = Vec::new();
+        for misspelling in misspellings {
+            // An earlier answer may have added the word.
+            if !dictionary.knows(&misspelling.word) {
+                println!(
+                    "{}:{}: {}",
+                    path.display(),
+                    misspelling.line,
+                    misspelling.word.warning().bold()
+                );
+                if !misspelling.suggestions.is_empty() {
+                    println!("  suggestions: {}", misspelling.suggestions.join(", "));
+                }
+                let answer = prompt::ask("  [a]dd to dictionary, [f]ix, [s]kip", "s")?;
+                match answer.trim().to_lowercase().as_str() {
+                    "a" | "add" => {
+                        dictionary.add(&misspelling.word);
+                        added = true;
+                    }
+                    "f" | "fix" => {
+                        let replacement = match misspelling.suggestions.len() {
+                            0 => prompt::ask("  Replace with", &misspelling.word)?,
+                            1 => misspelling.suggestions[0].clone(),
+                            _ => {
+                                let choice =
+                                    prompt::choose("  Replace with:", &misspelling.suggestions)?;
+                                misspelling.suggestions[choice].clone()
+                            }
+                        };
+                        if replacement != misspelling.word {
+                            fixes.push((
+                                misspelling.line,
+                                misspelling.column,
+                                misspelling.word,
+                                replacement,
+                            ));
+                        } else {
+                            remaining += 1;
+                        }
+                    }
+                    _ => remaining += 1,
+                }
+            }
+        }
+        if !fixes.is_empty() {
+            executor.apply(Mutation::Write {
+                path: path.clone(),
+                contents: spelling::apply_fixes(&content, &fixes),
+            })?;
+            changed |= !executor.is_dry_run();
+            if !executor.is_dry_run() {
+                println!(
+                    "{} Fixed {} words in {}",
+                    "✔".success(),
+                    fixes.len(),
+                    path.display()
+                );
+            }
+        }
+    }
+    if added {
+        dictionary.save(executor)?;
+        changed |= !executor.is_dry_run();
+    }
+
+    let summary = format!("{} files checked: {} unknown words", files.len(), remaining);
+    if remaining == 0 {
+        out.line(format_args!("{} {}", "✔".success(), summary));
+    } else {
+        out.line(format_args!("{} {}", "•".accent(), summary));
+    }
+    Ok((changed, remaining))
+}
+
+/// Shows how replacing `pattern` with `replacement` would change the snippets
+/// `filter` selects, asks whether to apply all, some, or none of the changes
+/// (unless `yes`), and writes each accepted file atomically. Returns whether
+/// any file changed.
+/// - `regex`: Treat `pattern` as a regular expression, whose capture groups
+///   `replacement` may refer to; otherwise both are literal text.
+/// - `include_archived`: Also edit snippets marked `archived = true`.
+/// - `use_cache`: Serve unchanged snippets from the metadata cache.
+#[allow(clippy::too_many_arguments)]
+fn replace_in_files(
+    config: &Config,
+    pattern: &str,
+    replacement: &str,
+    regex: bool,
+    filter: &listing::SnippetFilter,
+    include_archived: bool,
+    yes: bool,
+    use_cache: bool,
+    executor: &Executor,
+) -> Result<bool, SnippetVaultError> {
+    let snippet_dir = vault::snippet_dir(config);
+    let changes = replace::plan_replacements(
+        &snippet_dir,
+        &replace::build_pattern(pattern, regex)?,
+        replacement,
+        regex,
+        filter,
+        include_archived,
+        &config.extensions(),
+        use_cache,
+    )?;
+    if changes.is_empty() {
+        println!("{} No snippets contain '{}'.", "•".accent(), pattern);
+        return Ok(false);
+    }
+
+    for change in &changes {
+        replace::print_diff(change, &snippet_dir);
+        println!();
+    }
+    let total: usize = changes.iter().map(|change| change.count).sum();
+    if executor.is_dry_run() {
+        println!(
+            "{} {} matches in {} snippets would be replaced.",
+            "•".accent(),
+            total,
+            changes.len()
+        );
+        return Ok(false);
+    }
+
+    let accepted: Vec<&replace::FileReplacement> = if yes {
+        changes.iter().collect()
+    } else {
+        let question = format!(
+            "Replace {} matches in {} snippets? [a]ll, [p]er file, [N]o:",
+            total,
+            changes.len()
+        );
+        match prompt::ask(&question, "")?.to_lowercase().as_str() {
+            "a" | "all" => changes.iter().collect(),
+            "p" | "per-file" => {
+                let mut accepted = Vec::new();
+                for change in &changes {
+                    let relative = change
+                        .path
+                        .strip_prefix(&snippet_dir)
+                        .unwrap_or(&change.path);
+                    if prompt::confirm(&format!("Apply to {}?", relative.display()), false)? {
+                        accepted.push(change);
+                    }
+                }
+                accepted
+            }
+            _ => Vec::new(),
+        }
+    };
+    if accepted.is_empty() {
+        println!("{} Nothing was replaced.", "•".accent());
+        return Ok(false);
+    }
+
+    let _lock = executor.lock()?;
+    let (mut files, mut replaced) = (0, 0);
+    for change in accepted {
+        match replace::apply_replacement(change, executor) {
+            Ok(()) => {
+                files += 1;
+                replaced += change.count;
+            }
+            Err(err) => println!("{} {}: {}", "✘".error(), change.path.display(), err),
+        }
+    }
+    println!(
+        "{} Replaced {} matches in {} snippets.",
+        "✔".success(),
+        replaced,
+        files
+    );
+    Ok(files > 0)
+}
+
+/// Lets the user edit the tags of `file` (picked with fzf when `None`) as a
+/// comma-separated line, completing tags used elsewhere in the vault with
+/// tab. The new tags are written to the frontmatter and the generated
+/// filename, which is renamed when they change. Returns whether they did.
+/// - `use_cache`: Serve unchanged snippets from the metadata cache.
+/// - `executor`: Applies (or, with `--dry-run`, only prints) the changes.
+fn interactive_tag_editor(
+    config: &Config,
+    file: Option<PathBuf>,
+    use_cache: bool,
+    executor: &Executor,
+) -> Result<bool, SnippetVaultError> {
+    let Some(path) = file.or_else(|| choose_snippet(config, &config.extensions(), use_cache))
+    else {
+        return Ok(false);
+    };
+    let current = tags::snippet_tags(&snippet::SnippetMeta::read(&path)?);
+    let mut known: Vec<String> =
+        vault::load_snippets(&vault::snippet_dir(config), &config.extensions(), use_cache)?
+            .iter()
+            .flat_map(tags::snippet_tags)
+            .collect();
+    known.sort();
+    known.dedup();
+
+    let answer = match prompt::edit_line("Tags:", &current.join(", "), known) {
+        Ok(answer) => answer,
+        Err(err) if err.kind() == std::io::ErrorKind::Interrupted => return Ok(false),
+        Err(err) => return Err(err.into()),
+    };
+    let edited = tags::parse_tag_list(&answer);
+    let add: Vec<String> = edited
+        .iter()
+        .filter(|tag| !current.contains(tag))
+        .cloned()
+        .collect();
+    let remove: Vec<String> = current
+        .iter()
+        .filter(|tag| !edited.contains(tag))
+        .cloned()
+        .collect();
+    if add.is_empty() && remove.is_empty() {
+        println!("{} Tags unchanged.", "•".accent());
+        return Ok(false);
+    }
+
+    let _lock = executor.lock()?;
+    let new_path = bulk::retag(&path, &add, &remove, executor)?;
+    if executor.is_dry_run() {
+        return Ok(false);
+    }
+    index::reindex_snippet(&path, &new_path);
+    println!(
+        "{} Tags of {}: {}",
+        "✔".success(),
+        new_path.display(),
+        if edited.is_empty() {
+            "none".to_string()
+        } else {
+            edited.join(", ")
+        }
+    );
+    Ok(true)
+}
+
+/// Removes repeated tags from the snippet `file` (picked with fzf when `None`),
+/// or from every snippet with `all`, and reports how many files changed.
+/// Returns whether any did.
+/// - `use_cache`: Serve unchanged snippets from the metadata cache when picking.
+fn dedup_tags(
+    config: &Config,
+    file: Option<PathBuf>,
+    all: bool,
+    use_cache: bool,
+    executor: &Executor,
+) -> bool {
+    let files = match (file, all) {
+        (Some(file), _) => vec![file],
+        (None, true) => match vault::vault_files(&vault::snippet_dir(config), &config.extensions())
+        {
+            Ok(files) => files,
+            Err(err) => {
+                println!("{} {}", "✘".error(), err);
+                return false;
+            }
+        },
+        (None, false) => match choose_snippet(config, &config.extensions(), use_cache) {
+            Some(file) => vec![file],
+            None => return false,
+        },
+    };
+
+    let _lock = match executor.lock() {
+        Ok(lock) => lock,
+        Err(err) => {
+            println!("{} {}", "✘".error(), err);
+            return false;
+        }
+    };
+    let mut modified = 0;
+    for path in &files {
+        match tags::dedup_tags(path, executor) {
+            Ok(Some(_)) if executor.is_dry_run() => modified += 1,
+            Ok(Some(new_path)) => {
+                index::reindex_snippet(path, &new_path);
+                if new_path != *path {
+                    println!(
+                        "{} {} -> {}",
+                        "✔".success(),
+                        path.display(),
+                        new_path.display()
+                    );
+                } else {
+                    println!("{} {}", "✔".success(), path.display());
+                }
+                modified += 1;
+            }
+            Ok(None) => {}
+            Err(err) => println!("{} {}: {}", "✘".error(), path.display(), err),
+        }
+    }
+
+    match (modified, executor.is_dry_run()) {
+        (0, _) => println!("{} No duplicate tags found.", "•".accent()),
+        (count, true) => println!("{} Would modify {} files.", "•".accent(), count),
+        (count, false) => println!("{} Modified {} files.", "✔".success(), count),
+    }
+    modified > 0 && !executor.is_dry_run()
+}
+
+/// Renames `old_tag` to `new_tag` across the vault and its collection files
+/// (see [`tags::rename_tag_cascade`]), reindexes the renamed snippets, and
+/// prints a summary. Returns whether anything changed.
+/// - `executor`: Applies (or, with `--dry-run`, only prints) the changes.
+fn rename_tag(
+    config: &Config,
+    old_tag: &str,
+    new_tag: &str,
+    executor: &Executor,
+) -> Result<bool, SnippetVaultError> {
+    if new_tag.is_empty() || new_tag.contains(|c: char| c == '_' || c == ',' || c.is_whitespace()) {
+        return Err(SnippetVaultError::Command(format!(
+            "'{}' is not a valid tag; tags cannot be empty or contain '_', ',', or spaces",
+            new_tag
+        )));
+    }
+    let snippet_dir = vault::snippet_dir(config);
+    let _lock = executor.lock()?;
+    let (report, moved) = tags::rename_tag_cascade(old_tag, new_tag, &snippet_dir, executor)?;
+    if report == tags::CascadeReport::default() {
+        println!(
+            "{} No snippets or collections use '{}'.",
+            "•".accent(),
+            old_tag
+        );
+        return Ok(false);
+    }
+    if executor.is_dry_run() {
+        println!(
+            "{} Would update {} snippets and {} collections.",
+            "•".accent(),
+            report.snippets_updated,
+            report.collections_updated
+        );
+        return Ok(false);
+    }
+    for (from, to) in &moved {
+        index::reindex_snippet(from, to);
+    }
+    println!(
+        "{} Renamed '{}' to '{}': {} snippets and {} collections updated.",
+        "✔".success(),
+        old_tag,
+        new_tag,
+        report.snippets_updated,
+        report.collections_updated
+    );
+    Ok(true)
+}
+
+/// Prints a before/after listing of the snippets a tag rename would touch,
+/// followed by any collisions the rename would cause.
+fn diff_tags(config: &Config, old_tag: &str, new_tag: &str) {
+    let snippet_dir = vault::snippet_dir(config);
+    let preview = tags::preview_tag_rename(old_tag, new_tag, &snippet_dir).and_then(|renames| {
+        let collisions = tags::find_tag_collisions(new_tag, &snippet_dir, &renames)?;
+        Ok((renames, collisions))
+    });
+    let (renames, collisions) = match preview {
+        Ok(preview) => preview,
+        Err(err) => {
+            println!("{} {}", "✘".error(), err);
+            return;
+        }
+    };
+
+    if renames.is_empty() {
+        println!("{} No snippets are tagged '{}'.", "•".accent(), old_tag);
+        return;
+    }
+
+    let mut out = Pager::new(config);
+    let column = renames
+        .iter()
+        .map(|(_, old_filename, _)| width::display_width(old_filename))
+        .max()
+        .unwrap_or(0);
+    for (_, old_filename, new_filename) in &renames {
+        if old_filename == new_filename {
+            out.line(format_args!(
+                "{}  {}",
+                width::pad(old_filename, column),
+                "(frontmatter only)".dimmed()
+            ));
+        } else {
+            out.line(format_args!(
+                "{}  {} {}",
+                width::pad(old_filename, column).error(),
+                "→".accent(),
+                new_filename.success()
+            ));
+        }
+    }
+    out.line(format_args!(
+        "{} {} snippets would change.",
+        "•".accent(),
+        renames.len()
+    ));
+
+    for warning in collisions {
+        out.line(format_args!("{} {}", "⚠".warning(), warning));
+    }
+}
+
+/// Displays a list of supported programming languages.
+fn list_languages(config: &Config) {
+    let mut out = Pager::new(config);
+    for lang in language::LISTED_LANGUAGES {
+        out.line(lang.accent());
+    }
+}
+
+/// Prints every hook with the command configured for it, and warns about
+/// `[hooks]` entries that name no known hook.
+fn list_hooks(config: &Config) {
+    for name in hooks::HOOK_NAMES {
+        match config.hooks.get(*name) {
+            Some(command) => println!("{}  {}", width::pad(name, 12).bold(), command),
+            None => println!("{}  {}", width::pad(name, 12).bold(), "—".dimmed()),
+        }
+    }
+    let mut unknown: Vec<&String> = config
+        .hooks
+        .keys()
+        .filter(|name| !hooks::HOOK_NAMES.contains(&name.as_str()))
+        .collect();
+    unknown.sort();
+    for name in unknown {
+        println!(
+            "{} Unknown hook '{}' is never run; known hooks: {}",
+            "⚠".warning(),
+            name,
+            hooks::HOOK_NAMES.join(", ")
+        );
+    }
+    if config.skip_hooks {
+        println!("{} --skip_hooks is set; no hook runs.", "•".accent());
+    }
+}
+
+/// Retrieves the default editor for editing snippets.
+/// Uses the configured `editor`, then tries a list of known paths for `nvim` or defaults to `nvim`.
+/// Checks the configuration and the external tools the commands rely on,
+/// printing one line per check.
+fn doctor(config: &Config) {
+    let snippet_dir = vault::snippet_dir(config);
+    if snippet_dir.is_dir() {
+        println!("{} Vault: {}", "✔".success(), snippet_dir.display());
+    } else {
+        println!(
+            "{} Vault directory does not exist: {}",
+            "✘".error(),
+            snippet_dir.display()
+        );
+    }
+
+    let invalid_utf8 = vault::vault_files(&snippet_dir, &config.extensions())
+        .unwrap_or_default()
+        .into_iter()
+        .filter(|path| match snippet::read_limited(path) {
+            Ok(Some(content)) => {
+                !snippet::is_binary(&content) && std::str::from_utf8(&content).is_err()
+            }
+            _ => false,
+        })
+        .count();
+    if invalid_utf8 > 0 {
+        println!(
+            "{} {} snippet files are not valid UTF-8 and are not parsed; --snippet_lint --all lists them",
+            "⚠".warning(),
+            invalid_utf8
+        );
+    }
+
+    let conflict_pairs =
+        conflicts::find_conflicts(&snippet_dir, &config.extensions()).unwrap_or_default();
+    if conflict_pairs.is_empty() {
+        println!("{} No sync conflict copies", "✔".success());
+    } else {
+        println!(
+            "{} {} sync conflict copies are left out of listings; --resolve_conflicts resolves them:",
+            "⚠".warning(),
+            conflict_pairs.len()
+        );
+        for pair in &conflict_pairs {
+            println!("  {}", pair.copy.display());
+        }
+    }
+
+    if vault::is_read_only() {
+        println!(
+            "{} Read-only: the vault is never changed, committed, or pushed",
+            "•".accent()
+        );
+    }
+
+    let editor = editor::default_editor(config);
+    let words = editor::split_command(&editor);
+    let program = words.first().map(String::as_str).unwrap_or_default();
+    if Path::new(program).is_file() || platform::find_in_path(program).is_some() {
+        println!("{} Editor: {}", "✔".success(), editor);
+    } else {
+        println!("{} Editor not found: {}", "✘".error(), editor);
+    }
+    for tool in ["fzf", "rg", "bat", "glow"] {
+        if platform::find_in_path(tool).is_none() {
+            println!("{} {} is not on PATH", "⚠".warning(), tool);
+        }
+    }
+
+    if config.format_on_save || !config.formatters.is_empty() {
+        let mut missing: Vec<(String, Vec<String>)> = Vec::new();
+        for (language, command) in format::formatters(config) {
+            if format::is_installed(&command) {
+                continue;
+            }
+            let program = format::program(&command).unwrap_or(command);
+            match missing.iter_mut().find(|(name, _)| *name == program) {
+                Some((_, languages)) => languages.push(language),
+                None => missing.push((program, vec![language])),
+            }
+        }
+        if missing.is_empty() {
+            println!("{} Every code formatter is installed", "✔".success());
+        }
+        for (program, languages) in missing {
+            println!(
+                "{} Formatter {} is not on PATH; {} blocks are not formatted",
+                "⚠".warning(),
+                program,
+                languages.join(", ")
+            );
+        }
+    }
+
+    let format = config.timestamp_format();
+    let now = Local::now().naive_local();
+    match snippet::render_timestamp(&now, format) {
+        Ok(rendered) if snippet::parse_timestamp(&rendered).is_none() => println!(
+            "{} timestamp_format '{}' ({}) is not recognised when reading filenames; those snippets are dated by modification time",
+            "⚠".warning(),
+            format,
+            rendered
+        ),
+        Ok(rendered) => {
+            println!("{} timestamp_format: {} ({})", "✔".success(), format, rendered);
+            if !["%S", "%T", "%s"].iter().any(|spec| format.contains(spec)) {
+                println!(
+                    "{} timestamp_format has no seconds; snippets created in the same minute get a -2, -3, ... suffix",
+                    "•".accent()
+                );
+            }
+        }
+        Err(err) => println!(
+            "{} {}; new snippets use {} instead",
+            "✘".error(),
+            err,
+            TIMESTAMP_FORMAT
+        ),
+    }
+
+    if let Some(theme) = config
+        .theme
+        .as_ref()
+        .and_then(style::ThemeConfig::highlight)
+    {
+        let theme = theme.to_string();
+        let themes = render::theme_names();
+        if themes.contains(&theme) {
+            println!("{} theme: {}", "✔".success(), theme);
+        } else {
+            println!(
+                "{} Unknown theme '{}'; using {}. Available: {}",
+                "✘".error(),
+                theme,
+                render::DEFAULT_THEME,
+                themes.join(", ")
+            );
+        }
+    }
+
+    let (_, problems) = config.palette();
+    if problems.is_empty() {
+        println!("{} status colors: ok", "✔".success());
+    }
+    for problem in problems {
+        println!("{} status colors: {}", "✘".error(), problem);
+    }
+    println!(
+        "{} terminal_theme: {} ({} background)",
+        "✔".success(),
+        format!("{:?}", config.terminal_theme).to_lowercase(),
+        style::resolve_glow_style(config.terminal_theme)
+    );
+
+    if let Some(open_with) = &config.open_with {
+        if editor::Opener::from_name(open_with).is_some() {
+            println!("{} open_with: {}", "✔".success(), open_with);
+        } else {
+            println!(
+                "{} Unknown open_with '{}'; using the editor. Available: {}",
+                "✘".error(),
+                open_with,
+                editor::OPENERS.join(", ")
+            );
+        }
+    }
+
+    if let Some(prefer) = &config.motd_prefer {
+        if motd::Preference::from_name(prefer).is_some() {
+            println!("{} motd_prefer: {}", "✔".success(), prefer);
+        } else {
+            println!(
+                "{} Unknown motd_prefer '{}'; picking evenly. Available: {}",
+                "✘".error(),
+                prefer,
+                motd::PREFERENCES.join(", ")
+            );
+        }
+    }
+
+    if let Some(store) = &config.usage_store {
+        if usage::UsageStore::from_name(store).is_some() {
+            println!("{} usage_store: {}", "✔".success(), store);
+        } else {
+            println!(
+                "{} Unknown usage_store '{}'; using index. Available: {}",
+                "✘".error(),
+                store,
+                usage::USAGE_STORES.join(", ")
+            );
+        }
+    }
 }
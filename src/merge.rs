@@ -0,0 +1,69 @@
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use crate::error::SnippetVaultError;
+use crate::language;
+use crate::snippet::{code_blocks, is_markdown, render_new_snippet, snippet_filename, SnippetMeta};
+use crate::vault::{Executor, Mutation};
+
+/// Combines `sources` into one new snippet in `snippet_dir` and returns its path.
+/// The new code block holds every source's code blocks in order, each preceded by
+/// a `--- {source title} ---` comment in `new_language`'s comment syntax, and the
+/// Note section lists the merged-from filenames. Plain code files contribute
+/// their whole content.
+/// - `executor`: Applies (or, with `--dry-run`, only prints) the new file.
+pub fn merge_snippets(
+    snippet_dir: &Path,
+    sources: &[PathBuf],
+    new_language: &str,
+    new_tags: &[&str],
+    timestamp: &str,
+    executor: &Executor,
+) -> Result<PathBuf, SnippetVaultError> {
+    let comment = language::comment_prefix(new_language).unwrap_or("//");
+
+    let mut code = String::new();
+    let mut note = String::from("Merged from:\n");
+    for source in sources {
+        let meta = SnippetMeta::read(source)?;
+        let content = fs::read_to_string(source)?;
+
+        if !code.is_empty() && !code.ends_with("\n\n") {
+            code.push('\n');
+        }
+        code.push_str(&format!("{} --- {} ---\n", comment, meta.title));
+        if is_markdown(source) {
+            for block in code_blocks(&content) {
+                code.push_str(&block.code);
+            }
+        } else {
+            code.push_str(&content);
+            if !content.ends_with('\n') {
+                code.push('\n');
+            }
+        }
+
+        let name = source
+            .file_name()
+            .map(|name| name.to_string_lossy().into_owned())
+            .unwrap_or_default();
+        note.push_str(&format!("- {}\n", name));
+    }
+
+    let tags: Vec<String> = new_tags.iter().map(|tag| tag.to_string()).collect();
+    let path = snippet_dir.join(snippet_filename(timestamp, new_language, &tags));
+    if path.exists() {
+        return Err(SnippetVaultError::AlreadyExists(path));
+    }
+
+    let contents = format!(
+        "{}{}",
+        render_new_snippet(new_language, &tags, timestamp, &code),
+        note
+    );
+    executor.apply(Mutation::Write {
+        path: path.clone(),
+        contents,
+    })?;
+    Ok(path)
+}
@@ -0,0 +1,64 @@
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use crate::error::SnippetVaultError;
+use crate::snippet::{split_frontmatter, Frontmatter};
+use crate::vault::{scan_snippets, Executor, Mutation};
+
+/// What happened to a single snippet during migration.
+enum Outcome {
+    Migrate(Mutation),
+    Skipped,
+    Failed(String),
+}
+
+/// Outcome of a v1 → v2 vault migration.
+#[derive(Debug, Default)]
+pub struct MigrationReport {
+    pub migrated: usize,
+    pub skipped: usize,
+    pub errors: Vec<(PathBuf, String)>,
+}
+
+/// Upgrades every v1 snippet (no frontmatter) under `snippet_dir` to v2 by prepending
+/// TOML frontmatter synthesised from the filename. Snippets that already carry
+/// frontmatter are skipped, so running the migration twice is a no-op.
+/// Files are parsed in parallel; the rewrites are then applied in path order
+/// through `executor`, which only prints them in dry-run mode.
+pub fn migrate_v1_to_v2(
+    snippet_dir: &Path,
+    executor: &Executor,
+) -> Result<MigrationReport, SnippetVaultError> {
+    let mut report = MigrationReport::default();
+
+    for (path, outcome) in scan_snippets(snippet_dir, plan_file)? {
+        match outcome {
+            Outcome::Migrate(mutation) => match executor.apply(mutation) {
+                Ok(()) => report.migrated += 1,
+                Err(err) => report.errors.push((path, err.to_string())),
+            },
+            Outcome::Skipped => report.skipped += 1,
+            Outcome::Failed(err) => report.errors.push((path, err)),
+        }
+    }
+
+    Ok(report)
+}
+
+/// Plans prepending synthesised frontmatter to one snippet unless it already has some.
+fn plan_file(path: &Path) -> Outcome {
+    let content = match fs::read_to_string(path) {
+        Ok(content) => content,
+        Err(err) => return Outcome::Failed(err.to_string()),
+    };
+
+    if split_frontmatter(&content).0.is_some() {
+        return Outcome::Skipped;
+    }
+
+    let frontmatter = Frontmatter::from_v1(path, &content);
+    Outcome::Migrate(Mutation::Write {
+        path: path.to_path_buf(),
+        contents: format!("{}{}", frontmatter.render(), content),
+    })
+}
@@ -0,0 +1,224 @@
+use chrono::NaiveDate;
+use sha2::{Digest, Sha256};
+use std::path::Path;
+
+use crate::cache::MetadataCache;
+use crate::expiry;
+use crate::snippet::{self, SnippetMeta};
+use crate::usage::USES_FIELD;
+
+/// Values accepted by `motd_prefer`.
+pub const PREFERENCES: &[&str] = &["pinned", "rare"];
+
+/// Which snippets the snippet of the day leans towards.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum Preference {
+    /// Every snippet is equally likely.
+    #[default]
+    Even,
+    /// Snippets with `pinned = true` are four times as likely.
+    Pinned,
+    /// Snippets with few `uses` are likelier: never used ones eight times as
+    /// likely as those used three times or more.
+    Rare,
+}
+
+impl Preference {
+    /// Parses one of [`PREFERENCES`].
+    pub fn from_name(name: &str) -> Option<Self> {
+        match name {
+            "pinned" => Some(Preference::Pinned),
+            "rare" => Some(Preference::Rare),
+            _ => None,
+        }
+    }
+
+    /// How many draws `snippet` gets.
+    fn weight(self, snippet: &SnippetMeta) -> u64 {
+        match self {
+            Preference::Even => 1,
+            Preference::Pinned => match snippet.extra.get("pinned").map(String::as_str) {
+                Some("true") => 4,
+                _ => 1,
+            },
+            Preference::Rare => {
+                let uses = snippet
+                    .extra
+                    .get(USES_FIELD)
+                    .and_then(|uses| uses.parse::<u32>().ok())
+                    .unwrap_or(0);
+                8 >> uses.min(3)
+            }
+        }
+    }
+}
+
+/// The snippet of the day for `date`: drawn from the metadata cache's
+/// snippets under `snippet_dir` (archived and binary ones left out) by a hash
+/// of the date, so it stays the same all day. Only the cache is read, never
+/// the vault; a cold cache, or a pick whose file is gone, gives `None`.
+pub fn snippet_of_the_day(
+    snippet_dir: &Path,
+    date: NaiveDate,
+    preference: Preference,
+) -> Option<SnippetMeta> {
+    let cache = MetadataCache::load();
+    let picked = pick(cache.snippets(), snippet_dir, date, preference)?;
+    picked.path.is_file().then(|| picked.clone())
+}
+
+/// Draws the snippet of the day for `date` from `snippets`, leaving out those
+/// outside `snippet_dir`, binary ones, and archived ones.
+fn pick<'a>(
+    snippets: impl Iterator<Item = &'a SnippetMeta>,
+    snippet_dir: &Path,
+    date: NaiveDate,
+    preference: Preference,
+) -> Option<&'a SnippetMeta> {
+    let mut candidates: Vec<&SnippetMeta> = snippets
+        .filter(|snippet| snippet.path.starts_with(snippet_dir))
+        .filter(|snippet| !snippet.stats.binary && !expiry::is_archived(snippet))
+        .collect();
+    candidates.sort_by(|a, b| a.path.cmp(&b.path));
+
+    let total: u64 = candidates
+        .iter()
+        .map(|snippet| preference.weight(snippet))
+        .sum();
+    if total == 0 {
+        return None;
+    }
+    let digest = Sha256::digest(date.format("%Y-%m-%d").to_string().as_bytes());
+    let seed = u64::from_be_bytes(digest[..8].try_into().expect("digest has 32 bytes"));
+    let mut draw = seed % total;
+    candidates.into_iter().find(|snippet| {
+        let weight = preference.weight(snippet);
+        if draw < weight {
+            return true;
+        }
+        draw -= weight;
+        false
+    })
+}
+
+/// One line for a shell prompt: the snippet's title and, after a dash, the
+/// first non-blank line of its code (the first code block for markdown).
+pub fn headline(snippet: &SnippetMeta) -> String {
+    // Quietly, since this runs in shell prompts.
+    let content = snippet::read_limited(&snippet.path)
+        .ok()
+        .flatten()
+        .filter(|bytes| !snippet::is_binary(bytes))
+        .and_then(|bytes| String::from_utf8(bytes).ok());
+    let code_line = content.and_then(|content| {
+        let code = if snippet::is_markdown(&snippet.path) {
+            snippet::code_blocks(&content).into_iter().next()?.code
+        } else {
+            snippet::split_frontmatter(&content).1.to_string()
+        };
+        code.lines()
+            .map(str::trim)
+            .find(|line| !line.is_empty())
+            .map(str::to_string)
+    });
+    match code_line {
+        Some(line) => format!("{} — {}", snippet.title, line),
+        None => snippet.title.clone(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::snippet::SnippetStats;
+    use std::collections::BTreeMap;
+    use std::path::PathBuf;
+
+    fn snippet(path: &str, extra: &[(&str, &str)]) -> SnippetMeta {
+        SnippetMeta {
+            path: PathBuf::from(path),
+            title: path.to_string(),
+            language: "bash".to_string(),
+            tags: Vec::new(),
+            created: None,
+            summary: None,
+            extra: extra
+                .iter()
+                .map(|(key, value)| (key.to_string(), value.to_string()))
+                .collect::<BTreeMap<_, _>>(),
+            stats: SnippetStats::default(),
+        }
+    }
+
+    fn days(count: u32) -> impl Iterator<Item = NaiveDate> {
+        let first = NaiveDate::from_ymd_opt(2024, 1, 1).unwrap();
+        (0..count).map(move |offset| first + chrono::Days::new(offset.into()))
+    }
+
+    #[test]
+    fn preferences_weigh_pinned_and_rarely_used_snippets() {
+        let plain = snippet("/vault/plain.md", &[]);
+        let pinned = snippet("/vault/pinned.md", &[("pinned", "true")]);
+        assert_eq!(Preference::Even.weight(&pinned), 1);
+        assert_eq!(Preference::Pinned.weight(&plain), 1);
+        assert_eq!(Preference::Pinned.weight(&pinned), 4);
+
+        let weights: Vec<u64> = ["0", "1", "2", "3", "12", "many"]
+            .iter()
+            .map(|uses| Preference::Rare.weight(&snippet("/vault/a.md", &[(USES_FIELD, uses)])))
+            .collect();
+        assert_eq!(weights, [8, 4, 2, 1, 1, 8]);
+        assert_eq!(Preference::Rare.weight(&plain), 8);
+    }
+
+    #[test]
+    fn the_pick_holds_for_a_day_and_skips_archived_snippets() {
+        let vault = Path::new("/vault");
+        let snippets = [
+            snippet("/vault/a.md", &[]),
+            snippet("/vault/b.md", &[]),
+            snippet("/vault/c.md", &[]),
+            snippet("/vault/flagged.md", &[("archived", "true")]),
+            snippet("/elsewhere/d.md", &[]),
+        ];
+        let mut picked = Vec::new();
+        for date in days(60) {
+            let first = pick(snippets.iter(), vault, date, Preference::Even).unwrap();
+            let again = pick(snippets.iter().rev(), vault, date, Preference::Even).unwrap();
+            assert_eq!(first.path, again.path);
+            picked.push(first.path.clone());
+        }
+        picked.sort();
+        picked.dedup();
+        let expected: Vec<PathBuf> = ["/vault/a.md", "/vault/b.md", "/vault/c.md"]
+            .iter()
+            .map(PathBuf::from)
+            .collect();
+        assert_eq!(picked, expected);
+
+        let archived_only = [snippet("/vault/flagged.md", &[("archived", "true")])];
+        assert!(pick(
+            archived_only.iter(),
+            vault,
+            days(1).next().unwrap(),
+            Preference::Even
+        )
+        .is_none());
+    }
+
+    #[test]
+    fn pinned_snippets_come_up_more_often() {
+        let vault = Path::new("/vault");
+        let snippets = [
+            snippet("/vault/plain.md", &[]),
+            snippet("/vault/pinned.md", &[("pinned", "true")]),
+        ];
+        let pinned_days = days(365)
+            .filter(|date| {
+                let picked = pick(snippets.iter(), vault, *date, Preference::Pinned).unwrap();
+                picked.path.ends_with("pinned.md")
+            })
+            .count();
+        assert!(pinned_days > 365 * 2 / 3, "pinned on {} days", pinned_days);
+    }
+}
@@ -0,0 +1,285 @@
+use serde_json::{json, Value};
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use crate::detect;
+use crate::error::SnippetVaultError;
+use crate::snippet::{is_markdown, split_frontmatter, SnippetMeta};
+use crate::vault::{Executor, Mutation};
+
+/// Longest text Notion accepts in a single rich-text object.
+const MAX_TEXT_LENGTH: usize = 2000;
+
+/// Common language names that Notion spells differently.
+const LANGUAGE_ALIASES: &[(&str, &str)] = &[
+    ("sh", "shell"),
+    ("zsh", "shell"),
+    ("fish", "shell"),
+    ("js", "javascript"),
+    ("jsx", "javascript"),
+    ("ts", "typescript"),
+    ("tsx", "typescript"),
+    ("py", "python"),
+    ("rs", "rust"),
+    ("rb", "ruby"),
+    ("cpp", "c++"),
+    ("cxx", "c++"),
+    ("csharp", "c#"),
+    ("cs", "c#"),
+    ("fsharp", "f#"),
+    ("objc", "objective-c"),
+    ("objectivec", "objective-c"),
+    ("dockerfile", "docker"),
+    ("yml", "yaml"),
+    ("md", "markdown"),
+    ("tex", "latex"),
+    ("make", "makefile"),
+    ("ps1", "powershell"),
+    ("pwsh", "powershell"),
+    ("vb", "visual basic"),
+    ("vbnet", "vb.net"),
+    ("wasm", "webassembly"),
+    ("proto", "protobuf"),
+    ("kt", "kotlin"),
+    ("golang", "go"),
+    ("text", "plain text"),
+    ("txt", "plain text"),
+    ("plaintext", "plain text"),
+];
+
+/// Languages Notion's `code` block accepts.
+const NOTION_LANGUAGES: &[&str] = &[
+    "abap",
+    "arduino",
+    "bash",
+    "basic",
+    "c",
+    "clojure",
+    "coffeescript",
+    "c++",
+    "c#",
+    "css",
+    "dart",
+    "diff",
+    "docker",
+    "elixir",
+    "elm",
+    "erlang",
+    "flow",
+    "fortran",
+    "f#",
+    "gherkin",
+    "glsl",
+    "go",
+    "graphql",
+    "groovy",
+    "haskell",
+    "html",
+    "java",
+    "javascript",
+    "json",
+    "julia",
+    "kotlin",
+    "latex",
+    "less",
+    "lisp",
+    "livescript",
+    "lua",
+    "makefile",
+    "markdown",
+    "markup",
+    "matlab",
+    "mermaid",
+    "nix",
+    "objective-c",
+    "ocaml",
+    "pascal",
+    "perl",
+    "php",
+    "plain text",
+    "powershell",
+    "prolog",
+    "protobuf",
+    "python",
+    "r",
+    "reason",
+    "ruby",
+    "rust",
+    "sass",
+    "scala",
+    "scheme",
+    "scss",
+    "shell",
+    "solidity",
+    "sql",
+    "swift",
+    "typescript",
+    "vb.net",
+    "verilog",
+    "vhdl",
+    "visual basic",
+    "webassembly",
+    "xml",
+    "yaml",
+];
+
+/// Maps a snippet or fence language to Notion's name for it; unknown
+/// languages become `plain text`.
+pub fn notion_language(language: &str) -> &'static str {
+    let language = language.trim().to_lowercase();
+    LANGUAGE_ALIASES
+        .iter()
+        .find(|(alias, _)| *alias == language)
+        .map(|(_, notion)| *notion)
+        .or_else(|| {
+            NOTION_LANGUAGES
+                .iter()
+                .find(|notion| **notion == language)
+                .copied()
+        })
+        .unwrap_or("plain text")
+}
+
+/// Renders the snippet at `path` as a JSON array of Notion blocks, ready to
+/// send as a page's `children`: a `heading_1` with the title, a `paragraph`
+/// per line of prose (`heading_2`/`heading_3` for `##`/`###` lines), and a
+/// `code` block per fenced code block. Plain code files become one code block.
+pub fn export_notion_json(path: &Path) -> Result<String, SnippetVaultError> {
+    let meta = SnippetMeta::read(path)?;
+    let content = fs::read_to_string(path)?;
+
+    let mut blocks = vec![text_block("heading_1", &meta.title)];
+    if is_markdown(path) {
+        let mut fence: Option<(String, String)> = None;
+        for line in split_frontmatter(&content).1.lines() {
+            let trimmed = line.trim();
+            if let Some(info) = trimmed.strip_prefix("```") {
+                match fence.take() {
+                    Some((language, code)) => blocks.push(code_block(
+                        &detect::block_language(&language, &code, &meta.language),
+                        &code,
+                    )),
+                    None => {
+                        let language = info.split_whitespace().next().unwrap_or_default();
+                        fence = Some((language.to_string(), String::new()));
+                    }
+                }
+                continue;
+            }
+            if let Some((_, code)) = fence.as_mut() {
+                code.push_str(line);
+                code.push('\n');
+                continue;
+            }
+
+            if trimmed.is_empty() || trimmed.starts_with("# Title:") || trimmed == "# ---" {
+                continue;
+            }
+            let block = if let Some(heading) = trimmed.strip_prefix("### ") {
+                text_block("heading_3", heading)
+            } else if let Some(heading) = trimmed.strip_prefix("## ") {
+                text_block("heading_2", heading)
+            } else {
+                text_block("paragraph", trimmed)
+            };
+            blocks.push(block);
+        }
+        if let Some((language, code)) = fence {
+            blocks.push(code_block(
+                &detect::block_language(&language, &code, &meta.language),
+                &code,
+            ));
+        }
+    } else {
+        blocks.push(code_block(&meta.language, &content));
+    }
+
+    Ok(serde_json::to_string_pretty(&blocks).expect("blocks are always serializable"))
+}
+
+/// Writes [`export_notion_json`] for every snippet in `snippets` to
+/// `output_dir/<file stem>.json` and returns the files written.
+pub fn export_notion_all(
+    snippets: &[PathBuf],
+    output_dir: &Path,
+    executor: &Executor,
+) -> Result<Vec<PathBuf>, SnippetVaultError> {
+    if !output_dir.exists() {
+        executor.apply(Mutation::CreateDir(output_dir.to_path_buf()))?;
+    }
+
+    let mut written = Vec::with_capacity(snippets.len());
+    for path in snippets {
+        let stem = path.file_stem().unwrap_or_default().to_string_lossy();
+        let target = output_dir.join(format!("{}.json", stem));
+        executor.apply(Mutation::Write {
+            path: target.clone(),
+            contents: export_notion_json(path)?,
+        })?;
+        written.push(target);
+    }
+    Ok(written)
+}
+
+fn text_block(kind: &str, text: &str) -> Value {
+    json!({
+        "object": "block",
+        "type": kind,
+        kind: { "rich_text": rich_text(text) },
+    })
+}
+
+fn code_block(language: &str, code: &str) -> Value {
+    json!({
+        "object": "block",
+        "type": "code",
+        "code": {
+            "rich_text": rich_text(code.trim_end_matches('\n')),
+            "language": notion_language(language),
+        },
+    })
+}
+
+/// Splits `text` into rich-text objects no longer than Notion's limit.
+fn rich_text(text: &str) -> Vec<Value> {
+    let chars: Vec<char> = text.chars().collect();
+    chars
+        .chunks(MAX_TEXT_LENGTH)
+        .map(|chunk| {
+            json!({
+                "type": "text",
+                "text": { "content": chunk.iter().collect::<String>() },
+            })
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn code_languages(json: &str) -> Vec<String> {
+        serde_json::from_str::<Vec<Value>>(json)
+            .unwrap()
+            .iter()
+            .filter(|block| block["type"] == "code")
+            .map(|block| block["code"]["language"].as_str().unwrap().to_string())
+            .collect()
+    }
+
+    #[test]
+    fn bare_fence_takes_the_snippet_language() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("deploy.md");
+        fs::write(
+            &path,
+            "+++\ntitle = \"Deploy\"\nlanguage = \"sh\"\ntags = []\n+++\n\n\
+             ```python\nprint(1)\n```\n\n```\ndeploy now\n```\n",
+        )
+        .unwrap();
+        assert_eq!(
+            code_languages(&export_notion_json(&path).unwrap()),
+            ["python", "shell"]
+        );
+    }
+}
@@ -0,0 +1,72 @@
+use std::path::{Component, Path};
+
+use crate::error::SnippetVaultError;
+use crate::platform;
+use crate::uri::percent_encode;
+
+/// Name of the Obsidian vault at `snippet_dir`: the configured `obsidian_vault`,
+/// or else the directory's own name, which is what Obsidian calls it by default.
+pub fn vault_name(configured: Option<&str>, snippet_dir: &Path) -> Option<String> {
+    configured.map(str::to_string).or_else(|| {
+        snippet_dir
+            .file_name()
+            .map(|name| name.to_string_lossy().into_owned())
+    })
+}
+
+/// The `obsidian://open` URI of the file at `relative` (a path inside the
+/// vault) in the vault named `vault`. Path components are joined with `/`
+/// whatever the platform.
+pub fn open_uri(vault: &str, relative: &Path) -> String {
+    let file = relative
+        .components()
+        .filter_map(|component| match component {
+            Component::Normal(part) => Some(part.to_string_lossy()),
+            _ => None,
+        })
+        .collect::<Vec<_>>()
+        .join("/");
+    format!(
+        "obsidian://open?vault={}&file={}",
+        percent_encode(vault),
+        percent_encode(&file)
+    )
+}
+
+/// Opens `path` in Obsidian through its URI handler.
+/// - `vault`: Obsidian's name for the vault at `snippet_dir`.
+pub fn open(snippet_dir: &Path, vault: &str, path: &Path) -> Result<(), SnippetVaultError> {
+    let relative = path.strip_prefix(snippet_dir).map_err(|_| {
+        SnippetVaultError::Command(format!(
+            "{} is outside the vault {}",
+            path.display(),
+            snippet_dir.display()
+        ))
+    })?;
+    platform::open_with_system(&open_uri(vault, relative))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn uris_encode_spaces_unicode_and_separators() {
+        let relative = Path::new("git tricks").join("café & co.md");
+        assert_eq!(
+            open_uri("My Vault", &relative),
+            "obsidian://open?vault=My%20Vault&file=git%20tricks%2Fcaf%C3%A9%20%26%20co.md"
+        );
+        assert_eq!(
+            open_uri("整理", Path::new("提交.md")),
+            "obsidian://open?vault=%E6%95%B4%E7%90%86&file=%E6%8F%90%E4%BA%A4.md"
+        );
+    }
+
+    #[test]
+    fn vault_names_default_to_the_directory() {
+        let dir = Path::new("/home/me/My Vault");
+        assert_eq!(vault_name(None, dir).as_deref(), Some("My Vault"));
+        assert_eq!(vault_name(Some("Work"), dir).as_deref(), Some("Work"));
+    }
+}
@@ -0,0 +1,110 @@
+use std::env;
+use std::fmt::{self, Write as _};
+use std::io::{self, IsTerminal, Write};
+use std::process::Command as ProcessCommand;
+
+use crate::config::Config;
+use crate::editor;
+use crate::logging;
+use crate::platform;
+use crate::width;
+
+/// Pager used when `$PAGER` is unset.
+pub const DEFAULT_PAGER: &str = "less -RFX";
+
+/// Output sink for commands that can print more than a screenful.
+/// Lines are buffered and written when the sink is dropped: through the pager
+/// when stdout is a terminal and the output is taller than it, otherwise
+/// straight to stdout. Redirected output is never paged.
+pub struct Pager {
+    buffer: String,
+    enabled: bool,
+}
+
+impl Pager {
+    /// A sink that pages unless `--no-pager` was passed or stdout is not a terminal.
+    pub fn new(config: &Config) -> Self {
+        Pager {
+            buffer: String::new(),
+            enabled: !config.no_pager && io::stdout().is_terminal(),
+        }
+    }
+
+    /// Appends one line.
+    pub fn line(&mut self, line: impl fmt::Display) {
+        // Writing to a String cannot fail.
+        let _ = writeln!(self.buffer, "{}", line);
+    }
+
+    /// Appends `text` as-is, for output that already ends in a newline.
+    pub fn text(&mut self, text: &str) {
+        self.buffer.push_str(text);
+    }
+
+    fn flush(&mut self) {
+        let output = std::mem::take(&mut self.buffer);
+        if output.is_empty() {
+            return;
+        }
+        if self.enabled && exceeds_screen(&output) {
+            if let Some(command) = pager_command() {
+                if page(&command, &output) {
+                    return;
+                }
+            }
+        }
+        // A closed pipe (`| head`) only means the reader has seen enough.
+        let mut stdout = io::stdout().lock();
+        let _ = stdout
+            .write_all(output.as_bytes())
+            .and_then(|_| stdout.flush());
+    }
+}
+
+impl Drop for Pager {
+    fn drop(&mut self) {
+        self.flush();
+    }
+}
+
+/// `$PAGER`, or [`DEFAULT_PAGER`] when unset. An empty `$PAGER` disables paging.
+fn pager_command() -> Option<Vec<String>> {
+    let command = env::var("PAGER").unwrap_or_else(|_| DEFAULT_PAGER.to_string());
+    let command = editor::split_command(&command);
+    if command.is_empty() {
+        None
+    } else {
+        Some(command)
+    }
+}
+
+/// Whether `output` needs more rows than the terminal has, counting wrapped lines.
+fn exceeds_screen(output: &str) -> bool {
+    let Some(height) = platform::terminal_height() else {
+        return false;
+    };
+    let width = platform::terminal_width()
+        .unwrap_or(width::DEFAULT_WIDTH)
+        .max(1);
+    let rows: usize = output
+        .lines()
+        .map(|line| width::display_width(line).div_ceil(width).max(1))
+        .sum();
+    rows >= height
+}
+
+/// Feeds `output` to the pager and waits for it to exit. Returns false when the
+/// pager could not be started, so the caller can print the output instead.
+fn page(command: &[String], output: &str) -> bool {
+    // Ctrl-C is for the pager; ignore it here so the write side is not killed
+    // mid-write while the pager still owns the terminal.
+    let _ = ctrlc::set_handler(|| {});
+
+    let mut process = ProcessCommand::new(&command[0]);
+    process.args(&command[1..]);
+    if env::var_os("LESS").is_none() {
+        // Keep colors and short output on screen when $PAGER is a bare `less`.
+        process.env("LESS", "FRX");
+    }
+    logging::run_with_stdin(&mut process, output).is_ok()
+}
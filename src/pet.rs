@@ -0,0 +1,184 @@
+use chrono::Local;
+use serde::{Deserialize, Serialize};
+use std::collections::HashSet;
+use std::fs;
+use std::path::Path;
+
+use crate::error::SnippetVaultError;
+use crate::import;
+use crate::index;
+use crate::snippet::{self, Frontmatter, SnippetMeta};
+use crate::style::Styled;
+use crate::vault::{self, Executor, Mutation};
+
+/// Languages exported to pet, whose snippets are all shell commands.
+pub const SHELL_LANGUAGES: &[&str] = &["bash", "sh", "shell", "zsh"];
+
+/// Frontmatter field holding pet's `output`, so it survives a round trip.
+const OUTPUT_FIELD: &str = "output";
+
+/// A pet `snippet.toml`: a list of `[[snippets]]` tables.
+#[derive(Debug, Default, Serialize, Deserialize)]
+#[serde(default)]
+struct PetFile {
+    snippets: Vec<PetSnippet>,
+}
+
+/// One pet snippet. The command is kept verbatim, including newlines and
+/// `<param>` placeholders.
+#[derive(Debug, Default, Serialize, Deserialize)]
+#[serde(default)]
+struct PetSnippet {
+    description: String,
+    command: String,
+    tag: Vec<String>,
+    output: String,
+}
+
+/// Creates a `bash` snippet for each entry of the pet `snippet.toml` at
+/// `pet_path` and returns `(imported, skipped)`. The description becomes the
+/// title and the command the code block; entries whose title matches an
+/// existing snippet (case-insensitively) are skipped, so importing again only
+/// adds new entries.
+/// - `extensions`: File extensions that count as snippets.
+/// - `use_cache`: Serve unchanged snippets from the metadata cache.
+/// - `timestamp_format`: strftime format of generated filenames.
+pub fn import_pet(
+    pet_path: &Path,
+    snippet_dir: &Path,
+    extensions: &[String],
+    use_cache: bool,
+    timestamp_format: &str,
+    executor: &Executor,
+) -> Result<(usize, usize), SnippetVaultError> {
+    let pet: PetFile =
+        toml::from_str(&fs::read_to_string(pet_path)?).map_err(|err| SnippetVaultError::Parse {
+            path: pet_path.to_path_buf(),
+            message: err.message().to_string(),
+        })?;
+    let mut titles: HashSet<String> = vault::load_snippets(snippet_dir, extensions, use_cache)?
+        .into_iter()
+        .map(|snippet| snippet.title.to_lowercase())
+        .collect();
+
+    let _lock = executor.lock()?;
+    if !snippet_dir.exists() {
+        executor.apply(Mutation::CreateDir(snippet_dir.to_path_buf()))?;
+    }
+    let (mut imported, mut skipped) = (0, 0);
+    for entry in pet.snippets {
+        if entry.command.trim().is_empty() {
+            println!(
+                "{} Skipping '{}': it has no command.",
+                "⚠".warning(),
+                entry.description
+            );
+            skipped += 1;
+            continue;
+        }
+        let title = match entry.description.trim() {
+            "" => entry.command.lines().next().unwrap_or_default().trim(),
+            description => description,
+        }
+        .to_string();
+        if !titles.insert(title.to_lowercase()) {
+            skipped += 1;
+            continue;
+        }
+
+        let time = Local::now().naive_local();
+        let timestamp = import::render_timestamp(&time, timestamp_format);
+        let mut frontmatter = Frontmatter {
+            title,
+            language: "bash".to_string(),
+            tags: entry.tag,
+            created: Some(time),
+            ..Default::default()
+        };
+        if !entry.output.is_empty() {
+            frontmatter
+                .extra
+                .insert(OUTPUT_FIELD.to_string(), toml::Value::String(entry.output));
+        }
+        let path = vault::available_path(snippet_dir, &timestamp, |timestamp| {
+            let tags: Vec<String> = frontmatter
+                .tags
+                .iter()
+                .map(|tag| tag.replace(|c: char| !c.is_alphanumeric() && c != '.', "-"))
+                .collect();
+            snippet::snippet_filename(timestamp, "bash", &tags)
+        });
+        executor.apply(Mutation::Write {
+            path: path.clone(),
+            contents: import::render_snippet(&frontmatter, &timestamp, &entry.command),
+        })?;
+        if !executor.is_dry_run() {
+            index::log_access(&path, index::Access::Created);
+        }
+        imported += 1;
+    }
+    Ok((imported, skipped))
+}
+
+/// Renders the shell snippets among `snippets` (see [`SHELL_LANGUAGES`]) as a
+/// pet `snippet.toml` and returns it with the number of entries. Each entry's
+/// command is the snippet's code blocks joined; snippets without code are left out.
+pub fn export_pet(snippets: &[SnippetMeta]) -> Result<(String, usize), SnippetVaultError> {
+    let mut pet = PetFile::default();
+    for meta in snippets
+        .iter()
+        .filter(|meta| SHELL_LANGUAGES.contains(&meta.language.to_lowercase().as_str()))
+    {
+        let Some(content) = snippet::read_text(&meta.path)? else {
+            continue;
+        };
+        let command = if snippet::is_markdown(&meta.path) {
+            snippet::code_blocks(&content)
+                .into_iter()
+                .map(|block| block.code)
+                .collect::<Vec<_>>()
+                .join("\n")
+        } else {
+            content
+        };
+        let command = command.trim_end();
+        if command.trim().is_empty() {
+            continue;
+        }
+        pet.snippets.push(PetSnippet {
+            description: meta.title.clone(),
+            command: command.to_string(),
+            tag: meta.tags.clone(),
+            output: meta.extra.get(OUTPUT_FIELD).cloned().unwrap_or_default(),
+        });
+    }
+
+    let count = pet.snippets.len();
+    let toml = toml::to_string(&pet)
+        .map_err(|err| SnippetVaultError::Command(format!("cannot render pet TOML: {}", err)))?;
+    Ok((toml, count))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn binary_snippets_are_left_out() {
+        crate::testing::isolate_user_dirs();
+        let vault = tempfile::tempdir().unwrap();
+        fs::write(
+            vault.path().join("prune.md"),
+            "+++\ntitle = \"Prune images\"\nlanguage = \"bash\"\ntags = []\n+++\n\n```bash\ndocker image prune\n```\n",
+        )
+        .unwrap();
+        fs::write(vault.path().join("blob.sh"), b"#!/bin/sh\n\0\xff").unwrap();
+        let snippets =
+            vault::load_snippets(vault.path(), &["md".to_string(), "sh".to_string()], false)
+                .unwrap();
+
+        let (toml, count) = export_pet(&snippets).unwrap();
+        assert_eq!(count, 1);
+        assert!(toml.contains("docker image prune"));
+    }
+}
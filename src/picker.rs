@@ -0,0 +1,370 @@
+use regex::Regex;
+use std::path::{Path, PathBuf};
+use std::process::Command as ProcessCommand;
+
+use crate::config::Config;
+use crate::error::SnippetVaultError;
+use crate::logging;
+use crate::platform;
+use crate::prompt;
+use crate::snippet;
+use crate::style;
+
+/// How to do without a picker, for the error shown when there is no terminal.
+pub const PICK_HINT: &str = "name the snippet with --file, --id, or as an argument";
+
+/// fzf `--preview-window` used when neither `fzf_preview_size` nor
+/// `--preview_size` sets one.
+pub const DEFAULT_PREVIEW_SIZE: &str = "down:80%:wrap";
+
+/// fzf `--preview-window` of the compact picker, leaving more room for the
+/// list, unless `fzf_preview_size` or `--preview_size` sets one.
+pub const COMPACT_PREVIEW_SIZE: &str = "down:30%";
+
+/// Checks that `size` is a `--preview-window` value of the form
+/// `POSITION:N%` with an optional `:wrap`, e.g. `right:50%`.
+pub fn validate_preview_size(size: &str) -> Result<(), String> {
+    let pattern = Regex::new(r"^(up|down|left|right):[0-9]+%(:wrap)?$")
+        .expect("the preview size pattern is valid");
+    if pattern.is_match(size) {
+        Ok(())
+    } else {
+        Err(format!(
+            "invalid fzf preview size '{}'; expected up, down, left, or right, \
+             a percentage, and optionally :wrap (e.g. right:50%:wrap)",
+            size
+        ))
+    }
+}
+
+/// Preview of markdown snippets when glow is installed, styled for the
+/// terminal's background.
+fn glow_preview(config: &Config) -> String {
+    format!(
+        "glow --style={} {{}}",
+        style::resolve_glow_style(config.terminal_theme)
+    )
+}
+
+/// Preview of plain code files, and of markdown when glow is not installed.
+fn bat_preview(config: &Config) -> String {
+    format!(
+        "bat --style=numbers --color=always --theme={} --paging=never {{}}",
+        style::resolve_bat_theme(config.terminal_theme)
+    )
+}
+
+/// Preview of plain code files when bat is not installed either.
+pub const CAT_PREVIEW: &str = "cat {}";
+
+/// Shell command that previews the file at `path`, with `{}` standing for the
+/// file: `preview_command` from the config when set, otherwise glow for
+/// markdown, then bat, then `cat` (markdown falls back to
+/// `--snippet_render_terminal` rather than `cat`).
+pub fn choose_previewer(path: &Path, config: &Config) -> String {
+    if let Some(command) = &config.preview_command {
+        return command.clone();
+    }
+    let markdown = snippet::is_markdown(path);
+    if markdown && platform::find_in_path("glow").is_some() {
+        glow_preview(config)
+    } else if platform::find_in_path("bat").is_some() {
+        bat_preview(config)
+    } else if markdown {
+        // Inside fzf the preview is rendered at the preview window's width.
+        format!(
+            "CLICOLOR_FORCE=1 \"{}\" ${{FZF_PREVIEW_COLUMNS:+--width \"$FZF_PREVIEW_COLUMNS\"}} --snippet_render_terminal {{}}",
+            current_exe()
+        )
+    } else {
+        CAT_PREVIEW.to_string()
+    }
+}
+
+/// fzf preview for the highlighted file, using [`choose_previewer`] for
+/// markdown snippets and plain code files alike. Files over the parse limit
+/// and binary files are never handed to a previewer; a one-line notice is
+/// shown instead.
+pub fn preview_command(config: &Config) -> String {
+    // fzf only names the file at preview time, so both choices are made up front.
+    let render = format!(
+        "case {{}} in *.md) {} ;; *) {} ;; esac",
+        choose_previewer(Path::new("snippet.md"), config),
+        choose_previewer(Path::new("snippet"), config)
+    );
+    // `grep -I` treats files with NUL bytes as never matching, even the empty pattern.
+    format!(
+        "if [ \"$(wc -c < {{}})\" -gt {} ]; then echo \"file too large to preview\"; \
+         elif [ -s {{}} ] && ! grep -qI '' {{}}; then echo \"binary file\"; \
+         else {}; fi",
+        snippet::parse_limit(),
+        render
+    )
+}
+
+/// Path of the running binary, for fzf commands that call back into it.
+fn current_exe() -> String {
+    std::env::current_exe()
+        .map(|exe| exe.display().to_string())
+        .unwrap_or_else(|_| "snippets_vault".to_string())
+}
+
+/// Preview that prints the file name and its one-line summary above the usual preview.
+pub fn preview_with_summary(config: &Config) -> String {
+    let exe = current_exe();
+    format!(
+        "echo {{}}; \"{}\" --snippet_summary {{}} 2>/dev/null; echo; {}",
+        exe,
+        preview_command(config)
+    )
+}
+
+/// Flags added to every fzf invocation besides the functional ones: the preview
+/// window size, the default key bindings (ctrl-y copies the highlighted path,
+/// ctrl-o opens it in the editor and returns to the picker, ctrl-x makes a new
+/// snippet from lines selected in it), then `fzf_args`
+/// from the config and `--fzf-arg`, so a user's own `--bind` or
+/// `--preview-window` wins. Colors are left to `$FZF_DEFAULT_OPTS`.
+/// - `field`: fzf placeholder holding the highlighted path, e.g. `{}` or `{2}`.
+pub fn fzf_args(config: &Config, field: &str) -> Vec<String> {
+    fzf_args_with_preview(config, field, config.fzf_preview_size())
+}
+
+/// [`fzf_args`] with `preview_size` as the preview window size.
+fn fzf_args_with_preview(config: &Config, field: &str, preview_size: &str) -> Vec<String> {
+    let exe = current_exe();
+    let mut args = vec![
+        "--preview-window".to_string(),
+        preview_size.to_string(),
+        "--bind".to_string(),
+        format!("ctrl-y:execute-silent(\"{}\" --copy_path {})", exe, field),
+        "--bind".to_string(),
+        format!("ctrl-o:execute(\"{}\" --open_snippet {})", exe, field),
+        "--bind".to_string(),
+        format!("ctrl-x:execute(\"{}\" --extract_selection {})", exe, field),
+    ];
+    args.extend(config.fzf_args.iter().cloned());
+    args
+}
+
+/// [`fzf_args`] quoted for splicing into a shell script.
+pub fn fzf_shell_args(config: &Config, field: &str) -> String {
+    fzf_args(config, field)
+        .iter()
+        .map(|arg| shell_quote(arg))
+        .collect::<Vec<_>>()
+        .join(" ")
+}
+
+/// `arg` single-quoted for a shell command line, such as an fzf preview.
+pub fn shell_quote(arg: &str) -> String {
+    format!("'{}'", arg.replace('\'', "'\\''"))
+}
+
+/// Lets the user choose one of `entries` in fzf and returns the chosen path, or
+/// `None` when the picker was dismissed or nothing matched.
+/// - `entries`: `(label, path)` pairs; only the label is shown and searched.
+/// - `query`: Initial fzf query.
+pub fn pick(
+    config: &Config,
+    entries: &[(String, PathBuf)],
+    query: Option<&str>,
+) -> Result<Option<PathBuf>, SnippetVaultError> {
+    Ok(run_fzf(config, entries, query, None, false, false)?
+        .into_iter()
+        .next())
+}
+
+/// Like [`pick`], but shows `header` above the entries instead of taking a query.
+pub fn pick_with_header(
+    config: &Config,
+    entries: &[(String, PathBuf)],
+    header: &str,
+) -> Result<Option<PathBuf>, SnippetVaultError> {
+    Ok(run_fzf(config, entries, None, Some(header), false, false)?
+        .into_iter()
+        .next())
+}
+
+/// Like [`pick`], but lets the user mark several entries (tab in fzf) and
+/// returns all of them in order; empty when the picker was dismissed.
+pub fn pick_many(
+    config: &Config,
+    entries: &[(String, PathBuf)],
+    query: Option<&str>,
+) -> Result<Vec<PathBuf>, SnippetVaultError> {
+    run_fzf(config, entries, query, None, true, false)
+}
+
+/// Like [`pick`] (or [`pick_many`] with `multi`), but keeps `entries` in the
+/// order given while filtering instead of letting fzf rank them by match score.
+pub fn pick_in_order(
+    config: &Config,
+    entries: &[(String, PathBuf)],
+    query: Option<&str>,
+    multi: bool,
+) -> Result<Vec<PathBuf>, SnippetVaultError> {
+    run_fzf(config, entries, query, None, multi, true)
+}
+
+/// Lets the user mark any of `labels`, plain lines of text rather than
+/// snippets, and returns the indices of the marked ones in order; empty when
+/// the picker was dismissed.
+/// - `header`: Shown above the entries.
+pub fn pick_labels(
+    config: &Config,
+    labels: &[String],
+    header: &str,
+) -> Result<Vec<usize>, SnippetVaultError> {
+    prompt::require_interactive("pick with fzf", PICK_HINT)?;
+    let input: String = labels
+        .iter()
+        .enumerate()
+        .map(|(index, label)| format!("{}\t{}\n", index, label.replace(['\t', '\n'], " ")))
+        .collect();
+
+    let mut command = ProcessCommand::new("fzf");
+    command
+        .args(["--delimiter", "\t", "--with-nth", "2", "--multi"])
+        .arg("--header")
+        .arg(header)
+        .args(&config.fzf_args);
+
+    let output = logging::run_with_input(&mut command, &input)
+        .map_err(|err| SnippetVaultError::Command(format!("fzf: {}", err)))?;
+    if !output.status.success() {
+        return Ok(Vec::new());
+    }
+    Ok(String::from_utf8_lossy(&output.stdout)
+        .lines()
+        .filter_map(|line| line.split('\t').next()?.parse().ok())
+        .collect())
+}
+
+/// Lets the user choose among snippets shown as one-line summaries and
+/// returns the paths of the chosen ones (several with `multi`); empty when
+/// the picker was dismissed. Paths rather than ids come back, since snippets
+/// created in the same second share an id.
+/// - `entries`: `(id, line, path)`; only `line` is shown and searched, `path`
+///   is previewed.
+/// - `query`: Initial fzf query.
+pub fn pick_compact(
+    config: &Config,
+    entries: &[(String, String, PathBuf)],
+    query: Option<&str>,
+    multi: bool,
+) -> Result<Vec<PathBuf>, SnippetVaultError> {
+    prompt::require_interactive("pick with fzf", PICK_HINT)?;
+    let input: String = entries
+        .iter()
+        .map(|(id, line, path)| {
+            format!(
+                "{}\t{}\t{}\n",
+                id.replace('\t', " "),
+                line.replace('\t', " "),
+                path.display()
+            )
+        })
+        .collect();
+
+    let preview_size = config
+        .fzf_preview_size
+        .as_deref()
+        .unwrap_or(COMPACT_PREVIEW_SIZE);
+    let mut command = ProcessCommand::new("fzf");
+    command
+        .args(["--delimiter", "\t", "--with-nth", "2"])
+        .arg("--preview")
+        .arg(preview_command(config).replace("{}", "{3}"));
+    if let Some(query) = query {
+        command.arg("--query").arg(query);
+    }
+    if multi {
+        command.arg("--multi");
+    }
+    command.args(fzf_args_with_preview(config, "{3}", preview_size));
+
+    let output = logging::run_with_input(&mut command, &input)
+        .map_err(|err| SnippetVaultError::Command(format!("fzf: {}", err)))?;
+    if !output.status.success() {
+        return Ok(Vec::new());
+    }
+    Ok(compact_selection(&String::from_utf8_lossy(&output.stdout)))
+}
+
+/// The paths in the third column of the `id\tline\tpath` lines fzf printed
+/// for [`pick_compact`].
+fn compact_selection(stdout: &str) -> Vec<PathBuf> {
+    stdout
+        .lines()
+        .filter_map(|line| line.splitn(3, '\t').nth(2))
+        .map(PathBuf::from)
+        .collect()
+}
+
+fn run_fzf(
+    config: &Config,
+    entries: &[(String, PathBuf)],
+    query: Option<&str>,
+    header: Option<&str>,
+    multi: bool,
+    keep_order: bool,
+) -> Result<Vec<PathBuf>, SnippetVaultError> {
+    prompt::require_interactive("pick with fzf", PICK_HINT)?;
+    let input: String = entries
+        .iter()
+        .map(|(label, path)| format!("{}\t{}\n", label.replace('\t', " "), path.display()))
+        .collect();
+
+    let mut command = ProcessCommand::new("fzf");
+    command
+        .args(["--delimiter", "\t", "--with-nth", "1"])
+        .arg("--preview")
+        .arg(preview_command(config).replace("{}", "{2}"));
+    if let Some(query) = query {
+        command.arg("--query").arg(query);
+    }
+    if let Some(header) = header {
+        command.arg("--header").arg(header);
+    }
+    if multi {
+        command.arg("--multi");
+    }
+    if keep_order {
+        command.arg("--no-sort");
+    }
+    command.args(fzf_args(config, "{2}"));
+
+    let output = logging::run_with_input(&mut command, &input)
+        .map_err(|err| SnippetVaultError::Command(format!("fzf: {}", err)))?;
+    if !output.status.success() {
+        return Ok(Vec::new());
+    }
+
+    // Paths are read back from the second column, so spaces in them are safe.
+    let selection = String::from_utf8_lossy(&output.stdout);
+    Ok(selection
+        .lines()
+        .filter_map(|line| line.split('\t').nth(1))
+        .map(PathBuf::from)
+        .collect())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn compact_picks_resolve_by_path_not_id() {
+        let stdout = "2026-10-16-192648\trust a\t/vault/snippet_2026-10-16-192648_rust_a.md\n\
+                      2026-10-16-192648\tpython b\t/vault/snippet_2026-10-16-192648_python_b.md\n";
+        assert_eq!(
+            compact_selection(stdout),
+            [
+                PathBuf::from("/vault/snippet_2026-10-16-192648_rust_a.md"),
+                PathBuf::from("/vault/snippet_2026-10-16-192648_python_b.md"),
+            ]
+        );
+        assert_eq!(compact_selection(""), Vec::<PathBuf>::new());
+    }
+}
@@ -0,0 +1,200 @@
+use regex::{Captures, Regex};
+use std::collections::HashMap;
+
+use crate::error::SnippetVaultError;
+use crate::pet::SHELL_LANGUAGES;
+use crate::prompt;
+
+/// `{{name}}` or `{{name:default}}`, recognised in every language.
+const BRACE_PATTERN: &str = r"\{\{\s*([A-Za-z_][A-Za-z0-9_-]*)\s*(?::([^}]*))?\}\}";
+
+/// `<name>` or pet's `<name=default>`, recognised only in shell snippets, where
+/// it cannot be confused with generics or `#include <header>`.
+const ANGLE_PATTERN: &str = r"<([A-Za-z_][A-Za-z0-9_-]*)(?:=([^<>]*))?>";
+
+/// One placeholder of a snippet, with the default it was given, if any.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Placeholder {
+    pub name: String,
+    pub default: Option<String>,
+}
+
+/// The placeholder pattern for `language`. Groups 1 and 2 (or 3 and 4 for
+/// `<name>`) are the name and the default.
+fn pattern(language: &str) -> Regex {
+    let pattern = if SHELL_LANGUAGES.contains(&language.to_lowercase().as_str()) {
+        format!("{}|{}", BRACE_PATTERN, ANGLE_PATTERN)
+    } else {
+        BRACE_PATTERN.to_string()
+    };
+    Regex::new(&pattern).expect("placeholder pattern is valid")
+}
+
+/// The name and default of one placeholder match.
+fn name_and_default<'a>(captures: &'a Captures) -> (&'a str, Option<&'a str>) {
+    let name = captures.get(1).or_else(|| captures.get(3));
+    let default = captures.get(2).or_else(|| captures.get(4));
+    (name.map_or("", |m| m.as_str()), default.map(|m| m.as_str()))
+}
+
+/// The placeholders of `code` in order of first appearance, each name once.
+/// A default given anywhere applies to every occurrence of the name.
+/// - `language`: The snippet's language; `<name>` only counts in shell snippets.
+pub fn find_placeholders(code: &str, language: &str) -> Vec<Placeholder> {
+    let mut found: Vec<Placeholder> = Vec::new();
+    for captures in pattern(language).captures_iter(code) {
+        let (name, default) = name_and_default(&captures);
+        match found.iter_mut().find(|known| known.name == name) {
+            Some(known) => {
+                if known.default.is_none() {
+                    known.default = default.map(str::to_string);
+                }
+            }
+            None => found.push(Placeholder {
+                name: name.to_string(),
+                default: default.map(str::to_string),
+            }),
+        }
+    }
+    found
+}
+
+/// Parses one `--param name=value` argument.
+pub fn parse_param(param: &str) -> Result<(String, String), String> {
+    match param.split_once('=') {
+        Some((name, value)) if !name.trim().is_empty() => {
+            Ok((name.trim().to_string(), value.to_string()))
+        }
+        _ => Err("expected name=value".to_string()),
+    }
+}
+
+/// Fills the placeholders of `code` and returns the result. Values come from
+/// `params` first; the rest are asked for when the terminal is interactive
+/// (an empty answer takes the default). Otherwise defaults are used, and
+/// placeholders with neither a value nor a default are reported together as
+/// [`SnippetVaultError::MissingParams`].
+/// - `language`: The snippet's language; `<name>` only counts in shell snippets.
+pub fn fill_in(
+    code: &str,
+    language: &str,
+    params: &HashMap<String, String>,
+) -> Result<String, SnippetVaultError> {
+    let placeholders = find_placeholders(code, language);
+    if placeholders.is_empty() {
+        return Ok(code.to_string());
+    }
+
+    let interactive = prompt::is_interactive();
+    let mut values = HashMap::new();
+    let mut missing = Vec::new();
+    for placeholder in &placeholders {
+        let value = match params.get(&placeholder.name) {
+            Some(value) => value.clone(),
+            None if interactive => prompt::ask(
+                &format!("{}:", placeholder.name),
+                placeholder.default.as_deref().unwrap_or_default(),
+            )?,
+            None => match &placeholder.default {
+                Some(default) => default.clone(),
+                None => {
+                    missing.push(placeholder.name.clone());
+                    continue;
+                }
+            },
+        };
+        values.insert(placeholder.name.clone(), value);
+    }
+    if !missing.is_empty() {
+        return Err(SnippetVaultError::MissingParams(missing));
+    }
+    Ok(substitute(code, language, &values))
+}
+
+/// Replaces every placeholder of `code` that has an entry in `values`.
+fn substitute(code: &str, language: &str, values: &HashMap<String, String>) -> String {
+    pattern(language)
+        .replace_all(code, |captures: &Captures| {
+            let (name, _) = name_and_default(captures);
+            values
+                .get(name)
+                .cloned()
+                .unwrap_or_else(|| captures[0].to_string())
+        })
+        .into_owned()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn placeholder(name: &str, default: Option<&str>) -> Placeholder {
+        Placeholder {
+            name: name.to_string(),
+            default: default.map(str::to_string),
+        }
+    }
+
+    #[test]
+    fn placeholders_are_found_once_in_order() {
+        let code = "docker logs <container_id> --tail {{ lines }}\n\
+                    kubectl -n {{namespace:default}} logs <container_id=web>";
+        assert_eq!(
+            find_placeholders(code, "bash"),
+            [
+                placeholder("container_id", Some("web")),
+                placeholder("lines", None),
+                placeholder("namespace", Some("default")),
+            ]
+        );
+        // Angle brackets are generics and includes outside shell snippets.
+        let code = "#include <stdio>\nVec<T> items = {{items:vec![]}};";
+        assert_eq!(
+            find_placeholders(code, "rust"),
+            [placeholder("items", Some("vec![]"))]
+        );
+    }
+
+    #[test]
+    fn params_are_name_equals_value() {
+        assert_eq!(
+            parse_param("region=eu-west-1"),
+            Ok(("region".to_string(), "eu-west-1".to_string()))
+        );
+        assert_eq!(
+            parse_param("query=a=b"),
+            Ok(("query".to_string(), "a=b".to_string()))
+        );
+        assert_eq!(
+            parse_param("empty="),
+            Ok(("empty".to_string(), String::new()))
+        );
+        assert!(parse_param("region").is_err());
+        assert!(parse_param("=value").is_err());
+    }
+
+    #[test]
+    fn without_a_terminal_params_and_defaults_fill_in() {
+        prompt::set_non_interactive();
+        let code = "aws s3 ls --region {{region:us-east-1}} s3://<bucket>/{{prefix}}";
+        let params = HashMap::from([
+            ("bucket".to_string(), "logs".to_string()),
+            ("prefix".to_string(), "2024".to_string()),
+        ]);
+        assert_eq!(
+            fill_in(code, "bash", &params).unwrap(),
+            "aws s3 ls --region us-east-1 s3://logs/2024"
+        );
+        assert_eq!(
+            fill_in("ls -la", "bash", &HashMap::new()).unwrap(),
+            "ls -la"
+        );
+
+        match fill_in(code, "bash", &HashMap::new()) {
+            Err(SnippetVaultError::MissingParams(names)) => {
+                assert_eq!(names, ["bucket", "prefix"])
+            }
+            other => panic!("expected missing params, got {:?}", other),
+        }
+    }
+}
@@ -0,0 +1,144 @@
+use std::env;
+use std::path::{Path, PathBuf};
+use std::process::Command as ProcessCommand;
+
+use crate::error::SnippetVaultError;
+use crate::logging;
+
+/// Returns the program (and leading arguments) that opens files, directories,
+/// and URLs with the desktop's default application.
+pub fn opener_command() -> (&'static str, &'static [&'static str]) {
+    if cfg!(target_os = "macos") {
+        ("open", &[])
+    } else if cfg!(target_os = "windows") {
+        ("cmd", &["/C", "start", ""])
+    } else {
+        ("xdg-open", &[])
+    }
+}
+
+/// Returns the shell (and the flag before a command string) that runs
+/// user-configured commands.
+pub fn shell_command() -> (&'static str, &'static [&'static str]) {
+    if cfg!(target_os = "windows") {
+        ("cmd", &["/C"])
+    } else {
+        ("sh", &["-c"])
+    }
+}
+
+/// Opens `target` (a path or URL) with the system default application.
+pub fn open_with_system(target: &str) -> Result<(), SnippetVaultError> {
+    let (program, args) = opener_command();
+    let mut command = ProcessCommand::new(program);
+    command.args(args);
+    #[cfg(windows)]
+    {
+        use std::os::windows::process::CommandExt;
+        command.raw_arg(cmd_quote(target));
+    }
+    #[cfg(not(windows))]
+    command.arg(target);
+    let status = logging::run_quiet(&mut command)?;
+
+    if status.success() {
+        Ok(())
+    } else {
+        Err(SnippetVaultError::Command(format!(
+            "{} {} exited with {}",
+            program, target, status
+        )))
+    }
+}
+
+/// `target` as one argument of `cmd /C start`. Quoted only when it holds
+/// spaces, an argument ends at the first `&`, which cuts a URI with several
+/// query parameters short, so it is always quoted. Paths and encoded URIs
+/// hold no `"`.
+#[cfg_attr(not(windows), allow(dead_code))]
+fn cmd_quote(target: &str) -> String {
+    format!("\"{}\"", target)
+}
+
+/// Opens `dir` in the system file manager (`explorer` on Windows).
+pub fn open_directory(dir: &Path) -> Result<(), SnippetVaultError> {
+    if cfg!(target_os = "windows") {
+        // explorer exits with 1 even after opening the folder, so its status is ignored.
+        logging::run_quiet(ProcessCommand::new("explorer").arg(dir))?;
+        return Ok(());
+    }
+    open_with_system(&dir.display().to_string())
+}
+
+/// Shows `file` selected in the system file manager (`open -R` on macOS,
+/// `explorer /select,` on Windows). Other platforms have no common way to
+/// select a file, so its directory is opened instead.
+pub fn reveal_file(file: &Path) -> Result<(), SnippetVaultError> {
+    if cfg!(target_os = "macos") {
+        let status = logging::run_quiet(ProcessCommand::new("open").arg("-R").arg(file))?;
+        if !status.success() {
+            return Err(SnippetVaultError::Command(format!(
+                "open -R {} exited with {}",
+                file.display(),
+                status
+            )));
+        }
+        Ok(())
+    } else if cfg!(target_os = "windows") {
+        logging::run_quiet(
+            ProcessCommand::new("explorer").arg(format!("/select,{}", file.display())),
+        )?;
+        Ok(())
+    } else {
+        open_directory(file.parent().unwrap_or(Path::new(".")))
+    }
+}
+
+/// Whether a process with `pid` is running.
+pub fn process_alive(pid: u32) -> bool {
+    if cfg!(target_os = "windows") {
+        return ProcessCommand::new("tasklist")
+            .args(["/FI", &format!("PID eq {}", pid), "/NH"])
+            .output()
+            .is_ok_and(|output| {
+                String::from_utf8_lossy(&output.stdout).contains(&pid.to_string())
+            });
+    }
+    let proc_dir = Path::new("/proc");
+    if proc_dir.is_dir() {
+        return proc_dir.join(pid.to_string()).exists();
+    }
+    ProcessCommand::new("kill")
+        .args(["-0", &pid.to_string()])
+        .stderr(std::process::Stdio::null())
+        .status()
+        .is_ok_and(|status| status.success())
+}
+
+/// Looks `program` up on `PATH`, like `which`.
+pub fn find_in_path(program: &str) -> Option<PathBuf> {
+    env::split_paths(&env::var_os("PATH")?)
+        .map(|dir| dir.join(program))
+        .find(|candidate| candidate.is_file())
+}
+
+/// Width of the terminal attached to stdout, if there is one.
+pub fn terminal_width() -> Option<usize> {
+    terminal_size::terminal_size().map(|(terminal_size::Width(width), _)| usize::from(width))
+}
+
+/// Height (in rows) of the terminal attached to stdout, if there is one.
+pub fn terminal_height() -> Option<usize> {
+    terminal_size::terminal_size().map(|(_, terminal_size::Height(height))| usize::from(height))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn start_arguments_keep_every_query_parameter() {
+        let uri = "obsidian://open?vault=My%20Vault&file=a.md";
+        assert_eq!(cmd_quote(uri), format!("\"{}\"", uri));
+    }
+}
@@ -0,0 +1,158 @@
+use rustyline::completion::Completer;
+use rustyline::error::ReadlineError;
+use rustyline::highlight::Highlighter;
+use rustyline::hint::Hinter;
+use rustyline::history::DefaultHistory;
+use rustyline::validate::Validator;
+use rustyline::{Context, Editor, Helper};
+use std::io::{self, BufRead, IsTerminal, Write};
+use std::sync::atomic::{AtomicBool, Ordering};
+
+use crate::error::SnippetVaultError;
+
+static NON_INTERACTIVE: AtomicBool = AtomicBool::new(false);
+
+/// Set once [`require_interactive`] has refused, so the process can exit non-zero.
+static REFUSED: AtomicBool = AtomicBool::new(false);
+
+/// Treats the terminal as absent for the rest of the process (`--non-interactive`).
+pub fn set_non_interactive() {
+    NON_INTERACTIVE.store(true, Ordering::Relaxed);
+}
+
+/// Whether both stdin and stdout are attached to a terminal, so prompts can be
+/// answered, and `--non-interactive` was not given.
+pub fn is_interactive() -> bool {
+    !NON_INTERACTIVE.load(Ordering::Relaxed)
+        && io::stdin().is_terminal()
+        && io::stdout().is_terminal()
+}
+
+/// Fails with [`SnippetVaultError::NotInteractive`] unless [`is_interactive`],
+/// so pickers and editors are never started where nobody can use them.
+/// - `action`: What needs the terminal, e.g. "pick a snippet".
+/// - `hint`: The flags that make the terminal unnecessary.
+pub fn require_interactive(
+    action: &'static str,
+    hint: &'static str,
+) -> Result<(), SnippetVaultError> {
+    if is_interactive() {
+        return Ok(());
+    }
+    REFUSED.store(true, Ordering::Relaxed);
+    Err(SnippetVaultError::NotInteractive { action, hint })
+}
+
+/// Whether anything was refused for want of a terminal, so a script running
+/// the command sees it fail even when the error was only printed.
+pub fn was_refused() -> bool {
+    REFUSED.load(Ordering::Relaxed)
+}
+
+/// Prints `question` and reads one line; an empty answer (or EOF) yields `default`.
+/// Fails without asking when there is no terminal (see [`is_interactive`]).
+pub fn ask(question: &str, default: &str) -> io::Result<String> {
+    require_interactive("answer a prompt", "pass --yes or give the answer as a flag")
+        .map_err(io::Error::other)?;
+    if default.is_empty() {
+        print!("{} ", question);
+    } else {
+        print!("{} [{}] ", question, default);
+    }
+    io::stdout().flush()?;
+
+    let mut answer = String::new();
+    io::stdin().lock().read_line(&mut answer)?;
+    let answer = answer.trim();
+    Ok(if answer.is_empty() {
+        default.to_string()
+    } else {
+        answer.to_string()
+    })
+}
+
+/// Asks a yes/no question; an empty answer yields `default`.
+pub fn confirm(question: &str, default: bool) -> io::Result<bool> {
+    let hint = if default { "Y/n" } else { "y/N" };
+    let answer = ask(&format!("{} [{}]", question, hint), "")?;
+    Ok(match answer.to_lowercase().as_str() {
+        "y" | "yes" => true,
+        "n" | "no" => false,
+        _ => default,
+    })
+}
+
+/// Presents numbered `options` and returns the index of the chosen one.
+/// An empty answer selects the first option; invalid answers ask again.
+pub fn choose(question: &str, options: &[String]) -> io::Result<usize> {
+    println!("{}", question);
+    for (index, option) in options.iter().enumerate() {
+        println!("  {}) {}", index + 1, option);
+    }
+
+    loop {
+        let answer = ask("Choice:", "1")?;
+        match answer.parse::<usize>() {
+            Ok(choice) if (1..=options.len()).contains(&choice) => return Ok(choice - 1),
+            _ => println!("Please enter a number between 1 and {}.", options.len()),
+        }
+    }
+}
+
+/// Completes the word under the cursor of an [`edit_line`] prompt from a fixed
+/// list; words are separated by commas and whitespace.
+struct WordCompleter {
+    words: Vec<String>,
+}
+
+impl Completer for WordCompleter {
+    type Candidate = String;
+
+    fn complete(
+        &self,
+        line: &str,
+        pos: usize,
+        _context: &Context<'_>,
+    ) -> rustyline::Result<(usize, Vec<String>)> {
+        let start = line[..pos]
+            .rfind(|c: char| c == ',' || c.is_whitespace())
+            .map_or(0, |index| index + 1);
+        let prefix = &line[start..pos];
+        let candidates = self
+            .words
+            .iter()
+            .filter(|word| word.starts_with(prefix))
+            .cloned()
+            .collect();
+        Ok((start, candidates))
+    }
+}
+
+impl Hinter for WordCompleter {
+    type Hint = String;
+}
+
+impl Highlighter for WordCompleter {}
+
+impl Validator for WordCompleter {}
+
+impl Helper for WordCompleter {}
+
+/// Prints `question` and lets the user edit `initial` on a readline-style
+/// line, with tab completion from `completions`. Returns the edited line;
+/// Ctrl-C and Ctrl-D cancel with an [`io::ErrorKind::Interrupted`] error.
+/// Fails without asking when there is no terminal (see [`is_interactive`]).
+pub fn edit_line(question: &str, initial: &str, completions: Vec<String>) -> io::Result<String> {
+    require_interactive("answer a prompt", "edit the file instead").map_err(io::Error::other)?;
+    let mut editor: Editor<WordCompleter, DefaultHistory> =
+        Editor::new().map_err(io::Error::other)?;
+    editor.set_helper(Some(WordCompleter { words: completions }));
+    match editor.readline_with_initial(&format!("{} ", question), (initial, "")) {
+        Ok(line) => Ok(line),
+        Err(ReadlineError::Interrupted | ReadlineError::Eof) => {
+            Err(io::Error::new(io::ErrorKind::Interrupted, "cancelled"))
+        }
+        Err(ReadlineError::Io(err)) => Err(err),
+        Err(err) => Err(io::Error::other(err)),
+    }
+}
@@ -0,0 +1,289 @@
+use std::collections::HashSet;
+use std::fs;
+use std::path::{Component, Path, PathBuf};
+
+use crate::error::SnippetVaultError;
+use crate::index;
+use crate::vault::{Executor, Mutation};
+
+/// One line of a rename specification.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Rename {
+    pub from: PathBuf,
+    pub to: PathBuf,
+}
+
+/// Parses a rename specification: one `old_path<TAB>new_path` pair per line.
+/// Paths are relative to `snippet_dir` and may not leave it; blank lines and
+/// lines starting with `#` are skipped. Malformed lines are reported by number.
+pub fn parse_spec(content: &str, snippet_dir: &Path) -> (Vec<Rename>, Vec<String>) {
+    let mut renames = Vec::new();
+    let mut errors = Vec::new();
+    for (index, line) in content.lines().enumerate() {
+        if line.trim().is_empty() || line.trim_start().starts_with('#') {
+            continue;
+        }
+        match line
+            .split('\t')
+            .map(str::trim)
+            .collect::<Vec<_>>()
+            .as_slice()
+        {
+            [from, to] if !from.is_empty() && !to.is_empty() => {
+                match [from, to].into_iter().find(|path| !inside_vault(path)) {
+                    Some(path) => errors.push(format!(
+                        "line {}: '{}' must be a relative path inside the vault",
+                        index + 1,
+                        path
+                    )),
+                    None => renames.push(Rename {
+                        from: snippet_dir.join(from),
+                        to: snippet_dir.join(to),
+                    }),
+                }
+            }
+            _ => errors.push(format!(
+                "line {}: expected old_path<TAB>new_path, got '{}'",
+                index + 1,
+                line
+            )),
+        }
+    }
+    (renames, errors)
+}
+
+/// Whether `path` is relative and stays below the vault: no root, prefix, or
+/// `..` component.
+fn inside_vault(path: &str) -> bool {
+    Path::new(path)
+        .components()
+        .all(|component| matches!(component, Component::Normal(_) | Component::CurDir))
+}
+
+/// Problems that would make `renames` fail or clobber files: missing sources,
+/// existing destinations (unless `overwrite`), paths named twice, and chains
+/// where a destination is itself renamed.
+fn validate(renames: &[Rename], overwrite: bool) -> Vec<String> {
+    let mut errors = Vec::new();
+    let sources: HashSet<&Path> = renames.iter().map(|rename| rename.from.as_path()).collect();
+    let mut seen_sources = HashSet::new();
+    let mut seen_destinations = HashSet::new();
+    for rename in renames {
+        if !rename.from.is_file() {
+            errors.push(format!("{} does not exist", rename.from.display()));
+        }
+        if !seen_sources.insert(&rename.from) {
+            errors.push(format!("{} is renamed twice", rename.from.display()));
+        }
+        if !seen_destinations.insert(&rename.to) {
+            errors.push(format!(
+                "{} is the target of two renames",
+                rename.to.display()
+            ));
+        }
+        if sources.contains(rename.to.as_path()) {
+            errors.push(format!(
+                "{} is both renamed and a rename target; split the batch in two",
+                rename.to.display()
+            ));
+        } else if rename.to.exists() && !overwrite {
+            errors.push(format!(
+                "{} already exists (pass --overwrite to replace it)",
+                rename.to.display()
+            ));
+        }
+    }
+    errors
+}
+
+/// Applies every rename of the specification at `spec_file` in one pass and
+/// returns `(renamed, errors)`. The whole batch is validated first; when
+/// anything is wrong nothing is renamed and the problems are returned. If a
+/// rename fails midway, the ones already applied are undone in reverse order.
+/// - `overwrite`: Replace existing destination files.
+/// - `executor`: Applies (or, with `--dry-run`, only prints) the renames.
+pub fn rename_batch(
+    spec_file: &Path,
+    snippet_dir: &Path,
+    overwrite: bool,
+    executor: &Executor,
+) -> Result<(usize, Vec<String>), SnippetVaultError> {
+    let (renames, mut errors) = parse_spec(&fs::read_to_string(spec_file)?, snippet_dir);
+    errors.extend(validate(&renames, overwrite));
+    if !errors.is_empty() {
+        return Ok((0, errors));
+    }
+
+    let _lock = executor.lock()?;
+    let errors = apply_all(&renames, executor)?;
+    if !errors.is_empty() {
+        return Ok((0, errors));
+    }
+    if !executor.is_dry_run() {
+        for rename in &renames {
+            index::reindex_snippet(&rename.from, &rename.to);
+        }
+    }
+    Ok((renames.len(), Vec::new()))
+}
+
+/// Applies validated `renames` in order and returns the errors. If one fails,
+/// the ones already applied are undone in reverse order, directories they
+/// created included, and the failure is returned with any undo that failed.
+fn apply_all(renames: &[Rename], executor: &Executor) -> Result<Vec<String>, SnippetVaultError> {
+    let mut undo: Vec<Mutation> = Vec::new();
+    let mut replaced: Vec<PathBuf> = Vec::new();
+    for rename in renames {
+        if let Err(err) = apply_one(rename, executor, &mut undo, &mut replaced) {
+            let mut errors = vec![format!(
+                "{} -> {}: {}",
+                rename.from.display(),
+                rename.to.display(),
+                err
+            )];
+            for mutation in undo.into_iter().rev() {
+                if let Err(err) = executor.apply(mutation.clone()) {
+                    errors.push(format!("cannot undo ({}): {}", mutation, err));
+                }
+            }
+            return Ok(errors);
+        }
+    }
+
+    for backup in replaced {
+        executor.apply(Mutation::Remove(backup))?;
+    }
+    Ok(Vec::new())
+}
+
+/// Renames one file, first moving a destination it replaces out of the way,
+/// and records how to undo each step.
+fn apply_one(
+    rename: &Rename,
+    executor: &Executor,
+    undo: &mut Vec<Mutation>,
+    replaced: &mut Vec<PathBuf>,
+) -> Result<(), SnippetVaultError> {
+    if let Some(parent) = rename.to.parent().filter(|parent| !parent.exists()) {
+        let missing: Vec<PathBuf> = parent
+            .ancestors()
+            .take_while(|dir| !dir.exists())
+            .map(Path::to_path_buf)
+            .collect();
+        executor.apply(Mutation::CreateDir(parent.to_path_buf()))?;
+        // Outermost first, so undoing in reverse empties the innermost first.
+        undo.extend(missing.into_iter().rev().map(Mutation::RemoveDir));
+    }
+    if rename.to.exists() {
+        let file_name = rename.to.file_name().unwrap_or_default().to_string_lossy();
+        let backup =
+            rename
+                .to
+                .with_file_name(format!(".{}.{}.replaced", file_name, std::process::id()));
+        executor.apply(Mutation::Rename {
+            from: rename.to.clone(),
+            to: backup.clone(),
+        })?;
+        undo.push(Mutation::Rename {
+            from: backup.clone(),
+            to: rename.to.clone(),
+        });
+        replaced.push(backup);
+    }
+    executor.apply(Mutation::Rename {
+        from: rename.from.clone(),
+        to: rename.to.clone(),
+    })?;
+    undo.push(Mutation::Rename {
+        from: rename.to.clone(),
+        to: rename.from.clone(),
+    });
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Every file and directory under `dir` with its contents (empty for
+    /// directories).
+    fn snapshot(dir: &Path) -> Vec<(PathBuf, String)> {
+        let mut entries = Vec::new();
+        let mut pending = vec![dir.to_path_buf()];
+        while let Some(current) = pending.pop() {
+            for entry in fs::read_dir(&current).unwrap() {
+                let path = entry.unwrap().path();
+                let relative = path.strip_prefix(dir).unwrap().to_path_buf();
+                if path.is_dir() {
+                    entries.push((relative, String::new()));
+                    pending.push(path);
+                } else {
+                    entries.push((relative, fs::read_to_string(&path).unwrap()));
+                }
+            }
+        }
+        entries.sort();
+        entries
+    }
+
+    #[test]
+    fn targets_outside_the_vault_are_rejected() {
+        let vault = Path::new("/vault");
+        let (renames, errors) = parse_spec(
+            "a.md\tgit/a.md\n./b.md\tb2.md\nc.md\t/etc/c.md\nd.md\t../d.md\n../e.md\te.md\n",
+            vault,
+        );
+        assert_eq!(
+            renames,
+            [
+                Rename {
+                    from: vault.join("a.md"),
+                    to: vault.join("git/a.md"),
+                },
+                Rename {
+                    from: vault.join("./b.md"),
+                    to: vault.join("b2.md"),
+                },
+            ]
+        );
+        assert_eq!(
+            errors,
+            [
+                "line 3: '/etc/c.md' must be a relative path inside the vault",
+                "line 4: '../d.md' must be a relative path inside the vault",
+                "line 5: '../e.md' must be a relative path inside the vault",
+            ]
+        );
+    }
+
+    #[test]
+    fn a_failed_rename_undoes_the_batch_and_its_directories() {
+        crate::testing::isolate_user_dirs();
+        let dir = tempfile::tempdir().unwrap();
+        let vault = dir.path();
+        fs::write(vault.join("a.md"), "a").unwrap();
+        fs::write(vault.join("b.md"), "b").unwrap();
+        fs::write(vault.join("c.md"), "c").unwrap();
+        let before = snapshot(vault);
+
+        let renames = [
+            Rename {
+                from: vault.join("a.md"),
+                to: vault.join("tools/git/a.md"),
+            },
+            Rename {
+                from: vault.join("b.md"),
+                to: vault.join("c.md"),
+            },
+            Rename {
+                from: vault.join("gone.md"),
+                to: vault.join("d.md"),
+            },
+        ];
+        let errors = apply_all(&renames, &Executor::new(false)).unwrap();
+
+        assert_eq!(errors.len(), 1, "{:?}", errors);
+        assert!(errors[0].contains("gone.md"), "{}", errors[0]);
+        assert_eq!(snapshot(vault), before);
+    }
+}
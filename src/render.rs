@@ -0,0 +1,486 @@
+use colored::*;
+use pulldown_cmark::{CodeBlockKind, Event, HeadingLevel, Options, Parser, Tag, TagEnd};
+use std::path::Path;
+use syntect::easy::HighlightLines;
+use syntect::highlighting::{Theme, ThemeSet};
+use syntect::parsing::{SyntaxReference, SyntaxSet};
+use syntect::util::{as_24_bit_terminal_escaped, LinesWithEndings};
+
+use crate::language;
+use crate::snippet;
+use crate::width;
+
+/// Theme used when `theme` is not configured.
+pub const DEFAULT_THEME: &str = "base16-ocean.dark";
+
+/// Theme used on light terminals when `theme` is not configured.
+pub const LIGHT_THEME: &str = "InspiredGitHub";
+
+/// Renders snippets for the terminal without external tools: markdown headings
+/// are bolded and fenced code blocks highlighted by their fence language.
+/// Every in-process preview goes through this, so they all look the same.
+pub struct Renderer {
+    syntaxes: SyntaxSet,
+    theme: Option<Theme>,
+}
+
+impl Renderer {
+    /// A renderer using `theme` (see [`theme_names`]), or plain output when
+    /// `color` is false. An unknown theme falls back to [`DEFAULT_THEME`].
+    pub fn new(theme: Option<&str>, color: bool) -> Self {
+        let theme = color.then(|| {
+            let mut themes = ThemeSet::load_defaults().themes;
+            let name = theme.unwrap_or(DEFAULT_THEME);
+            themes.remove(name).unwrap_or_else(|| {
+                tracing::warn!(theme = %name, "unknown theme, using {}", DEFAULT_THEME);
+                themes
+                    .remove(DEFAULT_THEME)
+                    .expect("default theme is built in")
+            })
+        });
+        Renderer {
+            syntaxes: SyntaxSet::load_defaults_newlines(),
+            theme,
+        }
+    }
+
+    /// Renders the file at `path`: markdown snippets section by section, plain
+    /// code files highlighted as a whole by their extension.
+    pub fn render_file(&self, path: &Path, content: &str) -> String {
+        if snippet::is_markdown(path) {
+            return self.render_markdown(content);
+        }
+        let extension = path
+            .extension()
+            .and_then(|extension| extension.to_str())
+            .unwrap_or_default();
+        self.highlight(content, self.syntaxes.find_syntax_by_extension(extension))
+    }
+
+    /// Renders markdown: headings in bold, fenced code highlighted, and
+    /// everything else (frontmatter included) as-is.
+    pub fn render_markdown(&self, content: &str) -> String {
+        let mut rendered = String::with_capacity(content.len());
+        let mut fence: Option<(&str, Option<&SyntaxReference>)> = None;
+        let mut code = String::new();
+
+        for line in LinesWithEndings::from(content) {
+            let trimmed = line.trim_start();
+            match fence {
+                Some((marker, syntax)) if trimmed.starts_with(marker) => {
+                    rendered.push_str(&self.highlight(&code, syntax));
+                    code.clear();
+                    rendered.push_str(&self.dim(line));
+                    fence = None;
+                }
+                Some(_) => code.push_str(line),
+                None if trimmed.starts_with("```") || trimmed.starts_with("~~~") => {
+                    let marker = &trimmed[..3];
+                    let info = trimmed[3..].trim();
+                    fence = Some((marker, self.find_syntax(info)));
+                    rendered.push_str(&self.dim(line));
+                }
+                None if trimmed.starts_with('#') && self.theme.is_some() => {
+                    let text = line.trim_end_matches(['\r', '\n']);
+                    rendered.push_str(&format!("{}\n", text.bold()));
+                }
+                None => rendered.push_str(line),
+            }
+        }
+        // An unclosed fence still shows its code.
+        if fence.is_some() {
+            rendered.push_str(&self.highlight(&code, fence.and_then(|(_, syntax)| syntax)));
+        }
+        rendered
+    }
+
+    /// Renders markdown the way `glow` would, without it: frontmatter dropped,
+    /// headings as colored bold text, bold and italic styled, fenced code
+    /// highlighted, list items as `• item` (or `1. item`), and prose wrapped
+    /// to `width` columns.
+    pub fn render_markdown_terminal(&self, content: &str, width: u16) -> String {
+        let body = snippet::split_frontmatter(content).1;
+        let options = Options::ENABLE_STRIKETHROUGH | Options::ENABLE_TASKLISTS;
+        let mut writer = TerminalWriter::new(usize::from(width).max(20), self.theme.is_some());
+        let mut lists: Vec<Option<u64>> = Vec::new();
+        let mut code: Option<(String, Option<&SyntaxReference>)> = None;
+        let mut links: Vec<String> = Vec::new();
+
+        for event in Parser::new_ext(body, options) {
+            match event {
+                Event::Start(Tag::Heading { level, .. }) => {
+                    writer.end_block();
+                    writer.style.heading = Some(level);
+                }
+                Event::End(TagEnd::Heading(_)) => {
+                    writer.style.heading = None;
+                    writer.end_block();
+                }
+                Event::Start(Tag::Paragraph) => {}
+                Event::End(TagEnd::Paragraph) => {
+                    if lists.is_empty() {
+                        writer.end_block();
+                    } else {
+                        writer.end_line();
+                    }
+                }
+                Event::Start(Tag::CodeBlock(kind)) => {
+                    writer.end_line();
+                    let syntax = match &kind {
+                        CodeBlockKind::Fenced(info) => self.find_syntax(info),
+                        CodeBlockKind::Indented => None,
+                    };
+                    code = Some((String::new(), syntax));
+                }
+                Event::End(TagEnd::CodeBlock) => {
+                    if let Some((text, syntax)) = code.take() {
+                        writer.code_block(&self.highlight(&text, syntax));
+                    }
+                    if lists.is_empty() {
+                        writer.end_block();
+                    }
+                }
+                Event::Start(Tag::List(start)) => {
+                    writer.end_line();
+                    lists.push(start);
+                }
+                Event::End(TagEnd::List(_)) => {
+                    lists.pop();
+                    if lists.is_empty() {
+                        writer.end_block();
+                    }
+                }
+                Event::Start(Tag::Item) => {
+                    writer.end_line();
+                    let marker = match lists.last_mut() {
+                        Some(Some(number)) => {
+                            *number += 1;
+                            format!("{}. ", *number - 1)
+                        }
+                        _ => "• ".to_string(),
+                    };
+                    writer.start_item(&marker);
+                }
+                Event::End(TagEnd::Item) => {
+                    writer.end_line();
+                    writer.indents.pop();
+                }
+                Event::Start(Tag::BlockQuote(_)) => {
+                    writer.end_line();
+                    writer.indents.push("│ ".to_string());
+                }
+                Event::End(TagEnd::BlockQuote(_)) => {
+                    writer.indents.pop();
+                    writer.end_block();
+                }
+                Event::Start(Tag::Emphasis) => writer.style.italic = true,
+                Event::End(TagEnd::Emphasis) => writer.style.italic = false,
+                Event::Start(Tag::Strong) => writer.style.bold = true,
+                Event::End(TagEnd::Strong) => writer.style.bold = false,
+                Event::Start(Tag::Strikethrough) => writer.style.strike = true,
+                Event::End(TagEnd::Strikethrough) => writer.style.strike = false,
+                Event::Start(Tag::Link { dest_url, .. } | Tag::Image { dest_url, .. }) => {
+                    writer.style.link = true;
+                    links.push(dest_url.to_string());
+                }
+                Event::End(TagEnd::Link | TagEnd::Image) => {
+                    writer.style.link = false;
+                    if let Some(url) = links.pop().filter(|url| !url.starts_with('#')) {
+                        writer.dimmed(&format!("({})", url));
+                    }
+                }
+                Event::Text(text) => match code.as_mut() {
+                    Some((buffer, _)) => buffer.push_str(&text),
+                    None => writer.text(&text),
+                },
+                Event::Code(text) => {
+                    writer.style.code = true;
+                    writer.text(&text);
+                    writer.style.code = false;
+                }
+                Event::SoftBreak => writer.text(" "),
+                Event::HardBreak => writer.end_line(),
+                Event::Rule => {
+                    writer.end_line();
+                    writer.dimmed(&"─".repeat(writer.width.saturating_sub(writer.indent_width())));
+                    writer.end_block();
+                }
+                Event::TaskListMarker(done) => writer.text(if done { "[x] " } else { "[ ] " }),
+                _ => {}
+            }
+        }
+        writer.finish()
+    }
+
+    /// Finds the syntax for a fence language or file extension, trying the
+    /// language's known extension for names syntect does not know (`golang`).
+    fn find_syntax(&self, info: &str) -> Option<&SyntaxReference> {
+        let token = info.split_whitespace().next()?;
+        self.syntaxes.find_syntax_by_token(token).or_else(|| {
+            language::extension_for(&token.to_lowercase())
+                .and_then(|extension| self.syntaxes.find_syntax_by_extension(extension))
+        })
+    }
+
+    /// Highlights `code` with `syntax`, or returns it unchanged without a
+    /// syntax, without colors, or when highlighting fails.
+    fn highlight(&self, code: &str, syntax: Option<&SyntaxReference>) -> String {
+        let (Some(theme), Some(syntax)) = (&self.theme, syntax) else {
+            return code.to_string();
+        };
+        let mut highlighter = HighlightLines::new(syntax, theme);
+        let mut highlighted = String::with_capacity(code.len() * 2);
+        for line in LinesWithEndings::from(code) {
+            match highlighter.highlight_line(line, &self.syntaxes) {
+                Ok(ranges) => highlighted.push_str(&as_24_bit_terminal_escaped(&ranges, false)),
+                Err(_) => return code.to_string(),
+            }
+        }
+        highlighted.push_str("\x1b[0m");
+        highlighted
+    }
+
+    fn dim(&self, line: &str) -> String {
+        if self.theme.is_none() {
+            return line.to_string();
+        }
+        format!("{}\n", line.trim_end_matches(['\r', '\n']).dimmed())
+    }
+}
+
+/// Inline styles in effect while rendering markdown for the terminal.
+#[derive(Debug, Default, Clone, Copy)]
+struct InlineStyle {
+    heading: Option<HeadingLevel>,
+    bold: bool,
+    italic: bool,
+    strike: bool,
+    code: bool,
+    link: bool,
+}
+
+/// Accumulates word-wrapped terminal output for
+/// [`Renderer::render_markdown_terminal`].
+struct TerminalWriter {
+    out: String,
+    width: usize,
+    color: bool,
+    style: InlineStyle,
+    /// Prefixes of the enclosing block quotes and list items, outermost first.
+    indents: Vec<String>,
+    /// Marker replacing the innermost indent on the first line of a list item.
+    marker: Option<String>,
+    /// Visible width of the current line; 0 before anything was written to it.
+    column: usize,
+    /// Whitespace was seen since the last word.
+    space: bool,
+}
+
+impl TerminalWriter {
+    fn new(width: usize, color: bool) -> Self {
+        TerminalWriter {
+            out: String::new(),
+            width,
+            color,
+            style: InlineStyle::default(),
+            indents: Vec::new(),
+            marker: None,
+            column: 0,
+            space: false,
+        }
+    }
+
+    fn indent_width(&self) -> usize {
+        self.indents
+            .iter()
+            .map(|indent| width::display_width(indent))
+            .sum()
+    }
+
+    /// Writes the indentation (and pending list marker) at the start of a line.
+    fn start_line(&mut self) {
+        let mut prefix: String = self.indents.concat();
+        if let Some(marker) = self.marker.take() {
+            let inner = self.indents.last().map_or(0, String::len);
+            prefix.truncate(prefix.len() - inner);
+            prefix.push_str(&marker);
+        }
+        self.column = width::display_width(&prefix);
+        self.out.push_str(&prefix);
+        self.space = false;
+    }
+
+    fn start_item(&mut self, marker: &str) {
+        self.indents.push(" ".repeat(width::display_width(marker)));
+        self.marker = Some(marker.to_string());
+    }
+
+    /// Writes `text` word by word, wrapping before words that would overflow.
+    fn text(&mut self, text: &str) {
+        if text.starts_with(char::is_whitespace) {
+            self.space = true;
+        }
+        for word in text.split_whitespace() {
+            self.word(word);
+            self.space = true;
+        }
+        if !text.ends_with(char::is_whitespace) {
+            self.space = false;
+        }
+    }
+
+    fn word(&mut self, word: &str) {
+        let length = width::display_width(word);
+        // A word wider than a whole line, like a run of CJK text without
+        // spaces, is broken wherever it has to be.
+        let room = self.width.saturating_sub(self.indent_width()).max(1);
+        if length > room {
+            let (mut piece, mut used) = (String::new(), 0);
+            for c in word.chars() {
+                let char_width = width::display_width(c.encode_utf8(&mut [0; 4]));
+                if used + char_width > room && !piece.is_empty() {
+                    self.word(&std::mem::take(&mut piece));
+                    self.space = false;
+                    used = 0;
+                }
+                piece.push(c);
+                used += char_width;
+            }
+            self.word(&piece);
+            return;
+        }
+        if self.column == 0 {
+            self.start_line();
+        } else if self.column + usize::from(self.space) + length > self.width
+            && self.column > self.indent_width()
+        {
+            self.out.push('\n');
+            self.start_line();
+        } else if self.space {
+            self.out.push(' ');
+            self.column += 1;
+        }
+        let styled = self.paint(word);
+        self.out.push_str(&styled);
+        self.column += length;
+    }
+
+    fn paint(&self, word: &str) -> String {
+        if !self.color {
+            return word.to_string();
+        }
+        let style = self.style;
+        let mut painted = match style.heading {
+            Some(HeadingLevel::H1) => word.magenta().bold(),
+            Some(HeadingLevel::H2) => word.cyan().bold(),
+            Some(_) => word.bold(),
+            None if style.code => word.yellow(),
+            None if style.link => word.blue().underline(),
+            None => word.normal(),
+        };
+        if style.bold {
+            painted = painted.bold();
+        }
+        if style.italic {
+            painted = painted.italic();
+        }
+        if style.strike {
+            painted = painted.strikethrough();
+        }
+        painted.to_string()
+    }
+
+    /// Writes `text` dimmed and unwrapped, after a space.
+    fn dimmed(&mut self, text: &str) {
+        if self.column == 0 {
+            self.start_line();
+        } else {
+            self.out.push(' ');
+            self.column += 1;
+        }
+        if self.color {
+            self.out.push_str(&text.dimmed().to_string());
+        } else {
+            self.out.push_str(text);
+        }
+        self.column += width::display_width(text);
+    }
+
+    /// Writes already highlighted code line by line at the current indentation.
+    fn code_block(&mut self, code: &str) {
+        let (code, reset) = match code.strip_suffix("\x1b[0m") {
+            Some(code) => (code, "\x1b[0m"),
+            None => (code, ""),
+        };
+        for line in code.lines() {
+            self.start_line();
+            self.out.push_str(line);
+            self.out.push('\n');
+        }
+        self.out.push_str(reset);
+        self.column = 0;
+    }
+
+    fn end_line(&mut self) {
+        if self.column > 0 {
+            self.out.push('\n');
+            self.column = 0;
+        }
+    }
+
+    /// Ends the current block with a blank line.
+    fn end_block(&mut self) {
+        self.end_line();
+        if !self.out.is_empty() && !self.out.ends_with("\n\n") {
+            self.out.push('\n');
+        }
+    }
+
+    fn finish(mut self) -> String {
+        self.end_line();
+        let trimmed = self.out.trim_end_matches('\n').len();
+        self.out.truncate(trimmed);
+        self.out.push('\n');
+        self.out
+    }
+}
+
+/// Names of the built-in themes accepted by the `theme` setting.
+pub fn theme_names() -> Vec<String> {
+    let mut names: Vec<String> = ThemeSet::load_defaults().themes.into_keys().collect();
+    names.sort();
+    names
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn widest_line(text: &str) -> usize {
+        text.lines().map(width::display_width).max().unwrap_or(0)
+    }
+
+    #[test]
+    fn wrapping_counts_display_width() {
+        let renderer = Renderer::new(None, false);
+        let content = "使用 git rebase 整理提交历史并推送到远程仓库，然后删除本地分支并清理所有过期的引用\n\n\
+                       - Docker コンテナをすべて停止して削除する前にログを保存する\n";
+        for width in [20, 30, 44] {
+            let rendered = renderer.render_markdown_terminal(content, width);
+            assert!(
+                widest_line(&rendered) <= usize::from(width),
+                "{}:\n{}",
+                width,
+                rendered
+            );
+        }
+    }
+
+    #[test]
+    fn list_items_hang_under_their_marker() {
+        let renderer = Renderer::new(None, false);
+        let rendered =
+            renderer.render_markdown_terminal("- one two three four five six seven\n", 20);
+        assert_eq!(rendered, "• one two three four\n  five six seven\n");
+    }
+}
@@ -0,0 +1,473 @@
+use colored::*;
+use regex::{NoExpand, Regex};
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use crate::error::SnippetVaultError;
+use crate::expiry;
+use crate::index;
+use crate::listing::{self, SnippetFilter, SortCriterion};
+use crate::snippet;
+use crate::style::Styled;
+use crate::vault::{Executor, Mutation};
+
+/// The edit `--replace_in_files` would make to one file.
+#[derive(Debug, Clone)]
+pub struct FileReplacement {
+    pub path: PathBuf,
+    pub original: String,
+    pub updated: String,
+    /// Number of matches replaced.
+    pub count: usize,
+}
+
+/// Builds the pattern `--replace_in_files` searches for: `pattern` itself with
+/// `regex`, else `pattern` matched literally.
+pub fn build_pattern(pattern: &str, regex: bool) -> Result<Regex, SnippetVaultError> {
+    let source = if regex {
+        pattern.to_string()
+    } else {
+        regex::escape(pattern)
+    };
+    Regex::new(&source)
+        .map_err(|err| SnippetVaultError::Config(format!("invalid pattern: {}", err)))
+}
+
+/// Works out, without changing anything, how replacing `regex` with
+/// `replacement` would change every snippet matching `filter`, whole files
+/// included. Snippets in the archive directory are skipped unless
+/// `include_archived`, as are binary and oversized files.
+/// - `expand`: Whether `$1` or `${name}` in `replacement` refer to capture
+///   groups; otherwise it is inserted literally.
+/// - `extensions`: File extensions that count as snippets.
+/// - `use_cache`: Serve unchanged snippets from the metadata cache.
+#[allow(clippy::too_many_arguments)]
+pub fn plan_replacements(
+    snippet_dir: &Path,
+    regex: &Regex,
+    replacement: &str,
+    expand: bool,
+    filter: &SnippetFilter,
+    include_archived: bool,
+    extensions: &[String],
+    use_cache: bool,
+) -> Result<Vec<FileReplacement>, SnippetVaultError> {
+    let snippets = listing::query_snippets(
+        snippet_dir,
+        filter,
+        SortCriterion::Path,
+        extensions,
+        use_cache,
+    )?;
+
+    let mut planned = Vec::new();
+    for snippet in snippets {
+        if !include_archived && expiry::is_archived(&snippet) {
+            continue;
+        }
+        let Some(bytes) = snippet::read_limited(&snippet.path)? else {
+            continue;
+        };
+        if snippet::is_binary(&bytes) {
+            continue;
+        }
+        let Ok(original) = String::from_utf8(bytes) else {
+            continue;
+        };
+        let count = regex.find_iter(&original).count();
+        if count == 0 {
+            continue;
+        }
+        let updated = if expand {
+            regex.replace_all(&original, replacement).into_owned()
+        } else {
+            regex
+                .replace_all(&original, NoExpand(replacement))
+                .into_owned()
+        };
+        planned.push(FileReplacement {
+            path: snippet.path,
+            original,
+            updated,
+            count,
+        });
+    }
+    Ok(planned)
+}
+
+/// Prints the lines `change` rewrites, old in red and new in green, under the
+/// file's path relative to `snippet_dir`. Lines are compared one to one while
+/// the line count is unchanged; otherwise the changed region is shown as a block.
+pub fn print_diff(change: &FileReplacement, snippet_dir: &Path) {
+    let relative = change
+        .path
+        .strip_prefix(snippet_dir)
+        .unwrap_or(&change.path);
+    println!(
+        "{} ({} {})",
+        relative.display().to_string().bold(),
+        change.count,
+        if change.count == 1 {
+            "match"
+        } else {
+            "matches"
+        }
+    );
+    // `lines` splits on `\r\n` too, so CRLF files show without stray `\r`s.
+    let old: Vec<&str> = change.original.lines().collect();
+    let new: Vec<&str> = change.updated.lines().collect();
+
+    if old.len() == new.len() {
+        for (index, (before, after)) in old.iter().zip(&new).enumerate() {
+            if before != after {
+                println!("  {:>4} {} {}", index + 1, "-".error(), before.error());
+                println!("  {:>4} {} {}", "", "+".success(), after.success());
+            }
+        }
+        return;
+    }
+    let prefix = old.iter().zip(&new).take_while(|(a, b)| a == b).count();
+    let suffix = old[prefix..]
+        .iter()
+        .rev()
+        .zip(new[prefix..].iter().rev())
+        .take_while(|(a, b)| a == b)
+        .count();
+    for (offset, before) in old[prefix..old.len() - suffix].iter().enumerate() {
+        println!(
+            "  {:>4} {} {}",
+            prefix + offset + 1,
+            "-".error(),
+            before.error()
+        );
+    }
+    for after in &new[prefix..new.len() - suffix] {
+        println!("  {:>4} {} {}", "", "+".success(), after.success());
+    }
+}
+
+/// Writes `change` to its file in one atomic step. Fails without writing when
+/// the file changed since the replacement was planned.
+/// - `executor`: Applies (or, with `--dry-run`, only prints) the write.
+pub fn apply_replacement(
+    change: &FileReplacement,
+    executor: &Executor,
+) -> Result<(), SnippetVaultError> {
+    if fs::read(&change.path)? != change.original.as_bytes() {
+        return Err(SnippetVaultError::Config(format!(
+            "{} changed since the preview; run the replacement again",
+            change.path.display()
+        )));
+    }
+    executor.apply(Mutation::Write {
+        path: change.path.clone(),
+        contents: change.updated.clone(),
+    })?;
+    if !executor.is_dry_run() {
+        index::reindex_snippet(&change.path, &change.path);
+    }
+    Ok(())
+}
+
+/// Replaces every match of `pattern` with `replacement` (which may use `$1`
+/// style capture references) inside the code blocks of the snippets matching
+/// `filter`; prose, headings, and frontmatter are never touched. Plain code
+/// files count as one code block, and unreadable or binary files are skipped.
+/// Under `--dry-run` each match is printed with its line instead. Returns
+/// `(files_changed, replacements_made)`; a file whose content would stay the
+/// same is neither written nor counted.
+/// - `extensions`: File extensions that count as snippets.
+/// - `use_cache`: Serve unchanged snippets from the metadata cache.
+pub fn regex_replace_in_snippets(
+    snippet_dir: &Path,
+    pattern: &str,
+    replacement: &str,
+    filter: &SnippetFilter,
+    extensions: &[String],
+    use_cache: bool,
+    executor: &Executor,
+) -> Result<(usize, usize), SnippetVaultError> {
+    let regex = Regex::new(pattern)
+        .map_err(|err| SnippetVaultError::Config(format!("invalid pattern: {}", err)))?;
+    let snippets = listing::query_snippets(
+        snippet_dir,
+        filter,
+        SortCriterion::Path,
+        extensions,
+        use_cache,
+    )?;
+
+    let _lock = executor.lock()?;
+    let mut files_changed = 0;
+    let mut replacements = 0;
+    for snippet in &snippets {
+        let Some((content, updated, count)) =
+            replace_in_snippet(&snippet.path, &regex, replacement)
+        else {
+            continue;
+        };
+
+        if executor.is_dry_run() {
+            print_matches(&snippet.path, &content, &regex, replacement);
+        }
+        executor.apply(Mutation::Write {
+            path: snippet.path.clone(),
+            contents: updated,
+        })?;
+        if !executor.is_dry_run() {
+            index::reindex_snippet(&snippet.path, &snippet.path);
+        }
+        files_changed += 1;
+        replacements += count;
+    }
+    Ok((files_changed, replacements))
+}
+
+/// Reads the snippet at `path` and applies the replacement to its code,
+/// returning the content, the updated content, and the number of
+/// replacements. `None` when nothing would change, and for files that
+/// [`plan_replacements`] skips too: unreadable, binary, not UTF-8, or over the
+/// parse limit.
+fn replace_in_snippet(
+    path: &Path,
+    regex: &Regex,
+    replacement: &str,
+) -> Option<(String, String, usize)> {
+    let bytes = snippet::read_limited(path).ok()??;
+    if snippet::is_binary(&bytes) {
+        return None;
+    }
+    let content = String::from_utf8(bytes).ok()?;
+    let (updated, count) = if snippet::is_markdown(path) {
+        replace_in_code_blocks(&content, regex, replacement)
+    } else {
+        replace_in_code(&content, regex, replacement)
+    };
+    (count > 0 && updated != content).then_some((content, updated, count))
+}
+
+/// Applies the replacement to the inside of every fenced code block in `content`.
+fn replace_in_code_blocks(content: &str, regex: &Regex, replacement: &str) -> (String, usize) {
+    let mut output = String::with_capacity(content.len());
+    let mut code: Option<String> = None;
+    let mut count = 0;
+
+    for line in content.split_inclusive('\n') {
+        let is_fence = line.trim_start().starts_with("```");
+        match code.as_mut() {
+            Some(block) if is_fence => {
+                let (replaced, block_count) = replace_in_code(block, regex, replacement);
+                output.push_str(&replaced);
+                output.push_str(line);
+                count += block_count;
+                code = None;
+            }
+            Some(block) => block.push_str(line),
+            None => {
+                output.push_str(line);
+                if is_fence {
+                    code = Some(String::new());
+                }
+            }
+        }
+    }
+    // An unterminated fence runs to the end of the file, as in `code_blocks`.
+    if let Some(block) = code {
+        let (replaced, block_count) = replace_in_code(&block, regex, replacement);
+        output.push_str(&replaced);
+        count += block_count;
+    }
+    (output, count)
+}
+
+fn replace_in_code(code: &str, regex: &Regex, replacement: &str) -> (String, usize) {
+    let count = regex.find_iter(code).count();
+    if count == 0 {
+        return (code.to_string(), 0);
+    }
+    (regex.replace_all(code, replacement).into_owned(), count)
+}
+
+/// Prints each code line of `path` that the pattern matches, before and after.
+fn print_matches(path: &Path, content: &str, regex: &Regex, replacement: &str) {
+    let markdown = snippet::is_markdown(path);
+    let mut in_code = !markdown;
+    for (index, line) in content.lines().enumerate() {
+        if markdown && line.trim_start().starts_with("```") {
+            in_code = !in_code;
+            continue;
+        }
+        if !in_code || !regex.is_match(line) {
+            continue;
+        }
+        println!("{}:{}", path.display(), index + 1);
+        println!("  {} {}", "-".error(), line.error());
+        println!(
+            "  {} {}",
+            "+".success(),
+            regex.replace_all(line, replacement).success()
+        );
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::testing;
+    use tempfile::TempDir;
+
+    /// A vault holding `files`, given as (relative path, content).
+    fn vault(files: &[(&str, &str)]) -> TempDir {
+        let dir = tempfile::tempdir().unwrap();
+        for (name, content) in files {
+            let path = dir.path().join(name);
+            fs::create_dir_all(path.parent().unwrap()).unwrap();
+            fs::write(path, content).unwrap();
+        }
+        dir
+    }
+
+    fn plan(
+        dir: &TempDir,
+        pattern: &str,
+        regex: bool,
+        replacement: &str,
+        include_archived: bool,
+    ) -> Vec<FileReplacement> {
+        plan_replacements(
+            dir.path(),
+            &build_pattern(pattern, regex).unwrap(),
+            replacement,
+            regex,
+            &SnippetFilter::default(),
+            include_archived,
+            &["md".to_string()],
+            false,
+        )
+        .unwrap()
+    }
+
+    #[test]
+    fn regex_mode_expands_captures() {
+        let dir = vault(&[(
+            "mail.md",
+            "```bash\nmail ana@example.com bo@example.com\n```\n",
+        )]);
+        let planned = plan(&dir, r"(\w+)@example\.com", true, "$1@corp.test", false);
+        assert_eq!(planned.len(), 1);
+        assert_eq!(planned[0].count, 2);
+        assert_eq!(
+            planned[0].updated,
+            "```bash\nmail ana@corp.test bo@corp.test\n```\n"
+        );
+    }
+
+    #[test]
+    fn literal_mode_escapes_pattern_and_replacement() {
+        let dir = vault(&[("price.md", "total: a.b (axb) a.b\n")]);
+        let planned = plan(&dir, "a.b", false, "$1 ${x}", false);
+        assert_eq!(planned[0].count, 2);
+        assert_eq!(planned[0].updated, "total: $1 ${x} (axb) $1 ${x}\n");
+    }
+
+    #[test]
+    fn counts_every_match_on_a_line() {
+        let dir = vault(&[("loop.md", "foo foo foo\nbar\nfoo\n")]);
+        let planned = plan(&dir, "foo", false, "baz", false);
+        assert_eq!(planned[0].count, 4);
+        assert_eq!(planned[0].updated, "baz baz baz\nbar\nbaz\n");
+    }
+
+    #[test]
+    fn archived_snippets_are_skipped_unless_included() {
+        let dir = vault(&[
+            ("live.md", "old name\n"),
+            (
+                "gone.md",
+                "+++\ntitle = \"Gone\"\narchived = true\n+++\nold name\n",
+            ),
+        ]);
+        let planned = plan(&dir, "old", false, "new", false);
+        assert_eq!(planned.len(), 1);
+        assert!(planned[0].path.ends_with("live.md"));
+        assert_eq!(plan(&dir, "old", false, "new", true).len(), 2);
+    }
+
+    #[test]
+    fn apply_keeps_crlf_line_endings() {
+        testing::isolate_user_dirs();
+        let dir = vault(&[("win.md", "```bat\r\necho old\r\nset X=old\r\n```\r\n")]);
+        let planned = plan(&dir, "old", false, "new", false);
+        assert_eq!(planned[0].count, 2);
+
+        apply_replacement(&planned[0], &Executor::new(false)).unwrap();
+        assert_eq!(
+            fs::read_to_string(dir.path().join("win.md")).unwrap(),
+            "```bat\r\necho new\r\nset X=new\r\n```\r\n"
+        );
+    }
+
+    #[test]
+    fn apply_refuses_a_file_changed_since_planning() {
+        let dir = vault(&[("race.md", "old\n")]);
+        let planned = plan(&dir, "old", false, "new", false);
+        fs::write(dir.path().join("race.md"), "old and edited\n").unwrap();
+        assert!(apply_replacement(&planned[0], &Executor::new(true)).is_err());
+        assert_eq!(
+            fs::read_to_string(dir.path().join("race.md")).unwrap(),
+            "old and edited\n"
+        );
+    }
+
+    #[test]
+    fn dry_run_apply_leaves_the_file_alone() {
+        let dir = vault(&[("keep.md", "old\n")]);
+        let planned = plan(&dir, "old", false, "new", false);
+        apply_replacement(&planned[0], &Executor::new(true)).unwrap();
+        assert_eq!(
+            fs::read_to_string(dir.path().join("keep.md")).unwrap(),
+            "old\n"
+        );
+    }
+
+    #[test]
+    fn regex_replace_skips_unreadable_and_unchanged_files() {
+        testing::isolate_user_dirs();
+        let dir = vault(&[
+            ("code.md", "```sh\necho old\n```\n"),
+            ("same.md", "```sh\necho new\n```\n"),
+        ]);
+        // Matches inside code blocks, so only the skip keeps them unchanged.
+        let binary: &[u8] = b"```sh\necho old\0old\n```\n";
+        let latin1: &[u8] = b"```sh\necho old caf\xe9\n```\n";
+        fs::write(dir.path().join("binary.md"), binary).unwrap();
+        fs::write(dir.path().join("latin1.md"), latin1).unwrap();
+        let regex = Regex::new("old|new").unwrap();
+        let replace = |name: &str| replace_in_snippet(&dir.path().join(name), &regex, "new");
+
+        let (content, updated, count) = replace("code.md").unwrap();
+        assert_eq!(content, "```sh\necho old\n```\n");
+        assert_eq!(updated, "```sh\necho new\n```\n");
+        assert_eq!(count, 1);
+        assert_eq!(replace("same.md"), None);
+        assert_eq!(replace("binary.md"), None);
+        assert_eq!(replace("latin1.md"), None);
+        assert_eq!(replace("missing.md"), None);
+
+        let changed = regex_replace_in_snippets(
+            dir.path(),
+            "old|new",
+            "new",
+            &SnippetFilter::default(),
+            &["md".to_string()],
+            false,
+            &Executor::new(false),
+        )
+        .unwrap();
+        assert_eq!(changed, (1, 1));
+        let read = |name: &str| fs::read(dir.path().join(name)).unwrap();
+        assert_eq!(read("code.md"), b"```sh\necho new\n```\n");
+        assert_eq!(read("binary.md"), binary);
+        assert_eq!(read("latin1.md"), latin1);
+    }
+}
@@ -0,0 +1,404 @@
+use aws_sdk_s3::config::{BehaviorVersion, Credentials, Region};
+use aws_sdk_s3::primitives::ByteStream;
+use chrono::{DateTime, Local};
+use serde::Deserialize;
+use std::env;
+use std::fmt;
+use std::fs;
+use std::io::Write;
+use std::path::{Path, PathBuf};
+
+use crate::error::SnippetVaultError;
+
+/// Region used when `[s3_backup]` does not name one.
+pub const DEFAULT_REGION: &str = "us-east-1";
+
+/// Where vault backups are uploaded, from the `[s3_backup]` config section.
+#[derive(Clone, Default, Deserialize)]
+#[serde(default)]
+pub struct S3Config {
+    pub bucket: String,
+    /// Key prefix ("folder") inside the bucket; may be empty.
+    pub prefix: String,
+    pub region: String,
+    /// Falls back to `AWS_ACCESS_KEY_ID` when empty.
+    pub access_key_id: String,
+    /// Falls back to `AWS_SECRET_ACCESS_KEY` when empty.
+    pub secret_access_key: String,
+    /// Token of temporary credentials; falls back to `AWS_SESSION_TOKEN`.
+    pub session_token: String,
+    /// Endpoint of an S3-compatible service (MinIO, R2, ...); AWS when unset.
+    pub endpoint: Option<String>,
+}
+
+// Hand-written so the secret never ends up in debug logs.
+impl fmt::Debug for S3Config {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("S3Config")
+            .field("bucket", &self.bucket)
+            .field("prefix", &self.prefix)
+            .field("region", &self.region)
+            .field("endpoint", &self.endpoint)
+            .finish_non_exhaustive()
+    }
+}
+
+impl S3Config {
+    /// Replaces the bucket and prefix with those of `remote`, an
+    /// `s3://bucket/prefix` URL given on the command line.
+    pub fn with_remote(mut self, remote: &str) -> Result<Self, SnippetVaultError> {
+        let (bucket, prefix) = parse_remote(remote)?;
+        self.bucket = bucket;
+        self.prefix = prefix;
+        Ok(self)
+    }
+
+    /// Fills in missing credentials like the AWS tools do: from the standard
+    /// environment variables, then from the `AWS_PROFILE` (or `default`)
+    /// profile of the shared credentials file. The region comes from
+    /// `AWS_REGION` or [`DEFAULT_REGION`]. Then checks nothing required is missing.
+    pub fn resolve(mut self) -> Result<Self, SnippetVaultError> {
+        if self.access_key_id.is_empty() && self.secret_access_key.is_empty() {
+            self.access_key_id = env::var("AWS_ACCESS_KEY_ID").unwrap_or_default();
+            self.secret_access_key = env::var("AWS_SECRET_ACCESS_KEY").unwrap_or_default();
+            self.session_token = env::var("AWS_SESSION_TOKEN").unwrap_or_default();
+        }
+        if self.access_key_id.is_empty() && self.secret_access_key.is_empty() {
+            if let Some(profile) = profile_credentials() {
+                self.access_key_id = profile.access_key_id;
+                self.secret_access_key = profile.secret_access_key;
+                self.session_token = profile.session_token;
+            }
+        }
+        if self.region.is_empty() {
+            self.region = env::var("AWS_REGION")
+                .or_else(|_| env::var("AWS_DEFAULT_REGION"))
+                .unwrap_or_else(|_| DEFAULT_REGION.to_string());
+        }
+
+        if self.bucket.is_empty() {
+            return Err(SnippetVaultError::Config(
+                "[s3_backup] needs a bucket".to_string(),
+            ));
+        }
+        if self.access_key_id.is_empty() || self.secret_access_key.is_empty() {
+            return Err(SnippetVaultError::Config(
+                "no S3 credentials: set access_key_id and secret_access_key in [s3_backup], AWS_ACCESS_KEY_ID and AWS_SECRET_ACCESS_KEY, or a profile in ~/.aws/credentials".to_string(),
+            ));
+        }
+        Ok(self)
+    }
+
+    /// Object key for the archive at `vault_zip`: its file name under `prefix`.
+    pub fn key_for(&self, vault_zip: &Path) -> String {
+        let name = vault_zip
+            .file_name()
+            .map(|name| name.to_string_lossy().into_owned())
+            .unwrap_or_else(|| "snippets_vault.zip".to_string());
+        match self.prefix.trim_matches('/') {
+            "" => name,
+            prefix => format!("{}/{}", prefix, name),
+        }
+    }
+
+    /// Human-readable location of the object `key`.
+    pub fn url_for(&self, key: &str) -> String {
+        match &self.endpoint {
+            Some(endpoint) => format!("{}/{}/{}", endpoint.trim_end_matches('/'), self.bucket, key),
+            None => format!("s3://{}/{}", self.bucket, key),
+        }
+    }
+}
+
+/// Splits an `s3://bucket/prefix` URL into the bucket and the prefix, which
+/// may be empty.
+pub fn parse_remote(remote: &str) -> Result<(String, String), SnippetVaultError> {
+    let path = remote.strip_prefix("s3://").ok_or_else(|| {
+        SnippetVaultError::Config(format!("{} is not an s3://bucket/prefix URL", remote))
+    })?;
+    let (bucket, prefix) = path.split_once('/').unwrap_or((path, ""));
+    if bucket.is_empty() {
+        return Err(SnippetVaultError::Config(format!(
+            "{} names no bucket",
+            remote
+        )));
+    }
+    Ok((bucket.to_string(), prefix.trim_matches('/').to_string()))
+}
+
+/// Credentials of a profile in the shared credentials file.
+struct ProfileCredentials {
+    access_key_id: String,
+    secret_access_key: String,
+    session_token: String,
+}
+
+/// Credentials of the `AWS_PROFILE` profile (`default` when unset) in
+/// `AWS_SHARED_CREDENTIALS_FILE` or `~/.aws/credentials`, if it has both keys.
+fn profile_credentials() -> Option<ProfileCredentials> {
+    let path = env::var_os("AWS_SHARED_CREDENTIALS_FILE")
+        .map(PathBuf::from)
+        .or_else(|| dirs::home_dir().map(|home| home.join(".aws").join("credentials")))?;
+    let profile = env::var("AWS_PROFILE").unwrap_or_else(|_| "default".to_string());
+    let content = fs::read_to_string(path).ok()?;
+
+    let mut credentials = ProfileCredentials {
+        access_key_id: String::new(),
+        secret_access_key: String::new(),
+        session_token: String::new(),
+    };
+    let mut in_profile = false;
+    for line in content.lines().map(str::trim) {
+        if let Some(section) = line
+            .strip_prefix('[')
+            .and_then(|line| line.strip_suffix(']'))
+        {
+            in_profile = section.trim() == profile;
+        } else if let Some((key, value)) = line.split_once('=').filter(|_| in_profile) {
+            let value = value.trim().to_string();
+            match key.trim() {
+                "aws_access_key_id" => credentials.access_key_id = value,
+                "aws_secret_access_key" => credentials.secret_access_key = value,
+                "aws_session_token" => credentials.session_token = value,
+                _ => {}
+            }
+        }
+    }
+    (!credentials.access_key_id.is_empty() && !credentials.secret_access_key.is_empty())
+        .then_some(credentials)
+}
+
+/// A vault backup stored in the bucket.
+#[derive(Debug, Clone)]
+pub struct RemoteBackup {
+    pub key: String,
+    /// File name of the archive, which holds the time it was made.
+    pub name: String,
+    pub size: u64,
+    pub uploaded: Option<DateTime<Local>>,
+}
+
+/// Uploads the archive at `vault_zip` to `{bucket}/{prefix}/` and returns its URL.
+/// The archive is streamed from disk, and is left in place whether or not the
+/// upload succeeds.
+pub fn backup_to_s3(config: &S3Config, vault_zip: &Path) -> Result<String, SnippetVaultError> {
+    let key = config.key_for(vault_zip);
+    block_on(upload(config, vault_zip, &key))?;
+    Ok(config.url_for(&key))
+}
+
+/// The vault backups under `{bucket}/{prefix}/`, oldest first. Their names
+/// start with the time they were made, so they sort by it.
+pub fn list_backups(config: &S3Config) -> Result<Vec<RemoteBackup>, SnippetVaultError> {
+    let mut backups = block_on(list(config))?;
+    backups.sort_by(|a, b| a.name.cmp(&b.name));
+    Ok(backups)
+}
+
+/// Downloads `backup` into `dir` and returns the path of the archive. The body
+/// is written as it arrives; a local archive of the same name is only
+/// replaced once the download is complete, and kept when it fails.
+pub fn download_backup(
+    config: &S3Config,
+    backup: &RemoteBackup,
+    dir: &Path,
+) -> Result<PathBuf, SnippetVaultError> {
+    fs::create_dir_all(dir)?;
+    replace_when_complete(dir, &backup.name, |file| {
+        block_on(download(config, &backup.key, file))
+    })
+}
+
+/// Writes the file `name` in `dir` through `fill`, into a temporary file next
+/// to it that is renamed over `name` only when `fill` succeeds. On failure the
+/// temporary file is removed and an existing `name` is left alone.
+fn replace_when_complete(
+    dir: &Path,
+    name: &str,
+    fill: impl FnOnce(&mut fs::File) -> Result<(), SnippetVaultError>,
+) -> Result<PathBuf, SnippetVaultError> {
+    let mut partial = tempfile::Builder::new()
+        .prefix(".download")
+        .tempfile_in(dir)?;
+    fill(partial.as_file_mut())?;
+    let path = dir.join(name);
+    partial.persist(&path).map_err(|err| err.error)?;
+    Ok(path)
+}
+
+/// Deletes `backups` from the bucket.
+pub fn delete_backups(
+    config: &S3Config,
+    backups: &[RemoteBackup],
+) -> Result<(), SnippetVaultError> {
+    block_on(async {
+        let client = client(config);
+        for backup in backups {
+            tracing::debug!(bucket = %config.bucket, key = %backup.key, "deleting backup");
+            client
+                .delete_object()
+                .bucket(&config.bucket)
+                .key(&backup.key)
+                .send()
+                .await
+                .map_err(|err| SnippetVaultError::Backup(error_chain(&err)))?;
+        }
+        Ok(())
+    })
+}
+
+/// The S3 client is async, so each operation runs on a single-threaded tokio runtime.
+fn block_on<T>(
+    future: impl std::future::Future<Output = Result<T, SnippetVaultError>>,
+) -> Result<T, SnippetVaultError> {
+    tokio::runtime::Builder::new_current_thread()
+        .enable_all()
+        .build()?
+        .block_on(future)
+}
+
+fn client(config: &S3Config) -> aws_sdk_s3::Client {
+    let session_token = Some(config.session_token.clone()).filter(|token| !token.is_empty());
+    let credentials = Credentials::new(
+        &config.access_key_id,
+        &config.secret_access_key,
+        session_token,
+        None,
+        "snippets_vault",
+    );
+    let mut builder = aws_sdk_s3::Config::builder()
+        .behavior_version(BehaviorVersion::latest())
+        .region(Region::new(config.region.clone()))
+        .credentials_provider(credentials);
+    if let Some(endpoint) = &config.endpoint {
+        // Most S3-compatible services do not support bucket subdomains.
+        builder = builder.endpoint_url(endpoint).force_path_style(true);
+    }
+    aws_sdk_s3::Client::from_conf(builder.build())
+}
+
+async fn upload(config: &S3Config, vault_zip: &Path, key: &str) -> Result<(), SnippetVaultError> {
+    let client = client(config);
+    let body = ByteStream::from_path(vault_zip)
+        .await
+        .map_err(|err| SnippetVaultError::Backup(err.to_string()))?;
+    tracing::debug!(bucket = %config.bucket, %key, "uploading backup");
+    client
+        .put_object()
+        .bucket(&config.bucket)
+        .key(key)
+        .content_type("application/zip")
+        .body(body)
+        .send()
+        .await
+        .map_err(|err| SnippetVaultError::Backup(error_chain(&err)))?;
+    Ok(())
+}
+
+async fn list(config: &S3Config) -> Result<Vec<RemoteBackup>, SnippetVaultError> {
+    let client = client(config);
+    let prefix = match config.prefix.trim_matches('/') {
+        "" => String::new(),
+        prefix => format!("{}/", prefix),
+    };
+    let mut backups = Vec::new();
+    let mut continuation = None;
+    loop {
+        let page = client
+            .list_objects_v2()
+            .bucket(&config.bucket)
+            .prefix(&prefix)
+            .set_continuation_token(continuation)
+            .send()
+            .await
+            .map_err(|err| SnippetVaultError::Backup(error_chain(&err)))?;
+        for object in page.contents() {
+            let Some(key) = object.key() else { continue };
+            // Only archives directly under the prefix, named like local backups.
+            let name = &key[prefix.len()..];
+            if name.contains('/') || !name.starts_with("snippets_vault_") || !name.ends_with(".zip")
+            {
+                continue;
+            }
+            backups.push(RemoteBackup {
+                key: key.to_string(),
+                name: name.to_string(),
+                size: object.size().unwrap_or(0).max(0) as u64,
+                uploaded: object
+                    .last_modified()
+                    .and_then(|time| DateTime::from_timestamp(time.secs(), 0))
+                    .map(|time| time.with_timezone(&Local)),
+            });
+        }
+        continuation = page.next_continuation_token().map(str::to_string);
+        if continuation.is_none() {
+            break;
+        }
+    }
+    Ok(backups)
+}
+
+async fn download(
+    config: &S3Config,
+    key: &str,
+    file: &mut fs::File,
+) -> Result<(), SnippetVaultError> {
+    let client = client(config);
+    tracing::debug!(bucket = %config.bucket, %key, "downloading backup");
+    let mut body = client
+        .get_object()
+        .bucket(&config.bucket)
+        .key(key)
+        .send()
+        .await
+        .map_err(|err| SnippetVaultError::Backup(error_chain(&err)))?
+        .body;
+    while let Some(chunk) = body.next().await {
+        let chunk = chunk.map_err(|err| SnippetVaultError::Backup(error_chain(&err)))?;
+        file.write_all(&chunk)?;
+    }
+    file.sync_all()?;
+    Ok(())
+}
+
+/// `err` followed by each of its sources, since the SDK's own message is
+/// usually just "dispatch failure" or "service error".
+fn error_chain(err: &dyn std::error::Error) -> String {
+    let mut message = err.to_string();
+    let mut source = err.source();
+    while let Some(cause) = source {
+        message.push_str(": ");
+        message.push_str(&cause.to_string());
+        source = cause.source();
+    }
+    message
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn a_failed_download_keeps_the_local_archive() {
+        let dir = tempfile::tempdir().unwrap();
+        let archive = dir.path().join("backup.zip");
+        fs::write(&archive, "local copy").unwrap();
+
+        let failed = replace_when_complete(dir.path(), "backup.zip", |file| {
+            file.write_all(b"partial")?;
+            Err(SnippetVaultError::Backup("connection reset".to_string()))
+        });
+        assert!(failed.is_err());
+        assert_eq!(fs::read_to_string(&archive).unwrap(), "local copy");
+        assert_eq!(fs::read_dir(dir.path()).unwrap().count(), 1);
+
+        let path = replace_when_complete(dir.path(), "backup.zip", |file| {
+            file.write_all(b"downloaded")?;
+            Ok(())
+        })
+        .unwrap();
+        assert_eq!(path, archive);
+        assert_eq!(fs::read_to_string(&archive).unwrap(), "downloaded");
+        assert_eq!(fs::read_dir(dir.path()).unwrap().count(), 1);
+    }
+}
@@ -0,0 +1,153 @@
+use notify::{RecursiveMode, Watcher};
+use std::fs;
+use std::path::{Component, Path, PathBuf};
+use std::process;
+use std::sync::mpsc;
+use std::thread;
+use std::time::Duration;
+use tiny_http::{Header, Response, Server};
+
+use crate::error::SnippetVaultError;
+use crate::export::export_html;
+use crate::platform::open_with_system;
+use crate::style::Styled;
+use crate::vault::Executor;
+
+/// How long to wait for a burst of filesystem events to settle before rebuilding.
+const REBUILD_DEBOUNCE: Duration = Duration::from_millis(300);
+
+/// Exports the vault to HTML in a temporary directory and serves it at `localhost:port`,
+/// rebuilding the site whenever a snippet changes. Runs until interrupted with Ctrl-C,
+/// which removes the temporary directory.
+pub fn serve_static(snippet_dir: &Path, port: u16) -> Result<(), SnippetVaultError> {
+    // The site is rebuilt inside a directory of its own with a random name,
+    // so no other user can guess the path and plant a file or symlink there.
+    let temp_dir = tempfile::Builder::new()
+        .prefix("snippets_vault_site")
+        .tempdir()?;
+    let site_dir = temp_dir.path().join("site");
+    export_html(snippet_dir, &site_dir, true, &Executor::new(false))?;
+
+    let server = Server::http(("127.0.0.1", port))
+        .map_err(|err| SnippetVaultError::Server(err.to_string()))?;
+    let address = format!("http://localhost:{}/", port);
+    println!("{} Serving snippets at {}", "✔".success(), address);
+    println!("{} Press Ctrl-C to stop.", "•".accent());
+
+    // `process::exit` skips destructors, so the handler removes it itself.
+    let cleanup_dir = temp_dir.path().to_path_buf();
+    ctrlc::set_handler(move || {
+        let _ = fs::remove_dir_all(&cleanup_dir);
+        println!("\n{} Server stopped.", "✔".success());
+        process::exit(0);
+    })
+    .map_err(|err| SnippetVaultError::Server(err.to_string()))?;
+
+    // Keep the watcher alive for as long as the server runs.
+    let _watcher = spawn_rebuilder(snippet_dir, &site_dir)?;
+
+    if let Err(err) = open_with_system(&address) {
+        println!("{} Could not open the browser: {}", "✘".error(), err);
+    }
+
+    for request in server.incoming_requests() {
+        let response = match resolve_request_path(&site_dir, request.url()) {
+            Some(path) => match fs::read(&path) {
+                Ok(bytes) => Response::from_data(bytes).with_header(content_type(&path)),
+                Err(_) => Response::from_string("404 Not Found").with_status_code(404),
+            },
+            None => Response::from_string("400 Bad Request").with_status_code(400),
+        };
+        let _ = request.respond(response);
+    }
+
+    Ok(())
+}
+
+/// Watches `snippet_dir` and re-exports the site into `site_dir` after each burst of changes.
+fn spawn_rebuilder(
+    snippet_dir: &Path,
+    site_dir: &Path,
+) -> Result<notify::RecommendedWatcher, SnippetVaultError> {
+    let (tx, rx) = mpsc::channel::<notify::Result<notify::Event>>();
+    let mut watcher = notify::recommended_watcher(tx)?;
+    watcher.watch(snippet_dir, RecursiveMode::Recursive)?;
+
+    let snippet_dir = snippet_dir.to_path_buf();
+    let site_dir = site_dir.to_path_buf();
+    thread::spawn(move || {
+        while let Ok(event) = rx.recv() {
+            if !event.is_ok_and(|event| !event.kind.is_access()) {
+                continue;
+            }
+            while rx.recv_timeout(REBUILD_DEBOUNCE).is_ok() {}
+
+            let _ = fs::remove_dir_all(&site_dir);
+            match export_html(&snippet_dir, &site_dir, true, &Executor::new(false)) {
+                Ok(count) => println!("{} Rebuilt {} pages.", "↻".accent(), count),
+                Err(err) => println!("{} Rebuild failed: {}", "✘".error(), err),
+            }
+        }
+    });
+
+    Ok(watcher)
+}
+
+/// Maps a request URL onto a file inside `site_dir`, rejecting paths that escape it.
+fn resolve_request_path(site_dir: &Path, url: &str) -> Option<PathBuf> {
+    let path = url.split(['?', '#']).next().unwrap_or_default();
+    let relative = PathBuf::from(percent_decode(path.trim_start_matches('/')));
+
+    if relative
+        .components()
+        .any(|component| !matches!(component, Component::Normal(_)))
+    {
+        return None;
+    }
+
+    let target = site_dir.join(relative);
+    if target.is_dir() {
+        Some(target.join("index.html"))
+    } else {
+        Some(target)
+    }
+}
+
+/// Decodes `%XX` escapes in a URL path.
+fn percent_decode(input: &str) -> String {
+    let bytes = input.as_bytes();
+    let mut decoded = Vec::with_capacity(bytes.len());
+    let mut i = 0;
+
+    while i < bytes.len() {
+        let escaped = (bytes[i] == b'%')
+            .then(|| input.get(i + 1..i + 3))
+            .flatten()
+            .and_then(|hex| u8::from_str_radix(hex, 16).ok());
+        match escaped {
+            Some(byte) => {
+                decoded.push(byte);
+                i += 3;
+            }
+            None => {
+                decoded.push(bytes[i]);
+                i += 1;
+            }
+        }
+    }
+
+    String::from_utf8_lossy(&decoded).into_owned()
+}
+
+/// Chooses a `Content-Type` header from the file extension.
+fn content_type(path: &Path) -> Header {
+    let mime = match path.extension().and_then(|ext| ext.to_str()) {
+        Some("html") => "text/html; charset=utf-8",
+        Some("css") => "text/css",
+        Some("js") => "application/javascript",
+        Some("png") => "image/png",
+        Some("svg") => "image/svg+xml",
+        _ => "text/plain; charset=utf-8",
+    };
+    Header::from_bytes("Content-Type", mime).expect("static header is valid")
+}
@@ -0,0 +1,136 @@
+use colored::*;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::process::Command as ProcessCommand;
+
+use crate::config::Config;
+use crate::error::SnippetVaultError;
+use crate::logging;
+use crate::platform::find_in_path;
+use crate::prompt::{ask, choose, confirm};
+use crate::style::Styled;
+use crate::vault::{default_snippet_dir, Executor, Mutation};
+
+/// Editors offered during setup, in order of preference.
+const KNOWN_EDITORS: &[&str] = &["nvim", "vim", "hx", "code"];
+
+/// Whether this looks like a fresh install: no configuration file and no vault
+/// at the default location.
+pub fn needs_onboarding() -> bool {
+    !Config::path().exists() && !default_snippet_dir().exists()
+}
+
+/// Walks through choosing the vault location and editor, optionally initialises a
+/// git repository in the vault, and writes `config.toml`. Settings in an existing
+/// configuration file that setup does not ask about are preserved.
+/// Returns the resulting configuration.
+pub fn run_setup(executor: &Executor) -> Result<Config, SnippetVaultError> {
+    println!("{}", "SnippetsVault setup".bold());
+
+    let default_dir = default_snippet_dir();
+    let home_snippets = dirs::home_dir().unwrap_or_default().join("snippets");
+    let locations = vec![
+        format!("{} (Obsidian default)", default_dir.display()),
+        home_snippets.display().to_string(),
+        "Custom path".to_string(),
+    ];
+    let vault_dir = match choose("Where should the vault live?", &locations)? {
+        0 => default_dir,
+        1 => home_snippets,
+        _ => PathBuf::from(shellexpand::tilde(&ask("Vault path:", "")?).into_owned()),
+    };
+
+    let mut editors: Vec<String> = KNOWN_EDITORS
+        .iter()
+        .filter(|editor| find_in_path(editor).is_some())
+        .map(|editor| editor.to_string())
+        .collect();
+    editors.push("Other".to_string());
+    let choice = choose("Which editor should open snippets?", &editors)?;
+    let editor = if choice == editors.len() - 1 {
+        ask("Editor command:", "nvim")?
+    } else {
+        editors[choice].clone()
+    };
+
+    let init_git = confirm("Initialise a git repository in the vault?", false)?;
+
+    let rendered = write_config(&vault_dir, editor, executor)?;
+
+    if init_git && !vault_dir.join(".git").exists() {
+        if executor.is_dry_run() {
+            println!(
+                "{} would run git init in {}",
+                "•".accent(),
+                vault_dir.display()
+            );
+        } else {
+            logging::run_quiet(ProcessCommand::new("git").arg("init").arg(&vault_dir))?;
+        }
+    }
+
+    println!(
+        "{} Configuration written to {}",
+        "✔".success(),
+        Config::path().display()
+    );
+    toml::from_str(&rendered).map_err(|err| SnippetVaultError::Config(err.to_string()))
+}
+
+/// Writes `config.toml` without asking anything: the default vault location
+/// and the first of [`KNOWN_EDITORS`] found on `PATH` (`nvim` when none is),
+/// for scripts and other runs without a terminal. Fails when a configuration
+/// file already exists. Returns the resulting configuration.
+pub fn init_config(executor: &Executor) -> Result<Config, SnippetVaultError> {
+    let config_path = Config::path();
+    if config_path.exists() {
+        return Err(SnippetVaultError::Config(format!(
+            "{} already exists; edit it or run --setup",
+            config_path.display()
+        )));
+    }
+
+    let editor = KNOWN_EDITORS
+        .iter()
+        .find(|editor| find_in_path(editor).is_some())
+        .unwrap_or(&"nvim");
+    let rendered = write_config(&default_snippet_dir(), editor.to_string(), executor)?;
+    println!(
+        "{} Configuration written to {}",
+        "✔".success(),
+        config_path.display()
+    );
+    toml::from_str(&rendered).map_err(|err| SnippetVaultError::Config(err.to_string()))
+}
+
+/// Sets `vault_dir` and `editor` in `config.toml`, keeping its other settings,
+/// creates the vault directory, and returns the written configuration text.
+fn write_config(
+    vault_dir: &Path,
+    editor: String,
+    executor: &Executor,
+) -> Result<String, SnippetVaultError> {
+    let config_path = Config::path();
+    let mut table: toml::Table = match fs::read_to_string(&config_path) {
+        Ok(raw) => raw
+            .parse()
+            .map_err(|err: toml::de::Error| SnippetVaultError::Config(err.to_string()))?,
+        Err(_) => toml::Table::new(),
+    };
+    table.insert(
+        "vault_dir".to_string(),
+        vault_dir.display().to_string().into(),
+    );
+    table.insert("editor".to_string(), editor.into());
+    let rendered = toml::to_string(&table).expect("config table is always serializable");
+
+    if let Some(parent) = config_path.parent() {
+        executor.apply(Mutation::CreateDir(parent.to_path_buf()))?;
+    }
+    executor.apply(Mutation::Write {
+        path: config_path,
+        contents: rendered.clone(),
+    })?;
+    executor.apply(Mutation::CreateDir(vault_dir.to_path_buf()))?;
+    Ok(rendered)
+}
@@ -0,0 +1,1125 @@
+use chrono::{DateTime, Local, NaiveDate, NaiveDateTime};
+use regex::Regex;
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use std::collections::BTreeMap;
+use std::fs::{self, File};
+use std::io::{self, Read};
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicU64, Ordering};
+
+use crate::dates;
+use crate::error::SnippetVaultError;
+use crate::language;
+use crate::style::Styled;
+
+/// Default format of the timestamp embedded in snippet filenames
+/// (see `timestamp_format` in the configuration).
+pub const TIMESTAMP_FORMAT: &str = "%Y-%m-%d-%H%M%S";
+
+/// Filename timestamp formats recognised when reading a vault: the default
+/// first, then common alternatives (including date-only ones).
+pub const TIMESTAMP_FORMATS: &[&str] = &[
+    TIMESTAMP_FORMAT,
+    "%Y-%m-%d-%H%M",
+    "%Y-%m-%d-%H-%M-%S",
+    "%Y-%m-%dT%H%M%S",
+    "%Y-%m-%dT%H-%M-%S",
+    "%Y%m%d-%H%M%S",
+    "%Y%m%dT%H%M%S",
+    "%Y%m%d%H%M%S",
+    "%Y%m%d-%H%M",
+    "%Y-%m-%d",
+    "%Y%m%d",
+];
+
+/// Characters that cannot appear in a filename timestamp: path separators,
+/// characters Windows rejects, and `_`, which separates filename components.
+const TIMESTAMP_ILLEGAL_CHARS: &[char] = &['/', '\\', ':', '*', '?', '"', '<', '>', '|', '_'];
+
+/// Files larger than this many bytes are listed but not parsed, unless
+/// `max_parse_size` in the configuration says otherwise.
+pub const DEFAULT_PARSE_LIMIT: u64 = 1024 * 1024;
+
+/// Leading bytes inspected for a NUL byte when telling binary files from text.
+const BINARY_SNIFF_BYTES: u64 = 8 * 1024;
+
+static PARSE_LIMIT: AtomicU64 = AtomicU64::new(DEFAULT_PARSE_LIMIT);
+
+/// Sets the size above which files are not parsed, for the rest of the process.
+pub fn set_parse_limit(bytes: u64) {
+    PARSE_LIMIT.store(bytes, Ordering::Relaxed);
+}
+
+/// Size in bytes above which files are listed from their filename alone.
+pub fn parse_limit() -> u64 {
+    PARSE_LIMIT.load(Ordering::Relaxed)
+}
+
+/// Reads the file at `path` unless it is over [`parse_limit`], in which case
+/// `None` is returned. The read itself stops at the limit, so a file that
+/// grows in the meantime is never loaded whole.
+pub fn read_limited(path: &Path) -> io::Result<Option<Vec<u8>>> {
+    let limit = parse_limit();
+    let mut content = Vec::new();
+    File::open(path)?
+        .take(limit.saturating_add(1))
+        .read_to_end(&mut content)?;
+    Ok((content.len() as u64 <= limit).then_some(content))
+}
+
+/// Reads the snippet at `path` as text for a command that goes over many
+/// snippets. A binary file, one over [`parse_limit`], or one that is not
+/// UTF-8 gives `None` after a warning on stderr, so the command skips it
+/// instead of failing (and stdout stays clean for exports).
+pub fn read_text(path: &Path) -> io::Result<Option<String>> {
+    let reason = match read_limited(path)? {
+        None => "it is over the parse limit",
+        Some(bytes) if is_binary(&bytes) => "it looks binary",
+        Some(bytes) => match String::from_utf8(bytes) {
+            Ok(text) => return Ok(Some(text)),
+            Err(_) => "it is not UTF-8 text",
+        },
+    };
+    eprintln!("{} Skipping {}: {}.", "⚠".warning(), path.display(), reason);
+    Ok(None)
+}
+
+/// Whether `content` looks binary: a NUL byte near its start.
+pub fn is_binary(content: &[u8]) -> bool {
+    content
+        .iter()
+        .take(BINARY_SNIFF_BYTES as usize)
+        .any(|byte| *byte == 0)
+}
+
+/// Whether the file at `path` looks binary, judged from its first bytes.
+pub fn is_binary_file(path: &Path) -> io::Result<bool> {
+    let mut head = Vec::new();
+    File::open(path)?
+        .take(BINARY_SNIFF_BYTES)
+        .read_to_end(&mut head)?;
+    Ok(is_binary(&head))
+}
+
+/// Byte order mark some Windows editors put at the start of UTF-8 files.
+pub const BOM: char = '\u{feff}';
+
+/// `content` without a leading byte order mark.
+pub fn strip_bom(content: &str) -> &str {
+    content.strip_prefix(BOM).unwrap_or(content)
+}
+
+/// How a file ends its lines.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum LineEnding {
+    #[default]
+    Lf,
+    Crlf,
+    /// Both kinds; rewrites leave such files' endings as they come.
+    Mixed,
+}
+
+/// The line endings and byte order mark of a file, so that rewriting it does
+/// not turn every line into a change in git.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct TextStyle {
+    pub line_ending: LineEnding,
+    pub bom: bool,
+}
+
+impl TextStyle {
+    /// The style of `content`; text without line breaks counts as LF.
+    pub fn of(content: &str) -> Self {
+        let lines = content.matches('\n').count();
+        let crlf = content.matches("\r\n").count();
+        let line_ending = match crlf {
+            0 => LineEnding::Lf,
+            crlf if crlf == lines => LineEnding::Crlf,
+            _ => LineEnding::Mixed,
+        };
+        TextStyle {
+            line_ending,
+            bom: content.starts_with(BOM),
+        }
+    }
+
+    /// The style of the file at `path`, or `None` when it cannot be read as text.
+    pub fn of_file(path: &Path) -> Option<Self> {
+        fs::read_to_string(path)
+            .ok()
+            .map(|content| Self::of(&content))
+    }
+
+    /// `contents` with this style's line endings and byte order mark. Line
+    /// endings are only converted for CRLF files: LF files are written as
+    /// generated, and mixed ones keep whatever `contents` has.
+    pub fn apply(self, contents: &str) -> String {
+        let contents = strip_bom(contents);
+        let mut styled = String::with_capacity(contents.len() + 3);
+        if self.bom {
+            styled.push(BOM);
+        }
+        if self.line_ending == LineEnding::Crlf {
+            styled.push_str(&contents.replace("\r\n", "\n").replace('\n', "\r\n"));
+        } else {
+            styled.push_str(contents);
+        }
+        styled
+    }
+}
+
+/// Line that opens and closes the TOML frontmatter block (v2 snippets).
+pub const FRONTMATTER_DELIMITER: &str = "+++";
+
+/// Metadata stored at the top of a v2 snippet.
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct Frontmatter {
+    #[serde(default)]
+    pub title: String,
+    #[serde(default)]
+    pub language: String,
+    #[serde(default)]
+    pub tags: Vec<String>,
+    #[serde(
+        default,
+        skip_serializing_if = "Option::is_none",
+        deserialize_with = "deserialize_created"
+    )]
+    pub created: Option<NaiveDateTime>,
+    /// Relationships to other snippets.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub links: Vec<SnippetLink>,
+    /// User-defined fields (`project = "acme"`, `reviewed = true`), kept as written.
+    #[serde(flatten)]
+    pub extra: BTreeMap<String, toml::Value>,
+}
+
+/// Reads `created` as a string or a bare TOML date-time, see [`dates::parse_created`].
+fn deserialize_created<'de, D: serde::Deserializer<'de>>(
+    deserializer: D,
+) -> Result<Option<NaiveDateTime>, D::Error> {
+    use serde::de::Error;
+
+    let text = match toml::Value::deserialize(deserializer)? {
+        toml::Value::String(text) => text,
+        toml::Value::Datetime(time) => time.to_string(),
+        other => {
+            return Err(D::Error::custom(format!(
+                "expected a date and time, got {}",
+                other
+            )))
+        }
+    };
+    dates::parse_created(&text)
+        .map(Some)
+        .ok_or_else(|| D::Error::custom(format!("'{}' is not a date and time", text)))
+}
+
+/// Frontmatter keys with a fixed meaning, which `--set_field` does not touch.
+pub const RESERVED_FIELDS: &[&str] = &["title", "language", "tags", "created", "links"];
+
+/// A typed relationship from one snippet to another, e.g. `uses` or `see-also`.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct SnippetLink {
+    /// File name (or vault-relative path) of the linked snippet.
+    pub target: String,
+    #[serde(default = "default_relation")]
+    pub relation: String,
+}
+
+fn default_relation() -> String {
+    "related".to_string()
+}
+
+impl Frontmatter {
+    /// Parses the frontmatter block of `content`, if there is one.
+    /// - `path`: Used only for error messages.
+    pub fn parse(path: &Path, content: &str) -> Result<Option<Self>, SnippetVaultError> {
+        match split_frontmatter(content).0 {
+            Some(block) => {
+                toml::from_str(block)
+                    .map(Some)
+                    .map_err(|err| SnippetVaultError::Parse {
+                        path: path.to_path_buf(),
+                        message: err.message().to_string(),
+                    })
+            }
+            None => Ok(None),
+        }
+    }
+
+    /// Returns the snippet's frontmatter, synthesising it from the filename for v1 snippets.
+    pub fn load(path: &Path, content: &str) -> Result<Self, SnippetVaultError> {
+        Ok(Self::parse(path, content)?.unwrap_or_else(|| Self::from_v1(path, content)))
+    }
+
+    /// Renders the frontmatter as a delimited TOML block, ready to prepend to a snippet body.
+    pub fn render(&self) -> String {
+        let body = toml::to_string(self).expect("frontmatter is always serializable");
+        format!(
+            "{}\n{}{}\n",
+            FRONTMATTER_DELIMITER, body, FRONTMATTER_DELIMITER
+        )
+    }
+
+    /// Synthesises frontmatter for a plain code file: the language comes from the
+    /// generated filename or the file extension, the title from the first comment
+    /// line or the file name.
+    pub fn from_plain(path: &Path, content: &str) -> Self {
+        let language = parse_language_from_filename(path)
+            .or_else(|| {
+                path.extension()
+                    .and_then(|ext| ext.to_str())
+                    .and_then(language::language_for_extension)
+                    .map(str::to_string)
+            })
+            .unwrap_or_default();
+        let title = language::comment_prefix(&language)
+            .and_then(|prefix| parse_comment_title(strip_bom(content), prefix))
+            .unwrap_or_else(|| {
+                path.file_stem()
+                    .map(|stem| stem.to_string_lossy().to_string())
+                    .unwrap_or_default()
+            });
+
+        Frontmatter {
+            title,
+            language,
+            tags: parse_tags_from_filename(path),
+            created: parse_timestamp_from_filename(path),
+            links: Vec::new(),
+            extra: BTreeMap::new(),
+        }
+    }
+
+    /// Synthesises frontmatter for a v1 snippet from its filename and `# Title:` line.
+    pub fn from_v1(path: &Path, content: &str) -> Self {
+        let language = parse_language_from_filename(path).unwrap_or_default();
+        let title = parse_title(content).unwrap_or_else(|| format!("{} - Snippet", language));
+
+        Frontmatter {
+            title,
+            language,
+            tags: parse_tags_from_filename(path),
+            created: parse_timestamp_from_filename(path),
+            links: Vec::new(),
+            extra: BTreeMap::new(),
+        }
+    }
+}
+
+/// Parsed metadata of one snippet, as used by listings and reports.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct SnippetMeta {
+    pub path: PathBuf,
+    pub title: String,
+    pub language: String,
+    pub tags: Vec<String>,
+    pub created: Option<NaiveDateTime>,
+    /// First line of prose below the title, used as a one-line description.
+    pub summary: Option<String>,
+    /// User-defined frontmatter fields, with non-string values in TOML notation.
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    pub extra: BTreeMap<String, String>,
+    #[serde(flatten)]
+    pub stats: SnippetStats,
+}
+
+/// Size of a snippet's body (frontmatter excluded).
+#[derive(Debug, Clone, Copy, Default, PartialEq, Serialize, Deserialize)]
+pub struct SnippetStats {
+    pub lines: usize,
+    pub words: usize,
+    pub bytes: usize,
+    /// The file contains NUL bytes; only `bytes` is meaningful.
+    pub binary: bool,
+}
+
+impl SnippetStats {
+    /// Counts the lines, words, and bytes of `content` after its frontmatter.
+    /// Content with NUL bytes is treated as binary and only its size is reported.
+    pub fn of(content: &[u8]) -> Self {
+        if is_binary(content) {
+            return SnippetStats {
+                bytes: content.len(),
+                binary: true,
+                ..Default::default()
+            };
+        }
+
+        let text = String::from_utf8_lossy(content);
+        let body = split_frontmatter(&text).1;
+        SnippetStats {
+            lines: body.lines().count(),
+            words: body.split_whitespace().count(),
+            bytes: body.len(),
+            binary: false,
+        }
+    }
+}
+
+impl SnippetMeta {
+    /// Reads and parses the snippet at `path`. Malformed frontmatter falls back to
+    /// the metadata encoded in the filename rather than failing the whole listing,
+    /// and so do binary files, files that are not valid UTF-8, and files over
+    /// [`parse_limit`], none of which are parsed.
+    pub fn read(path: &Path) -> Result<Self, SnippetVaultError> {
+        let Some(raw) = read_limited(path)? else {
+            let stats = SnippetStats {
+                bytes: fs::metadata(path)?.len() as usize,
+                binary: is_binary_file(path)?,
+                ..Default::default()
+            };
+            return Ok(Self::from_filename(path, stats));
+        };
+        let stats = SnippetStats::of(&raw);
+        if stats.binary {
+            return Ok(Self::from_filename(path, stats));
+        }
+        let Ok(content) = std::str::from_utf8(&raw) else {
+            return Ok(Self::from_filename(path, stats));
+        };
+        let content = strip_bom(content);
+        let (frontmatter, summary) = if is_markdown(path) {
+            let frontmatter = Frontmatter::parse(path, content)
+                .ok()
+                .flatten()
+                .unwrap_or_else(|| Frontmatter::from_v1(path, content));
+            (frontmatter, extract_summary(content))
+        } else {
+            (Frontmatter::from_plain(path, content), None)
+        };
+
+        Ok(SnippetMeta {
+            path: path.to_path_buf(),
+            title: frontmatter.title,
+            language: frontmatter.language,
+            tags: frontmatter.tags,
+            created: frontmatter
+                .created
+                .or_else(|| parse_timestamp_from_filename(path))
+                .or_else(|| modified_time(path)),
+            summary,
+            extra: frontmatter
+                .extra
+                .into_iter()
+                .map(|(key, value)| (key, field_to_string(&value)))
+                .collect(),
+            stats,
+        })
+    }
+
+    /// Metadata derived from the filename of `path` alone, for files that are
+    /// not parsed.
+    fn from_filename(path: &Path, stats: SnippetStats) -> Self {
+        let frontmatter = Frontmatter::from_plain(path, "");
+        SnippetMeta {
+            path: path.to_path_buf(),
+            title: frontmatter.title,
+            language: frontmatter.language,
+            tags: frontmatter.tags,
+            created: frontmatter.created.or_else(|| modified_time(path)),
+            summary: None,
+            extra: BTreeMap::new(),
+            stats,
+        }
+    }
+}
+
+/// Last modification time of the file at `path`, in local time.
+pub fn modified_time(path: &Path) -> Option<NaiveDateTime> {
+    let modified = fs::metadata(path).and_then(|meta| meta.modified()).ok()?;
+    Some(DateTime::<Local>::from(modified).naive_local())
+}
+
+/// Renders a frontmatter value for display and `--where` matching: strings
+/// as-is, everything else in TOML notation.
+pub fn field_to_string(value: &toml::Value) -> String {
+    match value {
+        toml::Value::String(text) => text.clone(),
+        other => other.to_string(),
+    }
+}
+
+/// Parses a value given on the command line: booleans and numbers keep their
+/// type, anything else is stored as a string.
+pub fn field_from_str(text: &str) -> toml::Value {
+    if let Ok(flag) = text.parse::<bool>() {
+        toml::Value::Boolean(flag)
+    } else if let Ok(number) = text.parse::<i64>() {
+        toml::Value::Integer(number)
+    } else if let Ok(number) = text.parse::<f64>() {
+        toml::Value::Float(number)
+    } else {
+        toml::Value::String(text.to_string())
+    }
+}
+
+/// A fenced code block inside a snippet.
+#[derive(Debug, Clone, PartialEq)]
+pub struct CodeBlock {
+    /// Language from the opening fence (`` ```rust ``); empty when none was given.
+    pub language: String,
+    /// The code between the fences, with a trailing newline per line.
+    pub code: String,
+    /// 1-based line number of the opening fence in the whole file.
+    pub start_line: usize,
+}
+
+/// The opening line of a fenced code block: three or more backticks or
+/// tildes. Only a line of the same character, at least as long, closes it, so
+/// a `` ``` `` line inside a `~~~~` block is code.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Fence {
+    marker: char,
+    length: usize,
+}
+
+impl Fence {
+    /// The fence `line` opens and its info string, or `None` when `line` is
+    /// not an opening fence.
+    pub fn open(line: &str) -> Option<(Fence, &str)> {
+        let trimmed = line.trim_start();
+        let marker = trimmed.chars().next().filter(|c| matches!(c, '`' | '~'))?;
+        let length = trimmed.len() - trimmed.trim_start_matches(marker).len();
+        let info = &trimmed[length..];
+        // A backtick in the info string makes the line inline code instead.
+        if length < 3 || (marker == '`' && info.contains('`')) {
+            return None;
+        }
+        Some((Fence { marker, length }, info.trim()))
+    }
+
+    /// Whether `line` closes the block this fence opened.
+    pub fn is_closed_by(&self, line: &str) -> bool {
+        let trimmed = line.trim();
+        let length = trimmed.len() - trimmed.trim_start_matches(self.marker).len();
+        length >= self.length && length == trimmed.len()
+    }
+}
+
+/// Extracts every fenced code block from `content` (see [`Fence`]). An
+/// unterminated fence runs to the end.
+pub fn code_blocks(content: &str) -> Vec<CodeBlock> {
+    let mut blocks = Vec::new();
+    let mut current: Option<(Fence, CodeBlock)> = None;
+
+    for (index, line) in content.lines().enumerate() {
+        match current.as_mut() {
+            Some((fence, _)) if fence.is_closed_by(line) => {
+                blocks.extend(current.take().map(|(_, block)| block));
+            }
+            Some((_, block)) => {
+                block.code.push_str(line);
+                block.code.push('\n');
+            }
+            None => {
+                if let Some((fence, info)) = Fence::open(line) {
+                    let block = CodeBlock {
+                        language: info.split_whitespace().next().unwrap_or("").to_string(),
+                        code: String::new(),
+                        start_line: index + 1,
+                    };
+                    current = Some((fence, block));
+                }
+            }
+        }
+    }
+
+    blocks.extend(current.map(|(_, block)| block));
+    blocks
+}
+
+/// Hex-encoded SHA-256 of the code in the snippet at `path`: its fenced code
+/// blocks concatenated for markdown, the file body for plain code files.
+/// Prose and frontmatter do not count, so retitling or retagging a snippet
+/// keeps its hash. Files that are not UTF-8 are hashed byte for byte.
+pub fn hash_code_blocks(path: &Path) -> Result<String, SnippetVaultError> {
+    let raw = fs::read(path)?;
+    let mut hasher = Sha256::new();
+    match std::str::from_utf8(&raw) {
+        Ok(content) if is_markdown(path) => {
+            for block in code_blocks(content) {
+                hasher.update(block.code.as_bytes());
+            }
+        }
+        Ok(content) => hasher.update(split_frontmatter(content).1.as_bytes()),
+        Err(_) => hasher.update(&raw),
+    }
+    Ok(hex::encode(hasher.finalize()))
+}
+
+/// The 1-based line where editing a new snippet starts: the first line inside
+/// the first code fence of a markdown snippet, or the line after the content
+/// of a plain code file.
+pub fn entry_line(path: &Path, content: &str) -> Option<usize> {
+    if is_markdown(path) {
+        code_blocks(content)
+            .first()
+            .map(|block| block.start_line + 1)
+    } else {
+        Some(content.lines().count() + 1)
+    }
+}
+
+/// Returns the first line of prose in `content`: frontmatter, headings (including
+/// the `# Title:` line), blank lines, and fenced code are skipped.
+pub fn extract_summary(content: &str) -> Option<String> {
+    let mut in_fence = false;
+    for line in split_frontmatter(content).1.lines() {
+        let line = line.trim();
+        if line.starts_with("```") {
+            in_fence = !in_fence;
+            continue;
+        }
+        if in_fence || line.is_empty() || line.starts_with('#') {
+            continue;
+        }
+        return Some(line.to_string());
+    }
+    None
+}
+
+/// Returns the `http://` and `https://` URLs in the `### Link:` section of
+/// `content`, in order and without duplicates. The section runs until the next
+/// heading; fenced code inside it is skipped.
+pub fn link_urls(content: &str) -> Vec<String> {
+    let url = Regex::new(r#"https?://[^\s<>()\[\]"'`]+"#).expect("URL pattern is valid");
+    let mut urls: Vec<String> = Vec::new();
+    let mut in_links = false;
+    let mut in_fence = false;
+
+    for line in split_frontmatter(content).1.lines() {
+        let trimmed = line.trim_start();
+        if trimmed.starts_with("```") {
+            in_fence = !in_fence;
+            continue;
+        }
+        if in_fence {
+            continue;
+        }
+        let text = if let Some(rest) = trimmed.strip_prefix("### Link:") {
+            in_links = true;
+            rest
+        } else if trimmed.starts_with('#') {
+            in_links = false;
+            continue;
+        } else {
+            line
+        };
+        if !in_links {
+            continue;
+        }
+        for found in url.find_iter(text) {
+            let found = found
+                .as_str()
+                .trim_end_matches(['.', ',', ';', ':', '!', '?']);
+            if !urls.iter().any(|known| known == found) {
+                urls.push(found.to_string());
+            }
+        }
+    }
+    urls
+}
+
+/// Adds `url` on its own line at the end of the `### Link:` section of
+/// `content`, appending the section when there is none. Returns `None` when
+/// the section already lists `url`.
+pub fn add_link(content: &str, url: &str) -> Option<String> {
+    if link_urls(content).iter().any(|known| known == url) {
+        return None;
+    }
+
+    let lines: Vec<&str> = content.lines().collect();
+    let Some(start) = lines
+        .iter()
+        .position(|line| line.trim_start().starts_with("### Link:"))
+    else {
+        let separator = if content.is_empty() || content.ends_with('\n') {
+            ""
+        } else {
+            "\n"
+        };
+        return Some(format!("{}{}### Link:\n{}\n", content, separator, url));
+    };
+
+    // Insert after the section's last non-blank line, before the next heading.
+    let end = lines[start + 1..]
+        .iter()
+        .position(|line| line.trim_start().starts_with('#'))
+        .map_or(lines.len(), |offset| start + 1 + offset);
+    let insert_at = (start + 1..end)
+        .rev()
+        .find(|&index| !lines[index].trim().is_empty())
+        .map_or(start + 1, |index| index + 1);
+
+    let mut updated: Vec<&str> = lines[..insert_at].to_vec();
+    updated.push(url);
+    updated.extend_from_slice(&lines[insert_at..]);
+    let mut updated = updated.join("\n");
+    if content.ends_with('\n') {
+        updated.push('\n');
+    }
+    Some(updated)
+}
+
+/// Splits `content` into its frontmatter block (without delimiters) and the remaining body.
+/// Returns `None` for the block when the file has no frontmatter.
+/// A leading byte order mark is dropped from the body, and CRLF delimiter lines
+/// are recognised like LF ones.
+pub fn split_frontmatter(content: &str) -> (Option<&str>, &str) {
+    let content = strip_bom(content);
+    let Some(rest) = content
+        .strip_prefix(FRONTMATTER_DELIMITER)
+        .and_then(strip_line_break)
+    else {
+        return (None, content);
+    };
+
+    let closing = format!("\n{}", FRONTMATTER_DELIMITER);
+    if let Some(body) = rest.strip_prefix(FRONTMATTER_DELIMITER) {
+        return (Some(""), strip_line_break(body).unwrap_or(body));
+    }
+    match rest.find(&closing) {
+        Some(end) => {
+            let body = &rest[end + closing.len()..];
+            (Some(&rest[..=end]), strip_line_break(body).unwrap_or(body))
+        }
+        None => (None, content),
+    }
+}
+
+/// `text` after the `\n` or `\r\n` it starts with, if it starts with one.
+fn strip_line_break(text: &str) -> Option<&str> {
+    text.strip_prefix("\r\n")
+        .or_else(|| text.strip_prefix('\n'))
+}
+
+/// Builds the generated filename `snippet_<timestamp>_<language>_<tags...>.md`.
+pub fn snippet_filename(timestamp: &str, language: &str, tags: &[String]) -> String {
+    snippet_filename_with_extension(timestamp, language, tags, "md")
+}
+
+/// Builds a generated filename with a custom extension, as used for plain code files.
+pub fn snippet_filename_with_extension(
+    timestamp: &str,
+    language: &str,
+    tags: &[String],
+    extension: &str,
+) -> String {
+    let mut parts = vec![timestamp.to_string(), language.to_string()];
+    parts.extend(tags.iter().cloned());
+    format!("snippet_{}.{}", parts.join("_"), extension)
+}
+
+/// Whether `path` is a markdown snippet rather than a plain code file.
+pub fn is_markdown(path: &Path) -> bool {
+    path.extension().is_some_and(|ext| ext == "md")
+}
+
+/// Renders a new snippet from the template: frontmatter, header, a code fence
+/// holding `code` (empty for a blank snippet), and the Link/Note sections.
+pub fn render_new_snippet(language: &str, tags: &[String], timestamp: &str, code: &str) -> String {
+    let frontmatter = Frontmatter {
+        title: format!("{} - Snippet", language),
+        language: language.to_string(),
+        tags: tags.to_vec(),
+        created: parse_timestamp(timestamp).or_else(|| Some(Local::now().naive_local())),
+        links: Vec::new(),
+        extra: BTreeMap::new(),
+    };
+    let code = if code.is_empty() || code.ends_with('\n') {
+        code.to_string()
+    } else {
+        format!("{}\n", code)
+    };
+
+    format!(
+        "{}# Title: {} - Snippet\n# ---\n### Tags: {}\n\n### Content\n\n```{}\n{}```\n### Link:\n### Note:\n",
+        frontmatter.render(),
+        language,
+        tags.join(", "),
+        language,
+        if code.is_empty() { "\n" } else { &code }
+    )
+}
+
+/// Tab-stop syntax written into new snippets for neovim snippet engines.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum PlaceholderStyle {
+    #[default]
+    None,
+    /// `$1`, `$2`, `$0` (LuaSnip's LSP-style tab stops).
+    LuaSnip,
+    /// `${1}`, `${2}`, `${0}` (vim-vsnip).
+    Vsnip,
+}
+
+impl PlaceholderStyle {
+    /// Names accepted by `--placeholder_style`.
+    pub const NAMES: &'static [&'static str] = &["none", "luasnip", "vsnip"];
+
+    /// Parses one of [`PlaceholderStyle::NAMES`].
+    pub fn from_name(name: &str) -> Option<Self> {
+        match name {
+            "none" => Some(PlaceholderStyle::None),
+            "luasnip" => Some(PlaceholderStyle::LuaSnip),
+            "vsnip" => Some(PlaceholderStyle::Vsnip),
+            _ => None,
+        }
+    }
+
+    fn tab_stop(self, index: u8) -> String {
+        match self {
+            PlaceholderStyle::None => String::new(),
+            PlaceholderStyle::LuaSnip => format!("${}", index),
+            PlaceholderStyle::Vsnip => format!("${{{}}}", index),
+        }
+    }
+}
+
+/// Adds tab stops to a snippet rendered by [`render_new_snippet`]: `1` in the
+/// code body (after any existing code), `2` right after the first fence's
+/// language label, and `0`, the final cursor position, in the Note section.
+/// [`PlaceholderStyle::None`] returns `template` unchanged.
+pub fn format_template_with_placeholders(template: &str, style: PlaceholderStyle) -> String {
+    if style == PlaceholderStyle::None {
+        return template.to_string();
+    }
+
+    let mut out = String::with_capacity(template.len() + 16);
+    let mut fence = 0;
+    let mut code_lines = 0;
+    for line in template.lines() {
+        if line.trim_start().starts_with("```") {
+            fence += 1;
+            match fence {
+                1 => {
+                    out.push_str(line);
+                    out.push_str(&style.tab_stop(2));
+                    out.push('\n');
+                    continue;
+                }
+                2 => {
+                    // An empty body is one blank line; put the stop on it.
+                    if code_lines == 1 && out.ends_with("\n\n") {
+                        out.pop();
+                    }
+                    out.push_str(&style.tab_stop(1));
+                    out.push('\n');
+                }
+                _ => {}
+            }
+        } else if fence == 1 {
+            code_lines += 1;
+        } else if line == "### Note:" {
+            out.push_str(line);
+            out.push('\n');
+            out.push_str(&style.tab_stop(0));
+            out.push('\n');
+            continue;
+        }
+        out.push_str(line);
+        out.push('\n');
+    }
+    out
+}
+
+/// Returns the underscore-separated components of a generated snippet filename
+/// (`snippet_<timestamp>_<language>_<tags...>.md`), without the `snippet_` prefix.
+fn filename_components(path: &Path) -> Option<Vec<String>> {
+    let stem = path.file_stem()?.to_str()?;
+    let rest = stem.strip_prefix("snippet_")?;
+    Some(rest.split('_').map(str::to_string).collect())
+}
+
+/// Returns the generated filename of `path` with its language component replaced,
+/// or `None` when `path` is not a generated snippet filename.
+pub fn rename_language_in_filename(path: &Path, new_language: &str) -> Option<String> {
+    let mut components = filename_components(path)?;
+    *components.get_mut(1)? = new_language.to_string();
+    let extension = path.extension()?.to_str()?;
+    Some(format!("snippet_{}.{}", components.join("_"), extension))
+}
+
+/// Extracts the creation timestamp from a generated snippet filename.
+pub fn parse_timestamp_from_filename(path: &Path) -> Option<NaiveDateTime> {
+    parse_timestamp(&filename_timestamp(path)?)
+}
+
+/// The timestamp component of a generated snippet filename, exactly as written.
+pub fn filename_timestamp(path: &Path) -> Option<String> {
+    let timestamp = filename_components(path)?.into_iter().next()?;
+    parse_timestamp(&timestamp).map(|_| timestamp)
+}
+
+/// Parses a filename timestamp in any of [`TIMESTAMP_FORMATS`], ignoring the
+/// `-N` counter added when two snippets were created in the same second
+/// (or minute, for formats without seconds). Date-only stamps mean midnight.
+pub fn parse_timestamp(text: &str) -> Option<NaiveDateTime> {
+    let parse = |text: &str| {
+        TIMESTAMP_FORMATS.iter().find_map(|format| {
+            NaiveDateTime::parse_from_str(text, format)
+                .ok()
+                .or_else(|| {
+                    NaiveDate::parse_from_str(text, format)
+                        .ok()
+                        .and_then(|date| date.and_hms_opt(0, 0, 0))
+                })
+        })
+    };
+    parse(text).or_else(|| {
+        let (stem, counter) = text.rsplit_once('-')?;
+        let is_counter =
+            (1..=3).contains(&counter.len()) && counter.chars().all(|c| c.is_ascii_digit());
+        if is_counter {
+            parse(stem)
+        } else {
+            None
+        }
+    })
+}
+
+/// Renders `time` in the strftime `format`, or explains why the format cannot
+/// be used for filenames (an invalid specifier or an illegal character).
+pub fn render_timestamp(time: &NaiveDateTime, format: &str) -> Result<String, String> {
+    use std::fmt::Write;
+
+    let mut rendered = String::new();
+    write!(rendered, "{}", time.format(format))
+        .map_err(|_| format!("'{}' is not a valid strftime format", format))?;
+    if let Some(c) = rendered
+        .chars()
+        .find(|c| TIMESTAMP_ILLEGAL_CHARS.contains(c) || c.is_control() || c.is_whitespace())
+    {
+        return Err(format!(
+            "'{}' produces '{}', and {:?} is not allowed in snippet filenames",
+            format, rendered, c
+        ));
+    }
+    if rendered.is_empty() {
+        return Err(format!("'{}' produces an empty timestamp", format));
+    }
+    Ok(rendered)
+}
+
+/// Extracts the language from a generated snippet filename.
+pub fn parse_language_from_filename(path: &Path) -> Option<String> {
+    filename_components(path)?
+        .get(1)
+        .filter(|language| !language.is_empty())
+        .cloned()
+}
+
+/// Extracts the tags from a generated snippet filename.
+pub fn parse_tags_from_filename(path: &Path) -> Vec<String> {
+    filename_components(path)
+        .map(|components| {
+            components
+                .into_iter()
+                .skip(2)
+                .filter(|tag| !tag.is_empty())
+                .collect()
+        })
+        .unwrap_or_default()
+}
+
+/// Returns the text of the first line comment starting with `prefix`, skipping shebangs.
+fn parse_comment_title(content: &str, prefix: &str) -> Option<String> {
+    content
+        .lines()
+        .map(str::trim)
+        .filter(|line| !line.starts_with("#!"))
+        .find_map(|line| line.strip_prefix(prefix))
+        .map(|title| title.trim_start_matches(prefix).trim().to_string())
+        .filter(|title| !title.is_empty())
+}
+
+/// Returns the text of the `# Title:` line written by the snippet template.
+pub fn parse_title(content: &str) -> Option<String> {
+    strip_bom(content)
+        .lines()
+        .find_map(|line| line.strip_prefix("# Title:"))
+        .map(|title| title.trim().to_string())
+        .filter(|title| !title.is_empty())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::fields;
+    use crate::testing;
+    use crate::vault::Executor;
+
+    const LF: &str =
+        "+++\ntitle = \"Undo\"\nlanguage = \"bash\"\n+++\n```bash\ngit reset HEAD~1\n```\n";
+
+    fn crlf() -> String {
+        LF.replace('\n', "\r\n")
+    }
+
+    fn with_bom(content: &str) -> String {
+        format!("{}{}", BOM, content)
+    }
+
+    #[test]
+    fn text_style_of_fixtures() {
+        let style = |line_ending, bom| TextStyle { line_ending, bom };
+        assert_eq!(TextStyle::of(LF), style(LineEnding::Lf, false));
+        assert_eq!(TextStyle::of(&crlf()), style(LineEnding::Crlf, false));
+        assert_eq!(TextStyle::of(&with_bom(LF)), style(LineEnding::Lf, true));
+        assert_eq!(
+            TextStyle::of(&with_bom(&crlf())),
+            style(LineEnding::Crlf, true)
+        );
+        assert_eq!(
+            TextStyle::of("one\r\ntwo\nthree\r\n"),
+            style(LineEnding::Mixed, false)
+        );
+        assert_eq!(TextStyle::of("no line break"), style(LineEnding::Lf, false));
+    }
+
+    #[test]
+    fn text_style_apply() {
+        let crlf_bom = TextStyle::of(&with_bom(&crlf()));
+        assert_eq!(crlf_bom.apply(LF), with_bom(&crlf()));
+        // Already converted text is not doubled up.
+        assert_eq!(crlf_bom.apply(&with_bom(&crlf())), with_bom(&crlf()));
+        assert_eq!(TextStyle::of(LF).apply(&with_bom(LF)), LF);
+        let mixed = TextStyle::of("a\r\nb\n");
+        assert_eq!(mixed.apply("x\r\ny\nz\n"), "x\r\ny\nz\n");
+    }
+
+    #[test]
+    fn strip_bom_only_strips_a_leading_mark() {
+        assert_eq!(strip_bom(&with_bom("text")), "text");
+        assert_eq!(strip_bom("text"), "text");
+        assert_eq!(strip_bom("a\u{feff}b"), "a\u{feff}b");
+    }
+
+    #[test]
+    fn split_frontmatter_fixtures() {
+        let expected = (
+            Some("title = \"Undo\"\nlanguage = \"bash\"\n"),
+            "```bash\ngit reset HEAD~1\n```\n",
+        );
+        assert_eq!(split_frontmatter(LF), expected);
+        assert_eq!(split_frontmatter(&with_bom(LF)), expected);
+
+        let crlf = crlf();
+        let (block, body) = split_frontmatter(&crlf);
+        assert_eq!(block, Some("title = \"Undo\"\r\nlanguage = \"bash\"\r\n"));
+        assert_eq!(body, "```bash\r\ngit reset HEAD~1\r\n```\r\n");
+
+        let mixed = "+++\r\ntitle = \"Undo\"\n+++\nbody\r\n";
+        assert_eq!(
+            split_frontmatter(mixed),
+            (Some("title = \"Undo\"\n"), "body\r\n")
+        );
+        assert_eq!(split_frontmatter("+++\n+++\nbody\n"), (Some(""), "body\n"));
+        assert_eq!(
+            split_frontmatter("no frontmatter\n"),
+            (None, "no frontmatter\n")
+        );
+        assert_eq!(
+            split_frontmatter("+++\nunclosed\n"),
+            (None, "+++\nunclosed\n")
+        );
+    }
+
+    #[test]
+    fn rewrite_keeps_line_endings_and_bom() {
+        testing::isolate_user_dirs();
+        let dir = tempfile::tempdir().unwrap();
+        for (name, content) in [
+            ("lf.md", LF.to_string()),
+            ("crlf.md", crlf()),
+            ("bom.md", with_bom(LF)),
+            ("crlf_bom.md", with_bom(&crlf())),
+        ] {
+            let path = dir.path().join(name);
+            fs::write(&path, &content).unwrap();
+            let frontmatter = Frontmatter::parse(&path, &content).unwrap().unwrap();
+            assert_eq!(frontmatter.title, "Undo", "{}", name);
+
+            let changed = fields::set_field(
+                &path,
+                "reviewed",
+                Some(toml::Value::Boolean(true)),
+                &Executor::new(false),
+            )
+            .unwrap();
+            assert!(changed, "{}", name);
+
+            let rewritten = fs::read_to_string(&path).unwrap();
+            assert_eq!(
+                TextStyle::of(&rewritten),
+                TextStyle::of(&content),
+                "{}",
+                name
+            );
+            assert!(rewritten.contains("reviewed = true"), "{}", name);
+            assert!(
+                rewritten.ends_with(split_frontmatter(&content).1),
+                "{} lost its body",
+                name
+            );
+        }
+    }
+
+    #[test]
+    fn created_may_be_a_string_or_a_bare_toml_date_time() {
+        let created = |value: &str| {
+            let content = format!("+++\ntitle = \"T\"\ncreated = {}\n+++\n", value);
+            Frontmatter::parse(Path::new("t.md"), &content)
+                .map(|frontmatter| frontmatter.unwrap().created)
+        };
+        let expected =
+            NaiveDate::from_ymd_opt(2024, 5, 17).and_then(|date| date.and_hms_opt(9, 30, 0));
+        assert_eq!(created("\"2024-05-17T09:30:00\"").unwrap(), expected);
+        assert_eq!(created("2024-05-17T09:30:00").unwrap(), expected);
+        assert_eq!(created("2024-05-17 09:30:00").unwrap(), expected);
+        assert_eq!(
+            created("2024-05-17T09:30:00+02:00").unwrap(),
+            dates::parse_created("2024-05-17T09:30:00+02:00")
+        );
+        assert!(created("\"last tuesday\"").is_err());
+        assert!(created("42").is_err());
+
+        // Written back as the string the tool has always written.
+        let frontmatter = Frontmatter {
+            created: expected,
+            ..Frontmatter::default()
+        };
+        assert!(frontmatter
+            .render()
+            .contains("created = \"2024-05-17T09:30:00\""));
+    }
+
+    #[test]
+    fn code_blocks_match_fence_character_and_length() {
+        let content = "~~~~md\n```rust\nfn a() {}\n```\n~~~~\n````\n```\n````\n```sh\nls\n";
+        let blocks = code_blocks(content);
+        let found: Vec<(&str, &str, usize)> = blocks
+            .iter()
+            .map(|block| {
+                (
+                    block.language.as_str(),
+                    block.code.as_str(),
+                    block.start_line,
+                )
+            })
+            .collect();
+        assert_eq!(
+            found,
+            [
+                ("md", "```rust\nfn a() {}\n```\n", 1),
+                ("", "```\n", 6),
+                ("sh", "ls\n", 9),
+            ]
+        );
+        assert_eq!(Fence::open("``"), None);
+        assert_eq!(Fence::open("``` `x`"), None);
+    }
+}
@@ -0,0 +1,419 @@
+use regex::Regex;
+use std::collections::{BTreeSet, HashSet};
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::sync::LazyLock;
+
+use crate::error::SnippetVaultError;
+use crate::snippet::{self, Fence};
+use crate::vault::{Executor, Mutation};
+
+/// The vault's own dictionary: one word per line, kept at the vault root.
+pub const DICTIONARY_FILE: &str = ".spelling";
+
+/// Common English and programming words compiled into the binary, so the
+/// check works where no system dictionary is installed.
+const BUNDLED_WORDS: &str = include_str!("spelling_words.txt");
+
+/// Word lists read when present: plain lists and hunspell `.dic` files, whose
+/// `/FLAGS` suffixes and leading word count are dropped.
+const SYSTEM_DICTIONARIES: &[&str] = &[
+    "/usr/share/dict/words",
+    "/usr/share/hunspell/en_US.dic",
+    "/usr/share/myspell/en_US.dic",
+    "/Library/Spelling/en_US.dic",
+];
+
+/// How many suggestions are offered for a misspelled word.
+const MAX_SUGGESTIONS: usize = 3;
+
+/// URLs and link targets, which are never prose; inline code is blanked by
+/// [`blank_code_spans`] first.
+static NOISE: LazyLock<Regex> = LazyLock::new(|| {
+    Regex::new(r"https?://\S+|\]\([^)]*\)|<[^>]*>").expect("noise pattern is valid")
+});
+
+/// Letter runs with inner apostrophes or hyphens; anything touching a digit,
+/// underscore, dot, or slash is left out later as an identifier.
+static WORD: LazyLock<Regex> = LazyLock::new(|| {
+    Regex::new(r"[\p{L}\w][\p{L}\w'’./-]*[\p{L}\w]|[\p{L}]").expect("word pattern is valid")
+});
+
+/// The words the checker accepts: the bundled list, any system dictionaries,
+/// and the vault's [`DICTIONARY_FILE`].
+pub struct Dictionary {
+    words: HashSet<String>,
+    /// The vault dictionary's words, as written back by [`Dictionary::save`].
+    custom: BTreeSet<String>,
+    custom_path: PathBuf,
+}
+
+impl Dictionary {
+    /// Loads the dictionaries for the vault at `snippet_dir`. A missing vault
+    /// dictionary is treated as empty.
+    pub fn load(snippet_dir: &Path) -> Result<Self, SnippetVaultError> {
+        let mut words: HashSet<String> = BUNDLED_WORDS.lines().map(str::to_string).collect();
+        for path in SYSTEM_DICTIONARIES {
+            let Ok(content) = fs::read_to_string(path) else {
+                continue;
+            };
+            words.extend(
+                content
+                    .lines()
+                    .filter_map(|line| line.split('/').next())
+                    .map(str::trim)
+                    .filter(|word| word.chars().any(char::is_alphabetic))
+                    .map(str::to_lowercase),
+            );
+        }
+
+        let custom_path = snippet_dir.join(DICTIONARY_FILE);
+        let custom: BTreeSet<String> = match fs::read_to_string(&custom_path) {
+            Ok(content) => content
+                .lines()
+                .map(str::trim)
+                .filter(|word| !word.is_empty() && !word.starts_with('#'))
+                .map(str::to_string)
+                .collect(),
+            Err(err) if err.kind() == std::io::ErrorKind::NotFound => BTreeSet::new(),
+            Err(err) => return Err(err.into()),
+        };
+        words.extend(custom.iter().map(|word| word.to_lowercase()));
+        Ok(Self {
+            words,
+            custom,
+            custom_path,
+        })
+    }
+
+    /// Whether `word` is spelled correctly, ignoring case and allowing the
+    /// common English suffixes on a known stem.
+    pub fn knows(&self, word: &str) -> bool {
+        let word = word.replace('’', "'").to_lowercase();
+        if self.words.contains(&word) {
+            return true;
+        }
+        let word = word.strip_suffix("'s").unwrap_or(&word);
+        stems(word).iter().any(|stem| self.words.contains(stem))
+    }
+
+    /// Up to three known words within two edits of `word`, closest first,
+    /// with the first letter's case carried over.
+    pub fn suggest(&self, word: &str) -> Vec<String> {
+        let lower = word.to_lowercase();
+        let length = lower.chars().count();
+        let mut candidates: Vec<(usize, &String)> = self
+            .words
+            .iter()
+            .filter(|known| known.chars().count().abs_diff(length) <= 2)
+            .map(|known| (strsim::damerau_levenshtein(&lower, known), known))
+            .filter(|(distance, _)| *distance <= 2)
+            .collect();
+        // Among equally close words, prefer ones with the same letters: "teh" -> "the".
+        let letters = |word: &str| {
+            let mut letters: Vec<char> = word.chars().collect();
+            letters.sort_unstable();
+            letters
+        };
+        let own_letters = letters(&lower);
+        candidates
+            .sort_by_key(|(distance, known)| (*distance, letters(known) != own_letters, *known));
+        let capitalized = word.chars().next().is_some_and(char::is_uppercase);
+        candidates
+            .into_iter()
+            .take(MAX_SUGGESTIONS)
+            .map(|(_, known)| match capitalized {
+                true => capitalize(known),
+                false => known.clone(),
+            })
+            .collect()
+    }
+
+    /// Accepts `word` from now on and adds it to the vault dictionary.
+    pub fn add(&mut self, word: &str) {
+        self.words.insert(word.to_lowercase());
+        self.custom.insert(word.to_string());
+    }
+
+    /// Writes the vault dictionary, one word per line in sorted order.
+    /// - `executor`: Applies (or, with `--dry-run`, only prints) the write.
+    pub fn save(&self, executor: &Executor) -> Result<(), SnippetVaultError> {
+        let mut contents = String::new();
+        for word in &self.custom {
+            contents.push_str(word);
+            contents.push('\n');
+        }
+        executor.apply(Mutation::Write {
+            path: self.custom_path.clone(),
+            contents,
+        })
+    }
+}
+
+/// `word` with its first letter uppercased.
+fn capitalize(word: &str) -> String {
+    let mut chars = word.chars();
+    match chars.next() {
+        Some(first) => first.to_uppercase().chain(chars).collect(),
+        None => String::new(),
+    }
+}
+
+/// The forms `word` may be an inflection of: plurals, past tenses, `-ing`,
+/// `-ly`, `-er`, and `-est` forms, with a doubled final consonant or dropped
+/// `e` restored.
+fn stems(word: &str) -> Vec<String> {
+    let mut stems = Vec::new();
+    for (suffix, replacements) in [
+        ("ies", &["y"][..]),
+        ("ied", &["y"]),
+        ("es", &[""]),
+        ("s", &[""]),
+        ("ed", &["", "e"]),
+        ("ing", &["", "e"]),
+        ("ly", &[""]),
+        ("ily", &["y"]),
+        ("er", &["", "e"]),
+        ("ers", &["", "e"]),
+        ("est", &["", "e"]),
+        ("ness", &[""]),
+        ("ment", &[""]),
+        ("able", &["", "e"]),
+    ] {
+        let Some(stem) = word.strip_suffix(suffix) else {
+            continue;
+        };
+        if stem.chars().count() < 2 {
+            continue;
+        }
+        for replacement in replacements {
+            stems.push(format!("{}{}", stem, replacement));
+        }
+        // running -> run, stopped -> stop
+        let mut chars = stem.chars().rev();
+        if let (Some(last), Some(before)) = (chars.next(), chars.next()) {
+            if last == before && !"aeiou".contains(last) {
+                stems.push(stem[..stem.len() - last.len_utf8()].to_string());
+            }
+        }
+    }
+    for prefix in ["un", "re", "pre", "non"] {
+        if let Some(rest) = word.strip_prefix(prefix) {
+            if rest.chars().count() >= 3 {
+                stems.push(rest.to_string());
+            }
+        }
+    }
+    stems
+}
+
+/// A word of a snippet's prose that no dictionary knows.
+#[derive(Debug, Clone)]
+pub struct Misspelling {
+    /// 1-based line in the file.
+    pub line: usize,
+    /// Byte offset of the word in its line.
+    pub column: usize,
+    pub word: String,
+    pub suggestions: Vec<String>,
+}
+
+/// The misspelled words of `content`'s prose, in file order. Frontmatter,
+/// fenced code blocks, inline code, URLs, and the `### Tags:` line are not
+/// checked, nor are words that look like identifiers: ones with digits,
+/// underscores, dots, or slashes, all-caps acronyms, and camelCase names.
+pub fn check(content: &str, dictionary: &Dictionary) -> Vec<Misspelling> {
+    let body = snippet::split_frontmatter(content).1;
+    let first_line = content[..content.len() - body.len()].matches('\n').count();
+    let mut fence: Option<Fence> = None;
+    let mut misspellings = Vec::new();
+
+    for (index, line) in body.lines().enumerate() {
+        if let Some(open) = fence {
+            if open.is_closed_by(line) {
+                fence = None;
+            }
+            continue;
+        }
+        if let Some((open, _)) = Fence::open(line) {
+            fence = Some(open);
+            continue;
+        }
+        if line.trim_start().starts_with("### Tags:") {
+            continue;
+        }
+        // Blank out noise rather than removing it, so columns stay right.
+        let line = blank_code_spans(line);
+        let text = NOISE.replace_all(&line, |caps: &regex::Captures| " ".repeat(caps[0].len()));
+        for token in WORD.find_iter(&text) {
+            let mut offset = token.start();
+            for part in token.as_str().split('-') {
+                let start = offset;
+                offset += part.len() + 1;
+                let word = part.trim_start_matches(['\'', '’']);
+                let column = start + part.len() - word.len();
+                let word = word.trim_end_matches(['\'', '’']);
+                if word.is_empty() || is_identifier(word) || dictionary.knows(word) {
+                    continue;
+                }
+                misspellings.push(Misspelling {
+                    line: first_line + index + 1,
+                    column,
+                    word: word.to_string(),
+                    suggestions: dictionary.suggest(word),
+                });
+            }
+        }
+    }
+    misspellings
+}
+
+/// `line` with its inline code spans replaced by spaces. A span opens with a
+/// run of backticks of any length and closes at the next run of the same
+/// length (so ``` `` a ` b `` ``` is one span); an unmatched run is text.
+fn blank_code_spans(line: &str) -> String {
+    let run_length = |text: &str| text.len() - text.trim_start_matches('`').len();
+    let mut blanked = String::with_capacity(line.len());
+    let mut rest = line;
+    while let Some(start) = rest.find('`') {
+        let run = run_length(&rest[start..]);
+        let inner = &rest[start + run..];
+        let mut search = 0;
+        let mut close = None;
+        while let Some(offset) = inner[search..].find('`') {
+            let at = search + offset;
+            let length = run_length(&inner[at..]);
+            if length == run {
+                close = Some(at);
+                break;
+            }
+            search = at + length;
+        }
+        blanked.push_str(&rest[..start]);
+        match close {
+            Some(at) => {
+                blanked.push_str(&" ".repeat(run + at + run));
+                rest = &inner[at + run..];
+            }
+            None => {
+                blanked.push_str(&rest[start..start + run]);
+                rest = inner;
+            }
+        }
+    }
+    blanked.push_str(rest);
+    blanked
+}
+
+/// Whether `word` looks like code rather than prose.
+fn is_identifier(word: &str) -> bool {
+    let letters = word.chars().filter(|c| c.is_alphabetic()).count();
+    word.chars()
+        .any(|c| c.is_ascii_digit() || matches!(c, '_' | '.' | '/'))
+        || letters < 2
+        || word.chars().all(|c| !c.is_lowercase())
+        || word.chars().skip(1).any(char::is_uppercase)
+}
+
+/// `content` with each `(line, column, word, replacement)` applied; positions
+/// refer to `content` as given.
+pub fn apply_fixes(content: &str, fixes: &[(usize, usize, String, String)]) -> String {
+    let mut lines: Vec<String> = content.split_inclusive('\n').map(str::to_string).collect();
+    let mut fixes = fixes.to_vec();
+    // Right to left, so earlier columns on a line stay valid.
+    fixes.sort_by_key(|fix| std::cmp::Reverse((fix.0, fix.1)));
+    for (line, column, word, replacement) in fixes {
+        if let Some(text) = lines.get_mut(line - 1) {
+            if text.get(column..column + word.len()) == Some(word.as_str()) {
+                text.replace_range(column..column + word.len(), &replacement);
+            }
+        }
+    }
+    lines.concat()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn dictionary() -> Dictionary {
+        let vault = tempfile::tempdir().unwrap();
+        Dictionary::load(vault.path()).unwrap()
+    }
+
+    fn flagged(content: &str) -> Vec<String> {
+        check(content, &dictionary())
+            .into_iter()
+            .map(|misspelling| misspelling.word)
+            .collect()
+    }
+
+    #[test]
+    fn code_fences_are_never_checked() {
+        assert_eq!(flagged("```\nweirdd\n```\n"), Vec::<String>::new());
+        assert_eq!(flagged("~~~\nweirdd\n~~~\n"), Vec::<String>::new());
+        // A backtick fence line inside a tilde block is code, not a fence.
+        assert_eq!(
+            flagged("~~~~\n```\nweirdd\n```\nweirdd\n~~~~\nafter blargh\n"),
+            ["blargh"]
+        );
+        // A shorter run does not close a longer fence.
+        assert_eq!(
+            flagged("````md\nweirdd\n```\nweirdd\n````\nblargh\n"),
+            ["blargh"]
+        );
+    }
+
+    #[test]
+    fn inline_code_is_never_checked() {
+        assert_eq!(flagged("Run `weirdd` now\n"), Vec::<String>::new());
+        assert_eq!(flagged("Run ``weirdd`` now\n"), Vec::<String>::new());
+        assert_eq!(flagged("Run ``a ` weirdd`` and blargh\n"), ["blargh"]);
+        // An unmatched run is plain text.
+        assert_eq!(flagged("a `` blargh\n"), ["blargh"]);
+    }
+
+    #[test]
+    fn columns_survive_blanked_spans() {
+        let misspellings = check("``x`` blargh\n", &dictionary());
+        assert_eq!(misspellings.len(), 1);
+        assert_eq!((misspellings[0].line, misspellings[0].column), (1, 6));
+        assert_eq!(blank_code_spans("a ``b`` c"), "a       c");
+    }
+
+    #[test]
+    fn fixes_apply_right_to_left_and_only_where_the_word_is() {
+        let content = "teh cat and teh dog\nsecond teh\n";
+        let fixes = vec![
+            (1, 0, "teh".to_string(), "the".to_string()),
+            (1, 12, "teh".to_string(), "the".to_string()),
+            (2, 7, "teh".to_string(), "the".to_string()),
+            // Stale position: the word is not there, so nothing changes.
+            (2, 0, "teh".to_string(), "the".to_string()),
+        ];
+        assert_eq!(
+            apply_fixes(content, &fixes),
+            "the cat and the dog\nsecond the\n"
+        );
+    }
+
+    #[test]
+    fn stems_undo_common_suffixes() {
+        for (word, stem) in [
+            ("running", "run"),
+            ("stopped", "stop"),
+            ("parsed", "parse"),
+            ("libraries", "library"),
+            ("quickly", "quick"),
+            ("rebuild", "build"),
+        ] {
+            assert!(
+                stems(word).contains(&stem.to_string()),
+                "{} -> {}",
+                word,
+                stem
+            );
+        }
+        assert!(stems("is").is_empty());
+    }
+}
@@ -0,0 +1,409 @@
+use colored::{Color, ColoredString, Colorize};
+use serde::Deserialize;
+use std::sync::OnceLock;
+
+/// Built-in palettes accepted by `--theme` (or `--status-theme`) and `preset`.
+pub const PRESETS: &[&str] = &["default", "high-contrast"];
+
+/// Colors of the roles in status output, from the `[theme]` or
+/// `[status_colors]` config section. Each role takes a color name (`green`,
+/// `bright blue`, `#ff8800`) or `none`; unset roles keep the preset's color.
+#[derive(Debug, Clone, Default, Deserialize)]
+#[serde(default)]
+pub struct StatusColors {
+    /// One of [`PRESETS`] to start from; `default` when unset.
+    pub preset: Option<String>,
+    pub success: Option<String>,
+    pub error: Option<String>,
+    pub warning: Option<String>,
+    /// Informational markers, names, and other highlights.
+    pub accent: Option<String>,
+}
+
+impl StatusColors {
+    /// These colors, with the roles left unset here taken from `fallback`.
+    pub fn or(&self, fallback: &StatusColors) -> StatusColors {
+        let pick = |own: &Option<String>, other: &Option<String>| own.clone().or(other.clone());
+        StatusColors {
+            preset: pick(&self.preset, &fallback.preset),
+            success: pick(&self.success, &fallback.success),
+            error: pick(&self.error, &fallback.error),
+            warning: pick(&self.warning, &fallback.warning),
+            accent: pick(&self.accent, &fallback.accent),
+        }
+    }
+}
+
+/// The `theme` config key: the name of the highlighting theme, as it always
+/// was, or a `[theme]` section holding the status colors and, as `highlight`,
+/// that name.
+#[derive(Debug, Clone, Deserialize)]
+#[serde(untagged)]
+pub enum ThemeConfig {
+    Highlight(String),
+    Section {
+        highlight: Option<String>,
+        #[serde(flatten)]
+        colors: StatusColors,
+    },
+}
+
+impl ThemeConfig {
+    /// The highlighting theme's name, if one is set.
+    pub fn highlight(&self) -> Option<&str> {
+        match self {
+            ThemeConfig::Highlight(name) => Some(name),
+            ThemeConfig::Section { highlight, .. } => highlight.as_deref(),
+        }
+    }
+
+    /// The status colors of a `[theme]` section.
+    pub fn colors(&self) -> Option<&StatusColors> {
+        match self {
+            ThemeConfig::Highlight(_) => None,
+            ThemeConfig::Section { colors, .. } => Some(colors),
+        }
+    }
+}
+
+/// Background of the terminal, from `terminal_theme` in the config or
+/// `--snippet_color_theme`; decides the glow and bat styles and the status
+/// palette.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum TerminalTheme {
+    Dark,
+    Light,
+    /// Guessed from `COLORFGBG` or `TERM_PROGRAM`; dark when neither tells.
+    #[default]
+    Auto,
+}
+
+impl TerminalTheme {
+    /// Names accepted by `--snippet_color_theme` and `terminal_theme`.
+    pub const NAMES: &'static [&'static str] = &["dark", "light", "auto"];
+
+    /// Parses one of [`TerminalTheme::NAMES`].
+    pub fn from_name(name: &str) -> Option<Self> {
+        match name {
+            "dark" => Some(TerminalTheme::Dark),
+            "light" => Some(TerminalTheme::Light),
+            "auto" => Some(TerminalTheme::Auto),
+            _ => None,
+        }
+    }
+
+    /// `Dark` or `Light`, with `Auto` resolved from the environment.
+    pub fn resolve(self) -> TerminalTheme {
+        match self {
+            TerminalTheme::Auto => detect_background(),
+            theme => theme,
+        }
+    }
+}
+
+/// The background the terminal reports. `COLORFGBG` (set by rxvt, Konsole,
+/// and iTerm2) ends with the background's color number, 7 and 9-15 being
+/// light; otherwise Apple's Terminal, whose default profile is light, counts
+/// as light and anything else as dark.
+fn detect_background() -> TerminalTheme {
+    if let Ok(colors) = std::env::var("COLORFGBG") {
+        if let Some(Ok(background)) = colors.rsplit(';').next().map(str::parse::<u8>) {
+            return match background {
+                7 | 9..=15 => TerminalTheme::Light,
+                _ => TerminalTheme::Dark,
+            };
+        }
+    }
+    match std::env::var("TERM_PROGRAM").as_deref() {
+        Ok("Apple_Terminal") => TerminalTheme::Light,
+        _ => TerminalTheme::Dark,
+    }
+}
+
+/// The `--style` glow renders markdown with on a `theme` terminal.
+pub fn resolve_glow_style(theme: TerminalTheme) -> &'static str {
+    match theme.resolve() {
+        TerminalTheme::Light => "light",
+        _ => "dark",
+    }
+}
+
+/// The `--theme` bat highlights code with on a `theme` terminal.
+pub fn resolve_bat_theme(theme: TerminalTheme) -> &'static str {
+    match theme.resolve() {
+        TerminalTheme::Light => "GitHub",
+        _ => "TwoDark",
+    }
+}
+
+/// What a piece of status output means, which decides its color.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Role {
+    Success,
+    Error,
+    Warning,
+    Accent,
+}
+
+/// The color of each [`Role`] (`None` for plain text), and whether every
+/// role is also bold.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Palette {
+    pub success: Option<Color>,
+    pub error: Option<Color>,
+    pub warning: Option<Color>,
+    pub accent: Option<Color>,
+    pub bold: bool,
+}
+
+impl Palette {
+    /// Green, red, yellow, and cyan.
+    pub const DEFAULT: Palette = Palette {
+        success: Some(Color::Green),
+        error: Some(Color::Red),
+        warning: Some(Color::Yellow),
+        accent: Some(Color::Cyan),
+        bold: false,
+    };
+
+    /// Bold bright blue and magenta instead of green and red, which many
+    /// colour-blind readers cannot tell apart.
+    pub const HIGH_CONTRAST: Palette = Palette {
+        success: Some(Color::BrightBlue),
+        error: Some(Color::BrightMagenta),
+        warning: Some(Color::BrightYellow),
+        accent: Some(Color::BrightWhite),
+        bold: true,
+    };
+
+    /// The palette with the colors that wash out on a light background
+    /// (yellow, cyan, white) swapped for darker ones.
+    pub fn for_light_background(self) -> Self {
+        let darken = |color: Option<Color>| match color {
+            Some(Color::Yellow | Color::BrightYellow) => Some(Color::TrueColor {
+                r: 0xb5,
+                g: 0x76,
+                b: 0x00,
+            }),
+            Some(Color::Cyan | Color::BrightCyan) => Some(Color::Blue),
+            Some(Color::White | Color::BrightWhite) => Some(Color::Black),
+            color => color,
+        };
+        Palette {
+            success: darken(self.success),
+            error: darken(self.error),
+            warning: darken(self.warning),
+            accent: darken(self.accent),
+            bold: self.bold,
+        }
+    }
+
+    /// Parses one of [`PRESETS`].
+    pub fn preset(name: &str) -> Option<Self> {
+        match name {
+            "default" => Some(Palette::DEFAULT),
+            "high-contrast" => Some(Palette::HIGH_CONTRAST),
+            _ => None,
+        }
+    }
+
+    /// The palette `colors` describes, starting from the preset `preset`
+    /// (`--theme`) or else the configured one, adjusted for a light
+    /// `theme`. Colors set in `colors` are used as given. Unknown presets and
+    /// colors are skipped and returned as problems for the caller to report.
+    pub fn from_config(
+        colors: &StatusColors,
+        preset: Option<&str>,
+        theme: TerminalTheme,
+    ) -> (Self, Vec<String>) {
+        let mut problems = Vec::new();
+        let preset = preset.or(colors.preset.as_deref()).unwrap_or("default");
+        let mut palette = Palette::preset(preset).unwrap_or_else(|| {
+            problems.push(format!(
+                "unknown status theme '{}'; expected one of {}",
+                preset,
+                PRESETS.join(", ")
+            ));
+            Palette::DEFAULT
+        });
+        if theme.resolve() == TerminalTheme::Light {
+            palette = palette.for_light_background();
+        }
+
+        let roles = [
+            ("success", &colors.success, &mut palette.success),
+            ("error", &colors.error, &mut palette.error),
+            ("warning", &colors.warning, &mut palette.warning),
+            ("accent", &colors.accent, &mut palette.accent),
+        ];
+        for (role, configured, color) in roles {
+            match configured.as_deref() {
+                None => {}
+                Some("none") => *color = None,
+                Some(name) => match name.parse() {
+                    Ok(parsed) => *color = Some(parsed),
+                    Err(()) => problems.push(format!("unknown {} color '{}'", role, name)),
+                },
+            }
+        }
+        (palette, problems)
+    }
+
+    fn color(&self, role: Role) -> Option<Color> {
+        match role {
+            Role::Success => self.success,
+            Role::Error => self.error,
+            Role::Warning => self.warning,
+            Role::Accent => self.accent,
+        }
+    }
+}
+
+static PALETTE: OnceLock<Palette> = OnceLock::new();
+
+/// Uses `palette` for all status output of this process. Only the first call
+/// has an effect; output before it uses [`Palette::DEFAULT`].
+pub fn set_palette(palette: Palette) {
+    let _ = PALETTE.set(palette);
+}
+
+/// Styles `text` for `role` with the active palette. With colors off
+/// (`NO_COLOR`, no terminal, or a role set to `none`) the text is plain, so
+/// the status symbols alone carry the meaning.
+pub fn paint(role: Role, text: &str) -> ColoredString {
+    paint_with(PALETTE.get().unwrap_or(&Palette::DEFAULT), role, text)
+}
+
+/// [`paint`] with `palette` instead of the active one.
+fn paint_with(palette: &Palette, role: Role, text: &str) -> ColoredString {
+    let painted = match palette.color(role) {
+        Some(color) => text.color(color),
+        None => text.normal(),
+    };
+    if palette.bold {
+        painted.bold()
+    } else {
+        painted
+    }
+}
+
+/// Status styling by role, so output follows the configured palette instead
+/// of fixed colors.
+pub trait Styled {
+    fn success(&self) -> ColoredString;
+    fn error(&self) -> ColoredString;
+    fn warning(&self) -> ColoredString;
+    fn accent(&self) -> ColoredString;
+}
+
+impl Styled for str {
+    fn success(&self) -> ColoredString {
+        paint(Role::Success, self)
+    }
+
+    fn error(&self) -> ColoredString {
+        paint(Role::Error, self)
+    }
+
+    fn warning(&self) -> ColoredString {
+        paint(Role::Warning, self)
+    }
+
+    fn accent(&self) -> ColoredString {
+        paint(Role::Accent, self)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn theme_section_or_highlight_name() {
+        let config: crate::config::Config = toml::from_str(
+            "[theme]\npreset = \"high-contrast\"\nhighlight = \"InspiredGitHub\"\n\
+             success = \"green\"\n\n[status_colors]\nsuccess = \"red\"\nerror = \"yellow\"\n",
+        )
+        .unwrap();
+        assert_eq!(config.highlight_theme(), Some("InspiredGitHub"));
+        let (palette, problems) = config.palette();
+        assert!(problems.is_empty(), "{:?}", problems);
+        assert!(palette.bold);
+        // `[theme]` wins; roles it leaves unset come from `[status_colors]`.
+        assert_eq!(palette.color(Role::Success), Some(Color::Green));
+        assert_eq!(palette.color(Role::Error), Some(Color::Yellow));
+
+        let config: crate::config::Config =
+            toml::from_str("theme = \"base16-ocean.dark\"\n").unwrap();
+        assert_eq!(config.highlight_theme(), Some("base16-ocean.dark"));
+        assert!(!config.palette().0.bold);
+    }
+
+    #[test]
+    fn high_contrast_preset_maps_every_role() {
+        let (palette, problems) = Palette::from_config(
+            &StatusColors::default(),
+            Some("high-contrast"),
+            TerminalTheme::Dark,
+        );
+        assert!(problems.is_empty(), "{:?}", problems);
+        let expected = [
+            (Role::Success, Color::BrightBlue),
+            (Role::Error, Color::BrightMagenta),
+            (Role::Warning, Color::BrightYellow),
+            (Role::Accent, Color::BrightWhite),
+        ];
+        for (role, color) in expected {
+            assert_eq!(palette.color(role), Some(color), "{:?}", role);
+        }
+        assert!(palette.bold);
+        // No role shares a color with another.
+        for (index, (role, color)) in expected.iter().enumerate() {
+            for (other, other_color) in &expected[index + 1..] {
+                assert_ne!(color, other_color, "{:?} and {:?}", role, other);
+            }
+        }
+    }
+
+    #[test]
+    fn high_contrast_on_light_background() {
+        let colors = StatusColors {
+            preset: Some("high-contrast".to_string()),
+            ..Default::default()
+        };
+        let (palette, _) = Palette::from_config(&colors, None, TerminalTheme::Light);
+        assert_eq!(palette.color(Role::Success), Some(Color::BrightBlue));
+        assert_eq!(palette.color(Role::Error), Some(Color::BrightMagenta));
+        assert_eq!(
+            palette.color(Role::Warning),
+            Some(Color::TrueColor {
+                r: 0xb5,
+                g: 0x76,
+                b: 0x00
+            })
+        );
+        assert_eq!(palette.color(Role::Accent), Some(Color::Black));
+    }
+
+    #[test]
+    fn configured_roles_override_the_preset() {
+        let colors = StatusColors {
+            preset: Some("high-contrast".to_string()),
+            success: Some("green".to_string()),
+            accent: Some("none".to_string()),
+            warning: Some("mauve".to_string()),
+            ..Default::default()
+        };
+        let (palette, problems) = Palette::from_config(&colors, None, TerminalTheme::Dark);
+        assert_eq!(palette.color(Role::Success), Some(Color::Green));
+        assert_eq!(palette.color(Role::Accent), None);
+        assert_eq!(palette.color(Role::Warning), Some(Color::BrightYellow));
+        assert_eq!(problems, ["unknown warning color 'mauve'"]);
+
+        let (palette, problems) =
+            Palette::from_config(&StatusColors::default(), Some("neon"), TerminalTheme::Dark);
+        assert_eq!(palette, Palette::DEFAULT);
+        assert_eq!(problems.len(), 1);
+    }
+}
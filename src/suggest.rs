@@ -0,0 +1,230 @@
+use chrono::Local;
+use std::collections::HashMap;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use crate::error::SnippetVaultError;
+use crate::import;
+use crate::index;
+use crate::pet::SHELL_LANGUAGES;
+use crate::snippet::{self, Frontmatter};
+use crate::vault::{self, Executor, Mutation};
+
+/// Words of a command that make up the title of a snippet created from it.
+const TITLE_WORDS: usize = 5;
+
+/// A history command run often enough, and long enough, to be worth saving.
+#[derive(Debug, Clone)]
+pub struct Candidate {
+    /// The command as last typed, continuation lines included.
+    pub command: String,
+    /// How many times it was run.
+    pub count: usize,
+}
+
+impl Candidate {
+    /// The command on one line with whitespace collapsed, as shown in the picker.
+    pub fn label(&self) -> String {
+        normalize(&self.command)
+    }
+}
+
+/// Which history commands [`find_candidates`] reports.
+#[derive(Debug, Clone, Copy)]
+pub struct Thresholds {
+    /// Commands at least this many characters long (normalized) are complex.
+    pub min_length: usize,
+    /// Commands with at least this many pipes are complex whatever their length.
+    pub min_pipes: usize,
+    /// Commands run fewer times than this are left out.
+    pub min_count: usize,
+}
+
+/// Reads the bash or zsh history at `history` and returns its repeated complex
+/// commands (multi-line ones always count as complex), most frequent first.
+/// Commands already in a code block of a shell snippet are left out.
+/// - `extensions`: File extensions that count as snippets.
+/// - `use_cache`: Serve unchanged snippets from the metadata cache.
+pub fn find_candidates(
+    history: &Path,
+    snippet_dir: &Path,
+    thresholds: Thresholds,
+    extensions: &[String],
+    use_cache: bool,
+) -> Result<Vec<Candidate>, SnippetVaultError> {
+    // zsh may write bytes that are not UTF-8 ("metafied" characters).
+    let content = String::from_utf8_lossy(&fs::read(history)?).into_owned();
+
+    let mut counts: HashMap<String, Candidate> = HashMap::new();
+    for command in parse_history(&content) {
+        let key = normalize(&command);
+        if !is_complex(&command, &key, thresholds) {
+            continue;
+        }
+        let candidate = counts.entry(key).or_insert(Candidate {
+            command: String::new(),
+            count: 0,
+        });
+        candidate.command = command;
+        candidate.count += 1;
+    }
+
+    let saved = saved_commands(snippet_dir, extensions, use_cache)?;
+    let mut candidates: Vec<(String, Candidate)> = counts
+        .into_iter()
+        .filter(|(key, candidate)| {
+            candidate.count >= thresholds.min_count
+                && !saved.iter().any(|code| code.contains(key.as_str()))
+        })
+        .collect();
+    candidates.sort_by(|(a_key, a), (b_key, b)| {
+        b.count
+            .cmp(&a.count)
+            .then_with(|| b_key.len().cmp(&a_key.len()))
+            .then_with(|| a_key.cmp(b_key))
+    });
+    Ok(candidates
+        .into_iter()
+        .map(|(_, candidate)| candidate)
+        .collect())
+}
+
+/// Splits a history file into commands. Understands zsh's extended format
+/// (`: 1700000000:0;command`), bash's `#1700000000` timestamp lines, and
+/// commands continued over several lines with a trailing backslash.
+pub fn parse_history(content: &str) -> Vec<String> {
+    let mut commands = Vec::new();
+    let mut current: Option<String> = None;
+
+    for line in content.lines() {
+        let command = match current.take() {
+            Some(mut command) => {
+                command.push('\n');
+                command.push_str(line);
+                command
+            }
+            None if is_bash_timestamp(line) => continue,
+            None => zsh_command(line).unwrap_or(line).to_string(),
+        };
+        if continues(&command) {
+            current = Some(command);
+        } else if !command.trim().is_empty() {
+            commands.push(command);
+        }
+    }
+    if let Some(command) = current.filter(|command| !command.trim().is_empty()) {
+        commands.push(command);
+    }
+    commands
+}
+
+/// The command of a zsh extended history line, if `line` is one.
+fn zsh_command(line: &str) -> Option<&str> {
+    let (header, command) = line.strip_prefix(": ")?.split_once(';')?;
+    let (start, duration) = header.split_once(':')?;
+    let numeric = |text: &str| !text.is_empty() && text.chars().all(|c| c.is_ascii_digit());
+    (numeric(start) && numeric(duration)).then_some(command)
+}
+
+/// Whether `line` is a timestamp bash writes before each command when
+/// `HISTTIMEFORMAT` is set.
+fn is_bash_timestamp(line: &str) -> bool {
+    line.strip_prefix('#')
+        .is_some_and(|rest| !rest.is_empty() && rest.chars().all(|c| c.is_ascii_digit()))
+}
+
+/// Whether `command` ends with an unescaped backslash, continuing on the next line.
+fn continues(command: &str) -> bool {
+    let trailing = command.len() - command.trim_end_matches('\\').len();
+    trailing % 2 == 1
+}
+
+/// `command` on one line: continuations joined and whitespace collapsed.
+fn normalize(command: &str) -> String {
+    command
+        .replace("\\\n", " ")
+        .split_whitespace()
+        .collect::<Vec<_>>()
+        .join(" ")
+}
+
+fn is_complex(command: &str, key: &str, thresholds: Thresholds) -> bool {
+    let pipes = key.matches('|').count() - 2 * key.matches("||").count();
+    command.contains('\n')
+        || key.chars().count() >= thresholds.min_length
+        || (thresholds.min_pipes > 0 && pipes >= thresholds.min_pipes)
+}
+
+/// The normalized code blocks of every shell snippet in the vault.
+fn saved_commands(
+    snippet_dir: &Path,
+    extensions: &[String],
+    use_cache: bool,
+) -> Result<Vec<String>, SnippetVaultError> {
+    if !snippet_dir.exists() {
+        return Ok(Vec::new());
+    }
+    let mut saved = Vec::new();
+    for meta in vault::load_snippets(snippet_dir, extensions, use_cache)?
+        .into_iter()
+        .filter(|meta| SHELL_LANGUAGES.contains(&meta.language.to_lowercase().as_str()))
+    {
+        let Ok(content) = fs::read_to_string(&meta.path) else {
+            continue;
+        };
+        if snippet::is_markdown(&meta.path) {
+            saved.extend(
+                snippet::code_blocks(&content)
+                    .iter()
+                    .map(|block| normalize(&block.code)),
+            );
+        } else {
+            saved.push(normalize(&content));
+        }
+    }
+    Ok(saved)
+}
+
+/// Creates a `bash` snippet for each of `candidates`, titled after the first
+/// words of its command, and returns their paths.
+/// - `timestamp_format`: strftime format of generated filenames.
+pub fn save_candidates(
+    candidates: &[Candidate],
+    snippet_dir: &Path,
+    timestamp_format: &str,
+    executor: &Executor,
+) -> Result<Vec<PathBuf>, SnippetVaultError> {
+    let _lock = executor.lock()?;
+    if !snippet_dir.exists() {
+        executor.apply(Mutation::CreateDir(snippet_dir.to_path_buf()))?;
+    }
+
+    let mut created = Vec::with_capacity(candidates.len());
+    for candidate in candidates {
+        let time = Local::now().naive_local();
+        let timestamp = import::render_timestamp(&time, timestamp_format);
+        let frontmatter = Frontmatter {
+            title: candidate
+                .label()
+                .split(' ')
+                .take(TITLE_WORDS)
+                .collect::<Vec<_>>()
+                .join(" "),
+            language: "bash".to_string(),
+            created: snippet::parse_timestamp(&timestamp).or(Some(time)),
+            ..Default::default()
+        };
+        let path = vault::available_path(snippet_dir, &timestamp, |timestamp| {
+            snippet::snippet_filename(timestamp, "bash", &[])
+        });
+        executor.apply(Mutation::Write {
+            path: path.clone(),
+            contents: import::render_snippet(&frontmatter, &timestamp, &candidate.command),
+        })?;
+        if !executor.is_dry_run() {
+            index::log_access(&path, index::Access::Created);
+        }
+        created.push(path);
+    }
+    Ok(created)
+}
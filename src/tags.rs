@@ -0,0 +1,659 @@
+use regex::Regex;
+use std::collections::{HashMap, HashSet};
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use crate::error::SnippetVaultError;
+use crate::snippet::{
+    filename_timestamp, is_markdown, parse_language_from_filename, parse_tags_from_filename,
+    snippet_filename_with_extension, split_frontmatter, Frontmatter, SnippetMeta,
+};
+use crate::vault::{self, scan_snippets, Executor, Mutation};
+
+/// Returns the filename `path` would have with `old_tag` replaced by `new_tag`,
+/// or `None` when the filename does not carry `old_tag`. A tag that would appear
+/// twice after the rename is kept only once.
+pub fn rename_tag_in_filename(path: &Path, old_tag: &str, new_tag: &str) -> Option<String> {
+    let tags = parse_tags_from_filename(path);
+    if !tags.iter().any(|tag| tag == old_tag) {
+        return None;
+    }
+
+    let timestamp = filename_timestamp(path)?;
+    let language = parse_language_from_filename(path)?;
+
+    let mut seen = HashSet::new();
+    let renamed: Vec<String> = tags
+        .into_iter()
+        .map(|tag| {
+            if tag == old_tag {
+                new_tag.to_string()
+            } else {
+                tag
+            }
+        })
+        .filter(|tag| seen.insert(tag.clone()))
+        .collect();
+
+    let extension = path.extension()?.to_str()?;
+    Some(snippet_filename_with_extension(
+        &timestamp, &language, &renamed, extension,
+    ))
+}
+
+/// Returns the filename `path` would have if it carried exactly `tags`, or
+/// `None` when the filename is not a generated one (it then keeps its name).
+pub fn retag_filename(path: &Path, tags: &[String]) -> Option<String> {
+    let timestamp = filename_timestamp(path)?;
+    let language = parse_language_from_filename(path)?;
+    let extension = path.extension()?.to_str()?;
+    Some(snippet_filename_with_extension(
+        &timestamp, &language, tags, extension,
+    ))
+}
+
+/// `tags` without repeats, each kept where it first appears.
+pub fn dedup_preserving_order(tags: &[String]) -> Vec<String> {
+    let mut seen = HashSet::new();
+    tags.iter()
+        .filter(|tag| seen.insert(tag.as_str()))
+        .cloned()
+        .collect()
+}
+
+/// Splits an edited tag list on commas and whitespace, dropping empty entries
+/// and repeats.
+pub fn parse_tag_list(input: &str) -> Vec<String> {
+    let tags: Vec<String> = input
+        .split(|c: char| c == ',' || c.is_whitespace())
+        .filter(|tag| !tag.is_empty())
+        .map(str::to_string)
+        .collect();
+    dedup_preserving_order(&tags)
+}
+
+/// Drops repeated tags from the frontmatter of the snippet at `path` and from
+/// its generated filename (`snippet_..._rust_async_async.md`), renaming the
+/// file when its name changes. Both changes are planned first, so a filename
+/// the rename would clash with stops it before the frontmatter is written, and
+/// a failed rename puts the frontmatter back. Returns the snippet's new path
+/// when anything changed, `None` when it had no duplicates.
+/// - `executor`: Applies (or, with `--dry-run`, only prints) the changes.
+pub fn dedup_tags(path: &Path, executor: &Executor) -> Result<Option<PathBuf>, SnippetVaultError> {
+    let tags = parse_tags_from_filename(path);
+    let deduped = dedup_preserving_order(&tags);
+    let new_path = match retag_filename(path, &deduped) {
+        Some(name) if deduped != tags => path.with_file_name(name),
+        _ => path.to_path_buf(),
+    };
+    if new_path != path && new_path.exists() {
+        return Err(SnippetVaultError::AlreadyExists(new_path));
+    }
+
+    let mut original = None;
+    if is_markdown(path) {
+        let content = fs::read_to_string(path)?;
+        if let Some(mut frontmatter) = Frontmatter::parse(path, &content)? {
+            let tags = dedup_preserving_order(&frontmatter.tags);
+            if tags != frontmatter.tags {
+                frontmatter.tags = tags;
+                let body = split_frontmatter(&content).1;
+                executor.apply(Mutation::Write {
+                    path: path.to_path_buf(),
+                    contents: format!("{}{}", frontmatter.render(), body),
+                })?;
+                original = Some(content);
+            }
+        }
+    }
+
+    if new_path != path {
+        let renamed = executor.apply(Mutation::Rename {
+            from: path.to_path_buf(),
+            to: new_path.clone(),
+        });
+        if let Err(err) = renamed {
+            if let Some(contents) = original {
+                let undo = Mutation::Write {
+                    path: path.to_path_buf(),
+                    contents,
+                };
+                if let Err(undo_err) = executor.apply(undo.clone()) {
+                    tracing::error!(mutation = %undo, err = %undo_err, "cannot undo tag dedup step");
+                }
+            }
+            return Err(err);
+        }
+        return Ok(Some(new_path));
+    }
+    Ok(original.is_some().then_some(new_path))
+}
+
+/// Lists every snippet that renaming `old_tag` to `new_tag` would touch, as
+/// `(path, old_filename, new_filename)`. Snippets that only carry the tag in their
+/// frontmatter keep their filename.
+pub fn preview_tag_rename(
+    old_tag: &str,
+    new_tag: &str,
+    snippet_dir: &Path,
+) -> Result<Vec<(PathBuf, String, String)>, SnippetVaultError> {
+    let mut affected = Vec::new();
+
+    for (path, meta) in scan_snippets(snippet_dir, SnippetMeta::read)? {
+        let old_filename = file_name(&path);
+        let renamed = rename_tag_in_filename(&path, old_tag, new_tag);
+        let in_frontmatter = meta.is_ok_and(|meta| meta.tags.iter().any(|tag| tag == old_tag));
+
+        if renamed.is_some() || in_frontmatter {
+            let new_filename = renamed.unwrap_or_else(|| old_filename.clone());
+            affected.push((path, old_filename, new_filename));
+        }
+    }
+
+    Ok(affected)
+}
+
+/// Directory of the vault holding collection files (`*.toml`), whose
+/// `tag = "..."` filters follow tag renames.
+pub const COLLECTIONS_DIR: &str = "_collections";
+
+/// What [`rename_tag_cascade`] changed.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub struct CascadeReport {
+    pub snippets_updated: usize,
+    pub collections_updated: usize,
+}
+
+/// Renames `old_tag` to `new_tag` in every snippet that carries it (in its
+/// frontmatter, its generated filename, or both) and in the `tag = "..."`
+/// entries of the collection files under [`COLLECTIONS_DIR`]. Every change is
+/// planned first, and a filename the rename would clash with stops it before
+/// anything is written. If a write or rename then fails, the ones already
+/// applied are undone in reverse order, so snippets and collections change
+/// together or not at all. Returns the renamed snippets as `(old, new)` paths
+/// alongside the counts.
+/// - `executor`: Applies (or, with `--dry-run`, only prints) the changes.
+pub fn rename_tag_cascade(
+    old_tag: &str,
+    new_tag: &str,
+    snippet_dir: &Path,
+    executor: &Executor,
+) -> Result<(CascadeReport, Vec<(PathBuf, PathBuf)>), SnippetVaultError> {
+    let renames = preview_tag_rename(old_tag, new_tag, snippet_dir)?;
+    let mut targets = HashSet::new();
+    let mut mutations = Vec::new();
+    let mut moved = Vec::new();
+    for (path, old_filename, new_filename) in &renames {
+        if is_markdown(path) {
+            let content = fs::read_to_string(path)?;
+            if let Some(mut frontmatter) = Frontmatter::parse(path, &content)? {
+                if frontmatter.tags.iter().any(|tag| tag == old_tag) {
+                    let tags: Vec<String> = frontmatter
+                        .tags
+                        .iter()
+                        .map(|tag| if tag == old_tag { new_tag } else { tag }.to_string())
+                        .collect();
+                    frontmatter.tags = dedup_preserving_order(&tags);
+                    let body = split_frontmatter(&content).1;
+                    mutations.push((
+                        Mutation::Write {
+                            path: path.clone(),
+                            contents: format!("{}{}", frontmatter.render(), body),
+                        },
+                        Mutation::Write {
+                            path: path.clone(),
+                            contents: content,
+                        },
+                    ));
+                }
+            }
+        }
+        if old_filename != new_filename {
+            let target = path.with_file_name(new_filename);
+            if target.exists() || !targets.insert(target.clone()) {
+                return Err(SnippetVaultError::AlreadyExists(target));
+            }
+            mutations.push((
+                Mutation::Rename {
+                    from: path.clone(),
+                    to: target.clone(),
+                },
+                Mutation::Rename {
+                    from: target.clone(),
+                    to: path.clone(),
+                },
+            ));
+            moved.push((path.clone(), target));
+        } else {
+            moved.push((path.clone(), path.clone()));
+        }
+    }
+
+    let collections = collection_updates(old_tag, new_tag, snippet_dir)?;
+    let report = CascadeReport {
+        snippets_updated: renames.len(),
+        collections_updated: collections.len(),
+    };
+    for (path, original, updated) in collections {
+        mutations.push((
+            Mutation::Write {
+                path: path.clone(),
+                contents: updated,
+            },
+            Mutation::Write {
+                path,
+                contents: original,
+            },
+        ));
+    }
+
+    let mut undo: Vec<Mutation> = Vec::new();
+    for (mutation, reverse) in mutations {
+        if let Err(err) = executor.apply(mutation) {
+            for mutation in undo.into_iter().rev() {
+                if let Err(undo_err) = executor.apply(mutation.clone()) {
+                    tracing::error!(%mutation, err = %undo_err, "cannot undo tag rename step");
+                }
+            }
+            return Err(err);
+        }
+        undo.push(reverse);
+    }
+    Ok((report, moved))
+}
+
+/// The collection files under [`COLLECTIONS_DIR`] with a `tag = "old_tag"`
+/// (or `tag = 'old_tag'`) entry, as `(path, original, updated)`; only the
+/// quoted tag changes, so comments and layout are kept. A literal string
+/// stays literal unless `new_tag` contains a `'`, which only a basic string can
+/// hold. A vault without collections has none.
+fn collection_updates(
+    old_tag: &str,
+    new_tag: &str,
+    snippet_dir: &Path,
+) -> Result<Vec<(PathBuf, String, String)>, SnippetVaultError> {
+    let dir = snippet_dir.join(COLLECTIONS_DIR);
+    if !dir.is_dir() {
+        return Ok(Vec::new());
+    }
+    // No backreferences in `regex`, so each quote style gets its own branch.
+    let pattern = Regex::new(&format!(
+        r#"(?m)^(\s*tag\s*=\s*)(?:"{}"|'{}')"#,
+        regex::escape(&basic_string_body(old_tag)),
+        regex::escape(old_tag)
+    ))
+    .expect("collection tag pattern is valid");
+
+    let mut paths: Vec<PathBuf> = fs::read_dir(&dir)?
+        .filter_map(|entry| entry.ok().map(|entry| entry.path()))
+        .filter(|path| path.extension().is_some_and(|ext| ext == "toml"))
+        .collect();
+    paths.sort();
+    let mut updates = Vec::new();
+    for path in paths {
+        let original = fs::read_to_string(&path)?;
+        let updated = pattern.replace_all(&original, |caps: &regex::Captures| {
+            let literal = caps[0].ends_with('\'');
+            if literal && !new_tag.contains('\'') {
+                format!("{}'{}'", &caps[1], new_tag)
+            } else {
+                format!("{}\"{}\"", &caps[1], basic_string_body(new_tag))
+            }
+        });
+        if updated != original {
+            let updated = updated.into_owned();
+            updates.push((path, original, updated));
+        }
+    }
+    Ok(updates)
+}
+
+/// `text` escaped for the inside of a TOML basic (double-quoted) string.
+fn basic_string_body(text: &str) -> String {
+    text.replace('\\', "\\\\").replace('"', "\\\"")
+}
+
+/// Finds problems a tag rename would run into: snippets already tagged `new_tag`
+/// (which the rename would merge with), and new filenames that already exist
+/// or that two renamed snippets would share.
+pub fn find_tag_collisions(
+    new_tag: &str,
+    snippet_dir: &Path,
+    renames: &[(PathBuf, String, String)],
+) -> Result<Vec<String>, SnippetVaultError> {
+    let mut warnings = Vec::new();
+
+    let renamed_paths: HashSet<&PathBuf> = renames.iter().map(|(path, _, _)| path).collect();
+    for (path, meta) in scan_snippets(snippet_dir, SnippetMeta::read)? {
+        let tagged = parse_tags_from_filename(&path)
+            .iter()
+            .any(|tag| tag == new_tag)
+            || meta.is_ok_and(|meta| meta.tags.iter().any(|tag| tag == new_tag));
+        if tagged && !renamed_paths.contains(&path) {
+            warnings.push(format!(
+                "{} is already tagged '{}'",
+                file_name(&path),
+                new_tag
+            ));
+        }
+    }
+
+    let mut targets = HashSet::new();
+    for (path, old_filename, new_filename) in renames {
+        if old_filename == new_filename {
+            continue;
+        }
+        let target = path.with_file_name(new_filename);
+        if target.exists() {
+            warnings.push(format!("{} already exists", new_filename));
+        }
+        if !targets.insert(target) {
+            warnings.push(format!(
+                "more than one snippet would become {}",
+                new_filename
+            ));
+        }
+    }
+
+    Ok(warnings)
+}
+
+/// Whether any of `tags` is `prefix` or lies below it in the dot-separated tag
+/// hierarchy (`rust` matches `rust`, `rust.async`, and `rust.async.tokio`, but
+/// not `rustls`). Compared case-insensitively.
+pub fn filter_by_tag_prefix(prefix: &str, tags: &[String]) -> bool {
+    tags.iter().any(|tag| {
+        tag.get(..prefix.len())
+            .is_some_and(|head| head.eq_ignore_ascii_case(prefix))
+            && (tag.len() == prefix.len() || tag[prefix.len()..].starts_with('.'))
+    })
+}
+
+/// Every tag of `snippet`: its frontmatter tags followed by any tags in its
+/// generated filename that the frontmatter lacks (compared case-insensitively).
+pub fn snippet_tags(snippet: &SnippetMeta) -> Vec<String> {
+    let mut tags = snippet.tags.clone();
+    for tag in parse_tags_from_filename(&snippet.path) {
+        if !tags.iter().any(|known| known.eq_ignore_ascii_case(&tag)) {
+            tags.push(tag);
+        }
+    }
+    tags
+}
+
+/// Paths of the snippets tagged both `tag_a` and `tag_b`, in their frontmatter
+/// or filename. Tags match as in [`filter_by_tag_prefix`], so `rust` also
+/// covers `rust.async`.
+/// - `extensions`: File extensions that count as snippets.
+/// - `use_cache`: Serve unchanged snippets from the metadata cache.
+pub fn find_tag_cooccurrence(
+    tag_a: &str,
+    tag_b: &str,
+    snippet_dir: &Path,
+    extensions: &[String],
+    use_cache: bool,
+) -> Result<Vec<PathBuf>, SnippetVaultError> {
+    let mut snippets = vault::load_snippets(snippet_dir, extensions, use_cache)?;
+    snippets.sort_by(|a, b| a.path.cmp(&b.path));
+    Ok(snippets
+        .into_iter()
+        .filter(|snippet| {
+            let tags = snippet_tags(snippet);
+            filter_by_tag_prefix(tag_a, &tags) && filter_by_tag_prefix(tag_b, &tags)
+        })
+        .map(|snippet| snippet.path)
+        .collect())
+}
+
+/// Groups `tags` by their dot-separated prefixes: each key is a parent tag
+/// (`""` for the top level) and its value the sorted full names of its direct
+/// children. Parents that are not used as tags themselves are still included,
+/// so `rust.async.tokio` alone yields `rust` and `rust.async`. Empty segments
+/// are skipped: `.x` is `x`, `a..b` is `a.b`, and an empty tag adds nothing.
+pub fn parse_tag_hierarchy(tags: &[String]) -> HashMap<String, Vec<String>> {
+    let mut hierarchy: HashMap<String, Vec<String>> = HashMap::new();
+    for tag in tags {
+        let mut parent = String::new();
+        for segment in tag.split('.').filter(|segment| !segment.is_empty()) {
+            let node = match parent.is_empty() {
+                true => segment.to_string(),
+                false => format!("{}.{}", parent, segment),
+            };
+            let children = hierarchy.entry(parent).or_default();
+            if !children.contains(&node) {
+                children.push(node.clone());
+            }
+            parent = node;
+        }
+    }
+    for children in hierarchy.values_mut() {
+        children.sort();
+    }
+    hierarchy
+}
+
+/// Renders a [`parse_tag_hierarchy`] result as an indented tree, one line per
+/// tag showing its last segment and `counts[tag]` when present.
+pub fn render_tag_tree(
+    hierarchy: &HashMap<String, Vec<String>>,
+    counts: &HashMap<String, usize>,
+) -> Vec<String> {
+    fn walk(
+        parent: &str,
+        indent: &str,
+        hierarchy: &HashMap<String, Vec<String>>,
+        counts: &HashMap<String, usize>,
+        lines: &mut Vec<String>,
+    ) {
+        let Some(children) = hierarchy.get(parent) else {
+            return;
+        };
+        // A node listed under itself would recurse forever.
+        let children: Vec<&String> = children.iter().filter(|child| *child != parent).collect();
+        for (index, &child) in children.iter().enumerate() {
+            let last = index + 1 == children.len();
+            let name = child.rsplit('.').next().unwrap_or(child);
+            let count = counts
+                .get(child)
+                .map(|count| format!(" ({})", count))
+                .unwrap_or_default();
+            let (branch, continuation) = match (parent.is_empty(), last) {
+                (true, _) => ("", ""),
+                (false, false) => ("├── ", "│   "),
+                (false, true) => ("└── ", "    "),
+            };
+            lines.push(format!("{}{}{}{}", indent, branch, name, count));
+            walk(
+                child,
+                &format!("{}{}", indent, continuation),
+                hierarchy,
+                counts,
+                lines,
+            );
+        }
+    }
+
+    let mut lines = Vec::new();
+    walk("", "", hierarchy, counts, &mut lines);
+    lines
+}
+
+fn file_name(path: &Path) -> String {
+    path.file_name()
+        .map(|name| name.to_string_lossy().into_owned())
+        .unwrap_or_default()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// A collection file holding `content` after renaming `old` to `new`, or
+    /// `None` when the rename leaves it alone.
+    fn renamed(content: &str, old: &str, new: &str) -> Option<String> {
+        let vault = tempfile::tempdir().unwrap();
+        let dir = vault.path().join(COLLECTIONS_DIR);
+        fs::create_dir_all(&dir).unwrap();
+        fs::write(dir.join("work.toml"), content).unwrap();
+        let mut updates = collection_updates(old, new, vault.path()).unwrap();
+        updates.pop().map(|(_, _, updated)| updated)
+    }
+
+    #[test]
+    fn collection_tags_keep_their_quotes() {
+        assert_eq!(
+            renamed("tag = \"rust\" # main\n", "rust", "rs").as_deref(),
+            Some("tag = \"rs\" # main\n")
+        );
+        assert_eq!(
+            renamed("  tag = 'rust'\n", "rust", "rs").as_deref(),
+            Some("  tag = 'rs'\n")
+        );
+        // Mismatched quotes are not a TOML string, and other tags stay.
+        assert_eq!(renamed("tag = \"rust'\n", "rust", "rs"), None);
+        assert_eq!(renamed("tag = \"rust.async\"\n", "rust", "rs"), None);
+    }
+
+    #[test]
+    fn collection_tags_are_escaped_for_toml() {
+        assert_eq!(
+            renamed("tag = \"old\"\n", "old", r#"say "hi" \o/"#).as_deref(),
+            Some("tag = \"say \\\"hi\\\" \\\\o/\"\n")
+        );
+        assert_eq!(
+            renamed("tag = 'old'\n", "old", r"c:\tmp").as_deref(),
+            Some("tag = 'c:\\tmp'\n")
+        );
+        // A literal string cannot hold a `'`, so it becomes a basic string.
+        assert_eq!(
+            renamed("tag = 'old'\n", "old", "it's").as_deref(),
+            Some("tag = \"it's\"\n")
+        );
+        assert_eq!(
+            renamed("tag = \"a\\\"b\"\n", "a\"b", "ab").as_deref(),
+            Some("tag = \"ab\"\n")
+        );
+        for updated in [
+            renamed("tag = 'old'\n", "old", "it's \"x\"").unwrap(),
+            renamed("tag = \"old\"\n", "old", r"back\slash").unwrap(),
+        ] {
+            assert!(updated.parse::<toml::Table>().is_ok(), "{}", updated);
+        }
+    }
+
+    /// Every file under `dir` with its contents, by path relative to `dir`.
+    fn snapshot(dir: &Path) -> Vec<(PathBuf, String)> {
+        let mut files = Vec::new();
+        let mut pending = vec![dir.to_path_buf()];
+        while let Some(current) = pending.pop() {
+            for entry in fs::read_dir(&current).unwrap() {
+                let path = entry.unwrap().path();
+                if path.is_dir() {
+                    pending.push(path);
+                } else {
+                    let relative = path.strip_prefix(dir).unwrap().to_path_buf();
+                    files.push((relative, fs::read_to_string(&path).unwrap()));
+                }
+            }
+        }
+        files.sort();
+        files
+    }
+
+    #[test]
+    fn a_failed_collection_write_restores_the_snippets() {
+        let vault = tempfile::tempdir().unwrap();
+        for (name, title) in [
+            ("snippet_2024-05-17-093000_bash_git.md", "Undo"),
+            ("snippet_2024-05-18-101500_bash_git_undo.md", "Amend"),
+        ] {
+            fs::write(
+                vault.path().join(name),
+                format!(
+                    "+++\ntitle = \"{}\"\nlanguage = \"bash\"\ntags = [\"git\"]\n+++\n\n```bash\ngit status\n```\n",
+                    title
+                ),
+            )
+            .unwrap();
+        }
+        let collections = vault.path().join(COLLECTIONS_DIR);
+        fs::create_dir_all(&collections).unwrap();
+        fs::write(collections.join("a.toml"), "tag = \"git\"\n").unwrap();
+        fs::write(collections.join("b.toml"), "tag = 'git'\n").unwrap();
+        let before = snapshot(vault.path());
+
+        // A directory where the atomic write of b.toml puts its temporary
+        // file makes that write fail after the snippets and a.toml changed.
+        fs::create_dir(collections.join(format!(".b.toml.{}.tmp", std::process::id()))).unwrap();
+        let result = rename_tag_cascade("git", "vcs", vault.path(), &Executor::new(false));
+
+        assert!(result.is_err());
+        assert_eq!(snapshot(vault.path()), before);
+    }
+
+    #[test]
+    fn a_clashing_dedup_rename_leaves_the_frontmatter_alone() {
+        crate::testing::isolate_user_dirs();
+        let vault = tempfile::tempdir().unwrap();
+        let path = vault
+            .path()
+            .join("snippet_2024-05-17-093000_rust_async_async.md");
+        fs::write(
+            &path,
+            "+++\ntitle = \"Spawn\"\nlanguage = \"rust\"\ntags = [\"async\", \"async\"]\n+++\n\n```rust\n```\n",
+        )
+        .unwrap();
+        fs::write(
+            vault.path().join("snippet_2024-05-17-093000_rust_async.md"),
+            "taken",
+        )
+        .unwrap();
+        let before = snapshot(vault.path());
+
+        let result = dedup_tags(&path, &Executor::new(false));
+
+        assert!(matches!(result, Err(SnippetVaultError::AlreadyExists(_))));
+        assert_eq!(snapshot(vault.path()), before);
+
+        fs::remove_file(vault.path().join("snippet_2024-05-17-093000_rust_async.md")).unwrap();
+        let renamed = dedup_tags(&path, &Executor::new(false)).unwrap().unwrap();
+        assert_eq!(
+            file_name(&renamed),
+            "snippet_2024-05-17-093000_rust_async.md"
+        );
+        assert!(fs::read_to_string(&renamed)
+            .unwrap()
+            .contains("tags = [\"async\"]"));
+    }
+
+    fn tree(tags: &[&str]) -> Vec<String> {
+        let tags: Vec<String> = tags.iter().map(|tag| tag.to_string()).collect();
+        let counts = HashMap::from([("rust".to_string(), 2)]);
+        render_tag_tree(&parse_tag_hierarchy(&tags), &counts)
+    }
+
+    #[test]
+    fn tag_tree_nests_dotted_tags() {
+        assert_eq!(
+            tree(&["rust.async.tokio", "rust", "go"]),
+            ["go", "rust (2)", "└── async", "    └── tokio"]
+        );
+    }
+
+    #[test]
+    fn empty_segments_are_skipped() {
+        assert_eq!(tree(&[".x"]), ["x"]);
+        assert_eq!(tree(&[""]), Vec::<String>::new());
+        assert_eq!(tree(&["", ".", "a..b", "a."]), ["a", "└── b"]);
+        assert!(!parse_tag_hierarchy(&[String::new()]).contains_key(""));
+    }
+
+    #[test]
+    fn a_node_under_itself_is_not_walked() {
+        let hierarchy = HashMap::from([
+            (String::new(), vec!["a".to_string()]),
+            ("a".to_string(), vec!["a.b".to_string(), "a".to_string()]),
+        ]);
+        assert_eq!(render_tag_tree(&hierarchy, &HashMap::new()), ["a", "└── b"]);
+    }
+}
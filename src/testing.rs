@@ -0,0 +1,18 @@
+use std::env;
+use std::sync::OnceLock;
+use tempfile::TempDir;
+
+/// Points the cache, data, and config directories of the test process at a
+/// throwaway directory, so tests that take the vault lock or update the index
+/// never touch the real ones. Call it first in every such test; the first
+/// call sets the variables and later calls do nothing.
+pub fn isolate_user_dirs() {
+    static DIRS: OnceLock<TempDir> = OnceLock::new();
+    DIRS.get_or_init(|| {
+        let dirs = tempfile::tempdir().expect("can create a temporary directory");
+        env::set_var("XDG_CACHE_HOME", dirs.path().join("cache"));
+        env::set_var("XDG_DATA_HOME", dirs.path().join("data"));
+        env::set_var("XDG_CONFIG_HOME", dirs.path().join("config"));
+        dirs
+    });
+}
@@ -0,0 +1,59 @@
+/// Escapes `input` for a URI path segment or query value: every UTF-8 byte
+/// other than the unreserved characters of RFC 3986 becomes `%XX`, so spaces,
+/// `/`, `&`, and non-ASCII letters survive the round trip.
+pub fn percent_encode(input: &str) -> String {
+    let mut encoded = String::with_capacity(input.len());
+    for byte in input.bytes() {
+        if byte.is_ascii_alphanumeric() || b"-._~".contains(&byte) {
+            encoded.push(byte as char);
+        } else {
+            encoded.push_str(&format!("%{:02X}", byte));
+        }
+    }
+    encoded
+}
+
+/// Undoes [`percent_encode`] (and any other `%XX` escapes); malformed escapes
+/// are kept as they are.
+pub fn percent_decode(input: &str) -> String {
+    let bytes = input.as_bytes();
+    let mut decoded = Vec::with_capacity(bytes.len());
+    let mut index = 0;
+    while index < bytes.len() {
+        let escaped = (bytes[index] == b'%')
+            .then(|| input.get(index + 1..index + 3))
+            .flatten()
+            .and_then(|hex| u8::from_str_radix(hex, 16).ok());
+        match escaped {
+            Some(byte) => {
+                decoded.push(byte);
+                index += 3;
+            }
+            None => {
+                decoded.push(bytes[index]);
+                index += 1;
+            }
+        }
+    }
+    String::from_utf8_lossy(&decoded).into_owned()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn encoding_escapes_everything_but_unreserved_characters() {
+        assert_eq!(percent_encode("café notes.md"), "caf%C3%A9%20notes.md");
+        assert_eq!(percent_encode("a&b=c/d?e#f"), "a%26b%3Dc%2Fd%3Fe%23f");
+        assert_eq!(percent_encode("Az09-._~"), "Az09-._~");
+    }
+
+    #[test]
+    fn decoding_undoes_encoding_and_keeps_malformed_escapes() {
+        for text in ["café notes.md", "a&b=c", "100%", "整理 提交"] {
+            assert_eq!(percent_decode(&percent_encode(text)), text);
+        }
+        assert_eq!(percent_decode("%2E%2E/%zz%4"), "../%zz%4");
+    }
+}
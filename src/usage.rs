@@ -0,0 +1,123 @@
+use std::collections::HashMap;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use crate::cache::{Fingerprint, MetadataCache};
+use crate::error::SnippetVaultError;
+use crate::fields;
+use crate::index::{self, Access};
+use crate::snippet::{self, Frontmatter, SnippetMeta};
+use crate::vault::{self, Executor};
+
+/// Frontmatter field counting the uses of a snippet when `usage_store` is
+/// `frontmatter`.
+pub const USES_FIELD: &str = "uses";
+
+/// Values of the `usage_store` config key.
+pub const USAGE_STORES: &[&str] = &["index", "frontmatter"];
+
+/// Where uses of a snippet are counted.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum UsageStore {
+    /// As `used` entries of the access log in the index.
+    #[default]
+    Index,
+    /// In the `uses` frontmatter field of markdown snippets with frontmatter;
+    /// other snippets fall back to the index.
+    Frontmatter,
+}
+
+impl UsageStore {
+    /// Parses one of [`USAGE_STORES`].
+    pub fn from_name(name: &str) -> Option<Self> {
+        match name {
+            "index" => Some(UsageStore::Index),
+            "frontmatter" => Some(UsageStore::Frontmatter),
+            _ => None,
+        }
+    }
+}
+
+/// Counts one use (copy, run, or `--get`) of the snippet at `path`. Best
+/// effort like [`index::log_access`]: a failure is logged, never returned.
+/// Uses of snippets in a read-only vault go to the index; under `--dry-run`
+/// nothing is counted.
+pub fn record_use(path: &Path, store: UsageStore, executor: &Executor) {
+    if executor.is_dry_run() {
+        return;
+    }
+    let result = match store {
+        UsageStore::Frontmatter if !vault::is_read_only() && has_frontmatter(path) => {
+            increment_field(path, executor)
+        }
+        _ => index::open(&index::index_path())
+            .and_then(|db| index::record_access(&db, path, Access::Used)),
+    };
+    if let Err(err) = result {
+        tracing::warn!(path = %path.display(), %err, "cannot record the use of a snippet");
+    }
+}
+
+/// Whether `path` is a markdown snippet that already has a frontmatter block;
+/// only those get a `uses` field, so counting never adds one.
+fn has_frontmatter(path: &Path) -> bool {
+    snippet::is_markdown(path)
+        && fs::read_to_string(path).is_ok_and(|content| {
+            Frontmatter::parse(path, &content).is_ok_and(|frontmatter| frontmatter.is_some())
+        })
+}
+
+/// Adds one to the `uses` field of `path` under the vault lock, then puts the
+/// modification time back so counting does not make the snippet look edited.
+fn increment_field(path: &Path, executor: &Executor) -> Result<(), SnippetVaultError> {
+    let _lock = executor.lock()?;
+    let modified = fs::metadata(path)?.modified()?;
+    let uses = field_uses(path).unwrap_or(0);
+    fields::set_field(
+        path,
+        USES_FIELD,
+        Some(toml::Value::Integer(uses as i64 + 1)),
+        executor,
+    )?;
+    fs::File::options()
+        .write(true)
+        .open(path)?
+        .set_modified(modified)?;
+
+    // The cache fingerprint is the size and modification time, which can both
+    // be unchanged now (`uses = 1` -> `uses = 2`), so refresh the entry here.
+    if let Some(fingerprint) = Fingerprint::of(path) {
+        let mut cache = MetadataCache::load();
+        cache.insert(fingerprint, SnippetMeta::read(path)?);
+        cache.save()?;
+    }
+    Ok(())
+}
+
+/// The `uses` frontmatter field of `path`, read from the file itself.
+fn field_uses(path: &Path) -> Option<usize> {
+    let content = fs::read_to_string(path).ok()?;
+    let frontmatter = Frontmatter::parse(path, &content).ok()??;
+    let uses = frontmatter.extra.get(USES_FIELD)?.as_integer()?;
+    usize::try_from(uses).ok()
+}
+
+/// Uses of each of `snippets`: its `uses` frontmatter field plus its `used`
+/// entries in the access log. Snippets never used are left out. The access
+/// log is best effort; when it cannot be read only the fields count.
+pub fn use_counts(snippets: &[SnippetMeta]) -> HashMap<PathBuf, usize> {
+    let mut counts = index::open(&index::index_path())
+        .and_then(|db| index::access_counts(&db, Access::Used))
+        .unwrap_or_default();
+    for snippet in snippets {
+        let uses = snippet
+            .extra
+            .get(USES_FIELD)
+            .and_then(|value| value.parse::<usize>().ok())
+            .unwrap_or(0);
+        if uses > 0 {
+            *counts.entry(snippet.path.clone()).or_default() += uses;
+        }
+    }
+    counts
+}
@@ -0,0 +1,917 @@
+use base64::Engine;
+use chrono::NaiveDate;
+use quick_xml::events::Event;
+use quick_xml::Reader;
+use serde::{Deserialize, Serialize};
+use std::cell::RefCell;
+use std::collections::{BTreeMap, BTreeSet};
+use std::env;
+use std::fmt;
+use std::fs;
+use std::path::{Component, Path, PathBuf};
+use std::process::Command as ProcessCommand;
+use std::time::UNIX_EPOCH;
+
+use crate::error::SnippetVaultError;
+use crate::platform;
+use crate::style::Styled;
+use crate::uri::{percent_decode, percent_encode};
+use crate::vault::{self, Executor, Mutation};
+
+/// Environment variable holding the WebDAV password when the config has none.
+pub const PASSWORD_ENV: &str = "SNIPPETS_VAULT_WEBDAV_PASSWORD";
+
+/// Marker in the names of the remote copies saved for conflicts; such files
+/// are never synced themselves.
+pub const CONFLICT_MARKER: &str = ".conflict-";
+
+/// Body of the PROPFIND requests listing a collection.
+const PROPFIND_BODY: &str = r#"<?xml version="1.0" encoding="utf-8"?>
+<d:propfind xmlns:d="DAV:"><d:prop><d:resourcetype/><d:getetag/><d:getcontentlength/><d:getlastmodified/></d:prop></d:propfind>"#;
+
+/// Where the vault is synced, from the `[webdav]` config section.
+#[derive(Clone, Default, Deserialize)]
+#[serde(default)]
+pub struct WebDavConfig {
+    /// WebDAV root of the account, e.g.
+    /// `https://cloud.example.com/remote.php/dav/files/alice`.
+    pub url: String,
+    /// Folder under `url` holding the vault; may be empty.
+    pub remote_path: String,
+    pub username: String,
+    /// Falls back to `SNIPPETS_VAULT_WEBDAV_PASSWORD`, then `password_command`.
+    pub password: String,
+    /// Command printing the password, e.g. a keyring lookup like
+    /// `secret-tool lookup service nextcloud`.
+    pub password_command: Option<String>,
+}
+
+// Hand-written so the password never ends up in debug logs.
+impl fmt::Debug for WebDavConfig {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("WebDavConfig")
+            .field("url", &self.url)
+            .field("remote_path", &self.remote_path)
+            .field("username", &self.username)
+            .finish_non_exhaustive()
+    }
+}
+
+impl WebDavConfig {
+    /// Fills in a missing password from [`PASSWORD_ENV`] or `password_command`,
+    /// then checks nothing required is missing.
+    pub fn resolve(mut self) -> Result<Self, SnippetVaultError> {
+        if self.url.is_empty() {
+            return Err(SnippetVaultError::Config(
+                "[webdav] needs a url".to_string(),
+            ));
+        }
+        if self.password.is_empty() {
+            self.password = env::var(PASSWORD_ENV).unwrap_or_default();
+        }
+        if self.password.is_empty() {
+            if let Some(command) = &self.password_command {
+                self.password = run_password_command(command)?;
+            }
+        }
+        Ok(self)
+    }
+
+    /// URL of the vault folder, ending in `/`.
+    pub fn root(&self) -> String {
+        let mut root = self.url.trim_end_matches('/').to_string();
+        for segment in self.remote_path.split('/').filter(|s| !s.is_empty()) {
+            root.push('/');
+            root.push_str(&percent_encode(segment));
+        }
+        root.push('/');
+        root
+    }
+}
+
+/// The first line `command` prints, run through the shell.
+fn run_password_command(command: &str) -> Result<String, SnippetVaultError> {
+    let (shell, flags) = platform::shell_command();
+    let output = ProcessCommand::new(shell)
+        .args(flags)
+        .arg(command)
+        .output()?;
+    if !output.status.success() {
+        return Err(SnippetVaultError::Command(format!(
+            "password_command exited with {}",
+            output.status
+        )));
+    }
+    Ok(String::from_utf8_lossy(&output.stdout)
+        .lines()
+        .next()
+        .unwrap_or_default()
+        .to_string())
+}
+
+/// Which way `--sync webdav` copies files.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Direction {
+    /// Upload local changes and download remote ones; deletions propagate.
+    Both,
+    /// Only upload new and changed local files.
+    PushOnly,
+    /// Only download new and changed remote files.
+    PullOnly,
+}
+
+/// A snippet in the vault as of now.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct LocalFile {
+    /// Modification time, in seconds since the epoch.
+    pub modified: i64,
+    pub size: u64,
+}
+
+/// A file on the server as listed by PROPFIND.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct RemoteFile {
+    /// The ETag, or the size and modification date for servers without ETags.
+    pub version: String,
+}
+
+/// Both sides of a file as of the last sync, from the manifest.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ManifestEntry {
+    pub modified: i64,
+    pub size: u64,
+    pub version: String,
+}
+
+/// What happened at the last sync of each remote folder, keyed by its URL and
+/// then by the path of each file relative to the vault.
+#[derive(Debug, Default, Serialize, Deserialize)]
+pub struct Manifest {
+    roots: BTreeMap<String, BTreeMap<String, ManifestEntry>>,
+}
+
+impl Manifest {
+    /// Location of the manifest file.
+    pub fn path() -> PathBuf {
+        dirs::cache_dir()
+            .unwrap_or_else(env::temp_dir)
+            .join("snippets_vault")
+            .join("webdav_manifest.json")
+    }
+
+    /// Loads the manifest; a missing or corrupt one is empty, which makes the
+    /// next sync compare every file as new.
+    pub fn load() -> Self {
+        fs::read_to_string(Self::path())
+            .ok()
+            .and_then(|raw| serde_json::from_str(&raw).ok())
+            .unwrap_or_default()
+    }
+
+    pub fn save(&self) -> Result<(), SnippetVaultError> {
+        let path = Self::path();
+        if let Some(parent) = path.parent() {
+            fs::create_dir_all(parent)?;
+        }
+        let raw = serde_json::to_string_pretty(self).expect("the manifest is always serializable");
+        vault::write_atomic(&path, &raw)
+    }
+}
+
+/// One step of a sync, on the file at a vault-relative path.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Action {
+    Upload(String),
+    Download(String),
+    DeleteRemote(String),
+    DeleteLocal(String),
+    /// Both sides changed: the remote copy is saved next to the local file.
+    Conflict(String),
+    /// Push-only: the remote copy changed too, so the file was left alone.
+    Skipped(String),
+}
+
+/// The steps that bring both sides up to date, comparing each to the state
+/// recorded at the last sync (`base`). Files changed on both sides are
+/// conflicts; a file deleted on one side and changed on the other is copied
+/// back rather than deleted. An empty `remote` never deletes local files: it
+/// is likelier a missing or misconfigured folder than a vault emptied on
+/// purpose.
+pub fn plan(
+    local: &BTreeMap<String, LocalFile>,
+    remote: &BTreeMap<String, RemoteFile>,
+    base: &BTreeMap<String, ManifestEntry>,
+    direction: Direction,
+) -> Vec<Action> {
+    let paths: BTreeSet<&String> = local
+        .keys()
+        .chain(remote.keys())
+        .chain(base.keys())
+        .collect();
+    let remote_empty = remote.is_empty();
+    let mut actions = Vec::new();
+    for path in paths {
+        let (local, remote, base) = (local.get(path), remote.get(path), base.get(path));
+        let local_changed = match (local, base) {
+            (Some(file), Some(base)) => file.modified != base.modified || file.size != base.size,
+            (None, None) => false,
+            _ => true,
+        };
+        let remote_changed = match (remote, base) {
+            (Some(file), Some(base)) => file.version != base.version,
+            (None, None) => false,
+            _ => true,
+        };
+        let path = path.clone();
+        let action = match (local_changed, remote_changed) {
+            (false, false) => None,
+            (true, false) => match local {
+                Some(_) if direction != Direction::PullOnly => Some(Action::Upload(path)),
+                None if direction == Direction::Both && remote.is_some() => {
+                    Some(Action::DeleteRemote(path))
+                }
+                _ => None,
+            },
+            (false, true) => match remote {
+                Some(_) if direction != Direction::PushOnly => Some(Action::Download(path)),
+                None if direction == Direction::Both && local.is_some() && !remote_empty => {
+                    Some(Action::DeleteLocal(path))
+                }
+                _ => None,
+            },
+            (true, true) => match (local, remote) {
+                (Some(_), Some(_)) if direction == Direction::PushOnly => {
+                    Some(Action::Skipped(path))
+                }
+                (Some(_), Some(_)) => Some(Action::Conflict(path)),
+                (Some(_), None) if direction != Direction::PullOnly => Some(Action::Upload(path)),
+                (None, Some(_)) if direction != Direction::PushOnly => Some(Action::Download(path)),
+                _ => None,
+            },
+        };
+        actions.extend(action);
+    }
+    actions
+}
+
+/// Where the remote copy of a conflicting `path` is saved: `notes.md` becomes
+/// `notes.conflict-2026-01-31.md`, with a counter added when that exists.
+pub fn conflict_path(path: &Path, date: NaiveDate) -> PathBuf {
+    let stem = path
+        .file_stem()
+        .map(|stem| stem.to_string_lossy().into_owned())
+        .unwrap_or_default();
+    let extension = path
+        .extension()
+        .map(|ext| format!(".{}", ext.to_string_lossy()))
+        .unwrap_or_default();
+    let mut candidate =
+        path.with_file_name(format!("{}{}{}{}", stem, CONFLICT_MARKER, date, extension));
+    let mut counter = 2;
+    while candidate.exists() {
+        candidate = path.with_file_name(format!(
+            "{}{}{}-{}{}",
+            stem, CONFLICT_MARKER, date, counter, extension
+        ));
+        counter += 1;
+    }
+    candidate
+}
+
+/// What a sync did, for the summary.
+#[derive(Debug, Default)]
+pub struct SyncReport {
+    pub uploaded: usize,
+    pub downloaded: usize,
+    pub deleted_remote: usize,
+    pub deleted_local: usize,
+    /// Paths of the remote copies saved for conflicts.
+    pub conflicts: Vec<PathBuf>,
+    /// Files push-only left alone because the remote copy changed too.
+    pub skipped: Vec<String>,
+}
+
+/// Syncs the vault at `snippet_dir` with the WebDAV folder of `config`.
+/// Local changes go through `executor`; under `--dry-run` the remote is only
+/// read, every step is printed instead, and the manifest is not updated.
+/// - `extensions`: File extensions that count as snippets.
+pub fn sync(
+    config: &WebDavConfig,
+    snippet_dir: &Path,
+    extensions: &[String],
+    direction: Direction,
+    executor: &Executor,
+) -> Result<SyncReport, SnippetVaultError> {
+    let client = Client::new(config);
+    let local = local_files(snippet_dir, extensions)?;
+    let remote = client.list(extensions)?;
+    let mut manifest = Manifest::load();
+    let base = manifest
+        .roots
+        .get(&client.root)
+        .cloned()
+        .unwrap_or_default();
+    let today = chrono::Local::now().date_naive();
+    if remote.is_empty() && !base.is_empty() && direction == Direction::Both {
+        println!(
+            "{} The server lists no files; no local files are deleted.",
+            "⚠".warning()
+        );
+    }
+
+    let mut report = SyncReport::default();
+    let mut transferred = BTreeSet::new();
+    let mut synced = base.clone();
+    let dry_run = executor.is_dry_run();
+    for action in plan(&local, &remote, &base, direction) {
+        match action {
+            Action::Upload(path) => {
+                if dry_run {
+                    println!("{} would upload {}", "•".accent(), path);
+                } else {
+                    client.upload(&path, &vault_path(snippet_dir, &path)?)?;
+                }
+                report.uploaded += 1;
+                transferred.insert(path);
+            }
+            Action::Download(path) => {
+                if dry_run {
+                    println!("{} would download {}", "•".accent(), path);
+                } else {
+                    let target = vault_path(snippet_dir, &path)?;
+                    if let Some(parent) = target.parent().filter(|parent| !parent.exists()) {
+                        executor.apply(Mutation::CreateDir(parent.to_path_buf()))?;
+                    }
+                    let contents = client.download(&path)?;
+                    executor.apply(Mutation::Write {
+                        path: target,
+                        contents,
+                    })?;
+                }
+                report.downloaded += 1;
+                transferred.insert(path);
+            }
+            Action::DeleteRemote(path) => {
+                if dry_run {
+                    println!("{} would delete {} on the server", "•".accent(), path);
+                } else {
+                    client.delete(&path)?;
+                }
+                synced.remove(&path);
+                report.deleted_remote += 1;
+            }
+            Action::DeleteLocal(path) => {
+                executor.apply(Mutation::Remove(vault_path(snippet_dir, &path)?))?;
+                synced.remove(&path);
+                report.deleted_local += 1;
+            }
+            Action::Conflict(path) => {
+                let copy = conflict_path(&vault_path(snippet_dir, &path)?, today);
+                if dry_run {
+                    println!(
+                        "{} would save the server copy of {} as {}",
+                        "•".accent(),
+                        path,
+                        copy.display()
+                    );
+                } else {
+                    let contents = client.download(&path)?;
+                    executor.apply(Mutation::Write {
+                        path: copy.clone(),
+                        contents,
+                    })?;
+                }
+                report.conflicts.push(copy);
+                transferred.insert(path);
+            }
+            Action::Skipped(path) => report.skipped.push(path),
+        }
+    }
+    if dry_run {
+        return Ok(report);
+    }
+
+    // Record the files copied just now as they are on both sides, so the next
+    // sync only sees later changes. Changes held back by --push-only or
+    // --pull-only keep their old entry and are still picked up later. A
+    // conflict keeps the local file and counts the server version as seen,
+    // so editing the file to resolve it uploads the result.
+    let local = local_files(snippet_dir, extensions)?;
+    let remote = client.list(extensions)?;
+    for path in transferred {
+        if let (Some(file), Some(version)) = (local.get(&path), remote.get(&path)) {
+            let entry = ManifestEntry {
+                modified: file.modified,
+                size: file.size,
+                version: version.version.clone(),
+            };
+            synced.insert(path, entry);
+        }
+    }
+    synced.retain(|path, _| local.contains_key(path) || remote.contains_key(path));
+    manifest.roots.insert(client.root.clone(), synced);
+    manifest.save()?;
+    Ok(report)
+}
+
+/// The file at the `/`-separated `relative` path (from the server or the
+/// manifest) inside `snippet_dir`. Anything but plain names, such as `..` or
+/// an absolute path, is refused, so a server cannot make a sync write outside
+/// the vault.
+fn vault_path(snippet_dir: &Path, relative: &str) -> Result<PathBuf, SnippetVaultError> {
+    let path = Path::new(relative);
+    let plain = path.components().next().is_some()
+        && path
+            .components()
+            .all(|part| matches!(part, Component::Normal(_)));
+    if !plain {
+        return Err(SnippetVaultError::Command(format!(
+            "the server listed {} outside the vault folder; not syncing",
+            relative
+        )));
+    }
+    Ok(snippet_dir.join(path))
+}
+
+/// Snippets under `snippet_dir` keyed by their `/`-separated relative path,
+/// without the saved conflict copies.
+fn local_files(
+    snippet_dir: &Path,
+    extensions: &[String],
+) -> Result<BTreeMap<String, LocalFile>, SnippetVaultError> {
+    if !snippet_dir.exists() {
+        return Ok(BTreeMap::new());
+    }
+    let mut files = BTreeMap::new();
+    for path in vault::vault_files(snippet_dir, extensions)? {
+        let Ok(relative) = path.strip_prefix(snippet_dir) else {
+            continue;
+        };
+        let relative = relative
+            .components()
+            .map(|part| part.as_os_str().to_string_lossy())
+            .collect::<Vec<_>>()
+            .join("/");
+        if relative.contains(CONFLICT_MARKER) {
+            continue;
+        }
+        let metadata = fs::metadata(&path)?;
+        let modified = metadata
+            .modified()?
+            .duration_since(UNIX_EPOCH)
+            .map_or(0, |age| age.as_secs() as i64);
+        files.insert(
+            relative,
+            LocalFile {
+                modified,
+                size: metadata.len(),
+            },
+        );
+    }
+    Ok(files)
+}
+
+/// Requests against the vault folder on the server.
+struct Client {
+    root: String,
+    authorization: Option<String>,
+    /// Folders already created (or found to exist) during this sync.
+    created: RefCell<BTreeSet<String>>,
+}
+
+impl Client {
+    fn new(config: &WebDavConfig) -> Self {
+        let authorization = (!config.username.is_empty()).then(|| {
+            let credentials = format!("{}:{}", config.username, config.password);
+            format!(
+                "Basic {}",
+                base64::engine::general_purpose::STANDARD.encode(credentials)
+            )
+        });
+        Client {
+            root: config.root(),
+            authorization,
+            created: RefCell::new(BTreeSet::new()),
+        }
+    }
+
+    fn url(&self, path: &str) -> String {
+        let encoded: Vec<String> = path.split('/').map(percent_encode).collect();
+        format!("{}{}", self.root, encoded.join("/"))
+    }
+
+    fn request(&self, method: &str, url: &str) -> ureq::Request {
+        let request = ureq::request(method, url).set("User-Agent", "snippets_vault");
+        match &self.authorization {
+            Some(authorization) => request.set("Authorization", authorization),
+            None => request,
+        }
+    }
+
+    /// Snippet files in the folder and its subfolders, keyed like [`local_files`].
+    /// A folder that does not exist yet holds no files.
+    fn list(
+        &self,
+        extensions: &[String],
+    ) -> Result<BTreeMap<String, RemoteFile>, SnippetVaultError> {
+        let mut files = BTreeMap::new();
+        let mut pending = vec![String::new()];
+        let root_path = percent_decode(url_path(&self.root));
+        while let Some(folder) = pending.pop() {
+            let url = if folder.is_empty() {
+                self.root.clone()
+            } else {
+                format!("{}/", self.url(&folder))
+            };
+            tracing::debug!(%url, "listing WebDAV folder");
+            let response = self
+                .request("PROPFIND", &url)
+                .set("Depth", "1")
+                .set("Content-Type", "application/xml; charset=utf-8")
+                .send_string(PROPFIND_BODY);
+            let body = match response {
+                Ok(response) => response.into_string()?,
+                Err(ureq::Error::Status(404, _)) if folder.is_empty() => return Ok(files),
+                Err(err) => return Err(request_error("list", &url, err)),
+            };
+
+            for entry in parse_multistatus(&body)? {
+                let href = percent_decode(url_path(&entry.href));
+                let Some(relative) = href.strip_prefix(&root_path) else {
+                    continue;
+                };
+                let relative = relative.trim_matches('/').to_string();
+                if relative.is_empty() || relative == folder {
+                    continue;
+                }
+                vault_path(Path::new(""), &relative)?;
+                let name = relative.rsplit('/').next().unwrap_or(&relative);
+                if name.starts_with('.') || relative.starts_with(vault::ATTACHMENTS_DIR) {
+                    continue;
+                }
+                if entry.collection {
+                    pending.push(relative);
+                } else if is_snippet(name, extensions) && !name.contains(CONFLICT_MARKER) {
+                    let version = entry
+                        .etag
+                        .unwrap_or_else(|| format!("{}:{}", entry.size, entry.modified));
+                    files.insert(relative, RemoteFile { version });
+                }
+            }
+        }
+        Ok(files)
+    }
+
+    fn download(&self, path: &str) -> Result<String, SnippetVaultError> {
+        let url = self.url(path);
+        tracing::debug!(%url, "downloading");
+        let response = self
+            .request("GET", &url)
+            .call()
+            .map_err(|err| request_error("download", &url, err))?;
+        let mut body = Vec::new();
+        std::io::Read::read_to_end(&mut response.into_reader(), &mut body)?;
+        String::from_utf8(body).map_err(|_| {
+            SnippetVaultError::Command(format!("{} is not UTF-8 text; not downloaded", path))
+        })
+    }
+
+    /// Streams the file at `local` to `path`, creating missing folders first.
+    fn upload(&self, path: &str, local: &Path) -> Result<(), SnippetVaultError> {
+        // The vault folder itself may not exist before the first upload.
+        let mut folders = vec![self.root.clone()];
+        let mut folder = String::new();
+        if let Some((parents, _)) = path.rsplit_once('/') {
+            for segment in parents.split('/') {
+                if !folder.is_empty() {
+                    folder.push('/');
+                }
+                folder.push_str(segment);
+                folders.push(format!("{}/", self.url(&folder)));
+            }
+        }
+        for url in folders {
+            if !self.created.borrow_mut().insert(url.clone()) {
+                continue;
+            }
+            match self.request("MKCOL", &url).call() {
+                // 405: the folder exists already.
+                Ok(_) | Err(ureq::Error::Status(405, _)) => {}
+                Err(err) => return Err(request_error("create the folder", &url, err)),
+            }
+        }
+
+        let url = self.url(path);
+        tracing::debug!(%url, "uploading");
+        self.request("PUT", &url)
+            .send(fs::File::open(local)?)
+            .map_err(|err| request_error("upload", &url, err))?;
+        Ok(())
+    }
+
+    fn delete(&self, path: &str) -> Result<(), SnippetVaultError> {
+        let url = self.url(path);
+        tracing::debug!(%url, "deleting");
+        match self.request("DELETE", &url).call() {
+            Ok(_) | Err(ureq::Error::Status(404, _)) => Ok(()),
+            Err(err) => Err(request_error("delete", &url, err)),
+        }
+    }
+}
+
+fn request_error(action: &str, url: &str, err: ureq::Error) -> SnippetVaultError {
+    match err {
+        ureq::Error::Status(status, response) => SnippetVaultError::Command(format!(
+            "cannot {} {}: the server answered {} {}",
+            action,
+            url,
+            status,
+            response.status_text()
+        )),
+        err => SnippetVaultError::Command(format!("cannot {} {}: {}", action, url, err)),
+    }
+}
+
+/// Whether `name` has one of `extensions`, as [`vault::vault_files`] decides.
+fn is_snippet(name: &str, extensions: &[String]) -> bool {
+    Path::new(name)
+        .extension()
+        .and_then(|ext| ext.to_str())
+        .is_some_and(|ext| {
+            extensions
+                .iter()
+                .any(|allowed| allowed.eq_ignore_ascii_case(ext))
+        })
+}
+
+/// One `<d:response>` of a PROPFIND answer.
+#[derive(Debug, Default)]
+struct DavEntry {
+    href: String,
+    collection: bool,
+    etag: Option<String>,
+    size: String,
+    modified: String,
+}
+
+/// The responses of a `207 Multi-Status` body. Element names are matched
+/// without their namespace prefix, which servers choose freely.
+fn parse_multistatus(xml: &str) -> Result<Vec<DavEntry>, SnippetVaultError> {
+    let mut reader = Reader::from_str(xml);
+    let mut entries = Vec::new();
+    let mut entry: Option<DavEntry> = None;
+    let mut text = String::new();
+    loop {
+        let event = reader.read_event().map_err(|err| {
+            SnippetVaultError::Command(format!("cannot read the WebDAV listing: {}", err))
+        })?;
+        match event {
+            Event::Start(start) | Event::Empty(start)
+                if start.local_name().as_ref() == "collection" =>
+            {
+                if let Some(entry) = entry.as_mut() {
+                    entry.collection = true;
+                }
+            }
+            Event::Start(start) => {
+                text.clear();
+                if start.local_name().as_ref() == "response" {
+                    entry = Some(DavEntry::default());
+                }
+            }
+            Event::Text(content) => text.push_str(content.as_ref()),
+            Event::CData(content) => text.push_str(content.as_ref()),
+            Event::GeneralRef(reference) => {
+                let resolved = match reference.as_ref() {
+                    "quot" => Some('"'),
+                    "amp" => Some('&'),
+                    "lt" => Some('<'),
+                    "gt" => Some('>'),
+                    "apos" => Some('\''),
+                    _ => reference.resolve_char_ref().ok().flatten(),
+                };
+                text.extend(resolved);
+            }
+            Event::End(end) => {
+                let name = end.local_name();
+                match (name.as_ref(), entry.as_mut()) {
+                    ("response", Some(_)) => entries.extend(entry.take()),
+                    ("href", Some(entry)) => entry.href = text.trim().to_string(),
+                    ("getetag", Some(entry)) => {
+                        entry.etag = Some(text.trim().to_string()).filter(|etag| !etag.is_empty())
+                    }
+                    ("getcontentlength", Some(entry)) => entry.size = text.trim().to_string(),
+                    ("getlastmodified", Some(entry)) => entry.modified = text.trim().to_string(),
+                    _ => {}
+                }
+                text.clear();
+            }
+            Event::Eof => break,
+            _ => {}
+        }
+    }
+    Ok(entries)
+}
+
+/// The path of `url`: everything from the first `/` after the host, or `url`
+/// itself when it is already a path (as most `<d:href>`s are).
+fn url_path(url: &str) -> &str {
+    match url.split_once("://") {
+        Some((_, rest)) => rest.find('/').map_or("/", |start| &rest[start..]),
+        None => url,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn local(entries: &[(&str, i64)]) -> BTreeMap<String, LocalFile> {
+        entries
+            .iter()
+            .map(|(path, modified)| {
+                let file = LocalFile {
+                    modified: *modified,
+                    size: 10,
+                };
+                (path.to_string(), file)
+            })
+            .collect()
+    }
+
+    fn remote(entries: &[(&str, &str)]) -> BTreeMap<String, RemoteFile> {
+        entries
+            .iter()
+            .map(|(path, version)| {
+                let file = RemoteFile {
+                    version: version.to_string(),
+                };
+                (path.to_string(), file)
+            })
+            .collect()
+    }
+
+    fn base(entries: &[(&str, i64, &str)]) -> BTreeMap<String, ManifestEntry> {
+        entries
+            .iter()
+            .map(|(path, modified, version)| {
+                let entry = ManifestEntry {
+                    modified: *modified,
+                    size: 10,
+                    version: version.to_string(),
+                };
+                (path.to_string(), entry)
+            })
+            .collect()
+    }
+
+    /// A vault where, since the last sync, `up.md` changed locally, `down.md`
+    /// changed on the server, `both.md` changed on both sides, `gone_local.md`
+    /// was deleted locally, `gone_remote.md` was deleted on the server, and
+    /// `new_local.md` and `new_remote.md` were added.
+    fn scenario(direction: Direction) -> Vec<Action> {
+        let local = local(&[
+            ("up.md", 2),
+            ("down.md", 1),
+            ("both.md", 2),
+            ("gone_remote.md", 1),
+            ("same.md", 1),
+            ("new_local.md", 1),
+        ]);
+        let remote = remote(&[
+            ("up.md", "v1"),
+            ("down.md", "v2"),
+            ("both.md", "v2"),
+            ("gone_local.md", "v1"),
+            ("same.md", "v1"),
+            ("new_remote.md", "v1"),
+        ]);
+        let base = base(&[
+            ("up.md", 1, "v1"),
+            ("down.md", 1, "v1"),
+            ("both.md", 1, "v1"),
+            ("gone_local.md", 1, "v1"),
+            ("gone_remote.md", 1, "v1"),
+            ("same.md", 1, "v1"),
+        ]);
+        plan(&local, &remote, &base, direction)
+    }
+
+    fn action(kind: fn(String) -> Action, path: &str) -> Action {
+        kind(path.to_string())
+    }
+
+    #[test]
+    fn both_directions_propagate_changes_and_deletions() {
+        assert_eq!(
+            scenario(Direction::Both),
+            [
+                action(Action::Conflict, "both.md"),
+                action(Action::Download, "down.md"),
+                action(Action::DeleteRemote, "gone_local.md"),
+                action(Action::DeleteLocal, "gone_remote.md"),
+                action(Action::Upload, "new_local.md"),
+                action(Action::Download, "new_remote.md"),
+                action(Action::Upload, "up.md"),
+            ]
+        );
+    }
+
+    #[test]
+    fn push_only_uploads_and_skips_remote_changes() {
+        assert_eq!(
+            scenario(Direction::PushOnly),
+            [
+                action(Action::Skipped, "both.md"),
+                action(Action::Upload, "new_local.md"),
+                action(Action::Upload, "up.md"),
+            ]
+        );
+    }
+
+    #[test]
+    fn pull_only_downloads_and_never_deletes() {
+        assert_eq!(
+            scenario(Direction::PullOnly),
+            [
+                action(Action::Conflict, "both.md"),
+                action(Action::Download, "down.md"),
+                action(Action::Download, "new_remote.md"),
+            ]
+        );
+    }
+
+    #[test]
+    fn empty_remote_listing_deletes_nothing_locally() {
+        let local = local(&[("a.md", 1), ("b.md", 1)]);
+        let base = base(&[("a.md", 1, "v1"), ("b.md", 1, "v1")]);
+        assert_eq!(plan(&local, &BTreeMap::new(), &base, Direction::Both), []);
+    }
+
+    #[test]
+    fn conflict_copies_get_a_counter_when_taken() {
+        let dir = tempfile::tempdir().unwrap();
+        let date = NaiveDate::from_ymd_opt(2026, 1, 31).unwrap();
+        let path = dir.path().join("notes.md");
+        let first = conflict_path(&path, date);
+        assert_eq!(first, dir.path().join("notes.conflict-2026-01-31.md"));
+        fs::write(&first, "").unwrap();
+        assert_eq!(
+            conflict_path(&path, date),
+            dir.path().join("notes.conflict-2026-01-31-2.md")
+        );
+    }
+
+    #[test]
+    fn multistatus_entries_are_read_whatever_the_prefix() {
+        let xml = r#"<?xml version="1.0"?>
+<D:multistatus xmlns:D="DAV:">
+  <D:response>
+    <D:href>/dav/vault/</D:href>
+    <D:propstat><D:prop><D:resourcetype><D:collection/></D:resourcetype></D:prop></D:propstat>
+  </D:response>
+  <D:response>
+    <D:href>/dav/vault/caf%C3%A9%20notes.md</D:href>
+    <D:propstat><D:prop>
+      <D:resourcetype/>
+      <D:getetag>&quot;abc&quot;</D:getetag>
+      <D:getcontentlength>42</D:getcontentlength>
+      <D:getlastmodified>Sat, 31 Jan 2026 10:00:00 GMT</D:getlastmodified>
+    </D:prop></D:propstat>
+  </D:response>
+</D:multistatus>"#;
+        let entries = parse_multistatus(xml).unwrap();
+        assert_eq!(entries.len(), 2);
+        assert!(entries[0].collection);
+        assert!(!entries[1].collection);
+        assert_eq!(entries[1].etag.as_deref(), Some("\"abc\""));
+        assert_eq!(entries[1].size, "42");
+        assert_eq!(percent_decode(&entries[1].href), "/dav/vault/café notes.md");
+    }
+
+    #[test]
+    fn url_paths_drop_the_scheme_and_host() {
+        assert_eq!(url_path("https://host:8080/dav/a%20b"), "/dav/a%20b");
+        assert_eq!(url_path("https://host"), "/");
+        assert_eq!(url_path("/dav/x.md"), "/dav/x.md");
+    }
+
+    #[test]
+    fn paths_leaving_the_vault_are_refused() {
+        let vault = Path::new("/vault");
+        assert_eq!(
+            vault_path(vault, "rust/async.md").unwrap(),
+            vault.join("rust").join("async.md")
+        );
+        for relative in [
+            "../x.md",
+            "rust/../../x.md",
+            &percent_decode("%2E%2E/%2E%2E/x.md"),
+            "/etc/passwd",
+            "./x.md",
+            "",
+        ] {
+            assert!(vault_path(vault, relative).is_err(), "{}", relative);
+        }
+    }
+}
@@ -0,0 +1,85 @@
+use std::env;
+use unicode_width::{UnicodeWidthChar, UnicodeWidthStr};
+
+use crate::platform;
+
+/// Width assumed when neither the terminal nor `COLUMNS` gives one.
+pub const DEFAULT_WIDTH: usize = 80;
+
+/// Columns available for output: `requested` (from `--width`) when given, else
+/// the terminal's width, else `$COLUMNS`, else [`DEFAULT_WIDTH`].
+pub fn output_width(requested: Option<usize>) -> usize {
+    requested
+        .or_else(platform::terminal_width)
+        .or_else(|| {
+            env::var("COLUMNS")
+                .ok()
+                .and_then(|columns| columns.trim().parse().ok())
+        })
+        .filter(|width| *width > 0)
+        .unwrap_or(DEFAULT_WIDTH)
+}
+
+/// Columns `text` takes on screen: wide (CJK, emoji) characters count twice,
+/// combining marks and ANSI color codes not at all.
+pub fn display_width(text: &str) -> usize {
+    if !text.contains('\x1b') {
+        return text.width();
+    }
+    let mut width = 0;
+    let mut in_escape = false;
+    for c in text.chars() {
+        match (in_escape, c) {
+            (false, '\x1b') => in_escape = true,
+            (false, _) => width += c.width().unwrap_or(0),
+            (true, 'm') => in_escape = false,
+            (true, _) => {}
+        }
+    }
+    width
+}
+
+/// Shortens `text` to at most `width` columns, marking the cut with an ellipsis.
+pub fn truncate(text: &str, width: usize) -> String {
+    if text.width() <= width {
+        return text.to_string();
+    }
+    let mut truncated = String::new();
+    let mut used = 0;
+    for c in text.chars() {
+        let char_width = c.width().unwrap_or(0);
+        if used + char_width + 1 > width {
+            break;
+        }
+        truncated.push(c);
+        used += char_width;
+    }
+    truncated.push('…');
+    truncated
+}
+
+/// `text` followed by enough spaces to fill `width` columns.
+pub fn pad(text: &str, width: usize) -> String {
+    let padding = width.saturating_sub(display_width(text));
+    format!("{}{}", text, " ".repeat(padding))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn width_override_wins() {
+        for width in [60, 80, 120] {
+            assert_eq!(output_width(Some(width)), width);
+        }
+    }
+
+    #[test]
+    fn wide_characters_count_twice() {
+        assert_eq!(display_width("整理提交"), 8);
+        assert_eq!(display_width("\x1b[1mgit\x1b[0m"), 3);
+        assert_eq!(truncate("整理提交历史", 7), "整理提…");
+        assert_eq!(pad("整理", 6), "整理  ");
+    }
+}
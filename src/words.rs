@@ -0,0 +1,179 @@
+use regex::Regex;
+use std::collections::HashMap;
+use std::path::Path;
+
+use crate::error::SnippetVaultError;
+use crate::listing::{self, SnippetFilter, SortCriterion};
+use crate::snippet;
+
+/// Common English words left out of the counts.
+const STOP_WORDS: &[&str] = &[
+    "a", "about", "above", "after", "again", "all", "also", "am", "an", "and", "any", "are", "as",
+    "at", "be", "because", "been", "before", "being", "below", "between", "both", "but", "by",
+    "can", "could", "did", "do", "does", "doing", "don't", "down", "during", "each", "else", "etc",
+    "few", "for", "from", "further", "get", "gets", "had", "has", "have", "having", "he", "her",
+    "here", "hers", "him", "his", "how", "i", "if", "in", "into", "is", "it", "it's", "its",
+    "itself", "just", "let", "like", "may", "me", "might", "more", "most", "much", "must", "my",
+    "no", "nor", "not", "now", "of", "off", "on", "once", "one", "only", "or", "other", "our",
+    "out", "over", "own", "same", "she", "should", "so", "some", "such", "than", "that", "the",
+    "their", "them", "then", "there", "these", "they", "this", "those", "through", "to", "too",
+    "under", "until", "up", "us", "use", "used", "using", "very", "was", "we", "were", "what",
+    "when", "where", "which", "while", "who", "whom", "why", "will", "with", "would", "you",
+    "your", "yours",
+];
+
+/// Counts the words in the prose of the snippets matching `filter` and returns
+/// the `top_n` most frequent, most frequent first (ties alphabetically). Prose
+/// is everything outside frontmatter, headings, fenced and inline code, and
+/// URLs; plain code files have none. Words are lowercased, and stop words and
+/// words shorter than `min_length` characters are skipped.
+/// - `extensions`: File extensions that count as snippets.
+/// - `use_cache`: Serve unchanged snippets from the metadata cache.
+pub fn word_frequency(
+    snippet_dir: &Path,
+    top_n: usize,
+    min_length: usize,
+    filter: &SnippetFilter,
+    extensions: &[String],
+    use_cache: bool,
+) -> Result<Vec<(String, usize)>, SnippetVaultError> {
+    let snippets = listing::query_snippets(
+        snippet_dir,
+        filter,
+        SortCriterion::Path,
+        extensions,
+        use_cache,
+    )?;
+
+    let mut counts: HashMap<String, usize> = HashMap::new();
+    for snippet in snippets
+        .iter()
+        .filter(|snippet| snippet::is_markdown(&snippet.path) && !snippet.stats.binary)
+    {
+        let content = match snippet::read_text(&snippet.path) {
+            Ok(Some(content)) => content,
+            Ok(None) => continue,
+            Err(err) => {
+                tracing::warn!(path = %snippet.path.display(), %err, "cannot read snippet");
+                continue;
+            }
+        };
+        for word in prose_words(&content) {
+            if word.chars().count() >= min_length && !STOP_WORDS.contains(&word.as_str()) {
+                *counts.entry(word).or_default() += 1;
+            }
+        }
+    }
+
+    let mut frequencies: Vec<(String, usize)> = counts.into_iter().collect();
+    frequencies.sort_by(|a, b| b.1.cmp(&a.1).then_with(|| a.0.cmp(&b.0)));
+    frequencies.truncate(top_n);
+    Ok(frequencies)
+}
+
+/// Lowercased words of the prose lines of `content`, in order.
+fn prose_words(content: &str) -> Vec<String> {
+    let noise = Regex::new(r"`[^`]*`|https?://\S+").expect("noise pattern is valid");
+    let mut in_fence = false;
+    let mut words = Vec::new();
+
+    for line in snippet::split_frontmatter(content).1.lines() {
+        let trimmed = line.trim();
+        if trimmed.starts_with("```") {
+            in_fence = !in_fence;
+            continue;
+        }
+        if in_fence || trimmed.starts_with('#') {
+            continue;
+        }
+        let text = noise.replace_all(trimmed, " ");
+        words.extend(
+            text.split(|c: char| !c.is_alphanumeric() && c != '\'')
+                .map(|word| word.trim_matches('\''))
+                .filter(|word| word.chars().any(char::is_alphabetic))
+                .map(str::to_lowercase),
+        );
+    }
+    words
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::fs;
+
+    #[test]
+    fn prose_words_skip_code_headings_and_links() {
+        let content = "+++\ntitle = \"Rebase\"\nlanguage = \"bash\"\n+++\n\
+                       # Rebase onto main\n\
+                       Rebase the branch; don't `git push --force` blindly.\n\
+                       ```bash\ngit rebase main\n```\n\
+                       See https://git-scm.com/docs for 2 more Options.\n";
+        assert_eq!(
+            prose_words(content),
+            ["rebase", "the", "branch", "don't", "blindly", "see", "for", "more", "options"]
+        );
+    }
+
+    #[test]
+    fn frequencies_skip_stop_words_and_short_words() {
+        crate::testing::isolate_user_dirs();
+        let vault = tempfile::tempdir().unwrap();
+        let snippets = [
+            (
+                "rebase.md",
+                "bash",
+                "Rebase the branch, then rebase again.\nCheck the branch.",
+            ),
+            (
+                "merge.md",
+                "bash",
+                "Merge the branch into main.\nA merge commit.",
+            ),
+            (
+                "spawn.md",
+                "rust",
+                "Spawn a task on the runtime. Spawn is cheap.",
+            ),
+        ];
+        for (name, language, prose) in snippets {
+            fs::write(
+                vault.path().join(name),
+                format!(
+                    "+++\ntitle = \"{}\"\nlanguage = \"{}\"\n+++\n{}\n```\nbranch branch branch\n```\n",
+                    name, language, prose
+                ),
+            )
+            .unwrap();
+        }
+        // Plain code files have no prose.
+        fs::write(vault.path().join("branch.sh"), "# branch branch branch\n").unwrap();
+        let extensions = vec!["md".to_string(), "sh".to_string()];
+        let frequencies = |top_n, min_length, language: Option<&str>| {
+            let filter = SnippetFilter {
+                language: language.map(str::to_string),
+                ..Default::default()
+            };
+            word_frequency(vault.path(), top_n, min_length, &filter, &extensions, false).unwrap()
+        };
+        let counted = |pairs: &[(&str, usize)]| -> Vec<(String, usize)> {
+            pairs
+                .iter()
+                .map(|(word, count)| (word.to_string(), *count))
+                .collect()
+        };
+
+        assert_eq!(
+            frequencies(4, 1, None),
+            counted(&[("branch", 3), ("merge", 2), ("rebase", 2), ("spawn", 2)])
+        );
+        assert_eq!(
+            frequencies(3, 6, None),
+            counted(&[("branch", 3), ("rebase", 2), ("commit", 1)])
+        );
+        assert_eq!(
+            frequencies(10, 1, Some("rust")),
+            counted(&[("spawn", 2), ("cheap", 1), ("runtime", 1), ("task", 1)])
+        );
+    }
+}
@@ -0,0 +1,573 @@
+//! Runs commands the way scripts, cron, and CI do: stdin and stdout piped,
+//! no terminal. Each must finish on its own instead of waiting for fzf, an
+//! editor, a pager, or a prompt, and none of those may even be started.
+#![cfg(unix)]
+
+use assert_cmd::cargo::cargo_bin_cmd;
+use std::fs;
+use std::os::unix::fs::PermissionsExt;
+use std::path::Path;
+use std::time::Duration;
+use tempfile::TempDir;
+
+/// Long enough for a slow debug build, far shorter than a hung prompt.
+const TIMEOUT: Duration = Duration::from_secs(20);
+
+/// Interactive programs replaced by stubs that only record being started.
+const STUBS: &[&str] = &["fzf", "nvim", "vim", "less", "code"];
+
+/// A throwaway home with a configured vault holding one snippet, and a
+/// `bin` directory of [`STUBS`] put first on `PATH`.
+struct Sandbox {
+    root: TempDir,
+}
+
+impl Sandbox {
+    fn new() -> Self {
+        let root = tempfile::tempdir().unwrap();
+        let vault = root.path().join("vault");
+        fs::create_dir_all(&vault).unwrap();
+        fs::write(
+            vault.join("snippet_2024-05-17-093000_bash_git.md"),
+            "+++\ntitle = \"Undo commit\"\nlanguage = \"bash\"\ntags = [\"git\"]\n+++\n```bash\ngit reset HEAD~1\n```\n",
+        )
+        .unwrap();
+
+        let config = root.path().join("config").join("snippets_vault");
+        fs::create_dir_all(&config).unwrap();
+        fs::write(
+            config.join("config.toml"),
+            format!(
+                "vault_dir = {:?}\neditor = \"nvim\"\n",
+                vault.display().to_string()
+            ),
+        )
+        .unwrap();
+
+        let bin = root.path().join("bin");
+        fs::create_dir_all(&bin).unwrap();
+        for stub in STUBS {
+            let path = bin.join(stub);
+            fs::write(&path, "#!/bin/sh\necho \"$0\" >> \"$LAUNCHED\"\nexit 1\n").unwrap();
+            fs::set_permissions(&path, fs::Permissions::from_mode(0o755)).unwrap();
+        }
+        Sandbox { root }
+    }
+
+    fn path(&self, name: &str) -> std::path::PathBuf {
+        self.root.path().join(name)
+    }
+
+    /// Runs the binary with `args`, empty piped stdin, and captured output.
+    /// Returns the exit code and stdout after checking it finished in time and
+    /// started nothing interactive.
+    fn run(&self, args: &[&str]) -> (i32, String) {
+        self.run_with_env(args, &[])
+    }
+
+    /// [`Sandbox::run`] with `env` added to the environment.
+    fn run_with_env(&self, args: &[&str], env: &[(&str, &str)]) -> (i32, String) {
+        let launched = self.path("launched");
+        let output = cargo_bin_cmd!("snippets_vault")
+            .args(args)
+            .env("HOME", self.root.path())
+            .env("XDG_CONFIG_HOME", self.path("config"))
+            .env("XDG_CACHE_HOME", self.path("cache"))
+            .env("XDG_DATA_HOME", self.path("data"))
+            .env("LAUNCHED", &launched)
+            .env(
+                "PATH",
+                format!("{}:/usr/bin:/bin", self.path("bin").display()),
+            )
+            .env_remove("SNIPPETS_VAULT_DIR")
+            .env_remove("EDITOR")
+            .env_remove("VISUAL")
+            .env_remove("PAGER")
+            .env_remove("NO_COLOR")
+            .env_remove("CLICOLOR_FORCE")
+            .envs(env.iter().copied())
+            .write_stdin("")
+            .timeout(TIMEOUT)
+            .output()
+            .unwrap();
+        let stdout = String::from_utf8_lossy(&output.stdout).into_owned();
+        let code = output
+            .status
+            .code()
+            .unwrap_or_else(|| panic!("{:?} did not finish within {:?}", args, TIMEOUT));
+        assert!(
+            !Path::new(&launched).exists(),
+            "{:?} started {}",
+            args,
+            fs::read_to_string(&launched).unwrap_or_default()
+        );
+        (code, stdout)
+    }
+}
+
+#[test]
+fn pickers_fail_fast() {
+    let sandbox = Sandbox::new();
+    for args in [
+        &["--list_snippets"][..],
+        &["--delete_snippet"],
+        &["--copy_snippet"],
+        &["--snippet_interactive_tag_editor"],
+    ] {
+        let (code, stdout) = sandbox.run(args);
+        assert_eq!(code, 1, "{:?} printed:\n{}", args, stdout);
+    }
+}
+
+#[test]
+fn editor_is_not_started() {
+    let sandbox = Sandbox::new();
+    let (code, stdout) = sandbox.run(&["--create_snippet", "bash", "ci"]);
+    assert_eq!(code, 1, "{}", stdout);
+}
+
+#[test]
+fn scriptable_commands_succeed() {
+    let sandbox = Sandbox::new();
+    let (code, stdout) = sandbox.run(&["--list_snippets", "--json"]);
+    assert_eq!(code, 0, "{}", stdout);
+    assert!(stdout.contains("Undo commit"), "{}", stdout);
+
+    let (code, stdout) = sandbox.run(&["--list_snippets", "--no_fzf"]);
+    assert_eq!(code, 0, "{}", stdout);
+    assert!(
+        stdout.contains("snippet_2024-05-17-093000_bash_git.md"),
+        "{}",
+        stdout
+    );
+
+    let (code, _) = sandbox.run(&["--non-interactive", "--snippet_stats_per_day_of_week"]);
+    assert_eq!(code, 0);
+}
+
+#[test]
+fn no_color_output_keeps_only_the_symbols() {
+    let sandbox = Sandbox::new();
+    let root = sandbox.root.path().display().to_string();
+    let forced = [("CLICOLOR_FORCE", "1")];
+    let (_, colored) = sandbox.run_with_env(&["--snippet_lint", "--all"], &forced);
+    assert!(colored.contains("\x1b["), "{}", colored);
+
+    // CLICOLOR_FORCE would win over NO_COLOR (as colored reads them), so it is
+    // left unset here; the symbols must carry the meaning with either palette.
+    let plain = [("NO_COLOR", "1")];
+    for theme in ["default", "high-contrast"] {
+        let (code, stdout) =
+            sandbox.run_with_env(&["--theme", theme, "--snippet_lint", "--all"], &plain);
+        assert_eq!(code, 0, "{}", stdout);
+        assert_eq!(
+            stdout.replace(&root, "<root>"),
+            "WARNING:\n  <root>/vault/snippet_2024-05-17-093000_bash_git.md:6 \
+             script does not `set -e`; failures will be ignored\n\
+             • 1 files checked: 0 errors, 1 warnings, 0 info\n"
+        );
+
+        let csv = sandbox.path("out.csv").display().to_string();
+        let (code, stdout) =
+            sandbox.run_with_env(&["--theme", theme, "--snippet_export_csv", &csv], &plain);
+        assert_eq!(code, 0, "{}", stdout);
+        assert_eq!(
+            stdout.replace(&root, "<root>"),
+            "✔ Exported 1 snippets to <root>/out.csv\n"
+        );
+    }
+}
+
+#[test]
+fn find_in_files_counts_within_a_created_range() {
+    let sandbox = Sandbox::new();
+    fs::write(
+        sandbox.path("vault/log.md"),
+        "+++\ntitle = \"Log\"\nlanguage = \"bash\"\ntags = [\"git\"]\ncreated = 2024-06-01T23:30:00-02:00\n+++\n```bash\ngit log --oneline\n```\n",
+    )
+    .unwrap();
+    let count = |args: &[&str], zone: &str| {
+        let args = [&["--find_in_files", "git", "--count"][..], args].concat();
+        let (code, stdout) = sandbox.run_with_env(&args, &[("TZ", zone)]);
+        assert_eq!(
+            code,
+            i32::from(stdout.trim() == "0"),
+            "{:?}: {}",
+            args,
+            stdout
+        );
+        stdout.trim().parse::<usize>().unwrap()
+    };
+
+    assert_eq!(count(&[], "UTC"), 2);
+    assert_eq!(count(&["--since", "2024-06"], "UTC"), 1);
+    assert_eq!(count(&["--before", "2024-06"], "UTC"), 1);
+    assert_eq!(
+        count(&["--since", "2024-05-17", "--before", "2024-05-18"], "UTC"),
+        1
+    );
+    // 01:30 on June 2nd in UTC, but still June 1st five hours further west.
+    assert_eq!(count(&["--since", "2024-06-02"], "UTC"), 1);
+    assert_eq!(count(&["--since", "2024-06-02"], "Etc/GMT+5"), 0);
+}
+
+/// Every file under `dir` with its bytes, sorted by path.
+fn tree(dir: &Path) -> Vec<(std::path::PathBuf, Vec<u8>)> {
+    let mut files = Vec::new();
+    let mut pending = vec![dir.to_path_buf()];
+    while let Some(current) = pending.pop() {
+        for entry in fs::read_dir(&current).unwrap() {
+            let path = entry.unwrap().path();
+            if path.is_dir() {
+                files.push((path.strip_prefix(dir).unwrap().to_path_buf(), Vec::new()));
+                pending.push(path);
+            } else {
+                files.push((
+                    path.strip_prefix(dir).unwrap().to_path_buf(),
+                    fs::read(&path).unwrap(),
+                ));
+            }
+        }
+    }
+    files.sort();
+    files
+}
+
+#[test]
+fn dry_run_leaves_the_vault_byte_identical() {
+    let sandbox = Sandbox::new();
+    let vault = sandbox.path("vault");
+    let undo = vault.join("snippet_2024-05-17-093000_bash_git.md");
+    let spawn = vault.join("snippet_2024-05-18-101500_rust_async_async.md");
+    fs::write(
+        &spawn,
+        "+++\ntitle = \"Spawn\"\nlanguage = \"rust\"\ntags = [\"async\", \"async\"]\n+++\n```rust\ntokio::spawn(work());\n```\n",
+    )
+    .unwrap();
+    fs::write(
+        vault.join("snippet_2024-05-19-080000_python_venv.md"),
+        "# Title: Make a venv\n# Language: python\n# ---\n```python\npython -m venv .venv\n```\n",
+    )
+    .unwrap();
+    fs::write(vault.join("snippet_2024-05-20-120000_bash_empty.md"), "").unwrap();
+    fs::create_dir_all(vault.join("_collections")).unwrap();
+    fs::write(vault.join("_collections/git.toml"), "tag = \"git\"\n").unwrap();
+    fs::write(
+        sandbox.path("renames.tsv"),
+        "snippet_2024-05-17-093000_bash_git.md\tgit/undo.md\n",
+    )
+    .unwrap();
+    fs::write(
+        sandbox.path("import.csv"),
+        "path,language,tags,created_at,modified_at,size_bytes,word_count,code_lines,rating,status\n\
+         new.md,bash,ops,2024-06-01T09:00:00,,,,,,\n",
+    )
+    .unwrap();
+    fs::write(
+        sandbox.path("pet.toml"),
+        "[[snippets]]\ndescription = \"List ports\"\ncommand = \"ss -tlnp\"\ntag = [\"net\"]\n",
+    )
+    .unwrap();
+    let before = tree(&vault);
+
+    let undo = undo.display().to_string();
+    let spawn = spawn.display().to_string();
+    let renames = sandbox.path("renames.tsv").display().to_string();
+    let csv = sandbox.path("import.csv").display().to_string();
+    let pet = sandbox.path("pet.toml").display().to_string();
+    let commands: Vec<Vec<&str>> = vec![
+        vec!["--create_snippet", "bash", "ci"],
+        vec!["--snippet_rename_batch", &renames],
+        vec!["--snippet_bulk", "--yes", "retag", "--add", "ops"],
+        vec!["--snippet_bulk", "--yes", "archive"],
+        vec!["--snippet_bulk", "--yes", "delete"],
+        vec!["--replace_in_files", "git", "jj", "--yes"],
+        vec!["--delete_snippet", &undo, "--yes"],
+        vec!["--snippet_migrate_v2", "--skip_backup"],
+        vec!["--snippet_import_csv", &csv],
+        vec!["--import", "pet", &pet],
+        vec!["--snippet_dedup_tags", "--all"],
+        vec!["--snippet_tag_rename_cascade", "git", "vcs"],
+        vec!["--snippet_convert_language", &undo, "zsh"],
+        vec![
+            "--snippet_merge",
+            &undo,
+            &spawn,
+            "--delete_sources",
+            "--force",
+        ],
+        vec!["--set_field", "reviewed", "true", "--file", &undo],
+        vec!["--unset_field", "reviewed", "--file", &undo],
+        vec!["--snippet_expiry", "2020-01-01", "--file", &undo],
+        vec!["--housekeep", "--yes"],
+    ];
+    for command in &commands {
+        let args = [&["--dry-run"][..], command].concat();
+        let (code, stdout) = sandbox.run(&args);
+        assert_eq!(code, 0, "{:?} failed:\n{}", command, stdout);
+        assert_eq!(
+            tree(&vault),
+            before,
+            "{:?} changed the vault:\n{}",
+            command,
+            stdout
+        );
+    }
+}
+
+/// One invocation of every subcommand, with just enough arguments to get
+/// past parsing to where it would pick, prompt, edit, or open something.
+/// `{root}` and `{snippet}` stand for the sandbox and its snippet. Left out
+/// are `--snippet_serve_static` and `--snippet_file_watcher_index`, which
+/// run until stopped whether or not there is a terminal.
+const EVERY_COMMAND: &[&[&str]] = &[
+    &["--create_snippet", "bash", "ci"],
+    &["--snippet_clipboard_create"],
+    &["--list_snippets"],
+    &["--snippet_rename_batch", "{root}/renames.tsv"],
+    &["--snippet_bulk", "retag", "--add", "ops"],
+    &["--snippet_bulk", "delete"],
+    &["--snippet_regex_replace", "git", "jj"],
+    &["--replace_in_files", "git", "jj"],
+    &["--snippet_summary", "{snippet}"],
+    &["--largest"],
+    &["--snippet_word_frequency"],
+    &["--snippet_context_search"],
+    &["--snippet_grep_tags", "git", "ops"],
+    &["--edit_snippet"],
+    &["--snippet_open_in_vscode"],
+    &["--snippet_create_from_selection"],
+    &["--snippet_language_docs"],
+    &["--copy_snippet"],
+    &["--get", "undo"],
+    &["--exec"],
+    &["--delete_snippet"],
+    &["--version"],
+    &["--check-version", ">=0.1"],
+    &["--cat_snippet"],
+    &["--info"],
+    &["--suggest"],
+    &["--snippet_render_terminal"],
+    &["--motd"],
+    &["--languages"],
+    &["--list_aliases"],
+    &["--find_in_files", "git"],
+    &["--snippet_search_recent_and_create", "git"],
+    &["--snippet_backup_to_s3"],
+    &["--snippet_restore_from_s3"],
+    &["--snippet_sync", "webdav"],
+    &["--snippet_migrate_v2"],
+    &["--export_obsidian", "{root}/out"],
+    &["--export_html", "{root}/out"],
+    &["--cheatsheet", "bash"],
+    &["--snippet_bulk_export_by_language", "{root}/out"],
+    &["--snippet_export_csv", "{root}/out.csv"],
+    &["--snippet_import_csv", "{root}/import.csv"],
+    &["--import", "pet", "{root}/pet.toml"],
+    &["--export", "pet"],
+    &["--snippet_export_notion", "{snippet}"],
+    &["--snippet_lint"],
+    &["--spellcheck", "--all"],
+    &["--snippet_dedup_tags"],
+    &["--snippet_interactive_tag_editor"],
+    &["--snippet_diff_tags", "git", "vcs"],
+    &["--snippet_tag_rename_cascade", "git", "vcs"],
+    &["--snippet_weekly_digest"],
+    &["--snippet_title_index"],
+    &["--find_snippet_by_title", "undo"],
+    &["--snippet_github_sync", "{root}/no-such-repo"],
+    &["--snippet_dependency_graph"],
+    &["--snippet_convert_language", "{snippet}", "zsh"],
+    &["--duplicate_snippet"],
+    &["--snippet_merge", "{snippet}", "{snippet}"],
+    &["--set_field", "reviewed", "true"],
+    &["--unset_field", "reviewed"],
+    &["--snippet_expiry", "2020-01-01"],
+    &["--find_expired"],
+    &["--find_expiring_soon", "7"],
+    &["--housekeep"],
+    &["--open_dir"],
+    &["--snippet_open_link"],
+    &["--snippet_convert_to_gist"],
+    &["--path"],
+    &["--copy_path", "{snippet}"],
+    &["--open_snippet", "{snippet}"],
+    &["--setup"],
+    &["--config_init"],
+    &["--tags"],
+    &["--stats"],
+    &["--snippet_resolve_conflicts"],
+    &["--snippet_stats_per_day_of_week"],
+    &["--snippet_last_error"],
+    &["--clear_last_error"],
+    &["--tag_tree"],
+    &["--cache_clear"],
+    &["--snippet_custom_hooks"],
+    &["--batch"],
+    &["--doctor"],
+];
+
+#[test]
+fn every_command_finishes_without_a_terminal() {
+    for command in EVERY_COMMAND {
+        let sandbox = Sandbox::new();
+        fs::write(
+            sandbox.path("renames.tsv"),
+            "snippet_2024-05-17-093000_bash_git.md\tgit/undo.md\n",
+        )
+        .unwrap();
+        fs::write(
+            sandbox.path("import.csv"),
+            "path,language,tags,created_at,modified_at,size_bytes,word_count,code_lines,rating,status\n\
+             new.md,bash,ops,2024-06-01T09:00:00,,,,,,\n",
+        )
+        .unwrap();
+        fs::write(
+            sandbox.path("pet.toml"),
+            "[[snippets]]\ndescription = \"List ports\"\ncommand = \"ss -tlnp\"\ntag = [\"net\"]\n",
+        )
+        .unwrap();
+        let root = sandbox.path("").display().to_string();
+        let snippet = sandbox
+            .path("vault/snippet_2024-05-17-093000_bash_git.md")
+            .display()
+            .to_string();
+        let args: Vec<String> = command
+            .iter()
+            .map(|arg| arg.replace("{root}/", &root).replace("{snippet}", &snippet))
+            .collect();
+        let args: Vec<&str> = args.iter().map(String::as_str).collect();
+        let (code, stdout) = sandbox.run(&args);
+        assert_ne!(
+            code, 2,
+            "{:?} was rejected by the parser:\n{}",
+            command, stdout
+        );
+    }
+}
+
+#[test]
+fn read_only_vault_refuses_every_change() {
+    let sandbox = Sandbox::new();
+    let vault = sandbox.path("vault");
+    let undo = vault.join("snippet_2024-05-17-093000_bash_git.md");
+    let venv = vault.join("snippet_2024-05-19-080000_python_venv.md");
+    fs::write(
+        &venv,
+        "# Title: Make a venv\n# Language: python\n# ---\n```python\npython -m venv .venv\n```\n",
+    )
+    .unwrap();
+    fs::write(
+        sandbox.path("renames.tsv"),
+        "snippet_2024-05-17-093000_bash_git.md\tgit/undo.md\n",
+    )
+    .unwrap();
+    fs::write(
+        sandbox.path("pet.toml"),
+        "[[snippets]]\ndescription = \"List ports\"\ncommand = \"ss -tlnp\"\ntag = [\"net\"]\n",
+    )
+    .unwrap();
+    let before = tree(&vault);
+
+    let undo = undo.display().to_string();
+    let venv = venv.display().to_string();
+    let renames = sandbox.path("renames.tsv").display().to_string();
+    let pet = sandbox.path("pet.toml").display().to_string();
+    let commands: Vec<Vec<&str>> = vec![
+        vec!["--create_snippet", "bash", "ci"],
+        vec!["--snippet_rename_batch", &renames],
+        vec!["--snippet_bulk", "--yes", "retag", "--add", "ops"],
+        vec!["--snippet_bulk", "--yes", "archive"],
+        vec!["--snippet_bulk", "--yes", "delete"],
+        vec!["--replace_in_files", "git", "jj", "--yes"],
+        vec!["--delete_snippet", &undo, "--yes"],
+        vec!["--snippet_migrate_v2", "--skip_backup"],
+        vec!["--import", "pet", &pet],
+        vec!["--snippet_tag_rename_cascade", "git", "vcs"],
+        vec!["--snippet_convert_language", &undo, "zsh"],
+        vec![
+            "--snippet_merge",
+            &undo,
+            &venv,
+            "--delete_sources",
+            "--force",
+        ],
+        vec!["--set_field", "reviewed", "true", "--file", &undo],
+        vec!["--snippet_expiry", "2020-01-01", "--file", &undo],
+    ];
+    for command in &commands {
+        let args = [&["--readonly"][..], command].concat();
+        let (code, stdout) = sandbox.run(&args);
+        assert_eq!(code, 1, "{:?} was not refused:\n{}", command, stdout);
+        assert!(
+            stdout.contains("read-only"),
+            "{:?} printed:\n{}",
+            command,
+            stdout
+        );
+        assert_eq!(
+            tree(&vault),
+            before,
+            "{:?} changed the vault:\n{}",
+            command,
+            stdout
+        );
+    }
+
+    // `readonly = true` in the configuration refuses changes the same way.
+    let config = sandbox.path("config/snippets_vault/config.toml");
+    let mut settings = fs::read_to_string(&config).unwrap();
+    settings.push_str("readonly = true\n");
+    fs::write(&config, settings).unwrap();
+    let (code, stdout) = sandbox.run(&["--set_field", "reviewed", "true", "--file", &undo]);
+    assert_eq!(code, 1, "{}", stdout);
+    assert_eq!(tree(&vault), before);
+
+    // Reading and exporting out of the vault still work.
+    let (code, stdout) = sandbox.run(&["--list_snippets", "--json"]);
+    assert_eq!(code, 0, "{}", stdout);
+    assert!(stdout.contains("Undo commit"), "{}", stdout);
+    let csv = sandbox.path("snippets.csv");
+    let (code, stdout) = sandbox.run(&["--snippet_export_csv", &csv.display().to_string()]);
+    assert_eq!(code, 0, "{}", stdout);
+    assert!(csv.exists());
+    assert_eq!(tree(&vault), before);
+}
+
+#[test]
+fn config_init_writes_the_defaults_once() {
+    let sandbox = Sandbox::new();
+    let config = sandbox.path("config/snippets_vault/config.toml");
+    fs::remove_file(&config).unwrap();
+
+    let (code, stdout) = sandbox.run(&["--config_init"]);
+    assert_eq!(code, 0, "{}", stdout);
+    let written = fs::read_to_string(&config).unwrap();
+    assert!(written.contains("vault_dir = "), "{}", written);
+    assert!(written.contains("editor = \"nvim\""), "{}", written);
+
+    let (code, stdout) = sandbox.run(&["--config_init"]);
+    assert_eq!(code, 1, "{}", stdout);
+    assert!(stdout.contains("already exists"), "{}", stdout);
+    assert_eq!(fs::read_to_string(&config).unwrap(), written);
+}
+
+#[test]
+fn placeholders_need_params_without_a_terminal() {
+    let sandbox = Sandbox::new();
+    fs::write(
+        sandbox.path("vault/snippet_2024-05-18-101500_bash_logs.md"),
+        "+++\ntitle = \"Container logs\"\nlanguage = \"bash\"\ntags = []\nalias = \"dlogs\"\n+++\n\
+         ```bash\ndocker logs --tail {{lines:100}} <container_id>\n```\n",
+    )
+    .unwrap();
+
+    let (code, stdout) = sandbox.run(&["--get", "dlogs"]);
+    assert_eq!(code, 1, "{}", stdout);
+    assert!(stdout.contains("container_id"), "{}", stdout);
+    assert!(!stdout.contains("docker logs"), "{}", stdout);
+
+    let (code, stdout) = sandbox.run(&["--get", "dlogs", "--param", "container_id=web"]);
+    assert_eq!(code, 0, "{}", stdout);
+    assert_eq!(stdout.trim(), "docker logs --tail 100 web");
+}